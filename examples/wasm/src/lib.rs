@@ -0,0 +1,60 @@
+//! mdlt's analysis for JavaScript: `analyze(bytes)` gives the counts of
+//! pasted text, and `report()` the text report `mdlt` prints for a file.
+
+use wasm_bindgen::prelude::*;
+
+/// What was found in the text.
+#[wasm_bindgen]
+pub struct Stats(mdlt::FileStats);
+
+#[wasm_bindgen]
+impl Stats {
+    /// All lines, the last one counted whether it is terminated or not.
+    #[wasm_bindgen(getter, js_name = totalLines)]
+    pub fn total_lines(&self) -> usize {
+        self.0.total_lines()
+    }
+
+    /// Lines ending in CRLF, LF alone and CR alone.
+    #[wasm_bindgen(getter)]
+    pub fn crlf(&self) -> usize {
+        self.0.dos_endings()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn lf(&self) -> usize {
+        self.0.unix_endings()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn cr(&self) -> usize {
+        self.0.mac_endings()
+    }
+
+    /// "lf", "crlf", "cr", "mixed", "unicode" or "none".
+    #[wasm_bindgen(getter, js_name = lineEnding)]
+    pub fn line_ending(&self) -> String {
+        self.0.line_ending().name().to_string()
+    }
+
+    /// Whether the last line is terminated, or `undefined` with no lines.
+    #[wasm_bindgen(getter, js_name = finalNewline)]
+    pub fn final_newline(&self) -> Option<bool> {
+        self.0.final_newline()
+    }
+
+    /// The text report, as `mdlt` prints it for a file.
+    pub fn report(&self) -> String {
+        let mut report = Vec::new();
+        self.0
+            .write_report(&mut report)
+            .expect("writing to a Vec cannot fail");
+        String::from_utf8_lossy(&report).into_owned()
+    }
+}
+
+/// Analyzes `bytes`, such as a `TextEncoder`'s output for pasted text.
+#[wasm_bindgen]
+pub fn analyze(bytes: &[u8]) -> Stats {
+    Stats(mdlt::analyze_bytes(bytes, "pasted.txt"))
+}
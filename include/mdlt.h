@@ -0,0 +1,66 @@
+/*
+ * The C interface of mdlt, built with `cargo build --release --features ffi`
+ * as libmdlt.so (libmdlt.dylib on macOS, mdlt.dll on Windows).
+ *
+ * The analysis functions return 0 on success and -1 on failure, when
+ * mdlt_last_error_message() says why. Strings are UTF-8.
+ */
+#ifndef MDLT_H
+#define MDLT_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* The ending a file mostly uses. */
+typedef enum MdltLineEnding {
+    MDLT_ENDING_NONE = 0,    /* no line breaks at all */
+    MDLT_ENDING_LF = 1,      /* LF alone */
+    MDLT_ENDING_CRLF = 2,    /* CRLF */
+    MDLT_ENDING_CR = 3,      /* CR alone */
+    MDLT_ENDING_MIXED = 4,   /* no one ending is the most common */
+    MDLT_ENDING_UNICODE = 5, /* only Unicode breaks such as U+2028 */
+} MdltLineEnding;
+
+/* The counts of one analysis, as `mdlt --format json` reports them. */
+typedef struct MdltStats {
+    uint64_t total_lines;
+    uint64_t empty_lines;
+    uint64_t blank_lines; /* lines of only spaces and tabs */
+    uint64_t crlf;
+    uint64_t lf;
+    uint64_t cr;
+    uint64_t total_bytes; /* BOM included */
+    uint64_t total_chars; /* as `wc -m` counts them */
+    uint64_t total_words; /* as `wc -w` counts them */
+    uint64_t trailing_whitespace_lines;
+    uint64_t max_line_length; /* display columns, tabs expanded to 8 */
+    MdltLineEnding line_ending;
+    int final_newline; /* 1 yes, 0 no, -1 when there are no lines */
+    bool is_binary;
+} MdltStats;
+
+/* Analyzes the file at `path`, as `mdlt <path>` does, into `*out`. Fails
+ * when a pointer is NULL, the path is not UTF-8 or the file cannot be read. */
+int mdlt_analyze_path(const char *path, MdltStats *out);
+
+/* Analyzes the `len` bytes at `data` into `*out`; `data` may be NULL when
+ * `len` is 0. */
+int mdlt_analyze_bytes(const uint8_t *data, size_t len, MdltStats *out);
+
+/* Why the last call on this thread failed, or NULL when it succeeded. The
+ * string belongs to mdlt and stays valid until the next call on the thread. */
+const char *mdlt_last_error_message(void);
+
+/* The version of mdlt, such as "1.0.0". */
+const char *mdlt_version(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* MDLT_H */
@@ -0,0 +1,639 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Component, Path};
+
+use crate::archive;
+use crate::budget::Verdict;
+use crate::checks::Severity;
+use crate::cli::{GroupBy, Sort, SortKey, UniformTree};
+use crate::figures::{self, grouped};
+use crate::format::escape_name;
+use crate::sample::Sampled;
+use crate::timing::Timing;
+use crate::{FileStats, SkippedFile};
+
+/// Totals across every file of a run, for the summary after the per-file
+/// reports. The counts are `u64` so that no tree is too big for them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AggregateStats {
+    /// Every file given, whether analyzed, skipped or failed.
+    pub files: u64,
+    /// Analyzed files by their endings; a file within `--max-minority-*`
+    /// counts under its most common ending, and binary files only as
+    /// binary, as do the files skipped for being binary.
+    pub lf_files: u64,
+    pub crlf_files: u64,
+    pub cr_files: u64,
+    pub mixed_files: u64,
+    pub no_ending_files: u64,
+    pub binary_files: u64,
+    pub skipped_files: u64,
+    pub failed_files: u64,
+    pub total_bytes: u64,
+    pub total_lines: u64,
+    pub empty_lines: u64,
+    pub dos_endings: u64,
+    pub unix_endings: u64,
+    pub mac_endings: u64,
+    /// What the checks found, by severity, and what markers silenced of
+    /// it whatever its severity.
+    pub error_violations: u64,
+    pub warning_violations: u64,
+    pub info_violations: u64,
+    pub suppressed_violations: u64,
+    /// How the run fared against `--max-problem-files` and
+    /// `--max-problems`, which only the totals of the whole run give.
+    pub budgets: Vec<Verdict>,
+    /// What `--timing` measured of the run.
+    pub timing: Option<Timing>,
+    /// What `--sample` picked the files from, which makes the totals an
+    /// estimate.
+    pub sample: Option<Sampled>,
+}
+
+impl AggregateStats {
+    pub fn add(&mut self, file: &FileStats) {
+        self.files += 1;
+        let counts = file.ending_counts();
+        let most = counts.iter().copied().max().unwrap_or(0);
+        let kind = if file.is_binary {
+            &mut self.binary_files
+        } else if file.is_mixed() {
+            &mut self.mixed_files
+        } else if most == 0 {
+            &mut self.no_ending_files
+        } else if most == file.dos_endings {
+            &mut self.crlf_files
+        } else if most == file.unix_endings {
+            &mut self.lf_files
+        } else {
+            &mut self.cr_files
+        };
+        *kind += 1;
+        self.total_bytes += file.total_bytes;
+        self.total_lines += file.total_lines as u64;
+        self.empty_lines += file.empty_lines as u64;
+        self.dos_endings += file.dos_endings as u64;
+        self.unix_endings += file.unix_endings as u64;
+        self.mac_endings += file.mac_endings as u64;
+        for violation in &file.violations {
+            *match violation.severity {
+                _ if violation.suppressed => &mut self.suppressed_violations,
+                Severity::Error => &mut self.error_violations,
+                Severity::Warning => &mut self.warning_violations,
+                Severity::Info => &mut self.info_violations,
+            } += 1;
+        }
+    }
+
+    pub fn add_skipped(&mut self, file: &SkippedFile) {
+        self.files += 1;
+        self.skipped_files += 1;
+        self.binary_files += u64::from(file.is_binary());
+    }
+
+    pub fn add_failed(&mut self) {
+        self.files += 1;
+        self.failed_files += 1;
+    }
+
+    /// What a group is sorted by for `--sort`.
+    fn count(&self, key: SortKey) -> u64 {
+        match key {
+            SortKey::Lines => self.total_lines,
+            SortKey::Mixed => self.mixed_files,
+            SortKey::Crlf => self.crlf_files,
+            SortKey::Lf => self.lf_files,
+            SortKey::Empty => self.empty_lines,
+            SortKey::Size => self.total_bytes,
+            SortKey::Name | SortKey::Files => self.files,
+        }
+    }
+
+    /// The "Summary" section of the text report, its counts grouped when
+    /// `group`.
+    pub fn display(&self, group: bool, mut writer: impl Write) -> io::Result<()> {
+        let count = |n: u64| figures::count(n, group);
+        writeln!(writer, "Summary")?;
+        writeln!(writer, "=======")?;
+        if let Some(sample) = &self.sample {
+            writeln!(writer, "Estimate: {}", sample.summary(group))?;
+        }
+        writeln!(writer, "Files scanned: {}", count(self.files))?;
+        writeln!(writer, "LF only: {}", count(self.lf_files))?;
+        writeln!(writer, "CRLF only: {}", count(self.crlf_files))?;
+        writeln!(writer, "CR only: {}", count(self.cr_files))?;
+        writeln!(writer, "Mixed: {}", count(self.mixed_files))?;
+        writeln!(writer, "No line endings: {}", count(self.no_ending_files))?;
+        writeln!(writer, "Binary: {}", count(self.binary_files))?;
+        writeln!(writer, "Skipped: {}", count(self.skipped_files))?;
+        writeln!(writer, "Failed: {}", count(self.failed_files))?;
+        writeln!(writer, "Total bytes: {}", count(self.total_bytes))?;
+        writeln!(writer, "Total lines: {}", count(self.total_lines))?;
+        writeln!(writer, "Empty lines: {}", count(self.empty_lines))?;
+        writeln!(
+            writer,
+            "Line endings: {} CRLF, {} LF, {} CR",
+            count(self.dos_endings),
+            count(self.unix_endings),
+            count(self.mac_endings)
+        )?;
+        let violations = [
+            (self.error_violations, "error"),
+            (self.warning_violations, "warning"),
+            (self.info_violations, "info"),
+            (self.suppressed_violations, "suppressed"),
+        ];
+        if violations.iter().all(|&(count, _)| count == 0) {
+            return Ok(());
+        }
+        let counts: Vec<String> = violations
+            .iter()
+            .map(|&(n, severity)| match (n, severity) {
+                (1, _) | (_, "info" | "suppressed") => format!("{} {}", count(n), severity),
+                _ => format!("{} {}s", count(n), severity),
+            })
+            .collect();
+        writeln!(writer, "Violations: {}", counts.join(", "))
+    }
+
+    /// The `summary` object of the JSON report, whose keys match the field
+    /// names, with `budgets` only when there are any, `timing` only with
+    /// `--timing` and `sample` only with `--sample`.
+    pub fn json(&self) -> String {
+        let fields = [
+            ("files", self.files),
+            ("lf_files", self.lf_files),
+            ("crlf_files", self.crlf_files),
+            ("cr_files", self.cr_files),
+            ("mixed_files", self.mixed_files),
+            ("no_ending_files", self.no_ending_files),
+            ("binary_files", self.binary_files),
+            ("skipped_files", self.skipped_files),
+            ("failed_files", self.failed_files),
+            ("total_bytes", self.total_bytes),
+            ("total_lines", self.total_lines),
+            ("empty_lines", self.empty_lines),
+            ("dos_endings", self.dos_endings),
+            ("unix_endings", self.unix_endings),
+            ("mac_endings", self.mac_endings),
+            ("error_violations", self.error_violations),
+            ("warning_violations", self.warning_violations),
+            ("info_violations", self.info_violations),
+            ("suppressed_violations", self.suppressed_violations),
+        ];
+        let mut fields: Vec<String> = fields
+            .iter()
+            .map(|(key, value)| format!("\"{}\": {}", key, value))
+            .collect();
+        if !self.budgets.is_empty() {
+            let budgets: Vec<String> = self.budgets.iter().map(Verdict::json).collect();
+            fields.push(format!("\"budgets\": [{}]", budgets.join(", ")));
+        }
+        if let Some(timing) = &self.timing {
+            fields.push(format!("\"timing\": {}", timing.json()));
+        }
+        if let Some(sample) = &self.sample {
+            fields.push(format!("\"sample\": {}", sample.json()));
+        }
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+/// The totals per extension, directory or archive of `--group-by`, in the order
+/// `--sort` asks for, most files first by default. Binary and skipped files get rows of their own at the
+/// end, so they do not count toward their extension's endings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Groups {
+    pub by: GroupBy,
+    pub rows: Vec<(String, AggregateStats)>,
+}
+
+/// The row binary files are totalled in.
+const BINARY: &str = "<binary>";
+/// The row skipped files are totalled in.
+const SKIPPED: &str = "<skipped>";
+
+/// The row `file` belongs in: its lowercase extension, or for a file
+/// without one its detected type or `<none>`, the
+/// first component of its path, which is `.` for files given without a
+/// directory, or the archive it is in or `<none>`.
+fn key(by: GroupBy, file: &FileStats) -> String {
+    match by {
+        GroupBy::Extension => file
+            .extension_or_type()
+            .map_or("<none>".to_string(), str::to_ascii_lowercase),
+        GroupBy::Dir => {
+            let mut parts = Path::new(&file.file_name)
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(part) => Some(part),
+                    _ => None,
+                });
+            match (parts.next(), parts.next()) {
+                (Some(first), Some(_)) => first.to_string_lossy().into_owned(),
+                _ => ".".to_string(),
+            }
+        }
+        GroupBy::Archive => archive::archive_of(&file.name())
+            .unwrap_or("<none>")
+            .to_string(),
+    }
+}
+
+impl Groups {
+    pub fn new(
+        by: GroupBy,
+        sort: Option<Sort>,
+        stats: &[FileStats],
+        skipped: &[SkippedFile],
+    ) -> Self {
+        let mut totals: BTreeMap<String, AggregateStats> = BTreeMap::new();
+        let mut binary = AggregateStats::default();
+        for file in stats {
+            match file.is_binary {
+                true => binary.add(file),
+                false => totals.entry(key(by, file)).or_default().add(file),
+            }
+        }
+        let mut rows: Vec<_> = totals.into_iter().collect();
+        let sort = sort.unwrap_or(Sort {
+            key: SortKey::Files,
+            descending: true,
+        });
+        // A stable sort leaves ties in name order.
+        rows.sort_by(|(first_key, first), (second_key, second)| {
+            let order = match sort.key {
+                SortKey::Name => first_key.cmp(second_key),
+                key => first.count(key).cmp(&second.count(key)),
+            };
+            if sort.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+        if binary.files > 0 {
+            rows.push((BINARY.to_string(), binary));
+        }
+        if !skipped.is_empty() {
+            let mut totals = AggregateStats::default();
+            skipped.iter().for_each(|file| totals.add_skipped(file));
+            rows.push((SKIPPED.to_string(), totals));
+        }
+        Groups { by, rows }
+    }
+
+    /// The table of the text report: files, lines and how many files use
+    /// only LF, only CRLF or a mix, one row per group, its counts grouped
+    /// when `group`.
+    pub fn display(&self, group: bool, mut writer: impl Write) -> io::Result<()> {
+        let (title, column) = match self.by {
+            GroupBy::Extension => ("By extension", "Extension"),
+            GroupBy::Dir => ("By directory", "Directory"),
+            GroupBy::Archive => ("By archive", "Archive"),
+        };
+        let headers = ["Files", "Lines", "LF", "CRLF", "Mixed"];
+        let cells: Vec<[String; 5]> = self
+            .rows
+            .iter()
+            .map(|(_, totals)| {
+                [
+                    totals.files,
+                    totals.total_lines,
+                    totals.lf_files,
+                    totals.crlf_files,
+                    totals.mixed_files,
+                ]
+                .map(|count| figures::count(count, group))
+            })
+            .collect();
+        let key_width = self
+            .rows
+            .iter()
+            .map(|(key, _)| key.chars().count())
+            .chain([column.len()])
+            .max()
+            .unwrap_or(0);
+        let widths: Vec<usize> = (0..headers.len())
+            .map(|index| {
+                cells
+                    .iter()
+                    .map(|row| row[index].len())
+                    .chain([headers[index].len()])
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        writeln!(writer, "{}", title)?;
+        writeln!(writer, "{}", "=".repeat(title.len()))?;
+        let line = |key: &str, values: &[&str]| {
+            let mut line = format!("{:key_width$}", key);
+            for (value, width) in values.iter().zip(&widths) {
+                line.push_str(&format!("  {:>width$}", value));
+            }
+            line
+        };
+        writeln!(writer, "{}", line(column, &headers))?;
+        for ((key, _), values) in self.rows.iter().zip(&cells) {
+            let values: Vec<&str> = values.iter().map(String::as_str).collect();
+            writeln!(writer, "{}", line(key, &values))?;
+        }
+        Ok(())
+    }
+}
+
+/// `--check uniform-tree`: the error of a run whose files do not all end
+/// their lines the same way, such as "Line endings differ across the tree:
+/// 1,204 files LF, 37 files CRLF; 37 not LF:" and each of those files.
+/// Binary files and files without line endings do not count; a file within
+/// `--max-minority-*` counts under its most common ending.
+pub fn uniform_tree(stats: &[FileStats], check: UniformTree, verbose: bool) -> Option<String> {
+    let files: Vec<(&FileStats, &str)> = stats
+        .iter()
+        .map(|file| (file, file.ending_word()))
+        .filter(|(_, word)| !matches!(*word, "binary" | "none"))
+        .collect();
+    let mut split: Vec<(&str, u64)> = Vec::new();
+    for &(_, word) in &files {
+        match split.iter_mut().find(|(style, _)| *style == word) {
+            Some((_, count)) => *count += 1,
+            None => split.push((word, 1)),
+        }
+    }
+    // Most files first, and ties in the order of LF, CRLF, CR, mixed.
+    let rank = |word: &str| {
+        ["LF", "CRLF", "CR", "mixed"]
+            .iter()
+            .position(|w| *w == word)
+    };
+    split.sort_by_key(|&(word, count)| (std::cmp::Reverse(count), rank(word)));
+    let pinned = check.pinned.map(|pinned| pinned.name().to_uppercase());
+    let wanted = match &pinned {
+        Some(pinned) => pinned.as_str(),
+        None => split
+            .iter()
+            .map(|(word, _)| *word)
+            .find(|word| *word != "mixed")?,
+    };
+    let off: Vec<&(&FileStats, &str)> = files.iter().filter(|(_, word)| *word != wanted).collect();
+    if off.is_empty() {
+        return None;
+    }
+    let split: Vec<String> = split
+        .iter()
+        .map(|(word, count)| {
+            let files = if *count == 1 { "file" } else { "files" };
+            format!("{} {} {}", grouped(*count), files, word)
+        })
+        .collect();
+    let mut lines = vec![format!(
+        "Line endings differ across the tree: {}; {} not {}{}:",
+        split.join(", "),
+        grouped(off.len() as u64),
+        wanted,
+        match check.pinned {
+            Some(pinned) => format!(", which --check uniform-tree={} asks for", pinned.name()),
+            None => String::new(),
+        }
+    )];
+    let shown = if verbose {
+        off.len()
+    } else {
+        off.len().min(check.listed)
+    };
+    lines.extend(
+        off[..shown]
+            .iter()
+            .map(|(file, word)| format!("  {}: {}", escape_name(&file.name()), word)),
+    );
+    if shown < off.len() {
+        lines.push(format!(
+            "  and {} more, which --verbose lists",
+            off.len() - shown
+        ));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Violation;
+    use crate::cli::ExpectedEndings;
+
+    fn file(name: &str, [dos, unix, mac]: [usize; 3]) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.dos_endings = dos;
+        stats.unix_endings = unix;
+        stats.mac_endings = mac;
+        stats.total_lines = dos + unix + mac;
+        stats.empty_lines = 1;
+        stats
+    }
+
+    #[test]
+    fn test_add_sorts_files_by_endings() {
+        let mut binary = file("logo.png", [2, 1, 0]);
+        binary.is_binary = true;
+        let mut tolerated = file("tolerated.txt", [1, 99, 0]);
+        tolerated.tolerance.endings = Some(1);
+        let mut aggregate = AggregateStats::default();
+        for file in [
+            file("lf.txt", [0, 3, 0]),
+            file("crlf.txt", [4, 0, 0]),
+            file("cr.txt", [0, 0, 2]),
+            file("mixed.txt", [1, 1, 0]),
+            file("empty.txt", [0, 0, 0]),
+            binary,
+            tolerated,
+        ] {
+            aggregate.add(&file);
+        }
+        for reason in ["binary file", "larger than --max-size (1024 bytes)"] {
+            aggregate.add_skipped(&SkippedFile {
+                file_name: "skipped".to_string(),
+                size: None,
+                reason: reason.to_string(),
+                after: 0,
+            });
+        }
+        aggregate.add_failed();
+        assert_eq!(
+            aggregate,
+            AggregateStats {
+                files: 10,
+                lf_files: 2,
+                crlf_files: 1,
+                cr_files: 1,
+                mixed_files: 1,
+                no_ending_files: 1,
+                binary_files: 2,
+                skipped_files: 2,
+                failed_files: 1,
+                total_bytes: 0,
+                total_lines: 114,
+                empty_lines: 7,
+                dos_endings: 8,
+                unix_endings: 104,
+                mac_endings: 2,
+                error_violations: 0,
+                warning_violations: 0,
+                info_violations: 0,
+                suppressed_violations: 0,
+                budgets: Vec::new(),
+                timing: None,
+                sample: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut logo = file("img/logo.png", [0, 1, 0]);
+        logo.is_binary = true;
+        let mut files = vec![
+            file("src/main.rs", [0, 10, 0]),
+            file("src/lib.RS", [0, 20, 0]),
+            file("README.md", [5, 0, 0]),
+            file("src/notes.md", [1, 1, 0]),
+            file("Makefile", [0, 4, 0]),
+            logo,
+        ];
+        for stats in &mut files {
+            stats.file_extension = Path::new(&stats.file_name)
+                .extension()
+                .map(|extension| extension.to_string_lossy().into_owned());
+        }
+        let skipped = [SkippedFile {
+            file_name: "dump.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 0,
+        }];
+        let keys = |groups: &Groups| -> Vec<String> {
+            groups.rows.iter().map(|(key, _)| key.clone()).collect()
+        };
+
+        let groups = Groups::new(GroupBy::Extension, None, &files, &skipped);
+        assert_eq!(
+            keys(&groups),
+            ["md", "rs", "<none>", "<binary>", "<skipped>"]
+        );
+        let mut buffer = Vec::new();
+        groups.display(true, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "By extension\n\
+             ============\n\
+             Extension  Files  Lines  LF  CRLF  Mixed\n\
+             md             2      7   0     1      1\n\
+             rs             2     30   2     0      0\n\
+             <none>         1      4   1     0      0\n\
+             <binary>       1      1   0     0      0\n\
+             <skipped>      1      0   0     0      0\n"
+        );
+
+        let sort = |value: &str| Some(value.parse().unwrap());
+        let groups = Groups::new(GroupBy::Extension, sort("lines:desc"), &files, &[]);
+        assert_eq!(keys(&groups), ["rs", "md", "<none>", "<binary>"]);
+        // Ties stay in name order whichever way the sort goes.
+        let groups = Groups::new(GroupBy::Extension, sort("crlf"), &files, &[]);
+        assert_eq!(keys(&groups), ["<none>", "rs", "md", "<binary>"]);
+        let groups = Groups::new(GroupBy::Extension, sort("lf:desc"), &files, &[]);
+        assert_eq!(keys(&groups), ["rs", "<none>", "md", "<binary>"]);
+        let groups = Groups::new(GroupBy::Dir, sort("name:desc"), &files, &[]);
+        assert_eq!(keys(&groups), ["src", ".", "<binary>"]);
+        assert_eq!(groups.rows[0].1.files, 3);
+        let archived = [
+            file("dist/release.zip!bin/run.sh", [0, 3, 0]),
+            file("dist/release.zip!README", [2, 0, 0]),
+            file("src.tar.gz!main.rs", [0, 1, 0]),
+            file("README.md", [5, 0, 0]),
+        ];
+        let groups = Groups::new(GroupBy::Archive, None, &archived, &[]);
+        assert_eq!(keys(&groups), ["dist/release.zip", "<none>", "src.tar.gz"]);
+    }
+
+    #[test]
+    fn test_display_and_json() {
+        let mut aggregate = AggregateStats::default();
+        let mut mixed = file("a.txt", [1, 2, 0]);
+        let violation = |severity| Violation {
+            check: "mixed-endings".to_string(),
+            severity,
+            line: None,
+            message: "mixes line endings: 1 CRLF, 2 LF".to_string(),
+            suppressed: false,
+        };
+        let silenced = Violation {
+            suppressed: true,
+            ..violation(Severity::Error)
+        };
+        mixed.violations = vec![
+            violation(Severity::Warning),
+            violation(Severity::Warning),
+            violation(Severity::Info),
+            silenced,
+        ];
+        aggregate.add(&mixed);
+        aggregate.add_failed();
+        let mut buffer = Vec::new();
+        aggregate.display(true, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Summary\n=======\nFiles scanned: 2\nLF only: 0\nCRLF only: 0\nCR only: 0\n\
+             Mixed: 1\nNo line endings: 0\nBinary: 0\nSkipped: 0\nFailed: 1\n\
+             Total bytes: 0\nTotal lines: 3\nEmpty lines: 1\nLine endings: 1 CRLF, 2 LF, 0 CR\n\
+             Violations: 0 errors, 2 warnings, 1 info, 1 suppressed\n"
+        );
+        assert_eq!(
+            aggregate.json(),
+            "{\"files\": 2, \"lf_files\": 0, \"crlf_files\": 0, \"cr_files\": 0, \
+             \"mixed_files\": 1, \"no_ending_files\": 0, \"binary_files\": 0, \
+             \"skipped_files\": 0, \"failed_files\": 1, \"total_bytes\": 0, \"total_lines\": 3, \
+             \"empty_lines\": 1, \"dos_endings\": 1, \"unix_endings\": 2, \"mac_endings\": 0, \
+             \"error_violations\": 0, \"warning_violations\": 2, \"info_violations\": 1, \
+             \"suppressed_violations\": 1}"
+        );
+    }
+
+    #[test]
+    fn test_uniform_tree() {
+        let mut binary = file("logo.png", [1, 0, 0]);
+        binary.is_binary = true;
+        let mut tolerated = file("tolerated.txt", [1, 99, 0]);
+        tolerated.tolerance.endings = Some(1);
+        let files = [
+            file("a.txt", [0, 3, 0]),
+            file("b.txt", [2, 0, 0]),
+            file("c.txt", [0, 1, 0]),
+            file("d.txt", [1, 1, 0]),
+            file("e.txt", [0, 0, 0]),
+            binary,
+            tolerated,
+        ];
+        let check = |pinned, listed| UniformTree { pinned, listed };
+        assert_eq!(
+            uniform_tree(&files, check(None, 10), false).unwrap(),
+            "Line endings differ across the tree: 3 files LF, 1 file CRLF, 1 file mixed; 2 not LF:\n  \
+             b.txt: CRLF\n  d.txt: mixed"
+        );
+        assert_eq!(
+            uniform_tree(&files, check(Some(ExpectedEndings::Crlf), 1), false).unwrap(),
+            "Line endings differ across the tree: 3 files LF, 1 file CRLF, 1 file mixed; 4 not CRLF, \
+             which --check uniform-tree=crlf asks for:\n  a.txt: LF\n  and 3 more, which --verbose lists"
+        );
+        assert_eq!(
+            uniform_tree(&files, check(Some(ExpectedEndings::Crlf), 1), true)
+                .unwrap()
+                .lines()
+                .count(),
+            5
+        );
+        assert_eq!(uniform_tree(&files[..1], check(None, 10), false), None);
+        assert_eq!(uniform_tree(&files[4..], check(None, 10), false), None);
+        // With mixed files alone there is no ending to hold the rest to.
+        assert_eq!(uniform_tree(&files[3..4], check(None, 10), false), None);
+    }
+}
@@ -0,0 +1,665 @@
+use std::fs::File;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+
+use crate::gzip::{self, Decoder};
+use crate::osname;
+
+/// The archives `--archive` looks inside, told apart by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Zip,
+    Tar,
+    /// A tar archive compressed with gzip, `.tar.gz` or `.tgz`.
+    TarGz,
+}
+
+impl Kind {
+    /// The kind of archive named `name`, if it is one.
+    pub fn of(name: &str) -> Option<Kind> {
+        let lower = name.to_ascii_lowercase();
+        [
+            (".zip", Kind::Zip),
+            (".tar", Kind::Tar),
+            (".tar.gz", Kind::TarGz),
+            (".tgz", Kind::TarGz),
+        ]
+        .into_iter()
+        .find(|(suffix, _)| lower.len() > suffix.len() && lower.ends_with(suffix))
+        .map(|(_, kind)| kind)
+    }
+}
+
+/// The name of a member of the archive at `archive`, as reports show it:
+/// `release.zip!bin/run.sh`.
+pub fn member_name(archive: &str, member: &str) -> String {
+    format!("{}!{}", archive, member)
+}
+
+/// The archive a member named by [`member_name`] is in, or `None` for a
+/// name that is not a member's.
+pub fn archive_of(name: &str) -> Option<&str> {
+    name.match_indices('!')
+        .map(|(index, _)| &name[..index])
+        .find(|archive| Kind::of(archive).is_some())
+}
+
+fn corrupt(what: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, what)
+}
+
+/// Calls `visit` with the name, size and contents of each regular file in
+/// the archive at `path`, in the order they are stored. Directories,
+/// symlinks and other special entries are passed over.
+///
+/// A member that cannot be read is an error for `visit` to report and go
+/// on from, either in place of its contents or as they are read; the error
+/// returned is one with the archive itself, once the members before it
+/// have been visited.
+pub fn members(
+    path: &str,
+    kind: Kind,
+    visit: impl FnMut(&str, u64, io::Result<&mut dyn Read>),
+) -> io::Result<()> {
+    let file = File::open(osname::path(path))?;
+    match kind {
+        Kind::Zip => zip(file, visit),
+        Kind::Tar => tar(BufReader::new(file), visit),
+        Kind::TarGz => tar(Decoder::new(file), visit),
+    }
+}
+
+/// A little-endian field of a zip record.
+fn le(record: &[u8], at: usize, size: usize) -> u64 {
+    record[at..at + size]
+        .iter()
+        .rev()
+        .fold(0, |value, &byte| value << 8 | byte as u64)
+}
+
+const END_OF_DIRECTORY: u32 = 0x0605_4b50;
+const ZIP64_END_OF_DIRECTORY: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR: u32 = 0x0706_4b50;
+const DIRECTORY_ENTRY: u32 = 0x0201_4b50;
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+
+/// Where the central directory of a zip archive is and how long it is,
+/// from the end-of-directory record in its last 64 KiB.
+fn zip_directory(file: &mut (impl Read + Seek)) -> io::Result<(u64, u64)> {
+    let len = file.seek(SeekFrom::End(0))?;
+    let tail_len = len.min(22 + 0xffff);
+    file.seek(SeekFrom::Start(len - tail_len))?;
+    let mut tail = vec![0; tail_len as usize];
+    file.read_exact(&mut tail)?;
+    let end = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&at| le(&tail, at, 4) == END_OF_DIRECTORY as u64)
+        .ok_or_else(|| corrupt("not a zip archive".to_string()))?;
+    let (size, offset) = (le(&tail, end + 12, 4), le(&tail, end + 16, 4));
+    if size != 0xffff_ffff && offset != 0xffff_ffff {
+        return Ok((offset, size));
+    }
+    // A zip64 archive, whose end-of-directory record is found through the
+    // locator just before the plain one.
+    if end < 20 || le(&tail, end - 20, 4) != ZIP64_LOCATOR as u64 {
+        return Err(corrupt("corrupt zip archive: no zip64 locator".to_string()));
+    }
+    file.seek(SeekFrom::Start(le(&tail, end - 12, 8)))?;
+    let mut record = [0; 56];
+    file.read_exact(&mut record)?;
+    if le(&record, 0, 4) != ZIP64_END_OF_DIRECTORY as u64 {
+        return Err(corrupt(
+            "corrupt zip archive: no zip64 end of directory".to_string(),
+        ));
+    }
+    Ok((le(&record, 48, 8), le(&record, 40, 8)))
+}
+
+/// A regular file listed in a zip archive's central directory.
+struct ZipEntry {
+    name: String,
+    method: u64,
+    encrypted: bool,
+    crc: u32,
+    compressed: u64,
+    size: u64,
+    offset: u64,
+}
+
+/// The regular files listed in the central directory `directory`.
+fn zip_entries(directory: &[u8]) -> io::Result<Vec<ZipEntry>> {
+    let mut entries = Vec::new();
+    let mut at = 0;
+    while at + 46 <= directory.len() && le(directory, at, 4) == DIRECTORY_ENTRY as u64 {
+        let header = &directory[at..];
+        let name_len = le(header, 28, 2) as usize;
+        let extra_len = le(header, 30, 2) as usize;
+        let comment_len = le(header, 32, 2) as usize;
+        let end = 46 + name_len + extra_len + comment_len;
+        if header.len() < end {
+            break;
+        }
+        at += end;
+        let name = String::from_utf8_lossy(&header[46..46 + name_len]).into_owned();
+        // Unix builds of zip keep the mode in the top of the external
+        // attributes, which tells symlinks apart.
+        let unix = le(header, 5, 1) == 3;
+        let file_type = le(header, 38, 4) >> 16 & 0o170000;
+        if name.ends_with('/') || (unix && file_type != 0 && file_type != 0o100000) {
+            continue;
+        }
+        let mut entry = ZipEntry {
+            name,
+            method: le(header, 10, 2),
+            encrypted: le(header, 8, 2) & 1 != 0,
+            crc: le(header, 16, 4) as u32,
+            compressed: le(header, 20, 4),
+            size: le(header, 24, 4),
+            offset: le(header, 42, 4),
+        };
+        // The zip64 extra field holds, in this order, whichever of the
+        // sizes and the offset did not fit.
+        let mut extra = &header[46 + name_len..46 + name_len + extra_len];
+        while extra.len() >= 4 {
+            let (id, len) = (le(extra, 0, 2), le(extra, 2, 2) as usize);
+            let field = &extra[4..(4 + len).min(extra.len())];
+            if id == 1 {
+                let mut values = field.chunks_exact(8).map(|value| le(value, 0, 8));
+                for slot in [&mut entry.size, &mut entry.compressed, &mut entry.offset] {
+                    if *slot == 0xffff_ffff {
+                        *slot = values.next().unwrap_or(*slot);
+                    }
+                }
+            }
+            extra = &extra[(4 + len).min(extra.len())..];
+        }
+        entries.push(entry);
+    }
+    if at != directory.len() {
+        return Err(corrupt(format!(
+            "corrupt zip archive: bad central directory entry at byte {}",
+            at
+        )));
+    }
+    Ok(entries)
+}
+
+/// The contents of a zip member, failing at the end when they do not have
+/// the CRC-32 and size the directory gives.
+struct Checked<R> {
+    reader: R,
+    crc: u32,
+    size: u64,
+    expected: (u32, u64),
+}
+
+impl<R: Read> Read for Checked<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        self.crc = gzip::crc32(self.crc, &buf[..read]);
+        self.size += read as u64;
+        if read == 0 && !buf.is_empty() && (self.crc, self.size) != self.expected {
+            return Err(corrupt(
+                "corrupt zip member: CRC or size mismatch".to_string(),
+            ));
+        }
+        Ok(read)
+    }
+}
+
+/// Opens the contents of `entry`, failing for what cannot be read: an
+/// encrypted member or one compressed with anything but deflate.
+fn zip_contents<'a, R: Read + Seek>(
+    reader: &'a mut R,
+    entry: &ZipEntry,
+) -> io::Result<Box<dyn Read + 'a>> {
+    if entry.encrypted {
+        return Err(io::Error::other("encrypted zip member"));
+    }
+    reader.seek(SeekFrom::Start(entry.offset))?;
+    let mut header = [0; 30];
+    reader.read_exact(&mut header)?;
+    if le(&header, 0, 4) != LOCAL_HEADER as u64 {
+        return Err(corrupt(format!(
+            "corrupt zip archive: no local header at byte {}",
+            entry.offset
+        )));
+    }
+    let skip = le(&header, 26, 2) + le(&header, 28, 2);
+    reader.seek(SeekFrom::Current(skip as i64))?;
+    let data = reader.take(entry.compressed);
+    let contents: Box<dyn Read + 'a> = match entry.method {
+        0 => Box::new(data),
+        8 => Box::new(Decoder::bare(data)),
+        method => {
+            return Err(io::Error::other(format!(
+                "zip compression method {} is not supported",
+                method
+            )))
+        }
+    };
+    Ok(Box::new(Checked {
+        reader: contents,
+        crc: 0,
+        size: 0,
+        expected: (entry.crc, entry.size),
+    }))
+}
+
+fn zip(
+    mut file: impl Read + Seek,
+    mut visit: impl FnMut(&str, u64, io::Result<&mut dyn Read>),
+) -> io::Result<()> {
+    let (offset, size) = zip_directory(&mut file)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut directory = Vec::new();
+    (&mut file).take(size).read_to_end(&mut directory)?;
+    for entry in zip_entries(&directory)? {
+        match zip_contents(&mut file, &entry) {
+            Ok(mut contents) => visit(&entry.name, entry.size, Ok(&mut contents)),
+            Err(e) => visit(&entry.name, entry.size, Err(e)),
+        }
+    }
+    Ok(())
+}
+
+/// A tar archive, read a block of 512 bytes at a time.
+struct Tar<R> {
+    reader: R,
+    /// Bytes read so far.
+    offset: u64,
+}
+
+impl<R: Read> Tar<R> {
+    /// Fills `buf`, returning `false` when the archive ends after nothing.
+    fn fill(&mut self, buf: &mut [u8]) -> io::Result<bool> {
+        let mut filled = 0;
+        while filled < buf.len() {
+            match self.reader.read(&mut buf[filled..]) {
+                Ok(0) if filled == 0 => return Ok(false),
+                Ok(0) => return Err(self.truncated(filled)),
+                Ok(read) => filled += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        self.offset += filled as u64;
+        Ok(true)
+    }
+
+    fn truncated(&self, read: usize) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!(
+                "truncated tar archive at byte {}",
+                self.offset + read as u64
+            ),
+        )
+    }
+
+    /// The `size` bytes of an entry's data, read into memory; for the
+    /// long names and extended headers, which are small.
+    fn data(&mut self, size: u64) -> io::Result<Vec<u8>> {
+        if size > 1024 * 1024 {
+            return Err(corrupt(format!(
+                "corrupt tar archive: {} byte header at byte {}",
+                size, self.offset
+            )));
+        }
+        let mut data = vec![0; padded(size) as usize];
+        if !self.fill(&mut data)? {
+            return Err(self.truncated(0));
+        }
+        data.truncate(size as usize);
+        Ok(data)
+    }
+}
+
+/// `size` rounded up to whole blocks.
+fn padded(size: u64) -> u64 {
+    size.div_ceil(512) * 512
+}
+
+/// A number field of a tar header: octal digits, or for large values a
+/// big-endian binary number after a set top bit.
+fn number(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        return Some(
+            field[1..]
+                .iter()
+                .fold(0, |value, &byte| value << 8 | byte as u64),
+        );
+    }
+    let digits = std::str::from_utf8(field).ok()?;
+    let digits = digits.trim_matches(|c: char| c == '\0' || c == ' ');
+    match digits {
+        "" => Some(0),
+        digits => u64::from_str_radix(digits, 8).ok(),
+    }
+}
+
+/// A string field of a tar header, up to its first NUL.
+fn text(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+/// The values of a pax extended header, `<length> <key>=<value>\n` each.
+fn pax(mut data: &[u8], name: &mut Option<String>, size: &mut Option<u64>) {
+    while let Some(space) = data.iter().position(|&byte| byte == b' ') {
+        let Some(len) = std::str::from_utf8(&data[..space])
+            .ok()
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|&len| len > space && len <= data.len())
+        else {
+            return;
+        };
+        let record = &data[space + 1..len];
+        let record = record.strip_suffix(b"\n").unwrap_or(record);
+        if let Some(equals) = record.iter().position(|&byte| byte == b'=') {
+            let value = String::from_utf8_lossy(&record[equals + 1..]);
+            match &record[..equals] {
+                b"path" => *name = Some(value.into_owned()),
+                b"size" => *size = value.parse().ok().or(*size),
+                _ => {}
+            }
+        }
+        data = &data[len..];
+    }
+}
+
+/// An entry's data, which fails rather than running past it when the
+/// archive ends early, and remembers when it failed so that the archive
+/// is not read on from nowhere in particular.
+struct Body<'a, R> {
+    tar: &'a mut Tar<R>,
+    left: u64,
+    failed: bool,
+}
+
+impl<R: Read> Read for Body<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.left == 0 || buf.is_empty() {
+            return Ok(0);
+        }
+        let len = buf.len().min(self.left.min(usize::MAX as u64) as usize);
+        let read = match self.tar.reader.read(&mut buf[..len]) {
+            Ok(0) => Err(self.tar.truncated(0)),
+            result => result,
+        };
+        match read {
+            Ok(read) => {
+                self.tar.offset += read as u64;
+                self.left -= read as u64;
+                Ok(read)
+            }
+            Err(e) => {
+                self.failed = e.kind() != io::ErrorKind::Interrupted;
+                Err(e)
+            }
+        }
+    }
+}
+
+fn tar(
+    reader: impl Read,
+    mut visit: impl FnMut(&str, u64, io::Result<&mut dyn Read>),
+) -> io::Result<()> {
+    let mut tar = Tar { reader, offset: 0 };
+    let mut header = [0; 512];
+    // What a GNU long name or a pax header says of the next entry.
+    let mut long_name = None;
+    let mut long_size = None;
+    while tar.fill(&mut header)? {
+        let at = tar.offset - 512;
+        if header.iter().all(|&byte| byte == 0) {
+            break;
+        }
+        let sum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(index, &byte)| match index {
+                148..=155 => b' ' as u64,
+                _ => byte as u64,
+            })
+            .sum();
+        let size = number(&header[124..136]);
+        match (number(&header[148..156]), size) {
+            (Some(checksum), Some(_)) if checksum == sum => {}
+            _ if at == 0 => return Err(corrupt("not a tar archive".to_string())),
+            _ => {
+                return Err(corrupt(format!(
+                    "corrupt tar archive: bad header at byte {}",
+                    at
+                )))
+            }
+        }
+        let size = long_size.take().or(size).unwrap_or(0);
+        match header[156] {
+            b'L' => long_name = Some(text(&tar.data(size)?)),
+            b'x' => {
+                let data = tar.data(size)?;
+                pax(&data, &mut long_name, &mut long_size);
+            }
+            kind => {
+                let name = match long_name.take() {
+                    Some(name) => name,
+                    None if &header[257..262] == b"ustar" && header[345] != 0 => {
+                        format!("{}/{}", text(&header[345..500]), text(&header[..100]))
+                    }
+                    None => text(&header[..100]),
+                };
+                let mut body = Body {
+                    tar: &mut tar,
+                    left: size,
+                    failed: false,
+                };
+                let name = name.trim_start_matches("./");
+                let regular = matches!(kind, b'0' | b'\0' | b'7');
+                if regular && !name.is_empty() && !name.ends_with('/') {
+                    visit(name, size, Ok(&mut body));
+                }
+                // A member that failed part way is reported as it is, and
+                // what follows it cannot be found.
+                if body.failed {
+                    return Ok(());
+                }
+                body.left += padded(size) - size;
+                io::copy(&mut body, &mut io::sink())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// What `visit` was given for each member: its name, its size, and its
+    /// contents or the error reading them.
+    type Visited = Vec<(String, u64, Result<Vec<u8>, String>)>;
+
+    fn visitor(visited: &mut Visited) -> impl FnMut(&str, u64, io::Result<&mut dyn Read>) + '_ {
+        |name, size, contents| {
+            let mut data = Vec::new();
+            let contents = contents
+                .and_then(|contents| contents.read_to_end(&mut data))
+                .map(|_| data)
+                .map_err(|e| e.to_string());
+            visited.push((name.to_string(), size, contents));
+        }
+    }
+
+    fn tar_entry(tar: &mut Vec<u8>, name: &str, kind: u8, data: &[u8]) {
+        let mut header = [0; 512];
+        header[..name.len()].copy_from_slice(name.as_bytes());
+        header[100..107].copy_from_slice(b"0000644");
+        header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+        header[156] = kind;
+        header[257..265].copy_from_slice(b"ustar\x0000");
+        header[148..156].fill(b' ');
+        let sum: u32 = header.iter().map(|&byte| byte as u32).sum();
+        header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+        tar.extend_from_slice(&header);
+        tar.extend_from_slice(data);
+        tar.resize(
+            tar.len() + (padded(data.len() as u64) as usize - data.len()),
+            0,
+        );
+    }
+
+    fn release_tar() -> Vec<u8> {
+        let mut tar = Vec::new();
+        tar_entry(&mut tar, "./bin/", b'5', b"");
+        tar_entry(&mut tar, "./bin/run.sh", b'0', b"echo\r\n");
+        tar_entry(&mut tar, "./link", b'2', b"");
+        tar_entry(&mut tar, "././@PaxHeader", b'x', b"22 path=long/name.txt\n");
+        tar_entry(&mut tar, "short", b'0', b"a\nb\n");
+        tar.resize(tar.len() + 1024, 0);
+        tar
+    }
+
+    #[test]
+    fn test_tar_members() {
+        let mut visited = Vec::new();
+        tar(&release_tar()[..], visitor(&mut visited)).unwrap();
+        assert_eq!(
+            visited,
+            [
+                ("bin/run.sh".to_string(), 6, Ok(b"echo\r\n".to_vec())),
+                ("long/name.txt".to_string(), 4, Ok(b"a\nb\n".to_vec())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tar_errors() {
+        let release = release_tar();
+        // Cut in the middle of a member, which fails on its own.
+        let mut visited = Vec::new();
+        tar(&release[..1024 + 3], visitor(&mut visited)).unwrap();
+        assert_eq!(
+            visited,
+            [(
+                "bin/run.sh".to_string(),
+                6,
+                Err("truncated tar archive at byte 1027".to_string())
+            )]
+        );
+        // Cut in a header, which fails the archive.
+        let error = tar(&release[..2048 + 100], visitor(&mut Vec::new())).unwrap_err();
+        assert_eq!(error.to_string(), "truncated tar archive at byte 2148");
+        let error = tar(&[b'x'; 1024][..], visitor(&mut Vec::new())).unwrap_err();
+        assert_eq!(error.to_string(), "not a tar archive");
+        let mut bad = release.clone();
+        bad[1536] ^= 1;
+        let error = tar(&bad[..], visitor(&mut Vec::new())).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "corrupt tar archive: bad header at byte 1536"
+        );
+    }
+
+    /// A zip entry: its name, unix mode, method, CRC-32, stored data and
+    /// size.
+    type Entry<'a> = (&'a str, u32, u16, u32, &'a [u8], usize);
+
+    fn zip_archive(entries: &[Entry]) -> Vec<u8> {
+        let mut archive = Vec::new();
+        let mut directory = Vec::new();
+        for &(name, mode, method, crc, data, size) in entries {
+            let offset = archive.len() as u32;
+            let fields = |record: &mut Vec<u8>| {
+                record.extend_from_slice(&[0, 0]);
+                record.extend_from_slice(&method.to_le_bytes());
+                record.extend_from_slice(&[0; 4]);
+                record.extend_from_slice(&crc.to_le_bytes());
+                record.extend_from_slice(&(data.len() as u32).to_le_bytes());
+                record.extend_from_slice(&(size as u32).to_le_bytes());
+                record.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                record.extend_from_slice(&[0, 0]);
+            };
+            archive.extend_from_slice(&LOCAL_HEADER.to_le_bytes());
+            archive.extend_from_slice(&[20, 0]);
+            fields(&mut archive);
+            archive.extend_from_slice(name.as_bytes());
+            archive.extend_from_slice(data);
+            directory.extend_from_slice(&DIRECTORY_ENTRY.to_le_bytes());
+            directory.extend_from_slice(&[20, 3, 20, 0]);
+            fields(&mut directory);
+            directory.extend_from_slice(&[0; 6]);
+            directory.extend_from_slice(&(mode << 16).to_le_bytes());
+            directory.extend_from_slice(&offset.to_le_bytes());
+            directory.extend_from_slice(name.as_bytes());
+        }
+        let offset = archive.len() as u32;
+        archive.extend_from_slice(&directory);
+        archive.extend_from_slice(&END_OF_DIRECTORY.to_le_bytes());
+        archive.extend_from_slice(&[0; 4]);
+        let count = (entries.len() as u16).to_le_bytes();
+        archive.extend_from_slice(&[count[0], count[1], count[0], count[1]]);
+        archive.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+        archive.extend_from_slice(&offset.to_le_bytes());
+        archive.extend_from_slice(&[0, 0]);
+        archive
+    }
+
+    #[test]
+    fn test_zip_members() {
+        // A raw deflate stream of "hello\r\nworld\n".
+        let deflated = [
+            0xcb, 0x48, 0xcd, 0xc9, 0xc9, 0xe7, 0xe5, 0x2a, 0xcf, 0x2f, 0xca, 0x49, 0xe1, 0x02,
+            0x00,
+        ];
+        let hello = gzip::crc32(0, b"hello\r\nworld\n");
+        let archive = zip_archive(&[
+            ("a.txt", 0o100644, 0, gzip::crc32(0, b"x\r\n"), b"x\r\n", 3),
+            ("dir/", 0o040755, 0, 0, b"", 0),
+            ("link", 0o120777, 0, 0, b"a.txt", 5),
+            ("docs/hello.md", 0, 8, hello, &deflated, 13),
+            ("bad.txt", 0o100644, 0, 1, b"oops", 4),
+            ("packed.txt", 0o100644, 12, 0, b"BZh", 3),
+        ]);
+        let mut visited = Vec::new();
+        zip(io::Cursor::new(&archive), visitor(&mut visited)).unwrap();
+        assert_eq!(
+            visited,
+            [
+                ("a.txt".to_string(), 3, Ok(b"x\r\n".to_vec())),
+                (
+                    "docs/hello.md".to_string(),
+                    13,
+                    Ok(b"hello\r\nworld\n".to_vec())
+                ),
+                (
+                    "bad.txt".to_string(),
+                    4,
+                    Err("corrupt zip member: CRC or size mismatch".to_string())
+                ),
+                (
+                    "packed.txt".to_string(),
+                    3,
+                    Err("zip compression method 12 is not supported".to_string())
+                ),
+            ]
+        );
+        let error = zip(io::Cursor::new(b"plain text"), visitor(&mut Vec::new())).unwrap_err();
+        assert_eq!(error.to_string(), "not a zip archive");
+    }
+
+    #[test]
+    fn test_kind_of() {
+        assert_eq!(Kind::of("release.zip"), Some(Kind::Zip));
+        assert_eq!(Kind::of("dist/release.TAR.GZ"), Some(Kind::TarGz));
+        assert_eq!(Kind::of("release.tgz"), Some(Kind::TarGz));
+        assert_eq!(Kind::of("release.tar"), Some(Kind::Tar));
+        assert_eq!(Kind::of("app.log.gz"), None);
+        assert_eq!(Kind::of(".zip"), None);
+        assert_eq!(archive_of("release.zip!bin/run.sh"), Some("release.zip"));
+        assert_eq!(archive_of("a!b.zip!c.txt"), Some("a!b.zip"));
+        assert_eq!(archive_of("wow!.txt"), None);
+    }
+}
@@ -0,0 +1,226 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use crate::osname;
+
+/// Where `--backup` copies originals before they are rewritten.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Backup {
+    pub suffix: String,
+    /// Replace an existing backup rather than refuse to rewrite the file.
+    pub overwrite: bool,
+}
+
+impl Backup {
+    pub fn path(&self, path: &str) -> String {
+        format!("{}{}", path, self.suffix)
+    }
+}
+
+/// How `replace` treats the file it rewrites.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InPlace {
+    pub backup: Option<Backup>,
+    /// Give the new file the original's modification time.
+    pub preserve_mtime: bool,
+}
+
+/// Replaces the contents of `path` with whatever `write` writes, so that
+/// being killed at any point leaves either the old file or the new one. The
+/// new contents go to a temporary file in the same directory, which gets
+/// the original's permissions, and on Unix its owner and group where that
+/// is allowed, is synced to disk and is then renamed over the original. A
+/// backup already in the way stops this before anything is written unless
+/// it may be overwritten. On any error the temporary file is removed and
+/// the original is left as it was.
+pub fn replace<T>(
+    path: &str,
+    in_place: &InPlace,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<T>,
+) -> io::Result<T> {
+    let backup_path = in_place.backup.as_ref().map(|backup| backup.path(path));
+    if let (Some(backup), Some(backup_path)) = (&in_place.backup, &backup_path) {
+        if !backup.overwrite && osname::path(backup_path).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "backup {} already exists (--backup-overwrite replaces it)",
+                    osname::display(backup_path)
+                ),
+            ));
+        }
+    }
+    let original = osname::path(path);
+    let metadata = fs::metadata(&original)?;
+    let temp = temp_path(&original);
+    let replaced = (|| {
+        let mut writer = BufWriter::new(File::create_new(&temp)?);
+        let value = write(&mut writer)?;
+        let file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        copy_owner(&file, &metadata);
+        if in_place.preserve_mtime {
+            file.set_modified(metadata.modified()?)?;
+        }
+        file.set_permissions(metadata.permissions())?;
+        file.sync_all()?;
+        drop(file);
+        if let Some(backup_path) = &backup_path {
+            fs::copy(&original, osname::path(backup_path))?;
+        }
+        rename_over(&temp, &original, &metadata)?;
+        Ok(value)
+    })();
+    match replaced {
+        Ok(_) => sync_parent(&original),
+        Err(_) => {
+            let _ = fs::remove_file(&temp);
+        }
+    }
+    replaced
+}
+
+/// Writes `path` afresh with whatever `write` writes, through a temporary
+/// file renamed over it, so that a reader never sees half of it. Unlike
+/// `replace`, `path` need not exist yet, and the new file's permissions
+/// are the usual ones for a new file.
+pub fn create(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let temp = temp_path(path);
+    let created = (|| {
+        let mut writer = BufWriter::new(File::create_new(&temp)?);
+        write(&mut writer)?;
+        let file = writer
+            .into_inner()
+            .map_err(io::IntoInnerError::into_error)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp, path)
+    })();
+    match created {
+        Ok(()) => sync_parent(path),
+        Err(_) => {
+            let _ = fs::remove_file(&temp);
+        }
+    }
+    created
+}
+
+/// A name next to `path` that no other run is writing to at the same time.
+fn temp_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".mdlt-{}.tmp", std::process::id()));
+    path.with_file_name(name)
+}
+
+#[cfg(unix)]
+fn copy_owner(file: &File, metadata: &fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    // Only root may give a file away, so failing here is expected and the
+    // new file simply stays owned by whoever ran mdlt.
+    let _ = std::os::unix::fs::fchown(file, Some(metadata.uid()), Some(metadata.gid()));
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_file: &File, _metadata: &fs::Metadata) {}
+
+/// Renames `temp` over `path`. Windows replaces an existing file on rename
+/// too, but not a read-only one, so that attribute is lifted for the rename
+/// and put back if it fails; the new file already carries it.
+#[cfg(windows)]
+fn rename_over(temp: &Path, path: &Path, metadata: &fs::Metadata) -> io::Result<()> {
+    let permissions = metadata.permissions();
+    if !permissions.readonly() {
+        return fs::rename(temp, path);
+    }
+    let mut writable = permissions.clone();
+    #[allow(clippy::permissions_set_readonly_false)]
+    writable.set_readonly(false);
+    fs::set_permissions(path, writable)?;
+    fs::rename(temp, path).inspect_err(|_| {
+        let _ = fs::set_permissions(path, permissions);
+    })
+}
+
+#[cfg(not(windows))]
+fn rename_over(temp: &Path, path: &Path, _metadata: &fs::Metadata) -> io::Result<()> {
+    fs::rename(temp, path)
+}
+
+/// Makes the rename itself durable. Best effort: not every filesystem lets
+/// a directory be synced.
+#[cfg(unix)]
+fn sync_parent(path: &Path) {
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let _ = File::open(parent).and_then(|dir| dir.sync_all());
+}
+
+#[cfg(not(unix))]
+fn sync_parent(_path: &Path) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_failed_write_leaves_original() {
+        let path = "atomic_failed.txt";
+        fs::write(path, "original").unwrap();
+        let result: io::Result<()> = replace(path, &InPlace::default(), |writer| {
+            writer.write_all(b"partial")?;
+            Err(io::Error::other("disk full"))
+        });
+        assert_eq!(result.unwrap_err().to_string(), "disk full");
+        assert_eq!(fs::read_to_string(path).unwrap(), "original");
+        assert!(!temp_path(Path::new(path)).exists());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_preserve_mtime() {
+        let path = "atomic_mtime.txt";
+        fs::write(path, "old").unwrap();
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let in_place = InPlace {
+            backup: None,
+            preserve_mtime: true,
+        };
+        replace(path, &in_place, |writer| writer.write_all(b"new")).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), "new");
+        assert_eq!(fs::metadata(path).unwrap().modified().unwrap(), modified);
+        replace(path, &InPlace::default(), |writer| {
+            writer.write_all(b"newer")
+        })
+        .unwrap();
+        assert_ne!(fs::metadata(path).unwrap().modified().unwrap(), modified);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let path = "atomic_mode.txt";
+        fs::write(path, "old").unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o751)).unwrap();
+        replace(path, &InPlace::default(), |writer| writer.write_all(b"new")).unwrap();
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o751);
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+use crate::format::json::{self, string, Value};
+use crate::{osname, MdltError};
+
+/// The version of the baseline format, which changes whenever it is
+/// written differently.
+const VERSION: &str = "1";
+
+/// The checks each file fails, by path: what a run found, with an entry for
+/// every file it analyzed, clean or not, or what a baseline recorded, with
+/// entries only for files that had problems then.
+pub type Found = BTreeMap<String, BTreeSet<String>>;
+
+/// The name a file goes by in a baseline: the path it was opened by,
+/// without a leading `./`, so that `mdlt -r .` and `mdlt -r src` agree.
+pub fn key(path: &str) -> String {
+    let mut path = path;
+    while let Some(rest) = path.strip_prefix("./") {
+        path = rest.trim_start_matches('/');
+    }
+    path.to_string()
+}
+
+/// The problems `--baseline` tolerates. Findings are matched on the path
+/// of the file and the name of the check, not on lines, so that editing a
+/// file elsewhere leaves its entry alone.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Baseline {
+    files: Found,
+}
+
+impl Baseline {
+    /// Reads the baseline at `path`; with `missing_ok`, one that is not
+    /// there yet is empty.
+    pub fn read(path: &str, missing_ok: bool) -> Result<Baseline, MdltError> {
+        let text = match fs::read_to_string(path) {
+            Err(e) if missing_ok && e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Baseline::default())
+            }
+            result => result.map_err(|e| MdltError::io(format!("reading baseline {}", path), e))?,
+        };
+        Baseline::parse(&text)
+            .map_err(|e| MdltError::Usage(format!("Error reading baseline {}: {}", path, e)))
+    }
+
+    fn parse(text: &str) -> Result<Baseline, String> {
+        let value = json::parse(text)?;
+        if value.get("version") != Some(&Value::Number(VERSION.to_string())) {
+            return Err("not a baseline written by this version of mdlt".to_string());
+        }
+        let Some(Value::Object(entries)) = value.get("files") else {
+            return Err("\"files\" must be an object".to_string());
+        };
+        let mut files = Found::new();
+        for (path, checks) in entries {
+            let invalid = || format!("\"{}\" must be an array of check names", path);
+            let Value::Array(checks) = checks else {
+                return Err(invalid());
+            };
+            let checks = checks
+                .iter()
+                .map(|check| check.as_str().map(str::to_string))
+                .collect::<Option<BTreeSet<String>>>()
+                .ok_or_else(invalid)?;
+            files.insert(path.clone(), checks);
+        }
+        Ok(Baseline { files })
+    }
+
+    /// Writes it one file per line, in order, so that it diffs cleanly.
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "{{")?;
+        writeln!(writer, "  \"version\": {},", VERSION)?;
+        if self.files.is_empty() {
+            return writeln!(writer, "  \"files\": {{}}\n}}");
+        }
+        writeln!(writer, "  \"files\": {{")?;
+        for (index, (path, checks)) in self.files.iter().enumerate() {
+            let checks: Vec<String> = checks.iter().map(|check| string(check)).collect();
+            let separator = if index + 1 < self.files.len() {
+                ","
+            } else {
+                ""
+            };
+            writeln!(
+                writer,
+                "    {}: [{}]{}",
+                string(path),
+                checks.join(", "),
+                separator
+            )?;
+        }
+        writeln!(writer, "  }}\n}}")
+    }
+
+    /// `--update-baseline`: records what this run `found` in place of what
+    /// the baseline had for the same files, and drops the entries of files
+    /// that no longer exist. Those of files the run did not look at are
+    /// kept.
+    pub fn update(&mut self, found: &Found) {
+        self.files
+            .retain(|path, _| !found.contains_key(path) && osname::path(path).exists());
+        self.files.extend(
+            found
+                .iter()
+                .filter(|(_, checks)| !checks.is_empty())
+                .map(|(path, checks)| (path.clone(), checks.clone())),
+        );
+    }
+
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        crate::atomic::create(Path::new(path), |writer| self.write(writer))
+    }
+
+    /// What `--update-baseline` says once it has written the baseline to
+    /// `path`: "Recorded 5 problems in 3 files in .mdlt-baseline.json".
+    pub fn recorded(&self, path: &str) -> String {
+        let count: usize = self.files.values().map(BTreeSet::len).sum();
+        format!(
+            "Recorded {} {} in {} file{} in {}",
+            count,
+            problems(count),
+            self.files.len(),
+            if self.files.len() == 1 { "" } else { "s" },
+            path
+        )
+    }
+
+    /// Sorts what a run `found` into the problems the baseline knows of and
+    /// those it does not.
+    pub fn compare(&self, found: &Found) -> Comparison {
+        let mut comparison = Comparison::default();
+        for (path, checks) in found {
+            let known = self.files.get(path);
+            for check in checks {
+                let problem = (path.clone(), check.clone());
+                if known.is_some_and(|known| known.contains(check)) {
+                    comparison.known.push(problem);
+                } else {
+                    comparison.new.push(problem);
+                }
+            }
+            comparison.fixed += known.map_or(0, |known| known.difference(checks).count());
+        }
+        comparison
+    }
+}
+
+/// What a run found, against a baseline.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Comparison {
+    /// The file and check of each problem the baseline does not have.
+    pub new: Vec<(String, String)>,
+    pub known: Vec<(String, String)>,
+    /// The problems the baseline has that are gone from the files the run
+    /// looked at.
+    pub fixed: usize,
+}
+
+impl Comparison {
+    /// The error of a run with new problems, which lists them apart from
+    /// the known ones; `None` without any.
+    pub fn failure(&self, baseline: &str) -> Option<String> {
+        if self.new.is_empty() {
+            return None;
+        }
+        let mut lines = vec![format!(
+            "{} new {} not in the baseline {}:",
+            self.new.len(),
+            problems(self.new.len()),
+            baseline
+        )];
+        lines.extend(listed(&self.new));
+        if !self.known.is_empty() {
+            lines.push(format!(
+                "{} known {}, in the baseline:",
+                self.known.len(),
+                problems(self.known.len())
+            ));
+            lines.extend(listed(&self.known));
+        }
+        Some(lines.join("\n"))
+    }
+
+    /// What a run without new problems says: "3 known problems in
+    /// .mdlt-baseline.json, none new", and how many were fixed.
+    pub fn summary(&self, baseline: &str) -> String {
+        let mut summary = format!(
+            "{} known {} in {}, none new",
+            self.known.len(),
+            problems(self.known.len()),
+            baseline
+        );
+        if self.fixed > 0 {
+            summary.push_str(&format!(
+                "; {} fixed since, which --update-baseline drops",
+                self.fixed
+            ));
+        }
+        summary
+    }
+}
+
+fn problems(count: usize) -> &'static str {
+    if count == 1 {
+        "problem"
+    } else {
+        "problems"
+    }
+}
+
+/// "  src/a.rs: final-newline, trailing-whitespace" for each file.
+fn listed(problems: &[(String, String)]) -> Vec<String> {
+    let mut by_file: Vec<(&str, Vec<&str>)> = Vec::new();
+    for (path, check) in problems {
+        match by_file.last_mut() {
+            Some((last, checks)) if last == path => checks.push(check),
+            _ => by_file.push((path, vec![check])),
+        }
+    }
+    by_file
+        .iter()
+        .map(|(path, checks)| format!("  {}: {}", path, checks.join(", ")))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(entries: &[(&str, &[&str])]) -> Found {
+        entries
+            .iter()
+            .map(|(path, checks)| {
+                let checks = checks.iter().map(|check| check.to_string()).collect();
+                (path.to_string(), checks)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(key("./src/a.rs"), "src/a.rs");
+        assert_eq!(key(".//./a"), "a");
+        assert_eq!(key("src/a.rs"), "src/a.rs");
+        assert_eq!(key(".hidden"), ".hidden");
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = Baseline {
+            files: found(&[
+                ("a.txt", &["final-newline", "trailing-whitespace"]),
+                ("b.txt", &["endings"]),
+            ]),
+        };
+        let now = found(&[
+            ("a.txt", &["trailing-whitespace"]),
+            ("b.txt", &["endings", "bom"]),
+            ("c.txt", &["trailing-whitespace"]),
+            ("d.txt", &[]),
+        ]);
+        let comparison = baseline.compare(&now);
+        assert_eq!(comparison.fixed, 1);
+        assert_eq!(
+            comparison.failure("base.json").unwrap(),
+            "2 new problems not in the baseline base.json:\n  b.txt: bom\n  c.txt: trailing-whitespace\n\
+             2 known problems, in the baseline:\n  a.txt: trailing-whitespace\n  b.txt: endings"
+        );
+        let comparison = baseline.compare(&found(&[("a.txt", &["final-newline"])]));
+        assert_eq!(comparison.failure("base.json"), None);
+        assert_eq!(
+            comparison.summary("base.json"),
+            "1 known problem in base.json, none new; 1 fixed since, which --update-baseline drops"
+        );
+    }
+
+    #[test]
+    fn test_write_and_parse() {
+        let mut baseline = Baseline::default();
+        let mut written = Vec::new();
+        baseline.write(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "{\n  \"version\": 1,\n  \"files\": {}\n}\n"
+        );
+        // The files are gone, so only what the run found is left.
+        baseline.files = found(&[("gone.txt", &["bom"])]);
+        baseline.update(&found(&[
+            ("b \"q\".txt", &["trailing-whitespace", "endings"]),
+            ("a.txt", &["bom"]),
+            ("clean.txt", &[]),
+        ]));
+        let mut written = Vec::new();
+        baseline.write(&mut written).unwrap();
+        let text = String::from_utf8(written).unwrap();
+        assert_eq!(
+            text,
+            "{\n  \"version\": 1,\n  \"files\": {\n    \"a.txt\": [\"bom\"],\n    \
+             \"b \\\"q\\\".txt\": [\"endings\", \"trailing-whitespace\"]\n  }\n}\n"
+        );
+        assert_eq!(
+            baseline.recorded("base.json"),
+            "Recorded 3 problems in 2 files in base.json"
+        );
+        assert_eq!(Baseline::parse(&text), Ok(baseline));
+        assert_eq!(
+            Baseline::parse("{\"version\": 2, \"files\": {}}").unwrap_err(),
+            "not a baseline written by this version of mdlt"
+        );
+        assert_eq!(
+            Baseline::parse("{\"version\": 1, \"files\": {\"a\": \"bom\"}}").unwrap_err(),
+            "\"a\" must be an array of check names"
+        );
+    }
+}
@@ -0,0 +1,84 @@
+/// How much of the start of a file is inspected.
+pub const SNIFF_LEN: usize = 8 * 1024;
+
+/// Guesses whether a file is binary from its first [`SNIFF_LEN`] bytes, the
+/// way grep and git do: any NUL byte, or more than 30% control bytes that
+/// do not appear in text, means binary. Bytes above 0x7F are not counted,
+/// since they are normal in UTF-8 and legacy 8-bit text.
+#[derive(Debug, Default)]
+pub struct Sniffer {
+    seen: usize,
+    suspicious: usize,
+    nul: bool,
+}
+
+impl Sniffer {
+    /// Inspects as much of `bytes` as still fits in the sniffed window.
+    pub fn feed(&mut self, bytes: &[u8]) {
+        let take = bytes.len().min(SNIFF_LEN - self.seen);
+        for &byte in &bytes[..take] {
+            match byte {
+                0x00 => self.nul = true,
+                // Bell, backspace, tab, LF, VT, FF, CR and escape are all
+                // common in text and terminal logs.
+                0x07..=0x0d | 0x1b => {}
+                0x01..=0x1f | 0x7f => self.suspicious += 1,
+                _ => {}
+            }
+        }
+        self.seen += take;
+    }
+
+    pub fn is_binary(&self) -> bool {
+        self.nul || self.suspicious * 10 > self.seen * 3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The first bytes of a 1x1 PNG.
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00\x00\x01\x00\x00\x00\x01\x08\x06\x00\x00\x00\x1f\x15\xc4\x89";
+
+    /// The start of an ELF executable header.
+    const ELF: &[u8] =
+        b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00\x03\x00>\x00\x01\x00\x00\x00";
+
+    fn sniff(bytes: &[u8]) -> bool {
+        let mut sniffer = Sniffer::default();
+        sniffer.feed(bytes);
+        sniffer.is_binary()
+    }
+
+    #[test]
+    fn test_binary_blobs() {
+        assert!(sniff(PNG));
+        assert!(sniff(ELF));
+        assert!(sniff(b"\x01\x02\x03\x04text"));
+    }
+
+    #[test]
+    fn test_text_is_not_binary() {
+        assert!(!sniff(b""));
+        assert!(!sniff(b"fn main() {\r\n\tprintln!(\"hi\");\r\n}\n"));
+        assert!(!sniff("caf\u{e9} \u{65e5}\u{672c}\n".as_bytes()));
+        assert!(!sniff(b"\x1b[31mred\x1b[0m\x07\x0c\n"));
+        assert!(!sniff(b"mostly text with one \x01 control byte"));
+    }
+
+    #[test]
+    fn test_only_the_window_is_inspected() {
+        let mut bytes = vec![b'a'; SNIFF_LEN];
+        bytes.push(0);
+        assert!(!sniff(&bytes));
+
+        let mut sniffer = Sniffer::default();
+        for chunk in bytes.chunks(100) {
+            sniffer.feed(chunk);
+        }
+        assert!(!sniffer.is_binary());
+        sniffer.feed(b"\x00");
+        assert!(!sniffer.is_binary());
+    }
+}
@@ -0,0 +1,229 @@
+use std::cmp::Reverse;
+
+use crate::figures;
+
+/// The runs of consecutive empty lines in a file, a lone one included,
+/// with whitespace-only lines among them under `--blank-as-empty`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BlankRuns {
+    pub runs: usize,
+    /// The length of the longest run and the line it starts on, the first
+    /// of them when several are as long.
+    pub longest: usize,
+    pub longest_at: usize,
+    /// How many runs are longer than `--max-blank-run`, only with it.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub over_limit: Option<usize>,
+}
+
+impl BlankRuns {
+    /// "14 (longest: 6 lines starting at line 203)".
+    /// The counts are grouped when `group`.
+    pub fn summary(&self, group: bool) -> String {
+        let mut summary = format!(
+            "{} (longest: {} starting at line {}",
+            figures::count(self.runs as u64, group),
+            figures::counted(self.longest as u64, "line", group),
+            self.longest_at
+        );
+        if let Some(over) = self.over_limit {
+            summary.push_str(&format!(
+                "; {} over --max-blank-run",
+                figures::count(over as u64, group)
+            ));
+        }
+        summary.push(')');
+        summary
+    }
+}
+
+impl BlankRuns {
+    /// Counts `other`'s runs too, its longest only if it is longer or, as
+    /// long, starts earlier.
+    fn add(&mut self, other: BlankRuns) {
+        self.runs += other.runs;
+        if (other.longest, Reverse(other.longest_at)) > (self.longest, Reverse(self.longest_at)) {
+            self.longest = other.longest;
+            self.longest_at = other.longest_at;
+        }
+        if let (Some(over), Some(other)) = (&mut self.over_limit, other.over_limit) {
+            *over += other;
+        }
+    }
+}
+
+/// Follows the run of empty lines the scan is in, keeping nothing but
+/// counts.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    limit: Option<usize>,
+    /// The length of the current run and the line it started on.
+    current: usize,
+    start: usize,
+    /// The length of the run the input starts with, once it has ended,
+    /// kept apart from `runs` until `finish` so that `merge` can join it to
+    /// the run the text before ends in.
+    leading: Option<usize>,
+    runs: Option<BlankRuns>,
+}
+
+impl Tracker {
+    pub fn new(limit: Option<usize>) -> Self {
+        Tracker {
+            limit,
+            ..Tracker::default()
+        }
+    }
+
+    /// Takes line `line`, which is `empty` or not.
+    pub fn end_line(&mut self, line: usize, empty: bool) {
+        if !empty {
+            self.end_run();
+        } else if self.current == 0 {
+            self.current = 1;
+            self.start = line;
+        } else {
+            self.current += 1;
+        }
+    }
+
+    fn end_run(&mut self) {
+        if self.current == 0 {
+            return;
+        }
+        match self.start {
+            1 => self.leading = Some(self.current),
+            start => self.count(self.current, start),
+        }
+        self.current = 0;
+    }
+
+    /// Counts a run of `length` lines starting at line `start`.
+    fn count(&mut self, length: usize, start: usize) {
+        self.add(BlankRuns {
+            runs: 1,
+            longest: length,
+            longest_at: start,
+            over_limit: self.limit.map(|limit| usize::from(length > limit)),
+        });
+    }
+
+    fn add(&mut self, runs: BlankRuns) {
+        match &mut self.runs {
+            Some(mine) => mine.add(runs),
+            None => self.runs = Some(runs),
+        }
+    }
+
+    /// Takes the tracker of the text right after this one's, which starts
+    /// on line `lines + 1`, as if this one had gone on through it. This
+    /// one's text must end with a line break.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker, lines: usize) {
+        // A run the text starts with goes on from the one this ends in.
+        let joined = next.leading.or((next.start == 1).then_some(next.current));
+        if let Some(length) = joined.filter(|&length| length > 0) {
+            if self.current == 0 {
+                self.start = lines + 1;
+            }
+            self.current += length;
+        }
+        if next.leading.is_some() || joined.is_none() {
+            self.end_run();
+        }
+        if let Some(mut runs) = next.runs {
+            runs.longest_at += lines;
+            self.add(runs);
+        }
+        if next.start > 1 {
+            self.current = next.current;
+            self.start = next.start + lines;
+        }
+    }
+
+    /// The runs of the file, or `None` when it has no empty line.
+    pub fn finish(mut self) -> Option<BlankRuns> {
+        self.end_run();
+        if let Some(length) = self.leading {
+            self.count(length, 1);
+        }
+        self.runs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn runs(lines: &str, limit: Option<usize>) -> Option<BlankRuns> {
+        let mut tracker = Tracker::new(limit);
+        for (index, line) in lines.chars().enumerate() {
+            tracker.end_line(index + 1, line == '.');
+        }
+        tracker.finish()
+    }
+
+    #[test]
+    fn test_runs() {
+        assert_eq!(runs("xxx", None), None);
+        assert_eq!(
+            runs("x..x...x.x...", Some(2)),
+            Some(BlankRuns {
+                runs: 4,
+                longest: 3,
+                longest_at: 5,
+                over_limit: Some(2),
+            })
+        );
+        let trailing = runs("x..", None).unwrap();
+        assert_eq!((trailing.runs, trailing.longest_at), (1, 2));
+        assert_eq!(
+            trailing.summary(true),
+            "1 (longest: 2 lines starting at line 2)"
+        );
+        assert_eq!(
+            runs(".", Some(0)).unwrap().summary(true),
+            "1 (longest: 1 line starting at line 1; 1 over --max-blank-run)"
+        );
+        let long = BlankRuns {
+            runs: 1200,
+            longest: 1500,
+            longest_at: 3,
+            over_limit: Some(1100),
+        };
+        assert_eq!(
+            long.summary(true),
+            "1,200 (longest: 1,500 lines starting at line 3; 1,100 over --max-blank-run)"
+        );
+        assert!(long
+            .summary(false)
+            .starts_with("1200 (longest: 1500 lines "));
+        // The leading run is counted last but still wins a tie.
+        assert_eq!(runs("..x.x..", None).unwrap().longest_at, 1);
+    }
+
+    #[test]
+    fn test_merge() {
+        let lines = "..x..x...x.x..";
+        for limit in [None, Some(1)] {
+            let whole = runs(lines, limit);
+            for split in 0..=lines.len() {
+                let (first, second) = lines.split_at(split);
+                let mut tracker = Tracker::new(limit);
+                for (index, line) in first.chars().enumerate() {
+                    tracker.end_line(index + 1, line == '.');
+                }
+                let mut next = Tracker::new(limit);
+                for (index, line) in second.chars().enumerate() {
+                    next.end_line(index + 1, line == '.');
+                }
+                tracker.merge(next, split);
+                assert_eq!(tracker.finish(), whole, "{:?} at {}", limit, split);
+            }
+        }
+    }
+}
@@ -0,0 +1,86 @@
+/// A byte-order mark found at the start of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Bom {
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-8"))]
+    Utf8,
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-16LE"))]
+    Utf16Le,
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-16BE"))]
+    Utf16Be,
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-32LE"))]
+    Utf32Le,
+    #[cfg_attr(feature = "serde", serde(rename = "UTF-32BE"))]
+    Utf32Be,
+}
+
+/// The longest byte-order mark, in bytes.
+pub const MAX_LEN: usize = 4;
+
+impl Bom {
+    /// Recognizes the byte-order mark `prefix` starts with. UTF-32LE is
+    /// checked before UTF-16LE since its mark begins with the same two bytes.
+    pub fn detect(prefix: &[u8]) -> Option<Bom> {
+        match prefix {
+            [0xff, 0xfe, 0x00, 0x00, ..] => Some(Bom::Utf32Le),
+            [0x00, 0x00, 0xfe, 0xff, ..] => Some(Bom::Utf32Be),
+            [0xef, 0xbb, 0xbf, ..] => Some(Bom::Utf8),
+            [0xff, 0xfe, ..] => Some(Bom::Utf16Le),
+            [0xfe, 0xff, ..] => Some(Bom::Utf16Be),
+            _ => None,
+        }
+    }
+
+    /// The encoded mark itself.
+    pub fn bytes(self) -> &'static [u8] {
+        match self {
+            Bom::Utf8 => b"\xef\xbb\xbf",
+            Bom::Utf16Le => b"\xff\xfe",
+            Bom::Utf16Be => b"\xfe\xff",
+            Bom::Utf32Le => b"\xff\xfe\x00\x00",
+            Bom::Utf32Be => b"\x00\x00\xfe\xff",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Bom::Utf8 => "UTF-8",
+            Bom::Utf16Le => "UTF-16LE",
+            Bom::Utf16Be => "UTF-16BE",
+            Bom::Utf32Le => "UTF-32LE",
+            Bom::Utf32Be => "UTF-32BE",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(Bom::detect(b"\xef\xbb\xbfabc"), Some(Bom::Utf8));
+        assert_eq!(Bom::detect(b"\xff\xfea\x00"), Some(Bom::Utf16Le));
+        assert_eq!(Bom::detect(b"\xfe\xff\x00a"), Some(Bom::Utf16Be));
+        assert_eq!(Bom::detect(b"\xff\xfe\x00\x00"), Some(Bom::Utf32Le));
+        assert_eq!(Bom::detect(b"\x00\x00\xfe\xff"), Some(Bom::Utf32Be));
+        assert_eq!(Bom::detect(b"\xff\xfe"), Some(Bom::Utf16Le));
+        assert_eq!(Bom::detect(b"\xef\xbb"), None);
+        assert_eq!(Bom::detect(b""), None);
+        assert_eq!(Bom::detect(b"plain"), None);
+    }
+
+    #[test]
+    fn test_bytes_round_trip() {
+        for bom in [
+            Bom::Utf8,
+            Bom::Utf16Le,
+            Bom::Utf16Be,
+            Bom::Utf32Le,
+            Bom::Utf32Be,
+        ] {
+            assert_eq!(Bom::detect(bom.bytes()), Some(bom), "{}", bom.name());
+            assert!(bom.bytes().len() <= MAX_LEN);
+        }
+    }
+}
@@ -0,0 +1,154 @@
+use std::fmt;
+
+use crate::format::json;
+
+/// What a failure budget counts: the files with problems for
+/// `--max-problem-files`, or each problem for `--max-problems`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counted {
+    Files,
+    Problems,
+}
+
+impl Counted {
+    /// The `counts` key of the JSON report.
+    fn name(self) -> &'static str {
+        match self {
+            Counted::Files => "problem_files",
+            Counted::Problems => "problems",
+        }
+    }
+
+    fn noun(self, count: usize) -> &'static str {
+        match (self, count) {
+            (Counted::Files, 1) => "problem file",
+            (Counted::Files, _) => "problem files",
+            (Counted::Problems, 1) => "problem",
+            (Counted::Problems, _) => "problems",
+        }
+    }
+}
+
+/// How a run fared against one budget.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub counted: Counted,
+    pub budget: usize,
+    pub observed: usize,
+}
+
+impl Verdict {
+    pub fn within(&self) -> bool {
+        self.observed <= self.budget
+    }
+
+    /// The `budgets` entry of the JSON summary: `{"counts": "problem_files",
+    /// "budget": 25, "observed": 17, "verdict": "ok"}`, or `"over"`.
+    pub fn json(&self) -> String {
+        format!(
+            "{{\"counts\": {}, \"budget\": {}, \"observed\": {}, \"verdict\": {}}}",
+            json::string(self.counted.name()),
+            self.budget,
+            self.observed,
+            json::string(if self.within() { "ok" } else { "over" })
+        )
+    }
+}
+
+/// "17 problem files, budget 25 — OK", or "— over by 6".
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} {}, budget {} — ",
+            self.observed,
+            self.counted.noun(self.observed),
+            self.budget
+        )?;
+        if self.within() {
+            write!(f, "OK")
+        } else {
+            write!(f, "over by {}", self.observed - self.budget)
+        }
+    }
+}
+
+/// Checks `problems`, a list of what was found in each file that has
+/// problems, against the budget for what `counted` counts. Over budget, the
+/// message names what went over it: the files past the budget, or each
+/// problem past it.
+pub fn check(
+    counted: Counted,
+    budget: usize,
+    problems: &[(String, Vec<String>)],
+) -> (Verdict, Option<String>) {
+    let listed: Vec<String> = match counted {
+        Counted::Files => problems.iter().map(|(name, _)| name.clone()).collect(),
+        Counted::Problems => problems
+            .iter()
+            .flat_map(|(name, found)| {
+                found
+                    .iter()
+                    .map(move |problem| format!("{}: {}", name, problem))
+            })
+            .collect(),
+    };
+    let verdict = Verdict {
+        counted,
+        budget,
+        observed: listed.len(),
+    };
+    if verdict.within() {
+        return (verdict, None);
+    }
+    let overflow: Vec<String> = listed[budget..]
+        .iter()
+        .map(|item| format!("  {}", item))
+        .collect();
+    let message = format!("{}:\n{}", verdict, overflow.join("\n"));
+    (verdict, Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let problems = vec![
+            (
+                "a.txt".to_string(),
+                vec!["line 2: trailing whitespace [trailing-whitespace]".to_string()],
+            ),
+            (
+                "b.txt".to_string(),
+                vec![
+                    "no final newline [final-newline]".to_string(),
+                    "expected LF, found 2 CRLF".to_string(),
+                ],
+            ),
+        ];
+        let (verdict, message) = check(Counted::Files, 2, &problems);
+        assert_eq!(verdict.to_string(), "2 problem files, budget 2 — OK");
+        assert_eq!(message, None);
+        assert_eq!(
+            verdict.json(),
+            "{\"counts\": \"problem_files\", \"budget\": 2, \"observed\": 2, \"verdict\": \"ok\"}"
+        );
+        let (verdict, message) = check(Counted::Problems, 1, &problems);
+        assert_eq!(verdict.to_string(), "3 problems, budget 1 — over by 2");
+        assert_eq!(
+            message.unwrap(),
+            "3 problems, budget 1 — over by 2:\n  b.txt: no final newline [final-newline]\n  b.txt: expected LF, found 2 CRLF"
+        );
+        assert!(verdict
+            .json()
+            .ends_with("\"observed\": 3, \"verdict\": \"over\"}"));
+        let (verdict, message) = check(Counted::Files, 0, &problems[..1]);
+        assert_eq!(verdict.to_string(), "1 problem file, budget 0 — over by 1");
+        assert_eq!(
+            message.unwrap(),
+            "1 problem file, budget 0 — over by 1:\n  a.txt"
+        );
+    }
+}
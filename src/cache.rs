@@ -0,0 +1,362 @@
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{self, Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::format::json::{self, string, Value};
+use crate::format::ReportOptions;
+use crate::gzip::Decompress;
+use crate::osname;
+use crate::scanner::ScanOptions;
+use crate::sha256::Sha256;
+use crate::FileStats;
+
+/// The first line of a cache file, which changes whenever entries are
+/// written differently.
+const HEADER: &str = "{\"mdlt_cache\": 1}";
+
+/// The name of the cache file in the platform cache directory.
+const FILE_NAME: &str = "results.jsonl";
+
+/// Where `--cache` keeps its results when not given a file:
+/// `$XDG_CACHE_HOME/mdlt`, else `~/Library/Caches/mdlt` on macOS,
+/// `%LOCALAPPDATA%\mdlt` on Windows and `~/.cache/mdlt` elsewhere.
+pub fn default_path() -> Option<PathBuf> {
+    let dir = env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(platform_dir)?;
+    Some(dir.join("mdlt").join(FILE_NAME))
+}
+
+#[cfg(target_os = "macos")]
+fn platform_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join("Library").join("Caches"))
+}
+
+#[cfg(windows)]
+fn platform_dir() -> Option<PathBuf> {
+    env::var_os("LOCALAPPDATA").map(PathBuf::from)
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+fn platform_dir() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache"))
+}
+
+/// What the results of a file depend on besides its contents: the version
+/// and the settings that change what is counted. An entry written under
+/// any other is analyzed again.
+pub fn fingerprint(scan: &ScanOptions, decompress: Decompress) -> String {
+    let mut hash = Sha256::default();
+    let settings = format!("{} {:?} {:?}", env!("CARGO_PKG_VERSION"), scan, decompress);
+    hash.update(settings.as_bytes());
+    hash.finish()[..16].to_string()
+}
+
+/// A file as it was when analyzed, which its entry is only used for while
+/// it still is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Key {
+    /// The absolute path, so that runs from other directories share it.
+    path: String,
+    size: u64,
+    /// Nanoseconds since the Unix epoch.
+    modified: u128,
+}
+
+/// One line of the cache file.
+struct Stored {
+    size: u64,
+    modified: u128,
+    config: String,
+    line: String,
+}
+
+/// The results `--cache` keeps between runs, keyed on each file's path,
+/// size and modification time. Entries are read when the run starts and
+/// the file is written again, all at once, when `save` is called.
+pub struct Cache {
+    path: PathBuf,
+    config: String,
+    /// Files modified in the second the run started, or later, may change
+    /// again without their modification time showing it, and are not
+    /// stored.
+    started: u64,
+    entries: HashMap<String, Stored>,
+    fresh: Mutex<HashMap<String, Stored>>,
+    /// The names of the files served from the cache.
+    hits: Mutex<HashSet<String>>,
+}
+
+impl Cache {
+    /// Reads the cache at `path` for settings with this `fingerprint`. A
+    /// missing file is an empty cache; one that cannot be read, or that
+    /// mdlt did not write, is warned about and then treated the same way.
+    pub fn open(path: PathBuf, fingerprint: String) -> Cache {
+        let entries = match fs::read_to_string(&path) {
+            Ok(text) => parse(&text).unwrap_or_else(|e| {
+                eprintln!("Warning: ignoring cache {}: {}", path.display(), e);
+                HashMap::new()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => {
+                eprintln!("Warning: ignoring cache {}: {}", path.display(), e);
+                HashMap::new()
+            }
+        };
+        let started = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        Cache {
+            path,
+            config: fingerprint,
+            started,
+            entries,
+            fresh: Mutex::new(HashMap::new()),
+            hits: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// How `path` is now, or `None` for what is not a regular file with a
+    /// modification time, which is never cached.
+    pub fn key(&self, path: &str) -> Option<Key> {
+        let path = osname::path(path);
+        let metadata = fs::metadata(&path).ok().filter(fs::Metadata::is_file)?;
+        let modified = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Key {
+            path: osname::encode(path::absolute(&path).ok()?.as_os_str()).into_owned(),
+            size: metadata.len(),
+            modified: modified.as_nanos(),
+        })
+    }
+
+    /// The results stored for the file at `key`, named `name`, if it has
+    /// not changed since and was analyzed with the same settings.
+    pub fn get(&self, key: &Key, name: &str) -> Option<FileStats> {
+        let stored = self.entries.get(&key.path)?;
+        if (stored.size, stored.modified) != (key.size, key.modified)
+            || stored.config != self.config
+        {
+            return None;
+        }
+        let mut stats = read_entry(&stored.line).ok()?;
+        stats.set_name(name);
+        self.hits.lock().unwrap().insert(name.to_string());
+        Some(stats)
+    }
+
+    /// Keeps `stats` for the file at `key`, as it was before it was read.
+    pub fn put(&self, key: Key, stats: &FileStats) {
+        if (key.modified / 1_000_000_000) as u64 >= self.started {
+            return;
+        }
+        let mut line = format!(
+            "{{\"path\": {}, \"size\": {}, \"modified\": {}, \"config\": {}, \
+             \"total_line_length\": {}, \"stats\": ",
+            string(&key.path),
+            key.size,
+            key.modified,
+            string(&self.config),
+            stats.total_line_length
+        )
+        .into_bytes();
+        let options = ReportOptions {
+            preview: true,
+            ..ReportOptions::default()
+        };
+        if json::write_object(stats, options, &mut line).is_err() {
+            return;
+        }
+        line.push(b'}');
+        let stored = Stored {
+            size: key.size,
+            modified: key.modified,
+            config: self.config.clone(),
+            line: String::from_utf8_lossy(&line).into_owned(),
+        };
+        self.fresh.lock().unwrap().insert(key.path, stored);
+    }
+
+    /// Whether the results for `name` came from the cache.
+    pub fn hit(&self, name: &str) -> bool {
+        self.hits.lock().unwrap().contains(name)
+    }
+
+    /// How many files were served from the cache.
+    pub fn hits(&self) -> usize {
+        self.hits.lock().unwrap().len()
+    }
+
+    /// Writes the cache back with what this run analyzed, leaving out the
+    /// entries of files that no longer exist. Nothing is written when
+    /// nothing changed.
+    pub fn save(self) -> io::Result<()> {
+        let mut entries = self.entries;
+        let fresh = self.fresh.into_inner().unwrap();
+        let before = entries.len();
+        entries.retain(|path, _| !fresh.contains_key(path) && osname::path(path).exists());
+        if fresh.is_empty() && entries.len() == before {
+            return Ok(());
+        }
+        entries.extend(fresh);
+        let mut lines: Vec<(&String, &Stored)> = entries.iter().collect();
+        lines.sort_by(|a, b| a.0.cmp(b.0));
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        crate::atomic::create(&self.path, |writer| {
+            writeln!(writer, "{}", HEADER)?;
+            for (_, stored) in lines {
+                writeln!(writer, "{}", stored.line)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Deletes the cache file at `path` for `--cache-clear`; one that is not
+/// there is already clear.
+pub fn clear(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+/// The entries of a cache file, by path.
+fn parse(text: &str) -> Result<HashMap<String, Stored>, String> {
+    let mut lines = text.lines();
+    if lines.next() != Some(HEADER) {
+        return Err("not a cache file written by this version of mdlt".to_string());
+    }
+    let mut entries = HashMap::new();
+    for (index, line) in lines.enumerate() {
+        let context = |message: String| format!("line {}: {}", index + 2, message);
+        let value = json::parse(line).map_err(context)?;
+        let path = value
+            .get("path")
+            .and_then(Value::as_str)
+            .ok_or_else(|| context("no \"path\"".to_string()))?;
+        let stored = Stored {
+            size: number(&value, "size").map_err(context)?,
+            modified: number(&value, "modified").map_err(context)?,
+            config: value
+                .get("config")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            line: line.to_string(),
+        };
+        entries.insert(path.to_string(), stored);
+    }
+    Ok(entries)
+}
+
+fn number<T: FromStr>(value: &Value, key: &str) -> Result<T, String> {
+    match value.get(key) {
+        Some(Value::Number(number)) => number
+            .parse()
+            .map_err(|_| format!("\"{}\" is not a count: {}", key, number)),
+        _ => Err(format!("no \"{}\"", key)),
+    }
+}
+
+/// The results in one line of a cache file, with the exact total line
+/// length the report only gives as a mean.
+fn read_entry(line: &str) -> Result<FileStats, String> {
+    let value = json::parse(line)?;
+    let stats = value
+        .get("stats")
+        .ok_or_else(|| "no \"stats\"".to_string())?;
+    let mut stats = json::read_object(stats)?;
+    stats.total_line_length = number(&value, "total_line_length")?;
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn cache(path: &Path) -> Cache {
+        Cache::open(
+            path.to_path_buf(),
+            fingerprint(&ScanOptions::default(), Decompress::Auto),
+        )
+    }
+
+    /// Sets the modification time of `path` back a minute, out of the
+    /// second a run starts in.
+    fn age(path: &str) {
+        let past = SystemTime::now() - Duration::from_secs(60);
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(past)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let dir = "cache_round_trip";
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir(dir).unwrap();
+        let file = "cache_round_trip/a.md";
+        fs::write(file, "# Title\r\nsome text\n\n").unwrap();
+        age(file);
+        let store = Path::new(dir).join("cache").join(FILE_NAME);
+        let first = cache(&store);
+        let key = first.key(file).unwrap();
+        assert!(first.get(&key, file).is_none());
+        let stats = crate::analyze_file(file).unwrap();
+        first.put(key.clone(), &stats);
+        first.save().unwrap();
+
+        let second = cache(&store);
+        let cached = second.get(&key, "a.md").unwrap();
+        assert_eq!(cached.file_name, "a.md");
+        assert_eq!(cached.total_line_length, stats.total_line_length);
+        assert_eq!(cached.markdown, stats.markdown);
+        assert_eq!(cached.line_ending(), stats.line_ending());
+        assert!(second.hit("a.md") && second.hits() == 1);
+
+        fs::write(file, "# Title\r\nsome text\n\nmore\n").unwrap();
+        age(file);
+        assert!(second.get(&second.key(file).unwrap(), file).is_none());
+        let other = Cache::open(store.clone(), "other".to_string());
+        assert!(other.get(&key, file).is_none());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_recent_files_are_not_stored() {
+        let file = "cache_recent.txt";
+        fs::write(file, "a\n").unwrap();
+        let store = PathBuf::from("cache_recent.jsonl");
+        let cache = cache(&store);
+        let key = cache.key(file).unwrap();
+        cache.put(key, &crate::analyze_file(file).unwrap());
+        cache.save().unwrap();
+        assert!(!store.exists());
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_unreadable_cache_is_empty() {
+        assert!(parse("garbage\n").is_err());
+        assert_eq!(
+            parse(&format!("{}\n{{\"path\": \"/a\"}}\n", HEADER))
+                .err()
+                .unwrap(),
+            "line 2: no \"size\""
+        );
+        assert!(parse(&format!("{}\n", HEADER)).unwrap().is_empty());
+        assert!(clear(Path::new("cache_never_written.jsonl")).is_ok());
+    }
+}
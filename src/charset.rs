@@ -0,0 +1,273 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::encoding::Encoding;
+
+/// How much of the start of a file without a byte-order mark
+/// `--detect-encoding` looks at.
+pub const SAMPLE_BYTES: usize = 8 * 1024;
+
+/// `--detect-encoding`: whether the guess is only reported, or also
+/// decides how a file that looks like UTF-16 is decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DetectEncoding {
+    Report,
+    Apply,
+}
+
+impl FromStr for DetectEncoding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "report" => Ok(DetectEncoding::Report),
+            "apply" => Ok(DetectEncoding::Apply),
+            _ => Err(format!(
+                "Unknown --detect-encoding mode '{}': expected report or apply",
+                value
+            )),
+        }
+    }
+}
+
+/// What the bytes of a file without a byte-order mark look like.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Charset {
+    #[cfg_attr(feature = "serde", serde(rename = "utf-8"))]
+    Utf8,
+    #[cfg_attr(feature = "serde", serde(rename = "utf-16le"))]
+    Utf16Le,
+    #[cfg_attr(feature = "serde", serde(rename = "utf-16be"))]
+    Utf16Be,
+    /// Neither UTF-8 nor UTF-16, so most likely one of the single-byte
+    /// charsets, which cannot be told apart from their bytes.
+    #[cfg_attr(feature = "serde", serde(rename = "unknown-8bit"))]
+    EightBit,
+}
+
+impl Charset {
+    /// The name JSON gives it.
+    #[cfg(feature = "cli")]
+    pub fn name(self) -> &'static str {
+        match self {
+            Charset::Utf8 => "utf-8",
+            Charset::Utf16Le => "utf-16le",
+            Charset::Utf16Be => "utf-16be",
+            Charset::EightBit => "unknown-8bit",
+        }
+    }
+
+    /// The encoding `--detect-encoding=apply` decodes it with, where that
+    /// differs from reading bytes.
+    fn decoding(self) -> Option<Encoding> {
+        match self {
+            Charset::Utf16Le => Some(Encoding::Utf16Le),
+            Charset::Utf16Be => Some(Encoding::Utf16Be),
+            Charset::Utf8 | Charset::EightBit => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Confidence {
+    Low,
+    Medium,
+    High,
+}
+
+impl Confidence {
+    pub fn name(self) -> &'static str {
+        match self {
+            Confidence::Low => "low",
+            Confidence::Medium => "medium",
+            Confidence::High => "high",
+        }
+    }
+}
+
+/// What `--detect-encoding` made of a file without a byte-order mark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Guess {
+    pub charset: Charset,
+    pub confidence: Confidence,
+    /// The bytes from 0x80 up in the whole file, for an 8-bit guess.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub high_bytes: Option<usize>,
+    /// Whether `--detect-encoding=apply` decoded the file as guessed.
+    pub decoded: bool,
+}
+
+impl Guess {
+    /// The guess for `sample`, the start of a file or all of it when
+    /// `complete`. NUL bytes on every other byte make UTF-16, ASCII text
+    /// being the most of it; then UTF-8 is anything it decodes, but for a
+    /// sequence cut off where the sample ends; what is left is 8-bit.
+    pub fn new(sample: &[u8], complete: bool) -> Self {
+        let guess = |charset, confidence| Guess {
+            charset,
+            confidence,
+            high_bytes: None,
+            decoded: false,
+        };
+        if let Some((charset, confidence)) = utf16(sample) {
+            return guess(charset, confidence);
+        }
+        let valid = match std::str::from_utf8(sample) {
+            Ok(_) => true,
+            Err(error) => !complete && error.error_len().is_none(),
+        };
+        match (valid, sample.iter().any(|&byte| byte >= 0x80)) {
+            (true, true) => guess(Charset::Utf8, Confidence::High),
+            // ASCII reads the same in every charset mdlt would guess.
+            (true, false) if !sample.is_empty() => guess(Charset::Utf8, Confidence::Medium),
+            (true, false) => guess(Charset::Utf8, Confidence::Low),
+            (false, _) => guess(Charset::EightBit, Confidence::Low),
+        }
+    }
+
+    /// The encoding to decode with instead of reading bytes, with the guess
+    /// marked as decoded, under `--detect-encoding=apply`.
+    pub fn apply(&mut self) -> Option<Encoding> {
+        let encoding = self.charset.decoding()?;
+        self.decoded = true;
+        Some(encoding)
+    }
+}
+
+/// UTF-16 when NUL bytes fill one half of the byte pairs and hardly any
+/// of the other: nine in ten is sure, two in five likely.
+fn utf16(sample: &[u8]) -> Option<(Charset, Confidence)> {
+    let pairs = sample.len() / 2;
+    if pairs == 0 {
+        return None;
+    }
+    let (mut even, mut odd) = (0, 0);
+    for pair in sample.chunks_exact(2) {
+        even += usize::from(pair[0] == 0);
+        odd += usize::from(pair[1] == 0);
+    }
+    let (charset, nuls, other) = match odd >= even {
+        true => (Charset::Utf16Le, odd, even),
+        false => (Charset::Utf16Be, even, odd),
+    };
+    if other * 4 > nuls {
+        return None;
+    }
+    match nuls * 10 {
+        share if share >= pairs * 9 => Some((charset, Confidence::High)),
+        share if share >= pairs * 4 => Some((charset, Confidence::Medium)),
+        _ => None,
+    }
+}
+
+/// As the report gives it: "UTF-16LE (high confidence)", or
+/// "unknown 8-bit (possibly Windows-1252/Latin-1), 37 bytes ≥ 0x80 (low
+/// confidence)".
+impl fmt::Display for Guess {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.charset {
+            Charset::Utf8 => write!(f, "UTF-8")?,
+            Charset::Utf16Le => write!(f, "UTF-16LE")?,
+            Charset::Utf16Be => write!(f, "UTF-16BE")?,
+            Charset::EightBit => write!(f, "unknown 8-bit (possibly Windows-1252/Latin-1)")?,
+        }
+        if let Some(high) = self.high_bytes {
+            write!(
+                f,
+                ", {} byte{} ≥ 0x80",
+                high,
+                if high == 1 { "" } else { "s" }
+            )?;
+        }
+        write!(f, " ({} confidence", self.confidence.name())?;
+        match self.decoded {
+            true => write!(f, ", decoded as such)"),
+            false => write!(f, ")"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn utf16be(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_be_bytes).collect()
+    }
+
+    fn charset(sample: &[u8]) -> (Charset, Confidence) {
+        let guess = Guess::new(sample, true);
+        (guess.charset, guess.confidence)
+    }
+
+    #[test]
+    fn test_guess() {
+        assert_eq!(
+            charset("café\r\n".as_bytes()),
+            (Charset::Utf8, Confidence::High)
+        );
+        assert_eq!(charset(b"plain\n"), (Charset::Utf8, Confidence::Medium));
+        assert_eq!(charset(b""), (Charset::Utf8, Confidence::Low));
+        assert_eq!(
+            charset(&utf16le("line one\r\nline two\r\n")),
+            (Charset::Utf16Le, Confidence::High)
+        );
+        assert_eq!(
+            charset(&utf16be("line one\nline two\n")),
+            (Charset::Utf16Be, Confidence::High)
+        );
+        // Half CJK, whose code units have no NUL byte.
+        assert_eq!(
+            charset(&utf16le("日本語 abc")),
+            (Charset::Utf16Le, Confidence::Medium)
+        );
+        assert_eq!(
+            charset(b"caf\xe9 cr\xe8me\r\n"),
+            (Charset::EightBit, Confidence::Low)
+        );
+        // NULs on both sides are not UTF-16, nor UTF-8 with other bytes.
+        assert_eq!(
+            charset(b"\x00\x00\x00\x00\xff\xfe"),
+            (Charset::EightBit, Confidence::Low)
+        );
+    }
+
+    #[test]
+    fn test_cut_off_sequences() {
+        let sample = "aé".as_bytes();
+        let cut = &sample[..2];
+        assert_eq!(Guess::new(cut, false).charset, Charset::Utf8);
+        assert_eq!(Guess::new(cut, true).charset, Charset::EightBit);
+    }
+
+    #[test]
+    fn test_apply_and_display() {
+        let mut guess = Guess::new(&utf16le("a\nb\n"), true);
+        assert_eq!(guess.apply(), Some(Encoding::Utf16Le));
+        assert_eq!(
+            guess.to_string(),
+            "UTF-16LE (high confidence, decoded as such)"
+        );
+        let mut guess = Guess::new(b"caf\xe9\n", true);
+        assert_eq!(guess.apply(), None);
+        guess.high_bytes = Some(1);
+        assert_eq!(
+            guess.to_string(),
+            "unknown 8-bit (possibly Windows-1252/Latin-1), 1 byte ≥ 0x80 (low confidence)"
+        );
+    }
+}
@@ -0,0 +1,707 @@
+use std::fmt;
+#[cfg(feature = "cli")]
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[cfg(feature = "cli")]
+use crate::cli::Options;
+#[cfg(feature = "cli")]
+use crate::FileStats;
+
+/// How much a violation matters. Only errors fail the run, and warnings
+/// too under `--warnings-as-errors`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    /// The name `--severity` and JSON give it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "info",
+        }
+    }
+
+    /// The style the text report tags it with.
+    pub fn style(self) -> &'static str {
+        match self {
+            Severity::Error => crate::color::RED,
+            Severity::Warning => crate::color::YELLOW,
+            Severity::Info => crate::color::CYAN,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "error" => Ok(Severity::Error),
+            "warning" => Ok(Severity::Warning),
+            "info" => Ok(Severity::Info),
+            _ => Err(format!(
+                "Unknown severity '{}': expected error, warning or info",
+                value
+            )),
+        }
+    }
+}
+
+/// Something a check found wrong with a file, on `line` when it is about
+/// one line rather than the whole file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Violation {
+    /// The name of the check, as `--enable` takes it.
+    pub check: String,
+    pub severity: Severity,
+    pub line: Option<usize>,
+    pub message: String,
+    /// An `mdlt:ignore-line` or `mdlt:ignore-file` marker silences it, so
+    /// that it is counted but fails nothing.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub suppressed: bool,
+}
+
+/// As the text report lists it: "line 4: trailing whitespace
+/// [trailing-whitespace]".
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        write!(f, "{} [{}]", self.message, self.check)
+    }
+}
+
+/// The flag that gives a check its setting, and whether it was given.
+#[cfg(feature = "cli")]
+type Setting = (&'static str, fn(&Options) -> bool);
+
+/// The line, if any, and message of a violation.
+#[cfg(feature = "cli")]
+type Found = (Option<usize>, String);
+
+/// A check that can fail the run, by the name `--enable`, `--disable` and
+/// `--list-checks` know it by.
+#[cfg(feature = "cli")]
+pub struct Check {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// Whether it is on unless turned off; a check that needs a setting
+    /// does nothing until that is given.
+    pub default: bool,
+    /// The severity of what it finds unless `--severity` says otherwise.
+    pub severity: Severity,
+    on: fn(&Options) -> bool,
+    set: fn(&mut Options, bool),
+    needs: Option<Setting>,
+    /// What the files failing it do, as in "3 of 9 files have trailing
+    /// whitespace".
+    problem: fn(&Options) -> String,
+    find: fn(&Options, &FileStats) -> Vec<Found>,
+}
+
+#[cfg(feature = "cli")]
+impl Check {
+    /// Whether the check is on and has what it needs, so that it can fail.
+    pub fn active(&self, options: &Options) -> bool {
+        (self.on)(options) && self.needs.is_none_or(|(_, given)| given(options))
+    }
+
+    /// Turns the check on or off, for `--enable` and `--disable`.
+    pub fn switch(&self, options: &mut Options, on: bool) {
+        (self.set)(options, on);
+    }
+
+    /// The severity `--severity` gives the check, or else its own.
+    pub fn severity(&self, options: &Options) -> Severity {
+        severity(options, self.name, self.severity)
+    }
+
+    /// Whether what the check finds fails the run: it is active and of a
+    /// severity that fails it.
+    pub fn fails(&self, options: &Options) -> bool {
+        self.active(options) && failing(options, self.severity(options))
+    }
+
+    pub fn problem(&self, options: &Options) -> String {
+        (self.problem)(options)
+    }
+
+    /// The error for the check turned on by name without its setting.
+    pub fn unmet(&self, options: &Options) -> Option<String> {
+        let (flag, given) = self.needs?;
+        (!given(options)).then(|| {
+            format!(
+                "--enable {} checks what {} sets; give it too",
+                self.name, flag
+            )
+        })
+    }
+
+    pub fn violations(&self, options: &Options, file: &FileStats) -> Vec<Violation> {
+        let severity = self.severity(options);
+        (self.find)(options, file)
+            .into_iter()
+            .map(|(line, message)| Violation {
+                check: self.name.to_string(),
+                severity,
+                line,
+                message,
+                suppressed: file
+                    .suppressions
+                    .iter()
+                    .any(|suppression| suppression.covers(self.name, line)),
+            })
+            .collect()
+    }
+}
+
+/// The severity `--severity` gives the check named `name`, built in or
+/// external, or else `default`.
+#[cfg(feature = "cli")]
+pub fn severity(options: &Options, name: &str, default: Severity) -> Severity {
+    options
+        .severities
+        .iter()
+        .find(|(given, _)| given == name)
+        .map_or(default, |&(_, severity)| severity)
+}
+
+/// Whether violations of `severity` fail the run: errors do, and warnings
+/// too under `--warnings-as-errors`.
+#[cfg(feature = "cli")]
+pub fn failing(options: &Options, severity: Severity) -> bool {
+    match severity {
+        Severity::Error => true,
+        Severity::Warning => options.warnings_as_errors,
+        Severity::Info => false,
+    }
+}
+
+/// "a NUL byte", or "the first of 3 NUL bytes".
+#[cfg(feature = "cli")]
+fn first_of(count: usize, what: &str) -> String {
+    match count {
+        1 => format!("a {}", what),
+        _ => format!("the first of {} {}s", count, what),
+    }
+}
+
+/// Every check, in the order the run reports the ones that fail.
+#[cfg(feature = "cli")]
+pub const CHECKS: &[Check] = &[
+    Check {
+        name: "encoding",
+        description: "fail on invalid UTF-8 (--strict-encoding)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.strict_encoding,
+        set: |options, on| {
+            options.strict_encoding = on;
+            options.scan.check_encoding |= on;
+        },
+        needs: None,
+        problem: |_| "are not valid UTF-8".to_string(),
+        find: |_, file| {
+            let Some(report) = &file.utf8 else {
+                return Vec::new();
+            };
+            report
+                .first
+                .iter()
+                .map(|sequence| {
+                    (
+                        Some(sequence.line),
+                        format!("invalid UTF-8 at byte {}", sequence.offset),
+                    )
+                })
+                .collect()
+        },
+    },
+    Check {
+        name: "control-chars",
+        description: "fail on NUL bytes and control characters (--fail-on-control-chars)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_control_chars,
+        set: |options, on| options.fail_on_control_chars = on,
+        needs: None,
+        problem: |_| "contain NUL bytes or control characters".to_string(),
+        find: |_, file| {
+            let nul = file
+                .first_nul_line
+                .map(|line| (line, first_of(file.nul_bytes, "NUL byte")));
+            let control = file
+                .first_control_line
+                .map(|line| (line, first_of(file.control_chars, "control character")));
+            nul.into_iter()
+                .chain(control)
+                .map(|(line, message)| (Some(line), message))
+                .collect()
+        },
+    },
+    Check {
+        name: "non-ascii",
+        description: "fail on characters above U+007F (--fail-on-non-ascii)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_non_ascii,
+        set: |options, on| options.fail_on_non_ascii = on,
+        needs: None,
+        problem: |_| "contain non-ASCII characters".to_string(),
+        find: |_, file| {
+            let Some(line) = file.first_non_ascii_line else {
+                return Vec::new();
+            };
+            vec![(
+                Some(line),
+                format!(
+                    "{} at column {}",
+                    first_of(file.non_ascii_chars, "non-ASCII character"),
+                    file.first_non_ascii_column.unwrap_or(1)
+                ),
+            )]
+        },
+    },
+    Check {
+        name: "embedded-cr",
+        description: "fail on carriage returns inside lines (--fail-on-embedded-cr)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_embedded_cr,
+        set: |options, on| options.fail_on_embedded_cr = on,
+        needs: None,
+        problem: |_| "have carriage returns inside lines".to_string(),
+        find: |_, file| {
+            file.embedded_cr_at
+                .iter()
+                .map(|&(line, column)| {
+                    (
+                        Some(line),
+                        format!("carriage return inside the line at column {}", column),
+                    )
+                })
+                .collect()
+        },
+    },
+    Check {
+        name: "unstable",
+        description: "fail on files that grew or shrank while read (--fail-on-unstable)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_unstable,
+        set: |options, on| options.fail_on_unstable = on,
+        needs: None,
+        problem: |_| "changed while they were read".to_string(),
+        find: |_, file| {
+            let grew = file
+                .grew_during_scan
+                .then(|| "grew while it was read".to_string());
+            let shrank = file
+                .truncated_during_scan
+                .then(|| "shrank while it was read".to_string());
+            grew.into_iter()
+                .chain(shrank)
+                .map(|message| (None, message))
+                .collect()
+        },
+    },
+    Check {
+        name: "bom",
+        description: "fail on a byte-order mark",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_bom,
+        set: |options, on| options.fail_on_bom = on,
+        needs: None,
+        problem: |_| "begin with a byte-order mark".to_string(),
+        find: |_, file| {
+            file.bom
+                .map(|bom| (Some(1), format!("{} byte-order mark", bom.name())))
+                .into_iter()
+                .collect()
+        },
+    },
+    Check {
+        name: "trailing-whitespace",
+        description: "fail on lines ending in spaces or tabs (--fail-on-trailing-whitespace)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_trailing_whitespace,
+        set: |options, on| options.fail_on_trailing_whitespace = on,
+        needs: None,
+        problem: |_| "have trailing whitespace".to_string(),
+        find: |_, file| {
+            file.trailing_whitespace_at
+                .iter()
+                .map(|&line| (Some(line), "trailing whitespace".to_string()))
+                .collect()
+        },
+    },
+    Check {
+        name: "final-newline",
+        description: "fail on a last line without a newline (--fail-on-missing-final-newline)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_missing_final_newline,
+        set: |options, on| options.fail_on_missing_final_newline = on,
+        needs: None,
+        problem: |_| "do not end with a newline".to_string(),
+        find: |_, file| match file.final_newline {
+            Some(false) => vec![(
+                Some(file.total_lines),
+                "no newline at the end of the file".to_string(),
+            )],
+            _ => Vec::new(),
+        },
+    },
+    Check {
+        name: "mixed-indentation",
+        description: "fail on files indented with both tabs and spaces \
+                      (--fail-on-mixed-indentation)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_mixed_indentation,
+        set: |options, on| options.fail_on_mixed_indentation = on,
+        needs: None,
+        problem: |_| "mix tab and space indentation".to_string(),
+        find: |_, file| match file.indentation.is_mixed() {
+            true => vec![(
+                None,
+                format!("indented with {}", file.indentation.summary(false)),
+            )],
+            false => Vec::new(),
+        },
+    },
+    Check {
+        name: "mixed-indent",
+        description: "fail on the lines --check=mixed-indent finds",
+        default: true,
+        severity: Severity::Error,
+        on: |options| options.fail_on_mixed_indent,
+        set: |options, on| options.fail_on_mixed_indent = on,
+        needs: Some(("--check=mixed-indent", |options| {
+            options.scan.mixed_indent.is_some()
+        })),
+        problem: |_| "have lines with mixed indentation".to_string(),
+        find: |_, file| {
+            let Some(mixed) = &file.mixed_indent else {
+                return Vec::new();
+            };
+            mixed
+                .first
+                .iter()
+                .map(|&line| (Some(line), "mixed indentation".to_string()))
+                .collect()
+        },
+    },
+    Check {
+        name: "line-length",
+        description: "fail on lines longer than --max-line-length",
+        default: true,
+        severity: Severity::Error,
+        on: |options| options.fail_on_long_lines && !options.no_fail,
+        set: |options, on| options.fail_on_long_lines = on,
+        needs: Some(("--max-line-length", |options| {
+            options.scan.line_limit.is_some()
+        })),
+        problem: |_| "have lines longer than --max-line-length".to_string(),
+        find: |_, file| {
+            let Some(long) = &file.long_lines else {
+                return Vec::new();
+            };
+            // `--max-listed-lines 0` lists none of them.
+            if long.first.is_empty() && long.count > 0 {
+                return vec![(
+                    None,
+                    format!("{} lines longer than {} columns", long.count, long.limit),
+                )];
+            }
+            long.first
+                .iter()
+                .map(|&line| (Some(line), format!("longer than {} columns", long.limit)))
+                .collect()
+        },
+    },
+    Check {
+        name: "trailing-blank-lines",
+        description: "fail on more blank lines at the end than --max-trailing-blank-lines",
+        default: true,
+        severity: Severity::Error,
+        on: |options| options.fail_on_trailing_blank_lines,
+        set: |options, on| options.fail_on_trailing_blank_lines = on,
+        needs: Some(("--max-trailing-blank-lines", |options| {
+            options.max_trailing_blank_lines.is_some()
+        })),
+        problem: |options| {
+            let limit = options.max_trailing_blank_lines.unwrap_or(0);
+            format!(
+                "end with more than {} blank line{}",
+                limit,
+                if limit == 1 { "" } else { "s" }
+            )
+        },
+        find: |options, file| {
+            let limit = options.max_trailing_blank_lines.unwrap_or(0);
+            // The blank lines a head ends on need not end the file.
+            match file.trailing_blank_lines > limit && !file.truncated {
+                true => vec![(
+                    None,
+                    format!(
+                        "ends with {} blank lines, more than {}",
+                        file.trailing_blank_lines, limit
+                    ),
+                )],
+                false => Vec::new(),
+            }
+        },
+    },
+    Check {
+        name: "blank-runs",
+        description: "fail on runs of more empty lines than --max-blank-run",
+        default: true,
+        severity: Severity::Error,
+        on: |options| options.fail_on_blank_runs,
+        set: |options, on| options.fail_on_blank_runs = on,
+        needs: Some(("--max-blank-run", |options| {
+            options.scan.max_blank_run.is_some()
+        })),
+        problem: |options| {
+            let limit = options.scan.max_blank_run.unwrap_or(0);
+            format!(
+                "have more than {} empty line{} in a row",
+                limit,
+                if limit == 1 { "" } else { "s" }
+            )
+        },
+        find: |options, file| {
+            let limit = options.scan.max_blank_run.unwrap_or(0);
+            let Some(runs) = file.blank_runs.filter(|runs| runs.longest > limit) else {
+                return Vec::new();
+            };
+            let message = match runs.over_limit {
+                Some(over) if over > 1 => format!(
+                    "the longest of {} runs of more than {} blank lines, {} in a row",
+                    over, limit, runs.longest
+                ),
+                _ => format!("{} blank lines in a row, more than {}", runs.longest, limit),
+            };
+            vec![(Some(runs.longest_at), message)]
+        },
+    },
+    Check {
+        name: "mixed-endings",
+        description: "fail on files with more than one line ending (--fail-on-mixed)",
+        default: false,
+        severity: Severity::Error,
+        on: |options| options.fail_on_mixed,
+        set: |options, on| options.fail_on_mixed = on,
+        needs: None,
+        problem: |_| "mix line endings".to_string(),
+        find: |_, file| match file.is_mixed() {
+            true => vec![(
+                None,
+                format!("mixes line endings: {}", crate::ending_counts_summary(file)),
+            )],
+            false => Vec::new(),
+        },
+    },
+];
+
+/// The check named `name`, for `flag`'s error when there is none.
+#[cfg(feature = "cli")]
+pub fn find(flag: &str, name: &str) -> Result<&'static Check, String> {
+    CHECKS
+        .iter()
+        .find(|check| check.name == name)
+        .ok_or_else(|| {
+            let known: Vec<&str> = CHECKS.iter().map(|check| check.name).collect();
+            format!(
+                "{}: unknown check '{}': expected {}",
+                flag,
+                name,
+                known.join(", ")
+            )
+        })
+}
+
+/// `--checks`: which checks to start from before `--enable` and
+/// `--disable`.
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    All,
+    None,
+    Default,
+}
+
+#[cfg(feature = "cli")]
+impl FromStr for Preset {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "all" => Ok(Preset::All),
+            "none" => Ok(Preset::None),
+            "default" => Ok(Preset::Default),
+            _ => Err(format!(
+                "Unknown --checks value '{}': expected all, none or default",
+                value
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl Preset {
+    /// Turns every check on or off as the preset has it.
+    pub fn apply(self, options: &mut Options) {
+        for check in CHECKS {
+            let on = match self {
+                Preset::All => true,
+                Preset::None => false,
+                Preset::Default => check.default,
+            };
+            (check.set)(options, on);
+        }
+    }
+}
+
+/// The checks that can fail the run with these options.
+#[cfg(feature = "cli")]
+pub fn active(options: &Options) -> Vec<&'static Check> {
+    CHECKS
+        .iter()
+        .filter(|check| check.active(options))
+        .collect()
+}
+
+/// What the active checks find wrong with `file`, check by check.
+#[cfg(feature = "cli")]
+pub fn violations(options: &Options, file: &FileStats) -> Vec<Violation> {
+    active(options)
+        .into_iter()
+        .flat_map(|check| check.violations(options, file))
+        .collect()
+}
+
+/// `--severity`: the check and severity of each `NAME=LEVEL` in `value`.
+/// The names are left for the end of the command line to check, which may
+/// name external checks after them.
+#[cfg(feature = "cli")]
+pub fn overrides(value: &str) -> Result<Vec<(String, Severity)>, String> {
+    value
+        .split(',')
+        .map(|item| {
+            let (name, level) = item
+                .split_once('=')
+                .ok_or_else(|| format!("--severity: expected <check>=<level>, not '{}'", item))?;
+            let severity = level
+                .parse()
+                .map_err(|error| format!("--severity {}: {}", name, error))?;
+            Ok((name.to_string(), severity))
+        })
+        .collect()
+}
+
+/// `--list-checks`: a line for each check with whether it is on by default
+/// and the severity of what it finds.
+#[cfg(feature = "cli")]
+pub fn list(writer: &mut impl Write) -> io::Result<()> {
+    let width = CHECKS
+        .iter()
+        .map(|check| check.name.len())
+        .max()
+        .unwrap_or(0);
+    for check in CHECKS {
+        writeln!(
+            writer,
+            "{:<width$}  {:<3}  {:<7}  {}",
+            check.name,
+            if check.default { "on" } else { "off" },
+            check.severity.name(),
+            check.description,
+            width = width
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+    use crate::cli::parse_args;
+
+    fn options(argv: &[&str]) -> Options {
+        let args: Vec<String> = argv.iter().map(|arg| arg.to_string()).collect();
+        parse_args(&args).unwrap()
+    }
+
+    #[test]
+    fn test_registry() {
+        // The defaults are those of a bare command line.
+        let bare = options(&["mdlt", "a"]);
+        for check in CHECKS {
+            assert_eq!((check.on)(&bare), check.default, "{}", check.name);
+        }
+        let mut names: Vec<&str> = CHECKS.iter().map(|check| check.name).collect();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(names.len(), CHECKS.len());
+    }
+
+    #[test]
+    fn test_violations() {
+        let options = options(&[
+            "mdlt",
+            "--checks=all",
+            "--disable",
+            "non-ascii",
+            "--max-line-length=3",
+            "a",
+        ]);
+        let file = crate::analyze_bytes_with(
+            b"\xef\xbb\xbfshort \r\nlong line\nend".to_vec().as_slice(),
+            "a.txt".to_string(),
+            options.scan,
+        );
+        let found: Vec<String> = violations(&options, &file)
+            .iter()
+            .map(Violation::to_string)
+            .collect();
+        assert_eq!(
+            found,
+            [
+                "line 1: UTF-8 byte-order mark [bom]",
+                "line 1: trailing whitespace [trailing-whitespace]",
+                "line 3: no newline at the end of the file [final-newline]",
+                "line 1: longer than 3 columns [line-length]",
+                "line 2: longer than 3 columns [line-length]",
+                "mixes line endings: 1 CRLF, 1 LF [mixed-endings]",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_list() {
+        let mut listed = Vec::new();
+        list(&mut listed).unwrap();
+        let listed = String::from_utf8(listed).unwrap();
+        assert_eq!(listed.lines().count(), CHECKS.len());
+        assert!(listed.contains("line-length           on   error    fail on lines longer than"));
+        assert!(listed.contains("bom                   off  error    fail on a byte-order mark\n"));
+    }
+}
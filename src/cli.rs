@@ -0,0 +1,5422 @@
+use std::mem;
+use std::slice;
+use std::str::FromStr;
+use std::time::Duration;
+
+use crate::cache;
+use crate::charset::DetectEncoding;
+use crate::checks::{self, Preset, Severity};
+use crate::compare;
+use crate::encoding::Encoding;
+use crate::external::{self, ExternalCheck};
+use crate::filetype::Types;
+use crate::format::template::{self, Template};
+use crate::format::{OutputFormat, ReportOptions};
+use crate::gzip::Decompress;
+use crate::histogram::Buckets;
+use crate::layout::Layout;
+use crate::mmap::MmapMode;
+use crate::parallel;
+use crate::pattern::Patterns;
+use crate::preview;
+use crate::regex::{Regex, Units};
+use crate::sample;
+use crate::scanner::{LineLimit, MixedIndent, ScanOptions};
+use crate::top::TopLines;
+use crate::tree;
+use crate::walk::WalkOptions;
+use crate::window::{Head, Window};
+
+/// What to do with files that look binary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreatBinary {
+    /// Leave them out of the report, listing them as skipped.
+    Skip,
+    /// Report their line counts like any other file.
+    Analyze,
+    /// Treat them as errors.
+    Fail,
+}
+
+impl FromStr for TreatBinary {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "skip" => Ok(TreatBinary::Skip),
+            "analyze" => Ok(TreatBinary::Analyze),
+            "fail" => Ok(TreatBinary::Fail),
+            other => Err(format!(
+                "Unknown --treat-binary value '{}': expected skip, analyze or fail",
+                other
+            )),
+        }
+    }
+}
+
+/// The line ending `--convert` rewrites files to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvertTo {
+    Lf,
+    Crlf,
+}
+
+impl ConvertTo {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConvertTo::Lf => "LF",
+            ConvertTo::Crlf => "CRLF",
+        }
+    }
+
+    pub fn terminator(self) -> &'static [u8] {
+        match self {
+            ConvertTo::Lf => b"\n",
+            ConvertTo::Crlf => b"\r\n",
+        }
+    }
+}
+
+/// What `--convert` was asked for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Conversion {
+    To(ConvertTo),
+    /// Each file's most common ending.
+    Auto,
+}
+
+impl FromStr for Conversion {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "lf" => Ok(Conversion::To(ConvertTo::Lf)),
+            "crlf" => Ok(Conversion::To(ConvertTo::Crlf)),
+            "auto" => Ok(Conversion::Auto),
+            other => Err(format!(
+                "Unknown --convert value '{}': expected lf, crlf or auto",
+                other
+            )),
+        }
+    }
+}
+
+/// The repairs `--fix` makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fix {
+    /// Remove spaces and tabs at the ends of lines.
+    TrailingWhitespace,
+    /// Terminate an unterminated last line.
+    FinalNewline,
+    /// Remove a leading UTF-8 byte-order mark.
+    StripBom,
+    /// Rewrite the indentation of each line in one style.
+    Retab(Retab),
+    /// Keep at most this many blank lines in a row.
+    SqueezeBlank(usize),
+}
+
+impl Fix {
+    /// The name given to `--fix`, without any value.
+    pub fn name(self) -> &'static str {
+        match self {
+            Fix::TrailingWhitespace => "trailing-whitespace",
+            Fix::FinalNewline => "final-newline",
+            Fix::StripBom => "strip-bom",
+            Fix::Retab(_) => "retab",
+            Fix::SqueezeBlank(_) => "squeeze-blank",
+        }
+    }
+}
+
+impl FromStr for Fix {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "trailing-whitespace" => Ok(Fix::TrailingWhitespace),
+            "final-newline" => Ok(Fix::FinalNewline),
+            "strip-bom" => Ok(Fix::StripBom),
+            _ if value.starts_with("retab=") => value["retab=".len()..].parse().map(Fix::Retab),
+            "squeeze-blank" => Ok(Fix::SqueezeBlank(1)),
+            _ if value.starts_with("squeeze-blank=") => {
+                parse_count("--fix squeeze-blank", &value["squeeze-blank=".len()..])
+                    .map(Fix::SqueezeBlank)
+            }
+            other => Err(format!(
+                "Unknown --fix '{}': expected trailing-whitespace, final-newline, strip-bom, retab=spaces:N|tabs:N or squeeze-blank[=N]",
+                other
+            )),
+        }
+    }
+}
+
+/// The indentation `--fix retab` writes, with the width of a tab stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retab {
+    /// Only spaces.
+    Spaces(usize),
+    /// As many tabs as fit, then spaces for the rest.
+    Tabs(usize),
+}
+
+impl Retab {
+    pub fn name(self) -> &'static str {
+        match self {
+            Retab::Spaces(_) => "spaces",
+            Retab::Tabs(_) => "tabs",
+        }
+    }
+
+    pub fn width(self) -> usize {
+        match self {
+            Retab::Spaces(width) | Retab::Tabs(width) => width,
+        }
+    }
+}
+
+impl FromStr for Retab {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "--fix retab expects spaces:N or tabs:N with N at least 1, got '{}'",
+                value
+            )
+        };
+        let (style, width) = value.split_once(':').ok_or_else(invalid)?;
+        let width = width
+            .parse()
+            .ok()
+            .filter(|&width| width > 0)
+            .ok_or_else(invalid)?;
+        match style {
+            "spaces" => Ok(Retab::Spaces(width)),
+            "tabs" => Ok(Retab::Tabs(width)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// The optional checks `--check` turns on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Check {
+    /// Lines indented with spaces before tabs.
+    MixedIndent,
+    /// Line endings other than the expected ones.
+    Endings(ExpectedEndings),
+    /// Files of the run that do not share one line ending, the one given
+    /// or else the one most of them have.
+    UniformTree(Option<ExpectedEndings>),
+}
+
+impl FromStr for Check {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "mixed-indent" => Ok(Check::MixedIndent),
+            "lf" => Ok(Check::Endings(ExpectedEndings::Lf)),
+            "crlf" => Ok(Check::Endings(ExpectedEndings::Crlf)),
+            "cr" => Ok(Check::Endings(ExpectedEndings::Cr)),
+            "consistent" => Ok(Check::Endings(ExpectedEndings::Consistent)),
+            "uniform-tree" => Ok(Check::UniformTree(None)),
+            "uniform-tree=lf" => Ok(Check::UniformTree(Some(ExpectedEndings::Lf))),
+            "uniform-tree=crlf" => Ok(Check::UniformTree(Some(ExpectedEndings::Crlf))),
+            "uniform-tree=cr" => Ok(Check::UniformTree(Some(ExpectedEndings::Cr))),
+            other => Err(format!(
+                "Unknown --check '{}': expected mixed-indent, lf, crlf, cr, consistent or \
+                 uniform-tree[=lf|crlf|cr]",
+                other
+            )),
+        }
+    }
+}
+
+/// The line endings `--check lf`, `crlf`, `cr` or `consistent` expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedEndings {
+    Lf,
+    Crlf,
+    Cr,
+    /// Any one style per file.
+    Consistent,
+}
+
+impl ExpectedEndings {
+    pub fn name(self) -> &'static str {
+        match self {
+            ExpectedEndings::Lf => "lf",
+            ExpectedEndings::Crlf => "crlf",
+            ExpectedEndings::Cr => "cr",
+            ExpectedEndings::Consistent => "consistent",
+        }
+    }
+}
+
+/// `--check uniform-tree`: the line ending every file of the run is to have,
+/// and how many of those that do not the error lists without `--verbose`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UniformTree {
+    /// `None` for the ending most files have.
+    pub pinned: Option<ExpectedEndings>,
+    pub listed: usize,
+}
+
+/// Which files `--list` prints the paths of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListPredicate {
+    /// Files with at least one CRLF.
+    Crlf,
+    /// Files with at least one LF.
+    Lf,
+    /// Files with at least one lone CR.
+    Cr,
+    /// Files whose endings count as mixed.
+    Mixed,
+    /// Files without a single CRLF, LF or CR.
+    NoEndings,
+    MissingFinalNewline,
+    TrailingWhitespace,
+}
+
+impl FromStr for ListPredicate {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "crlf" => Ok(ListPredicate::Crlf),
+            "lf" => Ok(ListPredicate::Lf),
+            "cr" => Ok(ListPredicate::Cr),
+            "mixed" => Ok(ListPredicate::Mixed),
+            "none" => Ok(ListPredicate::NoEndings),
+            "missing-final-newline" => Ok(ListPredicate::MissingFinalNewline),
+            "trailing-whitespace" => Ok(ListPredicate::TrailingWhitespace),
+            other => Err(format!(
+                "Unknown --list '{}': expected crlf, lf, cr, mixed, none, \
+                 missing-final-newline or trailing-whitespace",
+                other
+            )),
+        }
+    }
+}
+
+/// The files `--staged`, `--git-modified` or `--git-diff` asks git for
+/// instead of taking paths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Changed {
+    /// What is staged for commit, checked as staged.
+    Staged,
+    /// Files changed since `HEAD`, and untracked ones.
+    Modified,
+    /// Files changed since `HEAD` branched from this ref, and untracked
+    /// ones.
+    Since(String),
+}
+
+impl Changed {
+    pub fn flag(&self) -> &'static str {
+        match self {
+            Changed::Staged => "--staged",
+            Changed::Modified => "--git-modified",
+            Changed::Since(_) => "--git-diff",
+        }
+    }
+}
+
+/// The languages `--lang` can force.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    Markdown,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "markdown" | "md" => Ok(Language::Markdown),
+            other => Err(format!("Unknown --lang '{}': expected markdown", other)),
+        }
+    }
+}
+
+/// What `--group-by` totals files by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// The lowercase extension, with files that have none together.
+    Extension,
+    /// The first component of the path.
+    Dir,
+    /// The archive a file is in, with files outside one together.
+    Archive,
+}
+
+impl GroupBy {
+    /// The name `--group-by` takes.
+    pub fn name(self) -> &'static str {
+        match self {
+            GroupBy::Extension => "extension",
+            GroupBy::Dir => "dir",
+            GroupBy::Archive => "archive",
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "extension" | "ext" => Ok(GroupBy::Extension),
+            "dir" | "directory" => Ok(GroupBy::Dir),
+            "archive" => Ok(GroupBy::Archive),
+            other => Err(format!(
+                "Unknown --group-by '{}': expected extension, dir or archive",
+                other
+            )),
+        }
+    }
+}
+
+/// When `--color` colors the text report.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// When stdout is a terminal and `NO_COLOR` is not set.
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl FromStr for ColorChoice {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(ColorChoice::Auto),
+            "always" => Ok(ColorChoice::Always),
+            "never" => Ok(ColorChoice::Never),
+            other => Err(format!(
+                "Unknown --color '{}': expected auto, always or never",
+                other
+            )),
+        }
+    }
+}
+
+/// What `--sort` orders files, and the rows of the `--group-by` table, by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    Lines,
+    /// Endings other than a file's most common one; the mixed files of a
+    /// group.
+    Mixed,
+    /// CRLF endings; the CRLF files of a group.
+    Crlf,
+    /// LF endings; the LF files of a group.
+    Lf,
+    Empty,
+    Size,
+    /// The files of a group, for `--group-by` only.
+    Files,
+}
+
+/// `--sort KEY[:desc]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl FromStr for Sort {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (key, order) = value.split_once(':').unwrap_or((value, "asc"));
+        let key = match key {
+            "name" => SortKey::Name,
+            "lines" => SortKey::Lines,
+            "mixed" => SortKey::Mixed,
+            "crlf" => SortKey::Crlf,
+            "lf" => SortKey::Lf,
+            "empty" => SortKey::Empty,
+            "size" => SortKey::Size,
+            "files" => SortKey::Files,
+            other => {
+                return Err(format!(
+                    "Unknown --sort '{}': expected name, lines, mixed, crlf, lf, empty, \
+                     size or files",
+                    other
+                ))
+            }
+        };
+        let descending = match order {
+            "asc" => false,
+            "desc" => true,
+            other => {
+                return Err(format!(
+                    "Unknown --sort order '{}': expected asc or desc",
+                    other
+                ))
+            }
+        };
+        Ok(Sort { key, descending })
+    }
+}
+
+/// Which differences between two reports make `mdlt diff` fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailOn {
+    /// Any file whose endings changed, appeared with problems or went away.
+    Any,
+    /// Only files whose endings got worse.
+    Regression,
+    None,
+}
+
+impl FromStr for FailOn {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "any" => Ok(FailOn::Any),
+            "regression" => Ok(FailOn::Regression),
+            "none" => Ok(FailOn::None),
+            other => Err(format!(
+                "Unknown --fail-on '{}': expected any, regression or none",
+                other
+            )),
+        }
+    }
+}
+
+/// What `mdlt diff` needs to know, parsed from the arguments after `diff`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DiffOptions {
+    pub help: bool,
+    pub baseline: String,
+    pub current: String,
+    pub format: OutputFormat,
+    pub fail_on: FailOn,
+}
+
+/// What `mdlt compare` needs to know, parsed from the arguments after
+/// `compare`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompareOptions {
+    pub help: bool,
+    /// The two files, either of which may be `-` for stdin.
+    pub first: String,
+    pub second: String,
+    pub format: OutputFormat,
+    /// Stats that may differ without failing the comparison.
+    pub ignore: Vec<&'static str>,
+}
+
+/// The shells `mdlt completions` writes a script for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+impl FromStr for Shell {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" | "pwsh" => Ok(Shell::Powershell),
+            other => Err(format!(
+                "Unknown shell '{}': expected bash, zsh, fish or powershell",
+                other
+            )),
+        }
+    }
+}
+
+/// What `mdlt completions` needs to know, parsed from the arguments after
+/// `completions`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct CompletionsOptions {
+    pub help: bool,
+    pub shell: Option<Shell>,
+}
+
+/// Everything `run` needs to know, parsed from the command line.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub format: OutputFormat,
+    pub template: Option<Template>,
+    /// `--get`: print these fields of each file instead of a report.
+    pub get: Vec<&'static str>,
+    pub report: ReportOptions,
+    /// Where the report goes instead of stdout; `--output -` leaves it
+    /// `None`.
+    pub output: Option<String>,
+    /// `--append`: add to the end of `output` rather than replace it.
+    pub append: bool,
+    /// `--buffer-output`: write the report once every file is analyzed,
+    /// rather than each file's part as soon as it is.
+    pub buffer_output: bool,
+    pub recursive: bool,
+    /// Expand arguments containing glob metacharacters.
+    pub glob: bool,
+    pub walk: WalkOptions,
+    pub verbose: u8,
+    /// Name used for `-` (stdin) in reports and extension detection.
+    pub stdin_filename: Option<String>,
+    /// Show paths relative to this directory; `--relative` is `.`.
+    pub relative_to: Option<String>,
+    /// Show paths with forward slashes whatever the platform.
+    pub slash: bool,
+    /// File (or `-` for stdin) listing more paths to analyze.
+    pub files_from: Option<String>,
+    /// The `--files-from` list is NUL-separated rather than line-based.
+    pub null_data: bool,
+    /// Number of files analyzed concurrently.
+    pub jobs: usize,
+    /// `--intra-file-jobs`: the threads a large regular file is analyzed
+    /// on in parts, 1 to analyze every file on one.
+    pub intra_file_jobs: usize,
+    /// `--sample` or `--sample-percent`: how many of the files found to
+    /// analyze, picked at random.
+    pub sample: Option<sample::Size>,
+    /// `--seed`: what picks the files of `sample`, for a run that picks the
+    /// same ones again.
+    pub seed: Option<u64>,
+    /// `--timing`: time each file and the run, for a block on stderr and
+    /// the JSON summary.
+    pub timing: bool,
+    /// When to memory-map files instead of streaming them.
+    pub mmap: MmapMode,
+    /// Which files to read through a gzip decoder.
+    pub decompress: Decompress,
+    pub scan: ScanOptions,
+    /// Exit non-zero when a file is not valid UTF-8.
+    pub strict_encoding: bool,
+    /// Exit non-zero when a file contains NUL bytes or control characters.
+    pub fail_on_control_chars: bool,
+    /// Exit non-zero when a file contains anything but ASCII.
+    pub fail_on_non_ascii: bool,
+    /// Exit non-zero when a file has carriage returns inside its lines.
+    pub fail_on_embedded_cr: bool,
+    /// Exit non-zero when a file grew or shrank while it was read.
+    pub fail_on_unstable: bool,
+    /// Exit non-zero when a file begins with a byte-order mark.
+    pub fail_on_bom: bool,
+    /// Report each file's modification time, size and permissions.
+    pub metadata: bool,
+    /// Rewrite the files to this line ending instead of reporting on them.
+    pub convert: Option<Conversion>,
+    /// Repair these problems in place.
+    pub fixes: Vec<Fix>,
+    /// Convert files that look binary too.
+    pub force: bool,
+    /// Copy each file to its name plus this suffix before rewriting it.
+    pub backup: Option<String>,
+    /// Replace backups left by an earlier run instead of refusing.
+    pub backup_overwrite: bool,
+    /// Keep the modification time of files that are rewritten.
+    pub preserve_mtime: bool,
+    /// Say what `--convert` would change without writing anything.
+    pub dry_run: bool,
+    /// Ask on the terminal before rewriting each file.
+    pub interactive: bool,
+    /// `-q`: print one line per file instead of the report, or leave out
+    /// what `--convert` prints about each file; `-qq`: print nothing but
+    /// errors, leaving the exit status to tell.
+    pub quiet: u8,
+    /// Exit non-zero when a file has lines ending in whitespace.
+    pub fail_on_trailing_whitespace: bool,
+    /// Exit non-zero when a file's last line is not terminated.
+    pub fail_on_missing_final_newline: bool,
+    /// Exit non-zero when a file ends with more blank lines than this.
+    pub max_trailing_blank_lines: Option<usize>,
+    /// Whether `--max-trailing-blank-lines`, `--max-line-length` and
+    /// `--check mixed-indent` fail the run, as they do unless disabled.
+    pub fail_on_trailing_blank_lines: bool,
+    /// Whether `--max-blank-run` fails the run, as it does unless disabled.
+    pub fail_on_blank_runs: bool,
+    pub fail_on_long_lines: bool,
+    pub fail_on_mixed_indent: bool,
+    /// Exit non-zero when a file is indented with both tabs and spaces.
+    pub fail_on_mixed_indentation: bool,
+    /// Exit non-zero when a file uses more than one line ending.
+    pub fail_on_mixed: bool,
+    /// Report `--max-line-length` violations without failing.
+    pub no_fail: bool,
+    /// `--severity`: the severity each named check's violations get instead
+    /// of its own, the last given for a check.
+    pub severities: Vec<(String, Severity)>,
+    /// `--external-check`: the commands run on each file as checks of
+    /// their own, in the order given.
+    pub external_checks: Vec<ExternalCheck>,
+    /// `--external-check-timeout`: how long each of them may take on a
+    /// file.
+    pub external_check_timeout: Duration,
+    /// `--warnings-as-errors`: fail the run on warning-severity violations
+    /// too.
+    pub warnings_as_errors: bool,
+    /// `--max-problem-files` and `--max-problems`: pass the run as long as
+    /// at most this many files have problems, or this many problems are
+    /// found, of those that would fail it.
+    pub max_problem_files: Option<usize>,
+    pub max_problems: Option<usize>,
+    /// `--baseline`: the file of known problems, which only new ones fail
+    /// the run against, and `--update-baseline` to write it from this run.
+    pub baseline: Option<String>,
+    pub update_baseline: bool,
+    /// The line endings `--check` expects every file to have.
+    pub expected_endings: Option<ExpectedEndings>,
+    /// `--check uniform-tree`, which looks at the files together.
+    pub uniform_tree: Option<UniformTree>,
+    /// `--expect`: the line endings files must have by lowercase extension,
+    /// or `*` for any other file, overriding `--check`; `None` is `any`,
+    /// which leaves those files unchecked.
+    pub expect: Vec<(String, Option<ExpectedEndings>)>,
+    /// Check files against the `eol` and `text` attributes of their
+    /// repository's `.gitattributes`.
+    pub gitattributes: bool,
+    /// Check files against the properties their `.editorconfig` files set.
+    pub editorconfig: bool,
+    /// `None` skips binary files in multi-file runs and analyzes a lone one.
+    pub treat_binary: Option<TreatBinary>,
+    /// Skip regular files larger than this many bytes.
+    pub max_size: Option<u64>,
+    /// Skip files that look generated or minified.
+    pub skip_generated: bool,
+    /// Exit non-zero when any file was skipped.
+    pub fail_on_skip: bool,
+    /// Stop at the first file that cannot be analyzed, with no report.
+    pub fail_fast: bool,
+    /// Report only the files that fail a check, then how many did.
+    pub only_problems: bool,
+    /// Add a table of totals per extension or directory to the report.
+    pub group_by: Option<GroupBy>,
+    /// `--tree`: print the directories with their totals instead of a
+    /// report.
+    pub tree: Option<tree::Settings>,
+    pub color: ColorChoice,
+    /// `--sort`: the order of the files, and of the `--group-by` table. The
+    /// files stay in the order given or walked, and the table puts the most
+    /// files first, when `None`.
+    pub sort: Option<Sort>,
+    /// Print just the paths of files matching any of these.
+    pub list: Vec<ListPredicate>,
+    /// End each `--list` path with NUL rather than a newline.
+    pub print0: bool,
+    /// Show a progress counter on stderr for long runs (`--no-progress`
+    /// turns it off).
+    pub progress: bool,
+    /// `--watch`: after the report, analyze the files again whenever they
+    /// change, until interrupted.
+    pub watch: bool,
+    /// `--follow`: keep reading the one file given as it grows, with a
+    /// summary line now and then, until interrupted.
+    pub follow: bool,
+    /// `--serve`: answer analysis requests read from stdin as JSON lines,
+    /// one JSON line each on stdout, until stdin ends.
+    pub serve: bool,
+    /// `--follow-lines`: print the `--follow` summary after this many new
+    /// lines.
+    pub follow_lines: usize,
+    /// `--interval`: print it at least this often while new lines come in.
+    pub interval: Duration,
+    /// `--timeout`: how long fetching a URL may take.
+    pub timeout: Option<Duration>,
+    /// `--cache`: keep results in this file and reuse those of files that
+    /// have not changed since; `--no-cache` leaves it `None`.
+    pub cache: Option<String>,
+    /// `--cache-clear`: delete the cache file before the run, which is all
+    /// that happens without paths.
+    pub cache_clear: bool,
+    /// `-h`/`--help`: print `help` and do nothing else.
+    pub help: bool,
+    /// `-V`/`--version`: print the version and do nothing else.
+    pub version: bool,
+    /// Check the files git picks out instead of `paths`.
+    pub changed: Option<Changed>,
+    /// `--rev`: analyze `paths` as they are at this git revision.
+    pub rev: Option<String>,
+    /// `--config`: read this file rather than looking for `mdlt.toml`.
+    pub config: Option<String>,
+    /// `--no-config`: read no `mdlt.toml` at all.
+    pub no_config: bool,
+    /// `--print-config`: print the settings in effect and where each comes
+    /// from, and do nothing else.
+    pub print_config: bool,
+    /// `--list-checks`: print the checks `--enable` and `--disable` take,
+    /// and do nothing else.
+    pub list_checks: bool,
+    pub paths: Vec<String>,
+}
+
+impl Options {
+    /// Whether the run rewrites files rather than reporting on them.
+    pub fn rewrites(&self) -> bool {
+        self.convert.is_some() || !self.fixes.is_empty()
+    }
+
+    /// The line endings a file with this extension must have: the
+    /// `--expect` rule for the extension, else its `*` rule, else `--check`.
+    pub fn expected_endings_for(&self, extension: Option<&str>) -> Option<ExpectedEndings> {
+        let rule = |key: &str| {
+            self.expect
+                .iter()
+                .find(|(given, _)| given.eq_ignore_ascii_case(key))
+                .map(|&(_, endings)| endings)
+        };
+        extension
+            .and_then(rule)
+            .or_else(|| rule("*"))
+            .unwrap_or(self.expected_endings)
+    }
+
+    /// The indentation `--fix retab` asked for.
+    pub fn retab(&self) -> Option<Retab> {
+        self.fixes.iter().find_map(|fix| match fix {
+            Fix::Retab(retab) => Some(*retab),
+            _ => None,
+        })
+    }
+
+    /// The most blank lines in a row `--fix squeeze-blank` keeps.
+    pub fn squeeze_blank(&self) -> Option<usize> {
+        self.fixes.iter().find_map(|fix| match fix {
+            Fix::SqueezeBlank(limit) => Some(*limit),
+            _ => None,
+        })
+    }
+}
+
+impl Default for Options {
+    /// What a bare `mdlt <path>` does.
+    fn default() -> Self {
+        Options {
+            format: OutputFormat::Text,
+            template: None,
+            get: Vec::new(),
+            report: ReportOptions::default(),
+            output: None,
+            append: false,
+            buffer_output: false,
+            recursive: false,
+            glob: true,
+            walk: WalkOptions::default(),
+            verbose: 0,
+            stdin_filename: None,
+            relative_to: None,
+            slash: false,
+            files_from: None,
+            null_data: false,
+            jobs: parallel::default_jobs(),
+            intra_file_jobs: 1,
+            sample: None,
+            seed: None,
+            timing: false,
+            mmap: MmapMode::Auto,
+            decompress: Decompress::Auto,
+            scan: ScanOptions::default(),
+            strict_encoding: false,
+            treat_binary: None,
+            convert: None,
+            fixes: Vec::new(),
+            force: false,
+            backup: None,
+            backup_overwrite: false,
+            preserve_mtime: false,
+            dry_run: false,
+            interactive: false,
+            quiet: 0,
+            fail_on_control_chars: false,
+            fail_on_non_ascii: false,
+            fail_on_embedded_cr: false,
+            fail_on_unstable: false,
+            fail_on_bom: false,
+            metadata: false,
+            fail_on_trailing_whitespace: false,
+            fail_on_missing_final_newline: false,
+            max_trailing_blank_lines: None,
+            fail_on_trailing_blank_lines: true,
+            fail_on_blank_runs: true,
+            fail_on_long_lines: true,
+            fail_on_mixed_indent: true,
+            no_fail: false,
+            severities: Vec::new(),
+            external_checks: Vec::new(),
+            external_check_timeout: external::DEFAULT_TIMEOUT,
+            warnings_as_errors: false,
+            max_problem_files: None,
+            max_problems: None,
+            baseline: None,
+            update_baseline: false,
+            expected_endings: None,
+            uniform_tree: None,
+            expect: Vec::new(),
+            gitattributes: false,
+            editorconfig: false,
+            fail_on_mixed_indentation: false,
+            fail_on_mixed: false,
+            max_size: None,
+            skip_generated: false,
+            fail_on_skip: false,
+            fail_fast: false,
+            only_problems: false,
+            group_by: None,
+            tree: None,
+            color: ColorChoice::Auto,
+            sort: None,
+            list: Vec::new(),
+            print0: false,
+            progress: true,
+            watch: false,
+            follow: false,
+            serve: false,
+            follow_lines: 1000,
+            interval: Duration::from_secs(5),
+            timeout: None,
+            cache: None,
+            cache_clear: false,
+            help: false,
+            version: false,
+            changed: None,
+            rev: None,
+            config: None,
+            no_config: false,
+            print_config: false,
+            list_checks: false,
+            paths: Vec::new(),
+        }
+    }
+}
+
+/// How a flag takes its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Takes {
+    Nothing,
+    /// A value after `=` or as the next argument, shown in the usage line
+    /// as this placeholder or these choices.
+    Value(&'static str),
+    /// A value only after `=`, or none, as in `--cache[=PATH]`.
+    Optional(&'static str),
+}
+
+/// Which part of `parse_args_with_config` reads a flag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Group {
+    /// `--help`, `--version` and `--`.
+    Command,
+    Report,
+    Mode,
+    File,
+    Scan,
+    Check,
+}
+
+/// A flag of the main command.
+#[derive(Debug)]
+pub struct Flag {
+    pub name: &'static str,
+    pub takes: Takes,
+    pub group: Group,
+}
+
+const fn switch(name: &'static str, group: Group) -> Flag {
+    Flag {
+        name,
+        takes: Takes::Nothing,
+        group,
+    }
+}
+
+const fn valued(name: &'static str, shown: &'static str, group: Group) -> Flag {
+    Flag {
+        name,
+        takes: Takes::Value(shown),
+        group,
+    }
+}
+
+const fn optional(name: &'static str, shown: &'static str, group: Group) -> Flag {
+    Flag {
+        name,
+        takes: Takes::Optional(shown),
+        group,
+    }
+}
+
+/// Every flag of the main command, which the parser, the usage line and the
+/// completion scripts all go by. Each entry is one `[...]` of the usage
+/// line, with the flags that are alternatives to each other, and whether it
+/// can be given more than once.
+pub const FLAGS: &[(&[Flag], bool)] = &[
+    (
+        &[
+            switch("-h", Group::Command),
+            switch("--help", Group::Command),
+        ],
+        false,
+    ),
+    (
+        &[
+            switch("-V", Group::Command),
+            switch("--version", Group::Command),
+        ],
+        false,
+    ),
+    (
+        &[
+            valued("--config", "<path>", Group::Mode),
+            switch("--no-config", Group::Mode),
+        ],
+        false,
+    ),
+    (&[switch("--print-config", Group::Mode)], false),
+    (
+        &[
+            switch("--staged", Group::File),
+            switch("--git-modified", Group::File),
+            valued("--git-diff", "<ref>", Group::File),
+        ],
+        false,
+    ),
+    (&[valued("--rev", "<rev>", Group::File)], false),
+    (
+        &[
+            switch("-r", Group::File),
+            switch("--recursive", Group::File),
+        ],
+        false,
+    ),
+    (&[switch("--hidden", Group::File)], false),
+    (&[switch("--no-ignore", Group::File)], false),
+    (&[valued("--min-depth", "<n>", Group::File)], false),
+    (&[valued("--max-depth", "<n>", Group::File)], false),
+    (&[switch("--no-default-prune", Group::File)], false),
+    (
+        &[
+            switch("-L", Group::File),
+            switch("--follow-symlinks", Group::File),
+        ],
+        false,
+    ),
+    (&[switch("--no-glob", Group::File)], false),
+    (&[valued("--include", "<glob>", Group::File)], true),
+    (&[valued("--exclude", "<glob>", Group::File)], true),
+    (&[switch("--archive", Group::File)], false),
+    (
+        &[
+            switch("-v", Group::Report),
+            switch("--verbose", Group::Report),
+            switch("-vv", Group::Report),
+        ],
+        false,
+    ),
+    (
+        &[
+            valued("--lines", "<a>..<b>", Group::Scan),
+            valued("--bytes", "<a>..<b>", Group::Scan),
+        ],
+        false,
+    ),
+    (
+        &[
+            valued("--head-lines", "N", Group::Scan),
+            valued("--head-bytes", "N", Group::Scan),
+        ],
+        false,
+    ),
+    (&[valued("--stdin-filename", "<name>", Group::File)], false),
+    (
+        &[
+            switch("--relative", Group::File),
+            valued("--relative-to", "<dir>", Group::File),
+        ],
+        false,
+    ),
+    (&[switch("--slash", Group::File)], false),
+    (&[valued("--files-from", "<list>", Group::File)], false),
+    (
+        &[
+            switch("-0", Group::File),
+            switch("--null-data", Group::File),
+        ],
+        false,
+    ),
+    (&[valued("--jobs", "<n>", Group::File)], false),
+    (&[valued("--intra-file-jobs", "<n>", Group::File)], false),
+    (&[switch("--timing", Group::Report)], false),
+    (&[switch("--no-progress", Group::Report)], false),
+    (&[switch("--watch", Group::Mode)], false),
+    (&[switch("--follow", Group::Mode)], false),
+    (&[valued("--follow-lines", "<n>", Group::Mode)], false),
+    (&[valued("--interval", "<seconds>", Group::Mode)], false),
+    (&[switch("--serve", Group::Mode)], false),
+    (&[valued("--timeout", "<seconds>", Group::Mode)], false),
+    (
+        &[
+            optional("--cache", "PATH", Group::Mode),
+            switch("--no-cache", Group::Mode),
+        ],
+        false,
+    ),
+    (&[switch("--cache-clear", Group::Mode)], false),
+    (
+        &[
+            switch("--mmap", Group::File),
+            switch("--no-mmap", Group::File),
+        ],
+        false,
+    ),
+    (
+        &[valued("--decompress", "auto|gzip|none", Group::File)],
+        false,
+    ),
+    (&[switch("--unicode-linebreaks", Group::Scan)], false),
+    (
+        &[valued("--encoding", "utf-8|utf-16le|utf-16be", Group::Scan)],
+        false,
+    ),
+    (
+        &[optional("--detect-encoding", "apply", Group::Scan)],
+        false,
+    ),
+    (&[switch("--check-encoding", Group::Scan)], false),
+    (&[switch("--strict-encoding", Group::Check)], false),
+    (
+        &[valued("--treat-binary", "skip|analyze|fail", Group::File)],
+        false,
+    ),
+    (&[valued("--convert", "lf|crlf|auto", Group::Mode)], false),
+    (
+        &[valued(
+            "--fix",
+            "trailing-whitespace,final-newline,strip-bom,retab=spaces:N|tabs:N,squeeze-blank[=N]",
+            Group::Mode,
+        )],
+        false,
+    ),
+    (&[switch("--force", Group::Mode)], false),
+    (
+        &[
+            optional("--backup", "SUFFIX", Group::Mode),
+            switch("--no-backup", Group::Mode),
+        ],
+        false,
+    ),
+    (&[switch("--backup-overwrite", Group::Mode)], false),
+    (&[switch("--preserve-mtime", Group::Mode)], false),
+    (
+        &[
+            switch("--dry-run", Group::Mode),
+            switch("--interactive", Group::Mode),
+        ],
+        false,
+    ),
+    (
+        &[
+            switch("-q", Group::Report),
+            switch("--quiet", Group::Report),
+            switch("-qq", Group::Report),
+        ],
+        false,
+    ),
+    (&[switch("--allow-form-feed", Group::Scan)], false),
+    (&[switch("--blank-as-empty", Group::Scan)], false),
+    (&[valued("--lang", "markdown", Group::Scan)], false),
+    (&[switch("--skip-fenced-code", Group::Scan)], false),
+    (
+        &[valued("--type-name", "<name>=<type>,...", Group::Scan)],
+        true,
+    ),
+    (
+        &[valued(
+            "--type-interpreter",
+            "<name>=<type>,...",
+            Group::Scan,
+        )],
+        true,
+    ),
+    (&[switch("--hash", Group::Scan)], false),
+    (&[switch("--metadata", Group::Scan)], false),
+    (&[switch("--duplicates", Group::Scan)], false),
+    (&[switch("--transitions", Group::Scan)], false),
+    (&[switch("--locate", Group::Scan)], false),
+    (&[switch("--show-empty", Group::Scan)], false),
+    (&[valued("--count-pattern", "<regex>", Group::Scan)], true),
+    (&[switch("--bytes-regex", Group::Scan)], false),
+    (&[valued("--max-minority-endings", "N", Group::Scan)], false),
+    (
+        &[
+            valued("--max-minority-percent", "<percent>", Group::Scan),
+            valued("--mixed-threshold", "<percent>", Group::Scan),
+        ],
+        false,
+    ),
+    (&[switch("--fail-on-control-chars", Group::Check)], false),
+    (&[switch("--fail-on-non-ascii", Group::Check)], false),
+    (&[switch("--fail-on-embedded-cr", Group::Check)], false),
+    (&[switch("--fail-on-unstable", Group::Check)], false),
+    (&[switch("--preview", Group::Report)], false),
+    (&[switch("--peek", Group::Scan)], false),
+    (&[valued("--peek-width", "N", Group::Scan)], false),
+    (&[switch("--histogram", Group::Scan)], false),
+    (
+        &[valued("--histogram-buckets", "40,80,120", Group::Scan)],
+        false,
+    ),
+    (
+        &[switch("--fail-on-trailing-whitespace", Group::Check)],
+        false,
+    ),
+    (
+        &[switch("--fail-on-missing-final-newline", Group::Check)],
+        false,
+    ),
+    (
+        &[valued("--max-trailing-blank-lines", "N", Group::Check)],
+        false,
+    ),
+    (&[valued("--max-blank-run", "N", Group::Scan)], false),
+    (&[valued("--max-line-length", "N", Group::Scan)], false),
+    (&[valued("--max-listed-lines", "K", Group::Scan)], false),
+    (&[valued("--top-lines", "N", Group::Scan)], false),
+    (&[valued("--tab-width", "N", Group::Scan)], false),
+    (&[switch("--no-fail", Group::Check)], false),
+    (
+        &[switch("--fail-on-mixed-indentation", Group::Check)],
+        false,
+    ),
+    (&[switch("--fail-on-mixed", Group::Check)], false),
+    (
+        &[valued(
+            "--check",
+            "mixed-indent,lf|crlf|cr|consistent,uniform-tree",
+            Group::Check,
+        )],
+        false,
+    ),
+    (
+        &[valued(
+            "--expect",
+            "<ext>=lf|crlf|cr|consistent|any,...",
+            Group::Check,
+        )],
+        true,
+    ),
+    (&[switch("--gitattributes", Group::Check)], false),
+    (&[switch("--editorconfig", Group::Check)], false),
+    (&[switch("--strict-mixed-indent", Group::Check)], false),
+    (&[valued("--max-size", "<size>", Group::File)], false),
+    (
+        &[
+            valued("--sample", "<n>", Group::File),
+            valued("--sample-percent", "<p>", Group::File),
+        ],
+        false,
+    ),
+    (&[valued("--seed", "<n>", Group::File)], false),
+    (&[switch("--skip-generated", Group::File)], false),
+    (
+        &[valued("--generated-line-length", "N", Group::File)],
+        false,
+    ),
+    (
+        &[valued("--generated-line-share", "<percent>", Group::File)],
+        false,
+    ),
+    (&[switch("--fail-on-skip", Group::File)], false),
+    (&[switch("--fail-fast", Group::File)], false),
+    (
+        &[valued("--checks", "all|none|default", Group::Check)],
+        false,
+    ),
+    (&[valued("--enable", "<check>,...", Group::Check)], true),
+    (&[valued("--disable", "<check>,...", Group::Check)], true),
+    (
+        &[valued(
+            "--severity",
+            "<check>=error|warning|info,...",
+            Group::Check,
+        )],
+        true,
+    ),
+    (
+        &[valued("--external-check", "<name>:<command>", Group::Check)],
+        true,
+    ),
+    (
+        &[valued(
+            "--external-check-timeout",
+            "<seconds>",
+            Group::Check,
+        )],
+        false,
+    ),
+    (&[switch("--warnings-as-errors", Group::Check)], false),
+    (&[valued("--max-problem-files", "N", Group::Check)], false),
+    (&[valued("--max-problems", "N", Group::Check)], false),
+    (&[valued("--baseline", "<path>", Group::Check)], false),
+    (&[switch("--update-baseline", Group::Check)], false),
+    (&[switch("--show-suppressed", Group::Report)], false),
+    (&[switch("--list-checks", Group::Check)], false),
+    (&[switch("--only-problems", Group::Report)], false),
+    (&[switch("--summary-only", Group::Report)], false),
+    (
+        &[valued("--group-by", "extension|dir|archive", Group::Report)],
+        false,
+    ),
+    (&[switch("--tree", Group::Report)], false),
+    (&[valued("--tree-depth", "N", Group::Report)], false),
+    (&[switch("--ascii", Group::Report)], false),
+    (&[switch("--collapse-clean", Group::Report)], false),
+    (
+        &[valued(
+            "--sort",
+            "name|lines|mixed|crlf|lf|empty|size|files[:desc]",
+            Group::Report,
+        )],
+        false,
+    ),
+    (
+        &[valued(
+            "--list",
+            "crlf|lf|cr|mixed|none|missing-final-newline|trailing-whitespace",
+            Group::Report,
+        )],
+        true,
+    ),
+    (&[switch("--print0", Group::Report)], false),
+    (
+        &[valued(
+            "--format",
+            "text|markdown|html|json|csv|tsv|table|sarif|checkstyle|github",
+            Group::Report,
+        )],
+        false,
+    ),
+    (&[valued("--width", "<columns>", Group::Report)], false),
+    (&[valued("--columns", "<name>,...", Group::Report)], false),
+    (&[switch("--no-header", Group::Report)], false),
+    (
+        &[valued("--color", "auto|always|never", Group::Report)],
+        false,
+    ),
+    (&[switch("--no-group-digits", Group::Report)], false),
+    (&[valued("--stable-output", "v1", Group::Report)], false),
+    (&[valued("--template", "<template>", Group::Report)], false),
+    (&[valued("--get", "<field>", Group::Report)], true),
+    (
+        &[valued("--output", "<report_path>|-", Group::Report)],
+        false,
+    ),
+    (&[switch("--append", Group::Report)], false),
+    (&[switch("--buffer-output", Group::Report)], false),
+    (&[switch("--", Group::Command)], false),
+];
+
+/// The flags of `FLAGS` one by one.
+pub fn flags() -> impl Iterator<Item = &'static Flag> {
+    FLAGS.iter().flat_map(|(flags, _)| flags.iter())
+}
+
+pub fn usage(program: &str) -> String {
+    let mut usage = format!("Usage: {}", program);
+    for (flags, repeats) in FLAGS {
+        let flags: Vec<String> = flags
+            .iter()
+            .map(|flag| match flag.takes {
+                Takes::Nothing => flag.name.to_string(),
+                Takes::Value(shown) => format!("{} {}", flag.name, shown),
+                Takes::Optional(shown) => format!("{}[={}]", flag.name, shown),
+            })
+            .collect();
+        usage.push_str(&format!(" [{}]", flags.join("|")));
+        if *repeats {
+            usage.push_str("...");
+        }
+    }
+    usage.push_str(" <path>...");
+    usage
+}
+
+/// The options `--help` describes, by what they are for.
+const OPTION_GROUPS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Choosing files",
+        &[
+            ("-r, --recursive", "analyze the files under directories"),
+            (
+                "--hidden, --no-ignore",
+                "include dot files, and files .gitignore ignores",
+            ),
+            ("--min-depth, --max-depth <n>", "limit how deep -r goes"),
+            (
+                "--no-default-prune",
+                "walk into node_modules, target and the like",
+            ),
+            (
+                "-L, --follow-symlinks",
+                "follow symbolic links found while walking",
+            ),
+            (
+                "--include, --exclude <glob>",
+                "analyze only, or skip, matching files",
+            ),
+            (
+                "--archive",
+                "analyze the files inside .zip, .tar and .tar.gz files",
+            ),
+            ("--no-glob", "take arguments with * or ? as file names"),
+            (
+                "--files-from <list>, -0",
+                "read more paths from a file, NUL-separated with -0",
+            ),
+            (
+                "--staged, --git-modified",
+                "check what git has staged, or changed",
+            ),
+            (
+                "--git-diff <ref>, --rev <rev>",
+                "check what changed since <ref>, or files at <rev>",
+            ),
+            (
+                "--max-size <size>",
+                "skip files larger than <size>, such as 10M",
+            ),
+            (
+                "--sample <n>, --sample-percent <p>",
+                "analyze <n> of the files, or <p>% of them, picked at random",
+            ),
+            ("--seed <n>", "pick the same files for --sample again"),
+            (
+                "--skip-generated",
+                "skip files that look generated or minified",
+            ),
+            (
+                "--generated-line-length <n>",
+                "the line length that looks minified",
+            ),
+            (
+                "--generated-line-share <percent>",
+                "the share of the bytes one line may hold",
+            ),
+            (
+                "--timeout <seconds>",
+                "give up on a URL that takes longer to fetch",
+            ),
+            (
+                "--treat-binary skip|analyze|fail",
+                "what to do with binary files",
+            ),
+            ("--stdin-filename <name>", "the name - is reported by"),
+            (
+                "--config <path>, --no-config",
+                "read flags from another mdlt.toml, or none",
+            ),
+        ],
+    ),
+    (
+        "Reading files",
+        &[
+            ("--jobs <n>", "analyze <n> files at a time"),
+            (
+                "--intra-file-jobs <n>",
+                "analyze a file of 64 MiB or more in up to <n> parts at a time",
+            ),
+            (
+                "--cache[=PATH], --no-cache",
+                "reuse the results of files unchanged since the last run",
+            ),
+            ("--cache-clear", "delete the --cache file"),
+            (
+                "--lines <a>..<b>, --bytes <a>..<b>",
+                "analyze only lines, or bytes, a up to but not b",
+            ),
+            (
+                "--head-lines N, --head-bytes N",
+                "analyze only the first N lines, or bytes, of each file",
+            ),
+            (
+                "--mmap, --no-mmap",
+                "always, or never, map large files into memory",
+            ),
+            (
+                "--decompress auto|gzip|none",
+                "read .gz files, or every file, or none, through gunzip",
+            ),
+            (
+                "--encoding utf-8|utf-16le|utf-16be",
+                "read files without a BOM as this",
+            ),
+            (
+                "--detect-encoding[=apply]",
+                "guess what files without a BOM are; apply reads UTF-16",
+            ),
+            (
+                "--check-encoding, --strict-encoding",
+                "report, or fail on, invalid UTF-8",
+            ),
+            (
+                "--unicode-linebreaks",
+                "count NEL, LS, PS, VT and FF as well",
+            ),
+            (
+                "--allow-form-feed",
+                "do not count form feeds as control characters",
+            ),
+            ("--blank-as-empty", "count whitespace-only lines as empty"),
+            ("--tab-width <n>", "the columns a tab takes in line lengths"),
+            (
+                "--lang markdown, --skip-fenced-code",
+                "read files as Markdown",
+            ),
+            (
+                "--type-name <name>=<type>,...",
+                "take files so named for a type",
+            ),
+            (
+                "--type-interpreter <name>=<type>,...",
+                "take scripts this runs for a type",
+            ),
+        ],
+    ),
+    (
+        "Checks",
+        &[
+            (
+                "--check lf|crlf|cr|consistent",
+                "require these line endings",
+            ),
+            (
+                "--check mixed-indent, --strict-mixed-indent",
+                "require consistent indentation",
+            ),
+            (
+                "--check uniform-tree[=lf|crlf|cr]",
+                "require one line ending across all files",
+            ),
+            (
+                "--expect <ext>=<ending>,...",
+                "require endings by extension",
+            ),
+            (
+                "--gitattributes, --editorconfig",
+                "require what those files say",
+            ),
+            ("--fail-on-mixed", "fail on files with mixed line endings"),
+            (
+                "--max-minority-endings <n>",
+                "endings of another style a file may have",
+            ),
+            (
+                "--max-minority-percent <percent>",
+                "the same as a share of its endings",
+            ),
+            (
+                "--fail-on-trailing-whitespace",
+                "fail on lines ending in spaces or tabs",
+            ),
+            (
+                "--fail-on-missing-final-newline",
+                "fail on files without a final newline",
+            ),
+            (
+                "--max-trailing-blank-lines <n>",
+                "fail on more blank lines at the end",
+            ),
+            ("--max-blank-run <n>", "fail on more empty lines in a row"),
+            ("--max-line-length <n>", "fail on longer lines"),
+            (
+                "--fail-on-mixed-indentation",
+                "fail on lines indented with both",
+            ),
+            (
+                "--fail-on-control-chars, --fail-on-non-ascii",
+                "fail on such characters",
+            ),
+            (
+                "--fail-on-embedded-cr",
+                "fail on carriage returns inside lines",
+            ),
+            ("--fail-on-unstable", "fail on files that change while read"),
+            ("--fail-on-skip", "fail when a file is skipped"),
+            ("--fail-fast", "stop at the first file that cannot be read"),
+            ("--no-fail", "report what failed but exit with 0"),
+            (
+                "--checks all|none|default",
+                "start from all checks, none or the default ones",
+            ),
+            (
+                "--enable, --disable <names>",
+                "turn checks on or off by name",
+            ),
+            (
+                "--severity <check>=error|warning|info,...",
+                "set the severity of what a check finds",
+            ),
+            (
+                "--external-check <name>:<command>",
+                "fail files <command> exits non-zero on, given the path or {stdin}",
+            ),
+            (
+                "--external-check-timeout <seconds>",
+                "how long an external check may take on a file (default 30)",
+            ),
+            (
+                "--warnings-as-errors",
+                "fail on warning-severity violations too",
+            ),
+            (
+                "--max-problem-files <n>",
+                "pass while at most n files have problems",
+            ),
+            (
+                "--max-problems <n>",
+                "pass while at most n problems are found",
+            ),
+            (
+                "--baseline <path>",
+                "fail only on problems the baseline file does not list",
+            ),
+            (
+                "--update-baseline",
+                "write the --baseline file from what this run finds",
+            ),
+            (
+                "--show-suppressed",
+                "list the violations mdlt:ignore-line and mdlt:ignore-file silence",
+            ),
+            (
+                "--list-checks",
+                "list the checks, which are on by default and their severity",
+            ),
+        ],
+    ),
+    (
+        "Rewriting files",
+        &[
+            ("--convert lf|crlf|auto", "rewrite the line endings"),
+            (
+                "--fix <fix>,...",
+                "fix trailing whitespace, final newlines and the like",
+            ),
+            ("--dry-run", "say what would change, and change nothing"),
+            ("--interactive", "show each change and ask before making it"),
+            (
+                "--backup[=SUFFIX], --no-backup",
+                "keep a copy of each file changed",
+            ),
+            ("--backup-overwrite", "replace an older backup"),
+            ("--preserve-mtime", "keep the modification time"),
+            ("--force", "rewrite files that look binary too"),
+        ],
+    ),
+    (
+        "Reporting",
+        &[
+            (
+                "--format text|markdown|html|json|csv|tsv|table|sarif|checkstyle|github",
+                "the report format",
+            ),
+            (
+                "--template <template>",
+                "one line per file, such as '{name}: {crlf}'",
+            ),
+            ("--get <field>", "print only this field of each file"),
+            (
+                "--list <predicate>, --print0",
+                "print only the paths of matching files",
+            ),
+            ("--only-problems", "report only the files that fail a check"),
+            ("--summary-only, --group-by extension|dir", "print totals"),
+            (
+                "--tree, --tree-depth <n>",
+                "print the directories with their totals",
+            ),
+            (
+                "--ascii, --collapse-clean",
+                "draw the tree in ASCII, fold clean directories",
+            ),
+            ("--sort <key>[:desc]", "the order of the files"),
+            (
+                "--relative, --relative-to <dir>, --slash",
+                "how paths are shown",
+            ),
+            ("--width <columns>", "the width of --format table"),
+            (
+                "--columns <name>,..., --no-header",
+                "the columns of --format csv, tsv or table",
+            ),
+            ("--color auto|always|never", "color the text report"),
+            (
+                "--no-group-digits",
+                "write 10432 in the text report, not 10,432",
+            ),
+            (
+                "--stable-output v1",
+                "keep the text report worded as in version 1",
+            ),
+            (
+                "--hash, --metadata, --histogram",
+                "add checksums, file times, line lengths",
+            ),
+            (
+                "--duplicates, --transitions, --locate",
+                "add repeated lines, ending runs",
+            ),
+            ("--show-empty", "list where the empty lines are"),
+            ("--top-lines <n>", "list the n longest lines"),
+            (
+                "--preview",
+                "add the longest line to machine-readable formats",
+            ),
+            (
+                "--peek, --peek-width <n>",
+                "show the first and last lines with text",
+            ),
+            (
+                "--count-pattern <regex>, --bytes-regex",
+                "count the lines matching <regex>, read as text or bytes",
+            ),
+            ("--max-listed-lines <n>", "how many line numbers are listed"),
+            (
+                "-q, -qq",
+                "one line per file, or nothing but the exit status",
+            ),
+            ("-v, -vv", "more detail, and a row for every line"),
+            ("--no-progress", "hide the progress counter"),
+            ("--timing", "time the reading and analysis of each file"),
+            ("--watch", "report again on the files that change"),
+            (
+                "--follow, --follow-lines <n>, --interval <s>",
+                "keep reading a growing file",
+            ),
+            (
+                "--serve",
+                "answer JSON requests on stdin, one JSON line each",
+            ),
+            ("--output <path>|-, --append", "write the report to a file"),
+            (
+                "--buffer-output",
+                "write the report only once every file is analyzed",
+            ),
+            ("--print-config", "show the settings mdlt.toml gives"),
+            (
+                "--",
+                "take what follows as paths, even if they start with -",
+            ),
+        ],
+    ),
+];
+
+/// The usage lines followed by the options by category and what each exit
+/// status means.
+pub fn help(program: &str) -> String {
+    let mut groups = String::new();
+    for (title, options) in OPTION_GROUPS {
+        groups.push_str(&format!("\n{}:\n", title));
+        for (flags, description) in *options {
+            let line = format!("  {:<44} {}", flags, description);
+            groups.push_str(line.trim_end());
+            groups.push('\n');
+        }
+    }
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n\
+         Exit status:\n  \
+         0  every file was analyzed or rewritten and passed every check\n  \
+         1  a check or policy failed: --check, the --fail-on-* flags, --max-line-length,\n     \
+         --max-trailing-blank-lines, files that --dry-run would change, or what\n     \
+         --fail-on picks from the changes mdlt diff found, or mdlt compare found the\n     \
+         files differ\n  \
+         2  the command line is invalid, a pattern matches no files, or mdlt diff was\n     \
+         not given two JSON reports of this version\n  \
+         3  files or the report could not be read, analyzed or written, even if\n     \
+         the other files were\n  \
+         4  --convert or --fix left binary files alone and nothing else failed\n",
+        usage(program),
+        diff_usage(program).replacen("Usage:", "      ", 1),
+        compare_usage(program).replacen("Usage:", "      ", 1),
+        completions_usage(program).replacen("Usage:", "      ", 1),
+        groups
+    )
+}
+
+/// The flag of `FLAGS` that `arg` is, with its value from `arg` itself, as
+/// in `--flag=VALUE`, or from `rest`; `None` when `arg` is no flag.
+fn find_flag<'a>(
+    arg: &'a str,
+    rest: &mut slice::Iter<'a, String>,
+) -> Result<Option<(&'static Flag, Option<&'a str>)>, String> {
+    let (name, inline) = match arg.split_once('=') {
+        Some((name, value)) if name.starts_with("--") => (name, Some(value)),
+        _ => (arg, None),
+    };
+    let Some(flag) = flags().find(|flag| flag.name == name) else {
+        return Ok(None);
+    };
+    let value = match (flag.takes, inline) {
+        (Takes::Nothing, Some(_)) => return Ok(None),
+        (_, Some(value)) => Some(value),
+        (Takes::Value(_), None) => Some(
+            rest.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("Missing value for {}", flag.name))?,
+        ),
+        (_, None) => None,
+    };
+    Ok(Some((flag, value)))
+}
+
+/// Matches `--flag VALUE` and `--flag=VALUE`, returning `None` when `arg` is
+/// not `flag` at all.
+fn flag_value<'a>(
+    arg: &'a str,
+    flag: &str,
+    rest: &mut impl Iterator<Item = &'a String>,
+) -> Option<Result<&'a str, String>> {
+    if arg == flag {
+        Some(
+            rest.next()
+                .map(String::as_str)
+                .ok_or_else(|| format!("Missing value for {}", flag)),
+        )
+    } else {
+        arg.strip_prefix(flag)
+            .and_then(|tail| tail.strip_prefix('='))
+            .map(Ok)
+    }
+}
+
+/// The window of `--lines` or `--bytes` once `given` is, which may replace
+/// an earlier one of the same flag but not one of the other.
+fn window(earlier: Option<Window>, given: Window) -> Result<Window, String> {
+    match (earlier, given) {
+        (Some(Window::Lines(_)), Window::Bytes(_)) | (Some(Window::Bytes(_)), Window::Lines(_)) => {
+            Err("--lines and --bytes cannot be combined; pass one of them".to_string())
+        }
+        _ => Ok(given),
+    }
+}
+
+/// The head of `--head-lines` or `--head-bytes` once `given` is, which may
+/// replace an earlier one of the same flag but not one of the other.
+fn head(earlier: Option<Head>, given: Head) -> Result<Head, String> {
+    match (earlier, given) {
+        (Some(Head::Lines(_)), Head::Bytes(_)) | (Some(Head::Bytes(_)), Head::Lines(_)) => {
+            Err("--head-lines and --head-bytes cannot be combined; pass one of them".to_string())
+        }
+        _ => Ok(given),
+    }
+}
+
+/// What `--backup` appends to a file's name when no suffix is given.
+const DEFAULT_BACKUP_SUFFIX: &str = ".bak";
+
+/// How many long lines, or lines with a minority ending, are listed by
+/// number unless `--max-listed-lines` says otherwise.
+const DEFAULT_LISTED_LINES: usize = 10;
+
+/// Parses an `--expect` list such as `rs=lf,.bat=crlf,*=lf`, with keys
+/// lowercased and without their dot.
+fn parse_expect(value: &str) -> Result<Vec<(String, Option<ExpectedEndings>)>, String> {
+    let mut rules: Vec<(String, Option<ExpectedEndings>)> = Vec::new();
+    for entry in value.split(',') {
+        let (key, endings) = match entry.split_once('=') {
+            Some((key, endings)) if !key.trim_start_matches('.').is_empty() => (key, endings),
+            _ => return Err(format!("--expect: expected ext=ending, got '{}'", entry)),
+        };
+        let key = key.trim_start_matches('.').to_ascii_lowercase();
+        let endings = match endings {
+            "any" => None,
+            _ => match endings.parse() {
+                Ok(Check::Endings(endings)) => Some(endings),
+                _ => {
+                    return Err(format!(
+                        "--expect: unknown ending '{}' for {}: expected lf, crlf, cr, \
+                         consistent or any",
+                        endings, key
+                    ))
+                }
+            },
+        };
+        if rules.iter().any(|(given, _)| *given == key) {
+            return Err(format!("--expect gives {} more than once", key));
+        }
+        rules.push((key, endings));
+    }
+    Ok(rules)
+}
+
+/// Parses the `name=type,...` of `--type-name` or `--type-interpreter`,
+/// with the types in lowercase.
+fn parse_types(flag: &str, value: &str) -> Result<Vec<(String, String)>, String> {
+    value
+        .split(',')
+        .map(|entry| match entry.split_once('=') {
+            Some((name, kind)) if !name.is_empty() && !kind.is_empty() => {
+                Ok((name.to_string(), kind.to_ascii_lowercase()))
+            }
+            _ => Err(format!("{}: expected name=type, got '{}'", flag, entry)),
+        })
+        .collect()
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a non-negative integer, got '{}'", flag, value))
+}
+
+/// Parses a byte count with an optional binary suffix: `512`, `10K`, `10M`,
+/// `1G` or `2T`, case-insensitive, optionally followed by `B` or `iB`.
+fn parse_size(flag: &str, value: &str) -> Result<u64, String> {
+    let invalid = || {
+        format!(
+            "{} expects a size like 512, 10K, 10M or 1G, got '{}'",
+            flag, value
+        )
+    };
+    let digits = value.trim_end_matches(|c: char| !c.is_ascii_digit());
+    let suffix = value[digits.len()..].to_ascii_uppercase();
+    let unit = suffix
+        .strip_suffix("IB")
+        .or_else(|| suffix.strip_suffix('B'))
+        .unwrap_or(&suffix);
+    let shift = match unit {
+        "" => 0,
+        "K" => 10,
+        "M" => 20,
+        "G" => 30,
+        "T" => 40,
+        _ => return Err(invalid()),
+    };
+    if unit.is_empty() && suffix.len() > 1 {
+        // "iB" or "BB" without a unit.
+        return Err(invalid());
+    }
+    let count: u64 = digits.parse().map_err(|_| invalid())?;
+    count.checked_mul(1 << shift).ok_or_else(invalid)
+}
+
+pub fn parse_args(args: &[String]) -> Result<Options, String> {
+    parse_args_with_config(args, &[])
+}
+
+/// A `--format` for the subcommands, which only write text or JSON.
+fn text_or_json(command: &str, value: &str) -> Result<OutputFormat, String> {
+    match value.parse()? {
+        format @ (OutputFormat::Text | OutputFormat::Json) => Ok(format),
+        other => Err(format!(
+            "mdlt {} writes text or json, not {}",
+            command,
+            other.name()
+        )),
+    }
+}
+
+pub fn diff_usage(program: &str) -> String {
+    format!(
+        "Usage: {} diff [-h|--help] [--format text|json] [--fail-on any|regression|none] \
+         <baseline.json> <current.json>",
+        program
+    )
+}
+
+/// Parses `mdlt diff`, whose arguments start at `args[2]`.
+pub fn parse_diff_args(args: &[String]) -> Result<DiffOptions, String> {
+    let mut format = OutputFormat::Text;
+    let mut fail_on = FailOn::Regression;
+    let mut reports = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "-h" || arg == "--help" {
+            return Ok(DiffOptions {
+                help: true,
+                baseline: String::new(),
+                current: String::new(),
+                format,
+                fail_on,
+            });
+        } else if let Some(value) = flag_value(arg, "--format", &mut rest) {
+            format = text_or_json("diff", value?)?;
+        } else if let Some(value) = flag_value(arg, "--fail-on", &mut rest) {
+            fail_on = value?.parse()?;
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(format!("Unknown option {}\n{}", arg, diff_usage(&args[0])));
+        } else {
+            reports.push(arg.clone());
+        }
+    }
+    let [baseline, current] = <[String; 2]>::try_from(reports).map_err(|_| diff_usage(&args[0]))?;
+    Ok(DiffOptions {
+        help: false,
+        baseline,
+        current,
+        format,
+        fail_on,
+    })
+}
+
+pub fn compare_usage(program: &str) -> String {
+    format!(
+        "Usage: {} compare [-h|--help] [--format text|json] [--ignore <stat|group>,...]... \
+         <path|-> <path|->",
+        program
+    )
+}
+
+/// Parses `mdlt compare`, whose arguments start at `args[2]`; `--ignore`
+/// takes stat names and groups such as `line-endings`, which are expanded.
+pub fn parse_compare_args(args: &[String]) -> Result<CompareOptions, String> {
+    let mut options = CompareOptions {
+        help: false,
+        first: String::new(),
+        second: String::new(),
+        format: OutputFormat::Text,
+        ignore: Vec::new(),
+    };
+    let mut paths = Vec::new();
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        if arg == "-h" || arg == "--help" {
+            options.help = true;
+            return Ok(options);
+        } else if let Some(value) = flag_value(arg, "--format", &mut rest) {
+            options.format = text_or_json("compare", value?)?;
+        } else if let Some(value) = flag_value(arg, "--ignore", &mut rest) {
+            for name in value?.split(',') {
+                options.ignore.extend(compare::stats_named(name)?);
+            }
+        } else if arg.starts_with('-') && arg != "-" {
+            return Err(format!(
+                "Unknown option {}\n{}",
+                arg,
+                compare_usage(&args[0])
+            ));
+        } else {
+            paths.push(arg.clone());
+        }
+    }
+    [options.first, options.second] =
+        <[String; 2]>::try_from(paths).map_err(|_| compare_usage(&args[0]))?;
+    if options.first == "-" && options.second == "-" {
+        return Err("mdlt compare can read only one of the files from stdin".to_string());
+    }
+    Ok(options)
+}
+
+pub fn completions_usage(program: &str) -> String {
+    format!(
+        "Usage: {} completions [-h|--help] bash|zsh|fish|powershell",
+        program
+    )
+}
+
+/// Parses `mdlt completions`, whose arguments start at `args[2]`.
+pub fn parse_completions_args(args: &[String]) -> Result<CompletionsOptions, String> {
+    let mut options = CompletionsOptions {
+        help: false,
+        shell: None,
+    };
+    for arg in &args[2..] {
+        if arg == "-h" || arg == "--help" {
+            options.help = true;
+            return Ok(options);
+        } else if arg.starts_with('-') {
+            return Err(format!(
+                "Unknown option {}\n{}",
+                arg,
+                completions_usage(&args[0])
+            ));
+        } else if options.shell.is_some() {
+            return Err(completions_usage(&args[0]));
+        } else {
+            options.shell = Some(arg.parse()?);
+        }
+    }
+    if options.shell.is_none() {
+        return Err(completions_usage(&args[0]));
+    }
+    Ok(options)
+}
+
+/// What the flags say that is only settled once they are all read, such as
+/// the `--check`s from `mdlt.toml` that the command line may replace, or
+/// `--max-listed-lines`, which applies to flags given before it.
+#[derive(Default)]
+struct Pending {
+    /// Each `--check`, and whether it came from `mdlt.toml`.
+    checks: Vec<(bool, Check)>,
+    /// The checks `--enable` turned on since the last `--checks`.
+    enabled: Vec<&'static checks::Check>,
+    /// The keys of each `--expect`, and whether it came from `mdlt.toml`.
+    expect_keys: Vec<(bool, String)>,
+    changed: Vec<Changed>,
+    strict_mixed_indent: bool,
+    max_line_length: Option<usize>,
+    top_lines: Option<usize>,
+    tree_given: bool,
+    /// The flags that shape the `--tree` view, which need `--tree`.
+    tree_settings: Vec<&'static str>,
+    tree: tree::Settings,
+    tab_width_given: bool,
+    listed_lines: usize,
+    output_given: bool,
+    /// The last of the flags that need `--follow`.
+    follow_given: Option<&'static str>,
+    sample_given: Vec<&'static str>,
+    locate: bool,
+    show_empty: bool,
+    count_patterns: Vec<String>,
+    bytes_regex: bool,
+    type_names: Vec<(String, String)>,
+    type_interpreters: Vec<(String, String)>,
+    external_timeout_given: bool,
+}
+
+/// Parses `args` as if the flags `config` holds, which come from
+/// `mdlt.toml`, had been given before them.
+pub fn parse_args_with_config(args: &[String], config: &[String]) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut pending = Pending {
+        listed_lines: DEFAULT_LISTED_LINES,
+        ..Pending::default()
+    };
+    // The flags `mdlt.toml` stands for come first, so that the command line
+    // overrides them.
+    for (from_config, given) in [(true, config), (false, &args[1..])] {
+        let mut iter = given.iter();
+        while let Some(arg) = iter.next() {
+            let Some((flag, value)) = find_flag(arg, &mut iter)? else {
+                if arg.starts_with('-') && arg != "-" {
+                    return Err(format!("Unknown option {}\n{}", arg, usage(&args[0])));
+                }
+                options.paths.push(arg.clone());
+                continue;
+            };
+            let name = flag.name;
+            let taken = match flag.group {
+                Group::Report => report_flag(name, value, &mut options, &mut pending)?,
+                Group::Mode => mode_flag(name, value, &mut options, &mut pending)?,
+                Group::File => file_flag(name, value, &mut options, &mut pending)?,
+                Group::Scan => scan_flag(name, value, &mut options, &mut pending)?,
+                Group::Check => check_flag(name, value, &mut options, &mut pending, from_config)?,
+                Group::Command if name == "--help" || name == "-h" => {
+                    options.help = true;
+                    return Ok(options);
+                }
+                Group::Command if name == "--version" || name == "-V" => {
+                    options.version = true;
+                    return Ok(options);
+                }
+                // What follows `--` is a path even if it looks like a flag.
+                Group::Command if !from_config => {
+                    options.paths.extend(iter.by_ref().cloned());
+                    true
+                }
+                Group::Command => false,
+            };
+            if !taken {
+                return Err(format!("Unknown option {}\n{}", arg, usage(&args[0])));
+            }
+        }
+    }
+
+    settle_checks(&mut options, &pending)?;
+    settle_tree(&mut options, &pending)?;
+    settle_scan(&mut options, &mut pending)?;
+    needs(&options, &pending)?;
+    sample_conflicts(&options, &pending)?;
+    mode_conflicts(&options, &pending)?;
+    window_conflicts(&options)?;
+    report_conflicts(&options)?;
+    settle_changed(&mut options, &mut pending)?;
+    checks_unmet(&options, &pending)?;
+    if options.paths.is_empty()
+        && options.files_from.is_none()
+        && options.changed.is_none()
+        && !options.print_config
+        && !options.list_checks
+        && !options.cache_clear
+        && !options.serve
+    {
+        return Err(usage(&args[0]));
+    }
+    Ok(options)
+}
+
+/// Takes the flag `name`, with its `value`, if it is one of what the report
+/// shows and how, and tells whether it was.
+fn report_flag(
+    name: &str,
+    value: Option<&str>,
+    options: &mut Options,
+    pending: &mut Pending,
+) -> Result<bool, String> {
+    let given = value.unwrap_or_default();
+    if name == "--format" {
+        options.format = given.parse()?;
+    } else if name == "--template" {
+        options.template = Some(given.parse()?);
+    } else if name == "--get" {
+        options.get.push(template::field(given)?);
+    } else if name == "--output" {
+        options.output = (given != "-").then(|| given.to_string());
+        pending.output_given = true;
+    } else if name == "--append" {
+        options.append = true;
+    } else if name == "--buffer-output" {
+        options.buffer_output = true;
+    } else if name == "--only-problems" {
+        options.only_problems = true;
+    } else if name == "--summary-only" {
+        options.report.summary_only = true;
+    } else if name == "--group-by" {
+        options.group_by = Some(given.parse()?);
+    } else if name == "--tree" {
+        pending.tree_given = true;
+    } else if name == "--tree-depth" {
+        pending.tree.depth = Some(parse_count("--tree-depth", given)?);
+        pending.tree_settings.push("--tree-depth");
+    } else if name == "--ascii" {
+        pending.tree.ascii = true;
+        pending.tree_settings.push("--ascii");
+    } else if name == "--collapse-clean" {
+        pending.tree.collapse_clean = true;
+        pending.tree_settings.push("--collapse-clean");
+    } else if name == "--color" {
+        options.color = given.parse()?;
+    } else if name == "--sort" {
+        options.sort = Some(given.parse()?);
+    } else if name == "--list" {
+        for name in given.split(',') {
+            let predicate = name.parse()?;
+            if !options.list.contains(&predicate) {
+                options.list.push(predicate);
+            }
+        }
+    } else if name == "--print0" {
+        options.print0 = true;
+    } else if name == "--no-progress" {
+        options.progress = false;
+    } else if name == "--timing" {
+        options.timing = true;
+    } else if name == "-q" || name == "--quiet" {
+        options.quiet += 1;
+    } else if name == "-qq" {
+        options.quiet += 2;
+    } else if name == "--preview" {
+        options.report.preview = true;
+    } else if name == "--width" {
+        options.report.width = Some(parse_count("--width", given)?);
+    } else if name == "--columns" {
+        options.report.columns = Some(given.parse()?);
+    } else if name == "--no-header" {
+        options.report.no_header = true;
+    } else if name == "--no-group-digits" {
+        options.report.no_group_digits = true;
+    } else if name == "--stable-output" {
+        options.report.layout = given.parse()?;
+    } else if name == "--show-suppressed" {
+        options.report.show_suppressed = true;
+    } else if name == "-v" || name == "--verbose" {
+        options.verbose += 1;
+        options.report.verbose = true;
+    } else if name == "-vv" {
+        options.verbose += 2;
+        options.report.verbose = true;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Takes the flag `name`, with its `value`, if it is one of what the run
+/// does, such as `--watch`, `--convert` or `--cache`, and tells whether it
+/// was.
+fn mode_flag(
+    name: &str,
+    value: Option<&str>,
+    options: &mut Options,
+    pending: &mut Pending,
+) -> Result<bool, String> {
+    let given = value.unwrap_or_default();
+    if name == "--watch" {
+        options.watch = true;
+    } else if name == "--follow" {
+        options.follow = true;
+    } else if name == "--serve" {
+        options.serve = true;
+    } else if name == "--follow-lines" {
+        options.follow_lines = match given.parse() {
+            Ok(lines) if lines > 0 => lines,
+            _ => {
+                return Err(format!(
+                    "--follow-lines expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+        pending.follow_given = Some("--follow-lines");
+    } else if name == "--interval" {
+        options.interval = match given.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Duration::from_secs_f64(seconds),
+            _ => {
+                return Err(format!(
+                    "--interval expects a positive number of seconds, got '{}'",
+                    given
+                ))
+            }
+        };
+        pending.follow_given = Some("--interval");
+    } else if name == "--timeout" {
+        options.timeout = match given.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 && seconds.is_finite() => {
+                Some(Duration::from_secs_f64(seconds))
+            }
+            _ => {
+                return Err(format!(
+                    "--timeout expects a positive number of seconds, got '{}'",
+                    given
+                ))
+            }
+        };
+    } else if name == "--cache" {
+        options.cache = Some(match value {
+            None => cache::default_path()
+                .ok_or("--cache finds no cache directory; give it a file, as in --cache=PATH")?
+                .display()
+                .to_string(),
+            Some("") => {
+                return Err("--cache= needs a path, such as --cache=.mdlt-cache".to_string())
+            }
+            Some(path) => path.to_string(),
+        });
+    } else if name == "--no-cache" {
+        options.cache = None;
+    } else if name == "--cache-clear" {
+        options.cache_clear = true;
+    } else if name == "--convert" {
+        options.convert = Some(given.parse()?);
+    } else if name == "--force" {
+        options.force = true;
+    } else if name == "--backup" {
+        options.backup = Some(match value {
+            None => DEFAULT_BACKUP_SUFFIX.to_string(),
+            Some("") => return Err("--backup= needs a suffix, such as --backup=.orig".to_string()),
+            Some(suffix) => suffix.to_string(),
+        });
+    } else if name == "--no-backup" {
+        options.backup = None;
+    } else if name == "--backup-overwrite" {
+        options.backup_overwrite = true;
+    } else if name == "--preserve-mtime" {
+        options.preserve_mtime = true;
+    } else if name == "--dry-run" {
+        options.dry_run = true;
+    } else if name == "--interactive" {
+        options.interactive = true;
+    } else if name == "--fix" {
+        for name in given.split(',') {
+            let fix = name.parse::<Fix>()?;
+            if options
+                .fixes
+                .iter()
+                .any(|&given| given.name() == fix.name() && given != fix)
+            {
+                return Err(format!("--fix {} can only be given once", fix.name()));
+            }
+            if !options.fixes.contains(&fix) {
+                options.fixes.push(fix);
+            }
+        }
+    } else if name == "--config" {
+        options.config = Some(given.to_string());
+    } else if name == "--no-config" {
+        options.no_config = true;
+    } else if name == "--print-config" {
+        options.print_config = true;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Takes the flag `name`, with its `value`, if it is one of which files are
+/// analyzed and how they are read, and tells whether it was.
+fn file_flag(
+    name: &str,
+    value: Option<&str>,
+    options: &mut Options,
+    pending: &mut Pending,
+) -> Result<bool, String> {
+    let given = value.unwrap_or_default();
+    if name == "--exclude" {
+        options.walk.filter.exclude(given)?;
+    } else if name == "--include" {
+        options.walk.filter.include(given)?;
+    } else if name == "--max-depth" {
+        options.walk.max_depth = Some(parse_count("--max-depth", given)?);
+    } else if name == "--min-depth" {
+        options.walk.min_depth = parse_count("--min-depth", given)?;
+    } else if name == "--follow-symlinks" || name == "-L" {
+        options.walk.follow_symlinks = true;
+    } else if name == "--no-default-prune" {
+        options.walk.default_prune = false;
+    } else if name == "--stdin-filename" {
+        options.stdin_filename = Some(given.to_string());
+    } else if name == "--relative" {
+        options.relative_to = Some(".".to_string());
+    } else if name == "--relative-to" {
+        options.relative_to = Some(given.to_string());
+    } else if name == "--slash" {
+        options.slash = true;
+    } else if name == "--files-from" {
+        options.files_from = Some(given.to_string());
+    } else if name == "-0" || name == "--null-data" {
+        options.null_data = true;
+    } else if name == "--jobs" {
+        options.jobs = match given.parse() {
+            Ok(jobs) if jobs > 0 => jobs,
+            _ => {
+                return Err(format!(
+                    "--jobs expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+    } else if name == "--intra-file-jobs" {
+        options.intra_file_jobs = match given.parse() {
+            Ok(jobs) if jobs > 0 => jobs,
+            _ => {
+                return Err(format!(
+                    "--intra-file-jobs expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+    } else if name == "--sample" {
+        options.sample = match given.parse() {
+            Ok(count) if count > 0 => Some(sample::Size::Count(count)),
+            _ => {
+                return Err(format!(
+                    "--sample expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+        pending.sample_given.push("--sample");
+    } else if name == "--sample-percent" {
+        options.sample = match given.parse::<f64>() {
+            Ok(percent) if percent > 0.0 && percent <= 100.0 => {
+                Some(sample::Size::Percent(percent))
+            }
+            _ => {
+                return Err(format!(
+                    "--sample-percent expects a percentage above 0 and up to 100, got '{}'",
+                    given
+                ))
+            }
+        };
+        pending.sample_given.push("--sample-percent");
+    } else if name == "--seed" {
+        options.seed = Some(
+            given
+                .parse()
+                .map_err(|_| format!("--seed expects a non-negative integer, got '{}'", given))?,
+        );
+    } else if name == "--max-size" {
+        options.max_size = Some(parse_size("--max-size", given)?);
+    } else if name == "--skip-generated" {
+        options.skip_generated = true;
+    } else if name == "--generated-line-length" {
+        options.scan.generated.line_length = parse_count("--generated-line-length", given)?;
+    } else if name == "--generated-line-share" {
+        options.scan.generated.line_share = match given.parse::<u8>() {
+            Ok(percent) if percent < 100 => percent,
+            _ => {
+                return Err(format!(
+                    "--generated-line-share expects a percentage from 0 to 99, got '{}'",
+                    given
+                ))
+            }
+        };
+    } else if name == "--fail-on-skip" {
+        options.fail_on_skip = true;
+    } else if name == "--fail-fast" {
+        options.fail_fast = true;
+    } else if name == "--decompress" {
+        options.decompress = given.parse()?;
+    } else if name == "--treat-binary" {
+        options.treat_binary = Some(given.parse()?);
+    } else if name == "--staged" {
+        pending.changed.push(Changed::Staged);
+    } else if name == "--rev" {
+        options.rev = Some(given.to_string());
+    } else if name == "--git-modified" {
+        pending.changed.push(Changed::Modified);
+    } else if name == "--git-diff" {
+        pending.changed.push(Changed::Since(given.to_string()));
+    } else if name == "--mmap" {
+        options.mmap = MmapMode::Always;
+    } else if name == "--no-mmap" {
+        options.mmap = MmapMode::Never;
+    } else if name == "-r" || name == "--recursive" {
+        options.recursive = true;
+    } else if name == "--no-glob" {
+        options.glob = false;
+    } else if name == "--no-ignore" {
+        options.walk.ignore = false;
+    } else if name == "--hidden" {
+        options.walk.hidden = true;
+    } else if name == "--archive" {
+        options.walk.archives = true;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Takes the flag `name`, with its `value`, if it is one of what the scan of
+/// each file counts, and tells whether it was.
+fn scan_flag(
+    name: &str,
+    value: Option<&str>,
+    options: &mut Options,
+    pending: &mut Pending,
+) -> Result<bool, String> {
+    let given = value.unwrap_or_default();
+    if name == "--encoding" {
+        options.scan.encoding = Some(given.parse()?);
+    } else if name == "--detect-encoding" {
+        options.scan.detect_encoding = Some(match value {
+            None => DetectEncoding::Report,
+            Some(mode) => mode.parse()?,
+        });
+    } else if name == "--allow-form-feed" {
+        options.scan.allow_form_feed = true;
+    } else if name == "--blank-as-empty" {
+        options.scan.blank_as_empty = true;
+    } else if name == "--lang" {
+        match given.parse::<Language>()? {
+            Language::Markdown => options.scan.markdown = true,
+        }
+    } else if name == "--skip-fenced-code" {
+        options.scan.skip_fenced_code = true;
+    } else if name == "--type-name" || name == "--type-interpreter" {
+        let types = match name {
+            "--type-name" => &mut pending.type_names,
+            _ => &mut pending.type_interpreters,
+        };
+        for (name, kind) in parse_types(name, given)? {
+            // The command line replaces a type from `mdlt.toml`.
+            types.retain(|(given, _)| *given != name);
+            types.push((name, kind));
+        }
+    } else if name == "--duplicates" {
+        options.scan.duplicates = true;
+    } else if name == "--metadata" {
+        options.metadata = true;
+    } else if name == "--hash" {
+        options.scan.hash = true;
+    } else if name == "--transitions" {
+        options.scan.transitions = true;
+    } else if name == "--locate" {
+        pending.locate = true;
+    } else if name == "--show-empty" {
+        pending.show_empty = true;
+    } else if name == "--max-blank-run" {
+        options.scan.max_blank_run = Some(parse_count("--max-blank-run", given)?);
+    } else if name == "--histogram" {
+        options.scan.histogram.get_or_insert_with(Buckets::default);
+    } else if name == "--histogram-buckets" {
+        options.scan.histogram = Some(given.parse()?);
+    } else if name == "--peek" {
+        options.scan.peek.get_or_insert(preview::MAX_CHARS);
+    } else if name == "--peek-width" {
+        options.scan.peek = match given.parse() {
+            Ok(width) if width > 0 => Some(width),
+            _ => {
+                return Err(format!(
+                    "--peek-width expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+    } else if name == "--max-line-length" {
+        pending.max_line_length = Some(parse_count("--max-line-length", given)?);
+    } else if name == "--count-pattern" {
+        let pattern = given.to_string();
+        if !pending.count_patterns.contains(&pattern) {
+            pending.count_patterns.push(pattern);
+        }
+    } else if name == "--bytes-regex" {
+        pending.bytes_regex = true;
+    } else if name == "--max-listed-lines" {
+        pending.listed_lines = parse_count("--max-listed-lines", given)?;
+    } else if name == "--tab-width" {
+        options.scan.tab_width = match given.parse() {
+            Ok(width) if width > 0 => width,
+            _ => {
+                return Err(format!(
+                    "--tab-width expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+        pending.tab_width_given = true;
+    } else if name == "--top-lines" {
+        pending.top_lines = match given.parse() {
+            Ok(count) if count > 0 => Some(count),
+            _ => {
+                return Err(format!(
+                    "--top-lines expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+        };
+    } else if name == "--max-minority-endings" {
+        options.scan.tolerance.endings = Some(parse_count("--max-minority-endings", given)?);
+    } else if name == "--max-minority-percent" || name == "--mixed-threshold" {
+        options.scan.tolerance.percent = match given.parse::<f64>() {
+            Ok(percent) if (0.0..50.0).contains(&percent) => Some(percent),
+            _ => {
+                return Err(format!(
+                    "{} expects a percentage from 0 to below 50, got '{}'",
+                    name, given
+                ))
+            }
+        };
+    } else if name == "--check-encoding" {
+        options.scan.check_encoding = true;
+    } else if name == "--unicode-linebreaks" {
+        options.scan.unicode_linebreaks = true;
+    } else if name == "--lines" {
+        options.scan.window = Some(window(options.scan.window, Window::lines(given)?)?);
+    } else if name == "--bytes" {
+        options.scan.window = Some(window(options.scan.window, Window::bytes(given)?)?);
+    } else if name == "--head-lines" {
+        let lines = match parse_count("--head-lines", given)? {
+            0 => {
+                return Err(format!(
+                    "--head-lines expects a positive integer, got '{}'",
+                    given
+                ))
+            }
+            lines => lines as u64,
+        };
+        options.scan.head = Some(head(options.scan.head, Head::Lines(lines))?);
+    } else if name == "--head-bytes" {
+        let bytes = match parse_size("--head-bytes", given)? {
+            0 => {
+                return Err(format!(
+                    "--head-bytes expects a positive size, got '{}'",
+                    given
+                ))
+            }
+            bytes => bytes,
+        };
+        options.scan.head = Some(head(options.scan.head, Head::Bytes(bytes))?);
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Takes the flag `name`, with its `value`, if it is one of the checks and
+/// how they fail the run, and tells whether it was. Whether it is
+/// `from_config` tells `--check` and `--expect` what to replace.
+fn check_flag(
+    name: &str,
+    value: Option<&str>,
+    options: &mut Options,
+    pending: &mut Pending,
+    from_config: bool,
+) -> Result<bool, String> {
+    let given = value.unwrap_or_default();
+    if name == "--fail-on-control-chars" {
+        options.fail_on_control_chars = true;
+    } else if name == "--fail-on-non-ascii" {
+        options.fail_on_non_ascii = true;
+    } else if name == "--fail-on-embedded-cr" {
+        options.fail_on_embedded_cr = true;
+    } else if name == "--fail-on-unstable" {
+        options.fail_on_unstable = true;
+    } else if name == "--fail-on-trailing-whitespace" {
+        options.fail_on_trailing_whitespace = true;
+    } else if name == "--fail-on-missing-final-newline" {
+        options.fail_on_missing_final_newline = true;
+    } else if name == "--max-trailing-blank-lines" {
+        options.max_trailing_blank_lines = Some(parse_count("--max-trailing-blank-lines", given)?);
+    } else if name == "--check" {
+        for name in given.split(',') {
+            pending.checks.push((from_config, name.parse::<Check>()?));
+        }
+    } else if name == "--expect" {
+        for (key, endings) in parse_expect(given)? {
+            // The command line replaces a rule from `mdlt.toml`, but
+            // may not give one twice itself.
+            if pending.expect_keys.contains(&(from_config, key.clone())) {
+                return Err(format!("--expect gives {} more than once", key));
+            }
+            options.expect.retain(|(given, _)| *given != key);
+            options.expect.push((key.clone(), endings));
+            pending.expect_keys.push((from_config, key));
+        }
+    } else if name == "--strict-mixed-indent" {
+        pending.strict_mixed_indent = true;
+    } else if name == "--fail-on-mixed-indentation" {
+        options.fail_on_mixed_indentation = true;
+    } else if name == "--fail-on-mixed" {
+        options.fail_on_mixed = true;
+    } else if name == "--gitattributes" {
+        options.gitattributes = true;
+    } else if name == "--editorconfig" {
+        options.editorconfig = true;
+    } else if name == "--list-checks" {
+        options.list_checks = true;
+    } else if name == "--checks" {
+        given.parse::<Preset>()?.apply(options);
+        pending.enabled.clear();
+    } else if name == "--enable" || name == "--disable" {
+        for check in given.split(',') {
+            let check = checks::find(name, check)?;
+            check.switch(options, name == "--enable");
+            pending.enabled.retain(|given| given.name != check.name);
+            if name == "--enable" {
+                pending.enabled.push(check);
+            }
+        }
+    } else if name == "--severity" {
+        for (name, severity) in checks::overrides(given)? {
+            options.severities.retain(|(given, _)| *given != name);
+            options.severities.push((name, severity));
+        }
+    } else if name == "--external-check" {
+        let check: ExternalCheck = given.parse()?;
+        if options
+            .external_checks
+            .iter()
+            .any(|given| given.name == check.name)
+        {
+            return Err(format!("--external-check: '{}' is given twice", check.name));
+        }
+        options.external_checks.push(check);
+    } else if name == "--external-check-timeout" {
+        options.external_check_timeout = match given.parse::<f64>() {
+            Ok(seconds) if seconds > 0.0 && seconds.is_finite() => Duration::from_secs_f64(seconds),
+            _ => {
+                return Err(format!(
+                    "--external-check-timeout expects a positive number of seconds, got '{}'",
+                    given
+                ))
+            }
+        };
+        pending.external_timeout_given = true;
+    } else if name == "--warnings-as-errors" {
+        options.warnings_as_errors = true;
+    } else if name == "--max-problem-files" {
+        options.max_problem_files = Some(parse_count("--max-problem-files", given)?);
+    } else if name == "--max-problems" {
+        options.max_problems = Some(parse_count("--max-problems", given)?);
+    } else if name == "--baseline" {
+        options.baseline = Some(given.to_string());
+    } else if name == "--update-baseline" {
+        options.update_baseline = true;
+    } else if name == "--no-fail" {
+        options.no_fail = true;
+    } else if name == "--strict-encoding" {
+        options.scan.check_encoding = true;
+        options.strict_encoding = true;
+    } else {
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+/// Settles the line ending and indentation checks of `--check` once every
+/// flag is read.
+fn settle_checks(options: &mut Options, pending: &Pending) -> Result<(), String> {
+    options.scan.mixed_indent = if pending.strict_mixed_indent {
+        Some(MixedIndent::Any)
+    } else if pending
+        .checks
+        .iter()
+        .any(|&(_, check)| check == Check::MixedIndent)
+    {
+        Some(MixedIndent::SpaceBeforeTab)
+    } else {
+        None
+    };
+    // Endings given on the command line replace those from `mdlt.toml`
+    // rather than conflicting with them.
+    let endings_given = pending
+        .checks
+        .iter()
+        .any(|&(from_config, check)| !from_config && matches!(check, Check::Endings(_)));
+    let uniform_given = pending
+        .checks
+        .iter()
+        .any(|&(from_config, check)| !from_config && matches!(check, Check::UniformTree(_)));
+    for &(from_config, check) in &pending.checks {
+        if let Check::UniformTree(pinned) = check {
+            if from_config && uniform_given {
+                continue;
+            }
+            let name = |pinned: Option<ExpectedEndings>| match pinned {
+                Some(pinned) => format!("uniform-tree={}", pinned.name()),
+                None => "uniform-tree".to_string(),
+            };
+            match options.uniform_tree {
+                Some(given) if given.pinned != pinned => {
+                    return Err(format!(
+                        "--check {} and --check {} cannot both hold",
+                        name(given.pinned),
+                        name(pinned)
+                    ));
+                }
+                _ => {
+                    options.uniform_tree = Some(UniformTree {
+                        pinned,
+                        listed: pending.listed_lines,
+                    })
+                }
+            }
+            continue;
+        }
+        let Check::Endings(expected) = check else {
+            continue;
+        };
+        if from_config && endings_given {
+            continue;
+        }
+        match options.expected_endings {
+            Some(given) if given != expected => {
+                return Err(format!(
+                    "--check {} and --check {} cannot both hold",
+                    given.name(),
+                    expected.name()
+                ));
+            }
+            _ => options.expected_endings = Some(expected),
+        }
+    }
+    Ok(())
+}
+
+/// Settles the `--tree` view, failing on a setting of it given without
+/// `--tree`.
+fn settle_tree(options: &mut Options, pending: &Pending) -> Result<(), String> {
+    if let (Some(flag), false) = (pending.tree_settings.first(), pending.tree_given) {
+        return Err(format!("{} shapes the --tree view and needs --tree", flag));
+    }
+    options.tree = pending.tree_given.then_some(pending.tree);
+    Ok(())
+}
+
+/// Settles what the scan of each file lists and counts, which waits for
+/// `--max-listed-lines` and `--tab-width` wherever they are given.
+fn settle_scan(options: &mut Options, pending: &mut Pending) -> Result<(), String> {
+    options.scan.line_limit = pending.max_line_length.map(|max_columns| LineLimit {
+        max_columns,
+        listed: pending.listed_lines,
+    });
+    options.scan.locate = pending.locate.then_some(pending.listed_lines);
+    options.scan.top_lines = pending.top_lines.map(|count| TopLines {
+        count,
+        columns: pending.tab_width_given,
+    });
+    options.scan.show_empty = pending.show_empty.then_some(pending.listed_lines);
+    if pending.bytes_regex && pending.count_patterns.is_empty() {
+        return Err("--bytes-regex sets how --count-pattern matches and needs it".to_string());
+    }
+    if !pending.count_patterns.is_empty() {
+        let units = match pending.bytes_regex {
+            true => Units::Bytes,
+            false => Units::Chars,
+        };
+        let regexes = pending
+            .count_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern, units)
+                    .map_err(|e| format!("--count-pattern '{}': {}", pattern, e))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        options.scan.patterns = Some(Patterns {
+            regexes: Box::leak(regexes.into_boxed_slice()),
+            listed: pending.listed_lines,
+        });
+    }
+    if !pending.type_names.is_empty() || !pending.type_interpreters.is_empty() {
+        options.scan.types = Types {
+            names: mem::take(&mut pending.type_names).leak(),
+            interpreters: mem::take(&mut pending.type_interpreters).leak(),
+        };
+    }
+    Ok(())
+}
+
+/// Fails on a flag given without the flag it works with, such as
+/// `--dry-run` without `--convert`, or given with one it contradicts.
+fn needs(options: &Options, pending: &Pending) -> Result<(), String> {
+    if options.dry_run && !options.rewrites() {
+        return Err("--dry-run needs a flag that changes files, such as --convert".to_string());
+    }
+    if options.interactive && !options.rewrites() {
+        return Err(
+            "--interactive asks before changing files and needs a flag that does, such as --convert"
+                .to_string(),
+        );
+    }
+    if options.interactive && options.dry_run {
+        return Err(
+            "--interactive makes the changes it asks about and --dry-run makes none; pass one of them"
+                .to_string(),
+        );
+    }
+    if !options.list.is_empty() && options.rewrites() {
+        return Err(
+            "--list reports on files and cannot be combined with --convert or --fix".to_string(),
+        );
+    }
+    if options.report.width.is_some() && options.format != OutputFormat::Table {
+        return Err("--width sets the width of --format table".to_string());
+    }
+    if options.report.columns.is_some()
+        && !matches!(
+            options.format,
+            OutputFormat::Csv | OutputFormat::Tsv | OutputFormat::Table
+        )
+    {
+        return Err("--columns picks the columns of --format csv, tsv or table".to_string());
+    }
+    if options.report.no_header && !matches!(options.format, OutputFormat::Csv | OutputFormat::Tsv)
+    {
+        return Err("--no-header leaves out the header of --format csv or tsv".to_string());
+    }
+    if options.report.layout != Layout::Current && options.format != OutputFormat::Text {
+        return Err("--stable-output pins the layout of the text report".to_string());
+    }
+    if options.report.no_group_digits && options.format != OutputFormat::Text {
+        return Err(
+            "--no-group-digits writes the counts of the text report as plain digits, which the other formats always do"
+                .to_string(),
+        );
+    }
+    if options.print0 && options.list.is_empty() && !options.only_problems {
+        return Err("--print0 needs --list or --only-problems".to_string());
+    }
+    if options.sort.is_some_and(|sort| sort.key == SortKey::Files) && options.group_by.is_none() {
+        return Err("--sort files orders the --group-by table and needs --group-by".to_string());
+    }
+    if options.append && !pending.output_given {
+        return Err("--append adds to the --output file and needs --output".to_string());
+    }
+    if options.update_baseline && options.baseline.is_none() {
+        return Err(
+            "--update-baseline writes the --baseline file and needs --baseline".to_string(),
+        );
+    }
+    if options.baseline.is_some()
+        && (options.max_problem_files.is_some() || options.max_problems.is_some())
+    {
+        return Err(
+            "--baseline fails on new problems only and cannot be combined with --max-problem-files or --max-problems"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Fails on `--sample` and `--sample-percent` given together, or with a
+/// flag that needs every file.
+fn sample_conflicts(options: &Options, pending: &Pending) -> Result<(), String> {
+    if let [first, second, ..] = pending.sample_given[..] {
+        if first != second {
+            return Err(format!("{} and {} cannot be combined", first, second));
+        }
+    }
+    if options.seed.is_some() && options.sample.is_none() {
+        return Err(
+            "--seed picks the files of --sample or --sample-percent and needs one of them"
+                .to_string(),
+        );
+    }
+    if let Some(flag) = pending.sample_given.first() {
+        let conflict = if options.rewrites() {
+            Some("--convert or --fix")
+        } else if options.watch {
+            Some("--watch")
+        } else if options.follow {
+            Some("--follow")
+        } else if options.update_baseline {
+            Some("--update-baseline")
+        } else {
+            pending.changed.first().map(Changed::flag)
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "{} analyzes files picked at random and cannot be combined with {}",
+                flag, conflict
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fails on the flags that cannot go with `--watch`, `--follow`,
+/// `--serve`, `--buffer-output` or `--timing`, which change how a run goes.
+fn mode_conflicts(options: &Options, pending: &Pending) -> Result<(), String> {
+    if options.watch {
+        let conflict = if options.paths.iter().any(|path| path == "-") {
+            Some("stdin")
+        } else if options.rewrites() {
+            Some("--convert or --fix")
+        } else if let Some(changed) = pending.changed.first() {
+            Some(changed.flag())
+        } else if options.rev.is_some() {
+            Some("--rev")
+        } else if options.output.is_some() {
+            Some("--output")
+        } else if options.verbose > 1 {
+            Some("-vv")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--watch reports on files as they change and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if let Some(flag) = pending.follow_given.filter(|_| !options.follow) {
+        return Err(format!(
+            "{} sets how often --follow reports and needs --follow",
+            flag
+        ));
+    }
+    if options.follow {
+        let conflict = if options.paths.iter().any(|path| path == "-") {
+            Some("stdin".to_string())
+        } else if options.recursive {
+            Some("-r".to_string())
+        } else if options.rewrites() {
+            Some("--convert or --fix".to_string())
+        } else if options.scan.window.is_some() {
+            Some("--lines or --bytes".to_string())
+        } else if options.scan.head.is_some() {
+            Some("--head-lines or --head-bytes".to_string())
+        } else if options.watch {
+            Some("--watch".to_string())
+        } else if let Some(changed) = pending.changed.first() {
+            Some(changed.flag().to_string())
+        } else if options.rev.is_some() {
+            Some("--rev".to_string())
+        } else if options.output.is_some() {
+            Some("--output".to_string())
+        } else if options.verbose > 1 {
+            Some("-vv".to_string())
+        } else if options.quiet > 0 {
+            Some("--quiet".to_string())
+        } else if options.template.is_some() {
+            Some("--template".to_string())
+        } else if !options.get.is_empty() {
+            Some("--get".to_string())
+        } else if !options.list.is_empty() {
+            Some("--list".to_string())
+        } else if options.only_problems {
+            Some("--only-problems".to_string())
+        } else if options.group_by.is_some() {
+            Some("--group-by".to_string())
+        } else if options.gitattributes {
+            Some("--gitattributes".to_string())
+        } else if options.editorconfig {
+            Some("--editorconfig".to_string())
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--follow keeps reading one growing file and cannot be combined with {}",
+                conflict
+            ));
+        }
+        if options.paths.len() != 1 || options.files_from.is_some() {
+            return Err(
+                "--follow keeps reading one growing file and takes a single path".to_string(),
+            );
+        }
+    }
+    if options.serve {
+        // The requests name the files; the rest of the command line only
+        // says how each is analyzed.
+        let conflict = if !options.paths.is_empty() || options.files_from.is_some() {
+            Some("paths")
+        } else if options.rewrites() {
+            Some("--convert or --fix")
+        } else if options.watch {
+            Some("--watch")
+        } else if options.follow {
+            Some("--follow")
+        } else if let Some(changed) = pending.changed.first() {
+            Some(changed.flag())
+        } else if options.rev.is_some() {
+            Some("--rev")
+        } else if options.output.is_some() {
+            Some("--output")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--serve analyzes the files its requests name and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if options.buffer_output {
+        let conflict = if options.rewrites() {
+            Some("--convert or --fix")
+        } else if options.watch {
+            Some("--watch")
+        } else if options.follow {
+            Some("--follow")
+        } else if options.serve {
+            Some("--serve")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--buffer-output holds back the report of a run and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if options.timing {
+        let conflict = if options.rewrites() {
+            Some("--convert or --fix")
+        } else if options.watch {
+            Some("--watch")
+        } else if options.follow {
+            Some("--follow")
+        } else if options.serve {
+            Some("--serve")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--timing times the analysis of a run and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fails on the flags that cannot go with a scan of part of each file or
+/// with the encoding it is read in.
+fn window_conflicts(options: &Options) -> Result<(), String> {
+    if options.scan.window.is_some() && options.rewrites() {
+        return Err(
+            "--lines and --bytes analyze part of each file and cannot be combined with --convert or --fix"
+                .to_string(),
+        );
+    }
+    if options.scan.head.is_some() {
+        let conflict = if options.scan.window.is_some() {
+            Some("--lines or --bytes")
+        } else if options.rewrites() {
+            Some("--convert or --fix")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--head-lines and --head-bytes analyze the start of each file and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if options.scan.detect_encoding.is_some() && options.scan.encoding.is_some() {
+        return Err(
+            "--detect-encoding guesses what --encoding gives; pass one of them".to_string(),
+        );
+    }
+    let apply = options.scan.detect_encoding == Some(DetectEncoding::Apply);
+    if apply && options.rewrites() {
+        return Err(
+            "--detect-encoding=apply may decode files as UTF-16, which --convert and --fix do not rewrite; give --detect-encoding without it"
+                .to_string(),
+        );
+    }
+    let counts_lines = match (options.scan.window, options.scan.head) {
+        (Some(Window::Lines(_)), _) => Some("--lines"),
+        (_, Some(Head::Lines(_))) => Some("--head-lines"),
+        _ => None,
+    };
+    if let Some(flag) = counts_lines.filter(|_| {
+        apply
+            || options
+                .scan
+                .encoding
+                .is_some_and(|encoding| encoding != Encoding::Utf8)
+    }) {
+        return Err(format!(
+            "{} counts single-byte line endings and cannot read UTF-16",
+            flag
+        ));
+    }
+    Ok(())
+}
+
+/// Fails on the flags that cannot go with a report in place of the usual
+/// one: `-vv`, `--get`, `--tree`, `--quiet`, `--summary-only` and
+/// `--group-by`.
+fn report_conflicts(options: &Options) -> Result<(), String> {
+    if options.verbose > 1 {
+        let conflict = if options.paths.iter().any(|path| path == "-") {
+            Some("stdin")
+        } else if options.rewrites() {
+            Some("--convert or --fix")
+        } else if options.quiet > 0 {
+            Some("--quiet")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "-vv prints every line of each file and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if !options.get.is_empty() {
+        let conflict = if options.template.is_some() {
+            Some("--template".to_string())
+        } else if !options.list.is_empty() {
+            Some("--list".to_string())
+        } else if options.print0 {
+            Some("--print0".to_string())
+        } else if options.quiet > 0 {
+            Some("--quiet".to_string())
+        } else if options.verbose > 1 {
+            Some("-vv".to_string())
+        } else if options.report.summary_only {
+            Some("--summary-only".to_string())
+        } else if options.group_by.is_some() {
+            Some("--group-by".to_string())
+        } else if options.rewrites() {
+            Some("--convert or --fix".to_string())
+        } else if options.format != OutputFormat::Text {
+            Some(format!("--format {}", options.format.name()))
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--get prints field values instead of a report and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if options.tree.is_some() {
+        let conflict = if options.template.is_some() {
+            Some("--template".to_string())
+        } else if !options.get.is_empty() {
+            Some("--get".to_string())
+        } else if !options.list.is_empty() {
+            Some("--list".to_string())
+        } else if options.quiet > 0 {
+            Some("--quiet".to_string())
+        } else if options.report.summary_only {
+            Some("--summary-only".to_string())
+        } else if options.group_by.is_some() {
+            Some("--group-by".to_string())
+        } else if options.rewrites() {
+            Some("--convert or --fix".to_string())
+        } else if options.format != OutputFormat::Text {
+            Some(format!("--format {}", options.format.name()))
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--tree prints the directories instead of a report and cannot be combined with {}",
+                conflict
+            ));
+        }
+    }
+    if options.quiet > 0 && !options.rewrites() {
+        let conflict = if options.template.is_some() {
+            Some("--template")
+        } else if !options.list.is_empty() {
+            Some("--list")
+        } else if options.report.summary_only {
+            Some("--summary-only")
+        } else if options.group_by.is_some() {
+            Some("--group-by")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--quiet prints one line per file instead of a report and cannot be \
+                 combined with {}",
+                conflict
+            ));
+        }
+    }
+    let summarizing = if options.report.summary_only {
+        Some("--summary-only prints the text or JSON summary")
+    } else if options.group_by.is_some() {
+        Some("--group-by adds a table to the text or JSON report")
+    } else {
+        None
+    };
+    if let Some(summarizing) = summarizing {
+        let conflict = if options.template.is_some() {
+            Some("--template".to_string())
+        } else if !options.list.is_empty() {
+            Some("--list".to_string())
+        } else if options.rewrites() {
+            Some("--convert or --fix".to_string())
+        } else {
+            match options.format {
+                OutputFormat::Text | OutputFormat::Json => None,
+                other => Some(format!("--format {}", other.name())),
+            }
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "{} and cannot be combined with {}",
+                summarizing, conflict
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Settles which files `--staged`, `--git-modified` or `--git-diff` pick,
+/// and fails on what cannot go with them or with `--rev`.
+fn settle_changed(options: &mut Options, pending: &mut Pending) -> Result<(), String> {
+    if let [first, second, ..] = &pending.changed[..] {
+        return Err(format!(
+            "{} and {} cannot be combined",
+            first.flag(),
+            second.flag()
+        ));
+    }
+    options.changed = pending.changed.pop();
+    if let Some(changed) = &options.changed {
+        if !options.paths.is_empty() || options.files_from.is_some() {
+            return Err(format!(
+                "{} picks the files to check and takes no paths",
+                changed.flag()
+            ));
+        }
+    }
+    if let Some(rev) = &options.rev {
+        let conflict = match &options.changed {
+            Some(changed) => Some(changed.flag()),
+            None if options.recursive => Some("-r"),
+            None if options.rewrites() => Some("--convert or --fix"),
+            None if options.paths.iter().any(|path| path == "-") => Some("stdin"),
+            None => None,
+        };
+        if let Some(conflict) = conflict {
+            return Err(format!(
+                "--rev {} reads files as committed and cannot be combined with {}",
+                rev, conflict
+            ));
+        }
+    }
+    if options.changed == Some(Changed::Staged) && options.rewrites() {
+        return Err(
+            "--staged checks what is staged and cannot be combined with --convert or --fix"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Fails on a check turned on without what it needs, a `--severity` of no
+/// check and the flags of checks given without them.
+fn checks_unmet(options: &Options, pending: &Pending) -> Result<(), String> {
+    if let Some(unmet) = pending
+        .enabled
+        .iter()
+        .find_map(|check| check.unmet(options))
+    {
+        return Err(unmet);
+    }
+    // Only now are the external pending.checks `--severity` may name known.
+    for (name, _) in &options.severities {
+        if !options
+            .external_checks
+            .iter()
+            .any(|check| check.name == *name)
+        {
+            checks::find("--severity", name)?;
+        }
+    }
+    if pending.external_timeout_given && options.external_checks.is_empty() {
+        return Err("--external-check-timeout limits --external-check and needs it".to_string());
+    }
+    if options.config.is_some() && options.no_config {
+        return Err("--config and --no-config cannot be combined".to_string());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::Encoding;
+    use crate::generated::Thresholds;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_multiple_paths_in_order() {
+        let options = parse_args(&args(&["mdlt", "b.txt", "--format", "md", "a.txt"])).unwrap();
+        assert_eq!(options.paths, vec!["b.txt", "a.txt"]);
+        assert_eq!(options.format, OutputFormat::Markdown);
+    }
+
+    #[test]
+    fn test_parse_recursive_flags() {
+        let options = parse_args(&args(&["mdlt", "-r", "src"])).unwrap();
+        assert!(options.recursive);
+        assert!(!options.walk.hidden);
+        let options = parse_args(&args(&["mdlt", "--recursive", "--hidden", "src"])).unwrap();
+        assert!(options.recursive && options.walk.hidden);
+        assert!(!options.walk.archives);
+        let options = parse_args(&args(&["mdlt", "--archive", "release.zip"])).unwrap();
+        assert!(options.walk.archives);
+    }
+
+    #[test]
+    fn test_parse_filters_and_verbose() {
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--exclude",
+            "*.min.js",
+            "--exclude=vendor/**",
+            "--include",
+            "*.js",
+            "-v",
+            ".",
+        ]))
+        .unwrap();
+        assert!(!options.walk.filter.allows("a.min.js"));
+        assert!(!options.walk.filter.allows("vendor/a.js"));
+        assert!(options.walk.filter.allows("a.js"));
+        assert!(!options.walk.filter.allows("a.rs"));
+        assert_eq!(options.verbose, 1);
+    }
+
+    #[test]
+    fn test_parse_line_detail() {
+        let options = parse_args(&args(&["mdlt", "-vv", "--lines", "10..20", "a"])).unwrap();
+        assert_eq!(options.verbose, 2);
+        assert_eq!(
+            options
+                .scan
+                .window
+                .map(|window| window.to_string())
+                .as_deref(),
+            Some("lines 10..20")
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "-v", "-v", "a"]))
+                .unwrap()
+                .verbose,
+            2
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "-vv", "-"])).unwrap_err(),
+            "-vv prints every line of each file and cannot be combined with stdin"
+        );
+    }
+
+    #[test]
+    fn test_parse_window() {
+        let window = |list: &[&str]| {
+            parse_args(&args(list)).map(|options| options.scan.window.map(|w| w.to_string()))
+        };
+        assert_eq!(
+            window(&["mdlt", "--lines=5000..", "a"]),
+            Ok(Some("lines 5000..".to_string()))
+        );
+        assert_eq!(
+            window(&["mdlt", "--bytes", "..4096", "a"]),
+            Ok(Some("bytes 0..4096".to_string()))
+        );
+        assert_eq!(
+            window(&["mdlt", "--lines=1..2", "--bytes=0..1", "a"]),
+            Err("--lines and --bytes cannot be combined; pass one of them".to_string())
+        );
+        assert!(window(&["mdlt", "--lines=9..2", "a"])
+            .unwrap_err()
+            .starts_with("--lines expects a range"));
+        assert_eq!(
+            window(&["mdlt", "--bytes=4..", "--convert=lf", "a"]),
+            Err("--lines and --bytes analyze part of each file and cannot be combined with --convert or --fix".to_string())
+        );
+        assert_eq!(
+            window(&["mdlt", "--lines=4..", "--encoding=utf-16le", "a"]),
+            Err("--lines counts single-byte line endings and cannot read UTF-16".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_head() {
+        let head = |list: &[&str]| parse_args(&args(list)).map(|options| options.scan.head);
+        assert_eq!(head(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            head(&["mdlt", "--head-lines=1000", "a"]),
+            Ok(Some(Head::Lines(1000)))
+        );
+        assert_eq!(
+            head(&["mdlt", "--head-bytes", "64K", "--head-bytes=1M", "a"]),
+            Ok(Some(Head::Bytes(1 << 20)))
+        );
+        assert_eq!(
+            head(&["mdlt", "--head-lines=0", "a"]),
+            Err("--head-lines expects a positive integer, got '0'".to_string())
+        );
+        assert_eq!(
+            head(&["mdlt", "--head-lines=5", "--head-bytes=5", "a"]),
+            Err("--head-lines and --head-bytes cannot be combined; pass one of them".to_string())
+        );
+        assert_eq!(
+            head(&["mdlt", "--head-bytes=5", "--lines=2..", "a"]),
+            Err("--head-lines and --head-bytes analyze the start of each file and cannot be combined with --lines or --bytes".to_string())
+        );
+        assert_eq!(
+            head(&["mdlt", "--head-lines=5", "--fix=final-newline", "a"]),
+            Err("--head-lines and --head-bytes analyze the start of each file and cannot be combined with --convert or --fix".to_string())
+        );
+        assert_eq!(
+            head(&["mdlt", "--head-lines=5", "--encoding=utf-16be", "a"]),
+            Err("--head-lines counts single-byte line endings and cannot read UTF-16".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_count_pattern() {
+        let patterns = |list: &[&str]| {
+            parse_args(&args(list)).map(|options| {
+                options.scan.patterns.map(|patterns| {
+                    let sources: Vec<String> = patterns
+                        .regexes
+                        .iter()
+                        .map(|regex| format!("{:?}", regex))
+                        .collect();
+                    (sources, patterns.listed)
+                })
+            })
+        };
+        assert_eq!(patterns(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            patterns(&[
+                "mdlt",
+                "--count-pattern=TODO|FIXME",
+                "--count-pattern",
+                "\\t$",
+                "--count-pattern=TODO|FIXME",
+                "--max-listed-lines=2",
+                "a",
+            ]),
+            Ok(Some((
+                vec![
+                    "Regex(\"TODO|FIXME\", Chars)".to_string(),
+                    "Regex(\"\\\\t$\", Chars)".to_string(),
+                ],
+                2
+            )))
+        );
+        assert_eq!(
+            patterns(&["mdlt", "--bytes-regex", "--count-pattern=\\xFF", "a"]),
+            Ok(Some((vec!["Regex(\"\\\\xFF\", Bytes)".to_string()], 10)))
+        );
+        assert_eq!(
+            patterns(&["mdlt", "--count-pattern=TODO(", "a"]),
+            Err("--count-pattern 'TODO(': unclosed group opened at character 5".to_string())
+        );
+        assert_eq!(
+            patterns(&["mdlt", "--bytes-regex", "a"]),
+            Err("--bytes-regex sets how --count-pattern matches and needs it".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_depth_flags() {
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--max-depth",
+            "2",
+            "--min-depth=1",
+            "--no-default-prune",
+            ".",
+        ]))
+        .unwrap();
+        assert_eq!(options.walk.max_depth, Some(2));
+        assert_eq!(options.walk.min_depth, 1);
+        assert!(!options.walk.default_prune);
+        let err = parse_args(&args(&["mdlt", "--max-depth", "-1", "."])).unwrap_err();
+        assert_eq!(err, "--max-depth expects a non-negative integer, got '-1'");
+    }
+
+    #[test]
+    fn test_parse_serve() {
+        let options = parse_args(&args(&["mdlt", "--serve", "--check=lf"])).unwrap();
+        assert!(options.serve && options.paths.is_empty());
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--serve", "a.txt"])).unwrap_err(),
+            "--serve analyzes the files its requests name and cannot be combined with paths"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--serve", "--fix=final-newline"])).unwrap_err(),
+            "--serve analyzes the files its requests name and cannot be combined with --convert or --fix"
+        );
+    }
+
+    #[test]
+    fn test_parse_watch() {
+        let options = parse_args(&args(&["mdlt", "--watch", "-r", "--check=lf", "src"])).unwrap();
+        assert!(options.watch);
+        assert!(!parse_args(&args(&["mdlt", "src"])).unwrap().watch);
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--watch", "--fix=final-newline", "a"])).unwrap_err(),
+            "--watch reports on files as they change and cannot be combined with --convert or --fix"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--watch", "--staged"])).unwrap_err(),
+            "--watch reports on files as they change and cannot be combined with --staged"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--watch", "--output", "r.txt", "a"])).unwrap_err(),
+            "--watch reports on files as they change and cannot be combined with --output"
+        );
+    }
+
+    #[test]
+    fn test_parse_follow() {
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--follow",
+            "--follow-lines=50",
+            "--interval",
+            "0.5",
+            "app.log",
+        ]))
+        .unwrap();
+        assert!(options.follow);
+        assert_eq!(options.follow_lines, 50);
+        assert_eq!(options.interval, Duration::from_millis(500));
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--interval", "2", "app.log"])).unwrap_err(),
+            "--interval sets how often --follow reports and needs --follow"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--follow", "--interval=0", "app.log"])).unwrap_err(),
+            "--interval expects a positive number of seconds, got '0'"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--follow", "a.log", "b.log"])).unwrap_err(),
+            "--follow keeps reading one growing file and takes a single path"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--follow", "-q", "a.log"])).unwrap_err(),
+            "--follow keeps reading one growing file and cannot be combined with --quiet"
+        );
+    }
+
+    #[test]
+    fn test_parse_output() {
+        let options = parse_args(&args(&["mdlt", "--output", "r.json", "--append", "a"])).unwrap();
+        assert_eq!(options.output.as_deref(), Some("r.json"));
+        assert!(options.append);
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--output=-", "a"]))
+                .unwrap()
+                .output,
+            None
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--append", "a"])).unwrap_err(),
+            "--append adds to the --output file and needs --output"
+        );
+    }
+
+    #[test]
+    fn test_parse_color() {
+        let parse = |arguments: &[&str]| parse_args(&args(arguments)).map(|options| options.color);
+        assert_eq!(parse(&["mdlt", "a"]), Ok(ColorChoice::Auto));
+        assert_eq!(
+            parse(&["mdlt", "--color", "never", "a"]),
+            Ok(ColorChoice::Never)
+        );
+        assert_eq!(
+            parse(&["mdlt", "--color=always", "a"]),
+            Ok(ColorChoice::Always)
+        );
+        assert_eq!(
+            parse(&["mdlt", "--color=yes", "a"]),
+            Err("Unknown --color 'yes': expected auto, always or never".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_no_group_digits() {
+        let parse = |arguments: &[&str]| {
+            parse_args(&args(arguments)).map(|options| options.report.no_group_digits)
+        };
+        assert_eq!(parse(&["mdlt", "a"]), Ok(false));
+        assert_eq!(parse(&["mdlt", "--no-group-digits", "a"]), Ok(true));
+        assert_eq!(
+            parse(&["mdlt", "--format=csv", "--no-group-digits", "a"]),
+            Err("--no-group-digits writes the counts of the text report as plain digits, which the other formats always do".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_stable_output() {
+        let parse =
+            |arguments: &[&str]| parse_args(&args(arguments)).map(|options| options.report.layout);
+        assert_eq!(parse(&["mdlt", "a"]), Ok(Layout::Current));
+        assert_eq!(
+            parse(&["mdlt", "--stable-output", "v1", "a"]),
+            Ok(Layout::V1)
+        );
+        assert_eq!(
+            parse(&["mdlt", "--stable-output=v2", "a"]),
+            Err("Unknown --stable-output version 'v2'; supported versions are: v1".to_string())
+        );
+        assert_eq!(
+            parse(&["mdlt", "--format=json", "--stable-output=v1", "a"]),
+            Err("--stable-output pins the layout of the text report".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_get() {
+        let options =
+            parse_args(&args(&["mdlt", "--get", "total_lines", "--get=crlf", "a"])).unwrap();
+        assert_eq!(options.get, ["total_lines", "crlf"]);
+        assert!(parse_args(&args(&["mdlt", "--get", "colour", "a"]))
+            .unwrap_err()
+            .contains("valid fields are: name, extension, total_lines,"));
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--get=lf", "--format=json", "a"])).unwrap_err(),
+            "--get prints field values instead of a report and cannot be combined with --format json"
+        );
+    }
+
+    #[test]
+    fn test_parse_quiet() {
+        let quiet = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.quiet);
+        assert_eq!(quiet(&["mdlt", "a"]), Ok(0));
+        assert_eq!(quiet(&["mdlt", "-q", "a"]), Ok(1));
+        assert_eq!(quiet(&["mdlt", "--quiet", "a"]), Ok(1));
+        assert_eq!(quiet(&["mdlt", "-qq", "a"]), Ok(2));
+        assert_eq!(quiet(&["mdlt", "-q", "-q", "a"]), Ok(2));
+        assert_eq!(
+            quiet(&["mdlt", "-q", "--list=crlf", "a"]),
+            Err(
+                "--quiet prints one line per file instead of a report and cannot be \
+                 combined with --list"
+                    .to_string()
+            )
+        );
+        assert_eq!(quiet(&["mdlt", "-q", "--convert=lf", "a"]), Ok(1));
+    }
+
+    #[test]
+    fn test_parse_width() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        assert_eq!(parse(&["mdlt", "a"]).unwrap().report.width, None);
+        assert_eq!(
+            parse(&["mdlt", "--format=table", "--width=100", "a"])
+                .unwrap()
+                .report
+                .width,
+            Some(100)
+        );
+        assert_eq!(
+            parse(&["mdlt", "--width", "100", "a"]).unwrap_err(),
+            "--width sets the width of --format table"
+        );
+        assert!(parse(&["mdlt", "--format=table", "--width=wide", "a"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_columns() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&[
+            "mdlt",
+            "--format=tsv",
+            "--columns=file,crlf",
+            "--no-header",
+            "a",
+        ]);
+        let options = options.unwrap();
+        assert_eq!(options.format, OutputFormat::Tsv);
+        assert_eq!(options.report.columns, Some("file,crlf".parse().unwrap()));
+        assert!(options.report.no_header);
+        assert!(parse(&["mdlt", "--format=csv", "--columns", "lines", "a"]).is_ok());
+        assert!(parse(&["mdlt", "--format=tsv", "--columns=lenght", "a"])
+            .unwrap_err()
+            .starts_with("Unknown --columns name 'lenght'; valid columns are: name, extension, "));
+        assert_eq!(
+            parse(&["mdlt", "--columns=lines", "a"]).unwrap_err(),
+            "--columns picks the columns of --format csv, tsv or table"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--format=json", "--no-header", "a"]).unwrap_err(),
+            "--no-header leaves out the header of --format csv or tsv"
+        );
+    }
+
+    #[test]
+    fn test_parse_relative_to() {
+        let parse = |argv: &[&str]| parse_args(&args(argv)).unwrap();
+        let options = parse(&["mdlt", "a"]);
+        assert_eq!((options.relative_to, options.slash), (None, false));
+        let options = parse(&["mdlt", "--relative", "--slash", "a"]);
+        assert_eq!(
+            (options.relative_to.as_deref(), options.slash),
+            (Some("."), true)
+        );
+        assert_eq!(
+            parse(&["mdlt", "--relative-to=src", "a"])
+                .relative_to
+                .as_deref(),
+            Some("src")
+        );
+    }
+
+    #[test]
+    fn test_parse_stdin_dash_is_a_path() {
+        let options = parse_args(&args(&["mdlt", "--stdin-filename", "foo.c", "-"])).unwrap();
+        assert_eq!(options.paths, vec!["-"]);
+        assert_eq!(options.stdin_filename.as_deref(), Some("foo.c"));
+    }
+
+    #[test]
+    fn test_parse_files_from_without_paths() {
+        let options = parse_args(&args(&["mdlt", "--files-from", "-", "-0"])).unwrap();
+        assert_eq!(options.files_from.as_deref(), Some("-"));
+        assert!(options.null_data);
+        assert!(options.paths.is_empty());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("--max-size", "0"), Ok(0));
+        assert_eq!(parse_size("--max-size", "512"), Ok(512));
+        assert_eq!(parse_size("--max-size", "10K"), Ok(10 * 1024));
+        assert_eq!(parse_size("--max-size", "10k"), Ok(10 * 1024));
+        assert_eq!(parse_size("--max-size", "10M"), Ok(10 * 1024 * 1024));
+        assert_eq!(parse_size("--max-size", "1G"), Ok(1 << 30));
+        assert_eq!(parse_size("--max-size", "2T"), Ok(2 << 40));
+        assert_eq!(parse_size("--max-size", "1GB"), Ok(1 << 30));
+        assert_eq!(parse_size("--max-size", "1GiB"), Ok(1 << 30));
+        assert_eq!(parse_size("--max-size", "100B"), Ok(100));
+    }
+
+    #[test]
+    fn test_parse_size_rejects_malformed_values() {
+        for value in [
+            "",
+            "M",
+            "10X",
+            "1.5G",
+            "-1",
+            "10 M",
+            "10iB",
+            "10MM",
+            "99999999999T",
+        ] {
+            assert_eq!(
+                parse_size("--max-size", value),
+                Err(format!(
+                    "--max-size expects a size like 512, 10K, 10M or 1G, got '{}'",
+                    value
+                )),
+                "value {:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_max_size() {
+        let options =
+            parse_args(&args(&["mdlt", "--max-size=10M", "--fail-on-skip", "a"])).unwrap();
+        assert_eq!(options.max_size, Some(10 << 20));
+        assert!(options.fail_on_skip);
+        assert!(!options.fail_fast);
+        assert!(!options.only_problems);
+        assert!(
+            parse_args(&args(&["mdlt", "--fail-fast", "a"]))
+                .unwrap()
+                .fail_fast
+        );
+        assert_eq!(parse_args(&args(&["mdlt", "a"])).unwrap().max_size, None);
+        assert!(
+            parse_args(&args(&["mdlt", "--only-problems", "a"]))
+                .unwrap()
+                .only_problems
+        );
+    }
+
+    #[test]
+    fn test_parse_generated() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&["mdlt", "a"]).unwrap();
+        assert!(!options.skip_generated);
+        assert_eq!(options.scan.generated, Thresholds::default());
+        let options = parse(&[
+            "mdlt",
+            "--skip-generated",
+            "--generated-line-length=300",
+            "--generated-line-share",
+            "75",
+            "a",
+        ])
+        .unwrap();
+        assert!(options.skip_generated);
+        assert_eq!(
+            options.scan.generated,
+            Thresholds {
+                line_length: 300,
+                line_share: 75
+            }
+        );
+        assert_eq!(
+            parse(&["mdlt", "--generated-line-share=100", "a"]).unwrap_err(),
+            "--generated-line-share expects a percentage from 0 to 99, got '100'"
+        );
+    }
+
+    #[test]
+    fn test_parse_summary_only() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        assert!(!parse(&["mdlt", "a"]).unwrap().report.summary_only);
+        assert!(
+            parse(&["mdlt", "--summary-only", "--format=json", "a"])
+                .unwrap()
+                .report
+                .summary_only
+        );
+        assert_eq!(
+            parse(&["mdlt", "--summary-only", "--format=csv", "a"]).unwrap_err(),
+            "--summary-only prints the text or JSON summary and cannot be combined with \
+             --format csv"
+        );
+        assert!(parse(&["mdlt", "--summary-only", "--list=crlf", "a"])
+            .unwrap_err()
+            .ends_with("combined with --list"));
+        assert!(parse(&["mdlt", "--summary-only", "--convert=lf", "a"])
+            .unwrap_err()
+            .ends_with("combined with --convert or --fix"));
+    }
+
+    #[test]
+    fn test_parse_tree() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        assert_eq!(parse(&["mdlt", "a"]).unwrap().tree, None);
+        assert_eq!(
+            parse(&["mdlt", "--tree", "a"]).unwrap().tree,
+            Some(tree::Settings::default())
+        );
+        assert_eq!(
+            parse(&[
+                "mdlt",
+                "--ascii",
+                "--tree-depth",
+                "2",
+                "--collapse-clean",
+                "--tree",
+                "a"
+            ])
+            .unwrap()
+            .tree,
+            Some(tree::Settings {
+                depth: Some(2),
+                ascii: true,
+                collapse_clean: true,
+            })
+        );
+        assert_eq!(
+            parse(&["mdlt", "--tree-depth=1", "a"]).unwrap_err(),
+            "--tree-depth shapes the --tree view and needs --tree"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--tree", "--format=json", "a"]).unwrap_err(),
+            "--tree prints the directories instead of a report and cannot be combined with --format json"
+        );
+    }
+
+    #[test]
+    fn test_parse_group_by() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&["mdlt", "a"]).unwrap();
+        assert_eq!((options.group_by, options.sort), (None, None));
+        let options = parse(&["mdlt", "--group-by", "dir", "--sort=files", "a"]).unwrap();
+        assert_eq!(
+            (options.group_by, options.sort),
+            (
+                Some(GroupBy::Dir),
+                Some(Sort {
+                    key: SortKey::Files,
+                    descending: false
+                })
+            )
+        );
+        assert_eq!(
+            parse(&["mdlt", "--group-by=ext", "a"]).unwrap().group_by,
+            Some(GroupBy::Extension)
+        );
+        assert_eq!(
+            parse(&["mdlt", "--group-by=size", "a"]).unwrap_err(),
+            "Unknown --group-by 'size': expected extension, dir or archive"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--sort=files", "a"]).unwrap_err(),
+            "--sort files orders the --group-by table and needs --group-by"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--group-by=dir", "--format=html", "a"]).unwrap_err(),
+            "--group-by adds a table to the text or JSON report and cannot be combined with \
+             --format html"
+        );
+    }
+
+    #[test]
+    fn test_parse_sort() {
+        let sort = |value: &str| value.parse::<Sort>();
+        assert_eq!(
+            sort("lines"),
+            Ok(Sort {
+                key: SortKey::Lines,
+                descending: false
+            })
+        );
+        assert_eq!(
+            sort("mixed:desc"),
+            Ok(Sort {
+                key: SortKey::Mixed,
+                descending: true
+            })
+        );
+        assert_eq!(sort("size:asc").map(|sort| sort.descending), Ok(false));
+        assert!(sort("bytes")
+            .unwrap_err()
+            .starts_with("Unknown --sort 'bytes'"));
+        assert_eq!(
+            sort("crlf:down").unwrap_err(),
+            "Unknown --sort order 'down': expected asc or desc"
+        );
+        assert!(
+            parse_args(&args(&["mdlt", "--sort", "crlf:desc", "a", "b"]))
+                .unwrap()
+                .sort
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_parse_no_progress() {
+        assert!(parse_args(&args(&["mdlt", "a"])).unwrap().progress);
+        assert!(
+            !parse_args(&args(&["mdlt", "--no-progress", "a"]))
+                .unwrap()
+                .progress
+        );
+    }
+
+    #[test]
+    fn test_parse_unicode_linebreaks() {
+        let scan = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan;
+        assert!(!scan(&["mdlt", "a"]).unicode_linebreaks);
+        assert!(scan(&["mdlt", "--unicode-linebreaks", "a"]).unicode_linebreaks);
+    }
+
+    #[test]
+    fn test_parse_encoding() {
+        let scan = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan;
+        assert_eq!(scan(&["mdlt", "a"]).encoding, None);
+        assert_eq!(
+            scan(&["mdlt", "--encoding", "utf-16le", "a"]).encoding,
+            Some(Encoding::Utf16Le)
+        );
+        assert!(parse_args(&args(&["mdlt", "--encoding=ebcdic", "a"]))
+            .unwrap_err()
+            .starts_with("Unknown encoding 'ebcdic'"));
+    }
+
+    #[test]
+    fn test_parse_strict_encoding_implies_check() {
+        let options = parse_args(&args(&["mdlt", "--strict-encoding", "a"])).unwrap();
+        assert!(options.scan.check_encoding);
+        assert!(options.strict_encoding);
+        let options = parse_args(&args(&["mdlt", "--check-encoding", "a"])).unwrap();
+        assert!(options.scan.check_encoding);
+        assert!(!options.strict_encoding);
+    }
+
+    #[test]
+    fn test_parse_control_char_flags() {
+        let options = parse_args(&args(&["mdlt", "a"])).unwrap();
+        assert!(!options.scan.allow_form_feed);
+        assert!(!options.fail_on_control_chars);
+        assert!(!options.fail_on_non_ascii);
+        assert!(!options.fail_on_embedded_cr);
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--allow-form-feed",
+            "--fail-on-control-chars",
+            "--fail-on-non-ascii",
+            "--fail-on-embedded-cr",
+            "a",
+        ]))
+        .unwrap();
+        assert!(options.scan.allow_form_feed);
+        assert!(options.fail_on_control_chars);
+        assert!(options.fail_on_non_ascii);
+        assert!(options.fail_on_embedded_cr);
+    }
+
+    #[test]
+    fn test_parse_fail_on_unstable() {
+        assert!(!parse_args(&args(&["mdlt", "a"])).unwrap().fail_on_unstable);
+        let options = parse_args(&args(&["mdlt", "--fail-on-unstable", "a"])).unwrap();
+        assert!(options.fail_on_unstable);
+    }
+
+    #[test]
+    fn test_parse_blank_as_empty() {
+        let options = parse_args(&args(&["mdlt", "a"])).unwrap();
+        assert!(!options.scan.blank_as_empty);
+        let options = parse_args(&args(&["mdlt", "--blank-as-empty", "a"])).unwrap();
+        assert!(options.scan.blank_as_empty);
+    }
+
+    #[test]
+    fn test_parse_markdown_flags() {
+        let options = parse_args(&args(&["mdlt", "a"])).unwrap();
+        assert!(!options.scan.markdown && !options.scan.skip_fenced_code);
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--lang",
+            "markdown",
+            "--skip-fenced-code",
+            "a",
+        ]))
+        .unwrap();
+        assert!(options.scan.markdown && options.scan.skip_fenced_code);
+        assert!(
+            parse_args(&args(&["mdlt", "--lang=md", "a"]))
+                .unwrap()
+                .scan
+                .markdown
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--lang", "rust", "a"])).map(|_| ()),
+            Err("Unknown --lang 'rust': expected markdown".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_types() {
+        let options = parse_args(&args(&["mdlt", "a"])).unwrap();
+        assert!(options.scan.types.names.is_empty());
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--type-name",
+            "Jenkinsfile=Groovy,BUILD=starlark",
+            "--type-name=BUILD=bazel",
+            "--type-interpreter",
+            "deno=ts",
+            "a",
+        ]))
+        .unwrap();
+        let pairs = |types: &[(String, String)]| {
+            types
+                .iter()
+                .map(|(name, kind)| format!("{}={}", name, kind))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            pairs(options.scan.types.names),
+            ["Jenkinsfile=groovy", "BUILD=bazel"]
+        );
+        assert_eq!(pairs(options.scan.types.interpreters), ["deno=ts"]);
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--type-name", "Makefile", "a"])).map(|_| ()),
+            Err("--type-name: expected name=type, got 'Makefile'".to_string())
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--type-interpreter=deno=", "a"])).map(|_| ()),
+            Err("--type-interpreter: expected name=type, got 'deno='".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_duplicates() {
+        let duplicates = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.duplicates;
+        assert!(!duplicates(&["mdlt", "a"]));
+        assert!(duplicates(&["mdlt", "--duplicates", "a"]));
+    }
+
+    #[test]
+    fn test_parse_hash() {
+        let hash = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.hash;
+        assert!(!hash(&["mdlt", "a"]));
+        assert!(hash(&["mdlt", "--hash", "a"]));
+    }
+
+    #[test]
+    fn test_parse_convert() {
+        let convert = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.convert);
+        assert_eq!(convert(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            convert(&["mdlt", "--convert", "lf", "a"]),
+            Ok(Some(Conversion::To(ConvertTo::Lf)))
+        );
+        assert_eq!(
+            convert(&["mdlt", "--convert=crlf", "a"]),
+            Ok(Some(Conversion::To(ConvertTo::Crlf)))
+        );
+        assert_eq!(
+            convert(&["mdlt", "--convert", "auto", "a"]),
+            Ok(Some(Conversion::Auto))
+        );
+        assert_eq!(
+            convert(&["mdlt", "--convert=cr", "a"]),
+            Err("Unknown --convert value 'cr': expected lf, crlf or auto".to_string())
+        );
+        let options = parse_args(&args(&["mdlt", "--convert=lf", "--force", "-q", "-"])).unwrap();
+        assert!(options.force && options.quiet == 1);
+    }
+
+    #[test]
+    fn test_parse_fix() {
+        let fixes = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.fixes);
+        assert_eq!(fixes(&["mdlt", "a"]), Ok(Vec::new()));
+        assert_eq!(
+            fixes(&[
+                "mdlt",
+                "--fix",
+                "trailing-whitespace",
+                "--fix=final-newline,trailing-whitespace",
+                "--fix",
+                "strip-bom",
+                "a"
+            ]),
+            Ok(vec![
+                Fix::TrailingWhitespace,
+                Fix::FinalNewline,
+                Fix::StripBom
+            ])
+        );
+        assert_eq!(
+            fixes(&["mdlt", "--fix=tabs", "a"]),
+            Err(
+                "Unknown --fix 'tabs': expected trailing-whitespace, final-newline, strip-bom, retab=spaces:N|tabs:N or squeeze-blank[=N]"
+                    .to_string()
+            )
+        );
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--fix=trailing-whitespace",
+            "--dry-run",
+            "a",
+        ]));
+        assert!(options.unwrap().rewrites());
+        let retab = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.retab());
+        assert_eq!(
+            retab(&["mdlt", "--fix=retab=tabs:4,retab=tabs:4", "a"]),
+            Ok(Some(Retab::Tabs(4)))
+        );
+        assert_eq!(
+            retab(&["mdlt", "--fix", "retab=spaces:2", "a"]),
+            Ok(Some(Retab::Spaces(2)))
+        );
+        assert_eq!(retab(&["mdlt", "--fix=strip-bom", "a"]), Ok(None));
+        for bad in [
+            "retab=spaces:0",
+            "retab=tabs",
+            "retab=blanks:4",
+            "retab=tabs:x",
+        ] {
+            assert!(
+                retab(&["mdlt", "--fix", bad, "a"])
+                    .unwrap_err()
+                    .starts_with("--fix retab expects spaces:N or tabs:N"),
+                "{}",
+                bad
+            );
+        }
+        assert_eq!(
+            retab(&["mdlt", "--fix=retab=tabs:4", "--fix=retab=spaces:4", "a"]),
+            Err("--fix retab can only be given once".to_string())
+        );
+        let squeeze =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.squeeze_blank());
+        assert_eq!(squeeze(&["mdlt", "--fix=squeeze-blank", "a"]), Ok(Some(1)));
+        assert_eq!(
+            squeeze(&["mdlt", "--fix", "squeeze-blank=0,squeeze-blank=0", "a"]),
+            Ok(Some(0))
+        );
+        assert_eq!(squeeze(&["mdlt", "--fix=strip-bom", "a"]), Ok(None));
+        assert_eq!(
+            squeeze(&["mdlt", "--fix=squeeze-blank=-1", "a"]),
+            Err("--fix squeeze-blank expects a non-negative integer, got '-1'".to_string())
+        );
+        assert_eq!(
+            squeeze(&["mdlt", "--fix=squeeze-blank=2,squeeze-blank", "a"]),
+            Err("--fix squeeze-blank can only be given once".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_dry_run() {
+        let options = parse_args(&args(&["mdlt", "--convert=lf", "--dry-run", "a"])).unwrap();
+        assert!(options.dry_run);
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--dry-run", "a"])).map(|options| options.dry_run),
+            Err("--dry-run needs a flag that changes files, such as --convert".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_interactive() {
+        let interactive =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.interactive);
+        assert_eq!(interactive(&["mdlt", "--convert=lf", "a"]), Ok(false));
+        assert_eq!(
+            interactive(&["mdlt", "--convert=lf", "-r", ".", "--interactive"]),
+            Ok(true)
+        );
+        assert_eq!(
+            interactive(&["mdlt", "--interactive", "a"]),
+            Err(
+                "--interactive asks before changing files and needs a flag that does, such as --convert"
+                    .to_string()
+            )
+        );
+        assert!(interactive(&[
+            "mdlt",
+            "--fix=final-newline",
+            "--interactive",
+            "--dry-run",
+            "a"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_backup() {
+        let backup = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.backup);
+        assert_eq!(backup(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            backup(&["mdlt", "--backup", "a"]),
+            Ok(Some(".bak".to_string()))
+        );
+        assert_eq!(
+            backup(&["mdlt", "--backup=~", "a"]),
+            Ok(Some("~".to_string()))
+        );
+        assert_eq!(backup(&["mdlt", "--backup", "--no-backup", "a"]), Ok(None));
+        assert!(backup(&["mdlt", "--backup=", "a"]).is_err());
+        let options = parse_args(&args(&["mdlt", "--backup-overwrite", "a"])).unwrap();
+        assert!(options.backup_overwrite && !options.preserve_mtime);
+        assert!(
+            parse_args(&args(&["mdlt", "--preserve-mtime", "a"]))
+                .unwrap()
+                .preserve_mtime
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let metadata = |argv: &[&str]| parse_args(&args(argv)).unwrap().metadata;
+        assert!(!metadata(&["mdlt", "a"]));
+        assert!(metadata(&["mdlt", "--metadata", "a"]));
+    }
+
+    #[test]
+    fn test_parse_transitions() {
+        let transitions = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.transitions;
+        assert!(!transitions(&["mdlt", "a"]));
+        assert!(transitions(&["mdlt", "--transitions", "a"]));
+    }
+
+    #[test]
+    fn test_parse_locate() {
+        let locate = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.locate;
+        assert_eq!(locate(&["mdlt", "a"]), None);
+        assert_eq!(
+            locate(&["mdlt", "--locate", "a"]),
+            Some(DEFAULT_LISTED_LINES)
+        );
+        assert_eq!(
+            locate(&["mdlt", "--locate", "--max-listed-lines", "3", "a"]),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn test_parse_show_empty() {
+        let show_empty = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.show_empty;
+        assert_eq!(show_empty(&["mdlt", "a"]), None);
+        assert_eq!(
+            show_empty(&["mdlt", "--show-empty", "a"]),
+            Some(DEFAULT_LISTED_LINES)
+        );
+        assert_eq!(
+            show_empty(&["mdlt", "--max-listed-lines=500", "--show-empty", "a"]),
+            Some(500)
+        );
+    }
+
+    #[test]
+    fn test_parse_detect_encoding() {
+        let parse =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.detect_encoding);
+        assert_eq!(parse(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            parse(&["mdlt", "--detect-encoding", "a"]),
+            Ok(Some(DetectEncoding::Report))
+        );
+        assert_eq!(
+            parse(&["mdlt", "--detect-encoding=apply", "a"]),
+            Ok(Some(DetectEncoding::Apply))
+        );
+        assert_eq!(
+            parse(&["mdlt", "--detect-encoding=guess", "a"]),
+            Err("Unknown --detect-encoding mode 'guess': expected report or apply".to_string())
+        );
+        assert_eq!(
+            parse(&["mdlt", "--detect-encoding", "--encoding=utf-16le", "a"]),
+            Err("--detect-encoding guesses what --encoding gives; pass one of them".to_string())
+        );
+        assert!(parse(&["mdlt", "--detect-encoding", "--convert=lf", "a"]).is_ok());
+        assert!(parse(&[
+            "mdlt",
+            "--detect-encoding=apply",
+            "--fix=final-newline",
+            "a"
+        ])
+        .is_err());
+        assert_eq!(
+            parse(&["mdlt", "--detect-encoding=apply", "--lines=2..", "a"]),
+            Err("--lines counts single-byte line endings and cannot read UTF-16".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_histogram() {
+        let histogram = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.histogram;
+        assert_eq!(histogram(&["mdlt", "a"]), None);
+        assert_eq!(
+            histogram(&["mdlt", "--histogram", "a"]),
+            Some(Buckets::default())
+        );
+        let custom = histogram(&["mdlt", "--histogram-buckets=40,80", "--histogram", "a"]);
+        assert_eq!(custom.unwrap().bounds(), [40, 80]);
+        assert!(parse_args(&args(&["mdlt", "--histogram-buckets", "80,40", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_max_line_length() {
+        let limit = |argv: &[&str]| parse_args(&args(argv)).unwrap().scan.line_limit;
+        assert_eq!(limit(&["mdlt", "--max-listed-lines", "3", "a"]), None);
+        assert_eq!(
+            limit(&["mdlt", "--max-line-length", "120", "a"]),
+            Some(LineLimit {
+                max_columns: 120,
+                listed: DEFAULT_LISTED_LINES
+            })
+        );
+        assert_eq!(
+            limit(&["mdlt", "--max-listed-lines=3", "--max-line-length=80", "a"]),
+            Some(LineLimit {
+                max_columns: 80,
+                listed: 3
+            })
+        );
+        assert!(!parse_args(&args(&["mdlt", "a"])).unwrap().no_fail);
+        assert!(
+            parse_args(&args(&["mdlt", "--no-fail", "a"]))
+                .unwrap()
+                .no_fail
+        );
+    }
+
+    #[test]
+    fn test_parse_fail_on_mixed_indentation() {
+        let fail = |argv: &[&str]| parse_args(&args(argv)).unwrap().fail_on_mixed_indentation;
+        assert!(!fail(&["mdlt", "a"]));
+        assert!(fail(&["mdlt", "--fail-on-mixed-indentation", "a"]));
+    }
+
+    #[test]
+    fn test_parse_fail_on_mixed() {
+        let fail = |argv: &[&str]| parse_args(&args(argv)).unwrap().fail_on_mixed;
+        assert!(!fail(&["mdlt", "--fail-on-mixed-indentation", "a"]));
+        assert!(fail(&["mdlt", "--fail-on-mixed", "a"]));
+    }
+
+    #[test]
+    fn test_parse_checks() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&[
+            "mdlt",
+            "--checks=none",
+            "--enable",
+            "trailing-whitespace,bom",
+            "a",
+        ])
+        .unwrap();
+        assert!(options.fail_on_trailing_whitespace && options.fail_on_bom);
+        assert!(!options.fail_on_long_lines);
+        let options = parse(&["mdlt", "--checks", "all", "--disable=encoding", "a"]).unwrap();
+        assert!(options.fail_on_non_ascii && options.fail_on_mixed);
+        // Turning encoding off leaves the report of it on.
+        assert!(!options.strict_encoding && options.scan.check_encoding);
+        // The flags are taken in order.
+        let options = parse(&["mdlt", "--fail-on-mixed", "--disable", "mixed-endings", "a"]);
+        assert!(!options.unwrap().fail_on_mixed);
+        let options = parse(&["mdlt", "--checks=all", "--checks=default", "a"]).unwrap();
+        assert!(!options.fail_on_mixed && options.fail_on_long_lines);
+        assert_eq!(
+            parse(&["mdlt", "--enable", "line-length", "a"]).unwrap_err(),
+            "--enable line-length checks what --max-line-length sets; give it too"
+        );
+        assert!(parse(&["mdlt", "--enable=line-length", "--max-line-length=80", "a"]).is_ok());
+        // All checks are on without the setting some of them need.
+        assert!(parse(&["mdlt", "--checks=all", "a"]).is_ok());
+        assert!(parse(&["mdlt", "--enable=bogus", "a"])
+            .unwrap_err()
+            .starts_with("--enable: unknown check 'bogus': expected encoding, control-chars,"));
+        assert_eq!(
+            parse(&["mdlt", "--checks=some", "a"]).unwrap_err(),
+            "Unknown --checks value 'some': expected all, none or default"
+        );
+        assert!(parse(&["mdlt", "--list-checks"]).unwrap().list_checks);
+    }
+
+    #[test]
+    fn test_parse_severity() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&[
+            "mdlt",
+            "--severity",
+            "trailing-whitespace=warning,bom=info",
+            "--severity=bom=error",
+            "a",
+        ])
+        .unwrap();
+        assert_eq!(
+            options.severities,
+            [
+                ("trailing-whitespace".to_string(), Severity::Warning),
+                ("bom".to_string(), Severity::Error)
+            ]
+        );
+        assert!(!options.warnings_as_errors);
+        assert!(
+            parse(&["mdlt", "--warnings-as-errors", "a"])
+                .unwrap()
+                .warnings_as_errors
+        );
+        assert!(
+            parse(&["mdlt", "--show-suppressed", "a"])
+                .unwrap()
+                .report
+                .show_suppressed
+        );
+        assert_eq!(
+            parse(&["mdlt", "--severity=bom", "a"]).unwrap_err(),
+            "--severity: expected <check>=<level>, not 'bom'"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--severity=bom=fatal", "a"]).unwrap_err(),
+            "--severity bom: Unknown severity 'fatal': expected error, warning or info"
+        );
+        assert!(parse(&["mdlt", "--severity=tabs=info", "a"])
+            .unwrap_err()
+            .starts_with("--severity: unknown check 'tabs': expected encoding,"));
+    }
+
+    #[test]
+    fn test_parse_external_check() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&["mdlt", "a"]).unwrap();
+        assert!(options.external_checks.is_empty());
+        assert_eq!(options.external_check_timeout, external::DEFAULT_TIMEOUT);
+        // `--severity` may name an external check given after it.
+        let options = parse(&[
+            "mdlt",
+            "--severity=tabs=warning",
+            "--external-check",
+            "tabs:grep -qv '\t' {stdin}",
+            "--external-check-timeout=2.5",
+            "a",
+        ])
+        .unwrap();
+        assert_eq!(
+            options.external_checks,
+            [ExternalCheck {
+                name: "tabs".to_string(),
+                command: "grep -qv '\t' {stdin}".to_string(),
+            }]
+        );
+        assert_eq!(
+            options.severities,
+            [("tabs".to_string(), Severity::Warning)]
+        );
+        assert_eq!(options.external_check_timeout, Duration::from_millis(2500));
+        assert_eq!(
+            parse(&[
+                "mdlt",
+                "--external-check=tabs:a",
+                "--external-check=tabs:b",
+                "a"
+            ])
+            .unwrap_err(),
+            "--external-check: 'tabs' is given twice"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--external-check-timeout=5", "a"]).unwrap_err(),
+            "--external-check-timeout limits --external-check and needs it"
+        );
+        assert_eq!(
+            parse(&[
+                "mdlt",
+                "--external-check=tabs:a",
+                "--external-check-timeout=-1",
+                "a"
+            ])
+            .unwrap_err(),
+            "--external-check-timeout expects a positive number of seconds, got '-1'"
+        );
+    }
+
+    #[test]
+    fn test_parse_budgets() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&["mdlt", "a"]).unwrap();
+        assert_eq!(
+            (options.max_problem_files, options.max_problems),
+            (None, None)
+        );
+        let options =
+            parse(&["mdlt", "--max-problem-files", "25", "--max-problems=0", "a"]).unwrap();
+        assert_eq!(
+            (options.max_problem_files, options.max_problems),
+            (Some(25), Some(0))
+        );
+        assert_eq!(
+            parse(&["mdlt", "--max-problems=-1", "a"]).unwrap_err(),
+            "--max-problems expects a non-negative integer, got '-1'"
+        );
+    }
+
+    #[test]
+    fn test_parse_baseline() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options = parse(&["mdlt", "--baseline", "b.json", "--update-baseline", "a"]).unwrap();
+        assert_eq!(options.baseline.as_deref(), Some("b.json"));
+        assert!(options.update_baseline);
+        assert!(
+            !parse(&["mdlt", "--baseline=b.json", "a"])
+                .unwrap()
+                .update_baseline
+        );
+        assert_eq!(
+            parse(&["mdlt", "--update-baseline", "a"]).unwrap_err(),
+            "--update-baseline writes the --baseline file and needs --baseline"
+        );
+        assert!(
+            parse(&["mdlt", "--baseline=b.json", "--max-problems=3", "a"])
+                .unwrap_err()
+                .starts_with("--baseline fails on new problems only and cannot be combined")
+        );
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let options = parse_args(&args(&[
+            "mdlt",
+            "--list=crlf,none",
+            "--list",
+            "crlf",
+            "--print0",
+            "a",
+        ]))
+        .unwrap();
+        assert_eq!(
+            options.list,
+            [ListPredicate::Crlf, ListPredicate::NoEndings]
+        );
+        assert!(options.print0);
+        assert!(parse_args(&args(&["mdlt", "--list=tabs", "a"]))
+            .unwrap_err()
+            .starts_with("Unknown --list 'tabs'"));
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--print0", "a"])).unwrap_err(),
+            "--print0 needs --list or --only-problems"
+        );
+        assert!(parse_args(&args(&["mdlt", "--list=lf", "--convert=lf", "a"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert!(parse_args(&args(&["mdlt", "--help"])).unwrap().help);
+        assert!(
+            parse_args(&args(&["mdlt", "a", "-h", "--bogus"]))
+                .unwrap()
+                .help
+        );
+        assert!(!parse_args(&args(&["mdlt", "a"])).unwrap().help);
+        assert!(help("mdlt").contains("\n  3  files or the report could not be read"));
+        assert!(help("mdlt").contains("\nChecks:\n  --check lf|crlf|cr|consistent "));
+    }
+
+    #[test]
+    fn test_help_and_usage_name_every_flag() {
+        let help = help("mdlt");
+        let split = |text: &str| -> Vec<String> {
+            text.split([' ', ',', '|', '[', ']', '=', '\n'])
+                .map(str::to_string)
+                .collect()
+        };
+        let (helped, used) = (split(&help), split(&usage("mdlt")));
+        let mut names = Vec::new();
+        for flag in flags() {
+            assert!(helped.iter().any(|word| word == flag.name), "{}", flag.name);
+            assert!(used.iter().any(|word| word == flag.name), "{}", flag.name);
+            assert!(!names.contains(&flag.name), "{} twice", flag.name);
+            names.push(flag.name);
+        }
+    }
+
+    #[test]
+    fn test_parse_completions_args() {
+        let parse = |rest: &[&str]| {
+            let mut arguments = vec!["mdlt", "completions"];
+            arguments.extend(rest);
+            parse_completions_args(&args(&arguments))
+        };
+        assert_eq!(parse(&["zsh"]).unwrap().shell, Some(Shell::Zsh));
+        assert_eq!(parse(&["pwsh"]).unwrap().shell, Some(Shell::Powershell));
+        assert!(parse(&["--help"]).unwrap().help);
+        assert!(parse(&[])
+            .unwrap_err()
+            .starts_with("Usage: mdlt completions"));
+        assert!(parse(&["bash", "zsh"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_version_and_double_dash() {
+        assert!(parse_args(&args(&["mdlt", "-V"])).unwrap().version);
+        assert!(
+            parse_args(&args(&["mdlt", "a", "--version"]))
+                .unwrap()
+                .version
+        );
+        let options = parse_args(&args(&["mdlt", "-r", "--", "--check", "-v", "-"])).unwrap();
+        assert_eq!(options.paths, ["--check", "-v", "-"]);
+        assert!(options.recursive);
+        assert_eq!(options.verbose, 0);
+        assert!(parse_args(&args(&["mdlt", "-x", "a"]))
+            .unwrap_err()
+            .starts_with("Unknown option -x\n"));
+    }
+
+    #[test]
+    fn test_parse_diff_args() {
+        let parse = |args: &[&str]| {
+            let args: Vec<String> = ["mdlt", "diff"]
+                .iter()
+                .chain(args)
+                .map(|arg| arg.to_string())
+                .collect();
+            parse_diff_args(&args)
+        };
+        assert_eq!(
+            parse(&["old.json", "--format=json", "new.json", "--fail-on", "any"]),
+            Ok(DiffOptions {
+                help: false,
+                baseline: "old.json".to_string(),
+                current: "new.json".to_string(),
+                format: OutputFormat::Json,
+                fail_on: FailOn::Any,
+            })
+        );
+        assert_eq!(
+            parse(&["old.json", "new.json"]).unwrap().fail_on,
+            FailOn::Regression
+        );
+        assert!(parse(&["--help"]).unwrap().help);
+        assert_eq!(
+            parse(&["--format=csv", "old.json", "new.json"]),
+            Err("mdlt diff writes text or json, not csv".to_string())
+        );
+        assert_eq!(
+            parse(&["--fail-on=some", "old.json", "new.json"]),
+            Err("Unknown --fail-on 'some': expected any, regression or none".to_string())
+        );
+        assert_eq!(parse(&["old.json"]), Err(diff_usage("mdlt")));
+        assert_eq!(parse(&["a", "b", "c"]), Err(diff_usage("mdlt")));
+        assert!(parse(&["-r", "a", "b"])
+            .unwrap_err()
+            .starts_with("Unknown option -r\n"));
+    }
+
+    #[test]
+    fn test_parse_compare_args() {
+        let parse = |args: &[&str]| {
+            let args: Vec<String> = ["mdlt", "compare"]
+                .iter()
+                .chain(args)
+                .map(|arg| arg.to_string())
+                .collect();
+            parse_compare_args(&args)
+        };
+        assert_eq!(
+            parse(&[
+                "a.txt",
+                "--ignore=final-newline,bom",
+                "-",
+                "--ignore",
+                "size"
+            ]),
+            Ok(CompareOptions {
+                help: false,
+                first: "a.txt".to_string(),
+                second: "-".to_string(),
+                format: OutputFormat::Text,
+                ignore: vec![
+                    "final_newline",
+                    "bom",
+                    "total_bytes",
+                    "total_chars",
+                    "total_words"
+                ],
+            })
+        );
+        assert_eq!(
+            parse(&["--format=json", "a", "b"]).unwrap().format,
+            OutputFormat::Json
+        );
+        assert_eq!(
+            parse(&["--format=md", "a", "b"]),
+            Err("mdlt compare writes text or json, not markdown".to_string())
+        );
+        assert!(parse(&["--ignore=colour", "a", "b"])
+            .unwrap_err()
+            .starts_with("Unknown --ignore 'colour'"));
+        assert_eq!(
+            parse(&["-", "-"]),
+            Err("mdlt compare can read only one of the files from stdin".to_string())
+        );
+        assert_eq!(parse(&["a"]), Err(compare_usage("mdlt")));
+    }
+
+    #[test]
+    fn test_parse_config_flags() {
+        let config = args(&["--format=json", "--check=lf", "--expect=bat=crlf"]);
+        let parse = |argv: &[&str]| parse_args_with_config(&args(argv), &config);
+        let options = parse(&["mdlt", "a"]).unwrap();
+        assert_eq!(options.format, OutputFormat::Json);
+        assert_eq!(
+            options.expected_endings_for(Some("BAT")),
+            Some(ExpectedEndings::Crlf)
+        );
+        assert_eq!(
+            options.expected_endings_for(Some("rs")),
+            Some(ExpectedEndings::Lf)
+        );
+        let options = parse(&[
+            "mdlt",
+            "--format=csv",
+            "--check=crlf",
+            "--expect",
+            ".BAT=lf",
+            "a",
+        ])
+        .unwrap();
+        assert_eq!(options.format, OutputFormat::Csv);
+        assert_eq!(options.expected_endings, Some(ExpectedEndings::Crlf));
+        assert_eq!(
+            options.expect,
+            vec![("bat".to_string(), Some(ExpectedEndings::Lf))]
+        );
+        assert!(parse(&["mdlt", "--print-config"]).unwrap().print_config);
+        assert_eq!(
+            parse(&["mdlt", "--config=x.toml", "--no-config", "a"]).unwrap_err(),
+            "--config and --no-config cannot be combined"
+        );
+    }
+
+    #[test]
+    fn test_parse_changed() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let changed = |argv: &[&str]| parse(argv).map(|options| options.changed);
+        assert_eq!(changed(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            changed(&["mdlt", "--staged", "--check=lf"]),
+            Ok(Some(Changed::Staged))
+        );
+        assert_eq!(
+            changed(&["mdlt", "--git-modified"]),
+            Ok(Some(Changed::Modified))
+        );
+        assert_eq!(
+            changed(&["mdlt", "--git-diff", "origin/main"]),
+            Ok(Some(Changed::Since("origin/main".to_string())))
+        );
+        assert_eq!(
+            parse(&["mdlt", "--staged", "a"]).unwrap_err(),
+            "--staged picks the files to check and takes no paths"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--git-diff=main", "--files-from=list"]).unwrap_err(),
+            "--git-diff picks the files to check and takes no paths"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--staged", "--git-modified"]).unwrap_err(),
+            "--staged and --git-modified cannot be combined"
+        );
+        assert!(parse(&["mdlt", "--git-modified", "--convert=lf"]).is_ok());
+        assert_eq!(
+            parse(&["mdlt", "--rev", "HEAD~5", "a", "b"]).unwrap().rev,
+            Some("HEAD~5".to_string())
+        );
+        assert_eq!(
+            parse(&["mdlt", "--rev=HEAD", "-r", "src"]).unwrap_err(),
+            "--rev HEAD reads files as committed and cannot be combined with -r"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--rev=HEAD", "--staged"]).unwrap_err(),
+            "--rev HEAD reads files as committed and cannot be combined with --staged"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--rev=HEAD", "--fix=final-newline", "a"]).unwrap_err(),
+            "--rev HEAD reads files as committed and cannot be combined with --convert or --fix"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--staged", "--convert=lf"]).unwrap_err(),
+            "--staged checks what is staged and cannot be combined with --convert or --fix"
+        );
+    }
+
+    #[test]
+    fn test_parse_expect() {
+        let parse = |argv: &[&str]| parse_args(&args(argv));
+        let options =
+            parse(&["mdlt", "--expect", "rs=lf,.PY=lf,bat=crlf,md=any,*=cr", "a"]).unwrap();
+        assert_eq!(
+            options.expected_endings_for(Some("rs")),
+            Some(ExpectedEndings::Lf)
+        );
+        assert_eq!(
+            options.expected_endings_for(Some("py")),
+            Some(ExpectedEndings::Lf)
+        );
+        assert_eq!(
+            options.expected_endings_for(Some("BAT")),
+            Some(ExpectedEndings::Crlf)
+        );
+        assert_eq!(options.expected_endings_for(Some("md")), None);
+        assert_eq!(
+            options.expected_endings_for(Some("txt")),
+            Some(ExpectedEndings::Cr)
+        );
+        assert_eq!(
+            options.expected_endings_for(None),
+            Some(ExpectedEndings::Cr)
+        );
+        // Without a `*` rule, other files get `--check`, or no check at all.
+        let options = parse(&["mdlt", "--check=lf", "--expect=bat=crlf", "a"]).unwrap();
+        assert_eq!(
+            options.expected_endings_for(None),
+            Some(ExpectedEndings::Lf)
+        );
+        let options = parse(&["mdlt", "--expect=bat=crlf", "a"]).unwrap();
+        assert_eq!(options.expected_endings_for(None), None);
+        assert_eq!(options.expected_endings_for(Some("rs")), None);
+
+        let error = |value: &str| parse(&["mdlt", "--expect", value, "a"]).unwrap_err();
+        assert_eq!(error("rs:lf"), "--expect: expected ext=ending, got 'rs:lf'");
+        assert_eq!(error("rs=lf,"), "--expect: expected ext=ending, got ''");
+        assert_eq!(error("=lf"), "--expect: expected ext=ending, got '=lf'");
+        assert_eq!(
+            error("rs=unix"),
+            "--expect: unknown ending 'unix' for rs: expected lf, crlf, cr, consistent or any"
+        );
+        assert_eq!(error("rs=lf,RS=crlf"), "--expect gives rs more than once");
+        assert_eq!(
+            parse(&["mdlt", "--expect=rs=lf", "--expect=rs=lf", "a"]).unwrap_err(),
+            "--expect gives rs more than once"
+        );
+    }
+
+    #[test]
+    fn test_parse_check_mixed_indent() {
+        let mixed =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.mixed_indent);
+        assert_eq!(mixed(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            mixed(&["mdlt", "--check", "mixed-indent", "a"]),
+            Ok(Some(MixedIndent::SpaceBeforeTab))
+        );
+        assert_eq!(
+            mixed(&["mdlt", "--strict-mixed-indent", "--check=mixed-indent", "a"]),
+            Ok(Some(MixedIndent::Any))
+        );
+        assert_eq!(
+            mixed(&["mdlt", "--strict-mixed-indent", "a"]),
+            Ok(Some(MixedIndent::Any))
+        );
+        assert_eq!(
+            mixed(&["mdlt", "--check", "mixed-indent,spelling", "a"]),
+            Err(
+                "Unknown --check 'spelling': expected mixed-indent, lf, crlf, cr, consistent \
+                 or uniform-tree[=lf|crlf|cr]"
+                    .to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_check_endings() {
+        let expected =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.expected_endings);
+        assert_eq!(expected(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            expected(&["mdlt", "--check", "lf", "a"]),
+            Ok(Some(ExpectedEndings::Lf))
+        );
+        assert_eq!(
+            expected(&[
+                "mdlt",
+                "--check=mixed-indent,consistent",
+                "--check=consistent",
+                "a"
+            ]),
+            Ok(Some(ExpectedEndings::Consistent))
+        );
+        assert_eq!(
+            expected(&["mdlt", "--check=crlf", "--check=cr", "a"]),
+            Err("--check crlf and --check cr cannot both hold".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_uniform_tree() {
+        let uniform = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.uniform_tree);
+        assert_eq!(uniform(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            uniform(&[
+                "mdlt",
+                "--check=lf,uniform-tree",
+                "--max-listed-lines=3",
+                "a"
+            ]),
+            Ok(Some(UniformTree {
+                pinned: None,
+                listed: 3
+            }))
+        );
+        assert_eq!(
+            uniform(&["mdlt", "--check", "uniform-tree=crlf", "a"])
+                .unwrap()
+                .unwrap()
+                .pinned,
+            Some(ExpectedEndings::Crlf)
+        );
+        assert_eq!(
+            uniform(&[
+                "mdlt",
+                "--check=uniform-tree",
+                "--check=uniform-tree=lf",
+                "a"
+            ]),
+            Err("--check uniform-tree and --check uniform-tree=lf cannot both hold".to_string())
+        );
+        assert!(uniform(&["mdlt", "--check=uniform-tree=consistent", "a"])
+            .unwrap_err()
+            .starts_with("Unknown --check 'uniform-tree=consistent'"));
+    }
+
+    #[test]
+    fn test_parse_top_lines() {
+        let top = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.top_lines);
+        assert_eq!(top(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            top(&["mdlt", "--top-lines", "3", "a"]),
+            Ok(Some(TopLines {
+                count: 3,
+                columns: false
+            }))
+        );
+        assert_eq!(
+            top(&["mdlt", "--tab-width=4", "--top-lines=3", "a"]),
+            Ok(Some(TopLines {
+                count: 3,
+                columns: true
+            }))
+        );
+        assert_eq!(
+            top(&["mdlt", "--top-lines=0", "a"]),
+            Err("--top-lines expects a positive integer, got '0'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_tab_width() {
+        let width = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.tab_width);
+        assert_eq!(width(&["mdlt", "a"]), Ok(crate::scanner::DEFAULT_TAB_WIDTH));
+        assert_eq!(width(&["mdlt", "--tab-width", "4", "a"]), Ok(4));
+        assert_eq!(
+            width(&["mdlt", "--tab-width=0", "a"]),
+            Err("--tab-width expects a positive integer, got '0'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_mixed_threshold() {
+        let threshold =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.tolerance.percent);
+        assert_eq!(threshold(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            threshold(&["mdlt", "--mixed-threshold", "2.5", "a"]),
+            Ok(Some(2.5))
+        );
+        assert_eq!(
+            threshold(&["mdlt", "--max-minority-percent=0", "a"]),
+            Ok(Some(0.0))
+        );
+        assert_eq!(
+            threshold(&["mdlt", "--max-minority-percent", "-1", "a"]),
+            Err(
+                "--max-minority-percent expects a percentage from 0 to below 50, got '-1'"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            threshold(&["mdlt", "--mixed-threshold=50", "a"]),
+            Err("--mixed-threshold expects a percentage from 0 to below 50, got '50'".to_string())
+        );
+        assert!(threshold(&["mdlt", "--mixed-threshold", "NaN", "a"]).is_err());
+        let endings = parse_args(&args(&["mdlt", "--max-minority-endings=3", "a"])).unwrap();
+        assert_eq!(endings.scan.tolerance.endings, Some(3));
+        assert_eq!(endings.scan.tolerance.percent, None);
+    }
+
+    #[test]
+    fn test_parse_preview() {
+        let preview = |argv: &[&str]| parse_args(&args(argv)).unwrap().report.preview;
+        assert!(!preview(&["mdlt", "a"]));
+        assert!(preview(&["mdlt", "--preview", "a"]));
+    }
+
+    #[test]
+    fn test_parse_peek() {
+        let peek = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.peek);
+        assert_eq!(peek(&["mdlt", "a"]), Ok(None));
+        assert_eq!(peek(&["mdlt", "--peek", "a"]), Ok(Some(80)));
+        assert_eq!(
+            peek(&["mdlt", "--peek-width=40", "--peek", "a"]),
+            Ok(Some(40))
+        );
+        assert_eq!(
+            peek(&["mdlt", "--peek-width", "0", "a"]),
+            Err("--peek-width expects a positive integer, got '0'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_fail_on_trailing_whitespace() {
+        let fail = |argv: &[&str]| parse_args(&args(argv)).unwrap().fail_on_trailing_whitespace;
+        assert!(!fail(&["mdlt", "a"]));
+        assert!(fail(&["mdlt", "--fail-on-trailing-whitespace", "a"]));
+    }
+
+    #[test]
+    fn test_parse_fail_on_missing_final_newline() {
+        let fail = |argv: &[&str]| {
+            parse_args(&args(argv))
+                .unwrap()
+                .fail_on_missing_final_newline
+        };
+        assert!(!fail(&["mdlt", "a"]));
+        assert!(fail(&["mdlt", "--fail-on-missing-final-newline", "a"]));
+    }
+
+    #[test]
+    fn test_parse_max_trailing_blank_lines() {
+        let limit =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.max_trailing_blank_lines);
+        assert_eq!(limit(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            limit(&["mdlt", "--max-trailing-blank-lines", "1", "a"]),
+            Ok(Some(1))
+        );
+        assert_eq!(
+            limit(&["mdlt", "--max-trailing-blank-lines=-1", "a"]),
+            Err("--max-trailing-blank-lines expects a non-negative integer, got '-1'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_max_blank_run() {
+        let limit =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| options.scan.max_blank_run);
+        assert_eq!(limit(&["mdlt", "a"]), Ok(None));
+        assert_eq!(limit(&["mdlt", "--max-blank-run=2", "a"]), Ok(Some(2)));
+        assert_eq!(
+            limit(&["mdlt", "--max-blank-run", "x", "a"]),
+            Err("--max-blank-run expects a non-negative integer, got 'x'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_treat_binary() {
+        let treat = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.treat_binary);
+        assert_eq!(treat(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            treat(&["mdlt", "--treat-binary=analyze", "a"]),
+            Ok(Some(TreatBinary::Analyze))
+        );
+        assert_eq!(
+            treat(&["mdlt", "--treat-binary", "fail", "a"]),
+            Ok(Some(TreatBinary::Fail))
+        );
+        assert_eq!(
+            treat(&["mdlt", "--treat-binary=ignore", "a"]),
+            Err(
+                "Unknown --treat-binary value 'ignore': expected skip, analyze or fail".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_mmap() {
+        let mode = |argv: &[&str]| parse_args(&args(argv)).unwrap().mmap;
+        assert_eq!(mode(&["mdlt", "a"]), MmapMode::Auto);
+        assert_eq!(mode(&["mdlt", "--mmap", "a"]), MmapMode::Always);
+        assert_eq!(mode(&["mdlt", "--mmap", "--no-mmap", "a"]), MmapMode::Never);
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        let timeout = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.timeout);
+        assert_eq!(timeout(&["mdlt", "https://example.com/a"]), Ok(None));
+        assert_eq!(
+            timeout(&["mdlt", "--timeout", "2.5", "https://example.com/a"]),
+            Ok(Some(Duration::from_millis(2500)))
+        );
+        assert_eq!(
+            timeout(&["mdlt", "--timeout=0", "a"]),
+            Err("--timeout expects a positive number of seconds, got '0'".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_cache() {
+        let cache = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.cache);
+        assert_eq!(cache(&["mdlt", "a"]), Ok(None));
+        assert_eq!(
+            cache(&["mdlt", "--cache=.mdlt-cache", "a"]),
+            Ok(Some(".mdlt-cache".to_string()))
+        );
+        assert_eq!(cache(&["mdlt", "--cache=x", "--no-cache", "a"]), Ok(None));
+        assert!(cache(&["mdlt", "--cache=", "a"]).is_err());
+        let options = parse_args(&args(&["mdlt", "--cache-clear"])).unwrap();
+        assert!(options.cache_clear && options.paths.is_empty());
+    }
+
+    #[test]
+    fn test_parse_decompress() {
+        let mode = |argv: &[&str]| parse_args(&args(argv)).map(|options| options.decompress);
+        assert_eq!(mode(&["mdlt", "a.gz"]), Ok(Decompress::Auto));
+        assert_eq!(
+            mode(&["mdlt", "--decompress", "gzip", "a"]),
+            Ok(Decompress::Gzip)
+        );
+        assert_eq!(
+            mode(&["mdlt", "--decompress=none", "a.gz"]),
+            Ok(Decompress::Never)
+        );
+        assert_eq!(
+            mode(&["mdlt", "--decompress", "zstd", "a"]),
+            Err("Unknown --decompress value 'zstd': expected auto, gzip or none".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_jobs() {
+        assert_eq!(
+            parse_args(&args(&["mdlt", "a"])).unwrap().jobs,
+            parallel::default_jobs()
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--jobs", "3", "a"]))
+                .unwrap()
+                .jobs,
+            3
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--jobs=0", "a"])).unwrap_err(),
+            "--jobs expects a positive integer, got '0'"
+        );
+    }
+
+    #[test]
+    fn test_parse_intra_file_jobs() {
+        assert_eq!(
+            parse_args(&args(&["mdlt", "a"])).unwrap().intra_file_jobs,
+            1
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--intra-file-jobs=4", "a"]))
+                .unwrap()
+                .intra_file_jobs,
+            4
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--intra-file-jobs", "none", "a"])).unwrap_err(),
+            "--intra-file-jobs expects a positive integer, got 'none'"
+        );
+    }
+
+    #[test]
+    fn test_parse_sample() {
+        let parse =
+            |argv: &[&str]| parse_args(&args(argv)).map(|options| (options.sample, options.seed));
+        assert_eq!(parse(&["mdlt", "-r", "."]), Ok((None, None)));
+        assert_eq!(
+            parse(&["mdlt", "--sample", "5000", "--seed=42", "-r", "."]),
+            Ok((Some(sample::Size::Count(5000)), Some(42)))
+        );
+        assert_eq!(
+            parse(&["mdlt", "--sample-percent=0.5", "-r", "."]),
+            Ok((Some(sample::Size::Percent(0.5)), None))
+        );
+        assert_eq!(
+            parse(&["mdlt", "--sample", "0", "."]).unwrap_err(),
+            "--sample expects a positive integer, got '0'"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--sample-percent", "101", "."]).unwrap_err(),
+            "--sample-percent expects a percentage above 0 and up to 100, got '101'"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--sample", "5", "--sample-percent", "1", "."]).unwrap_err(),
+            "--sample and --sample-percent cannot be combined"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--seed", "42", "."]).unwrap_err(),
+            "--seed picks the files of --sample or --sample-percent and needs one of them"
+        );
+        assert_eq!(
+            parse(&["mdlt", "--sample", "5", "--convert", "lf", "."]).unwrap_err(),
+            "--sample analyzes files picked at random and cannot be combined with --convert or --fix"
+        );
+    }
+
+    #[test]
+    fn test_parse_timing() {
+        assert!(!parse_args(&args(&["mdlt", "a"])).unwrap().timing);
+        assert!(
+            parse_args(&args(&["mdlt", "--timing", "a"]))
+                .unwrap()
+                .timing
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--timing", "--fix=final-newline", "a"])).unwrap_err(),
+            "--timing times the analysis of a run and cannot be combined with --convert or --fix"
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--timing", "--serve"])).unwrap_err(),
+            "--timing times the analysis of a run and cannot be combined with --serve"
+        );
+    }
+
+    #[test]
+    fn test_parse_buffer_output() {
+        assert!(
+            !parse_args(&args(&["mdlt", "a", "b"]))
+                .unwrap()
+                .buffer_output
+        );
+        assert!(
+            parse_args(&args(&["mdlt", "--buffer-output", "a", "b"]))
+                .unwrap()
+                .buffer_output
+        );
+        assert_eq!(
+            parse_args(&args(&["mdlt", "--buffer-output", "--watch", "a"])).unwrap_err(),
+            "--buffer-output holds back the report of a run and cannot be combined with --watch"
+        );
+    }
+
+    #[test]
+    fn test_parse_requires_a_path() {
+        assert!(parse_args(&args(&["mdlt"]))
+            .unwrap_err()
+            .starts_with("Usage:"));
+        assert!(parse_args(&args(&["mdlt", "--format=html"]))
+            .unwrap_err()
+            .starts_with("Usage:"));
+    }
+
+    #[test]
+    fn test_parse_unknown_option() {
+        let err = parse_args(&args(&["mdlt", "--bogus", "a.txt"])).unwrap_err();
+        assert!(err.starts_with("Unknown option --bogus"));
+    }
+
+    #[test]
+    fn test_flag_value_forms() {
+        let rest = args(&["value"]);
+        assert_eq!(
+            flag_value("--output", "--output", &mut rest.iter()),
+            Some(Ok("value"))
+        );
+        assert_eq!(
+            flag_value("--output=x", "--output", &mut rest.iter()),
+            Some(Ok("x"))
+        );
+        assert_eq!(flag_value("--outputs", "--output", &mut rest.iter()), None);
+        assert_eq!(
+            flag_value("--output", "--output", &mut [].iter()),
+            Some(Err("Missing value for --output".to_string()))
+        );
+    }
+}
@@ -0,0 +1,83 @@
+#[cfg(feature = "cli")]
+use std::env;
+
+#[cfg(feature = "cli")]
+use crate::cli::ColorChoice;
+
+/// SGR parameters for the styles the text report uses.
+pub const BOLD: &str = "1";
+pub const RED: &str = "31";
+pub const GREEN: &str = "32";
+pub const YELLOW: &str = "33";
+pub const CYAN: &str = "36";
+
+/// `text` in `style` when `enabled`, else `text` as it is.
+pub fn paint(text: &str, style: &str, enabled: bool) -> String {
+    match enabled {
+        true => format!("\x1b[{}m{}\x1b[0m", style, text),
+        false => text.to_string(),
+    }
+}
+
+/// Whether to color a report written to a terminal or not: `--color auto`
+/// colors only the first, and a non-empty `NO_COLOR` turns it off unless
+/// `--color always` insists.
+#[cfg(feature = "cli")]
+pub fn enabled(choice: ColorChoice, terminal: bool) -> bool {
+    let wanted = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            terminal && env::var_os("NO_COLOR").is_none_or(|value| value.is_empty())
+        }
+    };
+    wanted && (!terminal || enable_ansi())
+}
+
+/// Turns on escape sequences in the Windows console, which older versions
+/// print as they are; without that the report stays plain.
+#[cfg(all(feature = "cli", windows))]
+fn enable_ansi() -> bool {
+    use std::ffi::c_void;
+    use std::os::windows::io::AsRawHandle;
+
+    const ENABLE_VIRTUAL_TERMINAL_PROCESSING: u32 = 0x0004;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetConsoleMode(handle: *mut c_void, mode: *mut u32) -> i32;
+        fn SetConsoleMode(handle: *mut c_void, mode: u32) -> i32;
+    }
+    let handle = std::io::stdout().as_raw_handle();
+    let mut mode = 0;
+    // SAFETY: the handle is the process's stdout and `mode` outlives both
+    // calls.
+    unsafe {
+        GetConsoleMode(handle, &mut mode) != 0
+            && (mode & ENABLE_VIRTUAL_TERMINAL_PROCESSING != 0
+                || SetConsoleMode(handle, mode | ENABLE_VIRTUAL_TERMINAL_PROCESSING) != 0)
+    }
+}
+
+#[cfg(all(feature = "cli", not(windows)))]
+fn enable_ansi() -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paint() {
+        assert_eq!(paint("mixed", RED, true), "\x1b[31mmixed\x1b[0m");
+        assert_eq!(paint("mixed", RED, false), "mixed");
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_enabled() {
+        assert!(enabled(ColorChoice::Always, false));
+        assert!(!enabled(ColorChoice::Never, true));
+        assert!(!enabled(ColorChoice::Auto, false));
+    }
+}
@@ -0,0 +1,310 @@
+/// How comments are written in a language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Syntax {
+    /// Markers that comment out the rest of the line.
+    pub line: &'static [&'static str],
+    /// Opening and closing markers of a block comment.
+    pub block: Option<(&'static str, &'static str)>,
+}
+
+const C_LIKE: Syntax = Syntax {
+    line: &["//"],
+    block: Some(("/*", "*/")),
+};
+
+const HASH: Syntax = Syntax {
+    line: &["#"],
+    block: None,
+};
+
+const SQL: Syntax = Syntax {
+    line: &["--"],
+    block: Some(("/*", "*/")),
+};
+
+const LUA: Syntax = Syntax {
+    line: &["--"],
+    block: Some(("--[[", "]]")),
+};
+
+const MARKUP: Syntax = Syntax {
+    line: &[],
+    block: Some(("<!--", "-->")),
+};
+
+/// Comment syntax by lowercase file extension; add a row to support another
+/// language.
+const LANGUAGES: &[(&[&str], Syntax)] = &[
+    (
+        &[
+            "rs", "c", "h", "cc", "cpp", "cxx", "hh", "hpp", "js", "mjs", "cjs", "jsx", "ts", "tsx",
+        ],
+        C_LIKE,
+    ),
+    (&["py", "sh", "bash", "toml", "yaml", "yml"], HASH),
+    (&["sql"], SQL),
+    (&["lua"], LUA),
+    (&["html", "htm", "xml", "md"], MARKUP),
+];
+
+/// Comment syntax by the types of files without an extension that are not
+/// named for one; see `filetype`.
+const TYPES: &[(&[&str], Syntax)] = &[
+    (
+        &[
+            "makefile",
+            "dockerfile",
+            "shell",
+            "bash",
+            "zsh",
+            "fish",
+            "python",
+            "ruby",
+            "perl",
+            "r",
+            "tcl",
+            "awk",
+            "powershell",
+        ],
+        HASH,
+    ),
+    (&["groovy", "javascript"], C_LIKE),
+];
+
+impl Syntax {
+    pub fn for_extension(extension: &str) -> Option<Syntax> {
+        let extension = extension.to_ascii_lowercase();
+        LANGUAGES
+            .iter()
+            .find(|(extensions, _)| extensions.contains(&extension.as_str()))
+            .map(|&(_, syntax)| syntax)
+    }
+
+    /// The syntax of a detected type, which may also be an extension, such
+    /// as `ts` for a type `--type-interpreter` gives.
+    pub fn for_type(kind: &str) -> Option<Syntax> {
+        let kind = kind.to_ascii_lowercase();
+        TYPES
+            .iter()
+            .find(|(kinds, _)| kinds.contains(&kind.as_str()))
+            .map(|&(_, syntax)| syntax)
+            .or_else(|| Syntax::for_extension(&kind))
+    }
+}
+
+/// Longest comment marker in `LANGUAGES`.
+const MAX_MARKER: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Marker {
+    Line,
+    Open,
+    Close,
+}
+
+/// Follows comments through a file one character at a time, where a
+/// character is a byte of UTF-8 or a UTF-16 code unit; markers are ASCII so
+/// either works. Characters that might start a marker are held back until
+/// the marker is complete or ruled out, preferring the longest marker.
+/// Markers inside string literals are not told apart from real ones.
+#[derive(Debug)]
+pub struct Tracker {
+    syntax: Syntax,
+    /// Bit `c` is set for each ASCII `c` that starts a marker outside and
+    /// inside block comments, so most characters skip the marker search.
+    starts_outside: u128,
+    starts_inside: u128,
+    pending: [u32; MAX_MARKER],
+    pending_len: usize,
+    in_block: bool,
+    /// The rest of the current line is commented out.
+    in_line_comment: bool,
+    /// The current line has text outside comments.
+    saw_code: bool,
+    /// The current line has a comment, or started inside one.
+    saw_comment: bool,
+}
+
+impl Tracker {
+    pub fn new(syntax: Syntax) -> Self {
+        let bit = |marker: &str| 1u128 << marker.as_bytes()[0];
+        let mut starts_outside = syntax
+            .line
+            .iter()
+            .fold(0, |bits, marker| bits | bit(marker));
+        let mut starts_inside = 0;
+        if let Some((open, close)) = syntax.block {
+            starts_outside |= bit(open);
+            starts_inside = bit(close);
+        }
+        Tracker {
+            syntax,
+            starts_outside,
+            starts_inside,
+            pending: [0; MAX_MARKER],
+            pending_len: 0,
+            in_block: false,
+            in_line_comment: false,
+            saw_code: false,
+            saw_comment: false,
+        }
+    }
+
+    /// Feeds the next character of the current line.
+    pub fn push(&mut self, c: u32) {
+        if self.in_line_comment {
+            return;
+        }
+        if self.pending_len == 0 && !self.could_start_marker(c) {
+            self.plain(c);
+            return;
+        }
+        self.pending[self.pending_len] = c;
+        self.pending_len += 1;
+        while self.pending_len > 0 {
+            let pending = &self.pending[..self.pending_len];
+            if self
+                .markers()
+                .any(|(marker, _)| marker.len() > pending.len() && matches(marker, pending))
+            {
+                return;
+            }
+            self.resolve();
+        }
+    }
+
+    /// Finishes the current line, which has text other than spaces and tabs
+    /// if `has_text`, and says whether it is a comment line: one with
+    /// comment text and nothing outside comments.
+    pub fn end_line(&mut self, has_text: bool) -> bool {
+        while self.pending_len > 0 {
+            self.resolve();
+        }
+        let comment = has_text && self.saw_comment && !self.saw_code;
+        self.in_line_comment = false;
+        self.saw_code = false;
+        self.saw_comment = self.in_block;
+        comment
+    }
+
+    /// Applies the longest marker the held characters start with, or lets
+    /// the first of them through as an ordinary character.
+    fn resolve(&mut self) {
+        let pending = &self.pending[..self.pending_len];
+        let found = self
+            .markers()
+            .filter(|(marker, _)| marker.len() <= pending.len() && matches(marker, pending))
+            .max_by_key(|(marker, _)| marker.len());
+        let used = match found {
+            Some((marker, kind)) => {
+                self.apply(kind);
+                marker.len()
+            }
+            None => {
+                self.plain(self.pending[0]);
+                1
+            }
+        };
+        self.pending.copy_within(used..self.pending_len, 0);
+        self.pending_len -= used;
+        if self.in_line_comment {
+            self.pending_len = 0;
+        }
+    }
+
+    fn could_start_marker(&self, c: u32) -> bool {
+        let starts = match self.in_block {
+            true => self.starts_inside,
+            false => self.starts_outside,
+        };
+        c < 128 && starts & (1 << c) != 0
+    }
+
+    /// The markers that mean something in the current state.
+    fn markers(&self) -> impl Iterator<Item = (&'static [u8], Marker)> {
+        let (line, block): (&[&str], _) = match self.syntax.block {
+            Some((_, close)) if self.in_block => (&[], Some((close, Marker::Close))),
+            Some((open, _)) => (self.syntax.line, Some((open, Marker::Open))),
+            None => (self.syntax.line, None),
+        };
+        line.iter()
+            .map(|marker| (marker.as_bytes(), Marker::Line))
+            .chain(block.map(|(marker, kind)| (marker.as_bytes(), kind)))
+    }
+
+    fn apply(&mut self, marker: Marker) {
+        match marker {
+            Marker::Line => self.in_line_comment = true,
+            Marker::Open => self.in_block = true,
+            Marker::Close => self.in_block = false,
+        }
+        self.saw_comment = true;
+    }
+
+    fn plain(&mut self, c: u32) {
+        if !self.in_block && c != u32::from(b' ') && c != u32::from(b'\t') {
+            self.saw_code = true;
+        }
+    }
+}
+
+/// Whether `marker` and `chars` agree as far as both go.
+fn matches(marker: &[u8], chars: &[u32]) -> bool {
+    marker.iter().zip(chars).all(|(&m, &c)| u32::from(m) == c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns which lines of `text` are comment lines.
+    fn comments(extension: &str, text: &str) -> Vec<bool> {
+        let mut tracker = Tracker::new(Syntax::for_extension(extension).unwrap());
+        text.lines()
+            .map(|line| {
+                for byte in line.bytes() {
+                    tracker.push(u32::from(byte));
+                }
+                tracker.end_line(!line.trim().is_empty())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_extension_lookup() {
+        assert_eq!(Syntax::for_extension("rs"), Some(C_LIKE));
+        assert_eq!(Syntax::for_extension("PY"), Some(HASH));
+        assert_eq!(Syntax::for_extension("txt"), None);
+        assert_eq!(Syntax::for_type("makefile"), Some(HASH));
+        assert_eq!(Syntax::for_type("ts"), Some(C_LIKE));
+        assert_eq!(Syntax::for_type("license"), None);
+    }
+
+    #[test]
+    fn test_line_comments() {
+        let text = "// doc\n  // indented\nlet x = 1; // trailing\n\n/ not\nx / 2";
+        assert_eq!(
+            comments("rs", text),
+            [true, true, false, false, false, false]
+        );
+        assert_eq!(comments("py", "# a\nx = 1 # b\n  #\n"), [true, false, true]);
+        assert_eq!(comments("sql", "-- a\nselect 1 - 2;\n"), [true, false]);
+    }
+
+    #[test]
+    fn test_block_comments() {
+        let text = "/* one */\n/*\n * two\n\n */ x();\ny(); /* z */\n/**/ /* */";
+        assert_eq!(
+            comments("c", text),
+            [true, true, true, false, false, false, true]
+        );
+        let text = "<p>\n<!-- a\n- b -> c\n-- -->\n<!-- x --> <br>";
+        assert_eq!(comments("html", text), [false, true, true, true, false]);
+    }
+
+    #[test]
+    fn test_longest_marker_wins() {
+        let text = "--[[ block\nstill ]] \n-- line\n--[ line\nx = 1\n--";
+        assert_eq!(comments("lua", text), [true, true, true, true, false, true]);
+    }
+}
@@ -0,0 +1,319 @@
+use std::io::{self, Write};
+
+use crate::bom::Bom;
+use crate::cli;
+use crate::format::json::{self, string};
+use crate::format::{escape_name, OutputFormat, ReportOptions};
+use crate::mmap::MmapMode;
+use crate::scanner::ScanOptions;
+use crate::{
+    analyze_file_with, analyze_reader_with, final_newline_summary, Exit, FileStats, MdltError,
+};
+
+/// Renders one stat of a file.
+type Stat = fn(&FileStats) -> String;
+
+/// The stats compared, by their key in `--format json` reports, which is
+/// also the name `--ignore` takes.
+const STATS: &[(&str, Stat)] = &[
+    ("total_bytes", |file| file.total_bytes.to_string()),
+    ("total_chars", |file| file.total_chars.to_string()),
+    ("total_words", |file| file.total_words.to_string()),
+    ("total_lines", |file| file.total_lines.to_string()),
+    ("empty_lines", |file| file.empty_lines.to_string()),
+    ("blank_lines", |file| file.blank_lines.to_string()),
+    ("line_ending_type", |file| file.line_ending_type()),
+    ("dos_endings", |file| file.dos_endings.to_string()),
+    ("unix_endings", |file| file.unix_endings.to_string()),
+    ("mac_endings", |file| file.mac_endings.to_string()),
+    ("final_newline", |file| {
+        final_newline_summary(file.final_newline).to_string()
+    }),
+    ("bom", |file| file.bom.map_or("none", Bom::name).to_string()),
+    ("is_binary", |file| file.is_binary.to_string()),
+    ("trailing_whitespace_lines", |file| {
+        file.trailing_whitespace_lines.to_string()
+    }),
+    ("trailing_blank_lines", |file| {
+        file.trailing_blank_lines.to_string()
+    }),
+    ("min_line_length", |file| file.min_line_length.to_string()),
+    ("max_line_length", |file| file.max_line_length.to_string()),
+    ("indent_tab_lines", |file| file.indentation.tabs.to_string()),
+    ("indent_space_lines", |file| {
+        file.indentation.spaces.to_string()
+    }),
+    ("indent_mixed_lines", |file| {
+        file.indentation.mixed.to_string()
+    }),
+    ("nul_bytes", |file| file.nul_bytes.to_string()),
+    ("control_chars", |file| file.control_chars.to_string()),
+    ("embedded_crs", |file| file.embedded_crs.to_string()),
+    ("phantom_blank_lines", |file| {
+        file.phantom_blank_lines.to_string()
+    }),
+    ("non_ascii_chars", |file| file.non_ascii_chars.to_string()),
+];
+
+/// Names `--ignore` takes for several stats at once.
+const GROUPS: &[(&str, &[&str])] = &[
+    (
+        "line-endings",
+        &[
+            "line_ending_type",
+            "dos_endings",
+            "unix_endings",
+            "mac_endings",
+        ],
+    ),
+    ("size", &["total_bytes", "total_chars", "total_words"]),
+    (
+        "whitespace",
+        &[
+            "blank_lines",
+            "trailing_whitespace_lines",
+            "trailing_blank_lines",
+            "indent_tab_lines",
+            "indent_space_lines",
+            "indent_mixed_lines",
+        ],
+    ),
+];
+
+/// The stats an `--ignore` name stands for: a group, or one stat, which
+/// may be spelled with dashes as in `final-newline`.
+pub fn stats_named(name: &str) -> Result<Vec<&'static str>, String> {
+    if let Some((_, stats)) = GROUPS.iter().find(|(group, _)| *group == name) {
+        return Ok(stats.to_vec());
+    }
+    let key = name.replace('-', "_");
+    match STATS.iter().find(|(stat, _)| *stat == key) {
+        Some((stat, _)) => Ok(vec![stat]),
+        None => Err(format!(
+            "Unknown --ignore '{}': expected a stat such as total_lines or \
+             final-newline, or line-endings, size or whitespace",
+            name
+        )),
+    }
+}
+
+/// One stat of the two files.
+#[derive(Debug, PartialEq, Eq)]
+struct Row {
+    name: &'static str,
+    first: String,
+    second: String,
+    ignored: bool,
+}
+
+impl Row {
+    fn differs(&self) -> bool {
+        self.first != self.second
+    }
+}
+
+fn rows(first: &FileStats, second: &FileStats, ignore: &[&str]) -> Vec<Row> {
+    STATS
+        .iter()
+        .map(|&(name, value)| Row {
+            name,
+            first: value(first),
+            second: value(second),
+            ignored: ignore.contains(&name),
+        })
+        .collect()
+}
+
+/// The stats that differ and are not ignored.
+fn differences(rows: &[Row]) -> Vec<&'static str> {
+    rows.iter()
+        .filter(|row| row.differs() && !row.ignored)
+        .map(|row| row.name)
+        .collect()
+}
+
+/// Prints the stats in two columns, marking those that differ with `*`
+/// and noting the ones `--ignore` leaves out of the verdict.
+fn write_text(
+    first: &FileStats,
+    second: &FileStats,
+    rows: &[Row],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let (first_name, second_name) = (escape_name(&first.name()), escape_name(&second.name()));
+    let name_width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0);
+    let first_width = rows
+        .iter()
+        .map(|row| row.first.chars().count())
+        .chain([first_name.chars().count()])
+        .max()
+        .unwrap_or(0);
+    writeln!(
+        writer,
+        "  {:name_width$}  {:first_width$}  {}",
+        "", first_name, second_name
+    )?;
+    for row in rows {
+        let line = format!(
+            "{} {:name_width$}  {:first_width$}  {}{}",
+            if row.differs() { '*' } else { ' ' },
+            row.name,
+            row.first,
+            row.second,
+            if row.differs() && row.ignored {
+                " (ignored)"
+            } else {
+                ""
+            }
+        );
+        writeln!(writer, "{}", line.trim_end())?;
+    }
+    writeln!(writer)?;
+    match differences(rows).as_slice() {
+        [] => writeln!(writer, "All compared stats match"),
+        [one] => writeln!(writer, "1 stat differs: {}", one),
+        many => writeln!(writer, "{} stats differ: {}", many.len(), many.join(", ")),
+    }
+}
+
+/// Writes `{"files": [...], "differences": [...]}`, where the files are
+/// objects as `--format json` writes them and `differences` names the
+/// stats that differ and are not ignored.
+fn write_json(
+    first: &FileStats,
+    second: &FileStats,
+    rows: &[Row],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"files\": [")?;
+    write!(writer, "    ")?;
+    json::write_object(first, ReportOptions::default(), &mut writer)?;
+    writeln!(writer, ",")?;
+    write!(writer, "    ")?;
+    json::write_object(second, ReportOptions::default(), &mut writer)?;
+    writeln!(writer)?;
+    writeln!(writer, "  ],")?;
+    let names: Vec<String> = differences(rows).into_iter().map(string).collect();
+    writeln!(writer, "  \"differences\": [{}]", names.join(", "))?;
+    writeln!(writer, "}}")
+}
+
+fn analyze(path: &str) -> Result<FileStats, MdltError> {
+    let stats = if path == "-" {
+        analyze_reader_with(
+            io::stdin().lock(),
+            "<stdin>".to_string(),
+            ScanOptions::default(),
+        )
+    } else {
+        analyze_file_with(path, MmapMode::Auto, ScanOptions::default(), 1)
+    };
+    stats.map_err(|source| MdltError::File {
+        path: path.to_string(),
+        source,
+    })
+}
+
+/// Runs `mdlt compare`, for which `args[1]` is `compare`.
+pub fn run(args: &[String]) -> Result<Exit, MdltError> {
+    let options = cli::parse_compare_args(args).map_err(MdltError::Usage)?;
+    if options.help {
+        println!("{}", cli::compare_usage(&args[0]));
+        return Ok(Exit::Clean);
+    }
+    let first = analyze(&options.first)?;
+    let second = analyze(&options.second)?;
+    let rows = rows(&first, &second, &options.ignore);
+    let stdout = io::stdout().lock();
+    match options.format {
+        OutputFormat::Json => write_json(&first, &second, &rows, stdout),
+        _ => write_text(&first, &second, &rows, stdout),
+    }
+    .map_err(crate::writing_report)?;
+    let differences = differences(&rows);
+    if !differences.is_empty() {
+        let message = format!(
+            "{} and {} differ in {}",
+            first.display_name(),
+            second.display_name(),
+            differences.join(", ")
+        );
+        return Err(MdltError::check("compare", message));
+    }
+    Ok(Exit::Clean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, unix: usize, dos: usize) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.unix_endings = unix;
+        stats.dos_endings = dos;
+        stats.total_lines = unix + dos;
+        stats.total_bytes = (2 * unix + 3 * dos) as u64;
+        stats.final_newline = Some(true);
+        stats
+    }
+
+    #[test]
+    fn test_stats_named() {
+        assert_eq!(stats_named("total_lines"), Ok(vec!["total_lines"]));
+        assert_eq!(stats_named("final-newline"), Ok(vec!["final_newline"]));
+        assert_eq!(stats_named("size").unwrap().len(), 3);
+        assert!(stats_named("line-endings")
+            .unwrap()
+            .contains(&"dos_endings"));
+        assert!(stats_named("colour")
+            .unwrap_err()
+            .starts_with("Unknown --ignore 'colour'"));
+    }
+
+    #[test]
+    fn test_write_text_marks_differences() {
+        let first = file("a.txt", 2, 0);
+        let second = file("b-with-a-long-name.txt", 0, 2);
+        let rows = rows(&first, &second, &stats_named("size").unwrap());
+        assert_eq!(
+            differences(&rows),
+            ["line_ending_type", "dos_endings", "unix_endings"]
+        );
+        let mut buffer = Vec::new();
+        write_text(&first, &second, &rows, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with(
+            "                             a.txt            b-with-a-long-name.txt\n\
+             * total_bytes                4                6 (ignored)\n"
+        ));
+        assert!(output.contains("\n  total_lines                2                2\n"));
+        assert!(
+            output.contains("\n* line_ending_type           Unix/Linux (LF)  DOS/Windows (CRLF)\n")
+        );
+        assert!(
+            output.ends_with("\n\n3 stats differ: line_ending_type, dos_endings, unix_endings\n")
+        );
+
+        let rows = super::rows(&first, &first, &[]);
+        let mut buffer = Vec::new();
+        write_text(&first, &first, &rows, &mut buffer).unwrap();
+        assert!(String::from_utf8(buffer)
+            .unwrap()
+            .ends_with("\n\nAll compared stats match\n"));
+    }
+
+    #[test]
+    fn test_write_json() {
+        let first = file("a.txt", 2, 0);
+        let second = file("b.txt", 3, 0);
+        let rows = rows(&first, &second, &stats_named("size").unwrap());
+        let mut buffer = Vec::new();
+        write_json(&first, &second, &rows, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("{\n  \"files\": [\n    {\"file_name\": \"a.txt\", "));
+        assert!(output.contains("},\n    {\"file_name\": \"b.txt\", "));
+        assert!(output
+            .ends_with("}\n  ],\n  \"differences\": [\"total_lines\", \"unix_endings\"]\n}\n"));
+    }
+}
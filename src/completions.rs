@@ -0,0 +1,347 @@
+use std::io::{self, Write};
+
+use crate::cli::{self, Shell, Takes};
+use crate::{Exit, MdltError};
+
+/// A flag of `cli::FLAGS`, with the values it completes.
+#[derive(Debug, PartialEq, Eq)]
+struct Flag {
+    name: &'static str,
+    takes_value: bool,
+    /// The values to offer, or none for a free value such as a path.
+    choices: Vec<String>,
+}
+
+/// The name a choice such as `squeeze-blank[=N]` or `files[:desc]` is typed
+/// as, or `None` for what is not a choice of its own, like the `tabs:N` of
+/// `retab=spaces:N|tabs:N`.
+fn choice_name(piece: &str) -> Option<String> {
+    let name = piece.split(['=', '[']).next().unwrap_or_default();
+    let is_name = !name.is_empty()
+        && !name.contains(':')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+    is_name.then(|| name.to_string())
+}
+
+/// The choices a value shown as `shown` in the usage line offers: words
+/// such as `text|markdown`, and none for a placeholder such as `<path>` or
+/// `N`, which takes anything.
+fn choices(shown: &str) -> Vec<String> {
+    let free = shown.starts_with('<')
+        || shown.starts_with(|c: char| c.is_ascii_digit() || c.is_ascii_uppercase());
+    let mut choices = Vec::new();
+    if !free {
+        for choice in shown.split(['|', ',']).filter_map(choice_name) {
+            if !choices.contains(&choice) {
+                choices.push(choice);
+            }
+        }
+    }
+    choices
+}
+
+/// The flags of `cli::FLAGS`, so that completion offers exactly what the
+/// parser takes, but for the `--` that ends them. A value given only after
+/// `=`, as in `--cache[=PATH]`, is not completed.
+fn flags() -> Vec<Flag> {
+    cli::flags()
+        .filter(|flag| flag.name != "--")
+        .map(|flag| {
+            let (takes_value, choices) = match flag.takes {
+                Takes::Value(shown) => (true, choices(shown)),
+                Takes::Nothing | Takes::Optional(_) => (false, Vec::new()),
+            };
+            Flag {
+                name: flag.name,
+                takes_value,
+                choices,
+            }
+        })
+        .collect()
+}
+
+/// The words that may come first instead of a path.
+const SUBCOMMANDS: &[&str] = &["diff", "compare", "completions"];
+
+fn bash(flags: &[Flag]) -> String {
+    let names: Vec<&str> = flags.iter().map(|flag| flag.name).collect();
+    let mut script = String::from(
+        "# bash completion for mdlt; load with: source <(mdlt completions bash)\n\
+         _mdlt() {\n    \
+         local cur=\"${COMP_WORDS[COMP_CWORD]}\" prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n    \
+         case \"$prev\" in\n",
+    );
+    for flag in flags.iter().filter(|flag| !flag.choices.is_empty()) {
+        script.push_str(&format!(
+            "        {})\n            \
+             COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            \
+             return ;;\n",
+            flag.name,
+            flag.choices.join(" ")
+        ));
+    }
+    let free: Vec<&str> = flags
+        .iter()
+        .filter(|flag| flag.takes_value && flag.choices.is_empty())
+        .map(|flag| flag.name)
+        .collect();
+    script.push_str(&format!(
+        "        {})\n            \
+         COMPREPLY=($(compgen -f -- \"$cur\"))\n            \
+         return ;;\n    \
+         esac\n    \
+         if [[ \"$cur\" == -* ]]; then\n        \
+         COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n    \
+         elif [[ $COMP_CWORD -eq 1 ]]; then\n        \
+         COMPREPLY=($(compgen -W \"{}\" -- \"$cur\") $(compgen -f -- \"$cur\"))\n    \
+         else\n        \
+         COMPREPLY=($(compgen -f -- \"$cur\"))\n    \
+         fi\n\
+         }}\n\
+         complete -o filenames -F _mdlt mdlt\n",
+        free.join("|"),
+        names.join(" "),
+        SUBCOMMANDS.join(" ")
+    ));
+    script
+}
+
+fn zsh(flags: &[Flag]) -> String {
+    let mut script = String::from("#compdef mdlt\n\n_arguments \\\n");
+    for flag in flags {
+        let spec = match (flag.takes_value, flag.choices.is_empty()) {
+            (false, _) => String::new(),
+            (true, true) => ":value:_files".to_string(),
+            (true, false) => format!(":value:({})", flag.choices.join(" ")),
+        };
+        // Long flags take their value after `=` or as the next word.
+        let equals = if flag.takes_value && flag.name.starts_with("--") {
+            "="
+        } else {
+            ""
+        };
+        script.push_str(&format!("  '*{}{}{}' \\\n", flag.name, equals, spec));
+    }
+    script.push_str(&format!(
+        "  '1: :_alternative \"commands:command:({})\" \"files:path:_files\"' \\\n  \
+         '*:path:_files'\n",
+        SUBCOMMANDS.join(" ")
+    ));
+    script
+}
+
+fn fish(flags: &[Flag]) -> String {
+    let mut script = format!(
+        "# fish completion for mdlt\n\
+         complete -c mdlt -n __fish_use_subcommand -a '{}'\n",
+        SUBCOMMANDS.join(" ")
+    );
+    for flag in flags {
+        let option = match flag.name.strip_prefix("--") {
+            Some(long) => format!("-l {}", long),
+            None => {
+                let short = flag.name.trim_start_matches('-');
+                match short.chars().count() {
+                    1 => format!("-s {}", short),
+                    _ => format!("-o {}", short),
+                }
+            }
+        };
+        let value = match (flag.takes_value, flag.choices.is_empty()) {
+            (false, _) => String::new(),
+            (true, true) => " -r -F".to_string(),
+            (true, false) => format!(" -x -a '{}'", flag.choices.join(" ")),
+        };
+        script.push_str(&format!("complete -c mdlt {}{}\n", option, value));
+    }
+    script
+}
+
+fn powershell(flags: &[Flag]) -> String {
+    let quoted = |words: &[String]| -> String {
+        let words: Vec<String> = words.iter().map(|word| format!("'{}'", word)).collect();
+        words.join(", ")
+    };
+    let names: Vec<String> = flags.iter().map(|flag| flag.name.to_string()).collect();
+    let subcommands: Vec<String> = SUBCOMMANDS.iter().map(|word| word.to_string()).collect();
+    let mut choices = String::new();
+    for flag in flags.iter().filter(|flag| !flag.choices.is_empty()) {
+        choices.push_str(&format!(
+            "        '{}' = @({})\n",
+            flag.name,
+            quoted(&flag.choices)
+        ));
+    }
+    format!(
+        "# PowerShell completion for mdlt; load with:\n\
+         # mdlt completions powershell | Out-String | Invoke-Expression\n\
+         Register-ArgumentCompleter -Native -CommandName mdlt -ScriptBlock {{\n    \
+         param($wordToComplete, $commandAst, $cursorPosition)\n    \
+         $flags = @({})\n    \
+         $subcommands = @({})\n    \
+         $choices = @{{\n{}    }}\n    \
+         $words = @($commandAst.CommandElements | ForEach-Object {{ $_.ToString() }})\n    \
+         $previous = if ($wordToComplete) {{ $words[-2] }} else {{ $words[-1] }}\n    \
+         if ($choices.ContainsKey($previous)) {{\n        \
+         $candidates = $choices[$previous]\n    \
+         }} elseif ($wordToComplete -like '-*') {{\n        \
+         $candidates = $flags\n    \
+         }} elseif ($words.Count -le 2) {{\n        \
+         $candidates = $subcommands\n    \
+         }} else {{\n        \
+         return\n    \
+         }}\n    \
+         $candidates | Where-Object {{ $_ -like \"$wordToComplete*\" }} | ForEach-Object {{\n        \
+         [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)\n    \
+         }}\n\
+         }}\n",
+        quoted(&names),
+        quoted(&subcommands),
+        choices
+    )
+}
+
+/// The completion script for `shell`, built from the flags of `mdlt`.
+pub fn script(shell: Shell) -> String {
+    let flags = flags();
+    match shell {
+        Shell::Bash => bash(&flags),
+        Shell::Zsh => zsh(&flags),
+        Shell::Fish => fish(&flags),
+        Shell::Powershell => powershell(&flags),
+    }
+}
+
+/// Runs `mdlt completions`, for which `args[1]` is `completions`.
+pub fn run(args: &[String]) -> Result<Exit, MdltError> {
+    let options = cli::parse_completions_args(args).map_err(MdltError::Usage)?;
+    let shell = match options.shell {
+        Some(shell) if !options.help => shell,
+        _ => {
+            println!("{}", cli::completions_usage(&args[0]));
+            return Ok(Exit::Clean);
+        }
+    };
+    io::stdout()
+        .write_all(script(shell).as_bytes())
+        .map_err(|e| MdltError::io("writing completions", e))?;
+    Ok(Exit::Clean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flag(flags: &[Flag], name: &str) -> (bool, Vec<String>) {
+        let flag = flags.iter().find(|flag| flag.name == name).unwrap();
+        (flag.takes_value, flag.choices.clone())
+    }
+
+    #[test]
+    fn test_flags_complete_their_values() {
+        let flags = flags();
+        assert_eq!(flag(&flags, "-r"), (false, vec![]));
+        assert_eq!(flag(&flags, "--output"), (true, vec![]));
+        assert_eq!(flag(&flags, "--no-config"), (false, vec![]));
+        assert_eq!(flag(&flags, "--max-line-length"), (true, vec![]));
+        let (_, formats) = flag(&flags, "--format");
+        assert_eq!(
+            formats,
+            [
+                "text",
+                "markdown",
+                "html",
+                "json",
+                "csv",
+                "tsv",
+                "table",
+                "sarif",
+                "checkstyle",
+                "github"
+            ]
+        );
+        let (_, checks) = flag(&flags, "--check");
+        assert_eq!(
+            checks,
+            [
+                "mixed-indent",
+                "lf",
+                "crlf",
+                "cr",
+                "consistent",
+                "uniform-tree"
+            ]
+        );
+        let (_, fixes) = flag(&flags, "--fix");
+        assert_eq!(
+            fixes,
+            [
+                "trailing-whitespace",
+                "final-newline",
+                "strip-bom",
+                "retab",
+                "squeeze-blank"
+            ]
+        );
+        assert_eq!(flag(&flags, "--convert").1, ["lf", "crlf", "auto"]);
+        assert_eq!(flag(&flags, "--backup"), (false, vec![]));
+    }
+
+    #[test]
+    fn test_every_flag_parses() {
+        for flag in flags() {
+            let mut args = vec!["mdlt".to_string(), flag.name.to_string()];
+            if flag.takes_value {
+                args.push(flag.choices.first().cloned().unwrap_or("1".to_string()));
+            }
+            args.push("a.txt".to_string());
+            if let Err(message) = cli::parse_args(&args) {
+                assert!(!message.starts_with("Unknown option"), "{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_scripts_offer_every_flag() {
+        let (bash, zsh) = (script(Shell::Bash), script(Shell::Zsh));
+        let (fish, powershell) = (script(Shell::Fish), script(Shell::Powershell));
+        let words: Vec<&str> = bash.split([' ', '"', '|', ')', '\n']).collect();
+        for flag in cli::flags().filter(|flag| flag.name != "--") {
+            let name = flag.name;
+            assert!(words.contains(&name), "bash lacks {}", name);
+            assert!(zsh.contains(&format!("'*{}", name)), "zsh lacks {}", name);
+            let option = match name.strip_prefix("--") {
+                Some(long) => format!("-l {}", long),
+                None if name.len() == 2 => format!("-s {}", &name[1..]),
+                None => format!("-o {}", &name[1..]),
+            };
+            let line = format!("complete -c mdlt {}", option);
+            assert!(
+                fish.lines()
+                    .any(|l| l == line || l.starts_with(&format!("{} ", line))),
+                "fish lacks {}",
+                name
+            );
+            assert!(
+                powershell.contains(&format!("'{}'", name)),
+                "powershell lacks {}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_scripts_complete_choices() {
+        let bash = script(Shell::Bash);
+        assert!(bash.contains(
+            "        --format)\n            COMPREPLY=($(compgen -W \"text markdown html json csv tsv table sarif checkstyle github\""
+        ));
+        assert!(bash.ends_with("complete -o filenames -F _mdlt mdlt\n"));
+        assert!(script(Shell::Zsh).contains("  '*--convert=:value:(lf crlf auto)' \\\n"));
+        assert!(script(Shell::Fish).contains("complete -c mdlt -o qq\n"));
+        assert!(script(Shell::Fish).contains("complete -c mdlt -l check -x -a 'mixed-indent lf"));
+        assert!(script(Shell::Powershell).contains("        '--format' = @('text', 'markdown', "));
+    }
+}
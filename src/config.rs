@@ -0,0 +1,658 @@
+use std::path::{Path, PathBuf};
+
+use crate::cli::{self, ExpectedEndings, Options};
+
+/// The file looked for in the current directory and its parents.
+pub const FILE_NAME: &str = "mdlt.toml";
+
+/// The names `checks` takes, the flag each stands for, and whether a set of
+/// options has it on.
+type CheckName = (&'static str, &'static str, fn(&Options) -> bool);
+
+const CHECKS: &[CheckName] = &[
+    ("mixed", "--fail-on-mixed", |options| options.fail_on_mixed),
+    (
+        "mixed-indentation",
+        "--fail-on-mixed-indentation",
+        |options| options.fail_on_mixed_indentation,
+    ),
+    ("mixed-indent", "--check=mixed-indent", |options| {
+        options.scan.mixed_indent.is_some()
+    }),
+    (
+        "trailing-whitespace",
+        "--fail-on-trailing-whitespace",
+        |options| options.fail_on_trailing_whitespace,
+    ),
+    (
+        "missing-final-newline",
+        "--fail-on-missing-final-newline",
+        |options| options.fail_on_missing_final_newline,
+    ),
+    ("control-chars", "--fail-on-control-chars", |options| {
+        options.fail_on_control_chars
+    }),
+    ("non-ascii", "--fail-on-non-ascii", |options| {
+        options.fail_on_non_ascii
+    }),
+    ("embedded-cr", "--fail-on-embedded-cr", |options| {
+        options.fail_on_embedded_cr
+    }),
+    ("unstable", "--fail-on-unstable", |options| {
+        options.fail_on_unstable
+    }),
+    ("skip", "--fail-on-skip", |options| options.fail_on_skip),
+    ("gitattributes", "--gitattributes", |options| {
+        options.gitattributes
+    }),
+    ("editorconfig", "--editorconfig", |options| {
+        options.editorconfig
+    }),
+];
+
+/// A value of the subset of TOML that `mdlt.toml` is written in.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    String(String),
+    Integer(i64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+/// What `Parser::value` reports when the input ends inside an array, which
+/// then goes on over the following lines.
+const UNTERMINATED: &str = "unterminated array";
+
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl Parser<'_> {
+    fn value(&mut self) -> Result<Value, String> {
+        self.rest = self.rest.trim_start();
+        if let Some(rest) = self.rest.strip_prefix('"') {
+            self.rest = rest;
+            return self.basic_string().map(Value::String);
+        }
+        if let Some(rest) = self.rest.strip_prefix('\'') {
+            let (text, rest) = rest
+                .split_once('\'')
+                .ok_or_else(|| "unterminated string".to_string())?;
+            self.rest = rest;
+            return Ok(Value::String(text.to_string()));
+        }
+        if let Some(rest) = self.rest.strip_prefix('[') {
+            self.rest = rest;
+            return self.array();
+        }
+        let end = self
+            .rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "+-_".contains(c)))
+            .unwrap_or(self.rest.len());
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        match word {
+            "true" => Ok(Value::Boolean(true)),
+            "false" => Ok(Value::Boolean(false)),
+            "" => Err(match self.rest.chars().next() {
+                Some(c) => format!("expected a value, got '{}'", c),
+                None => "expected a value".to_string(),
+            }),
+            _ => word
+                .replace('_', "")
+                .parse()
+                .map(Value::Integer)
+                .map_err(|_| {
+                    format!(
+                        "expected a string, integer, boolean or array, got '{}'",
+                        word
+                    )
+                }),
+        }
+    }
+
+    /// The rest of a `"..."` string with its escapes resolved.
+    fn basic_string(&mut self) -> Result<String, String> {
+        let mut text = String::new();
+        let mut chars = self.rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.rest = &self.rest[index + 1..];
+                    return Ok(text);
+                }
+                '\\' => match chars.next().map(|(_, c)| c) {
+                    Some('"') => text.push('"'),
+                    Some('\\') => text.push('\\'),
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('r') => text.push('\r'),
+                    Some(other) => return Err(format!("unknown escape \\{} in a string", other)),
+                    None => break,
+                },
+                _ => text.push(c),
+            }
+        }
+        Err("unterminated string".to_string())
+    }
+
+    /// The rest of a `[...]` array, whose last item may have a trailing
+    /// comma.
+    fn array(&mut self) -> Result<Value, String> {
+        let mut items = Vec::new();
+        loop {
+            self.rest = self.rest.trim_start();
+            if self.rest.is_empty() {
+                return Err(UNTERMINATED.to_string());
+            }
+            if let Some(rest) = self.rest.strip_prefix(']') {
+                self.rest = rest;
+                return Ok(Value::Array(items));
+            }
+            items.push(self.value()?);
+            self.rest = self.rest.trim_start();
+            if let Some(rest) = self.rest.strip_prefix(',') {
+                self.rest = rest;
+            } else if !self.rest.starts_with(']') && !self.rest.is_empty() {
+                return Err("expected , or ] in an array".to_string());
+            }
+        }
+    }
+}
+
+fn parse_value(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { rest: text };
+    let value = parser.value()?;
+    match parser.rest.trim() {
+        "" => Ok(value),
+        rest => Err(format!("unexpected '{}' after the value", rest)),
+    }
+}
+
+/// `line` without a `#` comment, which may not start inside a string.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, c) in line.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(open) if c == open => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return &line[..index],
+            None => {}
+        }
+    }
+    line
+}
+
+fn string(key: &str, value: Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text),
+        _ => Err(format!("{} expects a string", key)),
+    }
+}
+
+/// A string, or an array of them.
+fn strings(key: &str, value: Value) -> Result<Vec<String>, String> {
+    match value {
+        Value::String(text) => Ok(vec![text]),
+        Value::Array(items) => items.into_iter().map(|item| string(key, item)).collect(),
+        _ => Err(format!("{} expects a string or an array of strings", key)),
+    }
+}
+
+/// The tables `mdlt.toml` may have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Table {
+    Top,
+    /// `[endings]`: the expected line endings by extension.
+    Endings,
+    /// `[severity]`: the severity of each named check's violations.
+    Severity,
+    /// `[type_names]`: the types of files without an extension by name.
+    TypeNames,
+    /// `[type_interpreters]`: the types of scripts by interpreter.
+    TypeInterpreters,
+    /// Reported once where it starts; its keys are ignored.
+    Unknown,
+}
+
+/// An `mdlt.toml`, as the flags it stands for.
+#[derive(Debug)]
+pub struct Config {
+    /// Where the file is, as messages name it.
+    pub path: String,
+    /// Each flag with the line that set it.
+    flags: Vec<(usize, String)>,
+    /// Keys and tables that mean nothing here and were ignored.
+    pub warnings: Vec<String>,
+}
+
+impl Config {
+    /// Reads `contents`, failing on the first line that cannot be parsed or
+    /// sets a value the flag it stands for would not accept.
+    pub fn parse(path: &str, contents: &str) -> Result<Config, String> {
+        let mut config = Config {
+            path: path.to_string(),
+            flags: Vec::new(),
+            warnings: Vec::new(),
+        };
+        let mut table = Table::Top;
+        let mut lines = contents.lines().enumerate();
+        while let Some((index, line)) = lines.next() {
+            let line_number = index + 1;
+            let at = |message: String| format!("{}:{}: {}", path, line_number, message);
+            let line = strip_comment(line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[') {
+                let name = name
+                    .strip_suffix(']')
+                    .ok_or_else(|| at("expected ] after the table name".to_string()))?
+                    .trim();
+                table = match name {
+                    "endings" => Table::Endings,
+                    "severity" => Table::Severity,
+                    "type_names" => Table::TypeNames,
+                    "type_interpreters" => Table::TypeInterpreters,
+                    _ => {
+                        config
+                            .warnings
+                            .push(at(format!("unknown table [{}]", name)));
+                        Table::Unknown
+                    }
+                };
+                continue;
+            }
+            let (key, text) = line
+                .split_once('=')
+                .ok_or_else(|| at("expected key = value".to_string()))?;
+            let key = key.trim().trim_matches('"');
+            let mut text = text.trim().to_string();
+            let value = loop {
+                match parse_value(&text) {
+                    Err(error) if error == UNTERMINATED => match lines.next() {
+                        Some((_, more)) => {
+                            text.push(' ');
+                            text.push_str(strip_comment(more).trim());
+                        }
+                        None => return Err(at(error)),
+                    },
+                    value => break value.map_err(at)?,
+                }
+            };
+            match table {
+                Table::Top => config.top_level(line_number, key, value).map_err(at)?,
+                Table::Endings => {
+                    let endings = string(key, value).map_err(at)?;
+                    config.flag(line_number, format!("--expect={}={}", key, endings));
+                }
+                Table::Severity => {
+                    let severity = string(key, value).map_err(at)?;
+                    config.flag(line_number, format!("--severity={}={}", key, severity));
+                }
+                Table::TypeNames => {
+                    let kind = string(key, value).map_err(at)?;
+                    config.flag(line_number, format!("--type-name={}={}", key, kind));
+                }
+                Table::TypeInterpreters => {
+                    let kind = string(key, value).map_err(at)?;
+                    config.flag(line_number, format!("--type-interpreter={}={}", key, kind));
+                }
+                Table::Unknown => {}
+            }
+        }
+        for (line_number, flag) in &config.flags {
+            options_from(std::slice::from_ref(flag))
+                .map_err(|error| format!("{}:{}: {}", path, line_number, error))?;
+        }
+        Ok(config)
+    }
+
+    fn top_level(&mut self, line_number: usize, key: &str, value: Value) -> Result<(), String> {
+        match key {
+            "format" => {
+                let format = string(key, value)?;
+                self.flag(line_number, format!("--format={}", format));
+            }
+            "check" => {
+                let check = string(key, value)?;
+                self.flag(line_number, format!("--check={}", check));
+            }
+            "max_line_length" => match value {
+                Value::Integer(limit) if limit >= 0 => {
+                    self.flag(line_number, format!("--max-line-length={}", limit))
+                }
+                _ => return Err(format!("{} expects a non-negative integer", key)),
+            },
+            "warnings_as_errors" => match value {
+                Value::Boolean(true) => self.flag(line_number, "--warnings-as-errors".to_string()),
+                Value::Boolean(false) => {}
+                _ => return Err(format!("{} expects true or false", key)),
+            },
+            "exclude" => {
+                for pattern in strings(key, value)? {
+                    self.flag(line_number, format!("--exclude={}", pattern));
+                }
+            }
+            "checks" => {
+                for name in strings(key, value)? {
+                    let Some(&(_, flag, _)) = CHECKS.iter().find(|(known, _, _)| *known == name)
+                    else {
+                        let known: Vec<&str> = CHECKS.iter().map(|&(name, _, _)| name).collect();
+                        return Err(format!(
+                            "unknown check '{}': expected {}",
+                            name,
+                            known.join(", ")
+                        ));
+                    };
+                    self.flag(line_number, flag.to_string());
+                }
+            }
+            _ => self.warnings.push(format!(
+                "{}:{}: unknown key '{}'",
+                self.path, line_number, key
+            )),
+        }
+        Ok(())
+    }
+
+    fn flag(&mut self, line_number: usize, flag: String) {
+        self.flags.push((line_number, flag));
+    }
+
+    /// The flags to parse ahead of the command line.
+    pub fn flags(&self) -> Vec<String> {
+        self.flags.iter().map(|(_, flag)| flag.clone()).collect()
+    }
+}
+
+/// The options `flags` alone give, as `--print-config` sees them.
+fn options_from(flags: &[String]) -> Result<Options, String> {
+    let args = ["mdlt".to_string(), "--print-config".to_string()];
+    cli::parse_args_with_config(&args, flags)
+}
+
+/// The nearest `mdlt.toml` in `dir` or one of its parents.
+pub fn find(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(FILE_NAME))
+        .find(|path| path.is_file())
+}
+
+/// The file `options` asks to be read: the `--config` one, none with
+/// `--no-config`, or otherwise whichever `find` turns up from the current
+/// directory.
+pub fn path(options: &Options) -> Option<PathBuf> {
+    if options.no_config {
+        return None;
+    }
+    match &options.config {
+        Some(path) => Some(PathBuf::from(path)),
+        None => find(&std::env::current_dir().ok()?),
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn array(items: &[String]) -> String {
+    format!("[{}]", items.join(", "))
+}
+
+/// Every setting `mdlt.toml` can hold that `options` has, with its value as
+/// TOML; `[endings]` entries are keyed `endings.EXT`, `[severity]` ones
+/// `severity.CHECK`, and those of the type tables by their table name.
+fn settings(options: &Options) -> Vec<(String, String)> {
+    let mut settings = vec![("format".to_string(), quote(options.format.name()))];
+    if let Some(expected) = options.expected_endings {
+        settings.push(("check".to_string(), quote(expected.name())));
+    }
+    if let Some(limit) = &options.scan.line_limit {
+        settings.push(("max_line_length".to_string(), limit.max_columns.to_string()));
+    }
+    let excluded: Vec<String> = options
+        .walk
+        .filter
+        .excluded()
+        .iter()
+        .map(|pattern| quote(pattern))
+        .collect();
+    settings.push(("exclude".to_string(), array(&excluded)));
+    let checks: Vec<String> = CHECKS
+        .iter()
+        .filter(|(_, _, enabled)| enabled(options))
+        .map(|(name, _, _)| quote(name))
+        .collect();
+    settings.push(("checks".to_string(), array(&checks)));
+    settings.push((
+        "warnings_as_errors".to_string(),
+        options.warnings_as_errors.to_string(),
+    ));
+    let mut endings = options.expect.clone();
+    endings.sort_by(|a, b| a.0.cmp(&b.0));
+    for (extension, expected) in endings {
+        let name = expected.map_or("any", ExpectedEndings::name);
+        settings.push((format!("endings.{}", extension), quote(name)));
+    }
+    let mut severities = options.severities.clone();
+    severities.sort_by(|a, b| a.0.cmp(&b.0));
+    for (name, severity) in severities {
+        settings.push((format!("severity.{}", name), quote(severity.name())));
+    }
+    let types = options.scan.types;
+    for (table, rows) in [
+        ("type_names", types.names),
+        ("type_interpreters", types.interpreters),
+    ] {
+        let mut rows = rows.to_vec();
+        rows.sort();
+        for (name, kind) in rows {
+            settings.push((format!("{}.{}", table, name), quote(&kind)));
+        }
+    }
+    settings
+}
+
+/// What `--print-config` prints: the settings in effect as an `mdlt.toml`
+/// would give them, each followed by whether it comes from the command
+/// line, the file or the defaults.
+pub fn describe(config: Option<&Config>, options: &Options) -> Result<String, String> {
+    let flags = config.map(Config::flags).unwrap_or_default();
+    let defaults = settings(&options_from(&[])?);
+    let from_file = settings(&options_from(&flags)?);
+    let value = |settings: &[(String, String)], key: &str| {
+        settings
+            .iter()
+            .find(|(given, _)| given == key)
+            .map(|(_, value)| value.clone())
+    };
+    let mut text = match config {
+        Some(config) => format!("# {}\n", config.path),
+        None if options.no_config => "# --no-config\n".to_string(),
+        None => format!("# no {} found\n", FILE_NAME),
+    };
+    let mut in_table: Option<String> = None;
+    for (key, setting) in settings(options) {
+        let source = if Some(&setting) != value(&from_file, &key).as_ref() {
+            "command line"
+        } else if Some(&setting) != value(&defaults, &key).as_ref() {
+            config.map_or("command line", |config| config.path.as_str())
+        } else {
+            "default"
+        };
+        let key = match key.split_once('.') {
+            Some((table, name)) => {
+                if in_table.as_deref() != Some(table) {
+                    text.push_str(&format!("\n[{}]\n", table));
+                    in_table = Some(table.to_string());
+                }
+                // `*` is not a bare key.
+                if name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_".contains(c))
+                {
+                    name.to_string()
+                } else {
+                    quote(name)
+                }
+            }
+            None => key,
+        };
+        text.push_str(&format!("{} = {}  # {}\n", key, setting, source));
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn flags(contents: &str) -> Vec<String> {
+        Config::parse("mdlt.toml", contents).unwrap().flags()
+    }
+
+    #[test]
+    fn test_parse_settings() {
+        let config = Config::parse(
+            "mdlt.toml",
+            "# shared by every CI job\n\
+             format = \"json\"\n\
+             check = 'lf'  # most files\n\
+             max_line_length = 1_00\n\
+             exclude = [\n  \"target/**\", # build output\n  \"*.min.js\",\n]\n\
+             checks = [\"mixed\", \"trailing-whitespace\"]\n\
+             warnings_as_errors = true\n\
+             colour = true\n\
+             \n\
+             [endings]\n\
+             bat = \"crlf\"\n\
+             \"cmd\" = \"crlf\"\n\
+             \n\
+             [severity]\n\
+             trailing-whitespace = \"warning\"\n\
+             \n\
+             [type_names]\n\
+             Jenkinsfile = \"groovy\"\n\
+             \n\
+             [type_interpreters]\n\
+             deno = \"ts\"\n\
+             \n\
+             [plugins]\n\
+             spell = true\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config.flags(),
+            vec![
+                "--format=json",
+                "--check=lf",
+                "--max-line-length=100",
+                "--exclude=target/**",
+                "--exclude=*.min.js",
+                "--fail-on-mixed",
+                "--fail-on-trailing-whitespace",
+                "--warnings-as-errors",
+                "--expect=bat=crlf",
+                "--expect=cmd=crlf",
+                "--severity=trailing-whitespace=warning",
+                "--type-name=Jenkinsfile=groovy",
+                "--type-interpreter=deno=ts",
+            ]
+        );
+        assert_eq!(
+            config.warnings,
+            vec![
+                "mdlt.toml:11: unknown key 'colour'",
+                "mdlt.toml:26: unknown table [plugins]"
+            ]
+        );
+        assert_eq!(flags("exclude = 'a#b'\n"), vec!["--exclude=a#b"]);
+        assert_eq!(flags("exclude = []\n"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_parse_errors_name_the_line() {
+        let error = |contents: &str| Config::parse("mdlt.toml", contents).unwrap_err();
+        assert_eq!(
+            error("\nformat = \"yaml\"\n"),
+            "mdlt.toml:2: Unknown format 'yaml': expected text, markdown, html, json, csv, tsv, table, sarif, checkstyle or github"
+        );
+        assert_eq!(
+            error("max_line_length = \"80\"\n"),
+            "mdlt.toml:1: max_line_length expects a non-negative integer"
+        );
+        assert_eq!(
+            error("checks = [\"mixed\", \"spelling\"]\n"),
+            "mdlt.toml:1: unknown check 'spelling': expected mixed, mixed-indentation, \
+             mixed-indent, trailing-whitespace, missing-final-newline, control-chars, \
+             non-ascii, embedded-cr, unstable, skip, gitattributes, editorconfig"
+        );
+        assert_eq!(
+            error("[endings]\nrs = \"unix\"\n"),
+            "mdlt.toml:2: --expect: unknown ending 'unix' for rs: expected lf, crlf, cr, \
+             consistent or any"
+        );
+        assert_eq!(
+            error("[severity]\nbom = \"fatal\"\n"),
+            "mdlt.toml:2: --severity bom: Unknown severity 'fatal': expected error, warning or info"
+        );
+        assert_eq!(
+            error("exclude = [\"a\",\n"),
+            "mdlt.toml:1: unterminated array"
+        );
+        assert_eq!(error("format json\n"), "mdlt.toml:1: expected key = value");
+        assert_eq!(
+            error("format = \"json\" \"csv\"\n"),
+            "mdlt.toml:1: unexpected '\"csv\"' after the value"
+        );
+    }
+
+    #[test]
+    fn test_find_walks_up() {
+        let root = std::env::temp_dir().join(format!("mdlt_config_{}", std::process::id()));
+        let deep = root.join("a/b");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(root.join(FILE_NAME), "").unwrap();
+        assert_eq!(find(&deep), Some(root.join(FILE_NAME)));
+        fs::write(root.join("a").join(FILE_NAME), "").unwrap();
+        assert_eq!(find(&deep), Some(root.join("a").join(FILE_NAME)));
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_describe_sources() {
+        let config = Config::parse(
+            "mdlt.toml",
+            "format = \"json\"\ncheck = \"lf\"\n[endings]\nbat = \"crlf\"\n\"*\" = \"any\"\n\
+             [type_names]\n\".bazelrc\" = \"bazelrc\"\n",
+        )
+        .unwrap();
+        let args: Vec<String> = ["mdlt", "--print-config", "--check=crlf", "--fail-on-mixed"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let options = cli::parse_args_with_config(&args, &config.flags()).unwrap();
+        assert_eq!(
+            describe(Some(&config), &options).unwrap(),
+            "# mdlt.toml\n\
+             format = \"json\"  # mdlt.toml\n\
+             check = \"crlf\"  # command line\n\
+             exclude = []  # default\n\
+             checks = [\"mixed\"]  # command line\n\
+             warnings_as_errors = false  # default\n\
+             \n\
+             [endings]\n\
+             \"*\" = \"any\"  # mdlt.toml\n\
+             bat = \"crlf\"  # mdlt.toml\n\
+             \n\
+             [type_names]\n\
+             \".bazelrc\" = \"bazelrc\"  # mdlt.toml\n"
+        );
+    }
+}
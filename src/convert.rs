@@ -0,0 +1,995 @@
+use std::fs::File;
+use std::io::{self, Read, Write};
+
+use crate::atomic::{self, InPlace};
+use crate::bom::Bom;
+use crate::cli::{ConvertTo, Retab};
+use crate::interactive::Decision;
+use crate::osname;
+use crate::transitions::Ending;
+
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// The names of the endings counted as CRLF, LF and CR, in that order.
+const STYLES: [&str; 3] = ["CRLF", "LF", "CR"];
+
+/// The terminators counted as CRLF, LF and CR, in the order of `STYLES`.
+const TERMINATORS: [&[u8]; 3] = [b"\r\n", b"\n", b"\r"];
+
+/// What `--convert` and `--fix` did to one file, or with `--dry-run` would
+/// do.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// Why the file was not touched at all, such as "binary file".
+    pub skipped: Option<String>,
+    /// The ending the file was converted to, if any.
+    pub target: Option<ConvertTo>,
+    /// Why `--convert auto` left the endings alone.
+    pub left_alone: Option<String>,
+    /// The CRLF, LF and CR endings rewritten to `target`.
+    pub endings: [usize; 3],
+    /// With `--fix trailing-whitespace`, the lines it was stripped from.
+    pub trailing_whitespace: Option<usize>,
+    /// The terminator `--fix final-newline` added to the last line.
+    pub final_newline: Option<Ending>,
+    /// Whether `--fix strip-bom` removed a UTF-8 byte-order mark.
+    pub bom_stripped: bool,
+    /// The indentation `--fix retab` writes, and the lines it rewrote.
+    pub retab: Option<Retab>,
+    pub retabbed: usize,
+    /// With `--fix squeeze-blank`, the blank lines it removed.
+    pub blank_lines_removed: Option<usize>,
+    /// Where `--backup` copied the original.
+    pub backup: Option<String>,
+    pub dry_run: bool,
+    /// With `--interactive`, what came of a file there was a change for;
+    /// a declined file has its counts as `--dry-run` gives them.
+    pub decision: Option<Decision>,
+}
+
+impl Outcome {
+    /// The changed line endings, of all styles.
+    pub fn changed_endings(&self) -> usize {
+        self.endings.iter().sum()
+    }
+
+    /// Whether the file is, or with `--dry-run` would be, rewritten.
+    pub fn changes(&self) -> bool {
+        self.changed_endings() > 0
+            || self.trailing_whitespace.unwrap_or(0) > 0
+            || self.final_newline.is_some()
+            || self.bom_stripped
+            || self.retabbed > 0
+            || self.blank_lines_removed.unwrap_or(0) > 0
+    }
+
+    /// Takes the counts of what was changed from `changes`, for the fixes
+    /// that were asked for.
+    pub fn record(&mut self, changes: Changes) {
+        self.endings = changes.endings;
+        if self.trailing_whitespace.is_some() {
+            self.trailing_whitespace = Some(changes.trailing_whitespace);
+        }
+        self.final_newline = changes.final_newline;
+        self.bom_stripped = changes.bom_stripped;
+        self.retabbed = changes.retabbed;
+        if self.blank_lines_removed.is_some() {
+            self.blank_lines_removed = Some(changes.blank_lines_removed);
+        }
+    }
+
+    /// Whether `--dry-run` found something to change.
+    pub fn would_change(&self) -> bool {
+        self.dry_run && self.changes()
+    }
+
+    /// "converted 12 line endings to LF", "already CRLF, stripped trailing
+    /// whitespace on 3 lines" or "would convert 37 CRLF \u{2192} LF"; empty
+    /// when only `--fix final-newline` or `--fix strip-bom` was asked for
+    /// and it was not needed. `--interactive` adds "declined; " before what
+    /// a declined file would have had done.
+    pub fn summary(&self) -> String {
+        if let Some(reason) = &self.skipped {
+            return format!("skipped: {}", reason);
+        }
+        if self.decision == Some(Decision::Unvisited) {
+            return "not visited".to_string();
+        }
+        let mut parts = Vec::new();
+        if self.bom_stripped {
+            let verb = if self.dry_run {
+                "would strip"
+            } else {
+                "stripped"
+            };
+            parts.push(format!("{} UTF-8 BOM", verb));
+        }
+        if let Some(reason) = &self.left_alone {
+            parts.push(format!("left alone: {}", reason));
+        } else if let Some(target) = self.target {
+            let changed = self.changed_endings();
+            parts.push(match (changed, self.dry_run) {
+                (0, _) => format!("already {}", target.name()),
+                (_, true) => {
+                    let styles: Vec<String> = STYLES
+                        .iter()
+                        .zip(self.endings)
+                        .filter(|&(_, count)| count > 0)
+                        .map(|(style, count)| format!("{} {}", count, style))
+                        .collect();
+                    format!(
+                        "would convert {} \u{2192} {}",
+                        styles.join(" and "),
+                        target.name()
+                    )
+                }
+                (_, false) => format!(
+                    "converted {} line ending{} to {}",
+                    changed,
+                    if changed == 1 { "" } else { "s" },
+                    target.name()
+                ),
+            });
+        }
+        if let Some(lines) = self.trailing_whitespace {
+            let plural = if lines == 1 { "" } else { "s" };
+            parts.push(match (lines, self.dry_run) {
+                (0, _) => "no trailing whitespace".to_string(),
+                (_, true) => format!(
+                    "would strip trailing whitespace on {} line{}",
+                    lines, plural
+                ),
+                (_, false) => format!("stripped trailing whitespace on {} line{}", lines, plural),
+            });
+        }
+        if let Some(retab) = self.retab {
+            let lines = self.retabbed;
+            let plural = if lines == 1 { "" } else { "s" };
+            parts.push(match (lines, self.dry_run) {
+                (0, _) => "no indentation to change".to_string(),
+                (_, true) => format!(
+                    "would reindent {} line{} with {}",
+                    lines,
+                    plural,
+                    retab.name()
+                ),
+                (_, false) => format!("reindented {} line{} with {}", lines, plural, retab.name()),
+            });
+        }
+        if let Some(lines) = self.blank_lines_removed {
+            let plural = if lines == 1 { "" } else { "s" };
+            parts.push(match (lines, self.dry_run) {
+                (0, _) => "no blank lines to squeeze".to_string(),
+                (_, true) => format!("would remove {} blank line{}", lines, plural),
+                (_, false) => format!("removed {} blank line{}", lines, plural),
+            });
+        }
+        if let Some(ending) = self.final_newline {
+            let verb = if self.dry_run { "would add" } else { "added" };
+            parts.push(format!("{} final newline ({})", verb, ending.name()));
+        }
+        match self.decision {
+            Some(Decision::Declined) => format!("declined; {}", parts.join(", ")),
+            _ => parts.join(", "),
+        }
+    }
+}
+
+/// The endings a file's CRLF, LF and CR counts say will be rewritten to
+/// `target`, in the same order; what `--dry-run` reports and whether a file
+/// is rewritten at all both come from this.
+pub fn pending(counts: [usize; 3], target: ConvertTo) -> [usize; 3] {
+    let [crlf, lf, cr] = counts;
+    match target {
+        ConvertTo::Lf => [crlf, 0, cr],
+        ConvertTo::Crlf => [0, lf, cr],
+    }
+}
+
+/// The ending `--fix final-newline` adds to a file with these CRLF, LF and
+/// CR counts: the `--convert` target if there is one, or else the most
+/// common, with ties going to LF and then CRLF, and LF when there are none.
+pub fn final_newline(counts: [usize; 3], target: Option<ConvertTo>) -> Ending {
+    let [crlf, lf, cr] = counts;
+    if let Some(target) = target {
+        match target {
+            ConvertTo::Lf => Ending::Lf,
+            ConvertTo::Crlf => Ending::Crlf,
+        }
+    } else if lf >= crlf && lf >= cr {
+        Ending::Lf
+    } else if crlf >= cr {
+        Ending::Crlf
+    } else {
+        Ending::Cr
+    }
+}
+
+/// The index into `STYLES` of `ending`; Unicode breaks are never written,
+/// so they stand in for nothing in particular and count as LF.
+fn style(ending: Ending) -> usize {
+    match ending {
+        Ending::Crlf => 0,
+        Ending::Lf | Ending::Unicode => 1,
+        Ending::Cr => 2,
+    }
+}
+
+/// `indent`, a run of spaces and tabs, written in the style of `retab`
+/// with the same width: tabs stop at every multiple of the width, whatever
+/// mix of spaces and tabs reached them.
+pub fn reindent(indent: &[u8], retab: Retab) -> Vec<u8> {
+    let width = retab.width();
+    let columns = indent.iter().fold(0, |column, &byte| match byte {
+        b'\t' => (column / width + 1) * width,
+        _ => column + 1,
+    });
+    match retab {
+        Retab::Spaces(_) => vec![b' '; columns],
+        Retab::Tabs(_) => {
+            let mut indent = vec![b'\t'; columns / width];
+            indent.resize(indent.len() + columns % width, b' ');
+            indent
+        }
+    }
+}
+
+/// The ending `--convert auto` picks from a file's CRLF, LF and CR counts:
+/// whichever is most common. A tie, a file without endings and one that is
+/// mostly CR, which `--convert` does not write, are left alone.
+pub fn auto_target(counts: [usize; 3]) -> Result<ConvertTo, String> {
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let leaders: Vec<&str> = STYLES
+        .iter()
+        .zip(counts)
+        .filter(|&(_, count)| count == most)
+        .map(|(&name, _)| name)
+        .collect();
+    match (most, leaders.as_slice()) {
+        (0, _) => Err("no line endings".to_string()),
+        (_, ["CRLF"]) => Ok(ConvertTo::Crlf),
+        (_, ["LF"]) => Ok(ConvertTo::Lf),
+        (_, ["CR"]) => Err("mostly CR".to_string()),
+        (_, _) => Err(format!("tie between {}", leaders.join(" and "))),
+    }
+}
+
+/// What `rewrite` does to each line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Edits {
+    /// Replace every CRLF, LF and bare CR with this ending.
+    pub target: Option<ConvertTo>,
+    /// Remove the spaces and tabs at the end of each line.
+    pub strip_trailing_whitespace: bool,
+    /// Terminate an unterminated last line, with `target` or else the most
+    /// common ending the file had.
+    pub final_newline: bool,
+    /// Remove a leading UTF-8 byte-order mark.
+    pub strip_bom: bool,
+    /// Rewrite the leading spaces and tabs of each line in this style.
+    pub retab: Option<Retab>,
+    /// Keep at most this many blank lines in a row.
+    pub squeeze_blank: Option<usize>,
+    /// Count lines of only spaces and tabs as blank for `squeeze_blank`.
+    pub blank_as_empty: bool,
+}
+
+/// What `rewrite` changed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Changes {
+    /// The CRLF, LF and CR endings replaced.
+    pub endings: [usize; 3],
+    /// The lines trailing whitespace was removed from.
+    pub trailing_whitespace: usize,
+    /// The terminator added to the last line.
+    pub final_newline: Option<Ending>,
+    /// Whether a UTF-8 byte-order mark was removed.
+    pub bom_stripped: bool,
+    /// The lines whose indentation was rewritten.
+    pub retabbed: usize,
+    /// The blank lines removed.
+    pub blank_lines_removed: usize,
+}
+
+/// Writes lines out as `rewrite` finds their content and terminators.
+struct Lines<'a, W> {
+    writer: &'a mut W,
+    edits: Edits,
+    changes: Changes,
+    /// Spaces and tabs that end the content so far, held back until it is
+    /// known whether more content follows them on the same line.
+    spaces: Vec<u8>,
+    /// Whether the current line has any content, whitespace included.
+    open: bool,
+    /// The CRLF, LF and CR endings found.
+    found: [usize; 3],
+    /// Whether the line so far is all spaces and tabs, which `--fix retab`
+    /// and `--fix squeeze-blank` hold back in `indent` until they see where
+    /// the indentation ends or that there is nothing else.
+    indenting: bool,
+    indent: Vec<u8>,
+    /// The blank lines just before this one.
+    blank_run: usize,
+}
+
+impl<W: Write> Lines<'_, W> {
+    fn content(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.open |= !bytes.is_empty();
+        if !self.indenting || !self.holds_indent() {
+            return self.text(bytes);
+        }
+        let Some(end) = bytes.iter().position(|&byte| byte != b' ' && byte != b'\t') else {
+            self.indent.extend_from_slice(bytes);
+            return Ok(());
+        };
+        self.indenting = false;
+        self.indent.extend_from_slice(&bytes[..end]);
+        let mut indent = std::mem::take(&mut self.indent);
+        if let Some(retab) = self.edits.retab {
+            let reindented = reindent(&indent, retab);
+            if reindented != indent {
+                self.changes.retabbed += 1;
+            }
+            indent = reindented;
+        }
+        self.text(&indent)?;
+        self.text(&bytes[end..])
+    }
+
+    fn holds_indent(&self) -> bool {
+        self.edits.retab.is_some() || self.squeezes_whitespace()
+    }
+
+    /// Whether `--fix squeeze-blank` treats a line of spaces and tabs as
+    /// blank: with `--blank-as-empty`, or when they are being stripped.
+    fn squeezes_whitespace(&self) -> bool {
+        self.edits.squeeze_blank.is_some()
+            && (self.edits.blank_as_empty || self.edits.strip_trailing_whitespace)
+    }
+
+    /// Drops the line just ended, terminator and all, if it is blank and
+    /// there are already as many blank lines before it as may be kept.
+    fn squeezed(&mut self) -> bool {
+        let Some(limit) = self.edits.squeeze_blank else {
+            return false;
+        };
+        let blank = if self.squeezes_whitespace() {
+            self.indenting
+        } else {
+            !self.open
+        };
+        if !blank {
+            self.blank_run = 0;
+            return false;
+        }
+        self.blank_run += 1;
+        if self.blank_run <= limit {
+            return false;
+        }
+        self.indent.clear();
+        self.open = false;
+        self.changes.blank_lines_removed += 1;
+        true
+    }
+
+    /// Writes content past the indentation.
+    fn text(&mut self, bytes: &[u8]) -> io::Result<()> {
+        if !self.edits.strip_trailing_whitespace {
+            return self.writer.write_all(bytes);
+        }
+        match bytes
+            .iter()
+            .rposition(|&byte| byte != b' ' && byte != b'\t')
+        {
+            Some(last) => {
+                self.writer.write_all(&self.spaces)?;
+                self.spaces.clear();
+                self.writer.write_all(&bytes[..=last])?;
+                self.spaces.extend_from_slice(&bytes[last + 1..]);
+            }
+            None => self.spaces.extend_from_slice(bytes),
+        }
+        Ok(())
+    }
+
+    /// Ends the line, dropping any held back whitespace. A line of nothing
+    /// but spaces and tabs has no indentation to rewrite, so those are kept
+    /// for `--fix trailing-whitespace` to strip or leave.
+    fn end(&mut self) -> io::Result<()> {
+        self.indenting = true;
+        if !self.indent.is_empty() {
+            let indent = std::mem::take(&mut self.indent);
+            self.text(&indent)?;
+        }
+        if !self.spaces.is_empty() {
+            self.spaces.clear();
+            self.changes.trailing_whitespace += 1;
+        }
+        Ok(())
+    }
+
+    /// Ends the line with the terminator of `style`, an index into `STYLES`.
+    fn terminate(&mut self, style: usize) -> io::Result<()> {
+        self.found[style] += 1;
+        if self.squeezed() {
+            return Ok(());
+        }
+        self.end()?;
+        self.open = false;
+        let found = TERMINATORS[style];
+        let terminator = self.edits.target.map_or(found, ConvertTo::terminator);
+        if terminator != found {
+            self.changes.endings[style] += 1;
+        }
+        self.writer.write_all(terminator)
+    }
+}
+
+/// Copies `reader` to `writer` with `edits` applied to every line, where
+/// lines end in CRLF, LF or a bare CR. Everything else is copied as it is,
+/// and a last line without a terminator is left without one unless
+/// `edits.final_newline` says otherwise. A leading UTF-8 byte-order mark is
+/// not part of the first line, so it neither makes an empty file a line to
+/// terminate nor is stripped as content.
+pub fn rewrite(
+    mut reader: impl Read,
+    writer: &mut impl Write,
+    edits: Edits,
+) -> io::Result<Changes> {
+    let mut lines = Lines {
+        writer,
+        edits,
+        changes: Changes::default(),
+        spaces: Vec::new(),
+        open: false,
+        found: [0; 3],
+        indenting: true,
+        indent: Vec::new(),
+        blank_run: 0,
+    };
+    let bom = Bom::Utf8.bytes();
+    let mut head = Vec::with_capacity(bom.len());
+    (&mut reader)
+        .take(bom.len() as u64)
+        .read_to_end(&mut head)?;
+    if head == bom {
+        head.clear();
+        if edits.strip_bom {
+            lines.changes.bom_stripped = true;
+        } else {
+            lines.writer.write_all(bom)?;
+        }
+    }
+    let mut reader = head.as_slice().chain(reader);
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    // A CR at the end of one read may be the first half of a CRLF.
+    let mut pending_cr = false;
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        let chunk = &buffer[..read];
+        let mut start = 0;
+        if pending_cr {
+            pending_cr = false;
+            let crlf = chunk[0] == b'\n';
+            lines.terminate(if crlf { 0 } else { 2 })?;
+            start = usize::from(crlf);
+        }
+        while let Some(offset) = chunk[start..]
+            .iter()
+            .position(|&byte| byte == b'\n' || byte == b'\r')
+        {
+            let at = start + offset;
+            lines.content(&chunk[start..at])?;
+            let style = match chunk.get(at..at + 2) {
+                _ if chunk[at] == b'\n' => 1,
+                Some(b"\r\n") => 0,
+                Some(_) => 2,
+                None => {
+                    pending_cr = true;
+                    start = chunk.len();
+                    break;
+                }
+            };
+            lines.terminate(style)?;
+            start = at + TERMINATORS[style].len();
+        }
+        lines.content(&chunk[start..])?;
+    }
+    if pending_cr {
+        lines.terminate(2)?;
+    }
+    let open = lines.open && !lines.squeezed();
+    lines.end()?;
+    if open && edits.final_newline {
+        let ending = final_newline(lines.found, edits.target);
+        lines.writer.write_all(TERMINATORS[style(ending)])?;
+        lines.changes.final_newline = Some(ending);
+    }
+    Ok(lines.changes)
+}
+
+/// Applies `edits` to the file at `path` in place, through
+/// `atomic::replace`.
+pub fn convert_file(path: &str, edits: Edits, in_place: &InPlace) -> io::Result<Changes> {
+    atomic::replace(path, in_place, |writer| {
+        rewrite(File::open(osname::path(path))?, writer, edits)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::atomic::Backup;
+    use std::fs;
+
+    /// Hands out at most `chunk` bytes per read, to split terminators.
+    struct Chunked<'a> {
+        bytes: &'a [u8],
+        chunk: usize,
+    }
+
+    impl Read for Chunked<'_> {
+        fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+            let take = self.bytes.len().min(self.chunk).min(buffer.len());
+            buffer[..take].copy_from_slice(&self.bytes[..take]);
+            self.bytes = &self.bytes[take..];
+            Ok(take)
+        }
+    }
+
+    fn convert(bytes: &[u8], chunk: usize, edits: Edits) -> (Vec<u8>, Changes) {
+        let mut output = Vec::new();
+        let changes = rewrite(Chunked { bytes, chunk }, &mut output, edits).unwrap();
+        (output, changes)
+    }
+
+    fn to(target: ConvertTo) -> Edits {
+        Edits {
+            target: Some(target),
+            ..Edits::default()
+        }
+    }
+
+    fn changed(endings: [usize; 3], trailing_whitespace: usize) -> Changes {
+        Changes {
+            endings,
+            trailing_whitespace,
+            ..Changes::default()
+        }
+    }
+
+    #[test]
+    fn test_rewrite_to_lf() {
+        let input = b"one\r\ntwo\nthree\rfour\r\n\r\nlast";
+        for chunk in [1, 2, 3, 5, input.len()] {
+            assert_eq!(
+                convert(input, chunk, to(ConvertTo::Lf)),
+                (
+                    b"one\ntwo\nthree\nfour\n\nlast".to_vec(),
+                    changed([3, 0, 1], 0)
+                ),
+                "chunk {}",
+                chunk
+            );
+        }
+    }
+
+    #[test]
+    fn test_rewrite_to_crlf() {
+        let input = b"one\r\ntwo\nthree\r";
+        for chunk in [1, 4, 15, input.len()] {
+            assert_eq!(
+                convert(input, chunk, to(ConvertTo::Crlf)),
+                (b"one\r\ntwo\r\nthree\r\n".to_vec(), changed([0, 1, 1], 0)),
+                "chunk {}",
+                chunk
+            );
+        }
+        assert_eq!(
+            convert(b"\xff\0\r\n", 1, to(ConvertTo::Crlf)),
+            (b"\xff\0\r\n".to_vec(), changed([0, 0, 0], 0))
+        );
+    }
+
+    #[test]
+    fn test_strip_trailing_whitespace() {
+        let strip = Edits {
+            strip_trailing_whitespace: true,
+            ..Edits::default()
+        };
+        let input = b"a b \t\r\n  \nkeep\r\n\tx\ry  \t";
+        for chunk in [1, 2, 3, 7, input.len()] {
+            assert_eq!(
+                convert(input, chunk, strip),
+                (b"a b\r\n\nkeep\r\n\tx\ry".to_vec(), changed([0, 0, 0], 3)),
+                "chunk {}",
+                chunk
+            );
+        }
+        let both = Edits {
+            target: Some(ConvertTo::Lf),
+            ..strip
+        };
+        assert_eq!(
+            convert(b"a \r\nb\r\n", 1, both),
+            (b"a\nb\n".to_vec(), changed([2, 0, 0], 1))
+        );
+        assert_eq!(
+            convert(b"   ", 1, strip),
+            (Vec::new(), changed([0, 0, 0], 1))
+        );
+    }
+
+    #[test]
+    fn test_final_newline() {
+        let add = Edits {
+            final_newline: true,
+            ..Edits::default()
+        };
+        let added = |ending| Changes {
+            final_newline: Some(ending),
+            ..Changes::default()
+        };
+        assert_eq!(
+            convert(b"a\r\nb\r\nc", 1, add),
+            (b"a\r\nb\r\nc\r\n".to_vec(), added(Ending::Crlf))
+        );
+        assert_eq!(convert(b"a", 1, add), (b"a\n".to_vec(), added(Ending::Lf)));
+        assert_eq!(convert(b"", 1, add), (Vec::new(), Changes::default()));
+        assert_eq!(
+            convert(b"a\r", 1, add),
+            (b"a\r".to_vec(), Changes::default())
+        );
+        let all = Edits {
+            target: Some(ConvertTo::Crlf),
+            strip_trailing_whitespace: true,
+            final_newline: true,
+            strip_bom: false,
+            retab: None,
+            squeeze_blank: None,
+            blank_as_empty: false,
+        };
+        assert_eq!(
+            convert(b"a\nb  ", 2, all),
+            (
+                b"a\r\nb\r\n".to_vec(),
+                Changes {
+                    endings: [0, 1, 0],
+                    trailing_whitespace: 1,
+                    final_newline: Some(Ending::Crlf),
+                    bom_stripped: false,
+                    retabbed: 0,
+                    blank_lines_removed: 0,
+                }
+            )
+        );
+        assert_eq!(final_newline([1, 2, 3], None), Ending::Cr);
+        assert_eq!(final_newline([2, 2, 0], None), Ending::Lf);
+        assert_eq!(final_newline([2, 1, 2], None), Ending::Crlf);
+        assert_eq!(
+            final_newline([0, 5, 0], Some(ConvertTo::Crlf)),
+            Ending::Crlf
+        );
+    }
+
+    #[test]
+    fn test_strip_bom() {
+        let strip = Edits {
+            strip_bom: true,
+            ..Edits::default()
+        };
+        let stripped = Changes {
+            bom_stripped: true,
+            ..Changes::default()
+        };
+        let input = b"\xef\xbb\xbfa\r\nb";
+        for chunk in [1, 2, 3, input.len()] {
+            assert_eq!(
+                convert(input, chunk, strip),
+                (b"a\r\nb".to_vec(), stripped),
+                "chunk {}",
+                chunk
+            );
+        }
+        assert_eq!(convert(b"\xef\xbb\xbf", 1, strip), (Vec::new(), stripped));
+        for kept in [&b"\xef\xbb"[..], b"\xef\xbbx\xbf", b"a\xef\xbb\xbf", b""] {
+            assert_eq!(convert(kept, 1, strip), (kept.to_vec(), Changes::default()));
+        }
+        let add = Edits {
+            final_newline: true,
+            ..Edits::default()
+        };
+        assert_eq!(
+            convert(b"\xef\xbb\xbf", 2, add),
+            (b"\xef\xbb\xbf".to_vec(), Changes::default())
+        );
+    }
+
+    #[test]
+    fn test_retab() {
+        let retab = |retab| Edits {
+            retab: Some(retab),
+            ..Edits::default()
+        };
+        let retabbed = |lines| Changes {
+            retabbed: lines,
+            ..Changes::default()
+        };
+        let input = b"\tone\r\n\t\ttwo\t\"\t\"\n    three\rfour\n \t x";
+        for chunk in [1, 2, 5, input.len()] {
+            assert_eq!(
+                convert(input, chunk, retab(Retab::Spaces(4))),
+                (
+                    b"    one\r\n        two\t\"\t\"\n    three\rfour\n     x".to_vec(),
+                    retabbed(3)
+                ),
+                "chunk {}",
+                chunk
+            );
+            assert_eq!(
+                convert(input, chunk, retab(Retab::Tabs(4))),
+                (
+                    b"\tone\r\n\t\ttwo\t\"\t\"\n\tthree\rfour\n\t x".to_vec(),
+                    retabbed(2)
+                ),
+                "chunk {}",
+                chunk
+            );
+        }
+        assert_eq!(
+            convert(b" \t\n\t", 1, retab(Retab::Spaces(2))),
+            (b" \t\n\t".to_vec(), Changes::default())
+        );
+        let both = Edits {
+            strip_trailing_whitespace: true,
+            ..retab(Retab::Spaces(2))
+        };
+        assert_eq!(
+            convert(b"\tx \n \t\n", 1, both),
+            (
+                b"  x\n\n".to_vec(),
+                Changes {
+                    trailing_whitespace: 2,
+                    retabbed: 1,
+                    ..Changes::default()
+                }
+            )
+        );
+        assert_eq!(reindent(b"  \t", Retab::Spaces(4)), b"    ");
+        assert_eq!(reindent(b"      ", Retab::Tabs(4)), b"\t  ");
+        assert_eq!(reindent(b"\t \t", Retab::Tabs(8)), b"\t\t");
+        assert_eq!(reindent(b"", Retab::Tabs(8)), b"");
+    }
+
+    #[test]
+    fn test_squeeze_blank() {
+        let squeeze = |limit| Edits {
+            squeeze_blank: Some(limit),
+            ..Edits::default()
+        };
+        let removed = |lines| Changes {
+            blank_lines_removed: lines,
+            ..Changes::default()
+        };
+        let input = b"a\r\n\r\n\n\r\n\rb\n\n \n\n\nc\n\n\n\n";
+        for chunk in [1, 2, 3, input.len()] {
+            assert_eq!(
+                convert(input, chunk, squeeze(1)),
+                (b"a\r\n\r\nb\n\n \n\nc\n\n".to_vec(), removed(6)),
+                "chunk {}",
+                chunk
+            );
+        }
+        assert_eq!(
+            convert(input, 1, squeeze(0)),
+            (b"a\r\nb\n \nc\n".to_vec(), removed(10))
+        );
+        let whitespace = Edits {
+            blank_as_empty: true,
+            ..squeeze(1)
+        };
+        assert_eq!(
+            convert(b"a\n\n \n\t\nb\n\n  ", 1, whitespace),
+            (b"a\n\nb\n\n".to_vec(), removed(3))
+        );
+        let stripped = Edits {
+            strip_trailing_whitespace: true,
+            final_newline: true,
+            ..squeeze(1)
+        };
+        assert_eq!(
+            convert(b"a\n \n\t\nb\n  ", 1, stripped),
+            (
+                b"a\n\nb\n\n".to_vec(),
+                Changes {
+                    trailing_whitespace: 2,
+                    blank_lines_removed: 1,
+                    final_newline: Some(Ending::Lf),
+                    ..Changes::default()
+                }
+            )
+        );
+        assert_eq!(
+            convert(b"\n\n", 1, squeeze(2)),
+            (b"\n\n".to_vec(), removed(0))
+        );
+    }
+
+    #[test]
+    fn test_auto_target() {
+        assert_eq!(auto_target([3, 1, 0]), Ok(ConvertTo::Crlf));
+        assert_eq!(auto_target([1, 2, 1]), Ok(ConvertTo::Lf));
+        assert_eq!(auto_target([0, 0, 0]), Err("no line endings".to_string()));
+        assert_eq!(
+            auto_target([2, 2, 1]),
+            Err("tie between CRLF and LF".to_string())
+        );
+        assert_eq!(auto_target([1, 0, 4]), Err("mostly CR".to_string()));
+    }
+
+    #[test]
+    fn test_outcome_summary() {
+        let converted = |endings, dry_run| Outcome {
+            target: Some(ConvertTo::Lf),
+            endings,
+            dry_run,
+            ..Outcome::default()
+        };
+        assert_eq!(converted([0, 0, 0], false).summary(), "already LF");
+        assert_eq!(
+            converted([1, 0, 0], false).summary(),
+            "converted 1 line ending to LF"
+        );
+        assert_eq!(
+            converted([10, 0, 2], false).summary(),
+            "converted 12 line endings to LF"
+        );
+        let planned = converted(pending([37, 5, 2], ConvertTo::Lf), true);
+        assert_eq!(
+            planned.summary(),
+            "would convert 37 CRLF and 2 CR \u{2192} LF"
+        );
+        assert!(planned.would_change());
+        assert!(!converted([0, 0, 0], true).would_change());
+        let declined = Outcome {
+            decision: Some(Decision::Declined),
+            ..planned.clone()
+        };
+        assert_eq!(
+            declined.summary(),
+            "declined; would convert 37 CRLF and 2 CR \u{2192} LF"
+        );
+        let unvisited = Outcome {
+            decision: Some(Decision::Unvisited),
+            ..Outcome::default()
+        };
+        assert_eq!(unvisited.summary(), "not visited");
+
+        let left_alone = Outcome {
+            left_alone: Some("mostly CR".to_string()),
+            trailing_whitespace: Some(2),
+            ..Outcome::default()
+        };
+        assert_eq!(
+            left_alone.summary(),
+            "left alone: mostly CR, stripped trailing whitespace on 2 lines"
+        );
+        let fixed = Outcome {
+            trailing_whitespace: Some(1),
+            dry_run: true,
+            ..Outcome::default()
+        };
+        assert_eq!(fixed.summary(), "would strip trailing whitespace on 1 line");
+        let terminated = |dry_run| Outcome {
+            final_newline: Some(Ending::Crlf),
+            dry_run,
+            ..Outcome::default()
+        };
+        assert_eq!(terminated(false).summary(), "added final newline (CRLF)");
+        assert_eq!(terminated(true).summary(), "would add final newline (CRLF)");
+        assert!(terminated(true).would_change());
+        assert_eq!(Outcome::default().summary(), "");
+        let bom = Outcome {
+            bom_stripped: true,
+            trailing_whitespace: Some(0),
+            ..Outcome::default()
+        };
+        assert_eq!(bom.summary(), "stripped UTF-8 BOM, no trailing whitespace");
+        let reindented = |retabbed, dry_run| Outcome {
+            retab: Some(Retab::Tabs(4)),
+            retabbed,
+            dry_run,
+            ..Outcome::default()
+        };
+        assert_eq!(reindented(0, true).summary(), "no indentation to change");
+        assert_eq!(
+            reindented(1, false).summary(),
+            "reindented 1 line with tabs"
+        );
+        assert_eq!(
+            reindented(3, true).summary(),
+            "would reindent 3 lines with tabs"
+        );
+        let squeezed = |lines, dry_run| Outcome {
+            blank_lines_removed: Some(lines),
+            dry_run,
+            ..Outcome::default()
+        };
+        assert_eq!(squeezed(0, false).summary(), "no blank lines to squeeze");
+        assert_eq!(squeezed(1, false).summary(), "removed 1 blank line");
+        assert_eq!(squeezed(4, true).summary(), "would remove 4 blank lines");
+        let skipped = Outcome {
+            skipped: Some("binary file".to_string()),
+            ..Outcome::default()
+        };
+        assert_eq!(skipped.summary(), "skipped: binary file");
+    }
+
+    #[test]
+    fn test_convert_file() {
+        let path = "convert_unit.txt";
+        fs::write(path, "a\r\nb\r\nc").unwrap();
+        assert_eq!(
+            convert_file(path, to(ConvertTo::Lf), &InPlace::default()).unwrap(),
+            changed([2, 0, 0], 0)
+        );
+        assert_eq!(fs::read(path).unwrap(), b"a\nb\nc");
+        fs::remove_file(path).unwrap();
+        assert!(convert_file("no/such/file", to(ConvertTo::Lf), &InPlace::default()).is_err());
+    }
+
+    #[test]
+    fn test_convert_file_with_backup() {
+        let path = "convert_backup.txt";
+        let original = b"a\r\nb\n\xffc\r";
+        fs::write(path, original).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(path, fs::Permissions::from_mode(0o640)).unwrap();
+        }
+        let backup = InPlace {
+            backup: Some(Backup {
+                suffix: ".orig".to_string(),
+                overwrite: false,
+            }),
+            preserve_mtime: false,
+        };
+        assert_eq!(
+            convert_file(path, to(ConvertTo::Crlf), &backup).unwrap(),
+            changed([0, 1, 1], 0)
+        );
+        assert_eq!(fs::read("convert_backup.txt.orig").unwrap(), original);
+        assert_eq!(fs::read(path).unwrap(), b"a\r\nb\r\n\xffc\r\n");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata("convert_backup.txt.orig")
+                .unwrap()
+                .permissions()
+                .mode();
+            assert_eq!(mode & 0o777, 0o640);
+        }
+
+        fs::write(path, "x\n").unwrap();
+        let error = convert_file(path, to(ConvertTo::Crlf), &backup).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(path).unwrap(), b"x\n");
+        let overwrite = InPlace {
+            backup: Some(Backup {
+                suffix: ".orig".to_string(),
+                overwrite: true,
+            }),
+            preserve_mtime: false,
+        };
+        convert_file(path, to(ConvertTo::Crlf), &overwrite).unwrap();
+        assert_eq!(fs::read("convert_backup.txt.orig").unwrap(), b"x\n");
+        fs::remove_file(path).unwrap();
+        fs::remove_file("convert_backup.txt.orig").unwrap();
+    }
+}
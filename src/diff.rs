@@ -0,0 +1,421 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::fs;
+use std::io::{self, Write};
+
+use crate::cli::{self, FailOn};
+use crate::format::json::{self, string};
+use crate::format::{escape_name, OutputFormat};
+use crate::{ending_counts_summary, Exit, FileStats, MdltError};
+
+/// How a file's line endings changed from the baseline report to the
+/// current one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    /// More CRLF, CR or minority endings than before, or a new file with
+    /// any of them.
+    Regression,
+    /// Fewer of them, and no more of any.
+    Improvement,
+    /// The line ending type changed with neither, as when a file that had
+    /// no endings gains LF ones.
+    Other,
+    /// In the baseline but not in the current report.
+    Removed,
+}
+
+impl Kind {
+    fn name(self) -> &'static str {
+        match self {
+            Kind::Regression => "regression",
+            Kind::Improvement => "improvement",
+            Kind::Other => "other",
+            Kind::Removed => "removed",
+        }
+    }
+}
+
+/// A file that changed, with its entries in the two reports.
+#[derive(Debug)]
+pub struct Change<'a> {
+    pub kind: Kind,
+    pub before: Option<&'a FileStats>,
+    pub after: Option<&'a FileStats>,
+}
+
+impl Change<'_> {
+    fn file_name(&self) -> Cow<'_, str> {
+        let file = self.after.or(self.before).expect("a change has a side");
+        file.name()
+    }
+
+    /// What changed, e.g. "Unix/Linux (LF) -> DOS/Windows (CRLF), CRLF 0 -> 40".
+    fn details(&self) -> String {
+        let (before, after) = match (self.before, self.after) {
+            (Some(before), Some(after)) => (before, after),
+            (None, Some(after)) => return format!("new, {}", ending_counts_summary(after)),
+            _ => return "removed".to_string(),
+        };
+        let mut details = Vec::new();
+        let (was, is) = (before.line_ending(), after.line_ending());
+        if was != is {
+            details.push(format!("{} -> {}", was, is));
+        }
+        for ((name, old), (_, new)) in problems(before).into_iter().zip(problems(after)) {
+            if old != new {
+                details.push(format!("{} {} -> {}", name, old, new));
+            }
+        }
+        details.join(", ")
+    }
+}
+
+/// The counts that grow as a file strays from consistent LF endings.
+fn problems(file: &FileStats) -> [(&'static str, usize); 3] {
+    let counts = file.ending_counts();
+    let total: usize = counts.iter().sum();
+    let most = counts.iter().copied().max().unwrap_or(0);
+    [
+        ("CRLF", file.dos_endings),
+        ("CR", file.mac_endings),
+        ("minority endings", total - most),
+    ]
+}
+
+fn classify(before: Option<&FileStats>, after: Option<&FileStats>) -> Option<Kind> {
+    match (before, after) {
+        (Some(_), None) => Some(Kind::Removed),
+        (None, Some(after)) => problems(after)
+            .iter()
+            .any(|&(_, count)| count > 0)
+            .then_some(Kind::Regression),
+        (Some(before), Some(after)) => {
+            let pairs = problems(before).into_iter().zip(problems(after));
+            let (mut worse, mut better) = (false, false);
+            for ((_, old), (_, new)) in pairs {
+                worse |= new > old;
+                better |= new < old;
+            }
+            if worse {
+                Some(Kind::Regression)
+            } else if better {
+                Some(Kind::Improvement)
+            } else if before.line_ending() != after.line_ending() {
+                Some(Kind::Other)
+            } else {
+                None
+            }
+        }
+        (None, None) => None,
+    }
+}
+
+/// The files that changed, matched by name: those in `current` in its
+/// order, then the ones no longer there in `baseline` order. New files are
+/// only listed when they have something wrong with their endings.
+pub fn compare<'a>(baseline: &'a [FileStats], current: &'a [FileStats]) -> Vec<Change<'a>> {
+    let before: HashMap<&OsStr, &FileStats> = baseline
+        .iter()
+        .map(|file| (file.file_name(), file))
+        .collect();
+    let after: HashMap<&OsStr, &FileStats> = current
+        .iter()
+        .map(|file| (file.file_name(), file))
+        .collect();
+    let mut changes = Vec::new();
+    for file in current {
+        let before = before.get(file.file_name()).copied();
+        if let Some(kind) = classify(before, Some(file)) {
+            changes.push(Change {
+                kind,
+                before,
+                after: Some(file),
+            });
+        }
+    }
+    for file in baseline {
+        if !after.contains_key(file.file_name()) {
+            changes.push(Change {
+                kind: Kind::Removed,
+                before: Some(file),
+                after: None,
+            });
+        }
+    }
+    changes
+}
+
+/// Lists the changes grouped by kind, then counts them: "2 regressions,
+/// 1 improvement".
+fn write_text(changes: &[Change], mut writer: impl Write) -> io::Result<()> {
+    let groups = [
+        (Kind::Regression, "Regressions", "regression", "regressions"),
+        (
+            Kind::Improvement,
+            "Improvements",
+            "improvement",
+            "improvements",
+        ),
+        (
+            Kind::Other,
+            "Other changes",
+            "other change",
+            "other changes",
+        ),
+        (Kind::Removed, "Removed", "removed file", "removed files"),
+    ];
+    let mut counts = Vec::new();
+    for (kind, heading, one, many) in groups {
+        let group: Vec<&Change> = changes
+            .iter()
+            .filter(|change| change.kind == kind)
+            .collect();
+        if group.is_empty() {
+            continue;
+        }
+        writeln!(writer, "{}:", heading)?;
+        for change in &group {
+            match kind {
+                Kind::Removed => writeln!(writer, "  {}", escape_name(&change.file_name()))?,
+                _ => writeln!(
+                    writer,
+                    "  {}: {}",
+                    escape_name(&change.file_name()),
+                    change.details()
+                )?,
+            }
+        }
+        counts.push(format!(
+            "{} {}",
+            group.len(),
+            if group.len() == 1 { one } else { many }
+        ));
+    }
+    if counts.is_empty() {
+        return writeln!(writer, "No line ending changes");
+    }
+    writeln!(writer)?;
+    writeln!(writer, "{}", counts.join(", "))
+}
+
+/// Writes the changes as a JSON array of `{"file_name", "change",
+/// "before", "after"}` objects, where `change` is `regression`,
+/// `improvement`, `other` or `removed` and each side holds the
+/// `line_ending_type` and ending counts, or is `null` for a new or removed
+/// file.
+fn write_json(changes: &[Change], mut writer: impl Write) -> io::Result<()> {
+    let side = |file: Option<&FileStats>| {
+        file.map_or("null".to_string(), |file| {
+            format!(
+                "{{\"line_ending_type\": {}, \"dos_endings\": {}, \"unix_endings\": {}, \
+                 \"mac_endings\": {}}}",
+                string(&file.line_ending_type()),
+                file.dos_endings,
+                file.unix_endings,
+                file.mac_endings
+            )
+        })
+    };
+    writeln!(writer, "[")?;
+    for (index, change) in changes.iter().enumerate() {
+        let separator = if index + 1 < changes.len() { "," } else { "" };
+        writeln!(
+            writer,
+            "  {{\"file_name\": {}, \"change\": \"{}\", \"before\": {}, \"after\": {}}}{}",
+            string(&change.file_name()),
+            change.kind.name(),
+            side(change.before),
+            side(change.after),
+            separator
+        )?;
+    }
+    writeln!(writer, "]")
+}
+
+/// Reads a report written by `--format json`, of this version of the
+/// layout or an earlier one.
+fn load(path: &str) -> Result<Vec<FileStats>, MdltError> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| MdltError::io(format!("reading report {}", path), e))?;
+    let invalid = |e: String| {
+        MdltError::Usage(format!(
+            "{} is not a report of mdlt --format json: {}",
+            path, e
+        ))
+    };
+    let report = json::parse(&text).map_err(invalid)?;
+    let version = json::version(&report).map_err(invalid)?;
+    if version > json::VERSION {
+        return Err(MdltError::Usage(format!(
+            "{} is a version {} report, and mdlt {} reads reports up to version {}; \
+             compare it with the mdlt that wrote it",
+            path,
+            version,
+            env!("CARGO_PKG_VERSION"),
+            json::VERSION
+        )));
+    }
+    let (stats, _) = json::read(&report).map_err(invalid)?;
+    Ok(stats)
+}
+
+/// Runs `mdlt diff`, for which `args[1]` is `diff`.
+pub fn run(args: &[String]) -> Result<Exit, MdltError> {
+    let options = cli::parse_diff_args(args).map_err(MdltError::Usage)?;
+    if options.help {
+        println!("{}", cli::diff_usage(&args[0]));
+        return Ok(Exit::Clean);
+    }
+    let baseline = load(&options.baseline)?;
+    let current = load(&options.current)?;
+    let changes = compare(&baseline, &current);
+    let stdout = io::stdout().lock();
+    match options.format {
+        OutputFormat::Json => write_json(&changes, stdout),
+        _ => write_text(&changes, stdout),
+    }
+    .map_err(crate::writing_report)?;
+    let (failing, what) = match options.fail_on {
+        FailOn::Any => (changes.len(), "change"),
+        FailOn::Regression => (
+            changes
+                .iter()
+                .filter(|change| change.kind == Kind::Regression)
+                .count(),
+            "regression",
+        ),
+        FailOn::None => (0, ""),
+    };
+    if failing > 0 {
+        let message = format!(
+            "{} {}{} from {} to {}",
+            failing,
+            what,
+            if failing == 1 { "" } else { "s" },
+            options.baseline,
+            options.current
+        );
+        return Err(MdltError::check("diff", message));
+    }
+    Ok(Exit::Clean)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, [dos, unix, mac]: [usize; 3]) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.dos_endings = dos;
+        stats.unix_endings = unix;
+        stats.mac_endings = mac;
+        stats.total_lines = dos + unix + mac;
+        stats
+    }
+
+    fn render(baseline: &[FileStats], current: &[FileStats]) -> String {
+        let mut buffer = Vec::new();
+        write_text(&compare(baseline, current), &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_compare_classifies_changes() {
+        let baseline = [
+            file("flipped.txt", [0, 10, 0]),
+            file("worse.txt", [1, 10, 0]),
+            file("better.txt", [4, 10, 0]),
+            file("same.txt", [0, 10, 0]),
+            file("grew.txt", [0, 0, 0]),
+            file("gone.txt", [0, 3, 0]),
+        ];
+        let current = [
+            file("flipped.txt", [10, 0, 0]),
+            file("worse.txt", [3, 10, 0]),
+            file("better.txt", [0, 14, 0]),
+            file("same.txt", [0, 12, 0]),
+            file("grew.txt", [0, 2, 0]),
+            file("new.txt", [3, 12, 0]),
+            file("clean.txt", [0, 5, 0]),
+        ];
+        let changes = compare(&baseline, &current);
+        let kinds: Vec<(String, Kind)> = changes
+            .iter()
+            .map(|change| (change.file_name().into_owned(), change.kind))
+            .collect();
+        let kinds: Vec<(&str, Kind)> = kinds.iter().map(|(name, kind)| (&**name, *kind)).collect();
+        assert_eq!(
+            kinds,
+            [
+                ("flipped.txt", Kind::Regression),
+                ("worse.txt", Kind::Regression),
+                ("better.txt", Kind::Improvement),
+                ("grew.txt", Kind::Other),
+                ("new.txt", Kind::Regression),
+                ("gone.txt", Kind::Removed),
+            ]
+        );
+        assert_eq!(
+            render(&baseline, &current),
+            "Regressions:\n  \
+             flipped.txt: Unix/Linux (LF) -> DOS/Windows (CRLF), CRLF 0 -> 10\n  \
+             worse.txt: CRLF 1 -> 3, minority endings 1 -> 3\n  \
+             new.txt: new, 12 LF, 3 CRLF\n\
+             Improvements:\n  \
+             better.txt: CRLF 4 -> 0, minority endings 4 -> 0\n\
+             Other changes:\n  \
+             grew.txt: No line endings detected -> Unix/Linux (LF)\n\
+             Removed:\n  \
+             gone.txt\n\
+             \n\
+             3 regressions, 1 improvement, 1 other change, 1 removed file\n"
+        );
+        assert_eq!(render(&current, &current), "No line ending changes\n");
+    }
+
+    #[test]
+    fn test_load_checks_the_version() {
+        let path = std::env::temp_dir().join(format!("mdlt_diff_version_{}", std::process::id()));
+        let name = path.to_str().unwrap();
+        let files = "\"files\": [{\"file_name\": \"a.txt\", \"is_binary\": false}]";
+        fs::write(&path, format!("{{\"version\": 3, {}}}", files)).unwrap();
+        let Err(MdltError::Usage(message)) = load(name) else {
+            panic!("a newer report was read");
+        };
+        assert_eq!(
+            message,
+            format!(
+                "{} is a version 3 report, and mdlt {} reads reports up to version 2; \
+                 compare it with the mdlt that wrote it",
+                name,
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+        fs::write(&path, format!("{{\"version\": \"2\", {}}}", files)).unwrap();
+        let Err(MdltError::Usage(message)) = load(name) else {
+            panic!("a version that is not a number was read");
+        };
+        assert!(message.ends_with(": \"version\" is not a number"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_write_json() {
+        let baseline = [file("a.txt", [0, 2, 0]), file("b.txt", [0, 1, 0])];
+        let current = [file("a.txt", [1, 1, 0])];
+        let mut buffer = Vec::new();
+        write_json(&compare(&baseline, &current), &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "[\n  {\"file_name\": \"a.txt\", \"change\": \"regression\", \
+             \"before\": {\"line_ending_type\": \"Unix/Linux (LF)\", \"dos_endings\": 0, \
+             \"unix_endings\": 2, \"mac_endings\": 0}, \
+             \"after\": {\"line_ending_type\": \"Mixed line endings\", \"dos_endings\": 1, \
+             \"unix_endings\": 1, \"mac_endings\": 0}},\n  \
+             {\"file_name\": \"b.txt\", \"change\": \"removed\", \
+             \"before\": {\"line_ending_type\": \"Unix/Linux (LF)\", \"dos_endings\": 0, \
+             \"unix_endings\": 1, \"mac_endings\": 0}, \"after\": null}\n]\n"
+        );
+    }
+}
@@ -0,0 +1,226 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+use crate::figures;
+use crate::preview;
+
+/// How many of the most repeated lines are reported.
+pub const MOST_REPEATED: usize = 3;
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A line that appears more than once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RepeatedLine {
+    pub count: usize,
+    pub first_line: usize,
+    pub preview: String,
+}
+
+/// Result of `--duplicates`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DuplicateStats {
+    /// Different non-empty lines.
+    pub distinct_lines: usize,
+    /// Non-empty lines identical to an earlier line.
+    pub duplicate_lines: usize,
+    /// The `MOST_REPEATED` lines seen most often, most frequent first.
+    pub most_repeated: Vec<RepeatedLine>,
+}
+
+impl DuplicateStats {
+    /// "6 (120 distinct lines)", the counts grouped when `group`.
+    pub fn summary(&self, group: bool) -> String {
+        format!(
+            "{} ({})",
+            figures::count(self.duplicate_lines as u64, group),
+            figures::counted(self.distinct_lines as u64, "distinct line", group)
+        )
+    }
+
+    /// Writes the duplicate section of the text report.
+    pub fn write(&self, group: bool, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "Duplicate lines: {}", self.summary(group))?;
+        if !self.most_repeated.is_empty() {
+            writeln!(writer, "Most repeated lines:")?;
+        }
+        for line in &self.most_repeated {
+            writeln!(
+                writer,
+                "  {}x, first on line {}: {}",
+                figures::count(line.count as u64, group),
+                line.first_line,
+                line.preview
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    count: usize,
+    first_line: usize,
+    /// Taken when the line is first repeated, so unique lines cost no more
+    /// than their hash.
+    preview: Option<String>,
+}
+
+/// Spots repeated lines by their 64-bit FNV-1a hash rather than their text,
+/// so memory grows with the number of distinct lines but not their length.
+/// Two different lines could share a hash, but even a file with a million
+/// distinct lines has about a one in 37 million chance of that happening.
+/// Empty lines are left out.
+#[derive(Debug)]
+pub struct Tracker {
+    hash: u64,
+    lines: HashMap<u64, Entry>,
+    duplicate_lines: usize,
+}
+
+impl Default for Tracker {
+    fn default() -> Self {
+        Tracker {
+            hash: FNV_OFFSET,
+            lines: HashMap::new(),
+            duplicate_lines: 0,
+        }
+    }
+}
+
+impl Tracker {
+    /// Adds the next bytes of the current line's content.
+    pub fn push(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.hash = (self.hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Finishes line `number`, which is empty unless `has_content`. `head`
+    /// and `complete` describe its start as `preview::render` takes them.
+    pub fn end_line(&mut self, number: usize, has_content: bool, head: &[u8], complete: bool) {
+        let hash = std::mem::replace(&mut self.hash, FNV_OFFSET);
+        if !has_content {
+            return;
+        }
+        let entry = self.lines.entry(hash).or_insert(Entry {
+            count: 0,
+            first_line: number,
+            preview: None,
+        });
+        entry.count += 1;
+        if entry.count == 2 {
+            entry.preview = Some(preview::render(head, complete));
+            self.duplicate_lines += 1;
+        } else if entry.count > 2 {
+            self.duplicate_lines += 1;
+        }
+    }
+
+    pub fn finish(self) -> DuplicateStats {
+        let mut repeated: Vec<RepeatedLine> = self
+            .lines
+            .values()
+            .filter_map(|entry| {
+                Some(RepeatedLine {
+                    count: entry.count,
+                    first_line: entry.first_line,
+                    preview: entry.preview.clone()?,
+                })
+            })
+            .collect();
+        repeated.sort_by_key(|line| (std::cmp::Reverse(line.count), line.first_line));
+        repeated.truncate(MOST_REPEATED);
+        DuplicateStats {
+            distinct_lines: self.lines.len(),
+            duplicate_lines: self.duplicate_lines,
+            most_repeated: repeated,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(lines: &[&str]) -> DuplicateStats {
+        let mut tracker = Tracker::default();
+        for (index, line) in lines.iter().enumerate() {
+            tracker.push(line.as_bytes());
+            tracker.end_line(index + 1, !line.is_empty(), line.as_bytes(), true);
+        }
+        tracker.finish()
+    }
+
+    fn repeated(count: usize, first_line: usize, preview: &str) -> RepeatedLine {
+        RepeatedLine {
+            count,
+            first_line,
+            preview: preview.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_counts_and_ranking() {
+        let stats = track(&[
+            "b", "a", "", "b", "c", "a", "", "d", "b", "e", "e", "d", "a\tx", "a\tx",
+        ]);
+        assert_eq!(stats.distinct_lines, 6);
+        assert_eq!(stats.duplicate_lines, 6);
+        assert_eq!(
+            stats.most_repeated,
+            [
+                repeated(3, 1, "b"),
+                repeated(2, 2, "a"),
+                repeated(2, 8, "d")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_write_section() {
+        let stats = track(&["x", "y\0", "x", "y\0", "x"]);
+        let mut buffer = Vec::new();
+        stats.write(true, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Duplicate lines: 3 (2 distinct lines)\nMost repeated lines:\n  \
+             3x, first on line 1: x\n  2x, first on line 2: y\\0\n"
+        );
+    }
+
+    #[test]
+    fn test_write_groups_counts() {
+        let lines: Vec<String> = (0..2000).map(|line| (line % 2).to_string()).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        let stats = track(&lines);
+        let mut buffer = Vec::new();
+        stats.write(true, &mut buffer).unwrap();
+        let section = String::from_utf8(buffer).unwrap();
+        assert!(section.starts_with("Duplicate lines: 1,998 (2 distinct lines)\n"));
+        assert!(section.contains("\n  1,000x, first on line 1: 0\n"));
+        let mut buffer = Vec::new();
+        stats.write(false, &mut buffer).unwrap();
+        assert!(String::from_utf8(buffer)
+            .unwrap()
+            .contains("\n  1000x, first on line 1: 0\n"));
+        let one = track(&["a", "a"]);
+        assert_eq!(one.summary(true), "1 (1 distinct line)");
+    }
+
+    #[test]
+    fn test_no_repeats() {
+        let stats = track(&["one", "two", "", ""]);
+        assert_eq!(
+            stats,
+            DuplicateStats {
+                distinct_lines: 2,
+                duplicate_lines: 0,
+                most_repeated: Vec::new()
+            }
+        );
+    }
+}
@@ -0,0 +1,492 @@
+use std::collections::{BTreeSet, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::ExpectedEndings;
+
+/// One piece of an EditorConfig section glob.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Char(char),
+    /// `?`: any character but `/`.
+    AnyChar,
+    /// `*`: any run of characters without a `/`.
+    AnyRun,
+    /// `**`: any run of characters at all.
+    AnyPath,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    /// `{a,b,c}`.
+    Alternatives(Vec<Vec<Node>>),
+    /// `{1..10}`: an integer in the range.
+    Number(i64, i64),
+}
+
+/// A section name such as `*.{js,ts}` or `lib/**.rs`, matched the way the
+/// EditorConfig specification says: a glob without a `/` matches a file
+/// name in any directory, and one with a `/` matches the path relative to
+/// the `.editorconfig`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SectionGlob {
+    nodes: Vec<Node>,
+    anchored: bool,
+}
+
+impl SectionGlob {
+    fn new(pattern: &str) -> SectionGlob {
+        let anchored = pattern.contains('/');
+        let pattern = pattern.strip_prefix('/').unwrap_or(pattern);
+        let chars: Vec<char> = pattern.chars().collect();
+        SectionGlob {
+            nodes: parse_nodes(&chars),
+            anchored,
+        }
+    }
+
+    /// Matches `path`, relative to the directory of the `.editorconfig`
+    /// with `/` between components.
+    fn matches(&self, path: &str) -> bool {
+        let text: Vec<char> = if self.anchored {
+            path.chars().collect()
+        } else {
+            path.rsplit('/').next().unwrap_or(path).chars().collect()
+        };
+        if ends(&self.nodes, &text, BTreeSet::from([0])).contains(&text.len()) {
+            return true;
+        }
+        // `**/` also matches no directories at all.
+        match self.nodes.as_slice() {
+            [Node::AnyPath, Node::Char('/'), rest @ ..] => {
+                ends(rest, &text, BTreeSet::from([0])).contains(&text.len())
+            }
+            _ => false,
+        }
+    }
+}
+
+fn parse_nodes(chars: &[char]) -> Vec<Node> {
+    let mut nodes = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                nodes.push(Node::Char(chars[i + 1]));
+                i += 1;
+            }
+            '*' if chars.get(i + 1) == Some(&'*') => {
+                nodes.push(Node::AnyPath);
+                i += 1;
+            }
+            '*' => nodes.push(Node::AnyRun),
+            '?' => nodes.push(Node::AnyChar),
+            '[' => match parse_class(&chars[i + 1..]) {
+                Some((node, used)) => {
+                    nodes.push(node);
+                    i += used;
+                }
+                None => nodes.push(Node::Char('[')),
+            },
+            '{' => match parse_braces(&chars[i + 1..]) {
+                Some((node, used)) => {
+                    nodes.push(node);
+                    i += used;
+                }
+                None => nodes.push(Node::Char('{')),
+            },
+            c => nodes.push(Node::Char(c)),
+        }
+        i += 1;
+    }
+    nodes
+}
+
+/// Parses a class after its `[`, returning it and how many characters it
+/// used, or `None` if it is never closed.
+fn parse_class(chars: &[char]) -> Option<(Node, usize)> {
+    let negated = matches!(chars.first(), Some('!') | Some('^'));
+    let mut i = usize::from(negated);
+    let mut ranges = Vec::new();
+    loop {
+        let start = *chars.get(i)?;
+        if start == ']' && !ranges.is_empty() {
+            return Some((Node::Class { negated, ranges }, i + 1));
+        }
+        match (chars.get(i + 1), chars.get(i + 2)) {
+            (Some('-'), Some(&end)) if end != ']' => {
+                ranges.push((start, end));
+                i += 3;
+            }
+            _ => {
+                ranges.push((start, start));
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Parses braces after their `{`: alternatives split at top-level commas,
+/// or a numeric range. Braces with neither are literal.
+fn parse_braces(chars: &[char]) -> Option<(Node, usize)> {
+    let mut depth = 0;
+    let mut commas = Vec::new();
+    let mut close = None;
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' => i += 1,
+            '{' => depth += 1,
+            '}' if depth == 0 => {
+                close = Some(i);
+                break;
+            }
+            '}' => depth -= 1,
+            ',' if depth == 0 => commas.push(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    let close = close?;
+    let inner = &chars[..close];
+    if commas.is_empty() {
+        let text: String = inner.iter().collect();
+        let (lo, hi) = text.split_once("..")?;
+        return Some((Node::Number(lo.parse().ok()?, hi.parse().ok()?), close + 1));
+    }
+    let mut alternatives = Vec::new();
+    let mut start = 0;
+    for end in commas.into_iter().chain([close]) {
+        alternatives.push(parse_nodes(&inner[start..end]));
+        start = end + 1;
+    }
+    Some((Node::Alternatives(alternatives), close + 1))
+}
+
+/// Every position in `text` that matching `nodes` from one of `starts` can
+/// end at.
+fn ends(nodes: &[Node], text: &[char], starts: BTreeSet<usize>) -> BTreeSet<usize> {
+    let mut positions = starts;
+    for node in nodes {
+        let mut next = BTreeSet::new();
+        for &at in &positions {
+            let rest = &text[at..];
+            match node {
+                Node::Char(c) if rest.first() == Some(c) => {
+                    next.insert(at + 1);
+                }
+                Node::AnyChar if rest.first().is_some_and(|&c| c != '/') => {
+                    next.insert(at + 1);
+                }
+                Node::Class { negated, ranges } => {
+                    if let Some(&c) = rest.first() {
+                        let inside = ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi);
+                        if c != '/' && inside != *negated {
+                            next.insert(at + 1);
+                        }
+                    }
+                }
+                Node::AnyRun => {
+                    let run = rest.iter().take_while(|&&c| c != '/').count();
+                    next.extend(at..=at + run);
+                }
+                Node::AnyPath => next.extend(at..=text.len()),
+                Node::Alternatives(alternatives) => {
+                    for alternative in alternatives {
+                        next.extend(ends(alternative, text, BTreeSet::from([at])));
+                    }
+                }
+                Node::Number(lo, hi) => {
+                    let sign = usize::from(rest.first() == Some(&'-'));
+                    let digits = rest[sign..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count();
+                    for len in 1..=digits {
+                        let number: String = rest[..sign + len].iter().collect();
+                        if number.parse().is_ok_and(|n: i64| *lo <= n && n <= *hi) {
+                            next.insert(at + sign + len);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        positions = next;
+    }
+    positions
+}
+
+/// One `.editorconfig`: whether it is the root, and its sections in order.
+#[derive(Debug)]
+struct ConfigFile {
+    /// How reports name it, relative to the current directory when it is
+    /// inside it.
+    name: String,
+    root: bool,
+    sections: Vec<(SectionGlob, Vec<(String, String)>)>,
+}
+
+impl ConfigFile {
+    fn parse(name: String, contents: &str) -> ConfigFile {
+        let mut file = ConfigFile {
+            name,
+            root: false,
+            sections: Vec::new(),
+        };
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                file.sections.push((SectionGlob::new(section), Vec::new()));
+                continue;
+            }
+            let Some((key, value)) = line.split_once(['=', ':']) else {
+                continue;
+            };
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim().to_ascii_lowercase();
+            match file.sections.last_mut() {
+                Some((_, properties)) => properties.push((key, value)),
+                None if key == "root" => file.root = value == "true",
+                None => {}
+            }
+        }
+        file
+    }
+}
+
+/// A property's value and the `.editorconfig` that set it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Setting<T> {
+    pub value: T,
+    pub source: String,
+}
+
+/// `indent_style`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+impl IndentStyle {
+    pub fn name(self) -> &'static str {
+        match self {
+            IndentStyle::Tab => "tab",
+            IndentStyle::Space => "space",
+        }
+    }
+}
+
+/// The properties mdlt checks, as they apply to one file; `unset`, absent
+/// and unrecognized values are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Properties {
+    pub end_of_line: Option<Setting<ExpectedEndings>>,
+    pub insert_final_newline: Option<Setting<bool>>,
+    pub trim_trailing_whitespace: Option<Setting<bool>>,
+    pub indent_style: Option<Setting<IndentStyle>>,
+    pub max_line_length: Option<Setting<usize>>,
+}
+
+/// Finds the `.editorconfig` files that apply to each file, reading each
+/// only once however many files it applies to.
+#[derive(Debug, Default)]
+pub struct EditorConfig {
+    files: HashMap<PathBuf, Option<ConfigFile>>,
+}
+
+impl EditorConfig {
+    /// Applies the `.editorconfig` files from the nearest `root = true` one
+    /// down to the file's directory, so that later sections and nearer
+    /// files override earlier ones.
+    pub fn properties(&mut self, path: &Path) -> Properties {
+        let Ok(absolute) = fs::canonicalize(path) else {
+            return Properties::default();
+        };
+        let current = env::current_dir().and_then(fs::canonicalize).ok();
+        let mut dirs = Vec::new();
+        for dir in absolute.ancestors().skip(1) {
+            let source = dir.join(".editorconfig");
+            let file = self.files.entry(source.clone()).or_insert_with(|| {
+                let contents = fs::read_to_string(&source).ok()?;
+                let name = current
+                    .as_deref()
+                    .and_then(|current| source.strip_prefix(current).ok())
+                    .unwrap_or(&source);
+                Some(ConfigFile::parse(
+                    name.to_string_lossy().replace('\\', "/"),
+                    &contents,
+                ))
+            });
+            let root = file.as_ref().is_some_and(|file| file.root);
+            dirs.push(dir.to_path_buf());
+            if root {
+                break;
+            }
+        }
+
+        let mut values: HashMap<&str, (&str, &str)> = HashMap::new();
+        for dir in dirs.iter().rev() {
+            let Some(Some(file)) = self.files.get(&dir.join(".editorconfig")) else {
+                continue;
+            };
+            let Ok(relative) = absolute.strip_prefix(dir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            for (glob, properties) in &file.sections {
+                if glob.matches(&relative) {
+                    for (key, value) in properties {
+                        values.insert(key, (value, &file.name));
+                    }
+                }
+            }
+        }
+
+        fn setting<T>(
+            values: &HashMap<&str, (&str, &str)>,
+            key: &str,
+            parse: impl Fn(&str) -> Option<T>,
+        ) -> Option<Setting<T>> {
+            let &(value, source) = values.get(key)?;
+            Some(Setting {
+                value: parse(value)?,
+                source: source.to_string(),
+            })
+        }
+        let boolean = |value: &str| match value {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        };
+        Properties {
+            end_of_line: setting(&values, "end_of_line", |value| match value {
+                "lf" => Some(ExpectedEndings::Lf),
+                "crlf" => Some(ExpectedEndings::Crlf),
+                "cr" => Some(ExpectedEndings::Cr),
+                _ => None,
+            }),
+            insert_final_newline: setting(&values, "insert_final_newline", boolean),
+            trim_trailing_whitespace: setting(&values, "trim_trailing_whitespace", boolean),
+            indent_style: setting(&values, "indent_style", |value| match value {
+                "tab" => Some(IndentStyle::Tab),
+                "space" => Some(IndentStyle::Space),
+                _ => None,
+            }),
+            max_line_length: setting(&values, "max_line_length", |value| value.parse().ok()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        SectionGlob::new(pattern).matches(path)
+    }
+
+    #[test]
+    fn test_section_globs() {
+        assert!(matches("*", "src/main.rs"));
+        assert!(matches("*.rs", "src/main.rs"));
+        assert!(!matches("*.rs", "src/main.rsx"));
+        assert!(matches("*.{js,ts}", "app/index.ts"));
+        assert!(!matches("*.{js,ts}", "app/index.rs"));
+        assert!(matches("{Makefile,*.mk}", "build/rules.mk"));
+        assert!(matches("*.{c,{cc,cpp}}", "x.cpp"));
+        assert!(matches("lib/**.js", "lib/a/b/c.js"));
+        assert!(!matches("lib/*.js", "lib/a/c.js"));
+        assert!(matches("/docs/*.md", "docs/index.md"));
+        assert!(!matches("docs/*.md", "sub/docs/index.md"));
+        assert!(matches("**/*.md", "index.md"));
+        assert!(matches("file[0-9].txt", "file7.txt"));
+        assert!(!matches("file[!0-9].txt", "file7.txt"));
+        assert!(matches("v{1..12}.txt", "v10.txt"));
+        assert!(!matches("v{1..12}.txt", "v13.txt"));
+        assert!(matches("{single}.txt", "{single}.txt"));
+        assert!(matches("a\\*.txt", "a*.txt"));
+        assert!(!matches("a\\*.txt", "ab.txt"));
+    }
+
+    #[test]
+    fn test_parse_file() {
+        let file = ConfigFile::parse(
+            ".editorconfig".to_string(),
+            "root = true\n; comment\n[*]\nEnd_Of_Line = LF\n\n[*.md]\ntrim_trailing_whitespace: false\n",
+        );
+        assert!(file.root);
+        assert_eq!(file.sections.len(), 2);
+        assert_eq!(
+            file.sections[0].1,
+            [("end_of_line".to_string(), "lf".to_string())]
+        );
+        assert_eq!(
+            file.sections[1].1,
+            [("trim_trailing_whitespace".to_string(), "false".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_nested_configs_override() {
+        let root = PathBuf::from("editorconfig_fixture");
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub/deeper")).unwrap();
+        fs::write(
+            root.join("sub/deeper/.editorconfig"),
+            "[*.txt]\nmax_line_length = 80\nindent_style = unset\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join(".editorconfig"),
+            "root = true\n[*]\nend_of_line = lf\ninsert_final_newline = true\n\
+             [*.txt]\nindent_style = space\nmax_line_length = 100\n",
+        )
+        .unwrap();
+        fs::write(
+            root.join("sub/.editorconfig"),
+            "[*.txt]\nend_of_line = crlf\nindent_style = tab\n[*.bat]\nend_of_line = unset\n",
+        )
+        .unwrap();
+        for name in ["a.txt", "sub/a.txt", "sub/deeper/a.txt", "sub/b.bat"] {
+            fs::write(root.join(name), "").unwrap();
+        }
+        let mut config = EditorConfig::default();
+        let mut lookup = |name: &str| config.properties(&root.join(name));
+        fn from<T>(value: T, source: &str) -> Option<Setting<T>> {
+            Some(Setting {
+                value,
+                source: format!("editorconfig_fixture/{}", source),
+            })
+        }
+
+        let top = lookup("a.txt");
+        assert_eq!(top.end_of_line, from(ExpectedEndings::Lf, ".editorconfig"));
+        assert_eq!(top.insert_final_newline, from(true, ".editorconfig"));
+        assert_eq!(top.indent_style, from(IndentStyle::Space, ".editorconfig"));
+        assert_eq!(top.max_line_length, from(100, ".editorconfig"));
+        assert_eq!(top.trim_trailing_whitespace, None);
+
+        let sub = lookup("sub/a.txt");
+        assert_eq!(
+            sub.end_of_line,
+            from(ExpectedEndings::Crlf, "sub/.editorconfig")
+        );
+        assert_eq!(
+            sub.indent_style,
+            from(IndentStyle::Tab, "sub/.editorconfig")
+        );
+        assert_eq!(sub.insert_final_newline, from(true, ".editorconfig"));
+
+        let deeper = lookup("sub/deeper/a.txt");
+        assert_eq!(deeper.indent_style, None);
+        assert_eq!(deeper.max_line_length, from(80, "sub/deeper/.editorconfig"));
+        assert_eq!(lookup("sub/b.bat").end_of_line, None);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use std::str::FromStr;
+
+use crate::bom::Bom;
+
+/// How the scanner interprets the bytes of a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8 or any other ASCII-compatible encoding, scanned byte by byte.
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl Encoding {
+    /// The encoding a byte-order mark announces, if the scanner can decode
+    /// it.
+    pub fn from_bom(bom: Bom) -> Option<Encoding> {
+        match bom {
+            Bom::Utf8 => Some(Encoding::Utf8),
+            Bom::Utf16Le => Some(Encoding::Utf16Le),
+            Bom::Utf16Be => Some(Encoding::Utf16Be),
+            Bom::Utf32Le | Bom::Utf32Be => None,
+        }
+    }
+
+    /// The byte-order mark written for this encoding.
+    pub fn bom(self) -> Bom {
+        match self {
+            Encoding::Utf8 => Bom::Utf8,
+            Encoding::Utf16Le => Bom::Utf16Le,
+            Encoding::Utf16Be => Bom::Utf16Be,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        self.bom().name()
+    }
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Ok(Encoding::Utf8),
+            "utf-16le" | "utf16le" => Ok(Encoding::Utf16Le),
+            "utf-16be" | "utf16be" => Ok(Encoding::Utf16Be),
+            _ => Err(format!(
+                "Unknown encoding '{}': expected utf-8, utf-16le or utf-16be",
+                value
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("utf-8".parse(), Ok(Encoding::Utf8));
+        assert_eq!("UTF-16LE".parse(), Ok(Encoding::Utf16Le));
+        assert_eq!("utf16be".parse(), Ok(Encoding::Utf16Be));
+        assert_eq!(
+            "latin1".parse::<Encoding>(),
+            Err("Unknown encoding 'latin1': expected utf-8, utf-16le or utf-16be".to_string())
+        );
+    }
+
+    #[test]
+    fn test_bom_round_trip() {
+        for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be] {
+            assert_eq!(Encoding::from_bom(encoding.bom()), Some(encoding));
+        }
+        assert_eq!(Encoding::from_bom(Bom::Utf32Le), None);
+    }
+}
@@ -0,0 +1,387 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::checks::{self, Severity, Violation};
+use crate::cli::Options;
+use crate::{figures, osname, FileStats};
+
+/// How long a command may take on one file unless
+/// `--external-check-timeout` says otherwise.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The placeholder that has a command read the file on its stdin instead
+/// of getting its path; it stands for `-` in the command.
+const STDIN: &str = "{stdin}";
+
+/// The longest wait between two looks at whether a command has exited.
+const MAX_POLL: Duration = Duration::from_millis(50);
+
+/// `--external-check NAME:COMMAND`: a check of one's own, which a file
+/// fails when COMMAND exits with other than 0 on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExternalCheck {
+    /// The name of its violations, as markers and `--severity` take it.
+    pub name: String,
+    pub command: String,
+}
+
+impl FromStr for ExternalCheck {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let (name, command) = value.split_once(':').ok_or_else(|| {
+            format!(
+                "--external-check: expected <name>:<command>, not '{}'",
+                value
+            )
+        })?;
+        // Named as built-in checks are, so that markers can silence it.
+        let named = name.starts_with(|c: char| c.is_ascii_lowercase())
+            && name
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+        if !named {
+            return Err(format!(
+                "--external-check: '{}' is not a check name: use lowercase letters, digits and \
+                 hyphens, starting with a letter",
+                name
+            ));
+        }
+        if checks::find("--external-check", name).is_ok() {
+            return Err(format!(
+                "--external-check: '{}' is the name of a built-in check",
+                name
+            ));
+        }
+        if command.trim().is_empty() {
+            return Err(format!("--external-check {}: no command given", name));
+        }
+        Ok(ExternalCheck {
+            name: name.to_string(),
+            command: command.to_string(),
+        })
+    }
+}
+
+/// What running a check's command on one file came to.
+#[derive(Debug, PartialEq, Eq)]
+enum Ran {
+    Passed,
+    /// It exited with other than 0, and this is what it said.
+    Failed(String),
+    /// It could not tell: it is missing, crashed or timed out.
+    Broken(String),
+}
+
+impl ExternalCheck {
+    /// The severity `--severity` gives what it finds, an error otherwise.
+    pub fn severity(&self, options: &Options) -> Severity {
+        checks::severity(options, &self.name, Severity::Error)
+    }
+
+    /// Whether what it finds fails the run.
+    pub fn fails(&self, options: &Options) -> bool {
+        checks::failing(options, self.severity(options))
+    }
+
+    /// The command run on the file at `path` and whether it reads the file
+    /// on its stdin. The command line is the shell's, with the path as an
+    /// argument of its own, so that no name needs quoting.
+    fn command(&self, path: &str) -> (Command, bool) {
+        let stdin = self.command.contains(STDIN);
+        #[cfg(unix)]
+        {
+            let script = match stdin {
+                true => self.command.replace(STDIN, "-"),
+                false => format!("{} \"$1\"", self.command),
+            };
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(script).arg("mdlt");
+            if !stdin {
+                command.arg(osname::path(path).as_os_str());
+            }
+            (command, stdin)
+        }
+        #[cfg(not(unix))]
+        {
+            use std::os::windows::process::CommandExt;
+            let line = match stdin {
+                true => self.command.replace(STDIN, "-"),
+                false => format!("{} \"{}\"", self.command, path),
+            };
+            let mut command = Command::new("cmd");
+            command.arg("/C").raw_arg(line);
+            (command, stdin)
+        }
+    }
+
+    /// Runs the command on the file at `path`, killing it after `timeout`.
+    fn run(&self, path: &str, timeout: Duration) -> Ran {
+        let (mut command, stdin) = self.command(path);
+        let input = match stdin {
+            true => match File::open(osname::path(path)) {
+                Ok(file) => Some(file),
+                Err(e) => return Ran::Broken(format!("cannot read {}: {}", path, e)),
+            },
+            false => None,
+        };
+        let spawned = command
+            .stdin(match input {
+                Some(_) => Stdio::piped(),
+                None => Stdio::null(),
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(e) => return Ran::Broken(format!("cannot run '{}': {}", self.command, e)),
+        };
+        // Each pipe is served on a thread of its own, so that a command
+        // that reads little of the file or writes a lot is not left
+        // waiting on one while another is read. What a command that has
+        // exited did not read of the file is dropped.
+        if let (Some(mut file), Some(mut pipe)) = (input, child.stdin.take()) {
+            thread::spawn(move || io::copy(&mut file, &mut pipe));
+        }
+        let stdout = child
+            .stdout
+            .take()
+            .map(|pipe| thread::spawn(|| first_line(pipe)));
+        let stderr = child
+            .stderr
+            .take()
+            .map(|pipe| thread::spawn(|| first_line(pipe)));
+        let status = match wait(&mut child, timeout) {
+            Ok(Some(status)) => status,
+            // What the command started may still hold the pipes open, so
+            // they are left to their threads.
+            Ok(None) => {
+                return Ran::Broken(format!(
+                    "timed out after {}s on {}",
+                    timeout.as_secs_f64(),
+                    path
+                ))
+            }
+            Err(e) => return Ran::Broken(format!("cannot wait for '{}': {}", self.command, e)),
+        };
+        let said = |pipe: Option<thread::JoinHandle<String>>| {
+            pipe.and_then(|pipe| pipe.join().ok()).unwrap_or_default()
+        };
+        match status.code() {
+            Some(0) => Ran::Passed,
+            // The shell's statuses for a command it cannot find or run.
+            Some(code @ (126 | 127)) if cfg!(unix) => Ran::Broken(match said(stderr) {
+                message if message.is_empty() => {
+                    format!("'{}' exited with {}", self.command, code)
+                }
+                message => message,
+            }),
+            Some(code) => Ran::Failed(match said(stdout) {
+                message if message.is_empty() => format!("exited with {}", code),
+                message => message,
+            }),
+            None => Ran::Broken(format!("'{}' {} on {}", self.command, killed(status), path)),
+        }
+    }
+}
+
+/// How a command that exited without a status ended.
+fn killed(status: ExitStatus) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        if let Some(signal) = status.signal() {
+            return format!("was killed by signal {}", signal);
+        }
+    }
+    let _ = status;
+    "crashed".to_string()
+}
+
+/// Waits for `child` to exit, killing it once `timeout` is up, which is
+/// `None`.
+fn wait(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let started = Instant::now();
+    // Most commands are done in a moment, so the looks start often.
+    let mut poll = Duration::from_millis(1);
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let waited = started.elapsed();
+        if waited >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(poll.min(timeout - waited));
+        poll = (poll * 2).min(MAX_POLL);
+    }
+}
+
+/// The first line `pipe` gives, trimmed, reading the rest to the end so
+/// that the command is not blocked on a full pipe.
+fn first_line(pipe: impl Read) -> String {
+    let mut reader = BufReader::new(pipe);
+    let mut line = Vec::new();
+    let _ = reader.read_until(b'\n', &mut line);
+    let _ = io::copy(&mut reader, &mut io::sink());
+    String::from_utf8_lossy(&line).trim().to_string()
+}
+
+/// The external checks of a run, which remember for each how it broke, so
+/// that the run tells of that once rather than for every file.
+pub struct Hooks<'a> {
+    checks: &'a [ExternalCheck],
+    timeout: Duration,
+    /// The files they were run on.
+    files: AtomicUsize,
+    /// The first reason each check could not run and the files it could
+    /// not run on.
+    broken: Mutex<Vec<Option<(String, usize)>>>,
+}
+
+impl<'a> Hooks<'a> {
+    pub fn new(options: &'a Options) -> Self {
+        Hooks {
+            checks: &options.external_checks,
+            timeout: options.external_check_timeout,
+            files: AtomicUsize::new(0),
+            broken: Mutex::new(vec![None; options.external_checks.len()]),
+        }
+    }
+
+    /// Runs each check on `file`, which was read from `path`, one after
+    /// the other, giving what they found with the severity `--severity`
+    /// sets and the suppression of the file's markers.
+    pub fn run(&self, options: &Options, path: &str, file: &FileStats) -> Vec<Violation> {
+        if self.checks.is_empty() {
+            return Vec::new();
+        }
+        self.files.fetch_add(1, Ordering::Relaxed);
+        let mut found = Vec::new();
+        for (index, check) in self.checks.iter().enumerate() {
+            match check.run(path, self.timeout) {
+                Ran::Passed => {}
+                Ran::Failed(message) => found.push(Violation {
+                    check: check.name.clone(),
+                    severity: check.severity(options),
+                    line: None,
+                    message,
+                    suppressed: file
+                        .suppressions
+                        .iter()
+                        .any(|suppression| suppression.covers(&check.name, None)),
+                }),
+                Ran::Broken(reason) => {
+                    let mut broken = self.broken.lock().unwrap_or_else(|e| e.into_inner());
+                    let (_, files) = broken[index].get_or_insert((reason, 0));
+                    *files += 1;
+                }
+            }
+        }
+        found
+    }
+
+    /// A line for each check that could not run on some of the files,
+    /// which fails the run.
+    pub fn broken(&self) -> Vec<String> {
+        let files = self.files.load(Ordering::Relaxed);
+        let broken = self.broken.lock().unwrap_or_else(|e| e.into_inner());
+        self.checks
+            .iter()
+            .zip(broken.iter())
+            .filter_map(|(check, broken)| {
+                let (reason, count) = broken.as_ref()?;
+                Some(format!(
+                    "--external-check {} could not run on {}: {}",
+                    check.name,
+                    figures::of(*count as u64, files as u64, "file", true),
+                    reason
+                ))
+            })
+            .collect()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn check(value: &str) -> ExternalCheck {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            check("yaml-tabs:grep -q x"),
+            ExternalCheck {
+                name: "yaml-tabs".to_string(),
+                command: "grep -q x".to_string(),
+            }
+        );
+        // Only the first colon ends the name.
+        assert_eq!(check("a:b:c").command, "b:c");
+        assert_eq!(
+            "yaml-tabs".parse::<ExternalCheck>().unwrap_err(),
+            "--external-check: expected <name>:<command>, not 'yaml-tabs'"
+        );
+        assert!("Tabs:true"
+            .parse::<ExternalCheck>()
+            .unwrap_err()
+            .starts_with("--external-check: 'Tabs' is not a check name"));
+        assert_eq!(
+            "bom:true".parse::<ExternalCheck>().unwrap_err(),
+            "--external-check: 'bom' is the name of a built-in check"
+        );
+        assert_eq!(
+            "tabs: ".parse::<ExternalCheck>().unwrap_err(),
+            "--external-check tabs: no command given"
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let path = std::env::temp_dir().join(format!("mdlt_external_{}.txt", std::process::id()));
+        std::fs::write(&path, "a\tb\n").unwrap();
+        let path = path.to_str().unwrap();
+        let second = Duration::from_secs(1);
+        let run = |command: &str| check(&format!("tabs:{}", command)).run(path, second);
+        assert_eq!(run("test -f"), Ran::Passed);
+        assert_eq!(
+            run("echo \"$1\" | grep -q txt && echo tabs found; echo more; exit 3; :"),
+            Ran::Failed("tabs found".to_string())
+        );
+        assert_eq!(run("false"), Ran::Failed("exited with 1".to_string()));
+        // The file comes on stdin instead.
+        assert_eq!(run("grep -qv x {stdin}"), Ran::Passed);
+        assert_eq!(
+            run("! grep -q '\t' {stdin} || { echo has a tab; exit 1; }"),
+            Ran::Failed("has a tab".to_string())
+        );
+        assert!(matches!(
+            run("mdlt-no-such-command"),
+            Ran::Broken(reason) if reason.contains("not found")
+        ));
+        assert!(matches!(
+            run("kill -9 $$"),
+            Ran::Broken(reason) if reason.ends_with(&format!("was killed by signal 9 on {}", path))
+        ));
+        let started = Instant::now();
+        let hung = check("tabs:sleep 5; :").run(path, Duration::from_millis(100));
+        assert_eq!(
+            hung,
+            Ran::Broken(format!("timed out after 0.1s on {}", path))
+        );
+        assert!(started.elapsed() < Duration::from_secs(4));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,276 @@
+//! The C interface of the `ffi` feature, declared in `include/mdlt.h`.
+//!
+//! Every function checks its pointers, returns 0 on success and -1 on
+//! failure, and catches panics rather than unwinding into C. Why a call
+//! failed is kept per thread for [`mdlt_last_error_message`].
+
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+use crate::{FileStats, LineEndingKind};
+
+/// The ending a file mostly uses, as `MDLT_ENDING_*` in C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MdltLineEnding {
+    /// No line breaks at all.
+    None = 0,
+    /// LF alone.
+    Lf = 1,
+    /// CRLF.
+    Crlf = 2,
+    /// CR alone.
+    Cr = 3,
+    /// No one ending is the most common.
+    Mixed = 4,
+    /// Only Unicode breaks such as U+2028.
+    Unicode = 5,
+}
+
+/// The counts of one analysis, as `MdltStats` in C.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MdltStats {
+    /// All lines, the last one counted whether it is terminated or not.
+    pub total_lines: u64,
+    /// Lines with no content at all.
+    pub empty_lines: u64,
+    /// Lines of only spaces and tabs.
+    pub blank_lines: u64,
+    /// Lines ending in CRLF, LF alone and CR alone.
+    pub crlf: u64,
+    pub lf: u64,
+    pub cr: u64,
+    /// Size in bytes, BOM included.
+    pub total_bytes: u64,
+    /// Characters after the BOM and runs of non-whitespace, as `wc` counts
+    /// them.
+    pub total_chars: u64,
+    pub total_words: u64,
+    /// Lines ending in spaces or tabs.
+    pub trailing_whitespace_lines: u64,
+    /// The longest line in display columns, tabs expanded to 8.
+    pub max_line_length: u64,
+    /// The ending that occurs most often.
+    pub line_ending: MdltLineEnding,
+    /// 1 when the last line is terminated, 0 when it is not, and -1 when
+    /// there are no lines.
+    pub final_newline: c_int,
+    /// Whether the start looks like binary data rather than text.
+    pub is_binary: bool,
+}
+
+impl From<&FileStats> for MdltStats {
+    fn from(stats: &FileStats) -> Self {
+        let count = |n: usize| n as u64;
+        MdltStats {
+            total_lines: count(stats.total_lines()),
+            empty_lines: count(stats.empty_lines()),
+            blank_lines: count(stats.blank_lines()),
+            crlf: count(stats.dos_endings()),
+            lf: count(stats.unix_endings()),
+            cr: count(stats.mac_endings()),
+            total_bytes: stats.total_bytes(),
+            total_chars: stats.total_chars(),
+            total_words: stats.total_words(),
+            trailing_whitespace_lines: count(stats.trailing_whitespace_lines()),
+            max_line_length: count(stats.max_line_length()),
+            line_ending: match stats.line_ending() {
+                LineEndingKind::None => MdltLineEnding::None,
+                LineEndingKind::Lf => MdltLineEnding::Lf,
+                LineEndingKind::Crlf => MdltLineEnding::Crlf,
+                LineEndingKind::Cr => MdltLineEnding::Cr,
+                LineEndingKind::Mixed => MdltLineEnding::Mixed,
+                LineEndingKind::UnicodeOnly => MdltLineEnding::Unicode,
+            },
+            final_newline: match stats.final_newline() {
+                Some(true) => 1,
+                Some(false) => 0,
+                None => -1,
+            },
+            is_binary: stats.is_binary(),
+        }
+    }
+}
+
+thread_local! {
+    /// Why the last call on this thread failed, or `None` when it did not.
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Runs `analyze` as one call of the interface: its error, or a panic, is
+/// kept for [`mdlt_last_error_message`] and gives -1, and otherwise the
+/// stats are written to `out`.
+fn call(out: *mut MdltStats, analyze: impl FnOnce() -> Result<FileStats, String>) -> c_int {
+    let result = match out.is_null() {
+        true => Err("out is NULL".to_string()),
+        false => panic::catch_unwind(AssertUnwindSafe(analyze)).unwrap_or_else(|payload| {
+            let reason = payload
+                .downcast_ref::<&str>()
+                .map(|reason| reason.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_default();
+            Err(format!("mdlt panicked: {}", reason))
+        }),
+    };
+    let (status, error) = match result {
+        Ok(stats) => {
+            // SAFETY: `out` is not null, and the caller promises it points
+            // at an `MdltStats` it may write.
+            unsafe { out.write(MdltStats::from(&stats)) };
+            (0, None)
+        }
+        Err(message) => (-1, Some(message)),
+    };
+    let error = error
+        .map(|message| CString::new(message.replace('\0', "")).expect("no NUL bytes are left"));
+    LAST_ERROR.with(|last| *last.borrow_mut() = error);
+    status
+}
+
+/// Analyzes the file at `path` as `mdlt <path>` does and writes its counts
+/// to `out`. Returns 0 on success and -1 when a pointer is null, the path is
+/// not UTF-8 or the file cannot be read.
+///
+/// # Safety
+///
+/// `path` must be null or a NUL-terminated string, and `out` null or valid
+/// for writing an `MdltStats`.
+#[no_mangle]
+pub unsafe extern "C" fn mdlt_analyze_path(path: *const c_char, out: *mut MdltStats) -> c_int {
+    call(out, || {
+        if path.is_null() {
+            return Err("path is NULL".to_string());
+        }
+        // SAFETY: `path` is not null, and the caller promises it is
+        // NUL-terminated.
+        let path = unsafe { CStr::from_ptr(path) }
+            .to_str()
+            .map_err(|_| "path is not valid UTF-8".to_string())?;
+        crate::analyze_file(path).map_err(|failure| failure.to_string())
+    })
+}
+
+/// Analyzes the `len` bytes at `data` and writes their counts to `out`.
+/// `data` may be null when `len` is 0. Returns 0 on success and -1 when a
+/// pointer is null.
+///
+/// # Safety
+///
+/// `data` must be null or valid for reading `len` bytes, and `out` null or
+/// valid for writing an `MdltStats`.
+#[no_mangle]
+pub unsafe extern "C" fn mdlt_analyze_bytes(
+    data: *const u8,
+    len: usize,
+    out: *mut MdltStats,
+) -> c_int {
+    call(out, || {
+        let bytes = match (data.is_null(), len) {
+            (true, 0) => &[][..],
+            (true, _) => return Err("data is NULL".to_string()),
+            // SAFETY: `data` is not null, and the caller promises it holds
+            // `len` bytes.
+            (false, _) => unsafe { std::slice::from_raw_parts(data, len) },
+        };
+        Ok(crate::analyze_bytes(bytes, None))
+    })
+}
+
+/// Why the last call on this thread failed, as a UTF-8 string, or null
+/// when it succeeded. The string is owned by mdlt and stays valid until the
+/// next call on the same thread.
+#[no_mangle]
+pub extern "C" fn mdlt_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ref().map_or(ptr::null(), |e| e.as_ptr()))
+}
+
+/// The version of mdlt, such as "1.0.0", as a static string.
+#[no_mangle]
+pub extern "C" fn mdlt_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn last_error() -> Option<String> {
+        let message = mdlt_last_error_message();
+        (!message.is_null()).then(|| {
+            unsafe { CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        })
+    }
+
+    #[test]
+    fn test_analyze_bytes() {
+        let mut stats = MdltStats::from(&crate::analyze_bytes(b"", None));
+        let bytes = b"a\r\nb\r\nc\n";
+        assert_eq!(
+            unsafe { mdlt_analyze_bytes(bytes.as_ptr(), bytes.len(), &mut stats) },
+            0
+        );
+        assert_eq!((stats.total_lines, stats.crlf, stats.lf), (3, 2, 1));
+        assert_eq!(stats.line_ending, MdltLineEnding::Crlf);
+        assert_eq!(stats.final_newline, 1);
+        assert_eq!(last_error(), None);
+
+        assert_eq!(unsafe { mdlt_analyze_bytes(ptr::null(), 0, &mut stats) }, 0);
+        assert_eq!((stats.total_lines, stats.final_newline), (0, -1));
+        assert_eq!(stats.line_ending, MdltLineEnding::None);
+    }
+
+    #[test]
+    fn test_null_pointers_and_bad_paths() {
+        let mut stats = MdltStats::from(&crate::analyze_bytes(b"", None));
+        assert_eq!(
+            unsafe { mdlt_analyze_bytes(ptr::null(), 3, &mut stats) },
+            -1
+        );
+        assert_eq!(last_error().as_deref(), Some("data is NULL"));
+        let bytes = b"a\n";
+        let out = ptr::null_mut();
+        assert_eq!(unsafe { mdlt_analyze_bytes(bytes.as_ptr(), 2, out) }, -1);
+        assert_eq!(last_error().as_deref(), Some("out is NULL"));
+        assert_eq!(unsafe { mdlt_analyze_path(ptr::null(), &mut stats) }, -1);
+        assert_eq!(last_error().as_deref(), Some("path is NULL"));
+        let invalid = c"bad\xff.txt";
+        assert_eq!(
+            unsafe { mdlt_analyze_path(invalid.as_ptr(), &mut stats) },
+            -1
+        );
+        assert_eq!(last_error().as_deref(), Some("path is not valid UTF-8"));
+        let missing = c"no_such_file.txt";
+        assert_eq!(
+            unsafe { mdlt_analyze_path(missing.as_ptr(), &mut stats) },
+            -1
+        );
+        assert!(last_error()
+            .unwrap()
+            .starts_with("Error analyzing no_such_file.txt: "));
+    }
+
+    #[test]
+    fn test_panics_do_not_unwind() {
+        let mut stats = MdltStats::from(&crate::analyze_bytes(b"", None));
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let status = call(&mut stats, || panic!("out of cheese"));
+        panic::set_hook(hook);
+        assert_eq!(status, -1);
+        assert_eq!(
+            last_error().as_deref(),
+            Some("mdlt panicked: out of cheese")
+        );
+    }
+
+    #[test]
+    fn test_version() {
+        let version = unsafe { CStr::from_ptr(mdlt_version()) };
+        assert_eq!(version.to_str(), Ok(env!("CARGO_PKG_VERSION")));
+    }
+}
@@ -0,0 +1,211 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Writes `n` with commas between groups of three digits: "10,432". The
+/// separator is always a comma, whatever the locale, so that a report
+/// reads the same everywhere.
+pub fn grouped(n: u64) -> String {
+    let digits = n.to_string();
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    grouped
+}
+
+/// A count in the text report: [`grouped`] when `group`, which
+/// `--no-group-digits` turns off, and plain digits otherwise.
+pub fn count(n: u64, group: bool) -> String {
+    match group {
+        true => grouped(n),
+        false => n.to_string(),
+    }
+}
+
+/// `n` and `noun`, which takes an `s` unless `n` is 1, the number as
+/// [`count`] writes it: "1 line", "1,000 lines".
+pub fn counted(n: u64, noun: &str, group: bool) -> String {
+    format!(
+        "{} {}{}",
+        count(n, group),
+        noun,
+        if n == 1 { "" } else { "s" }
+    )
+}
+
+/// `part` of `total` of `noun`, as [`counted`] writes them: "3 of 9
+/// files", "1 of 1 file".
+pub fn of(part: u64, total: u64, noun: &str, group: bool) -> String {
+    format!("{} of {}", count(part, group), counted(total, noun, group))
+}
+
+/// Rounds each count's share of the total to tenths of a percent so that
+/// the shares add up to exactly 100%: each share is rounded down, and the
+/// tenths left over go to the shares that lost the most in rounding.
+pub fn tenths_of_percent(counts: [usize; 3]) -> [u64; 3] {
+    let total = counts.iter().sum::<usize>() as u64;
+    if total == 0 {
+        return [0; 3];
+    }
+    let exact = counts.map(|count| count as u64 * 1000);
+    let mut tenths = exact.map(|share| share / total);
+    let left = 1000 - tenths.iter().sum::<u64>();
+    let mut order = [0, 1, 2];
+    order.sort_by_key(|&index| std::cmp::Reverse(exact[index] % total));
+    for &index in &order[..left as usize] {
+        tenths[index] += 1;
+    }
+    tenths
+}
+
+/// A share in tenths of a percent with its one decimal: "98.7%", "0.0%".
+pub fn percent(tenths: u64) -> String {
+    format!("{}.{}%", tenths / 10, tenths % 10)
+}
+
+/// Formats `time` in UTC to the second, e.g. "2026-10-11T08:30:00Z". Times
+/// before 1970 are written as 1970.
+pub fn rfc3339(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs();
+    let (year, month, day) = civil_from_days(seconds / 86_400);
+    let seconds = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Reads back a time [`rfc3339`] wrote, e.g. "2024-03-09T14:05:00Z".
+#[cfg(feature = "serde")]
+pub fn parse_rfc3339(text: &str) -> Option<SystemTime> {
+    let (date, time) = text.strip_suffix('Z')?.split_once('T')?;
+    let numbers = |text: &str, separator: char| -> Option<Vec<u64>> {
+        text.split(separator)
+            .map(|part| part.parse().ok())
+            .collect()
+    };
+    let (Some([year, month, day]), Some([hours, minutes, seconds])) = (
+        numbers(date, '-').and_then(|date| <[u64; 3]>::try_from(date).ok()),
+        numbers(time, ':').and_then(|time| <[u64; 3]>::try_from(time).ok()),
+    ) else {
+        return None;
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || year < 1970 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    let seconds = days * 86_400 + hours * 3600 + minutes * 60 + seconds;
+    Some(UNIX_EPOCH + Duration::from_secs(seconds))
+}
+
+/// The days from 1970-01-01 to a date, by Howard Hinnant's
+/// `days_from_civil`; the inverse of [`civil_from_days`].
+#[cfg(feature = "serde")]
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = year - u64::from(month <= 2);
+    let era = year / 400;
+    let year_of_era = year % 400;
+    let shifted_month = (month + 9) % 12;
+    let day_of_year = (153 * shifted_month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date `days` after 1970-01-01, by Howard Hinnant's `civil_from_days`.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_grouped() {
+        assert_eq!(grouped(0), "0");
+        assert_eq!(grouped(999), "999");
+        assert_eq!(grouped(1000), "1,000");
+        assert_eq!(grouped(10_432), "10,432");
+        assert_eq!(grouped(1_000_000), "1,000,000");
+        assert_eq!(grouped(u64::MAX), "18,446,744,073,709,551,615");
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count(1000, true), "1,000");
+        assert_eq!(count(1000, false), "1000");
+        assert_eq!(count(u64::MAX, false), "18446744073709551615");
+    }
+
+    #[test]
+    fn test_counted() {
+        assert_eq!(counted(0, "file", true), "0 files");
+        assert_eq!(counted(1, "file", true), "1 file");
+        assert_eq!(counted(1000, "line", true), "1,000 lines");
+        assert_eq!(counted(1000, "line", false), "1000 lines");
+        assert_eq!(of(1, 1, "file", true), "1 of 1 file");
+        assert_eq!(of(3, 1200, "file", true), "3 of 1,200 files");
+    }
+
+    #[test]
+    fn test_tenths_of_percent_add_up() {
+        assert_eq!(tenths_of_percent([1, 1, 1]), [334, 333, 333]);
+        assert_eq!(tenths_of_percent([2, 1, 0]), [667, 333, 0]);
+        assert_eq!(tenths_of_percent([1, 1998, 1]), [1, 999, 0]);
+        assert_eq!(tenths_of_percent([0, 0, 0]), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_percent() {
+        assert_eq!(percent(0), "0.0%");
+        assert_eq!(percent(987), "98.7%");
+        assert_eq!(percent(1000), "100.0%");
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        assert_eq!(rfc3339(at(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(rfc3339(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(rfc3339(at(1_791_729_000)), "2026-10-11T14:30:00Z");
+        assert_eq!(rfc3339(at(4_107_542_399)), "2100-02-28T23:59:59Z");
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_parse_rfc3339() {
+        for seconds in [0, 951_782_400, 1_791_729_000, 4_107_542_399] {
+            assert_eq!(parse_rfc3339(&rfc3339(at(seconds))), Some(at(seconds)));
+        }
+        assert_eq!(parse_rfc3339("2026-10-11 14:30:00Z"), None);
+        assert_eq!(parse_rfc3339("2026-13-11T14:30:00Z"), None);
+        assert_eq!(parse_rfc3339("2026-10-11T14:30Z"), None);
+    }
+}
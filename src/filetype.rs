@@ -0,0 +1,115 @@
+use std::path::Path;
+
+use crate::shebang;
+
+/// Types by exact file name, for the files without an extension that are
+/// known by their name; add a row to recognize another.
+const NAMES: &[(&[&str], &str)] = &[
+    (&["Makefile", "makefile", "GNUmakefile"], "makefile"),
+    (&["Dockerfile", "Containerfile"], "dockerfile"),
+    (&["Jenkinsfile"], "groovy"),
+    (&["Rakefile", "Gemfile", "Vagrantfile", "Brewfile"], "ruby"),
+    (&["LICENSE", "LICENCE", "COPYING", "UNLICENSE"], "license"),
+    (&[".bashrc", ".bash_profile", ".bash_logout"], "bash"),
+    (&[".zshrc", ".zprofile", ".zshenv"], "zsh"),
+    (&[".profile"], "shell"),
+];
+
+/// The type of a text file without an extension that neither its name nor
+/// its shebang tells.
+pub const UNKNOWN: &str = "text/unknown";
+
+/// The types `--type-name` and `--type-interpreter` add, looked up ahead of
+/// the built-in ones. They are leaked when the flags are parsed so that
+/// scan settings stay `Copy`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Types {
+    /// Types by exact file name.
+    pub names: &'static [(String, String)],
+    /// Types by interpreter name, as [`shebang::interpreter`] gives it.
+    pub interpreters: &'static [(String, String)],
+}
+
+impl Types {
+    /// The type of a file without an extension by its name, for the last
+    /// component of `path`.
+    pub fn by_name(&self, path: &str) -> Option<String> {
+        let name = Path::new(path).file_name()?.to_str()?;
+        let given = self.names.iter().find(|(given, _)| given == name);
+        given.map(|(_, kind)| kind.clone()).or_else(|| {
+            NAMES
+                .iter()
+                .find(|(names, _)| names.contains(&name))
+                .map(|&(_, kind)| kind.to_string())
+        })
+    }
+
+    /// The type of a script by the interpreter its shebang runs: the
+    /// language of that interpreter in lowercase, such as `python`.
+    pub fn by_shebang(&self, shebang: &str) -> Option<String> {
+        let interpreter = shebang::interpreter(shebang)?;
+        let given = self
+            .interpreters
+            .iter()
+            .find(|(given, _)| given == interpreter);
+        given
+            .map(|(_, kind)| kind.clone())
+            .or_else(|| shebang::language(shebang).map(str::to_ascii_lowercase))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_by_name() {
+        let types = Types::default();
+        assert_eq!(types.by_name("Makefile"), Some("makefile".to_string()));
+        assert_eq!(
+            types.by_name("docker/Dockerfile"),
+            Some("dockerfile".to_string())
+        );
+        assert_eq!(
+            types.by_name("release.zip!ci/Jenkinsfile"),
+            Some("groovy".to_string())
+        );
+        assert_eq!(types.by_name("Dockerfile.prod"), None);
+        assert_eq!(types.by_name("README"), None);
+        let names = vec![
+            ("README".to_string(), "markdown".to_string()),
+            ("Makefile".to_string(), "make".to_string()),
+        ];
+        let types = Types {
+            names: names.leak(),
+            ..Types::default()
+        };
+        assert_eq!(types.by_name("README"), Some("markdown".to_string()));
+        assert_eq!(types.by_name("src/Makefile"), Some("make".to_string()));
+        assert_eq!(types.by_name("LICENSE"), Some("license".to_string()));
+    }
+
+    #[test]
+    fn test_by_shebang() {
+        let types = Types::default();
+        assert_eq!(
+            types.by_shebang("/usr/bin/env python3"),
+            Some("python".to_string())
+        );
+        assert_eq!(types.by_shebang("/bin/sh -e"), Some("shell".to_string()));
+        assert_eq!(types.by_shebang("/opt/bin/deno run"), None);
+        let interpreters = vec![("deno".to_string(), "typescript".to_string())];
+        let types = Types {
+            interpreters: interpreters.leak(),
+            ..Types::default()
+        };
+        assert_eq!(
+            types.by_shebang("/usr/bin/env -S deno run"),
+            Some("typescript".to_string())
+        );
+        assert_eq!(
+            types.by_shebang("/usr/bin/node"),
+            Some("javascript".to_string())
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::glob::Glob;
+
+/// `--include`/`--exclude` patterns, matched against paths relative to the
+/// scan root. A pattern without a `/` matches a file name at any depth.
+#[derive(Debug, Default, Clone)]
+pub struct PathFilter {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    /// The `--exclude` patterns as given.
+    excluded: Vec<String>,
+}
+
+fn compile(pattern: &str) -> Result<Glob, String> {
+    let anchored = pattern.trim_end_matches('/');
+    let source = if anchored.contains(['/', '\\']) {
+        anchored.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", anchored)
+    };
+    Ok(Glob::new(&source)?.matching_dotfiles())
+}
+
+impl PathFilter {
+    pub fn include(&mut self, pattern: &str) -> Result<(), String> {
+        self.include.push(compile(pattern)?);
+        Ok(())
+    }
+
+    pub fn exclude(&mut self, pattern: &str) -> Result<(), String> {
+        self.exclude.push(compile(pattern)?);
+        self.excluded.push(pattern.to_string());
+        Ok(())
+    }
+
+    pub fn excluded(&self) -> &[String] {
+        &self.excluded
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Whether a file should be analyzed. Excludes win over includes, and
+    /// with no includes every file not excluded is allowed.
+    pub fn allows(&self, relative: &str) -> bool {
+        if self.excludes(relative) {
+            return false;
+        }
+        self.include.is_empty() || self.include.iter().any(|glob| glob.matches(relative))
+    }
+
+    /// Whether a path is excluded outright; used to prune whole directories.
+    pub fn excludes(&self, relative: &str) -> bool {
+        self.exclude.iter().any(|glob| glob.matches(relative))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> PathFilter {
+        let mut filter = PathFilter::default();
+        for pattern in include {
+            filter.include(pattern).unwrap();
+        }
+        for pattern in exclude {
+            filter.exclude(pattern).unwrap();
+        }
+        filter
+    }
+
+    #[test]
+    fn test_empty_filter_allows_everything() {
+        let filter = filter(&[], &[]);
+        assert!(filter.is_empty());
+        assert!(filter.allows("src/main.rs"));
+    }
+
+    #[test]
+    fn test_exclude_by_name_and_path() {
+        let filter = filter(&[], &["*.min.js", "vendor/**"]);
+        assert!(!filter.allows("app.min.js"));
+        assert!(!filter.allows("static/js/app.min.js"));
+        assert!(!filter.allows("vendor/lib/x.js"));
+        assert!(filter.allows("src/vendor.js"));
+        assert!(filter.allows("src/app.js"));
+    }
+
+    #[test]
+    fn test_include_restricts_and_exclude_wins() {
+        let filter = filter(&["*.rs", "docs/*.md"], &["generated.rs"]);
+        assert!(filter.allows("src/main.rs"));
+        assert!(filter.allows("docs/readme.md"));
+        assert!(!filter.allows("src/readme.md"));
+        assert!(!filter.allows("src/generated.rs"));
+    }
+
+    #[test]
+    fn test_directory_pattern() {
+        let filter = filter(&[], &["vendor/"]);
+        assert!(filter.excludes("vendor"));
+        assert!(filter.excludes("third_party/vendor"));
+    }
+}
@@ -0,0 +1,181 @@
+use std::fs::{self, File};
+use std::io::{self, IsTerminal, Read};
+use std::slice;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::cli;
+use crate::format::{self, OutputFormat};
+use crate::scanner::Scanner;
+use crate::{color, figures, interrupt, osname, CheckFailure, Exit, FileMeta, MdltError};
+
+/// How long to wait at the end of the file before looking for more.
+const POLL: Duration = Duration::from_millis(200);
+
+/// Which file a path names, so that a log rotated by renaming it and
+/// creating a new one is noticed. Elsewhere than on Unix only truncation
+/// is.
+#[cfg(unix)]
+fn identity(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn identity(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// The line `--follow` prints: the lines so far, by line ending, and how
+/// the last one ended, its counts grouped when `group`.
+fn summary(name: &str, scanner: &Scanner, group: bool) -> String {
+    let stats = scanner.stats();
+    let mut endings = vec![
+        format!("{} LF", figures::count(stats.unix_endings as u64, group)),
+        format!("{} CRLF", figures::count(stats.dos_endings as u64, group)),
+    ];
+    if stats.mac_endings > 0 {
+        endings.push(format!(
+            "{} CR",
+            figures::count(stats.mac_endings as u64, group)
+        ));
+    }
+    let last = match scanner.last_terminator() {
+        Some(ending) => format!("last line {}", ending.name()),
+        None if stats.total_bytes == 0 => "empty".to_string(),
+        None => "last line unterminated".to_string(),
+    };
+    let time = figures::rfc3339(SystemTime::now());
+    format!(
+        "[{}] {}: {} line{}, {}, {}",
+        &time[11..],
+        name,
+        figures::count(stats.total_lines as u64, group),
+        if stats.total_lines == 1 { "" } else { "s" },
+        endings.join(" / "),
+        last
+    )
+}
+
+/// The file being followed and how far into it mdlt has read.
+struct Followed {
+    file: File,
+    identity: Option<(u64, u64)>,
+    read: u64,
+    scanner: Scanner,
+}
+
+impl Followed {
+    fn open(path: &str, options: &cli::Options) -> io::Result<Self> {
+        let file = File::open(osname::path(path))?;
+        let identity = identity(&file.metadata()?);
+        Ok(Followed {
+            file,
+            identity,
+            read: 0,
+            scanner: Scanner::new(path.to_string(), options.scan),
+        })
+    }
+
+    /// Whether `path` now names a shorter or another file than the one
+    /// being read, as when a log is truncated or rotated. Until a rotated
+    /// log is created again the old one is still read.
+    fn replaced(&self, path: &str) -> bool {
+        fs::metadata(osname::path(path))
+            .is_ok_and(|meta| meta.len() < self.read || identity(&meta) != self.identity)
+    }
+}
+
+/// Runs `--follow`: reads the file given to its end, then keeps reading
+/// what is appended to it, feeding the same scanner so that a log of any
+/// size is read once. A summary line is printed every `--follow-lines`
+/// new lines, or `--interval` after new lines came in, and counting starts
+/// again when the file is truncated or rotated. Ctrl-C ends it with the
+/// full report and the exit status the checks give.
+pub fn run(options: cli::Options) -> Result<Exit, MdltError> {
+    interrupt::catch();
+    let path = &options.paths[0];
+    let shown = crate::shown_paths(&options)?;
+    let name = format::escape_name(&shown.show(path));
+    let error = |doing: &str, e: io::Error| MdltError::io(format!("{} {}", doing, name), e);
+    let mut followed = Followed::open(path, &options).map_err(|e| error("opening", e))?;
+    let mut buffer = vec![0; crate::READ_BUFFER_SIZE];
+    // The lines counted when the last summary was printed, and when.
+    let mut reported = None;
+    let mut reported_at = Instant::now();
+    while !interrupt::requested() {
+        let read = match followed.file.read(&mut buffer) {
+            Ok(read) => read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(error("reading", e)),
+        };
+        followed.scanner.feed(&buffer[..read]);
+        followed.read += read as u64;
+
+        let lines = followed.scanner.stats().total_lines;
+        let due = match reported {
+            // The first summary is of what the file held to begin with.
+            None => read == 0,
+            Some(reported) => {
+                lines >= reported + options.follow_lines
+                    || (lines > reported && reported_at.elapsed() >= options.interval)
+            }
+        };
+        if due {
+            println!(
+                "{}",
+                summary(&name, &followed.scanner, !options.report.no_group_digits)
+            );
+            reported = Some(lines);
+            reported_at = Instant::now();
+        }
+        if read > 0 {
+            continue;
+        }
+        if followed.replaced(path) {
+            // The old file may be gone again before it is opened; it is
+            // looked for on the next round.
+            if let Ok(reopened) = Followed::open(path, &options) {
+                eprintln!("{}: truncated or replaced, counting from the start", name);
+                followed = reopened;
+                reported = None;
+                continue;
+            }
+        }
+        thread::sleep(POLL);
+    }
+
+    let mut stats = followed.scanner.finish();
+    if options.metadata {
+        stats.metadata = Some(FileMeta::read(path));
+    }
+    stats.set_name(&shown.show(path));
+    let mut report = options.report;
+    report.color = options.format == OutputFormat::Text
+        && color::enabled(options.color, io::stdout().is_terminal());
+    let files = slice::from_ref(&stats);
+    format::write_report(
+        options.format,
+        report,
+        files,
+        &[],
+        &[],
+        Some(&crate::aggregate(files, &[], 0)),
+        None,
+        io::stdout(),
+    )
+    .map_err(crate::writing_report)?;
+
+    let mut violations = crate::policy_violations(&options, files);
+    let ending = options
+        .expected_endings_for(stats.extension_or_type())
+        .and_then(|expected| crate::ending_violation(expected, None, &stats));
+    if let Some(reason) = ending {
+        let message = format!("{}: {}", name, reason);
+        violations.push(CheckFailure::new("endings", message));
+    }
+    match violations.is_empty() {
+        true => Ok(Exit::Clean),
+        false => Err(MdltError::checks(violations)),
+    }
+}
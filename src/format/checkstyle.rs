@@ -0,0 +1,127 @@
+use std::io::{self, Write};
+
+use super::escape_name;
+use crate::checks::Severity;
+use crate::FileStats;
+
+/// The checkstyle severity of a violation of `severity`.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "info",
+    }
+}
+
+/// Writes a checkstyle XML report: a `file` element for each analyzed file,
+/// holding an `error` element for each violation that no marker silences.
+/// The `source` of each is the check, as `mdlt.NAME`, and a file-wide
+/// violation has no `line`.
+pub fn write(stats: &[FileStats], mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(writer, "<checkstyle version=\"4.3\">")?;
+    for file in stats {
+        let name = escape(&escape_name(&file.name()));
+        let mut violations = file
+            .violations
+            .iter()
+            .filter(|found| !found.suppressed)
+            .peekable();
+        if violations.peek().is_none() {
+            writeln!(writer, "<file name=\"{}\"/>", name)?;
+            continue;
+        }
+        writeln!(writer, "<file name=\"{}\">", name)?;
+        for violation in violations {
+            let line = violation
+                .line
+                .map(|line| format!(" line=\"{}\"", line))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "<error{} severity=\"{}\" message=\"{}\" source=\"mdlt.{}\"/>",
+                line,
+                level(violation.severity),
+                escape(&violation.message),
+                escape(&violation.check)
+            )?;
+        }
+        writeln!(writer, "</file>")?;
+    }
+    writeln!(writer, "</checkstyle>")
+}
+
+/// Escapes `text` for an XML attribute value. Control characters, which
+/// XML 1.0 does not allow even as references, become `\xNN` as in the
+/// text report.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' => escaped.push_str("&#9;"),
+            '\n' => escaped.push_str("&#10;"),
+            '\r' => escaped.push_str("&#13;"),
+            c if c.is_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Violation;
+
+    #[test]
+    fn test_files_and_errors() {
+        let mut file = FileStats::new("a&b.txt".to_string());
+        for (check, severity, line) in [
+            ("trailing-whitespace", Severity::Warning, Some(4)),
+            ("mixed-endings", Severity::Error, None),
+            ("final-newline", Severity::Info, None),
+            ("line-length", Severity::Error, Some(2)),
+        ] {
+            file.violations.push(Violation {
+                check: check.to_string(),
+                severity,
+                line,
+                message: format!("\"{}\" <found>", check),
+                suppressed: check == "line-length",
+            });
+        }
+        let mut buffer = Vec::new();
+        write(
+            &[file, FileStats::new("clean.txt".to_string())],
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <checkstyle version=\"4.3\">\n\
+             <file name=\"a&amp;b.txt\">\n\
+             <error line=\"4\" severity=\"warning\" message=\"&quot;trailing-whitespace&quot; \
+             &lt;found&gt;\" source=\"mdlt.trailing-whitespace\"/>\n\
+             <error severity=\"error\" message=\"&quot;mixed-endings&quot; &lt;found&gt;\" \
+             source=\"mdlt.mixed-endings\"/>\n\
+             <error severity=\"info\" message=\"&quot;final-newline&quot; &lt;found&gt;\" \
+             source=\"mdlt.final-newline\"/>\n\
+             </file>\n\
+             <file name=\"clean.txt\"/>\n\
+             </checkstyle>\n"
+        );
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("tab\there\n"), "tab&#9;here&#10;");
+        assert_eq!(escape("bell\u{7}"), "bell\\x07");
+        assert_eq!(escape("it's"), "it&apos;s");
+    }
+}
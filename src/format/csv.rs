@@ -0,0 +1,245 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use super::fields::{self, Columns, Field};
+use super::{escape_name, ReportOptions, Reported};
+use crate::osname;
+use crate::{FileStats, SkippedFile};
+
+/// The columns, in order. Every row has one cell per column.
+const COLUMNS: [&str; 41] = [
+    "file_name",
+    "file_extension",
+    "bom",
+    "total_lines",
+    "empty_lines",
+    "blank_lines",
+    "comment_lines",
+    "code_lines",
+    "total_bytes",
+    "total_chars",
+    "total_words",
+    "dos_endings",
+    "unix_endings",
+    "mac_endings",
+    "dos_ratio",
+    "unix_ratio",
+    "mac_ratio",
+    "nul_bytes",
+    "first_nul_line",
+    "control_chars",
+    "first_control_line",
+    "non_ascii_chars",
+    "first_non_ascii_line",
+    "first_non_ascii_column",
+    "embedded_crs",
+    "trailing_whitespace_lines",
+    "min_line_length",
+    "avg_line_length",
+    "max_line_length",
+    "max_line_bytes",
+    "longest_line_number",
+    "indent_tab_lines",
+    "indent_space_lines",
+    "indent_mixed_lines",
+    "unindented_lines",
+    "indent_width",
+    "trailing_blank_lines",
+    "final_newline",
+    "line_ending_type",
+    "status",
+    "skip_reason",
+];
+
+/// What separates the cells of a row, and with it how a cell is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Separator {
+    /// `--format csv`, quoting the cells that need it.
+    Comma,
+    /// `--format tsv`, where no cell holds a tab or a line break: they are
+    /// written as the escapes the text report uses, `\t` and `\n`.
+    Tab,
+}
+
+impl Separator {
+    fn joined(self, cells: &[String]) -> String {
+        match self {
+            Separator::Comma => cells
+                .iter()
+                .map(|cell| field(cell))
+                .collect::<Vec<_>>()
+                .join(","),
+            Separator::Tab => cells
+                .iter()
+                .map(|cell| escape_name(cell))
+                .collect::<Vec<_>>()
+                .join("\t"),
+        }
+    }
+}
+
+/// Writes a header row, unless `--no-header` leaves it out, followed by one
+/// row per file in the order they were found, with the columns `--columns`
+/// picks or all of them. Skipped files get a row with empty counts, their
+/// size in `total_bytes`, `skipped` for their `status` and the reason in
+/// `skip_reason`.
+pub fn write(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    separator: Separator,
+    options: ReportOptions,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let picked: Vec<&Field> = match options.columns {
+        Some(Columns(picked)) => picked.to_vec(),
+        None => COLUMNS
+            .iter()
+            .map(|name| fields::find(name).expect("the columns are fields"))
+            .collect(),
+    };
+    if !options.no_header {
+        let names: Vec<String> = picked.iter().map(|field| field.name.to_string()).collect();
+        writeln!(writer, "{}", separator.joined(&names))?;
+    }
+    for file in super::in_order(stats, skipped) {
+        let cells: Vec<String> = match file {
+            Reported::Analyzed(file) => picked.iter().map(|field| (field.value)(file)).collect(),
+            Reported::Skipped(file) => picked
+                .iter()
+                .map(|field| skipped_cell(field, file))
+                .collect(),
+        };
+        writeln!(writer, "{}", separator.joined(&cells))?;
+    }
+    Ok(())
+}
+
+/// The cell of a skipped file, which has a name, an extension and a size
+/// when it is known, and the reason it was skipped.
+fn skipped_cell(field: &Field, file: &SkippedFile) -> String {
+    match field.name {
+        "name" | "file" | "file_name" => osname::display(&file.file_name).into_owned(),
+        "extension" | "file_extension" => Path::new(&file.file_name)
+            .extension()
+            .map_or(String::new(), |ext| ext.to_string_lossy().into_owned()),
+        "bytes" | "total_bytes" | "size_bytes" => {
+            file.size.map_or(String::new(), |size| size.to_string())
+        }
+        "status" => "skipped".to_string(),
+        "skip_reason" => file.reason.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Quotes a field when it contains a delimiter, quote, or line break.
+fn field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_quoting() {
+        assert_eq!(field("plain.txt"), "plain.txt");
+        assert_eq!(field("a,b.txt"), "\"a,b.txt\"");
+        assert_eq!(field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(field("two\nlines"), "\"two\nlines\"");
+    }
+
+    #[test]
+    fn test_write_rows() {
+        let mut stats = FileStats::new("a.bat".to_string());
+        stats.total_lines = 1;
+        stats.dos_endings = 1;
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            Separator::Comma,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            format!(
+                "{}\na.bat,bat,,1,0,0,,,0,0,0,1,0,0,1.0000,0.0000,0.0000,0,,0,,0,,,0,0,0,0.00,0,0,,0,0,0,0,,0,,DOS/Windows (CRLF),analyzed,\n",
+                COLUMNS.join(",")
+            )
+        );
+    }
+
+    #[test]
+    fn test_write_tsv() {
+        let mut stats = FileStats::new("tab\there\nand there.txt".to_string());
+        stats.total_lines = 2;
+        stats.unix_endings = 1;
+        let stats = [stats];
+        let skipped = [SkippedFile {
+            file_name: "big\t.bin".to_string(),
+            size: None,
+            reason: "binary".to_string(),
+            after: 1,
+        }];
+        let written = |options: ReportOptions| {
+            let mut buffer = Vec::new();
+            write(&stats, &skipped, Separator::Tab, options, &mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        let options = ReportOptions {
+            columns: Some("file,lines,lf,crlf".parse().unwrap()),
+            ..ReportOptions::default()
+        };
+        assert_eq!(
+            written(options),
+            "file\tlines\tlf\tcrlf\ntab\\there\\nand there.txt\t2\t1\t0\nbig\\t.bin\t\t\t\n"
+        );
+        let options = ReportOptions {
+            columns: Some("type,file".parse().unwrap()),
+            no_header: true,
+            ..ReportOptions::default()
+        };
+        assert_eq!(
+            written(options),
+            "Unix/Linux (LF)\ttab\\there\\nand there.txt\n\tbig\\t.bin\n"
+        );
+        let all = written(ReportOptions::default());
+        assert!(all.starts_with(&format!("{}\n", COLUMNS.join("\t"))));
+        assert_eq!(
+            all.lines().nth(1).unwrap().split('\t').count(),
+            COLUMNS.len()
+        );
+    }
+
+    #[test]
+    fn test_write_skipped_row() {
+        let skipped = SkippedFile {
+            file_name: "dump.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 0,
+        };
+        let mut buffer = Vec::new();
+        write(
+            &[],
+            &[skipped],
+            Separator::Comma,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            format!(
+                "{}\ndump.sql,sql,,,,,,,2048,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,,skipped,larger than --max-size (1024 bytes)\n",
+                COLUMNS.join(",")
+            )
+        );
+    }
+}
@@ -0,0 +1,552 @@
+use std::str::FromStr;
+
+use crate::FileStats;
+
+/// How a field's values line up in `--format table`, and whether its
+/// totals row adds them up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Text,
+    /// A count, which the totals row sums.
+    Count,
+    /// Any other number, such as a ratio or a line number.
+    Number,
+}
+
+/// A value of a file by the name `--get`, `--template` and `--columns` take
+/// for it.
+#[derive(Debug)]
+pub struct Field {
+    pub name: &'static str,
+    pub kind: Kind,
+    /// The value, empty where the file has none, such as the extension of
+    /// `Makefile`.
+    pub value: fn(&FileStats) -> String,
+}
+
+/// Leaves the value empty for `None`.
+fn optional(value: Option<impl ToString>) -> String {
+    value.map_or(String::new(), |value| value.to_string())
+}
+
+/// Every field, in the order they are listed in error messages: the short
+/// names first, then the fields as `--format json` names them, then the
+/// columns of `--format csv` that JSON does not have on their own.
+pub const FIELDS: &[Field] = &[
+    Field {
+        name: "name",
+        kind: Kind::Text,
+        value: |file| file.display_name().into_owned(),
+    },
+    Field {
+        name: "extension",
+        kind: Kind::Text,
+        value: |file| file.file_extension.clone().unwrap_or_default(),
+    },
+    Field {
+        name: "total_lines",
+        kind: Kind::Count,
+        value: |file| file.total_lines.to_string(),
+    },
+    Field {
+        name: "empty_lines",
+        kind: Kind::Count,
+        value: |file| file.empty_lines.to_string(),
+    },
+    Field {
+        name: "blank_lines",
+        kind: Kind::Count,
+        value: |file| file.blank_lines.to_string(),
+    },
+    Field {
+        name: "crlf",
+        kind: Kind::Count,
+        value: |file| file.dos_endings.to_string(),
+    },
+    Field {
+        name: "lf",
+        kind: Kind::Count,
+        value: |file| file.unix_endings.to_string(),
+    },
+    Field {
+        name: "cr",
+        kind: Kind::Count,
+        value: |file| file.mac_endings.to_string(),
+    },
+    Field {
+        name: "ending_type",
+        kind: Kind::Text,
+        value: |file| file.line_ending_type(),
+    },
+    Field {
+        name: "is_mixed",
+        kind: Kind::Text,
+        value: |file| file.is_mixed().to_string(),
+    },
+    Field {
+        name: "bom",
+        kind: Kind::Text,
+        value: |file| file.bom.map_or("", |bom| bom.name()).to_string(),
+    },
+    Field {
+        name: "detected_encoding",
+        kind: Kind::Text,
+        value: |file| {
+            let guess = file.detected_encoding;
+            guess.map_or("", |guess| guess.charset.name()).to_string()
+        },
+    },
+    Field {
+        name: "is_binary",
+        kind: Kind::Text,
+        value: |file| file.is_binary.to_string(),
+    },
+    Field {
+        name: "grew_during_scan",
+        kind: Kind::Text,
+        value: |file| file.grew_during_scan.to_string(),
+    },
+    Field {
+        name: "truncated_during_scan",
+        kind: Kind::Text,
+        value: |file| file.truncated_during_scan.to_string(),
+    },
+    Field {
+        name: "generated",
+        kind: Kind::Text,
+        value: |file| {
+            let generated = file.generated.as_ref();
+            generated
+                .map_or("", |generated| generated.heuristic())
+                .to_string()
+        },
+    },
+    Field {
+        name: "bytes",
+        kind: Kind::Count,
+        value: |file| file.total_bytes.to_string(),
+    },
+    Field {
+        name: "chars",
+        kind: Kind::Count,
+        value: |file| file.total_chars.to_string(),
+    },
+    Field {
+        name: "words",
+        kind: Kind::Count,
+        value: |file| file.total_words.to_string(),
+    },
+    Field {
+        name: "file",
+        kind: Kind::Text,
+        value: |file| file.display_name().into_owned(),
+    },
+    Field {
+        name: "lines",
+        kind: Kind::Count,
+        value: |file| file.total_lines.to_string(),
+    },
+    Field {
+        name: "type",
+        kind: Kind::Text,
+        value: |file| file.line_ending_type(),
+    },
+    Field {
+        name: "ending",
+        kind: Kind::Text,
+        value: |file| file.ending_word().to_string(),
+    },
+    Field {
+        name: "problems",
+        kind: Kind::Text,
+        value: |file| problems(file).join(", "),
+    },
+    Field {
+        name: "size_bytes",
+        kind: Kind::Count,
+        value: |file| file.size_bytes().to_string(),
+    },
+    Field {
+        name: "file_name",
+        kind: Kind::Text,
+        value: |file| file.display_name().into_owned(),
+    },
+    Field {
+        name: "file_extension",
+        kind: Kind::Text,
+        value: |file| file.file_extension.clone().unwrap_or_default(),
+    },
+    Field {
+        name: "total_bytes",
+        kind: Kind::Count,
+        value: |file| file.total_bytes.to_string(),
+    },
+    Field {
+        name: "compressed_bytes",
+        kind: Kind::Count,
+        value: |file| optional(file.compressed_bytes),
+    },
+    Field {
+        name: "window",
+        kind: Kind::Text,
+        value: |file| optional(file.window),
+    },
+    Field {
+        name: "head",
+        kind: Kind::Text,
+        value: |file| optional(file.head),
+    },
+    Field {
+        name: "truncated",
+        kind: Kind::Text,
+        value: |file| file.truncated.to_string(),
+    },
+    Field {
+        name: "total_chars",
+        kind: Kind::Count,
+        value: |file| file.total_chars.to_string(),
+    },
+    Field {
+        name: "total_words",
+        kind: Kind::Count,
+        value: |file| file.total_words.to_string(),
+    },
+    Field {
+        name: "dos_endings",
+        kind: Kind::Count,
+        value: |file| file.dos_endings.to_string(),
+    },
+    Field {
+        name: "unix_endings",
+        kind: Kind::Count,
+        value: |file| file.unix_endings.to_string(),
+    },
+    Field {
+        name: "mac_endings",
+        kind: Kind::Count,
+        value: |file| file.mac_endings.to_string(),
+    },
+    Field {
+        name: "first_lf_offset",
+        kind: Kind::Number,
+        value: |file| optional(file.first_lf_offset),
+    },
+    Field {
+        name: "first_crlf_offset",
+        kind: Kind::Number,
+        value: |file| optional(file.first_crlf_offset),
+    },
+    Field {
+        name: "first_cr_offset",
+        kind: Kind::Number,
+        value: |file| optional(file.first_cr_offset),
+    },
+    Field {
+        name: "final_newline",
+        kind: Kind::Text,
+        value: |file| optional(file.final_newline),
+    },
+    Field {
+        name: "shebang",
+        kind: Kind::Text,
+        value: |file| file.shebang.clone().unwrap_or_default(),
+    },
+    Field {
+        name: "detected_language",
+        kind: Kind::Text,
+        value: |file| file.detected_language.unwrap_or_default().to_string(),
+    },
+    Field {
+        name: "detected_type",
+        kind: Kind::Text,
+        value: |file| file.detected_type.clone().unwrap_or_default(),
+    },
+    Field {
+        name: "sha256",
+        kind: Kind::Text,
+        value: |file| file.sha256.clone().unwrap_or_default(),
+    },
+    Field {
+        name: "comment_lines",
+        kind: Kind::Count,
+        value: |file| optional(file.comment_lines),
+    },
+    Field {
+        name: "code_lines",
+        kind: Kind::Count,
+        value: |file| optional(file.code_lines),
+    },
+    Field {
+        name: "nul_bytes",
+        kind: Kind::Count,
+        value: |file| file.nul_bytes.to_string(),
+    },
+    Field {
+        name: "first_nul_line",
+        kind: Kind::Number,
+        value: |file| optional(file.first_nul_line),
+    },
+    Field {
+        name: "control_chars",
+        kind: Kind::Count,
+        value: |file| file.control_chars.to_string(),
+    },
+    Field {
+        name: "first_control_line",
+        kind: Kind::Number,
+        value: |file| optional(file.first_control_line),
+    },
+    Field {
+        name: "non_ascii_chars",
+        kind: Kind::Count,
+        value: |file| file.non_ascii_chars.to_string(),
+    },
+    Field {
+        name: "first_non_ascii_line",
+        kind: Kind::Number,
+        value: |file| optional(file.first_non_ascii_line),
+    },
+    Field {
+        name: "first_non_ascii_column",
+        kind: Kind::Number,
+        value: |file| optional(file.first_non_ascii_column),
+    },
+    Field {
+        name: "embedded_crs",
+        kind: Kind::Count,
+        value: |file| file.embedded_crs.to_string(),
+    },
+    Field {
+        name: "phantom_blank_lines",
+        kind: Kind::Count,
+        value: |file| file.phantom_blank_lines.to_string(),
+    },
+    Field {
+        name: "trailing_whitespace_lines",
+        kind: Kind::Count,
+        value: |file| file.trailing_whitespace_lines.to_string(),
+    },
+    Field {
+        name: "trailing_blank_lines",
+        kind: Kind::Count,
+        value: |file| file.trailing_blank_lines.to_string(),
+    },
+    Field {
+        name: "min_line_length",
+        kind: Kind::Number,
+        value: |file| file.min_line_length.to_string(),
+    },
+    Field {
+        name: "max_line_length",
+        kind: Kind::Number,
+        value: |file| file.max_line_length.to_string(),
+    },
+    Field {
+        name: "max_line_bytes",
+        kind: Kind::Number,
+        value: |file| file.max_line_bytes.to_string(),
+    },
+    Field {
+        name: "longest_line_number",
+        kind: Kind::Number,
+        value: |file| optional(file.longest_line),
+    },
+    Field {
+        name: "dos_ratio",
+        kind: Kind::Number,
+        value: |file| format!("{:.4}", file.ending_ratios()[0]),
+    },
+    Field {
+        name: "unix_ratio",
+        kind: Kind::Number,
+        value: |file| format!("{:.4}", file.ending_ratios()[1]),
+    },
+    Field {
+        name: "mac_ratio",
+        kind: Kind::Number,
+        value: |file| format!("{:.4}", file.ending_ratios()[2]),
+    },
+    Field {
+        name: "avg_line_length",
+        kind: Kind::Number,
+        value: |file| format!("{:.2}", file.mean_line_length()),
+    },
+    Field {
+        name: "indent_tab_lines",
+        kind: Kind::Count,
+        value: |file| file.indentation.tabs.to_string(),
+    },
+    Field {
+        name: "indent_space_lines",
+        kind: Kind::Count,
+        value: |file| file.indentation.spaces.to_string(),
+    },
+    Field {
+        name: "indent_mixed_lines",
+        kind: Kind::Count,
+        value: |file| file.indentation.mixed.to_string(),
+    },
+    Field {
+        name: "unindented_lines",
+        kind: Kind::Count,
+        value: |file| file.indentation.none.to_string(),
+    },
+    Field {
+        name: "indent_width",
+        kind: Kind::Number,
+        value: |file| optional(file.indentation.width),
+    },
+    Field {
+        name: "line_ending_type",
+        kind: Kind::Text,
+        value: |file| file.line_ending_type(),
+    },
+    Field {
+        name: "status",
+        kind: Kind::Text,
+        value: |_| "analyzed".to_string(),
+    },
+    // Empty but for a skipped file, whose row `--format csv` fills in.
+    Field {
+        name: "skip_reason",
+        kind: Kind::Text,
+        value: |_| String::new(),
+    },
+];
+
+/// The field called `name`.
+pub fn find(name: &str) -> Option<&'static Field> {
+    FIELDS.iter().find(|field| field.name == name)
+}
+
+/// The error for a name that is not a field, which lists them all in the
+/// words of whoever asked: "Unknown --get field 'x'; valid fields are:
+/// name, extension, ...".
+pub fn unknown(message: String, valid: &str) -> String {
+    let names: Vec<&str> = FIELDS.iter().map(|field| field.name).collect();
+    format!("{}; {} are: {}", message, valid, names.join(", "))
+}
+
+/// What is wrong with a file: the checks it fails, or when it fails none,
+/// what would be whatever the checks asked for.
+pub fn problems(file: &FileStats) -> Vec<&str> {
+    let mut problems: Vec<&str> = Vec::new();
+    for violation in file.violations.iter().filter(|found| !found.suppressed) {
+        if !problems.contains(&violation.check.as_str()) {
+            problems.push(&violation.check);
+        }
+    }
+    if !problems.is_empty() {
+        return problems;
+    }
+    if file.is_mixed() {
+        problems.push("mixed endings");
+    }
+    if file.final_newline == Some(false) {
+        problems.push("no final newline");
+    }
+    if file.trailing_whitespace_lines > 0 {
+        problems.push("trailing whitespace");
+    }
+    if file.indentation.mixed > 0 {
+        problems.push("mixed indentation");
+    }
+    problems
+}
+
+/// The fields `--columns` picks, in its order.
+#[derive(Debug, Clone, Copy)]
+pub struct Columns(pub &'static [&'static Field]);
+
+impl PartialEq for Columns {
+    fn eq(&self, other: &Columns) -> bool {
+        let names =
+            |columns: &Columns| columns.0.iter().map(|field| field.name).collect::<Vec<_>>();
+        names(self) == names(other)
+    }
+}
+
+impl Eq for Columns {}
+
+impl FromStr for Columns {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let picked = value
+            .split(',')
+            .map(|name| {
+                find(name).ok_or_else(|| {
+                    unknown(
+                        format!("Unknown --columns name '{}'", name),
+                        "valid columns",
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Columns(Box::leak(picked.into_boxed_slice())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::template::{self, Template};
+
+    #[test]
+    fn test_one_list_of_names() {
+        for field in FIELDS {
+            assert_eq!(template::field(field.name), Ok(field.name));
+            assert!(format!("{{{}}}", field.name).parse::<Template>().is_ok());
+            assert!(field.name.parse::<Columns>().is_ok());
+            assert_eq!(
+                FIELDS
+                    .iter()
+                    .filter(|other| other.name == field.name)
+                    .count(),
+                1
+            );
+        }
+        let names: Vec<&str> = FIELDS.iter().map(|field| field.name).collect();
+        let listed = |error: String| error.split_once(" are: ").unwrap().1.to_string();
+        assert_eq!(
+            listed(template::field("nope").unwrap_err()),
+            names.join(", ")
+        );
+        assert_eq!(
+            listed("{nope}".parse::<Template>().unwrap_err()),
+            names.join(", ")
+        );
+        assert_eq!(
+            listed("nope".parse::<Columns>().unwrap_err()),
+            names.join(", ")
+        );
+    }
+
+    #[test]
+    fn test_values() {
+        let mut file = FileStats::new("src/a.rs".to_string());
+        file.total_lines = 3;
+        file.unix_endings = 2;
+        file.dos_endings = 1;
+        file.total_bytes = 12;
+        file.final_newline = Some(false);
+        let value = |name: &str| (find(name).unwrap().value)(&file);
+        assert_eq!(value("file"), "src/a.rs");
+        assert_eq!(value("lines"), value("total_lines"));
+        assert_eq!(value("type"), "Mostly Unix/Linux (LF)");
+        assert_eq!(value("ending"), "mixed");
+        assert_eq!(value("problems"), "mixed endings, no final newline");
+        assert_eq!(value("size_bytes"), "12");
+        assert_eq!(value("unix_ratio"), "0.6667");
+        assert_eq!(value("comment_lines"), "");
+        assert_eq!(
+            "lines,,crlf"
+                .parse::<Columns>()
+                .unwrap_err()
+                .split_once(';')
+                .unwrap()
+                .0,
+            "Unknown --columns name ''"
+        );
+    }
+}
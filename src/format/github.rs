@@ -0,0 +1,92 @@
+use std::io::{self, Write};
+
+use super::escape_name;
+use crate::checks::Severity;
+use crate::FileStats;
+
+/// The workflow command GitHub Actions annotates a violation of `severity`
+/// with.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "notice",
+    }
+}
+
+/// Writes a GitHub Actions workflow command for each violation that no
+/// marker silences, which the workflow shows as an annotation on the file:
+/// `::warning file=a.txt,line=4,title=trailing-whitespace::trailing
+/// whitespace`. A file-wide violation has no `line`.
+pub fn write(stats: &[FileStats], mut writer: impl Write) -> io::Result<()> {
+    for file in stats {
+        let name = property(&escape_name(&file.name()));
+        for violation in file.violations.iter().filter(|found| !found.suppressed) {
+            let line = violation
+                .line
+                .map(|line| format!(",line={}", line))
+                .unwrap_or_default();
+            writeln!(
+                writer,
+                "::{} file={}{},title={}::{}",
+                level(violation.severity),
+                name,
+                line,
+                property(&violation.check),
+                message(&violation.message)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Escapes `text` for the message of a workflow command, which ends at the
+/// end of the line.
+fn message(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escapes `text` for a property of a workflow command, which also ends at
+/// `,` and `:`.
+fn property(text: &str) -> String {
+    message(text).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Violation;
+
+    #[test]
+    fn test_annotations() {
+        let mut file = FileStats::new("src/a,b:c.txt".to_string());
+        for (check, severity, line, suppressed) in [
+            ("trailing-whitespace", Severity::Warning, Some(4), false),
+            ("mixed-endings", Severity::Error, None, false),
+            ("final-newline", Severity::Info, None, false),
+            ("line-length", Severity::Error, Some(2), true),
+        ] {
+            file.violations.push(Violation {
+                check: check.to_string(),
+                severity,
+                line,
+                message: "100% off:\nsee".to_string(),
+                suppressed,
+            });
+        }
+        let mut buffer = Vec::new();
+        write(
+            &[file, FileStats::new("clean.txt".to_string())],
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "::warning file=src/a%2Cb%3Ac.txt,line=4,title=trailing-whitespace::100%25 off:%0Asee\n\
+             ::error file=src/a%2Cb%3Ac.txt,title=mixed-endings::100%25 off:%0Asee\n\
+             ::notice file=src/a%2Cb%3Ac.txt,title=final-newline::100%25 off:%0Asee\n"
+        );
+    }
+}
@@ -0,0 +1,238 @@
+use std::io::{self, Write};
+
+use super::escape_name;
+use crate::{FileStats, SkippedFile};
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;color:#222}\
+table{border-collapse:collapse}\
+th,td{border:1px solid #ccc;padding:4px 8px}\
+th{background:#eee;cursor:pointer;user-select:none}\
+td.num{text-align:right;font-variant-numeric:tabular-nums}\
+dl{display:grid;grid-template-columns:max-content auto;gap:2px 12px}\
+dt{font-weight:bold}dd{margin:0}";
+
+const SCRIPT: &str = "document.querySelectorAll('th').forEach(function(th,i){\
+th.addEventListener('click',function(){\
+var body=th.closest('table').tBodies[0];\
+var asc=th.dataset.dir!=='asc';th.dataset.dir=asc?'asc':'desc';\
+var rows=Array.prototype.slice.call(body.rows);\
+rows.sort(function(a,b){\
+var x=a.cells[i].textContent,y=b.cells[i].textContent;\
+var n=th.classList.contains('num');\
+var c=n?Number(x)-Number(y):x.localeCompare(y);\
+return asc?c:-c;});\
+rows.forEach(function(r){body.appendChild(r);});});});";
+
+/// Writes a standalone HTML page with a summary section and a sortable
+/// table. Rows are written to `writer` as they are produced so large runs
+/// never build the whole document in memory. Skipped files are listed after
+/// the table.
+pub fn write(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    writeln!(writer, "<!DOCTYPE html>")?;
+    writeln!(writer, "<html lang=\"en\">")?;
+    writeln!(writer, "<head>")?;
+    writeln!(writer, "<meta charset=\"utf-8\">")?;
+    writeln!(writer, "<title>mdlt report</title>")?;
+    writeln!(writer, "<style>{}</style>", STYLE)?;
+    writeln!(writer, "</head>")?;
+    writeln!(writer, "<body>")?;
+    writeln!(writer, "<h1>File Analysis Report</h1>")?;
+
+    write_summary(stats, skipped, &mut writer)?;
+
+    writeln!(writer, "<table>")?;
+    writeln!(
+        writer,
+        "<thead><tr><th>File</th><th>Ending type</th><th class=\"num\">Total lines</th>\
+<th class=\"num\">Empty lines</th><th class=\"num\">CRLF</th><th class=\"num\">LF</th>\
+<th class=\"num\">CR</th><th class=\"num\">Words</th><th class=\"num\">Characters</th>\
+<th class=\"num\">Bytes</th></tr></thead>"
+    )?;
+    writeln!(writer, "<tbody>")?;
+    for file in stats {
+        writeln!(
+            writer,
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+<td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+<td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+            escape(&escape_name(&file.name())),
+            escape(&file.line_ending_type()),
+            file.total_lines,
+            file.empty_lines,
+            file.dos_endings,
+            file.unix_endings,
+            file.mac_endings,
+            file.total_words,
+            file.total_chars,
+            file.total_bytes
+        )?;
+    }
+    writeln!(writer, "</tbody>")?;
+    writeln!(writer, "</table>")?;
+    let violations = stats
+        .iter()
+        .flat_map(|file| file.violations.iter().map(move |found| (file, found)))
+        .filter(|(_, found)| !found.suppressed);
+    if violations.clone().next().is_some() {
+        writeln!(writer, "<h2>Violations</h2>")?;
+        writeln!(writer, "<ul>")?;
+        for (file, violation) in violations {
+            writeln!(
+                writer,
+                "<li>{}: {}: {}</li>",
+                escape(&escape_name(&file.name())),
+                violation.severity.name(),
+                escape(&violation.to_string())
+            )?;
+        }
+        writeln!(writer, "</ul>")?;
+    }
+    if !skipped.is_empty() {
+        writeln!(writer, "<h2>Skipped files</h2>")?;
+        writeln!(writer, "<ul>")?;
+        for file in skipped {
+            writeln!(
+                writer,
+                "<li>{}: {}</li>",
+                escape(&file.label()),
+                escape(&file.reason)
+            )?;
+        }
+        writeln!(writer, "</ul>")?;
+    }
+    writeln!(writer, "<script>{}</script>", SCRIPT)?;
+    writeln!(writer, "</body>")?;
+    writeln!(writer, "</html>")?;
+    Ok(())
+}
+
+fn write_summary(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    // A file within `--max-minority-*` counts under its most common ending.
+    let only = |count: fn(&FileStats) -> usize| {
+        stats
+            .iter()
+            .filter(|s| {
+                !s.is_mixed()
+                    && count(s) > 0
+                    && Some(count(s)) == s.ending_counts().into_iter().max()
+            })
+            .count()
+    };
+    let lf = only(|s| s.unix_endings);
+    let crlf = only(|s| s.dos_endings);
+    let cr = only(|s| s.mac_endings);
+    let mixed = stats.iter().filter(|s| s.is_mixed()).count();
+    let none = stats.len() - lf - crlf - cr - mixed;
+
+    writeln!(writer, "<h2>Summary</h2>")?;
+    writeln!(writer, "<dl>")?;
+    writeln!(writer, "<dt>Files scanned</dt><dd>{}</dd>", stats.len())?;
+    writeln!(writer, "<dt>LF only</dt><dd>{}</dd>", lf)?;
+    writeln!(writer, "<dt>CRLF only</dt><dd>{}</dd>", crlf)?;
+    writeln!(writer, "<dt>CR only</dt><dd>{}</dd>", cr)?;
+    writeln!(writer, "<dt>Mixed</dt><dd>{}</dd>", mixed)?;
+    writeln!(writer, "<dt>No line endings</dt><dd>{}</dd>", none)?;
+    if !skipped.is_empty() {
+        writeln!(writer, "<dt>Skipped</dt><dd>{}</dd>", skipped.len())?;
+    }
+    writeln!(writer, "</dl>")?;
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(name: &str, unix: usize, dos: usize) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.unix_endings = unix;
+        stats.dos_endings = dos;
+        stats.total_lines = unix + dos;
+        stats
+    }
+
+    fn render(stats: &[FileStats]) -> String {
+        let mut buffer = Vec::new();
+        write(stats, &[], &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_document_contains_rows_and_summary() {
+        let output = render(&[
+            stats("a.txt", 2, 0),
+            stats("b.txt", 0, 3),
+            stats("c.txt", 1, 1),
+        ]);
+        assert!(output.starts_with("<!DOCTYPE html>"));
+        assert!(output.trim_end().ends_with("</html>"));
+        assert!(output.contains("<dt>Files scanned</dt><dd>3</dd>"));
+        assert!(output.contains("<dt>LF only</dt><dd>1</dd>"));
+        assert!(output.contains("<dt>CRLF only</dt><dd>1</dd>"));
+        assert!(output.contains("<dt>Mixed</dt><dd>1</dd>"));
+        assert!(output.contains("<tr><td>a.txt</td><td>Unix/Linux (LF)</td>"));
+        assert!(output.contains("<tr><td>b.txt</td><td>DOS/Windows (CRLF)</td>"));
+        assert_eq!(output.matches("<tr><td>").count(), 3);
+        assert!(!output.contains("<link") && !output.contains("src="));
+    }
+
+    #[test]
+    fn test_mixed_threshold_in_summary() {
+        let mut tolerated = stats("a.txt", 99, 1);
+        tolerated.tolerance.percent = Some(5.0);
+        let output = render(&[tolerated, stats("b.txt", 99, 1)]);
+        assert!(output.contains("<dt>LF only</dt><dd>1</dd>"));
+        assert!(output.contains("<dt>CRLF only</dt><dd>0</dd>"));
+        assert!(output.contains("<dt>Mixed</dt><dd>1</dd>"));
+        assert!(output.contains("<dt>No line endings</dt><dd>0</dd>"));
+    }
+
+    #[test]
+    fn test_pathological_file_name_is_escaped() {
+        let output = render(&[stats("<script>alert('x')</script>&.txt", 1, 0)]);
+        assert!(
+            output.contains("<td>&lt;script&gt;alert(&#39;x&#39;)&lt;/script&gt;&amp;.txt</td>")
+        );
+        assert!(!output.contains("<script>alert"));
+    }
+
+    #[test]
+    fn test_skipped_files_are_listed() {
+        let skipped = SkippedFile {
+            file_name: "dump<1>.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 0,
+        };
+        let mut buffer = Vec::new();
+        write(&[stats("a.txt", 1, 0)], &[skipped], &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains("<dt>Skipped</dt><dd>1</dd>"));
+        assert!(output.contains(
+            "<li>dump&lt;1&gt;.sql (2048 bytes): larger than --max-size (1024 bytes)</li>"
+        ));
+    }
+}
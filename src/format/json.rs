@@ -0,0 +1,2281 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::{ReportOptions, Reported};
+use crate::aggregate::{AggregateStats, Groups};
+use crate::blank::BlankRuns;
+use crate::bom::Bom;
+use crate::charset::{Charset, Confidence, Guess};
+use crate::checks::{Severity, Violation};
+use crate::convert::Outcome;
+use crate::duplicates::{DuplicateStats, RepeatedLine};
+use crate::figures;
+use crate::generated::Generated;
+use crate::histogram::Histogram;
+use crate::interactive::Decision;
+use crate::locate::MinorityLines;
+use crate::markdown::MarkdownStats;
+use crate::metadata::{FileMeta, Permissions};
+use crate::osname;
+use crate::pages::Pages;
+use crate::pattern::PatternCount;
+use crate::peek::Peek;
+use crate::suppress::{Scope, Suppression};
+use crate::top::TopLine;
+use crate::transitions::{Ending, Segment};
+use crate::utf8::{InvalidSequence, Utf8Report};
+use crate::{
+    shebang, FailedFile, FileStats, LongLines, MixedIndentLines, SkippedFile, UnicodeBreaks,
+};
+
+/// The version of the layout of the reports that [`write`] writes, as
+/// their `version` key gives it. Reports without the key are of version 1,
+/// from before it, and [`read`] takes the keys added since as absent.
+pub const VERSION: u64 = 2;
+
+/// Writes the files as a JSON array of objects whose keys match the
+/// `FileStats` field names; the `--unicode-linebreaks` counts are only
+/// included when that mode is on, the `utf8_*` keys only with
+/// `--check-encoding`, `histogram` only with `--histogram`, `markdown` only for
+/// Markdown files, `sha256` only with `--hash`, `detected_encoding` only
+/// with `--detect-encoding` for files without a byte-order mark,
+/// `generated` only for files that look generated or minified,
+/// `empty_lines_at` only with `--show-empty`, `duplicates` only with
+/// `--duplicates`, `transitions`
+/// only with `--transitions`, `minority_ending_lines` only with `--locate`,
+/// `metadata` only with `--metadata`, `compressed_bytes` only for files
+/// decompressed from gzip, `window` only with `--lines` or `--bytes`,
+/// `head` and `truncated` only with `--head-lines` or `--head-bytes`, the
+/// `long_lines` keys only with `--max-line-length`, `patterns`, an object
+/// of the counts of each `--count-pattern`, only with that flag, the `mixed_indent`
+/// keys only with `--check mixed-indent`, `comment_lines` and `code_lines`
+/// only for extensions with a known comment syntax, and `warnings` only
+/// when there are any. The
+/// `first_*_line` and `first_*_offset` keys are `null` when nothing was found,
+/// `top_lines` is only written with `--top-lines`, their `columns` with
+/// `--tab-width` too, and `longest_line_preview` and the `preview` of each
+/// of the `top_lines` only with `--preview`; `first_line_preview` and
+/// `last_line_preview` only with `--peek`, `null` without a line of text.
+/// `is_mixed` tells
+/// whether the file is mixed by the same tolerance as the summary, a mixed
+/// file's `line_ending_type` reads like "Mostly Unix/Linux (LF)", and
+/// `line_ending` names it in one lowercase word such as `lf` or `mixed`.
+/// Skipped files
+/// are objects with `"skipped": true`, their size and the reason instead
+/// of counts, in the order the files were found, and then the files that could not be analyzed, with
+/// `"failed": true`, an `error_kind` such as `not_found` or
+/// `permission_denied` and the `error` itself.
+///
+/// Given the totals, the array is the `files` of an object whose `version`
+/// is [`VERSION`] and whose `summary`
+/// holds them, and `--summary-only` leaves `files` out. The `--group-by`
+/// totals go in a `by_extension`, `by_dir` or `by_archive` object keyed by
+/// group, in the order of the table.
+pub fn write(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    failed: &[FailedFile],
+    summary: Option<&AggregateStats>,
+    groups: Option<&Groups>,
+    options: ReportOptions,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let Some(summary) = summary else {
+        write_array(stats, skipped, failed, options, "", &mut writer)?;
+        return writeln!(writer);
+    };
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"version\": {},", VERSION)?;
+    write!(writer, "  \"summary\": {}", summary.json())?;
+    if let Some(groups) = groups {
+        write!(writer, ",\n  \"by_{}\": {{", groups.by.name())?;
+        for (index, (key, totals)) in groups.rows.iter().enumerate() {
+            let separator = if index > 0 { "," } else { "" };
+            write!(
+                writer,
+                "{}\n    {}: {}",
+                separator,
+                string(key),
+                totals.json()
+            )?;
+        }
+        let close = if groups.rows.is_empty() { "" } else { "\n  " };
+        write!(writer, "{}}}", close)?;
+    }
+    if !options.summary_only {
+        write!(writer, ",\n  \"files\": ")?;
+        write_array(stats, skipped, failed, options, "  ", &mut writer)?;
+    }
+    writeln!(writer, "\n}}")
+}
+
+/// Writes the array of files with each line after the first indented by
+/// `indent`, without a trailing newline.
+fn write_array(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    failed: &[FailedFile],
+    options: ReportOptions,
+    indent: &str,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let count = stats.len() + skipped.len() + failed.len();
+    writeln!(writer, "[")?;
+    for (index, file) in super::in_order(stats, skipped).into_iter().enumerate() {
+        write!(writer, "{}  ", indent)?;
+        match file {
+            Reported::Analyzed(file) => write_object(file, options, &mut *writer)?,
+            Reported::Skipped(file) => {
+                write!(writer, "{{{}", file_name(&file.file_name))?;
+                write!(writer, ", \"skipped\": true")?;
+                write!(writer, ", \"size\": {}", number(file.size))?;
+                write!(writer, ", \"reason\": {}}}", string(&file.reason))?;
+            }
+        }
+        let separator = if index + 1 < count { "," } else { "" };
+        writeln!(writer, "{}", separator)?;
+    }
+    for (index, file) in failed.iter().enumerate() {
+        write!(writer, "{}  {{", indent)?;
+        write!(writer, "{}", file_name(&file.file_name))?;
+        write!(writer, ", \"failed\": true")?;
+        write!(writer, ", \"error_kind\": \"{}\"", file.kind)?;
+        write!(writer, ", \"error\": {}", string(&file.error))?;
+        let separator = if count - failed.len() + index + 1 < count {
+            ","
+        } else {
+            ""
+        };
+        writeln!(writer, "}}{}", separator)?;
+    }
+    write!(writer, "{}]", indent)
+}
+
+/// The `file_name` key of `name`, with `�` for the bytes of a name that is
+/// not UTF-8 and then `"file_name_lossy": true` to say so.
+fn file_name(name: &str) -> String {
+    let mut key = format!("\"file_name\": {}", string(&osname::display(name)));
+    if osname::is_lossy(name) {
+        key.push_str(", \"file_name_lossy\": true");
+    }
+    key
+}
+
+/// Writes one file's object of the array `write` writes, without a
+/// trailing newline.
+pub fn write_object(
+    file: &FileStats,
+    options: ReportOptions,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    write!(writer, "{{{}", file_name(&file.name()))?;
+    write!(
+        writer,
+        ", \"file_extension\": {}",
+        file.file_extension
+            .as_deref()
+            .map_or("null".to_string(), string)
+    )?;
+    write!(
+        writer,
+        ", \"bom\": {}",
+        file.bom
+            .map_or("null".to_string(), |bom| string(bom.name()))
+    )?;
+    if let Some(guess) = file.detected_encoding {
+        write!(
+            writer,
+            ", \"detected_encoding\": {{\"charset\": \"{}\", \"confidence\": \"{}\"",
+            guess.charset.name(),
+            guess.confidence.name()
+        )?;
+        if let Some(high) = guess.high_bytes {
+            write!(writer, ", \"high_bytes\": {}", high)?;
+        }
+        write!(writer, ", \"decoded\": {}}}", guess.decoded)?;
+    }
+    write!(
+        writer,
+        ", \"shebang\": {}",
+        file.shebang.as_deref().map_or("null".to_string(), string)
+    )?;
+    write!(
+        writer,
+        ", \"detected_language\": {}",
+        file.detected_language.map_or("null".to_string(), string)
+    )?;
+    write!(
+        writer,
+        ", \"detected_type\": {}",
+        file.detected_type
+            .as_deref()
+            .map_or("null".to_string(), string)
+    )?;
+    write!(writer, ", \"is_binary\": {}", file.is_binary)?;
+    if let Some(generated) = &file.generated {
+        let detail = match generated {
+            Generated::Marker { marker, line } => {
+                format!("\"marker\": {}, \"line\": {}", string(marker), line)
+            }
+            Generated::LineShare { percent } => format!("\"percent\": {}", percent),
+            Generated::LineLength { average } => format!("\"average\": {}", average),
+        };
+        write!(
+            writer,
+            ", \"generated\": {{\"heuristic\": \"{}\", {}}}",
+            generated.heuristic(),
+            detail
+        )?;
+    }
+    write!(writer, ", \"total_bytes\": {}", file.total_bytes)?;
+    write!(writer, ", \"size_bytes\": {}", file.total_bytes)?;
+    write!(writer, ", \"grew_during_scan\": {}", file.grew_during_scan)?;
+    write!(
+        writer,
+        ", \"truncated_during_scan\": {}",
+        file.truncated_during_scan
+    )?;
+    if let Some(compressed) = file.compressed_bytes {
+        write!(writer, ", \"compressed_bytes\": {}", compressed)?;
+    }
+    if let Some(window) = file.window {
+        write!(writer, ", \"window\": {}", string(&window.to_string()))?;
+    }
+    if let Some(head) = file.head {
+        write!(writer, ", \"head\": {}", string(&head.to_string()))?;
+        write!(writer, ", \"truncated\": {}", file.truncated)?;
+    }
+    if let Some(sha256) = &file.sha256 {
+        write!(writer, ", \"sha256\": {}", string(sha256))?;
+    }
+    write!(writer, ", \"total_chars\": {}", file.total_chars)?;
+    write!(writer, ", \"total_words\": {}", file.total_words)?;
+    write!(writer, ", \"total_lines\": {}", file.total_lines)?;
+    write!(writer, ", \"empty_lines\": {}", file.empty_lines)?;
+    if let Some(at) = &file.empty_lines_at {
+        let lines: Vec<String> = at.iter().map(usize::to_string).collect();
+        write!(writer, ", \"empty_lines_at\": [{}]", lines.join(", "))?;
+    }
+    write!(writer, ", \"blank_lines\": {}", file.blank_lines)?;
+    if let (Some(comment), Some(code)) = (file.comment_lines, file.code_lines) {
+        write!(writer, ", \"comment_lines\": {}", comment)?;
+        write!(writer, ", \"code_lines\": {}", code)?;
+    }
+    write!(writer, ", \"dos_endings\": {}", file.dos_endings)?;
+    write!(writer, ", \"unix_endings\": {}", file.unix_endings)?;
+    write!(writer, ", \"mac_endings\": {}", file.mac_endings)?;
+    let [dos, unix, mac] = file.ending_ratios();
+    write!(
+        writer,
+        ", \"dos_ratio\": {:.4}, \"unix_ratio\": {:.4}, \"mac_ratio\": {:.4}",
+        dos, unix, mac
+    )?;
+    write!(
+        writer,
+        ", \"first_lf_offset\": {}",
+        number(file.first_lf_offset)
+    )?;
+    write!(
+        writer,
+        ", \"first_crlf_offset\": {}",
+        number(file.first_crlf_offset)
+    )?;
+    write!(
+        writer,
+        ", \"first_cr_offset\": {}",
+        number(file.first_cr_offset)
+    )?;
+    write!(writer, ", \"nul_bytes\": {}", file.nul_bytes)?;
+    write!(
+        writer,
+        ", \"first_nul_line\": {}",
+        number(file.first_nul_line)
+    )?;
+    write!(writer, ", \"control_chars\": {}", file.control_chars)?;
+    write!(
+        writer,
+        ", \"first_control_line\": {}",
+        number(file.first_control_line)
+    )?;
+    write!(writer, ", \"non_ascii_chars\": {}", file.non_ascii_chars)?;
+    write!(
+        writer,
+        ", \"first_non_ascii_line\": {}",
+        number(file.first_non_ascii_line)
+    )?;
+    write!(
+        writer,
+        ", \"first_non_ascii_column\": {}",
+        number(file.first_non_ascii_column)
+    )?;
+    write!(writer, ", \"embedded_crs\": {}", file.embedded_crs)?;
+    let positions: Vec<String> = file
+        .embedded_cr_at
+        .iter()
+        .map(|(line, column)| format!("[{}, {}]", line, column))
+        .collect();
+    write!(writer, ", \"embedded_cr_at\": [{}]", positions.join(", "))?;
+    write!(
+        writer,
+        ", \"phantom_blank_lines\": {}",
+        file.phantom_blank_lines
+    )?;
+    let lines: Vec<String> = file
+        .phantom_blank_at
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    write!(writer, ", \"phantom_blank_at\": [{}]", lines.join(", "))?;
+    write!(
+        writer,
+        ", \"trailing_whitespace_lines\": {}",
+        file.trailing_whitespace_lines
+    )?;
+    let lines: Vec<String> = file
+        .trailing_whitespace_at
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    write!(
+        writer,
+        ", \"trailing_whitespace_at\": [{}]",
+        lines.join(", ")
+    )?;
+    write!(writer, ", \"min_line_length\": {}", file.min_line_length)?;
+    write!(
+        writer,
+        ", \"avg_line_length\": {:.2}",
+        file.mean_line_length()
+    )?;
+    write!(writer, ", \"max_line_length\": {}", file.max_line_length)?;
+    write!(writer, ", \"max_line_bytes\": {}", file.max_line_bytes)?;
+    write!(
+        writer,
+        ", \"longest_line_number\": {}",
+        number(file.longest_line)
+    )?;
+    write!(
+        writer,
+        ", \"longest_line_length\": {}",
+        file.max_line_length
+    )?;
+    if options.preview {
+        write!(
+            writer,
+            ", \"longest_line_preview\": {}",
+            string(&file.longest_line_preview)
+        )?;
+    }
+    if let Some(lines) = &file.top_lines {
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|top| {
+                let mut line = format!("{{\"line\": {}, \"length\": {}", top.line, top.length);
+                if let Some(columns) = top.columns {
+                    line.push_str(&format!(", \"columns\": {}", columns));
+                }
+                if options.preview {
+                    line.push_str(&format!(", \"preview\": {}", string(&top.preview)));
+                }
+                line.push('}');
+                line
+            })
+            .collect();
+        write!(writer, ", \"top_lines\": [{}]", lines.join(", "))?;
+    }
+    if let Some(peek) = &file.peek {
+        let preview = |line: &Option<String>| line.as_deref().map_or("null".to_string(), string);
+        write!(
+            writer,
+            ", \"first_line_preview\": {}, \"last_line_preview\": {}",
+            preview(&peek.first),
+            preview(&peek.last)
+        )?;
+    }
+    let indentation = &file.indentation;
+    write!(writer, ", \"indent_tab_lines\": {}", indentation.tabs)?;
+    write!(writer, ", \"indent_space_lines\": {}", indentation.spaces)?;
+    write!(writer, ", \"indent_mixed_lines\": {}", indentation.mixed)?;
+    write!(writer, ", \"unindented_lines\": {}", indentation.none)?;
+    write!(writer, ", \"indent_width\": {}", number(indentation.width))?;
+    write!(
+        writer,
+        ", \"trailing_blank_lines\": {}",
+        file.trailing_blank_lines
+    )?;
+    if let Some(runs) = &file.blank_runs {
+        write!(
+            writer,
+            ", \"blank_runs\": {{\"runs\": {}, \"longest\": {}, \"longest_at\": {}",
+            runs.runs, runs.longest, runs.longest_at
+        )?;
+        if let Some(over) = runs.over_limit {
+            write!(writer, ", \"over_limit\": {}", over)?;
+        }
+        write!(writer, "}}")?;
+    }
+    write!(
+        writer,
+        ", \"final_newline\": {}",
+        number(file.final_newline)
+    )?;
+    if let Some(breaks) = &file.unicode_breaks {
+        write!(writer, ", \"nel_endings\": {}", breaks.nel)?;
+        write!(writer, ", \"ls_endings\": {}", breaks.ls)?;
+        write!(writer, ", \"ps_endings\": {}", breaks.ps)?;
+        write!(writer, ", \"vt_endings\": {}", breaks.vt)?;
+        write!(writer, ", \"ff_endings\": {}", breaks.ff)?;
+    }
+    if let Some(pages) = &file.pages {
+        write!(
+            writer,
+            ", \"pages\": {{\"form_feeds\": {}, \"pages\": {}, \"min_lines\": {}, \"max_lines\": {}, \"avg_lines\": {:.2}}}",
+            pages.form_feeds,
+            pages.count(),
+            pages.min_lines,
+            pages.max_lines,
+            pages.mean_lines()
+        )?;
+    }
+    if let Some(report) = &file.utf8 {
+        let positions: Vec<String> = report
+            .first
+            .iter()
+            .map(|at| format!("{{\"line\": {}, \"offset\": {}}}", at.line, at.offset))
+            .collect();
+        write!(writer, ", \"utf8_valid\": {}", report.invalid == 0)?;
+        write!(writer, ", \"utf8_invalid_sequences\": {}", report.invalid)?;
+        write!(writer, ", \"utf8_invalid_at\": [{}]", positions.join(", "))?;
+    }
+    if let Some(histogram) = &file.histogram {
+        let buckets: Vec<String> = histogram
+            .buckets()
+            .map(|(bound, count)| {
+                format!(
+                    "{{\"upper_bound\": {}, \"count\": {}}}",
+                    number(bound),
+                    count
+                )
+            })
+            .collect();
+        write!(writer, ", \"histogram\": [{}]", buckets.join(", "))?;
+    }
+    if let Some(markdown) = &file.markdown {
+        let headings: Vec<String> = markdown.headings.iter().map(usize::to_string).collect();
+        write!(
+            writer,
+            ", \"markdown\": {{\"headings\": [{}], \"code_blocks\": {}, \"fenced_lines\": {}, \
+\"unclosed_fence_line\": {}, \"links\": {}, \"images\": {}}}",
+            headings.join(", "),
+            markdown.code_blocks,
+            markdown.fenced_lines,
+            number(markdown.unclosed_fence),
+            markdown.links,
+            markdown.images
+        )?;
+    }
+    if let Some(duplicates) = &file.duplicates {
+        let lines: Vec<String> = duplicates
+            .most_repeated
+            .iter()
+            .map(|line| {
+                format!(
+                    "{{\"count\": {}, \"first_line\": {}, \"preview\": {}}}",
+                    line.count,
+                    line.first_line,
+                    string(&line.preview)
+                )
+            })
+            .collect();
+        write!(
+            writer,
+            ", \"duplicates\": {{\"distinct_lines\": {}, \"duplicate_lines\": {}, \"most_repeated\": [{}]}}",
+            duplicates.distinct_lines,
+            duplicates.duplicate_lines,
+            lines.join(", ")
+        )?;
+    }
+    if let Some(segments) = &file.transitions {
+        let segments: Vec<String> = segments
+            .iter()
+            .map(|segment| {
+                format!(
+                    "{{\"start_line\": {}, \"end_line\": {}, \"ending\": \"{}\"}}",
+                    segment.start_line,
+                    segment.end_line,
+                    segment.ending.name()
+                )
+            })
+            .collect();
+        write!(writer, ", \"transitions\": [{}]", segments.join(", "))?;
+    }
+    if let Some(minority) = &file.minority_endings {
+        let endings: Vec<String> = minority
+            .iter()
+            .map(|minority| {
+                let lines: Vec<String> = minority.first.iter().map(usize::to_string).collect();
+                format!(
+                    "{{\"ending\": \"{}\", \"count\": {}, \"lines\": [{}]}}",
+                    minority.ending.name(),
+                    minority.count,
+                    lines.join(", ")
+                )
+            })
+            .collect();
+        write!(
+            writer,
+            ", \"minority_ending_lines\": [{}]",
+            endings.join(", ")
+        )?;
+    }
+    if let Some(metadata) = &file.metadata {
+        let permissions = metadata.permissions.map(|permissions| match permissions {
+            Permissions::Mode(mode) => format!("{:04o}", mode),
+            Permissions::ReadOnly(true) => "read-only".to_string(),
+            Permissions::ReadOnly(false) => "writable".to_string(),
+        });
+        write!(
+            writer,
+            ", \"metadata\": {{\"modified\": {}, \"size\": {}, \"permissions\": {}}}",
+            metadata
+                .modified
+                .map_or("null".to_string(), |time| string(&figures::rfc3339(time))),
+            number(metadata.size),
+            permissions.map_or("null".to_string(), |p| string(&p))
+        )?;
+    }
+    if let Some(mixed) = &file.mixed_indent {
+        let lines: Vec<String> = mixed.first.iter().map(|line| line.to_string()).collect();
+        write!(writer, ", \"mixed_indent_lines\": {}", mixed.count)?;
+        write!(writer, ", \"mixed_indent_at\": [{}]", lines.join(", "))?;
+    }
+    if let Some(long) = &file.long_lines {
+        let lines: Vec<String> = long.first.iter().map(|line| line.to_string()).collect();
+        write!(writer, ", \"max_line_length_limit\": {}", long.limit)?;
+        write!(writer, ", \"long_lines\": {}", long.count)?;
+        write!(writer, ", \"long_lines_at\": [{}]", lines.join(", "))?;
+    }
+    if !file.patterns.is_empty() {
+        let patterns: Vec<String> = file
+            .patterns
+            .iter()
+            .map(|count| {
+                let lines: Vec<String> = count.first.iter().map(|line| line.to_string()).collect();
+                format!(
+                    "{}: {{\"lines\": {}, \"matches\": {}, \"lines_at\": [{}]}}",
+                    string(&count.pattern),
+                    count.lines,
+                    count.matches,
+                    lines.join(", ")
+                )
+            })
+            .collect();
+        write!(writer, ", \"patterns\": {{{}}}", patterns.join(", "))?;
+    }
+    if !file.warnings.is_empty() {
+        let warnings: Vec<String> = file.warnings.iter().map(|w| string(w)).collect();
+        write!(writer, ", \"warnings\": [{}]", warnings.join(", "))?;
+    }
+    if !file.violations.is_empty() {
+        let violations: Vec<String> = file
+            .violations
+            .iter()
+            .map(|violation| {
+                format!(
+                    "{{\"check\": {}, \"severity\": {}, \"line\": {}, \"message\": {}, \
+                     \"suppressed\": {}}}",
+                    string(&violation.check),
+                    string(violation.severity.name()),
+                    number(violation.line),
+                    string(&violation.message),
+                    violation.suppressed
+                )
+            })
+            .collect();
+        write!(writer, ", \"violations\": [{}]", violations.join(", "))?;
+    }
+    if !file.suppressions.is_empty() {
+        let suppressions: Vec<String> = file
+            .suppressions
+            .iter()
+            .map(|suppression| {
+                let checks: Vec<String> =
+                    suppression.checks.iter().map(|name| string(name)).collect();
+                format!(
+                    "{{\"scope\": {}, \"line\": {}, \"checks\": [{}]}}",
+                    string(suppression.scope.name()),
+                    suppression.line,
+                    checks.join(", ")
+                )
+            })
+            .collect();
+        write!(writer, ", \"suppressions\": [{}]", suppressions.join(", "))?;
+    }
+    write!(writer, ", \"is_mixed\": {}", file.is_mixed())?;
+    write!(
+        writer,
+        ", \"line_ending_type\": {}",
+        string(&file.line_ending_type())
+    )?;
+    write!(
+        writer,
+        ", \"line_ending\": {}",
+        string(file.line_ending_name())
+    )?;
+    write!(writer, "}}")
+}
+
+/// Writes what `--convert` and `--fix` did as a JSON array of
+/// `{"file_name", "target", "changed"}` objects, where `target` is `null`
+/// unless the endings were converted and `note` says why `--convert auto`
+/// left them alone. `trailing_whitespace_fixed` is only written with
+/// `--fix trailing-whitespace`, `final_newline_added` only when
+/// `--fix final-newline` added one, naming its style, and `backup` only
+/// when a copy was made. `bom_stripped` is only written, as `true`, when
+/// `--fix strip-bom` removed a mark, `retabbed` only with `--fix retab` and
+/// `blank_lines_removed` only with `--fix squeeze-blank`.
+/// With `--dry-run` the counts are of what would change and every object
+/// has `would_change`. Skipped files have `"skipped": true` and the reason.
+/// With `--interactive` a file there was a change for has `decision`,
+/// `applied`, `declined` with the counts of what would have changed, or
+/// `unvisited` and nothing else.
+pub fn write_conversions(outcomes: &[(&str, Outcome)], mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "[")?;
+    for (index, (path, outcome)) in outcomes.iter().enumerate() {
+        write!(writer, "  {{{}", file_name(path))?;
+        if let Some(reason) = &outcome.skipped {
+            write!(
+                writer,
+                ", \"skipped\": true, \"reason\": {}",
+                string(reason)
+            )?;
+        } else if outcome.decision != Some(Decision::Unvisited) {
+            write!(
+                writer,
+                ", \"target\": {}, \"changed\": {}",
+                outcome
+                    .target
+                    .map_or("null".to_string(), |target| string(target.name())),
+                outcome.changed_endings()
+            )?;
+            if let Some(reason) = &outcome.left_alone {
+                write!(writer, ", \"note\": {}", string(reason))?;
+            }
+            if let Some(lines) = outcome.trailing_whitespace {
+                write!(writer, ", \"trailing_whitespace_fixed\": {}", lines)?;
+            }
+            if outcome.bom_stripped {
+                write!(writer, ", \"bom_stripped\": true")?;
+            }
+            if outcome.retab.is_some() {
+                write!(writer, ", \"retabbed\": {}", outcome.retabbed)?;
+            }
+            if let Some(lines) = outcome.blank_lines_removed {
+                write!(writer, ", \"blank_lines_removed\": {}", lines)?;
+            }
+            if let Some(ending) = outcome.final_newline {
+                write!(
+                    writer,
+                    ", \"final_newline_added\": {}",
+                    string(ending.name())
+                )?;
+            }
+            if let Some(backup) = &outcome.backup {
+                write!(writer, ", \"backup\": {}", string(backup))?;
+            }
+        }
+        if let Some(decision) = outcome.decision {
+            write!(writer, ", \"decision\": \"{}\"", decision.name())?;
+        }
+        if outcome.dry_run {
+            write!(writer, ", \"would_change\": {}", outcome.would_change())?;
+        }
+        let separator = if index + 1 < outcomes.len() { "," } else { "" };
+        writeln!(writer, "}}{}", separator)?;
+    }
+    writeln!(writer, "]")
+}
+
+/// A parsed JSON value. Numbers keep their text so that counts and byte
+/// offsets read back exactly.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Value>),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The value of `key` in an object.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Object(fields) => fields
+                .iter()
+                .find(|(name, _)| name == key)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(text) => Some(text),
+            _ => None,
+        }
+    }
+}
+
+/// Parses one JSON document, with errors that give the line and column.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let mut parser = Parser { text, at: 0 };
+    let value = parser.value()?;
+    parser.whitespace();
+    if parser.at < text.len() {
+        return Err(parser.error("unexpected text after the end"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> String {
+        let before = &self.text[..self.at];
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |at| at + 1) + 1;
+        format!("line {} column {}: {}", line, column, message)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.at).copied()
+    }
+
+    fn whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.at += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        self.whitespace();
+        if self.peek() != Some(byte) {
+            return Err(self.error(&format!("expected '{}'", byte as char)));
+        }
+        self.at += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Value, String> {
+        self.whitespace();
+        let rest = &self.text[self.at..];
+        for (word, value) in [
+            ("null", Value::Null),
+            ("true", Value::Bool(true)),
+            ("false", Value::Bool(false)),
+        ] {
+            if rest.starts_with(word) {
+                self.at += word.len();
+                return Ok(value);
+            }
+        }
+        match self.peek() {
+            Some(b'"') => Ok(Value::String(self.string()?)),
+            Some(b'[') => {
+                self.at += 1;
+                let mut items = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b']') {
+                    self.at += 1;
+                    return Ok(Value::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        Some(b']') => {
+                            self.at += 1;
+                            return Ok(Value::Array(items));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some(b'{') => {
+                self.at += 1;
+                let mut fields = Vec::new();
+                self.whitespace();
+                if self.peek() == Some(b'}') {
+                    self.at += 1;
+                    return Ok(Value::Object(fields));
+                }
+                loop {
+                    self.whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    self.whitespace();
+                    match self.peek() {
+                        Some(b',') => self.at += 1,
+                        Some(b'}') => {
+                            self.at += 1;
+                            return Ok(Value::Object(fields));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(b'-' | b'0'..=b'9') => {
+                let start = self.at;
+                while matches!(
+                    self.peek(),
+                    Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9')
+                ) {
+                    self.at += 1;
+                }
+                let number = &self.text[start..self.at];
+                if number.parse::<f64>().is_err() {
+                    self.at = start;
+                    return Err(self.error(&format!("invalid number '{}'", number)));
+                }
+                Ok(Value::Number(number.to_string()))
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    /// Reads a string starting at its opening quote.
+    fn string(&mut self) -> Result<String, String> {
+        self.at += 1;
+        let mut text = String::new();
+        loop {
+            let rest = &self.text[self.at..];
+            let Some(c) = rest.chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.at += c.len_utf8();
+            match c {
+                '"' => return Ok(text),
+                '\\' => {
+                    let escape = self
+                        .peek()
+                        .ok_or_else(|| self.error("unterminated string"))?;
+                    self.at += 1;
+                    text.push(match escape {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => {
+                            let high = self.hex4()?;
+                            let code = if (0xd800..0xdc00).contains(&high)
+                                && self.text[self.at..].starts_with("\\u")
+                            {
+                                self.at += 2;
+                                let low = self.hex4()?;
+                                0x10000 + ((high - 0xd800) << 10) + (low.wrapping_sub(0xdc00))
+                            } else {
+                                high
+                            };
+                            char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER)
+                        }
+                        _ => {
+                            self.at -= 1;
+                            return Err(self.error("invalid escape"));
+                        }
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Result<u32, String> {
+        let digits = self
+            .text
+            .get(self.at..self.at + 4)
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid \\u escape"))?;
+        self.at += 4;
+        Ok(code)
+    }
+}
+
+/// One object of a report, with errors naming the key.
+struct Entry<'a>(&'a Value);
+
+impl Entry<'_> {
+    /// A key that is always written.
+    fn field(&self, key: &str) -> Result<&Value, String> {
+        self.0.get(key).ok_or_else(|| format!("no \"{}\"", key))
+    }
+
+    fn parsed<T: FromStr>(&self, key: &str, value: &Value) -> Result<T, String> {
+        match value {
+            Value::Number(number) => number
+                .parse()
+                .map_err(|_| format!("\"{}\" is not a count: {}", key, number)),
+            _ => Err(format!("\"{}\" is not a number", key)),
+        }
+    }
+
+    fn count<T: FromStr>(&self, key: &str) -> Result<T, String> {
+        self.parsed(key, self.field(key)?)
+    }
+
+    /// A key that is always written but may be `null`.
+    fn nullable<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.field(key)? {
+            Value::Null => Ok(None),
+            value => self.parsed(key, value).map(Some),
+        }
+    }
+
+    /// A key that only some options write.
+    fn optional<T: FromStr>(&self, key: &str) -> Result<Option<T>, String> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(value) => self.parsed(key, value).map(Some),
+        }
+    }
+
+    /// A key added since version 1 of the report, which older reports
+    /// lack, read by `read` or taken as its default when it is missing.
+    fn added<T: Default>(
+        &self,
+        key: &str,
+        read: impl Fn(&Self, &str) -> Result<T, String>,
+    ) -> Result<T, String> {
+        match self.0.get(key) {
+            None => Ok(T::default()),
+            Some(_) => read(self, key),
+        }
+    }
+
+    fn boolean(&self, key: &str) -> Result<Option<bool>, String> {
+        match self.field(key)? {
+            Value::Null => Ok(None),
+            Value::Bool(value) => Ok(Some(*value)),
+            _ => Err(format!("\"{}\" is not true or false", key)),
+        }
+    }
+
+    fn string(&self, key: &str) -> Result<Option<String>, String> {
+        match self.0.get(key) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(text)) => Ok(Some(text.clone())),
+            Some(_) => Err(format!("\"{}\" is not a string", key)),
+        }
+    }
+
+    fn lines(&self, key: &str) -> Result<Vec<usize>, String> {
+        self.array(key)?
+            .iter()
+            .map(|item| self.parsed(key, item))
+            .collect()
+    }
+
+    /// Line and column pairs, written as two-number arrays.
+    fn positions(&self, key: &str) -> Result<Vec<(usize, usize)>, String> {
+        self.array(key)?
+            .iter()
+            .map(|item| match item {
+                Value::Array(pair) if pair.len() == 2 => {
+                    Ok((self.parsed(key, &pair[0])?, self.parsed(key, &pair[1])?))
+                }
+                _ => Err(format!(
+                    "\"{}\" is not a list of line and column pairs",
+                    key
+                )),
+            })
+            .collect()
+    }
+
+    fn array(&self, key: &str) -> Result<&[Value], String> {
+        match self.field(key)? {
+            Value::Array(items) => Ok(items),
+            _ => Err(format!("\"{}\" is not an array", key)),
+        }
+    }
+
+    /// The objects of an array, such as the segments of `transitions`.
+    fn objects(&self, key: &str) -> Result<Vec<Entry<'_>>, String> {
+        Ok(self.array(key)?.iter().map(Entry).collect())
+    }
+
+    /// An object that only some options write.
+    fn object(&self, key: &str) -> Option<Entry<'_>> {
+        self.0
+            .get(key)
+            .filter(|value| **value != Value::Null)
+            .map(Entry)
+    }
+
+    /// One of `choices`, by the name the report gives it.
+    fn named<T: Copy>(
+        &self,
+        key: &str,
+        choices: &[T],
+        name: fn(T) -> &'static str,
+    ) -> Result<Option<T>, String> {
+        match self.string(key)? {
+            None => Ok(None),
+            Some(given) => choices
+                .iter()
+                .copied()
+                .find(|&choice| name(choice) == given)
+                .map(Some)
+                .ok_or_else(|| format!("\"{}\" is not known: {}", key, given)),
+        }
+    }
+
+    fn ending(&self) -> Result<Ending, String> {
+        let endings = [Ending::Crlf, Ending::Lf, Ending::Cr, Ending::Unicode];
+        self.named("ending", &endings, Ending::name)?
+            .ok_or_else(|| "no \"ending\"".to_string())
+    }
+}
+
+/// The [`VERSION`] of a report [`parse`]d, 1 for one without the key.
+pub fn version(report: &Value) -> Result<u64, String> {
+    match report {
+        Value::Object(_) => Ok(Entry(report).optional("version")?.unwrap_or(1)),
+        _ => Ok(1),
+    }
+}
+
+/// Reads back a report that `write` wrote, once [`parse`]d, with or
+/// without a summary around the files, leaving out those that failed.
+/// Every key is restored but the ones worked out from others, such as the
+/// ratios; the total line length is rebuilt from `avg_line_length`, so
+/// only to two decimal places.
+pub fn read(report: &Value) -> Result<(Vec<FileStats>, Vec<SkippedFile>), String> {
+    let entries = match report {
+        Value::Array(entries) => entries,
+        Value::Object(fields) => match fields.iter().find(|(key, _)| key == "files") {
+            Some((_, Value::Array(entries))) => entries,
+            _ => {
+                return Err(
+                    "no \"files\" array, as in a report written with --summary-only".to_string(),
+                )
+            }
+        },
+        _ => return Err("expected an array of files".to_string()),
+    };
+    let mut stats = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, value) in entries.iter().enumerate() {
+        let entry = Entry(value);
+        let name = entry
+            .string("file_name")?
+            .ok_or_else(|| format!("entry {} has no \"file_name\"", index + 1))?;
+        let context = |message: String| format!("entry {} ({}): {}", index + 1, name, message);
+        if value.get("failed") == Some(&Value::Bool(true)) {
+            continue;
+        }
+        if value.get("skipped") == Some(&Value::Bool(true)) {
+            skipped.push(SkippedFile {
+                file_name: name.clone(),
+                size: entry.optional("size").map_err(context)?,
+                reason: entry.string("reason").map_err(context)?.unwrap_or_default(),
+                after: stats.len(),
+            });
+            continue;
+        }
+        stats.push(read_stats(&entry, name.clone()).map_err(context)?);
+    }
+    Ok((stats, skipped))
+}
+
+/// Reads back one file object that `write_object` wrote.
+pub fn read_object(value: &Value) -> Result<FileStats, String> {
+    let entry = Entry(value);
+    let name = entry
+        .string("file_name")?
+        .ok_or_else(|| "no \"file_name\"".to_string())?;
+    read_stats(&entry, name)
+}
+
+fn read_stats(entry: &Entry, file_name: String) -> Result<FileStats, String> {
+    let mut file = FileStats::new(file_name);
+    file.file_extension = entry.string("file_extension")?;
+    let boms = [
+        Bom::Utf8,
+        Bom::Utf16Le,
+        Bom::Utf16Be,
+        Bom::Utf32Le,
+        Bom::Utf32Be,
+    ];
+    file.bom = entry.named("bom", &boms, Bom::name)?;
+    if let Some(guess) = entry.object("detected_encoding") {
+        let charsets = [
+            Charset::Utf8,
+            Charset::Utf16Le,
+            Charset::Utf16Be,
+            Charset::EightBit,
+        ];
+        let confidences = [Confidence::Low, Confidence::Medium, Confidence::High];
+        file.detected_encoding = Some(Guess {
+            charset: guess
+                .named("charset", &charsets, Charset::name)?
+                .ok_or_else(|| "no \"charset\"".to_string())?,
+            confidence: guess
+                .named("confidence", &confidences, Confidence::name)?
+                .ok_or_else(|| "no \"confidence\"".to_string())?,
+            high_bytes: guess.optional("high_bytes")?,
+            decoded: guess.boolean("decoded")?.unwrap_or(false),
+        });
+    }
+    file.shebang = entry.string("shebang")?;
+    file.detected_language = file.shebang.as_deref().and_then(shebang::language);
+    file.detected_type = entry.string("detected_type")?;
+    file.is_binary = entry.boolean("is_binary")?.unwrap_or(false);
+    if let Some(generated) = entry.object("generated") {
+        file.generated = Some(match generated.string("heuristic")?.as_deref() {
+            Some("marker") => Generated::Marker {
+                marker: generated.string("marker")?.unwrap_or_default(),
+                line: generated.count("line")?,
+            },
+            Some("line_share") => Generated::LineShare {
+                percent: generated.count("percent")?,
+            },
+            Some("line_length") => Generated::LineLength {
+                average: generated.count("average")?,
+            },
+            other => {
+                return Err(format!(
+                    "\"heuristic\" is not known: {}",
+                    other.unwrap_or("null")
+                ))
+            }
+        });
+    }
+    file.total_bytes = entry.count("total_bytes")?;
+    file.grew_during_scan = entry
+        .added("grew_during_scan", Entry::boolean)?
+        .unwrap_or(false);
+    file.truncated_during_scan = entry
+        .added("truncated_during_scan", Entry::boolean)?
+        .unwrap_or(false);
+    file.compressed_bytes = entry.optional("compressed_bytes")?;
+    file.window = match entry.string("window")? {
+        Some(window) => Some(window.parse()?),
+        None => None,
+    };
+    file.head = match entry.string("head")? {
+        Some(head) => Some(head.parse()?),
+        None => None,
+    };
+    if file.head.is_some() {
+        file.truncated = entry.added("truncated", Entry::boolean)?.unwrap_or(false);
+    }
+    file.sha256 = entry.string("sha256")?;
+    file.total_chars = entry.count("total_chars")?;
+    file.total_words = entry.count("total_words")?;
+    file.total_lines = entry.count("total_lines")?;
+    file.empty_lines = entry.count("empty_lines")?;
+    if entry.0.get("empty_lines_at").is_some() {
+        file.empty_lines_at = Some(entry.lines("empty_lines_at")?);
+    }
+    file.blank_lines = entry.count("blank_lines")?;
+    file.comment_lines = entry.optional("comment_lines")?;
+    file.code_lines = entry.optional("code_lines")?;
+    file.dos_endings = entry.count("dos_endings")?;
+    file.unix_endings = entry.count("unix_endings")?;
+    file.mac_endings = entry.count("mac_endings")?;
+    file.first_lf_offset = entry.nullable("first_lf_offset")?;
+    file.first_crlf_offset = entry.nullable("first_crlf_offset")?;
+    file.first_cr_offset = entry.nullable("first_cr_offset")?;
+    file.nul_bytes = entry.count("nul_bytes")?;
+    file.first_nul_line = entry.nullable("first_nul_line")?;
+    file.control_chars = entry.count("control_chars")?;
+    file.first_control_line = entry.nullable("first_control_line")?;
+    file.non_ascii_chars = entry.count("non_ascii_chars")?;
+    file.first_non_ascii_line = entry.nullable("first_non_ascii_line")?;
+    file.first_non_ascii_column = entry.nullable("first_non_ascii_column")?;
+    file.embedded_crs = entry.optional("embedded_crs")?.unwrap_or(0);
+    file.embedded_cr_at = entry.added("embedded_cr_at", Entry::positions)?;
+    file.phantom_blank_lines = entry.optional("phantom_blank_lines")?.unwrap_or(0);
+    file.phantom_blank_at = entry.added("phantom_blank_at", Entry::lines)?;
+    file.trailing_whitespace_lines = entry.count("trailing_whitespace_lines")?;
+    file.trailing_whitespace_at = entry.lines("trailing_whitespace_at")?;
+    file.min_line_length = entry.count("min_line_length")?;
+    let mean: f64 = entry.count("avg_line_length")?;
+    file.total_line_length = (mean * file.total_lines as f64).round() as u64;
+    file.max_line_length = entry.count("max_line_length")?;
+    file.max_line_bytes = entry.count("max_line_bytes")?;
+    file.longest_line = entry.nullable("longest_line_number")?;
+    file.longest_line_preview = entry.string("longest_line_preview")?.unwrap_or_default();
+    if entry.0.get("top_lines").is_some() {
+        let lines = entry.objects("top_lines")?;
+        file.top_lines = Some(
+            lines
+                .iter()
+                .map(|top| {
+                    Ok(TopLine {
+                        line: top.count("line")?,
+                        length: top.count("length")?,
+                        columns: top.optional("columns")?,
+                        preview: top.string("preview")?.unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        );
+    }
+    if entry.0.get("first_line_preview").is_some() {
+        file.peek = Some(Peek {
+            first: entry.string("first_line_preview")?,
+            last: entry.string("last_line_preview")?,
+        });
+    }
+    file.indentation.tabs = entry.count("indent_tab_lines")?;
+    file.indentation.spaces = entry.count("indent_space_lines")?;
+    file.indentation.mixed = entry.count("indent_mixed_lines")?;
+    file.indentation.none = entry.count("unindented_lines")?;
+    file.indentation.width = entry.nullable("indent_width")?;
+    file.trailing_blank_lines = entry.count("trailing_blank_lines")?;
+    if let Some(runs) = entry.object("blank_runs") {
+        file.blank_runs = Some(BlankRuns {
+            runs: runs.count("runs")?,
+            longest: runs.count("longest")?,
+            longest_at: runs.count("longest_at")?,
+            over_limit: runs.optional("over_limit")?,
+        });
+    }
+    file.final_newline = entry.boolean("final_newline")?;
+    if entry.0.get("nel_endings").is_some() {
+        file.unicode_breaks = Some(UnicodeBreaks {
+            nel: entry.count("nel_endings")?,
+            ls: entry.count("ls_endings")?,
+            ps: entry.count("ps_endings")?,
+            vt: entry.count("vt_endings")?,
+            ff: entry.count("ff_endings")?,
+        });
+    }
+    if let Some(pages) = entry.object("pages") {
+        let count: usize = pages.count("pages")?;
+        let mean: f64 = pages.count("avg_lines")?;
+        file.pages = Some(Pages {
+            form_feeds: pages.count("form_feeds")?,
+            min_lines: pages.count("min_lines")?,
+            max_lines: pages.count("max_lines")?,
+            lines: (mean * count as f64).round() as usize,
+        });
+    }
+    if entry.0.get("utf8_invalid_sequences").is_some() {
+        let first = entry.objects("utf8_invalid_at")?;
+        file.utf8 = Some(Utf8Report {
+            invalid: entry.count("utf8_invalid_sequences")?,
+            first: first
+                .iter()
+                .map(|at| {
+                    Ok(InvalidSequence {
+                        line: at.count("line")?,
+                        offset: at.count("offset")?,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        });
+    }
+    if entry.0.get("histogram").is_some() {
+        let rows = entry
+            .objects("histogram")?
+            .iter()
+            .map(|bucket| Ok((bucket.nullable("upper_bound")?, bucket.count("count")?)))
+            .collect::<Result<Vec<_>, String>>()?;
+        file.histogram = Some(
+            Histogram::from_buckets(&rows)
+                .ok_or("\"histogram\" needs increasing upper bounds and a last one of null")?,
+        );
+    }
+    if let Some(markdown) = entry.object("markdown") {
+        let headings = markdown.lines("headings")?;
+        file.markdown = Some(MarkdownStats {
+            headings: headings
+                .try_into()
+                .map_err(|_| "\"headings\" needs six counts".to_string())?,
+            code_blocks: markdown.count("code_blocks")?,
+            fenced_lines: markdown.count("fenced_lines")?,
+            unclosed_fence: markdown.nullable("unclosed_fence_line")?,
+            links: markdown.count("links")?,
+            images: markdown.count("images")?,
+        });
+    }
+    if let Some(duplicates) = entry.object("duplicates") {
+        let lines = duplicates.objects("most_repeated")?;
+        file.duplicates = Some(DuplicateStats {
+            distinct_lines: duplicates.count("distinct_lines")?,
+            duplicate_lines: duplicates.count("duplicate_lines")?,
+            most_repeated: lines
+                .iter()
+                .map(|line| {
+                    Ok(RepeatedLine {
+                        count: line.count("count")?,
+                        first_line: line.count("first_line")?,
+                        preview: line.string("preview")?.unwrap_or_default(),
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        });
+    }
+    if entry.0.get("transitions").is_some() {
+        let segments = entry.objects("transitions")?;
+        file.transitions = Some(
+            segments
+                .iter()
+                .map(|segment| {
+                    Ok(Segment {
+                        start_line: segment.count("start_line")?,
+                        end_line: segment.count("end_line")?,
+                        ending: segment.ending()?,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        );
+    }
+    if entry.0.get("minority_ending_lines").is_some() {
+        let endings = entry.objects("minority_ending_lines")?;
+        file.minority_endings = Some(
+            endings
+                .iter()
+                .map(|minority| {
+                    Ok(MinorityLines {
+                        ending: minority.ending()?,
+                        count: minority.count("count")?,
+                        first: minority.lines("lines")?,
+                    })
+                })
+                .collect::<Result<_, String>>()?,
+        );
+    }
+    if let Some(meta) = entry.object("metadata") {
+        let modified = match meta.string("modified")? {
+            Some(time) => Some(
+                figures::parse_rfc3339(&time)
+                    .ok_or(format!("\"modified\" is not a time: {}", time))?,
+            ),
+            None => None,
+        };
+        let permissions = match meta.string("permissions")?.as_deref() {
+            None => None,
+            Some("read-only") => Some(Permissions::ReadOnly(true)),
+            Some("writable") => Some(Permissions::ReadOnly(false)),
+            Some(mode) => Some(Permissions::Mode(
+                u32::from_str_radix(mode, 8)
+                    .map_err(|_| format!("\"permissions\" is not a mode: {}", mode))?,
+            )),
+        };
+        file.metadata = Some(FileMeta {
+            modified,
+            size: meta.nullable("size")?,
+            permissions,
+        });
+    }
+    if entry.0.get("mixed_indent_lines").is_some() {
+        file.mixed_indent = Some(MixedIndentLines {
+            count: entry.count("mixed_indent_lines")?,
+            first: entry.lines("mixed_indent_at")?,
+        });
+    }
+    if entry.0.get("long_lines").is_some() {
+        file.long_lines = Some(LongLines {
+            limit: entry.count("max_line_length_limit")?,
+            count: entry.count("long_lines")?,
+            first: entry.lines("long_lines_at")?,
+        });
+    }
+    if let Some(Value::Object(patterns)) = entry.0.get("patterns") {
+        file.patterns = patterns
+            .iter()
+            .map(|(pattern, value)| {
+                let count = Entry(value);
+                Ok(PatternCount {
+                    pattern: pattern.clone(),
+                    lines: count.count("lines")?,
+                    matches: count.count("matches")?,
+                    first: count.lines("lines_at")?,
+                })
+            })
+            .collect::<Result<_, String>>()?;
+    }
+    if let Some(Value::Array(warnings)) = entry.0.get("warnings") {
+        file.warnings = warnings
+            .iter()
+            .filter_map(|warning| warning.as_str().map(str::to_string))
+            .collect();
+    }
+    if entry.0.get("violations").is_some() {
+        let severities = [Severity::Error, Severity::Warning, Severity::Info];
+        file.violations = entry
+            .objects("violations")?
+            .iter()
+            .map(|violation| {
+                Ok(Violation {
+                    check: violation.string("check")?.unwrap_or_default(),
+                    severity: violation
+                        .named("severity", &severities, Severity::name)?
+                        .unwrap_or(Severity::Error),
+                    line: violation.optional("line")?,
+                    message: violation.string("message")?.unwrap_or_default(),
+                    suppressed: violation
+                        .added("suppressed", Entry::boolean)?
+                        .unwrap_or(false),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+    }
+    if entry.0.get("suppressions").is_some() {
+        let scopes = [Scope::Line, Scope::File];
+        file.suppressions = entry
+            .objects("suppressions")?
+            .iter()
+            .map(|suppression| {
+                Ok(Suppression {
+                    scope: suppression
+                        .named("scope", &scopes, Scope::name)?
+                        .ok_or_else(|| "no \"scope\"".to_string())?,
+                    line: suppression.count("line")?,
+                    checks: suppression
+                        .array("checks")?
+                        .iter()
+                        .filter_map(|name| name.as_str().map(str::to_string))
+                        .collect(),
+                })
+            })
+            .collect::<Result<_, String>>()?;
+    }
+    Ok(file)
+}
+
+/// Renders an optional number or boolean, using `null` for `None`.
+pub fn number(value: Option<impl ToString>) -> String {
+    value.map_or("null".to_string(), |value| value.to_string())
+}
+
+/// Renders `text` as a quoted JSON string.
+pub fn string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::{ConvertTo, GroupBy, Retab};
+    use crate::histogram::Histogram;
+    use crate::interactive::Decision;
+    use crate::transitions::Ending;
+
+    #[test]
+    fn test_string_escaping() {
+        assert_eq!(string("plain"), "\"plain\"");
+        assert_eq!(string("a\"b\\c"), "\"a\\\"b\\\\c\"");
+        assert_eq!(string("line\nnext\t\u{1}"), "\"line\\nnext\\t\\u0001\"");
+    }
+
+    fn read(text: &str) -> Result<(Vec<FileStats>, Vec<SkippedFile>), String> {
+        super::read(&parse(text)?)
+    }
+
+    #[test]
+    fn test_write_array() {
+        let mut first = FileStats::new("a.rs".to_string());
+        first.total_lines = 2;
+        first.unix_endings = 2;
+        let second = FileStats::new("Makefile".to_string());
+        let mut buffer = Vec::new();
+        write(
+            &[first, second],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "[\n  {\"file_name\": \"a.rs\", \"file_extension\": \"rs\", \"bom\": null, \"shebang\": null, \"detected_language\": null, \"detected_type\": null, \"is_binary\": false, \"total_bytes\": 0, \"size_bytes\": 0, \"grew_during_scan\": false, \"truncated_during_scan\": false, \"total_chars\": 0, \"total_words\": 0, \"total_lines\": 2, \
+\"empty_lines\": 0, \"blank_lines\": 0, \"dos_endings\": 0, \"unix_endings\": 2, \"mac_endings\": 0, \"dos_ratio\": 0.0000, \"unix_ratio\": 1.0000, \"mac_ratio\": 0.0000, \"first_lf_offset\": null, \"first_crlf_offset\": null, \"first_cr_offset\": null, \"nul_bytes\": 0, \
+\"first_nul_line\": null, \"control_chars\": 0, \"first_control_line\": null, \"non_ascii_chars\": 0, \"first_non_ascii_line\": null, \"first_non_ascii_column\": null, \
+\"embedded_crs\": 0, \"embedded_cr_at\": [], \"phantom_blank_lines\": 0, \"phantom_blank_at\": [], \"trailing_whitespace_lines\": 0, \"trailing_whitespace_at\": [], \
+\"min_line_length\": 0, \"avg_line_length\": 0.00, \"max_line_length\": 0, \
+\"max_line_bytes\": 0, \"longest_line_number\": null, \"longest_line_length\": 0, \
+\"indent_tab_lines\": 0, \"indent_space_lines\": 0, \"indent_mixed_lines\": 0, \"unindented_lines\": 0, \
+\"indent_width\": null, \"trailing_blank_lines\": 0, \"final_newline\": null, \"is_mixed\": false, \"line_ending_type\": \"Unix/Linux (LF)\", \"line_ending\": \"lf\"},\n  \
+{\"file_name\": \"Makefile\", \"file_extension\": null, \"bom\": null, \"shebang\": null, \"detected_language\": null, \"detected_type\": null, \"is_binary\": false, \"total_bytes\": 0, \"size_bytes\": 0, \"grew_during_scan\": false, \"truncated_during_scan\": false, \"total_chars\": 0, \"total_words\": 0, \"total_lines\": 0, \"empty_lines\": 0, \"blank_lines\": 0, \
+\"dos_endings\": 0, \"unix_endings\": 0, \"mac_endings\": 0, \"dos_ratio\": 0.0000, \"unix_ratio\": 0.0000, \"mac_ratio\": 0.0000, \"first_lf_offset\": null, \"first_crlf_offset\": null, \"first_cr_offset\": null, \"nul_bytes\": 0, \
+\"first_nul_line\": null, \"control_chars\": 0, \"first_control_line\": null, \"non_ascii_chars\": 0, \"first_non_ascii_line\": null, \"first_non_ascii_column\": null, \
+\"embedded_crs\": 0, \"embedded_cr_at\": [], \"phantom_blank_lines\": 0, \"phantom_blank_at\": [], \"trailing_whitespace_lines\": 0, \"trailing_whitespace_at\": [], \
+\"min_line_length\": 0, \"avg_line_length\": 0.00, \"max_line_length\": 0, \
+\"max_line_bytes\": 0, \"longest_line_number\": null, \"longest_line_length\": 0, \
+\"indent_tab_lines\": 0, \"indent_space_lines\": 0, \"indent_mixed_lines\": 0, \"unindented_lines\": 0, \
+\"indent_width\": null, \"trailing_blank_lines\": 0, \"final_newline\": null, \"is_mixed\": false, \"line_ending_type\": \"No line endings detected\", \"line_ending\": \"none\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_write_conversions() {
+        let outcomes = [
+            (
+                "a.txt",
+                Outcome {
+                    target: Some(ConvertTo::Crlf),
+                    endings: [0, 3, 0],
+                    trailing_whitespace: Some(2),
+                    retab: Some(Retab::Spaces(4)),
+                    retabbed: 5,
+                    blank_lines_removed: Some(0),
+                    backup: Some("a.txt.bak".to_string()),
+                    ..Outcome::default()
+                },
+            ),
+            (
+                "b.txt",
+                Outcome {
+                    left_alone: Some("tie between CRLF and LF".to_string()),
+                    final_newline: Some(Ending::Lf),
+                    bom_stripped: true,
+                    ..Outcome::default()
+                },
+            ),
+            (
+                "c.png",
+                Outcome {
+                    skipped: Some("binary file".to_string()),
+                    ..Outcome::default()
+                },
+            ),
+        ];
+        let mut buffer = Vec::new();
+        write_conversions(&outcomes, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "[\n  {\"file_name\": \"a.txt\", \"target\": \"CRLF\", \"changed\": 3, \
+             \"trailing_whitespace_fixed\": 2, \"retabbed\": 5, \"blank_lines_removed\": 0, \"backup\": \"a.txt.bak\"},\n  \
+             {\"file_name\": \"b.txt\", \"target\": null, \"changed\": 0, \
+             \"note\": \"tie between CRLF and LF\", \"bom_stripped\": true, \"final_newline_added\": \"LF\"},\n  \
+             {\"file_name\": \"c.png\", \"skipped\": true, \"reason\": \"binary file\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_write_dry_run_conversions() {
+        let outcomes = [
+            (
+                "a.txt",
+                Outcome {
+                    target: Some(ConvertTo::Lf),
+                    endings: [2, 0, 1],
+                    dry_run: true,
+                    ..Outcome::default()
+                },
+            ),
+            (
+                "b.txt",
+                Outcome {
+                    left_alone: Some("no line endings".to_string()),
+                    dry_run: true,
+                    ..Outcome::default()
+                },
+            ),
+        ];
+        let mut buffer = Vec::new();
+        write_conversions(&outcomes, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "[\n  {\"file_name\": \"a.txt\", \"target\": \"LF\", \"changed\": 3, \
+             \"would_change\": true},\n  {\"file_name\": \"b.txt\", \"target\": null, \
+             \"changed\": 0, \"note\": \"no line endings\", \"would_change\": false}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_write_interactive_conversions() {
+        let converted = |decision, dry_run| Outcome {
+            target: Some(ConvertTo::Lf),
+            endings: [2, 0, 0],
+            dry_run,
+            decision: Some(decision),
+            ..Outcome::default()
+        };
+        let unvisited = Outcome {
+            decision: Some(Decision::Unvisited),
+            ..Outcome::default()
+        };
+        let outcomes = [
+            ("a.txt", converted(Decision::Applied, false)),
+            ("b.txt", converted(Decision::Declined, true)),
+            ("c.txt", unvisited),
+        ];
+        let mut buffer = Vec::new();
+        write_conversions(&outcomes, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "[\n  {\"file_name\": \"a.txt\", \"target\": \"LF\", \"changed\": 2, \"decision\": \"applied\"},\n  \
+             {\"file_name\": \"b.txt\", \"target\": \"LF\", \"changed\": 2, \"decision\": \"declined\", \
+             \"would_change\": true},\n  {\"file_name\": \"c.txt\", \"decision\": \"unvisited\"}\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_write_skipped_entries() {
+        let skipped = SkippedFile {
+            file_name: "dump.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 1,
+        };
+        let mut buffer = Vec::new();
+        write(
+            &[FileStats::new("a".to_string())],
+            &[skipped],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains(
+            "\"is_mixed\": false, \"line_ending_type\": \"No line endings detected\", \"line_ending\": \"none\"},\n"
+        ));
+        assert!(output.ends_with(
+            "  {\"file_name\": \"dump.sql\", \"skipped\": true, \"size\": 2048, \
+\"reason\": \"larger than --max-size (1024 bytes)\"}\n]\n"
+        ));
+    }
+
+    #[test]
+    fn test_write_failed_entries() {
+        let failed = FailedFile {
+            file_name: "gone.txt".to_string(),
+            kind: "not_found",
+            error: "no such file".to_string(),
+        };
+        let mut buffer = Vec::new();
+        write(
+            &[FileStats::new("a".to_string())],
+            &[],
+            &[failed],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.ends_with(
+            "  {\"file_name\": \"gone.txt\", \"failed\": true, \"error_kind\": \"not_found\", \
+\"error\": \"no such file\"}\n]\n"
+        ));
+        let (stats, skipped) = read(&output).unwrap();
+        assert_eq!(stats.len(), 1);
+        assert_eq!(stats[0].file_name, "a");
+        assert!(skipped.is_empty());
+    }
+
+    #[test]
+    fn test_write_unicode_breaks() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.unicode_breaks = Some(crate::UnicodeBreaks {
+            nel: 1,
+            ff: 2,
+            ..Default::default()
+        });
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"mac_endings\": 0, \"dos_ratio\": 0.0000, \"unix_ratio\": 0.0000, \"mac_ratio\": 0.0000, \"first_lf_offset\": null, \"first_crlf_offset\": null, \"first_cr_offset\": null, \"nul_bytes\": 0, \"first_nul_line\": null, \
+\"control_chars\": 0, \"first_control_line\": null, \"non_ascii_chars\": 0, \"first_non_ascii_line\": null, \"first_non_ascii_column\": null, \
+\"embedded_crs\": 0, \"embedded_cr_at\": [], \"phantom_blank_lines\": 0, \"phantom_blank_at\": [], \"trailing_whitespace_lines\": 0, \"trailing_whitespace_at\": [], \
+\"min_line_length\": 0, \"avg_line_length\": 0.00, \"max_line_length\": 0, \
+\"max_line_bytes\": 0, \"longest_line_number\": null, \"longest_line_length\": 0, \
+\"indent_tab_lines\": 0, \"indent_space_lines\": 0, \"indent_mixed_lines\": 0, \"unindented_lines\": 0, \
+\"indent_width\": null, \"trailing_blank_lines\": 0, \"final_newline\": null, \"nel_endings\": 1, \"ls_endings\": 0, \"ps_endings\": 0, \
+\"vt_endings\": 0, \"ff_endings\": 2, \"is_mixed\": false, \"line_ending_type\""
+        ));
+    }
+
+    #[test]
+    fn test_preview_only_with_option() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.total_lines = 1;
+        stats.max_line_length = 5;
+        stats.longest_line = Some(1);
+        stats.longest_line_preview = "a\\0\"b\"".to_string();
+        let render = |options| {
+            let mut buffer = Vec::new();
+            write(
+                std::slice::from_ref(&stats),
+                &[],
+                &[],
+                None,
+                None,
+                options,
+                &mut buffer,
+            )
+            .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        let output = render(ReportOptions::default());
+        assert!(output.contains("\"longest_line_number\": 1, \"longest_line_length\": 5, \"indent"));
+        assert!(!output.contains("longest_line_preview"));
+        let output = render(ReportOptions {
+            preview: true,
+            ..ReportOptions::default()
+        });
+        assert!(output.contains(
+            "\"longest_line_length\": 5, \"longest_line_preview\": \"a\\\\0\\\"b\\\"\", \"indent"
+        ));
+    }
+
+    #[test]
+    fn test_write_histogram() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        let mut histogram = Histogram::new("10".parse().unwrap());
+        histogram.add(3);
+        stats.histogram = Some(histogram);
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"histogram\": [{\"upper_bound\": 10, \"count\": 1}, {\"upper_bound\": null, \"count\": 0}]"
+        ));
+    }
+
+    #[test]
+    fn test_write_markdown() {
+        let mut stats = FileStats::new("README.md".to_string());
+        stats.markdown = Some(crate::MarkdownStats {
+            headings: [1, 2, 0, 0, 0, 0],
+            code_blocks: 1,
+            fenced_lines: 4,
+            unclosed_fence: None,
+            links: 3,
+            images: 0,
+        });
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"markdown\": {\"headings\": [1, 2, 0, 0, 0, 0], \"code_blocks\": 1, \"fenced_lines\": 4, \
+\"unclosed_fence_line\": null, \"links\": 3, \"images\": 0}"
+        ));
+    }
+
+    #[test]
+    fn test_write_duplicates() {
+        let mut stats = FileStats::new("app.log".to_string());
+        stats.duplicates = Some(crate::DuplicateStats {
+            distinct_lines: 4,
+            duplicate_lines: 2,
+            most_repeated: vec![crate::duplicates::RepeatedLine {
+                count: 3,
+                first_line: 2,
+                preview: "say \"hi\"".to_string(),
+            }],
+        });
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"duplicates\": {\"distinct_lines\": 4, \"duplicate_lines\": 2, \"most_repeated\": \
+[{\"count\": 3, \"first_line\": 2, \"preview\": \"say \\\"hi\\\"\"}]}"
+        ));
+    }
+
+    #[test]
+    fn test_write_transitions() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.transitions = Some(vec![
+            crate::Segment {
+                start_line: 1,
+                end_line: 40,
+                ending: crate::transitions::Ending::Lf,
+            },
+            crate::Segment {
+                start_line: 41,
+                end_line: 41,
+                ending: crate::transitions::Ending::Crlf,
+            },
+        ]);
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"transitions\": [{\"start_line\": 1, \"end_line\": 40, \"ending\": \"LF\"}, \
+{\"start_line\": 41, \"end_line\": 41, \"ending\": \"CRLF\"}]"
+        ));
+    }
+
+    #[test]
+    fn test_write_minority_endings() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.minority_endings = Some(vec![crate::MinorityLines {
+            ending: crate::transitions::Ending::Crlf,
+            count: 3,
+            first: vec![17, 204],
+        }]);
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"minority_ending_lines\": [{\"ending\": \"CRLF\", \"count\": 3, \"lines\": [17, 204]}]"
+        ));
+    }
+
+    #[test]
+    fn test_write_pages() {
+        let stats = crate::analyze_bytes(b"a\nb\n\x0cc\n", "a.txt");
+        let mut buffer = Vec::new();
+        write_object(&stats, ReportOptions::default(), &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains(
+            ", \"pages\": {\"form_feeds\": 1, \"pages\": 2, \"min_lines\": 1, \"max_lines\": 2, \"avg_lines\": 1.50}"
+        ));
+        let read = read_object(&parse(&output).unwrap()).unwrap();
+        assert_eq!(read.pages, stats.pages);
+        let plain = crate::analyze_bytes(b"a\n", "a.txt");
+        let mut buffer = Vec::new();
+        write_object(&plain, ReportOptions::default(), &mut buffer).unwrap();
+        assert!(!String::from_utf8(buffer).unwrap().contains("pages"));
+    }
+
+    #[test]
+    fn test_write_generated() {
+        let heuristics = [
+            (
+                Generated::Marker {
+                    marker: "@generated".to_string(),
+                    line: 1,
+                },
+                "{\"heuristic\": \"marker\", \"marker\": \"@generated\", \"line\": 1}",
+            ),
+            (
+                Generated::LineShare { percent: 97 },
+                "{\"heuristic\": \"line_share\", \"percent\": 97}",
+            ),
+            (
+                Generated::LineLength { average: 812 },
+                "{\"heuristic\": \"line_length\", \"average\": 812}",
+            ),
+        ];
+        for (generated, expected) in heuristics {
+            let mut stats = FileStats::new("app.min.js".to_string());
+            stats.generated = Some(generated.clone());
+            let mut buffer = Vec::new();
+            write(
+                &[stats],
+                &[],
+                &[],
+                None,
+                None,
+                ReportOptions::default(),
+                &mut buffer,
+            )
+            .unwrap();
+            let written = String::from_utf8(buffer).unwrap();
+            assert!(written.contains(&format!(
+                "\"is_binary\": false, \"generated\": {}, ",
+                expected
+            )));
+            assert_eq!(read(&written).unwrap().0[0].generated, Some(generated));
+        }
+    }
+
+    #[test]
+    fn test_write_metadata() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.metadata = Some(crate::FileMeta {
+            modified: Some(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_791_729_000)),
+            size: Some(12),
+            permissions: Some(Permissions::Mode(0o644)),
+        });
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"metadata\": {\"modified\": \"2026-10-11T14:30:00Z\", \"size\": 12, \"permissions\": \"0644\"}"
+        ));
+        let mut stats = FileStats::new("<stdin>".to_string());
+        stats.metadata = Some(crate::FileMeta::default());
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer)
+            .unwrap()
+            .contains("\"metadata\": {\"modified\": null, \"size\": null, \"permissions\": null}"));
+    }
+
+    #[test]
+    fn test_write_long_lines() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.long_lines = Some(crate::LongLines {
+            limit: 80,
+            count: 3,
+            first: vec![2, 9],
+        });
+        let mut buffer = Vec::new();
+        write(
+            &[stats],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\"max_line_length_limit\": 80, \"long_lines\": 3, \"long_lines_at\": [2, 9], \"is_mixed\": false, \"line_ending_type"
+        ));
+    }
+
+    #[test]
+    fn test_write_empty_array() {
+        let mut buffer = Vec::new();
+        write(
+            &[],
+            &[],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(buffer).unwrap(), "[\n]\n");
+    }
+
+    #[test]
+    fn test_read_what_write_wrote() {
+        let mut first = FileStats::new("dir/a \"b\" \u{e9}.rs".to_string());
+        first.total_lines = 3;
+        first.dos_endings = 1;
+        first.unix_endings = 2;
+        first.first_crlf_offset = Some(17);
+        first.comment_lines = Some(1);
+        first.code_lines = Some(2);
+        first.max_line_length = 12;
+        first.total_line_length = 20;
+        first.longest_line = Some(2);
+        first.trailing_whitespace_lines = 2;
+        first.trailing_whitespace_at = vec![1, 3];
+        first.indentation.spaces = 2;
+        first.indentation.width = Some(4);
+        first.final_newline = Some(true);
+        first.sha256 = Some("ab".repeat(32));
+        first.warnings = vec!["odd\tbyte".to_string()];
+        first.unicode_breaks = Some(crate::UnicodeBreaks {
+            ls: 1,
+            ..Default::default()
+        });
+        let skipped = SkippedFile {
+            file_name: "dump.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 1,
+        };
+        let stats = [first, FileStats::new("Makefile".to_string())];
+        let mut written = Vec::new();
+        write(
+            &stats,
+            &[skipped],
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut written,
+        )
+        .unwrap();
+        let written = String::from_utf8(written).unwrap();
+        let (stats, skipped) = read(&written).unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0].first_crlf_offset, Some(17));
+        assert_eq!(stats[0].total_line_length, 20);
+        let mut again = Vec::new();
+        write(
+            &stats,
+            &skipped,
+            &[],
+            None,
+            None,
+            ReportOptions::default(),
+            &mut again,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), written);
+    }
+
+    #[test]
+    fn test_read_reports_from_before_keys_were_added() {
+        let mut file = FileStats::new("a.txt".to_string());
+        file.total_lines = 2;
+        file.unix_endings = 2;
+        file.final_newline = Some(true);
+        let mut written = Vec::new();
+        write_array(
+            std::slice::from_ref(&file),
+            &[],
+            &[],
+            ReportOptions::default(),
+            "",
+            &mut written,
+        )
+        .unwrap();
+        let mut old = String::from_utf8(written).unwrap();
+        for added in [
+            ", \"grew_during_scan\": false",
+            ", \"truncated_during_scan\": false",
+            ", \"embedded_crs\": 0, \"embedded_cr_at\": []",
+            ", \"phantom_blank_lines\": 0, \"phantom_blank_at\": []",
+        ] {
+            assert!(old.contains(added), "{}", added);
+            old = old.replace(added, "");
+        }
+        let value = parse(&old).unwrap();
+        assert_eq!(version(&value), Ok(1));
+        let (stats, _) = super::read(&value).unwrap();
+        assert_eq!(stats[0].unix_endings, 2);
+        assert!(!stats[0].grew_during_scan && !stats[0].truncated_during_scan);
+        assert_eq!(stats[0].embedded_crs, 0);
+        assert!(stats[0].embedded_cr_at.is_empty());
+        assert_eq!(stats[0].phantom_blank_lines, 0);
+        assert!(stats[0].phantom_blank_at.is_empty());
+
+        let versioned = format!("{{\"version\": 2, \"files\": {}}}", old);
+        assert_eq!(version(&parse(&versioned).unwrap()), Ok(2));
+        assert_eq!(read(&versioned).unwrap().0.len(), 1);
+    }
+
+    #[test]
+    fn test_read_nested_details() {
+        use crate::scanner::{LineLimit, MixedIndent, ScanOptions};
+        let scan = ScanOptions {
+            check_encoding: true,
+            markdown: true,
+            duplicates: true,
+            transitions: true,
+            locate: Some(3),
+            histogram: Some(Default::default()),
+            line_limit: Some(LineLimit {
+                max_columns: 10,
+                listed: 3,
+            }),
+            mixed_indent: Some(MixedIndent::Any),
+            ..ScanOptions::default()
+        };
+        let text = b"\xef\xbb\xbf#!/bin/sh\r\n# Title\n\t  mixed indent  \ndup\ndup\n\
+                     [a](b) ![c](d)\n```\ncode \xff\n";
+        let mut file = crate::analyze_bytes_with(text, "notes.md".to_string(), scan);
+        file.metadata = Some(FileMeta {
+            modified: figures::parse_rfc3339("2026-10-14T09:30:00Z"),
+            size: Some(file.total_bytes),
+            permissions: Some(Permissions::ReadOnly(false)),
+        });
+        let options = ReportOptions {
+            preview: true,
+            ..ReportOptions::default()
+        };
+        let mut written = Vec::new();
+        write(&[file], &[], &[], None, None, options, &mut written).unwrap();
+        let written = String::from_utf8(written).unwrap();
+        let (stats, _) = read(&written).unwrap();
+        assert_eq!(stats[0].bom, Some(Bom::Utf8));
+        assert_eq!(stats[0].detected_language, Some("Shell"));
+        let mut again = Vec::new();
+        write(&stats, &[], &[], None, None, options, &mut again).unwrap();
+        assert_eq!(String::from_utf8(again).unwrap(), written);
+        assert_eq!(
+            read(&written.replace("\"ending\": \"LF\"", "\"ending\": \"NL\"")).unwrap_err(),
+            "entry 1 (notes.md): \"ending\" is not known: NL"
+        );
+    }
+
+    #[test]
+    fn test_write_summary() {
+        let file = FileStats::new("a".to_string());
+        let mut summary = AggregateStats::default();
+        summary.add(&file);
+        let render = |options| {
+            let mut buffer = Vec::new();
+            write(
+                std::slice::from_ref(&file),
+                &[],
+                &[],
+                Some(&summary),
+                None,
+                options,
+                &mut buffer,
+            )
+            .unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        let output = render(ReportOptions::default());
+        assert!(output.starts_with("{\n  \"version\": 2,\n  \"summary\": {\"files\": 1, "));
+        assert!(output.contains("},\n  \"files\": [\n    {\"file_name\": \"a\", "));
+        assert!(output.ends_with("\"line_ending\": \"none\"}\n  ]\n}\n"));
+        assert_eq!(read(&output).unwrap().0.len(), 1);
+        let output = render(ReportOptions {
+            summary_only: true,
+            ..ReportOptions::default()
+        });
+        assert_eq!(
+            output,
+            format!(
+                "{{\n  \"version\": 2,\n  \"summary\": {}\n}}\n",
+                summary.json()
+            )
+        );
+
+        let groups = Groups::new(GroupBy::Dir, None, std::slice::from_ref(&file), &[]);
+        let mut buffer = Vec::new();
+        let options = ReportOptions {
+            summary_only: true,
+            ..ReportOptions::default()
+        };
+        write(
+            &[],
+            &[],
+            &[],
+            Some(&summary),
+            Some(&groups),
+            options,
+            &mut buffer,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            format!(
+                "{{\n  \"version\": 2,\n  \"summary\": {0},\n  \"by_dir\": {{\n    \".\": {0}\n  }}\n}}\n",
+                summary.json()
+            )
+        );
+    }
+
+    #[test]
+    fn test_read_errors() {
+        assert_eq!(
+            parse("[\"\\u00e9\\ud83d\\ude00\", 1.5e3, null, {}]"),
+            Ok(Value::Array(vec![
+                Value::String("\u{e9}\u{1f600}".to_string()),
+                Value::Number("1.5e3".to_string()),
+                Value::Null,
+                Value::Object(Vec::new()),
+            ]))
+        );
+        assert_eq!(
+            read("[\n  {\"file_name\": \"a\",\n  \"oops\"]").unwrap_err(),
+            "line 3 column 9: expected ':'"
+        );
+        assert_eq!(read("true").unwrap_err(), "expected an array of files");
+        assert_eq!(
+            read("{\"summary\": {}}").unwrap_err(),
+            "no \"files\" array, as in a report written with --summary-only"
+        );
+        assert_eq!(read("[{}]").unwrap_err(), "entry 1 has no \"file_name\"");
+        assert_eq!(
+            read("[{\"file_name\": \"a.txt\", \"file_extension\": \"txt\", \"is_binary\": false}]")
+                .unwrap_err(),
+            "entry 1 (a.txt): no \"total_bytes\""
+        );
+        assert_eq!(
+            read("[{\"file_name\": \"a.txt\", \"is_binary\": 0}]").unwrap_err(),
+            "entry 1 (a.txt): \"is_binary\" is not true or false"
+        );
+    }
+}
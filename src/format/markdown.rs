@@ -0,0 +1,520 @@
+use std::io::{self, Write};
+
+use crate::bom::Bom;
+use crate::figures;
+use crate::{FileStats, SkippedFile};
+
+/// Writes a single file as a definition-style list and several files as a
+/// GitHub-flavored table with a totals row, followed by a list of skipped
+/// files.
+pub fn write(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    mut writer: impl Write,
+) -> io::Result<()> {
+    match stats {
+        [] if !skipped.is_empty() => {}
+        [single] => write_list(single, &mut writer)?,
+        _ => write_table(stats, &mut writer)?,
+    }
+    if !skipped.is_empty() {
+        if !stats.is_empty() {
+            writeln!(writer)?;
+        }
+        writeln!(writer, "**Skipped files:**")?;
+        writeln!(writer)?;
+        for file in skipped {
+            writeln!(
+                writer,
+                "- {}: {}",
+                escape(&file.label()),
+                escape(&file.reason)
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn write_list(stats: &FileStats, writer: &mut impl Write) -> io::Result<()> {
+    let count = |n: usize| figures::count(n as u64, true);
+    writeln!(writer, "### File Analysis Report")?;
+    writeln!(writer)?;
+    if stats.is_binary {
+        writeln!(writer, "> **{}**", crate::BINARY_WARNING)?;
+        writeln!(writer)?;
+    }
+    if let Some(generated) = &stats.generated {
+        writeln!(
+            writer,
+            "> **{}:** {}",
+            crate::GENERATED_NOTE,
+            escape(&generated.to_string())
+        )?;
+        writeln!(writer)?;
+    }
+    writeln!(writer, "- **File name:** {}", escape(&stats.display_name()))?;
+    if let Some(window) = stats.window {
+        writeln!(writer, "- **Window:** {}", window)?;
+    }
+    if let Some(head) = stats.head {
+        writeln!(writer, "- **Head:** {}", stats.head_summary(head, true))?;
+    }
+    writeln!(
+        writer,
+        "- **File extension:** {}",
+        stats
+            .file_extension
+            .as_ref()
+            .map_or("none".to_string(), |ext| escape(ext))
+    )?;
+    writeln!(
+        writer,
+        "- **Byte-order mark:** {}",
+        stats.bom.map_or("none", Bom::name)
+    )?;
+    if let Some(guess) = stats.detected_encoding {
+        writeln!(writer, "- **Detected encoding:** {}", guess)?;
+    }
+    if let Some(shebang) = &stats.shebang {
+        writeln!(writer, "- **Shebang:** {}", escape(shebang))?;
+        if let Some(language) = stats.detected_language {
+            writeln!(writer, "- **Detected language:** {}", language)?;
+        }
+    }
+    if let Some(kind) = &stats.detected_type {
+        writeln!(writer, "- **Detected type:** {}", escape(kind))?;
+    }
+    writeln!(writer, "- **Total lines:** {}", count(stats.total_lines))?;
+    writeln!(writer, "- **Empty lines:** {}", count(stats.empty_lines))?;
+    if let Some(at) = stats.empty_lines_at.as_ref().filter(|at| !at.is_empty()) {
+        writeln!(
+            writer,
+            "- **Empty lines at:** {}",
+            crate::empty_lines_summary(at, stats.empty_lines, true)
+        )?;
+    }
+    writeln!(
+        writer,
+        "- **Whitespace-only lines:** {}",
+        count(stats.blank_lines)
+    )?;
+    if let (Some(comment), Some(code)) = (stats.comment_lines, stats.code_lines) {
+        writeln!(writer, "- **Comment lines:** {}", count(comment))?;
+        writeln!(writer, "- **Code lines:** {}", count(code))?;
+    }
+    writeln!(writer, "- **Size:** {}", stats.size_summary(true))?;
+    if stats.grew_during_scan {
+        writeln!(
+            writer,
+            "- **Grew during scan:** yes, counted as it was when opened"
+        )?;
+    }
+    if stats.truncated_during_scan {
+        writeln!(
+            writer,
+            "- **Truncated during scan:** yes, counted up to where it shrank"
+        )?;
+    }
+    if let Some(sha256) = &stats.sha256 {
+        writeln!(writer, "- **SHA-256:** `{}`", sha256)?;
+    }
+    writeln!(
+        writer,
+        "- **Trailing blank lines:** {}",
+        count(stats.trailing_blank_lines)
+    )?;
+    if let Some(runs) = &stats.blank_runs {
+        writeln!(writer, "- **Blank-line runs:** {}", runs.summary(true))?;
+    }
+    writeln!(
+        writer,
+        "- **Line lengths (min/avg/max):** {}/{:.1}/{}",
+        count(stats.min_line_length),
+        stats.mean_line_length(),
+        count(stats.max_line_length)
+    )?;
+    if let Some(line) = stats.longest_line {
+        writeln!(
+            writer,
+            "- **Longest line:** {} on line {}",
+            figures::counted(stats.max_line_length as u64, "column", true),
+            line
+        )?;
+    }
+    if let Some(lines) = stats.top_lines.as_deref().filter(|lines| !lines.is_empty()) {
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|top| match top.columns {
+                Some(columns) => format!(
+                    "line {} ({} bytes, {} columns)",
+                    top.line, top.length, columns
+                ),
+                None => format!("line {} ({} bytes)", top.line, top.length),
+            })
+            .collect();
+        writeln!(writer, "- **Longest lines:** {}", lines.join(", "))?;
+    }
+    if let Some(peek) = &stats.peek {
+        let shown = |line: &Option<String>| line.as_deref().map_or("none".to_string(), escape);
+        writeln!(writer, "- **First line:** {}", shown(&peek.first))?;
+        writeln!(writer, "- **Last line:** {}", shown(&peek.last))?;
+    }
+    writeln!(
+        writer,
+        "- **Line ending type:** {}",
+        stats.line_ending_type()
+    )?;
+    writeln!(
+        writer,
+        "- **DOS line endings (CRLF):** {}",
+        count(stats.dos_endings)
+    )?;
+    writeln!(
+        writer,
+        "- **Unix line endings (LF):** {}",
+        count(stats.unix_endings)
+    )?;
+    writeln!(
+        writer,
+        "- **Mac line endings (CR):** {}",
+        count(stats.mac_endings)
+    )?;
+    writeln!(
+        writer,
+        "- **Ends with newline:** {}",
+        crate::final_newline_summary(stats.final_newline)
+    )?;
+    if let Some(breaks) = &stats.unicode_breaks {
+        writeln!(
+            writer,
+            "- **Next line (NEL, U+0085):** {}",
+            count(breaks.nel)
+        )?;
+        writeln!(
+            writer,
+            "- **Line separators (LS, U+2028):** {}",
+            count(breaks.ls)
+        )?;
+        writeln!(
+            writer,
+            "- **Paragraph separators (PS, U+2029):** {}",
+            count(breaks.ps)
+        )?;
+        writeln!(writer, "- **Vertical tabs (VT):** {}", count(breaks.vt))?;
+        writeln!(writer, "- **Form feeds (FF):** {}", count(breaks.ff))?;
+    }
+    if let Some(pages) = &stats.pages {
+        writeln!(
+            writer,
+            "- **Pages:** {}, {}/{:.1}/{} lines per page (min/avg/max)",
+            pages.summary(true),
+            count(pages.min_lines),
+            pages.mean_lines(),
+            count(pages.max_lines)
+        )?;
+    }
+    if let Some(report) = &stats.utf8 {
+        writeln!(writer, "- **UTF-8:** {}", crate::utf8_summary(report, true))?;
+    }
+    if let Some(line) = stats.first_nul_line {
+        writeln!(
+            writer,
+            "- **NUL bytes:** {} (first on line {})",
+            count(stats.nul_bytes),
+            line
+        )?;
+    }
+    if let Some(line) = stats.first_control_line {
+        writeln!(
+            writer,
+            "- **Control characters:** {} (first on line {})",
+            count(stats.control_chars),
+            line
+        )?;
+    }
+    if let Some(summary) = crate::embedded_cr_summary(stats, true) {
+        writeln!(writer, "- **Embedded carriage returns:** {}", summary)?;
+    }
+    if let Some(&line) = stats.phantom_blank_at.first() {
+        writeln!(
+            writer,
+            "- **Phantom blank lines (lone CR content):** {}, first at line {}",
+            count(stats.phantom_blank_lines),
+            line
+        )?;
+    }
+    if let Some(summary) = crate::non_ascii_summary(stats, true) {
+        writeln!(writer, "- **Non-ASCII characters:** {}", summary)?;
+    }
+    if stats.trailing_whitespace_lines > 0 {
+        writeln!(
+            writer,
+            "- **Trailing whitespace:** {}",
+            crate::trailing_whitespace_summary(stats, true)
+        )?;
+    }
+    writeln!(
+        writer,
+        "- **Indentation:** {}",
+        stats.indentation.summary(true)
+    )?;
+    if let Some(mixed) = &stats.mixed_indent {
+        writeln!(writer, "- {}", crate::mixed_indent_summary(mixed, true))?;
+    }
+    if let Some(long) = &stats.long_lines {
+        writeln!(
+            writer,
+            "- **Long lines:** {}",
+            crate::long_lines_summary(long, true)
+        )?;
+    }
+    for found in &stats.patterns {
+        writeln!(
+            writer,
+            "- **Pattern {}:** {}",
+            escape(&found.pattern),
+            found.summary(true)
+        )?;
+    }
+    for minority in stats.minority_endings.iter().flatten() {
+        writeln!(writer, "- **Minority ending:** {}", minority.summary(true))?;
+    }
+    if let Some(segments) = &stats.transitions {
+        let transitions = segments.len().saturating_sub(1);
+        writeln!(
+            writer,
+            "- **Line ending transitions:** {}",
+            count(transitions)
+        )?;
+    }
+    if let Some(duplicates) = &stats.duplicates {
+        writeln!(
+            writer,
+            "- **Duplicate lines:** {}",
+            duplicates.summary(true)
+        )?;
+        for line in &duplicates.most_repeated {
+            writeln!(
+                writer,
+                "  - {}x, first on line {}: {}",
+                count(line.count),
+                line.first_line,
+                escape(&line.preview)
+            )?;
+        }
+    }
+    if let Some(metadata) = &stats.metadata {
+        let modified = metadata.modified.map(|time| {
+            format!(
+                "{} ({})",
+                crate::figures::rfc3339(time),
+                crate::metadata::relative(time, std::time::SystemTime::now())
+            )
+        });
+        let permissions = metadata
+            .permissions
+            .map(|permissions| permissions.summary());
+        writeln!(
+            writer,
+            "- **Modified:** {}",
+            modified.as_deref().unwrap_or("unavailable")
+        )?;
+        writeln!(
+            writer,
+            "- **Permissions:** {}",
+            permissions.as_deref().unwrap_or("unavailable")
+        )?;
+    }
+    for warning in &stats.warnings {
+        writeln!(writer, "- **Warning:** {}", escape(warning))?;
+    }
+    let suppressed = stats
+        .violations
+        .iter()
+        .filter(|violation| violation.suppressed)
+        .count();
+    if stats.violations.len() > suppressed {
+        writeln!(writer, "- **Violations:**")?;
+        for violation in stats.violations.iter().filter(|found| !found.suppressed) {
+            writeln!(
+                writer,
+                "  - {}: {}",
+                violation.severity.name(),
+                escape(&violation.to_string())
+            )?;
+        }
+    }
+    if suppressed > 0 {
+        writeln!(writer, "- **Suppressed violations:** {}", count(suppressed))?;
+    }
+    Ok(())
+}
+
+fn write_table(stats: &[FileStats], writer: &mut impl Write) -> io::Result<()> {
+    let count = |n: usize| figures::count(n as u64, true);
+    writeln!(
+        writer,
+        "| File | Ending type | Total lines | Empty lines | CRLF | LF | CR | Words | Characters | Bytes |"
+    )?;
+    writeln!(
+        writer,
+        "| --- | --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: |"
+    )?;
+
+    let (mut total, mut empty, mut dos, mut unix, mut mac) = (0, 0, 0, 0, 0);
+    let (mut words, mut chars, mut bytes) = (0, 0, 0);
+    for file in stats {
+        writeln!(
+            writer,
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+            escape(&file.display_name()),
+            file.line_ending_type(),
+            count(file.total_lines),
+            count(file.empty_lines),
+            count(file.dos_endings),
+            count(file.unix_endings),
+            count(file.mac_endings),
+            figures::count(file.total_words, true),
+            figures::count(file.total_chars, true),
+            figures::count(file.total_bytes, true)
+        )?;
+        total += file.total_lines;
+        empty += file.empty_lines;
+        dos += file.dos_endings;
+        unix += file.unix_endings;
+        mac += file.mac_endings;
+        words += file.total_words;
+        chars += file.total_chars;
+        bytes += file.total_bytes;
+    }
+
+    writeln!(
+        writer,
+        "| **Total ({})** | | {} | {} | {} | {} | {} | {} | {} | {} |",
+        figures::counted(stats.len() as u64, "file", true),
+        count(total),
+        count(empty),
+        count(dos),
+        count(unix),
+        count(mac),
+        figures::count(words, true),
+        figures::count(chars, true),
+        figures::count(bytes, true)
+    )?;
+    Ok(())
+}
+
+/// Escapes characters that would break out of a table cell, open a code
+/// span, or be taken for HTML, such as a file named `<img src=x>`.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '|' | '`' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(name: &str, unix: usize, dos: usize, empty: usize) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.unix_endings = unix;
+        stats.dos_endings = dos;
+        stats.total_lines = unix + dos;
+        stats.empty_lines = empty;
+        stats
+    }
+
+    fn render(stats: &[FileStats]) -> String {
+        let mut buffer = Vec::new();
+        write(stats, &[], &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_single_file_renders_list() {
+        let output = render(&[stats("test.txt", 3, 0, 1)]);
+        assert!(output.starts_with("### File Analysis Report\n"));
+        assert!(output.contains("- **File name:** test.txt\n"));
+        assert!(output.contains("- **File extension:** txt\n"));
+        assert!(output.contains("- **Total lines:** 3\n"));
+        assert!(output.contains("- **Line ending type:** Unix/Linux (LF)\n"));
+        assert!(!output.contains("| --- |"));
+    }
+
+    #[test]
+    fn test_multiple_files_render_table_with_totals() {
+        let output = render(&[stats("a.txt", 2, 0, 1), stats("b.txt", 0, 3, 0)]);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(
+            lines[0],
+            "| File | Ending type | Total lines | Empty lines | CRLF | LF | CR | Words | Characters | Bytes |"
+        );
+        assert_eq!(
+            lines[1],
+            "| --- | --- | ---: | ---: | ---: | ---: | ---: | ---: | ---: | ---: |"
+        );
+        assert_eq!(
+            lines[2],
+            "| a.txt | Unix/Linux (LF) | 2 | 1 | 0 | 2 | 0 | 0 | 0 | 0 |"
+        );
+        assert_eq!(
+            lines[3],
+            "| b.txt | DOS/Windows (CRLF) | 3 | 0 | 3 | 0 | 0 | 0 | 0 | 0 |"
+        );
+        assert_eq!(
+            lines[4],
+            "| **Total (2 files)** | | 5 | 1 | 3 | 2 | 0 | 0 | 0 | 0 |"
+        );
+    }
+
+    #[test]
+    fn test_skipped_files_are_listed() {
+        let skipped = SkippedFile {
+            file_name: "dump.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 0,
+        };
+        let mut buffer = Vec::new();
+        write(&[stats("a.txt", 1, 0, 0)], &[skipped], &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("### File Analysis Report\n"));
+        assert!(output.ends_with(
+            "\n\n**Skipped files:**\n\n- dump.sql (2048 bytes): larger than --max-size (1024 bytes)\n"
+        ));
+    }
+
+    #[test]
+    fn test_escape_pipes_and_backticks() {
+        assert_eq!(escape("a|b`c"), "a\\|b\\`c");
+        assert_eq!(escape("dir\\file"), "dir\\\\file");
+        let output = render(&[stats("we|rd.txt", 1, 0, 0), stats("`x`", 1, 0, 0)]);
+        assert!(output.contains("| we\\|rd.txt |"));
+        assert!(output.contains("| \\`x\\` |"));
+    }
+
+    #[test]
+    fn test_escape_html() {
+        assert_eq!(escape("a&b"), "a&amp;b");
+        let output = render(&[stats("<img src=x>", 1, 0, 0)]);
+        assert!(output.contains("- **File name:** &lt;img src=x&gt;\n"));
+        assert!(!output.contains('<'));
+        let output = render(&[stats("<img src=x>", 1, 0, 0), stats("a.txt", 1, 0, 0)]);
+        assert!(output.contains("| &lt;img src=x&gt; |"));
+        assert!(!output.contains('<'));
+    }
+}
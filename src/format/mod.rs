@@ -0,0 +1,345 @@
+#[cfg(feature = "cli")]
+use std::io::{self, Write};
+#[cfg(feature = "cli")]
+use std::str::FromStr;
+
+#[cfg(feature = "cli")]
+use crate::aggregate::{AggregateStats, Groups};
+use crate::layout::Layout;
+use crate::osname;
+#[cfg(feature = "cli")]
+use crate::{FailedFile, FileStats, SkippedFile};
+
+#[cfg(feature = "cli")]
+mod checkstyle;
+#[cfg(feature = "cli")]
+mod csv;
+#[cfg(feature = "cli")]
+pub mod fields;
+#[cfg(feature = "cli")]
+mod github;
+#[cfg(feature = "cli")]
+mod html;
+#[cfg(feature = "cli")]
+pub mod json;
+#[cfg(feature = "cli")]
+mod markdown;
+#[cfg(feature = "cli")]
+mod sarif;
+#[cfg(feature = "cli")]
+pub mod table;
+#[cfg(feature = "cli")]
+pub mod template;
+
+/// Settings that change what goes into a report.
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+pub struct ReportOptions {
+    /// Include the longest line's preview in machine-readable output.
+    pub preview: bool,
+    /// Add the details `--verbose` asks for to the text report.
+    pub verbose: bool,
+    /// Leave out the per-file reports and print only the summary.
+    pub summary_only: bool,
+    /// The columns `--format table` fits in, or `None` for no limit.
+    pub width: Option<usize>,
+    /// Color the text report with ANSI escape sequences.
+    pub color: bool,
+    /// `--show-suppressed`: list the violations markers silence in the text
+    /// report, rather than only counting them.
+    pub show_suppressed: bool,
+    /// The columns `--columns` picks for `--format csv`, `tsv` and `table`,
+    /// or `None` for those each has by default.
+    #[cfg(feature = "cli")]
+    pub columns: Option<fields::Columns>,
+    /// `--no-header`: leave out the header row of `--format csv` and `tsv`.
+    pub no_header: bool,
+    /// `--no-group-digits`: write the counts of the text report as plain
+    /// digits, "10432" rather than "10,432".
+    pub no_group_digits: bool,
+    /// The parts of the text report and their order, which
+    /// `--stable-output` pins.
+    pub layout: Layout,
+}
+
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Markdown,
+    Html,
+    Json,
+    Csv,
+    Tsv,
+    Table,
+    Sarif,
+    Checkstyle,
+    Github,
+}
+
+#[cfg(feature = "cli")]
+impl OutputFormat {
+    /// The name `--format` takes.
+    pub fn name(self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Markdown => "markdown",
+            OutputFormat::Html => "html",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Table => "table",
+            OutputFormat::Sarif => "sarif",
+            OutputFormat::Checkstyle => "checkstyle",
+            OutputFormat::Github => "github",
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "text" => Ok(OutputFormat::Text),
+            "markdown" | "md" => Ok(OutputFormat::Markdown),
+            "html" => Ok(OutputFormat::Html),
+            "json" => Ok(OutputFormat::Json),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "table" => Ok(OutputFormat::Table),
+            "sarif" => Ok(OutputFormat::Sarif),
+            "checkstyle" => Ok(OutputFormat::Checkstyle),
+            "github" => Ok(OutputFormat::Github),
+            other => Err(format!(
+                "Unknown format '{}': expected text, markdown, html, json, csv, tsv, table, \
+                 sarif, checkstyle or github",
+                other
+            )),
+        }
+    }
+}
+
+/// `name` with the characters that would break the layout of a report
+/// written as escapes: `\n`, `\r` and `\t`, and `\xNN` for other control
+/// characters, and `�` for what is not UTF-8. `--list` prints names as
+/// they are but for the last, and `--print0` byte for byte.
+pub fn escape_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in osname::display(name).chars() {
+        match c {
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_ascii_control() => escaped.push_str(&format!("\\x{:02x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// A file of a report, analyzed or skipped.
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy)]
+pub enum Reported<'a> {
+    Analyzed(&'a FileStats),
+    Skipped(&'a SkippedFile),
+}
+
+/// The analyzed and skipped files in the order they were found, each
+/// skipped file after as many analyzed ones as its `after` says.
+#[cfg(feature = "cli")]
+pub fn in_order<'a>(stats: &'a [FileStats], skipped: &'a [SkippedFile]) -> Vec<Reported<'a>> {
+    let mut skipped = skipped.iter().peekable();
+    let mut files = Vec::with_capacity(stats.len() + skipped.len());
+    for (index, file) in stats.iter().enumerate() {
+        while let Some(first) = skipped.next_if(|first| first.after <= index) {
+            files.push(Reported::Skipped(first));
+        }
+        files.push(Reported::Analyzed(file));
+    }
+    files.extend(skipped.map(Reported::Skipped));
+    files
+}
+
+/// Renders the analyzed files in `format`, followed by the files that were
+/// skipped and, in JSON, those that failed, which the other formats leave
+/// to what was printed on stderr as they did. Given the totals, the text
+/// report ends with them, and the `--group-by` table, when there was more
+/// than one file, and the JSON one puts them in its `summary` and `by_*`
+/// objects.
+#[cfg(feature = "cli")]
+#[allow(clippy::too_many_arguments)]
+pub fn write_report(
+    format: OutputFormat,
+    options: ReportOptions,
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    failed: &[FailedFile],
+    summary: Option<&AggregateStats>,
+    groups: Option<&Groups>,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            if !options.summary_only {
+                for (index, file) in stats.iter().enumerate() {
+                    if index > 0 {
+                        writeln!(writer)?;
+                    }
+                    file.display(options, stats.len() > 1, &mut writer)?;
+                }
+            }
+            write_text_end(options, !stats.is_empty(), skipped, summary, groups, writer)
+        }
+        OutputFormat::Markdown => markdown::write(stats, skipped, writer),
+        OutputFormat::Html => html::write(stats, skipped, writer),
+        OutputFormat::Json => json::write(stats, skipped, failed, summary, groups, options, writer),
+        OutputFormat::Csv => csv::write(stats, skipped, csv::Separator::Comma, options, writer),
+        OutputFormat::Tsv => csv::write(stats, skipped, csv::Separator::Tab, options, writer),
+        OutputFormat::Table => table::write(stats, skipped, options, writer),
+        OutputFormat::Sarif => sarif::write(stats, writer),
+        OutputFormat::Checkstyle => checkstyle::write(stats, writer),
+        OutputFormat::Github => github::write(stats, writer),
+    }
+}
+
+/// What the text report has after the analyzed files, which are in it
+/// when `files` says so: the skipped files, then the totals and the
+/// `--group-by` table when there was more than one file.
+#[cfg(feature = "cli")]
+pub fn write_text_end(
+    options: ReportOptions,
+    files: bool,
+    skipped: &[SkippedFile],
+    summary: Option<&AggregateStats>,
+    groups: Option<&Groups>,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    if !options.summary_only {
+        if !skipped.is_empty() && files {
+            writeln!(writer)?;
+        }
+        for file in skipped {
+            file.display(&mut writer)?;
+        }
+    }
+    match summary {
+        Some(summary) if options.summary_only || summary.files > 1 => {
+            if !options.summary_only {
+                writeln!(writer)?;
+            }
+            summary.display(!options.no_group_digits, &mut writer)?;
+            match groups {
+                Some(groups) => {
+                    writeln!(writer)?;
+                    groups.display(!options.no_group_digits, writer)
+                }
+                None => Ok(()),
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_output_format_from_str() {
+        assert_eq!("text".parse::<OutputFormat>(), Ok(OutputFormat::Text));
+        assert_eq!(
+            "markdown".parse::<OutputFormat>(),
+            Ok(OutputFormat::Markdown)
+        );
+        assert_eq!("md".parse::<OutputFormat>(), Ok(OutputFormat::Markdown));
+        assert_eq!("html".parse::<OutputFormat>(), Ok(OutputFormat::Html));
+        assert_eq!("json".parse::<OutputFormat>(), Ok(OutputFormat::Json));
+        assert_eq!("csv".parse::<OutputFormat>(), Ok(OutputFormat::Csv));
+        assert_eq!("tsv".parse::<OutputFormat>(), Ok(OutputFormat::Tsv));
+        assert_eq!("table".parse::<OutputFormat>(), Ok(OutputFormat::Table));
+        assert_eq!("sarif".parse::<OutputFormat>(), Ok(OutputFormat::Sarif));
+        assert_eq!(
+            "checkstyle".parse::<OutputFormat>(),
+            Ok(OutputFormat::Checkstyle)
+        );
+        assert_eq!("github".parse::<OutputFormat>(), Ok(OutputFormat::Github));
+        assert!("yaml".parse::<OutputFormat>().is_err());
+    }
+
+    #[test]
+    fn test_escape_name() {
+        assert_eq!(escape_name("src/main.rs"), "src/main.rs");
+        assert_eq!(escape_name("two\nlines\t.txt"), "two\\nlines\\t.txt");
+        assert_eq!(escape_name("bell\u{7}"), "bell\\x07");
+        assert_eq!(escape_name("说明.md"), "说明.md");
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_text_report_lists_skipped_files() {
+        let skipped = SkippedFile {
+            file_name: "dump.sql".to_string(),
+            size: Some(2048),
+            reason: "larger than --max-size (1024 bytes)".to_string(),
+            after: 0,
+        };
+        let mut buffer = Vec::new();
+        write_report(
+            OutputFormat::Text,
+            ReportOptions::default(),
+            &[FileStats::new("a.txt".to_string())],
+            &[skipped],
+            &[],
+            None,
+            None,
+            &mut buffer,
+        )
+        .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("File Analysis Report\n"));
+        assert!(output
+            .ends_with("\n\nSkipped dump.sql (2048 bytes): larger than --max-size (1024 bytes)\n"));
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_every_format_names_a_mixed_file_alike() {
+        let mut file = FileStats::new("mixed.txt".to_string());
+        file.total_lines = 7;
+        file.dos_endings = 2;
+        file.unix_endings = 3;
+        file.mac_endings = 2;
+        assert!(file.is_mixed());
+        let files = [file];
+        let file = &files[0];
+        let render = |format: OutputFormat| {
+            let mut buffer = Vec::new();
+            let options = ReportOptions::default();
+            write_report(format, options, &files, &[], &[], None, None, &mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert!(file
+            .line_ending_summary()
+            .starts_with("Mostly Unix/Linux (LF) - "));
+        let json = render(OutputFormat::Json);
+        assert!(
+            json.contains(", \"is_mixed\": true, \"line_ending_type\": \"Mostly Unix/Linux (LF)\", \"line_ending\": \"mixed\"}"),
+            "{}",
+            json
+        );
+        let csv = render(OutputFormat::Csv);
+        assert!(csv.contains(",Mostly Unix/Linux (LF),"), "{}", csv);
+        let markdown = render(OutputFormat::Markdown);
+        assert!(markdown.contains("Mostly Unix/Linux (LF)"), "{}", markdown);
+        let html = render(OutputFormat::Html);
+        assert!(html.contains("Mostly Unix/Linux (LF)"), "{}", html);
+        let value = |name: &str| (fields::find(name).unwrap().value)(file);
+        assert_eq!(value("ending_type"), "Mostly Unix/Linux (LF)");
+        assert_eq!(value("line_ending_type"), "Mostly Unix/Linux (LF)");
+        assert_eq!(value("is_mixed"), "true");
+    }
+}
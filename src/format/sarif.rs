@@ -0,0 +1,182 @@
+use std::io::{self, Write};
+
+use super::json::string;
+use crate::checks::{Severity, CHECKS};
+use crate::osname;
+use crate::FileStats;
+
+/// The version of SARIF written, and the schema that describes it.
+const VERSION: &str = "2.1.0";
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// The SARIF level of a violation of `severity`.
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Info => "note",
+    }
+}
+
+/// `name` as the relative URI of an artifact: `/` between directories and
+/// every byte but the unreserved ones percent-encoded.
+fn uri(name: &str) -> String {
+    let mut uri = String::with_capacity(name.len());
+    for byte in osname::display(name).replace('\\', "/").bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/// Writes a SARIF log of one run, with a rule for each check that found
+/// something and a result for each violation. A violation a marker silences
+/// is a result with an in-source suppression, which viewers leave out.
+pub fn write(stats: &[FileStats], mut writer: impl Write) -> io::Result<()> {
+    let violations = stats
+        .iter()
+        .flat_map(|file| file.violations.iter().map(move |found| (file, found)));
+    let mut rules: Vec<&str> = Vec::new();
+    for (_, violation) in violations.clone() {
+        if !rules.contains(&violation.check.as_str()) {
+            rules.push(&violation.check);
+        }
+    }
+
+    writeln!(writer, "{{")?;
+    writeln!(writer, "  \"$schema\": {},", string(SCHEMA))?;
+    writeln!(writer, "  \"version\": {},", string(VERSION))?;
+    writeln!(writer, "  \"runs\": [")?;
+    writeln!(writer, "    {{")?;
+    write!(
+        writer,
+        "      \"tool\": {{\"driver\": {{\"name\": \"mdlt\", \"version\": {}, \"rules\": [",
+        string(env!("CARGO_PKG_VERSION"))
+    )?;
+    for (index, rule) in rules.iter().enumerate() {
+        let separator = if index > 0 { "," } else { "" };
+        let description = CHECKS
+            .iter()
+            .find(|check| check.name == *rule)
+            .map(|check| {
+                format!(
+                    ", \"shortDescription\": {{\"text\": {}}}",
+                    string(check.description)
+                )
+            })
+            .unwrap_or_default();
+        write!(
+            writer,
+            "{}\n        {{\"id\": {}{}}}",
+            separator,
+            string(rule),
+            description
+        )?;
+    }
+    let close = if rules.is_empty() { "" } else { "\n      " };
+    writeln!(writer, "{}]}}}},", close)?;
+    write!(writer, "      \"results\": [")?;
+    for (index, (file, violation)) in violations.enumerate() {
+        let separator = if index > 0 { "," } else { "" };
+        let region = violation
+            .line
+            .map(|line| format!(", \"region\": {{\"startLine\": {}}}", line))
+            .unwrap_or_default();
+        let suppressions = match violation.suppressed {
+            true => ", \"suppressions\": [{\"kind\": \"inSource\"}]",
+            false => "",
+        };
+        write!(
+            writer,
+            "{}\n        {{\"ruleId\": {}, \"level\": \"{}\", \"message\": {{\"text\": {}}}, \
+             \"locations\": [{{\"physicalLocation\": {{\"artifactLocation\": {{\"uri\": {}}}{}}}}}]{}}}",
+            separator,
+            string(&violation.check),
+            level(violation.severity),
+            string(&violation.message),
+            string(&uri(&file.name())),
+            region,
+            suppressions
+        )?;
+    }
+    let close = if stats.iter().all(|file| file.violations.is_empty()) {
+        ""
+    } else {
+        "\n      "
+    };
+    writeln!(writer, "{}]", close)?;
+    writeln!(writer, "    }}")?;
+    writeln!(writer, "  ]")?;
+    writeln!(writer, "}}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::Violation;
+
+    fn violation(check: &str, severity: Severity, line: Option<usize>) -> Violation {
+        Violation {
+            check: check.to_string(),
+            severity,
+            line,
+            message: format!("{} found", check),
+            suppressed: false,
+        }
+    }
+
+    fn render(stats: &[FileStats]) -> String {
+        let mut buffer = Vec::new();
+        write(stats, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_uri() {
+        assert_eq!(uri("src/main.rs"), "src/main.rs");
+        assert_eq!(uri("my notes#1.txt"), "my%20notes%231.txt");
+        assert_eq!(uri("dir\\été.md"), "dir/%C3%A9t%C3%A9.md");
+    }
+
+    #[test]
+    fn test_levels_and_locations() {
+        let mut file = FileStats::new("src/a b.rs".to_string());
+        file.violations = vec![
+            violation("trailing-whitespace", Severity::Warning, Some(4)),
+            violation("mixed-endings", Severity::Error, None),
+            violation("final-newline", Severity::Info, None),
+        ];
+        let mut silenced = violation("trailing-whitespace", Severity::Error, Some(9));
+        silenced.suppressed = true;
+        file.violations.push(silenced);
+        let output = render(&[file, FileStats::new("clean.txt".to_string())]);
+        assert!(output.starts_with(
+            "{\n  \"$schema\": \"https://json.schemastore.org/sarif-2.1.0.json\",\n  \
+             \"version\": \"2.1.0\",\n"
+        ));
+        assert!(output.contains(
+            "{\"ruleId\": \"trailing-whitespace\", \"level\": \"warning\", \"message\": \
+             {\"text\": \"trailing-whitespace found\"}, \"locations\": [{\"physicalLocation\": \
+             {\"artifactLocation\": {\"uri\": \"src/a%20b.rs\"}, \"region\": {\"startLine\": 4}}}]}"
+        ));
+        assert!(output.contains("\"ruleId\": \"mixed-endings\", \"level\": \"error\""));
+        assert!(output.contains("\"ruleId\": \"final-newline\", \"level\": \"note\""));
+        assert!(output.contains(
+            "{\"uri\": \"src/a%20b.rs\"}, \"region\": {\"startLine\": 9}}}], \
+             \"suppressions\": [{\"kind\": \"inSource\"}]}"
+        ));
+        assert_eq!(output.matches("{\"id\": ").count(), 3);
+        assert!(output.contains("{\"id\": \"final-newline\", \"shortDescription\": {\"text\": "));
+        assert!(!output.contains("clean.txt"));
+    }
+
+    #[test]
+    fn test_no_violations() {
+        let output = render(&[FileStats::new("a.txt".to_string())]);
+        assert!(output.contains("\"rules\": []}},\n      \"results\": []\n    }\n  ]\n}\n"));
+    }
+}
@@ -0,0 +1,291 @@
+use std::io::{self, Write};
+
+use super::fields::{self, problems, Columns, Field, Kind};
+use super::{escape_name, ReportOptions};
+use crate::{figures, FileStats, SkippedFile};
+
+/// The fewest columns a path is cut to to fit the width.
+const MIN_PATH_WIDTH: usize = 12;
+
+/// The columns of the table unless `--columns` picks others, each under its
+/// heading.
+const DEFAULT_COLUMNS: [(&str, &str); 7] = [
+    ("Path", "file_name"),
+    ("Type", "ending"),
+    ("Lines", "total_lines"),
+    ("Empty", "empty_lines"),
+    ("CRLF", "dos_endings"),
+    ("LF", "unix_endings"),
+    ("Problems", "problems"),
+];
+
+/// The columns `c` takes up in a terminal: two for wide and fullwidth
+/// characters, as in CJK text, none for combining marks and other
+/// zero-width characters, and one otherwise.
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x200B..=0x200F | 0x20D0..=0x20FF | 0xFE00..=0xFE0F | 0xFEFF => 0,
+        0x1100..=0x115F
+        | 0x2E80..=0x303E
+        | 0x3041..=0x33FF
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xA000..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ => 1,
+    }
+}
+
+/// The columns `text` takes up on a terminal.
+pub fn width(text: &str) -> usize {
+    text.chars().map(char_width).sum()
+}
+
+/// Cuts `path` to `limit` columns by dropping its start, so that the file
+/// name stays visible, marking the cut with an ellipsis.
+fn truncate_left(path: &str, limit: usize) -> String {
+    if width(path) <= limit {
+        return path.to_string();
+    }
+    let mut kept = Vec::new();
+    let mut used = 1;
+    for c in path.chars().rev() {
+        if used + char_width(c) > limit {
+            break;
+        }
+        used += char_width(c);
+        kept.push(c);
+    }
+    std::iter::once('…').chain(kept.into_iter().rev()).collect()
+}
+
+/// The cell of `field` for `file`, with the text escaped so as not to
+/// break the layout, and a `-` for a file without problems.
+fn cell(field: &Field, file: &FileStats) -> String {
+    let value = (field.value)(file);
+    match field.kind {
+        Kind::Text if field.name == "problems" && value.is_empty() => "-".to_string(),
+        Kind::Text => escape_name(&value),
+        Kind::Count | Kind::Number => value,
+    }
+}
+
+/// Writes one row per file in aligned columns, with a totals row for
+/// several files, followed by the skipped files. Long paths are cut from
+/// the left to keep the table within `options.width`.
+pub fn write(
+    stats: &[FileStats],
+    skipped: &[SkippedFile],
+    options: ReportOptions,
+    mut writer: impl Write,
+) -> io::Result<()> {
+    let columns: Vec<(&str, &Field)> = match options.columns {
+        Some(Columns(picked)) => picked.iter().map(|&field| (field.name, field)).collect(),
+        None => DEFAULT_COLUMNS
+            .iter()
+            .map(|&(heading, name)| (heading, fields::find(name).expect("the columns are fields")))
+            .collect(),
+    };
+    let path = columns
+        .iter()
+        .position(|(_, field)| matches!(field.name, "name" | "file" | "file_name"));
+    let mut rows: Vec<Vec<String>> = stats
+        .iter()
+        .map(|file| columns.iter().map(|(_, field)| cell(field, file)).collect())
+        .collect();
+    if stats.len() > 1 {
+        let with_problems = stats
+            .iter()
+            .filter(|file| !problems(file).is_empty())
+            .count();
+        let mut totals: Vec<String> = columns
+            .iter()
+            .enumerate()
+            .map(|(index, (_, field))| match field.kind {
+                Kind::Count => {
+                    let counts: Vec<u64> = rows
+                        .iter()
+                        .filter_map(|row| row[index].parse().ok())
+                        .collect();
+                    match counts.is_empty() {
+                        true => String::new(),
+                        false => counts.iter().sum::<u64>().to_string(),
+                    }
+                }
+                _ if field.name == "problems" => format!("{} with problems", with_problems),
+                _ => String::new(),
+            })
+            .collect();
+        // The label goes under the path, or else the first other text.
+        let label = path.or_else(|| {
+            columns
+                .iter()
+                .position(|(_, field)| field.kind == Kind::Text && field.name != "problems")
+        });
+        if let Some(index) = label {
+            totals[index] = format!(
+                "Total ({})",
+                figures::counted(stats.len() as u64, "file", false)
+            );
+        }
+        rows.push(totals);
+    }
+
+    let mut widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(index, (heading, _))| {
+            rows.iter()
+                .map(|row| width(&row[index]))
+                .chain([width(heading)])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    if let (Some(limit), Some(path)) = (options.width, path) {
+        // The path gives up what the other columns and the gaps need.
+        let others: usize = widths.iter().sum::<usize>() - widths[path] + 2 * (widths.len() - 1);
+        let floor = match stats.len() {
+            0 | 1 => MIN_PATH_WIDTH,
+            _ => width(&rows[stats.len()][path]).max(MIN_PATH_WIDTH),
+        };
+        let room = limit.saturating_sub(others).max(floor);
+        widths[path] = widths[path].min(room);
+    }
+
+    let line = |cells: &[String]| {
+        let mut line = String::new();
+        for (index, ((cell, width), (_, field))) in
+            cells.iter().zip(&widths).zip(&columns).enumerate()
+        {
+            if index > 0 {
+                line.push_str("  ");
+            }
+            let cell = match Some(index) == path {
+                true => truncate_left(cell, *width),
+                false => cell.clone(),
+            };
+            let padding = " ".repeat(width.saturating_sub(self::width(&cell)));
+            match field.kind {
+                Kind::Count | Kind::Number => line.push_str(&format!("{}{}", padding, cell)),
+                Kind::Text => line.push_str(&format!("{}{}", cell, padding)),
+            }
+        }
+        line.trim_end().to_string()
+    };
+    let rule: Vec<String> = widths.iter().map(|&width| "-".repeat(width)).collect();
+
+    if !stats.is_empty() {
+        let headings: Vec<String> = columns
+            .iter()
+            .map(|(heading, _)| heading.to_string())
+            .collect();
+        writeln!(writer, "{}", line(&headings))?;
+        writeln!(writer, "{}", line(&rule))?;
+        for (index, row) in rows.iter().enumerate() {
+            if index == stats.len() {
+                writeln!(writer, "{}", line(&rule))?;
+            }
+            writeln!(writer, "{}", line(row))?;
+        }
+    }
+    if !skipped.is_empty() && !stats.is_empty() {
+        writeln!(writer)?;
+    }
+    for file in skipped {
+        file.display(&mut writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, [dos, unix]: [usize; 2]) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.dos_endings = dos;
+        stats.unix_endings = unix;
+        stats.total_lines = dos + unix;
+        stats.final_newline = Some(dos + unix > 0);
+        stats
+    }
+
+    #[test]
+    fn test_width_counts_wide_characters() {
+        assert_eq!(width("main.rs"), 7);
+        assert_eq!(width("文档/说明.md"), 12);
+        assert_eq!(width("e\u{301}"), 1);
+        assert_eq!(truncate_left("src/format/json.rs", 10), "…t/json.rs");
+        assert_eq!(truncate_left("文档/说明.md", 8), "…说明.md");
+        assert_eq!(truncate_left("a.rs", 10), "a.rs");
+    }
+
+    #[test]
+    fn test_write_aligns_columns() {
+        let mut mixed = file("文档/说明.md", [1, 12]);
+        mixed.trailing_whitespace_lines = 2;
+        let stats = [file("src/main.rs", [0, 120]), mixed];
+        let mut buffer = Vec::new();
+        write(&stats, &[], ReportOptions::default(), &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Path             Type   Lines  Empty  CRLF   LF  Problems\n\
+             ---------------  -----  -----  -----  ----  ---  ----------------------------------\n\
+             src/main.rs      LF       120      0     0  120  -\n\
+             文档/说明.md     mixed     13      0     1   12  mixed endings, trailing whitespace\n\
+             ---------------  -----  -----  -----  ----  ---  ----------------------------------\n\
+             Total (2 files)           133      0     1  132  1 with problems\n"
+        );
+    }
+
+    #[test]
+    fn test_write_picked_columns() {
+        let mut commented = file("lib.rs", [2, 0]);
+        commented.comment_lines = Some(1);
+        let stats = [file("a.txt", [0, 3]), commented];
+        let options = ReportOptions {
+            columns: Some("crlf,file,comment_lines,problems".parse().unwrap()),
+            ..ReportOptions::default()
+        };
+        let mut buffer = Vec::new();
+        write(&stats, &[], options, &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "crlf  file             comment_lines  problems\n\
+             ----  ---------------  -------------  ---------------\n   \
+                0  a.txt                           -\n   \
+                2  lib.rs                       1  -\n\
+             ----  ---------------  -------------  ---------------\n   \
+                2  Total (2 files)              1  0 with problems\n"
+        );
+    }
+
+    #[test]
+    fn test_write_cuts_paths_to_the_width() {
+        let stats = [file("a/very/long/path/to/the/file.txt", [0, 1])];
+        let skipped = [SkippedFile {
+            file_name: "logo.png".to_string(),
+            size: Some(18),
+            reason: "binary file".to_string(),
+            after: 0,
+        }];
+        let options = ReportOptions {
+            width: Some(55),
+            ..ReportOptions::default()
+        };
+        let mut buffer = Vec::new();
+        write(&stats, &skipped, options, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.starts_with("Path             Type"));
+        assert!(output.contains("\n…o/the/file.txt  LF  "));
+        assert!(output.ends_with("\n\nSkipped logo.png (18 bytes): binary file\n"));
+    }
+}
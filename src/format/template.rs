@@ -0,0 +1,227 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use super::fields;
+use crate::FileStats;
+
+/// The placeholder `--get` names, or an error listing them all.
+pub fn field(name: &str) -> Result<&'static str, String> {
+    fields::find(name)
+        .map(|field| field.name)
+        .ok_or_else(|| fields::unknown(format!("Unknown --get field '{}'", name), "valid fields"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Placeholder(&'static str),
+}
+
+/// A parsed `--template` string. Parsing validates every placeholder up front
+/// so a typo is reported before any file is analyzed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(format!(
+                                    "Unterminated placeholder '{{{}' in template",
+                                    name
+                                ))
+                            }
+                        }
+                    }
+                    let known = fields::find(&name).ok_or_else(|| {
+                        fields::unknown(
+                            format!("Unknown template placeholder '{{{}}}'", name),
+                            "valid placeholders",
+                        )
+                    })?;
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Placeholder(known.name));
+                }
+                '}' => {
+                    return Err(
+                        "Unmatched '}' in template; use '}}' for a literal brace".to_string()
+                    )
+                }
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        Ok(Template { segments })
+    }
+}
+
+impl Template {
+    pub fn render(&self, stats: &FileStats) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(text) => rendered.push_str(text),
+                Segment::Placeholder(name) => rendered.push_str(&placeholder_value(stats, name)),
+            }
+        }
+        rendered
+    }
+}
+
+/// The value of placeholder `name` for a file, empty where the file has none,
+/// such as the extension of `Makefile`.
+pub fn placeholder_value(stats: &FileStats, name: &str) -> String {
+    let field = fields::find(name).expect("placeholder names are validated when parsing");
+    (field.value)(stats)
+}
+
+/// Writes one rendered line per file.
+pub fn write(template: &Template, stats: &[FileStats], mut writer: impl Write) -> io::Result<()> {
+    for file in stats {
+        writeln!(writer, "{}", template.render(file))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> FileStats {
+        let mut stats = FileStats::new("src/lib.rs".to_string());
+        stats.total_lines = 12;
+        stats.empty_lines = 3;
+        stats.blank_lines = 1;
+        stats.unix_endings = 10;
+        stats.dos_endings = 2;
+        stats.total_bytes = 300;
+        stats.total_chars = 290;
+        stats.total_words = 40;
+        stats
+    }
+
+    fn render(template: &str, stats: &FileStats) -> String {
+        template.parse::<Template>().unwrap().render(stats)
+    }
+
+    #[test]
+    fn test_every_placeholder() {
+        let stats = sample();
+        assert_eq!(render("{name}", &stats), "src/lib.rs");
+        assert_eq!(render("{extension}", &stats), "rs");
+        assert_eq!(render("{bom}", &stats), "");
+        assert_eq!(render("{is_binary}", &stats), "false");
+        assert_eq!(render("{total_lines}", &stats), "12");
+        assert_eq!(render("{empty_lines}", &stats), "3");
+        assert_eq!(render("{blank_lines}", &stats), "1");
+        assert_eq!(render("{crlf}", &stats), "2");
+        assert_eq!(render("{lf}", &stats), "10");
+        assert_eq!(render("{cr}", &stats), "0");
+        assert_eq!(render("{ending_type}", &stats), "Mostly Unix/Linux (LF)");
+        assert_eq!(render("{is_mixed}", &stats), "true");
+        assert_eq!(render("{bytes}/{chars}/{words}", &stats), "300/290/40");
+        let all: String = fields::FIELDS
+            .iter()
+            .map(|field| format!("{{{}}}", field.name))
+            .collect();
+        assert!(all.parse::<Template>().is_ok());
+    }
+
+    #[test]
+    fn test_fields_by_json_name() {
+        let stats = sample();
+        assert_eq!(render("{dos_endings}/{unix_endings}", &stats), "2/10");
+        assert_eq!(
+            render("[{first_lf_offset}] [{final_newline}]", &stats),
+            "[] []"
+        );
+        assert_eq!(field("total_bytes"), Ok("total_bytes"));
+        let error = field("lenght").unwrap_err();
+        assert!(error.starts_with("Unknown --get field 'lenght'; valid fields are: name, "));
+        assert!(error.ends_with(", line_ending_type, status, skip_reason"));
+    }
+
+    #[test]
+    fn test_missing_extension_and_not_mixed() {
+        let mut stats = FileStats::new("Makefile".to_string());
+        stats.unix_endings = 4;
+        assert_eq!(render("[{extension}] {is_mixed}", &stats), "[] false");
+    }
+
+    #[test]
+    fn test_full_template() {
+        assert_eq!(
+            render("{name}: {ending_type} ({lf} LF / {crlf} CRLF)", &sample()),
+            "src/lib.rs: Mostly Unix/Linux (LF) (10 LF / 2 CRLF)"
+        );
+    }
+
+    #[test]
+    fn test_brace_escaping() {
+        assert_eq!(
+            render("{{name}} = {name}", &sample()),
+            "{name} = src/lib.rs"
+        );
+        assert_eq!(render("}}{{", &sample()), "}{");
+        assert_eq!(render("no placeholders", &sample()), "no placeholders");
+    }
+
+    #[test]
+    fn test_unknown_placeholder_lists_valid_names() {
+        let err = "{nmae}".parse::<Template>().unwrap_err();
+        assert!(err.contains("Unknown template placeholder '{nmae}'"));
+        assert!(err.contains("name, extension, total_lines"));
+    }
+
+    #[test]
+    fn test_malformed_templates() {
+        assert!("{name"
+            .parse::<Template>()
+            .unwrap_err()
+            .contains("Unterminated"));
+        assert!("name}"
+            .parse::<Template>()
+            .unwrap_err()
+            .contains("Unmatched"));
+    }
+
+    #[test]
+    fn test_write_one_line_per_file() {
+        let template: Template = "{name}={lf}".parse().unwrap();
+        let mut other = sample();
+        other.set_name("b.txt");
+        let mut buffer = Vec::new();
+        write(&template, &[sample(), other], &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "src/lib.rs=10\nb.txt=10\n"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use std::fmt;
+
+/// What generators and minifiers leave near the top or the bottom of what
+/// they write: the `@generated` of Facebook's tools, the "Code generated
+/// ... DO NOT EDIT." of Go's, the comment that points to a source map, and
+/// the `<auto-generated>` of .NET.
+const MARKERS: [&str; 4] = [
+    "@generated",
+    "DO NOT EDIT",
+    "# sourceMappingURL=",
+    "<auto-generated",
+];
+
+/// How many lines at each end of a file are looked at for a marker.
+pub const MARKER_LINES: usize = 5;
+
+/// The `--generated-*` thresholds a file looks minified beyond.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Thresholds {
+    /// The mean line length in columns, and how many bytes long a line
+    /// must be to count as the one that holds the file.
+    pub line_length: usize,
+    /// The percentage of the bytes a single line may hold.
+    pub line_share: u8,
+}
+
+impl Default for Thresholds {
+    fn default() -> Self {
+        Thresholds {
+            line_length: 500,
+            line_share: 90,
+        }
+    }
+}
+
+/// Why a file looks generated or minified: the first heuristic that
+/// fired, markers first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "heuristic", rename_all = "snake_case")
+)]
+pub enum Generated {
+    /// `marker` is on line `line`.
+    Marker { marker: String, line: usize },
+    /// One line holds `percent` of the bytes.
+    LineShare { percent: u8 },
+    /// The lines are `average` columns long on average.
+    LineLength { average: u64 },
+}
+
+impl Generated {
+    /// The name JSON gives the heuristic.
+    #[cfg(feature = "cli")]
+    pub fn heuristic(&self) -> &'static str {
+        match self {
+            Generated::Marker { .. } => "marker",
+            Generated::LineShare { .. } => "line_share",
+            Generated::LineLength { .. } => "line_length",
+        }
+    }
+}
+
+/// The heuristic as the verbose report gives it, e.g. "'@generated' on
+/// line 1".
+impl fmt::Display for Generated {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Generated::Marker { marker, line } => write!(f, "'{}' on line {}", marker, line),
+            Generated::LineShare { percent } => {
+                write!(f, "one line holds {}% of the bytes", percent)
+            }
+            Generated::LineLength { average } => write!(f, "lines average {} columns", average),
+        }
+    }
+}
+
+/// The marker in the start of a line, if any.
+fn marker(head: &[u8]) -> Option<&'static str> {
+    MARKERS
+        .into_iter()
+        .find(|marker| head.windows(marker.len()).any(|w| w == marker.as_bytes()))
+}
+
+/// Looks for a marker in the first and last `MARKER_LINES` lines, as far as
+/// their previews go. The last lines are kept by swapping buffers with the
+/// scanner rather than copying them, so that nothing is done per line but
+/// for the first few.
+#[derive(Debug, Default)]
+pub struct Markers {
+    found: Option<(&'static str, usize)>,
+    /// The starts of the last lines and their numbers, oldest at `next`.
+    tail: [(Vec<u8>, usize); MARKER_LINES],
+    next: usize,
+}
+
+impl Markers {
+    /// Takes the start of line `number` from `head`, which is left holding
+    /// something to be cleared.
+    pub fn end_line(&mut self, number: usize, head: &mut Vec<u8>) {
+        if self.found.is_none() && number <= MARKER_LINES {
+            self.found = marker(head).map(|marker| (marker, number));
+        }
+        let slot = &mut self.tail[self.next];
+        std::mem::swap(&mut slot.0, head);
+        slot.1 = number;
+        self.next = (self.next + 1) % MARKER_LINES;
+    }
+
+    /// Takes the markers of the text right after this one's, which starts
+    /// on line `lines + 1`, as if this had gone on through it.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Markers, lines: usize) {
+        let found = self.found.or_else(|| {
+            next.found
+                .map(|(marker, number)| (marker, number + lines))
+                .filter(|&(_, number)| number <= MARKER_LINES)
+        });
+        let later = next
+            .into_tail()
+            .into_iter()
+            .map(|(head, number)| (head, number + lines));
+        let mut tail: Vec<(Vec<u8>, usize)> = std::mem::take(self)
+            .into_tail()
+            .into_iter()
+            .chain(later)
+            .collect();
+        let kept = tail.split_off(tail.len().saturating_sub(MARKER_LINES));
+        self.found = found;
+        self.next = kept.len() % MARKER_LINES;
+        for (slot, line) in self.tail.iter_mut().zip(kept) {
+            *slot = line;
+        }
+    }
+
+    /// The last lines, oldest first.
+    fn into_tail(mut self) -> Vec<(Vec<u8>, usize)> {
+        self.tail.rotate_left(self.next);
+        self.tail
+            .into_iter()
+            .filter(|(_, number)| *number > 0)
+            .collect()
+    }
+
+    /// The first marker found, from the start of the file or else from its
+    /// end.
+    pub fn finish(self) -> Option<(&'static str, usize)> {
+        if self.found.is_some() {
+            return self.found;
+        }
+        let (newer, older) = self.tail.split_at(self.next);
+        older
+            .iter()
+            .chain(newer)
+            .filter(|(_, number)| *number > 0)
+            .find_map(|(head, number)| marker(head).map(|marker| (marker, *number)))
+    }
+}
+
+/// Whether a file of `total_bytes` bytes looks generated, given the marker
+/// found, its longest line in bytes and the total and count of its line
+/// lengths in columns.
+pub fn classify(
+    found: Option<(&'static str, usize)>,
+    total_bytes: u64,
+    max_line_bytes: usize,
+    total_line_length: u64,
+    lines: usize,
+    thresholds: Thresholds,
+) -> Option<Generated> {
+    if let Some((marker, line)) = found {
+        return Some(Generated::Marker {
+            marker: marker.to_string(),
+            line,
+        });
+    }
+    if max_line_bytes > thresholds.line_length && total_bytes > 0 {
+        let percent = (max_line_bytes as u64 * 100 / total_bytes) as u8;
+        if percent > thresholds.line_share {
+            return Some(Generated::LineShare { percent });
+        }
+    }
+    let average = match lines {
+        0 => 0,
+        _ => total_line_length / lines as u64,
+    };
+    (average > thresholds.line_length as u64).then_some(Generated::LineLength { average })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The marker found in `lines`.
+    fn markers(lines: &[&str]) -> Option<(&'static str, usize)> {
+        let mut markers = Markers::default();
+        let mut head = Vec::new();
+        for (index, line) in lines.iter().enumerate() {
+            head.extend_from_slice(line.as_bytes());
+            markers.end_line(index + 1, &mut head);
+            head.clear();
+        }
+        markers.finish()
+    }
+
+    #[test]
+    fn test_markers() {
+        assert_eq!(
+            markers(&[
+                "// Code generated by protoc-gen-go. DO NOT EDIT.",
+                "package a"
+            ]),
+            Some(("DO NOT EDIT", 1))
+        );
+        let mut bundle = vec!["var a=1;"; 20];
+        bundle.push("//# sourceMappingURL=app.js.map");
+        assert_eq!(markers(&bundle), Some(("# sourceMappingURL=", 21)));
+        // Lines in the middle of a file are not looked at.
+        let mut lines = vec!["x"; 20];
+        lines[10] = "@generated";
+        assert_eq!(markers(&lines), None);
+        assert_eq!(
+            markers(&["a", "b", " * @generated"]),
+            Some(("@generated", 3))
+        );
+        assert_eq!(markers(&[]), None);
+    }
+
+    #[test]
+    fn test_classify() {
+        let thresholds = Thresholds::default();
+        assert_eq!(
+            classify(Some(("@generated", 2)), 10, 5, 5, 2, thresholds),
+            Some(Generated::Marker {
+                marker: "@generated".to_string(),
+                line: 2
+            })
+        );
+        // A bundle of one long line and a short one.
+        assert_eq!(
+            classify(None, 10_000, 9_600, 9_650, 2, thresholds),
+            Some(Generated::LineShare { percent: 96 })
+        );
+        assert_eq!(
+            classify(None, 60_000, 900, 59_000, 100, thresholds),
+            Some(Generated::LineLength { average: 590 })
+        );
+        // A short file of one line holds all its bytes in it.
+        assert_eq!(classify(None, 40, 39, 39, 1, thresholds), None);
+        assert_eq!(classify(None, 0, 0, 0, 0, thresholds), None);
+        assert_eq!(
+            Generated::LineShare { percent: 96 }.to_string(),
+            "one line holds 96% of the bytes"
+        );
+    }
+}
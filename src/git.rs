@@ -0,0 +1,223 @@
+use std::io::{self, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::cli::Changed;
+
+/// Git's mode for a submodule, which has no blob of its own.
+const GITLINK: &str = "160000";
+
+/// Git's mode for a symbolic link, whose blob is the target's path.
+const SYMLINK: &str = "120000";
+
+/// The tree of a repository with nothing in it, the base of a branch with
+/// no commits yet.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Runs git with `args` and returns its output, or what it said on stderr.
+fn git(args: &[&str]) -> Result<Vec<u8>, String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(|e| format!("cannot run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(output.stdout)
+}
+
+/// Like `git`, for a command whose output is a single line.
+fn git_line(args: &[&str]) -> Result<String, String> {
+    git(args).map(|output| String::from_utf8_lossy(&output).trim().to_string())
+}
+
+/// The paths in `git diff --raw -z` output that have contents to check:
+/// additions, copies, modifications and the new side of renames, but not
+/// submodules or symbolic links.
+fn parse_raw(output: &[u8]) -> Vec<String> {
+    let mut fields = output
+        .split(|&byte| byte == 0)
+        .map(|field| String::from_utf8_lossy(field).into_owned());
+    let mut paths = Vec::new();
+    while let Some(header) = fields.next() {
+        // ":100644 100644 <old> <new> M", then the path, or for a rename or
+        // copy ("R100", "C75") the old path and the new one.
+        let parts: Vec<&str> = header.trim_start_matches(':').split(' ').collect();
+        let (Some(mode), Some(status)) = (parts.get(1), parts.last()) else {
+            break;
+        };
+        let mut path = fields.next();
+        if status.starts_with(['R', 'C']) {
+            path = fields.next();
+        }
+        let Some(path) = path else {
+            break;
+        };
+        if *mode != GITLINK && *mode != SYMLINK {
+            paths.push(path);
+        }
+    }
+    paths
+}
+
+/// The files git picked out for `--staged`, `--git-modified` or
+/// `--git-diff`.
+#[derive(Debug)]
+pub struct Selection {
+    /// What leads from the current directory to the repository root, such
+    /// as `../`; empty at the root itself.
+    pub root: String,
+    /// Relative to the repository root, as git names them.
+    pub paths: Vec<String>,
+}
+
+/// Changes between `base` and the index (`--cached`) or the working tree.
+fn changes(base: &str, cached: bool) -> Result<Vec<String>, String> {
+    let mut args = vec!["diff", "--raw", "-z", "--no-abbrev", "--diff-filter=ACMR"];
+    if cached {
+        args.push("--cached");
+    }
+    args.push(base);
+    Ok(parse_raw(&git(&args)?))
+}
+
+/// Files git does not track and does not ignore either.
+fn untracked(root: &str) -> Result<Vec<String>, String> {
+    let output = git(&[
+        "ls-files",
+        "--others",
+        "--exclude-standard",
+        "--full-name",
+        "-z",
+        ":/",
+    ])?;
+    Ok(output
+        .split(|&byte| byte == 0)
+        .filter(|path| !path.is_empty())
+        .map(|path| String::from_utf8_lossy(path).into_owned())
+        // Symbolic links are listed too.
+        .filter(|path| {
+            Path::new(root)
+                .join(path)
+                .symlink_metadata()
+                .is_ok_and(|metadata| metadata.is_file())
+        })
+        .collect())
+}
+
+/// Asks git for the files `changed` describes in the repository around the
+/// current directory.
+pub fn select(changed: &Changed) -> Result<Selection, String> {
+    let root = git_line(&["rev-parse", "--show-cdup"])
+        .map_err(|e| format!("{} needs a git repository: {}", changed.flag(), e))?;
+    let head = || git_line(&["rev-parse", "--verify", "--quiet", "HEAD"]);
+    let paths = match changed {
+        Changed::Staged => changes(&head().unwrap_or_else(|_| EMPTY_TREE.to_string()), true)?,
+        Changed::Modified => {
+            let base = head().unwrap_or_else(|_| EMPTY_TREE.to_string());
+            let mut paths = changes(&base, false)?;
+            paths.extend(untracked(&root)?);
+            paths
+        }
+        Changed::Since(reference) => {
+            let base = git_line(&["merge-base", reference, "HEAD"]).map_err(|e| {
+                format!(
+                    "--git-diff {}: no common ancestor with HEAD ({})",
+                    reference, e
+                )
+            })?;
+            let mut paths = changes(&base, false)?;
+            paths.extend(untracked(&root)?);
+            paths
+        }
+    };
+    Ok(Selection { root, paths })
+}
+
+/// Checks that `rev` names a commit in the repository around the current
+/// directory, for `--rev`.
+pub fn verify_revision(rev: &str) -> Result<(), String> {
+    git_line(&["rev-parse", "--show-cdup"])
+        .map_err(|e| format!("--rev needs a git repository: {}", e))?;
+    git_line(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{}^{{commit}}", rev),
+    ])
+    .map(|_| ())
+    .map_err(|_| format!("--rev {}: no such revision", rev))
+}
+
+/// The staged blob of `path`, a path relative to the current directory.
+pub fn staged(path: &str) -> String {
+    format!(":./{}", path)
+}
+
+/// The blob of `path`, relative to the current directory, at `rev`.
+pub fn at(rev: &str, path: &str) -> String {
+    format!("{}:./{}", rev, path)
+}
+
+/// What `object` is ("blob", "tree"), or `None` if there is no such
+/// object.
+pub fn object_type(object: &str) -> Option<String> {
+    git_line(&["cat-file", "-t", object]).ok()
+}
+
+/// The size in bytes of `object`.
+pub fn size(object: &str) -> io::Result<u64> {
+    git_line(&["cat-file", "-s", object])
+        .map_err(io::Error::other)?
+        .parse()
+        .map_err(|_| io::Error::other(format!("git gave no size for {}", object)))
+}
+
+/// Streams the contents of the blob `object` into `consume`, so that what
+/// is checked is what was staged or committed even where the working tree
+/// differs.
+pub fn read<T>(
+    object: &str,
+    consume: impl FnOnce(&mut dyn Read) -> io::Result<T>,
+) -> io::Result<T> {
+    let mut child = Command::new("git")
+        .args(["cat-file", "blob", object])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let result = match child.stdout.take() {
+        Some(mut stdout) => consume(&mut stdout),
+        None => Err(io::Error::other("git cat-file has no output")),
+    };
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(io::Error::other(format!(
+            "git cat-file failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_raw() {
+        let blob = "0".repeat(40);
+        let raw = format!(
+            ":000000 100644 {blob} {blob} A\0new.txt\0\
+             :100644 100644 {blob} {blob} M\0src/main.rs\0\
+             :100644 100644 {blob} {blob} R087\0old name.txt\0renamed.txt\0\
+             :000000 160000 {blob} {blob} A\0vendor/lib\0\
+             :000000 120000 {blob} {blob} A\0link\0\
+             :100644 100755 {blob} {blob} C100\0run.sh\0copy.sh\0"
+        );
+        assert_eq!(
+            parse_raw(raw.as_bytes()),
+            vec!["new.txt", "src/main.rs", "renamed.txt", "copy.sh"]
+        );
+        assert!(parse_raw(b"").is_empty());
+    }
+}
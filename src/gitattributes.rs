@@ -0,0 +1,292 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::cli::ExpectedEndings;
+use crate::glob::Glob;
+
+/// The state a line gives an attribute: `name`, `-name`, `!name` or
+/// `name=value`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Set,
+    Unset,
+    Unspecified,
+    Value(String),
+}
+
+#[derive(Debug)]
+struct Rule {
+    glob: Glob,
+    /// 1-based, for pointing at the line that set an expectation.
+    line: usize,
+    text: Option<State>,
+    eol: Option<State>,
+}
+
+/// The rules of one `.gitattributes` (or `.git/info/attributes`) file.
+#[derive(Debug)]
+struct AttributesFile {
+    /// The file's path relative to the repository root, e.g.
+    /// "sub/.gitattributes".
+    name: String,
+    /// The directory that anchored patterns are relative to.
+    dir: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl AttributesFile {
+    fn parse(name: String, dir: PathBuf, contents: &str) -> AttributesFile {
+        let rules = contents
+            .lines()
+            .enumerate()
+            .filter_map(|(index, line)| parse_rule(index + 1, line))
+            .collect();
+        AttributesFile { name, dir, rules }
+    }
+}
+
+fn parse_rule(line: usize, text: &str) -> Option<Rule> {
+    let mut fields = text.split_whitespace();
+    let pattern = fields.next()?;
+    // Macro definitions are not supported, and git ignores negated and
+    // directory patterns here.
+    if pattern.starts_with('#')
+        || pattern.starts_with("[attr]")
+        || pattern.starts_with('!')
+        || pattern.ends_with('/')
+    {
+        return None;
+    }
+    let mut rule = Rule {
+        glob: glob(pattern)?,
+        line,
+        text: None,
+        eol: None,
+    };
+    for field in fields {
+        let (name, state) = if let Some(name) = field.strip_prefix('-') {
+            (name, State::Unset)
+        } else if let Some(name) = field.strip_prefix('!') {
+            (name, State::Unspecified)
+        } else if let Some((name, value)) = field.split_once('=') {
+            (name, State::Value(value.to_string()))
+        } else {
+            (field, State::Set)
+        };
+        match name {
+            "text" => rule.text = Some(state),
+            "eol" => rule.eol = Some(state),
+            // The one built-in macro, which among other things unsets text.
+            "binary" if state == State::Set => rule.text = Some(State::Unset),
+            _ => {}
+        }
+    }
+    (rule.text.is_some() || rule.eol.is_some()).then_some(rule)
+}
+
+/// A pattern containing a slash is anchored to the file's directory;
+/// otherwise it matches a name at any depth, as in `.gitignore`.
+fn glob(pattern: &str) -> Option<Glob> {
+    let pattern = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", pattern)
+    };
+    Some(Glob::new(&pattern).ok()?.matching_dotfiles())
+}
+
+/// What the attributes say about a file's line endings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    /// `eol=lf` or `eol=crlf`, with where it was set, e.g.
+    /// ".gitattributes line 12".
+    Expect {
+        endings: ExpectedEndings,
+        source: String,
+    },
+    /// `-text` or `binary`: git leaves the file's bytes alone.
+    NotText,
+    /// Nothing that fixes the line endings, including files outside any
+    /// repository.
+    Unspecified,
+}
+
+/// Looks up the `eol` and `text` attributes of files, reading each
+/// `.gitattributes` only once however many files it applies to.
+#[derive(Debug, Default)]
+pub struct GitAttributes {
+    files: HashMap<PathBuf, Option<AttributesFile>>,
+}
+
+impl GitAttributes {
+    /// Applies every `.gitattributes` from the repository root down to the
+    /// file's directory, then `.git/info/attributes`, so that for each
+    /// attribute the last matching line of the deepest file wins.
+    pub fn verdict(&mut self, path: &Path) -> Verdict {
+        let Ok(absolute) = fs::canonicalize(path) else {
+            return Verdict::Unspecified;
+        };
+        let Some(repo) = absolute
+            .ancestors()
+            .skip(1)
+            .find(|dir| dir.join(".git").exists())
+        else {
+            return Verdict::Unspecified;
+        };
+        // Each file with the directory its patterns are relative to.
+        let mut sources: Vec<(PathBuf, &Path)> = absolute
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(repo))
+            .map(|dir| (dir.join(".gitattributes"), dir))
+            .collect();
+        sources.reverse();
+        sources.push((repo.join(".git").join("info").join("attributes"), repo));
+
+        let mut text = None;
+        let mut eol = None;
+        for (source, dir) in &sources {
+            let file = self.files.entry(source.clone()).or_insert_with(|| {
+                let contents = fs::read_to_string(source).ok()?;
+                let name = source.strip_prefix(repo).unwrap_or(source);
+                Some(AttributesFile::parse(
+                    name.to_string_lossy().replace('\\', "/"),
+                    dir.to_path_buf(),
+                    &contents,
+                ))
+            });
+            let Some(file) = file else {
+                continue;
+            };
+            let Ok(relative) = absolute.strip_prefix(&file.dir) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            for rule in file
+                .rules
+                .iter()
+                .filter(|rule| rule.glob.matches(&relative))
+            {
+                if let Some(state) = &rule.text {
+                    text = Some(state.clone());
+                }
+                if let Some(state) = &rule.eol {
+                    eol = Some((state.clone(), format!("{} line {}", file.name, rule.line)));
+                }
+            }
+        }
+        if text == Some(State::Unset) {
+            return Verdict::NotText;
+        }
+        let endings = match eol {
+            Some((State::Value(value), source)) => match value.as_str() {
+                "lf" => Some((ExpectedEndings::Lf, source)),
+                "crlf" => Some((ExpectedEndings::Crlf, source)),
+                _ => None,
+            },
+            _ => None,
+        };
+        match endings {
+            Some((endings, source)) => Verdict::Expect { endings, source },
+            None => Verdict::Unspecified,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repository in `dir` with the given `.gitattributes` files, by
+    /// directory relative to the root.
+    fn repo(dir: &str, files: &[(&str, &str)]) -> PathBuf {
+        let root = PathBuf::from(dir);
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join(".git")).unwrap();
+        for (sub, contents) in files {
+            fs::create_dir_all(root.join(sub)).unwrap();
+            fs::write(root.join(sub).join(".gitattributes"), contents).unwrap();
+        }
+        root
+    }
+
+    fn verdict(root: &Path, path: &str) -> Verdict {
+        let path = root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "").unwrap();
+        GitAttributes::default().verdict(&path)
+    }
+
+    fn expect(endings: ExpectedEndings, source: &str) -> Verdict {
+        Verdict::Expect {
+            endings,
+            source: source.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_last_match_wins() {
+        let root = repo(
+            "gitattributes_last_match",
+            &[(
+                "",
+                "# line endings\n*.sh text eol=lf\n*.bat text eol=crlf\n\
+                 legacy/*.sh eol=crlf\n*.png binary\ndata.txt -text eol=lf\n",
+            )],
+        );
+        assert_eq!(
+            verdict(&root, "run.sh"),
+            expect(ExpectedEndings::Lf, ".gitattributes line 2")
+        );
+        assert_eq!(
+            verdict(&root, "tools/deep/run.sh"),
+            expect(ExpectedEndings::Lf, ".gitattributes line 2")
+        );
+        assert_eq!(
+            verdict(&root, "legacy/run.sh"),
+            expect(ExpectedEndings::Crlf, ".gitattributes line 4")
+        );
+        assert_eq!(
+            verdict(&root, "build.bat"),
+            expect(ExpectedEndings::Crlf, ".gitattributes line 3")
+        );
+        assert_eq!(verdict(&root, "logo.png"), Verdict::NotText);
+        assert_eq!(verdict(&root, "data.txt"), Verdict::NotText);
+        assert_eq!(verdict(&root, "notes.md"), Verdict::Unspecified);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_nested_files_override_outer() {
+        let root = repo(
+            "gitattributes_nested",
+            &[
+                ("", "*.txt eol=crlf\n*.md eol=lf\n"),
+                ("sub", "*.txt eol=lf\n*.md !eol\n"),
+            ],
+        );
+        assert_eq!(
+            verdict(&root, "a.txt"),
+            expect(ExpectedEndings::Crlf, ".gitattributes line 1")
+        );
+        assert_eq!(
+            verdict(&root, "sub/a.txt"),
+            expect(ExpectedEndings::Lf, "sub/.gitattributes line 1")
+        );
+        assert_eq!(verdict(&root, "sub/a.md"), Verdict::Unspecified);
+        fs::create_dir_all(root.join(".git/info")).unwrap();
+        fs::write(root.join(".git/info/attributes"), "a.txt -text\n").unwrap();
+        assert_eq!(verdict(&root, "sub/a.txt"), Verdict::NotText);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_outside_a_repository() {
+        let dir = std::env::temp_dir().join(format!("mdlt_gitattributes_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(".gitattributes"), "* eol=crlf\n").unwrap();
+        assert_eq!(verdict(&dir, "a.txt"), Verdict::Unspecified);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
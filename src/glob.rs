@@ -0,0 +1,350 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::osname;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Char(char),
+    AnyChar,
+    AnyRun,
+    Class {
+        negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Component {
+    Literal(String),
+    Wildcard(Vec<Token>),
+    /// `**`: zero or more whole path components.
+    Recursive,
+}
+
+/// A compiled glob supporting `*`, `?`, `[...]` classes (with `!` or `^`
+/// negation and ranges) and `**` for any number of directories. `/` and `\`
+/// both separate components; a literal metacharacter is written as a
+/// one-character class such as `[[]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    components: Vec<Component>,
+    dotfiles: bool,
+}
+
+/// Returns whether `text` contains characters that make it a glob.
+pub fn has_meta(text: &str) -> bool {
+    text.contains(['*', '?', '['])
+}
+
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|part| !part.is_empty() && *part != ".")
+}
+
+fn parse_component(source: &str, part: &str) -> Result<Component, String> {
+    if part == "**" {
+        return Ok(Component::Recursive);
+    }
+    if !has_meta(part) {
+        return Ok(Component::Literal(part.to_string()));
+    }
+
+    let mut tokens = Vec::new();
+    let mut chars = part.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' => {
+                while chars.peek() == Some(&'*') {
+                    chars.next();
+                }
+                tokens.push(Token::AnyRun);
+            }
+            '?' => tokens.push(Token::AnyChar),
+            '[' => {
+                let negated = matches!(chars.peek(), Some('!') | Some('^'));
+                if negated {
+                    chars.next();
+                }
+                let mut ranges = Vec::new();
+                let mut first = true;
+                loop {
+                    let start = match chars.next() {
+                        Some(']') if !first => break,
+                        Some(c) => c,
+                        None => return Err(format!("Unclosed '[' in glob pattern '{}'", source)),
+                    };
+                    first = false;
+                    let mut lookahead = chars.clone();
+                    if lookahead.next() == Some('-') && lookahead.peek().is_some_and(|c| *c != ']')
+                    {
+                        chars.next();
+                        let end = chars.next().unwrap();
+                        ranges.push((start, end));
+                    } else {
+                        ranges.push((start, start));
+                    }
+                }
+                tokens.push(Token::Class { negated, ranges });
+            }
+            c => tokens.push(Token::Char(c)),
+        }
+    }
+    Ok(Component::Wildcard(tokens))
+}
+
+fn match_tokens(tokens: &[Token], name: &[char]) -> bool {
+    match tokens.split_first() {
+        None => name.is_empty(),
+        Some((Token::AnyRun, rest)) => {
+            (0..=name.len()).any(|skip| match_tokens(rest, &name[skip..]))
+        }
+        Some((token, rest)) => {
+            let Some((c, tail)) = name.split_first() else {
+                return false;
+            };
+            let matched = match token {
+                Token::Char(expected) => expected == c,
+                Token::AnyChar => true,
+                Token::Class { negated, ranges } => {
+                    ranges.iter().any(|(lo, hi)| lo <= c && c <= hi) != *negated
+                }
+                Token::AnyRun => unreachable!(),
+            };
+            matched && match_tokens(rest, tail)
+        }
+    }
+}
+
+impl Component {
+    fn matches(&self, name: &str, dotfiles: bool) -> bool {
+        match self {
+            Component::Literal(literal) => literal == name,
+            Component::Wildcard(tokens) => {
+                // Like a shell, wildcards only match dotfiles when the pattern
+                // itself starts with a dot.
+                if !dotfiles && name.starts_with('.') && tokens.first() != Some(&Token::Char('.')) {
+                    return false;
+                }
+                let name: Vec<char> = name.chars().collect();
+                match_tokens(tokens, &name)
+            }
+            Component::Recursive => true,
+        }
+    }
+}
+
+impl Glob {
+    pub fn new(source: &str) -> Result<Glob, String> {
+        let components = split(source)
+            .map(|part| parse_component(source, part))
+            .collect::<Result<_, _>>()?;
+        Ok(Glob {
+            components,
+            dotfiles: false,
+        })
+    }
+
+    /// Lets wildcards match names starting with a dot, as `.gitignore`
+    /// patterns do.
+    pub fn matching_dotfiles(mut self) -> Self {
+        self.dotfiles = true;
+        self
+    }
+
+    /// Matches a whole relative path, component by component.
+    pub fn matches(&self, path: &str) -> bool {
+        let parts: Vec<&str> = split(path).collect();
+        self.match_components(&self.components, &parts)
+    }
+
+    fn match_components(&self, components: &[Component], parts: &[&str]) -> bool {
+        match components.split_first() {
+            None => parts.is_empty(),
+            Some((Component::Recursive, rest)) => {
+                (0..=parts.len()).any(|skip| self.match_components(rest, &parts[skip..]))
+            }
+            Some((component, rest)) => match parts.split_first() {
+                Some((part, tail)) => {
+                    component.matches(part, self.dotfiles) && self.match_components(rest, tail)
+                }
+                None => false,
+            },
+        }
+    }
+}
+
+/// Expands `pattern` against the filesystem, returning matching regular
+/// files in sorted order. A pattern with no matches is an error naming it.
+pub fn expand(pattern: &str) -> Result<Vec<String>, String> {
+    let glob = Glob::new(pattern)?;
+    let root = if pattern.starts_with(['/', '\\']) {
+        PathBuf::from(&pattern[..1])
+    } else {
+        PathBuf::new()
+    };
+
+    let mut matches = Vec::new();
+    expand_from(&root, &glob.components, &mut matches);
+    matches.sort();
+    matches.dedup();
+    if matches.is_empty() {
+        return Err(format!("No files match pattern '{}'", pattern));
+    }
+    Ok(matches)
+}
+
+fn sorted_entries(dir: &Path) -> Vec<(String, PathBuf, bool)> {
+    let read_from = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    let Ok(entries) = fs::read_dir(read_from) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<_> = entries
+        .filter_map(Result::ok)
+        .filter_map(|entry| {
+            let is_dir = entry.file_type().ok()?.is_dir();
+            let path = dir.join(entry.file_name());
+            Some((
+                osname::encode(&entry.file_name()).into_owned(),
+                path,
+                is_dir,
+            ))
+        })
+        .collect();
+    entries.sort();
+    entries
+}
+
+fn expand_from(base: &Path, components: &[Component], matches: &mut Vec<String>) {
+    let Some((component, rest)) = components.split_first() else {
+        if base.is_file() {
+            matches.push(osname::encode(base.as_os_str()).into_owned());
+        }
+        return;
+    };
+
+    match component {
+        Component::Literal(literal) => {
+            let next = base.join(osname::path(literal));
+            if rest.is_empty() || next.is_dir() {
+                expand_from(&next, rest, matches);
+            }
+        }
+        Component::Recursive => {
+            expand_from(base, rest, matches);
+            for (name, path, is_dir) in sorted_entries(base) {
+                if is_dir && !name.starts_with('.') {
+                    expand_from(&path, components, matches);
+                }
+            }
+        }
+        Component::Wildcard(_) => {
+            for (name, path, is_dir) in sorted_entries(base) {
+                if component.matches(&name, false) && (rest.is_empty() || is_dir) {
+                    expand_from(&path, rest, matches);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().matches(path)
+    }
+
+    #[test]
+    fn test_has_meta() {
+        assert!(has_meta("*.rs"));
+        assert!(has_meta("file?.txt"));
+        assert!(has_meta("a[12].txt"));
+        assert!(!has_meta("src/main.rs"));
+    }
+
+    #[test]
+    fn test_star_and_question_mark() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "main.rsx"));
+        assert!(matches("file?.txt", "file1.txt"));
+        assert!(!matches("file?.txt", "file10.txt"));
+        assert!(!matches("*", ".hidden"));
+        assert!(matches(".*", ".hidden"));
+    }
+
+    #[test]
+    fn test_character_classes() {
+        assert!(matches("a[12].txt", "a1.txt"));
+        assert!(!matches("a[12].txt", "a3.txt"));
+        assert!(matches("a[0-9].txt", "a7.txt"));
+        assert!(matches("a[!0-9].txt", "ax.txt"));
+        assert!(!matches("a[^0-9].txt", "a7.txt"));
+        assert!(matches("a[[].txt", "a[.txt"));
+        assert!(matches("[]]", "]"));
+        assert!(matches("[a-]", "-"));
+        assert!(Glob::new("a[12.txt").unwrap_err().contains("Unclosed '['"));
+    }
+
+    #[test]
+    fn test_recursive_wildcard() {
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("src/**/*.rs", "src/main.rs"));
+        assert!(matches("src/**/*.rs", "src/a/b/c.rs"));
+        assert!(!matches("src/**/*.rs", "lib/a.rs"));
+        assert!(matches("**", "any/thing/at/all"));
+        assert!(matches("vendor/**", "vendor/x/y.js"));
+        assert!(matches("./src/*.rs", "src/x.rs"));
+        assert!(matches("src\\*.rs", "src/x.rs"));
+    }
+
+    #[test]
+    fn test_matching_dotfiles() {
+        let glob = Glob::new("*.swp").unwrap();
+        assert!(!glob.matches(".file.swp"));
+        assert!(glob.matching_dotfiles().matches(".file.swp"));
+    }
+
+    #[test]
+    fn test_expand_against_filesystem() {
+        let root = std::env::temp_dir().join(format!("mdlt_glob_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for file in [
+            "b.rs",
+            "a.rs",
+            "notes.txt",
+            "sub/c.rs",
+            "sub/deep/d.rs",
+            ".git/e.rs",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x\n").unwrap();
+        }
+        let base = root.to_str().unwrap();
+        let strip = |files: Vec<String>| -> Vec<String> {
+            files
+                .into_iter()
+                .map(|f| f[base.len() + 1..].replace('\\', "/"))
+                .collect()
+        };
+
+        assert_eq!(
+            strip(expand(&format!("{}/*.rs", base)).unwrap()),
+            vec!["a.rs", "b.rs"]
+        );
+        assert_eq!(
+            strip(expand(&format!("{}/**/*.rs", base)).unwrap()),
+            vec!["a.rs", "b.rs", "sub/c.rs", "sub/deep/d.rs"]
+        );
+        let err = expand(&format!("{}/*.md", base)).unwrap_err();
+        assert!(err.starts_with("No files match pattern"));
+        assert!(err.contains("*.md"));
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,742 @@
+use std::io::{self, Read};
+use std::str::FromStr;
+
+/// When files are decompressed before they are analyzed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decompress {
+    /// Files whose names end in `.gz`.
+    Auto,
+    /// Every file, whatever its name.
+    Gzip,
+    /// No file; a `.gz` file is analyzed as the bytes on disk.
+    Never,
+}
+
+impl Decompress {
+    /// Whether the file named `name` is decompressed.
+    pub fn applies_to(self, name: &str) -> bool {
+        match self {
+            Decompress::Auto => is_gzip_name(name),
+            Decompress::Gzip => true,
+            Decompress::Never => false,
+        }
+    }
+}
+
+impl FromStr for Decompress {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "auto" => Ok(Decompress::Auto),
+            "gzip" => Ok(Decompress::Gzip),
+            "none" => Ok(Decompress::Never),
+            other => Err(format!(
+                "Unknown --decompress value '{}': expected auto, gzip or none",
+                other
+            )),
+        }
+    }
+}
+
+fn is_gzip_name(name: &str) -> bool {
+    name.len() > 3 && name[name.len() - 3..].eq_ignore_ascii_case(".gz")
+}
+
+/// `name` without its `.gz`, so that `app.log.gz` is analyzed as a `log`
+/// file.
+pub fn inner_name(name: &str) -> &str {
+    match is_gzip_name(name) {
+        true => &name[..name.len() - 3],
+        false => name,
+    }
+}
+
+/// How far back a match may reach, and so the history kept.
+const WINDOW: usize = 32 * 1024;
+
+/// Size of the buffer the compressed bytes are read through.
+const INPUT_BUFFER_SIZE: usize = 8 * 1024;
+
+/// The base lengths of the length symbols 257 to 285, and their extra bits.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// The base distances of the distance symbols 0 to 29, and their extra
+/// bits.
+const DISTANCE_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+/// The order the code length code lengths of a dynamic block come in.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// The CRC-32 of the gzip trailer, with the reflected polynomial
+/// 0xEDB88320, one entry per byte value.
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut byte = 0;
+    while byte < 256 {
+        let mut crc = byte as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = match crc & 1 {
+                1 => 0xedb8_8320 ^ (crc >> 1),
+                _ => crc >> 1,
+            };
+            bit += 1;
+        }
+        table[byte] = crc;
+        byte += 1;
+    }
+    table
+};
+
+/// The CRC-32 of `bytes` following those `crc` is the CRC-32 of, as gzip
+/// and zip check their contents with.
+pub fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    let crc = bytes.iter().fold(!crc, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    });
+    !crc
+}
+
+/// The compressed bytes, read a bit at a time, least significant first.
+struct Input<R> {
+    reader: R,
+    buffer: Box<[u8]>,
+    start: usize,
+    end: usize,
+    /// Bytes taken from the buffer so far.
+    offset: u64,
+    /// What the stream is called in errors: `gzip`, or `deflate` for a bare
+    /// one.
+    stream: &'static str,
+    bits: u32,
+    count: u32,
+}
+
+impl<R: Read> Input<R> {
+    /// The next byte, or `None` at the end of the input.
+    fn byte(&mut self) -> io::Result<Option<u8>> {
+        if self.start == self.end {
+            self.end = loop {
+                match self.reader.read(&mut self.buffer) {
+                    Ok(read) => break read,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(e),
+                }
+            };
+            self.start = 0;
+            if self.end == 0 {
+                return Ok(None);
+            }
+        }
+        self.start += 1;
+        self.offset += 1;
+        Ok(Some(self.buffer[self.start - 1]))
+    }
+
+    /// The next `n` bits, at most 16.
+    fn bits(&mut self, n: u32) -> io::Result<u32> {
+        while self.count < n {
+            let byte = self
+                .byte()?
+                .ok_or_else(|| truncated(self.stream, self.offset))?;
+            self.bits |= (byte as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1 << n) - 1);
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+
+    /// Skips to the next byte boundary, as stored blocks and the trailer
+    /// start on one.
+    fn align(&mut self) {
+        self.bits >>= self.count % 8;
+        self.count -= self.count % 8;
+    }
+}
+
+fn corrupt(stream: &str, offset: u64, what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("corrupt {} stream at byte {}: {}", stream, offset, what),
+    )
+}
+
+fn truncated(stream: &str, offset: u64) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::UnexpectedEof,
+        format!("truncated {} stream at byte {}", stream, offset),
+    )
+}
+
+/// A canonical Huffman code, as the number of codes of each length and the
+/// symbols in code order.
+struct Huffman {
+    counts: [u16; 16],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    /// The code with the given code length for each symbol, 0 for unused
+    /// ones, or `None` when there are more codes than the lengths allow.
+    fn new(lengths: &[u8]) -> Option<Huffman> {
+        let mut counts = [0; 16];
+        for &length in lengths {
+            counts[length as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = 2 * left - count as i32;
+            if left < 0 {
+                return None;
+            }
+        }
+        let mut offsets = [0; 16];
+        for length in 1..15 {
+            offsets[length + 1] = offsets[length] + counts[length];
+        }
+        let mut symbols = vec![0; lengths.len()];
+        for (symbol, &length) in lengths.iter().enumerate() {
+            if length != 0 {
+                symbols[offsets[length as usize] as usize] = symbol as u16;
+                offsets[length as usize] += 1;
+            }
+        }
+        Some(Huffman { counts, symbols })
+    }
+
+    fn decode<R: Read>(&self, input: &mut Input<R>) -> io::Result<u16> {
+        let (mut code, mut first, mut index) = (0, 0, 0);
+        for &count in &self.counts[1..] {
+            code |= input.bits(1)? as i32;
+            let count = count as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + code - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            code <<= 1;
+        }
+        Err(corrupt(input.stream, input.offset, "invalid code"))
+    }
+}
+
+/// The literal and length code and the distance code of a block.
+struct Codes {
+    literals: Huffman,
+    distances: Huffman,
+}
+
+impl Codes {
+    fn fixed() -> Codes {
+        let mut lengths = [8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        Codes {
+            literals: Huffman::new(&lengths).expect("the fixed code is complete"),
+            distances: Huffman::new(&[5; 30]).expect("the fixed code is complete"),
+        }
+    }
+
+    fn dynamic<R: Read>(input: &mut Input<R>) -> io::Result<Codes> {
+        let literals = input.bits(5)? as usize + 257;
+        let distances = input.bits(5)? as usize + 1;
+        let code_lengths = input.bits(4)? as usize + 4;
+        if literals > 286 || distances > 30 {
+            return Err(corrupt(input.stream, input.offset, "too many codes"));
+        }
+        let mut lengths = [0; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+            lengths[symbol] = input.bits(3)? as u8;
+        }
+        let stream = input.stream;
+        let invalid = |offset| corrupt(stream, offset, "invalid code lengths");
+        let code = Huffman::new(&lengths).ok_or_else(|| invalid(input.offset))?;
+        let mut lengths = vec![0; literals + distances];
+        let mut filled = 0;
+        while filled < lengths.len() {
+            let symbol = code.decode(input)?;
+            let (length, repeat) = match symbol {
+                0..=15 => (symbol as u8, 1),
+                16 if filled > 0 => (lengths[filled - 1], 3 + input.bits(2)? as usize),
+                17 => (0, 3 + input.bits(3)? as usize),
+                18 => (0, 11 + input.bits(7)? as usize),
+                _ => return Err(invalid(input.offset)),
+            };
+            if filled + repeat > lengths.len() {
+                return Err(invalid(input.offset));
+            }
+            lengths[filled..filled + repeat].fill(length);
+            filled += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err(corrupt(input.stream, input.offset, "no end-of-block code"));
+        }
+        Ok(Codes {
+            literals: Huffman::new(&lengths[..literals]).ok_or_else(|| invalid(input.offset))?,
+            distances: Huffman::new(&lengths[literals..]).ok_or_else(|| invalid(input.offset))?,
+        })
+    }
+}
+
+/// Where the decoder is in the stream.
+enum State {
+    /// Before a member's header; `true` for the first one, which must be
+    /// there.
+    Header(bool),
+    /// Before a block's header.
+    Block,
+    /// In a stored block, with the bytes left of it.
+    Stored(usize),
+    /// In a compressed block, with what is left of a match that did not fit
+    /// in the caller's buffer.
+    Compressed(Box<Codes>, Option<(usize, usize)>),
+    /// After a member's last block.
+    Trailer,
+    Done,
+}
+
+/// Decompresses a gzip stream, members one after another as `zcat` does,
+/// with memory bounded by the 32 KiB window whatever the size of the
+/// output. A corrupt or truncated stream is an `InvalidData` or
+/// `UnexpectedEof` error naming the compressed byte where it went wrong.
+pub struct Decoder<R> {
+    input: Input<R>,
+    state: State,
+    /// A bare deflate stream, without the gzip header and trailer.
+    bare: bool,
+    last_block: bool,
+    window: Box<[u8]>,
+    /// Bytes written by this member, of which the window holds the last
+    /// ones.
+    written: u64,
+    crc: u32,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        Decoder::with(reader, false)
+    }
+
+    /// Decompresses a bare deflate stream, as a zip member holds, which
+    /// ends with its last block.
+    pub fn bare(reader: R) -> Self {
+        Decoder::with(reader, true)
+    }
+
+    fn with(reader: R, bare: bool) -> Self {
+        Decoder {
+            input: Input {
+                reader,
+                buffer: vec![0; INPUT_BUFFER_SIZE].into_boxed_slice(),
+                start: 0,
+                end: 0,
+                offset: 0,
+                stream: if bare { "deflate" } else { "gzip" },
+                bits: 0,
+                count: 0,
+            },
+            state: if bare {
+                State::Block
+            } else {
+                State::Header(true)
+            },
+            bare,
+            last_block: false,
+            window: vec![0; WINDOW].into_boxed_slice(),
+            written: 0,
+            crc: 0,
+        }
+    }
+
+    /// The compressed bytes read so far, all of them once the output is
+    /// read to the end.
+    pub fn compressed_bytes(&self) -> u64 {
+        self.input.offset
+    }
+
+    fn header(&mut self, first: bool) -> io::Result<bool> {
+        let input = &mut self.input;
+        let start = input.offset;
+        let Some(id) = input.byte()? else {
+            return match first {
+                true => Err(truncated(input.stream, 0)),
+                false => Ok(false),
+            };
+        };
+        if id != 0x1f || input.byte()? != Some(0x8b) {
+            return Err(corrupt(input.stream, start, "not a gzip stream"));
+        }
+        // The method, the flags, the time, the extra flags and the system.
+        let mut fixed = [0x1f, 0x8b, 0, 0, 0, 0, 0, 0, 0, 0];
+        for byte in &mut fixed[2..] {
+            *byte = input.bits(8)? as u8;
+        }
+        if fixed[2] != 8 {
+            return Err(corrupt(
+                input.stream,
+                start + 2,
+                "unknown compression method",
+            ));
+        }
+        let flags = fixed[3];
+        if flags & 0xe0 != 0 {
+            return Err(corrupt(input.stream, start + 3, "reserved flags set"));
+        }
+        if flags & 0x04 != 0 {
+            let extra = input.bits(16)?;
+            for _ in 0..extra {
+                input.bits(8)?;
+            }
+        }
+        // The file name and the comment, each up to a NUL.
+        for flag in [0x08, 0x10] {
+            if flags & flag != 0 {
+                while input.bits(8)? != 0 {}
+            }
+        }
+        if flags & 0x02 != 0 {
+            input.bits(16)?;
+        }
+        Ok(true)
+    }
+
+    fn block(&mut self) -> io::Result<State> {
+        let input = &mut self.input;
+        self.last_block = input.bits(1)? == 1;
+        match input.bits(2)? {
+            0 => {
+                input.align();
+                let length = input.bits(16)?;
+                if input.bits(16)? != !length & 0xffff {
+                    return Err(corrupt(
+                        input.stream,
+                        input.offset,
+                        "stored block length mismatch",
+                    ));
+                }
+                Ok(State::Stored(length as usize))
+            }
+            1 => Ok(State::Compressed(Box::new(Codes::fixed()), None)),
+            2 => Ok(State::Compressed(Box::new(Codes::dynamic(input)?), None)),
+            _ => Err(corrupt(input.stream, input.offset, "invalid block type")),
+        }
+    }
+
+    fn trailer(&mut self) -> io::Result<()> {
+        let input = &mut self.input;
+        input.align();
+        let crc = input.bits(16)? | input.bits(16)? << 16;
+        let size = input.bits(16)? | input.bits(16)? << 16;
+        if crc != self.crc {
+            return Err(corrupt(input.stream, input.offset - 8, "CRC mismatch"));
+        }
+        if size != self.written as u32 {
+            return Err(corrupt(input.stream, input.offset - 4, "length mismatch"));
+        }
+        self.written = 0;
+        self.crc = 0;
+        Ok(())
+    }
+
+    fn put(&mut self, byte: u8, buf: &mut [u8], filled: &mut usize) {
+        self.window[(self.written % WINDOW as u64) as usize] = byte;
+        self.written += 1;
+        buf[*filled] = byte;
+        *filled += 1;
+    }
+
+    /// Copies what is left of a match of `length` bytes from `distance`
+    /// back, returning how much did not fit.
+    fn copy(
+        &mut self,
+        distance: usize,
+        length: usize,
+        buf: &mut [u8],
+        filled: &mut usize,
+    ) -> usize {
+        let n = length.min(buf.len() - *filled);
+        for _ in 0..n {
+            let byte = self.window[((self.written - distance as u64) % WINDOW as u64) as usize];
+            self.put(byte, buf, filled);
+        }
+        length - n
+    }
+
+    /// Decodes into `buf` until it is full or the block ends, returning
+    /// the state to go on in.
+    fn compressed(
+        &mut self,
+        codes: Box<Codes>,
+        mut pending: Option<(usize, usize)>,
+        buf: &mut [u8],
+        filled: &mut usize,
+    ) -> io::Result<State> {
+        loop {
+            if let Some((distance, length)) = pending {
+                let left = self.copy(distance, length, buf, filled);
+                pending = (left > 0).then_some((distance, left));
+            }
+            if *filled == buf.len() {
+                return Ok(State::Compressed(codes, pending));
+            }
+            let symbol = codes.literals.decode(&mut self.input)? as usize;
+            match symbol {
+                0..=255 => self.put(symbol as u8, buf, filled),
+                256 => {
+                    return Ok(match self.last_block {
+                        true => State::Trailer,
+                        false => State::Block,
+                    })
+                }
+                257..=285 => {
+                    let index = symbol - 257;
+                    let input = &mut self.input;
+                    let length = LENGTH_BASE[index] as usize
+                        + input.bits(LENGTH_EXTRA[index] as u32)? as usize;
+                    let index = codes.distances.decode(input)? as usize;
+                    if index >= 30 {
+                        return Err(corrupt(input.stream, input.offset, "invalid distance code"));
+                    }
+                    let distance = DISTANCE_BASE[index] as usize
+                        + input.bits(DISTANCE_EXTRA[index] as u32)? as usize;
+                    if distance as u64 > self.written.min(WINDOW as u64) {
+                        return Err(corrupt(input.stream, input.offset, "distance too far back"));
+                    }
+                    pending = Some((distance, length));
+                }
+                _ => {
+                    return Err(corrupt(
+                        self.input.stream,
+                        self.input.offset,
+                        "invalid length code",
+                    ))
+                }
+            }
+        }
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        // Where the current member's output starts in `buf`, for its CRC.
+        let mut member_start = 0;
+        while filled < buf.len() {
+            // Left as `Done` should a step fail, so that nothing follows
+            // the error.
+            self.state = match std::mem::replace(&mut self.state, State::Done) {
+                State::Header(first) => match self.header(first)? {
+                    true => State::Block,
+                    false => State::Done,
+                },
+                State::Block => self.block()?,
+                State::Stored(left) => {
+                    let n = left.min(buf.len() - filled);
+                    for _ in 0..n {
+                        let byte = self.input.bits(8)? as u8;
+                        self.put(byte, buf, &mut filled);
+                    }
+                    match (left - n, self.last_block) {
+                        (0, true) => State::Trailer,
+                        (0, false) => State::Block,
+                        (left, _) => State::Stored(left),
+                    }
+                }
+                State::Compressed(codes, pending) => {
+                    self.compressed(codes, pending, buf, &mut filled)?
+                }
+                State::Trailer => {
+                    self.crc = crc32(self.crc, &buf[member_start..filled]);
+                    member_start = filled;
+                    if self.bare {
+                        State::Done
+                    } else {
+                        self.trailer()?;
+                        State::Header(false)
+                    }
+                }
+                State::Done => break,
+            };
+        }
+        self.crc = crc32(self.crc, &buf[member_start..filled]);
+        Ok(filled)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// `gzip -9` of "hello\r\nworld\n", a single fixed-code block.
+    const FIXED: &str = "1f8b0800000000000203cb48cdc9c9e7e52acf2fca49e10200497035aa0d000000";
+
+    /// `gzip -9` of `log()`, a single dynamic-code block.
+    const DYNAMIC: &str = "1f8b080000000000020385d34b0ac3201485e179a07b7009eaf5954217e3c03ea8\
+                           44504bb75f0219a6e78caf9c0f42fefada8ad2aaddd57c1655dbe3aac6ccf3336e\
+                           ed7d59ea7e3567d76feedb71b767f7d27bebc70381f38ecc7b361fe07c24f389cd\
+                           aff8e368b26f0c038cc58230c151c1632130215221616165ff9066823550b09609\
+                           42058705cf8440858885c48495a6865316d6b2f09871cdc27216dab3e0a085152d\
+                           3469f9df741e53ed6f961fb165b4b019050000";
+
+    fn hex(text: &str) -> Vec<u8> {
+        (0..text.len())
+            .step_by(2)
+            .map(|index| u8::from_str_radix(&text[index..index + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn log() -> Vec<u8> {
+        let mut text = String::new();
+        for line in 0..40 {
+            let status = ["ok", "warn", "error"][line % 3];
+            text.push_str(&format!("line {} of the log: status={}\r\n", line, status));
+        }
+        text.push_str("last line\n");
+        text.into_bytes()
+    }
+
+    /// `data` in a member of one stored block, with a file name.
+    fn stored(data: &[u8]) -> Vec<u8> {
+        let mut member = vec![0x1f, 0x8b, 8, 0x08, 0, 0, 0, 0, 0, 3];
+        member.extend_from_slice(b"a.txt\0");
+        let length = data.len() as u16;
+        member.push(1);
+        member.extend_from_slice(&length.to_le_bytes());
+        member.extend_from_slice(&(!length).to_le_bytes());
+        member.extend_from_slice(data);
+        member.extend_from_slice(&crc32(0, data).to_le_bytes());
+        member.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        member
+    }
+
+    /// Decompresses `compressed` in reads of `size` bytes.
+    fn inflate(compressed: &[u8], size: usize) -> io::Result<Vec<u8>> {
+        let mut decoder = Decoder::new(compressed);
+        let mut output = Vec::new();
+        let mut buffer = vec![0; size];
+        loop {
+            match decoder.read(&mut buffer)? {
+                0 => break,
+                read => output.extend_from_slice(&buffer[..read]),
+            }
+        }
+        assert_eq!(decoder.compressed_bytes(), compressed.len() as u64);
+        Ok(output)
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(0, b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(crc32(0, b"1234"), b"56789"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn test_decompresses_every_block_type() {
+        for size in [1, 7, 64 * 1024] {
+            assert_eq!(inflate(&hex(FIXED), size).unwrap(), b"hello\r\nworld\n");
+            assert_eq!(inflate(&hex(DYNAMIC), size).unwrap(), log());
+            assert_eq!(inflate(&stored(b"a\rb\n"), size).unwrap(), b"a\rb\n");
+        }
+    }
+
+    #[test]
+    fn test_decompresses_bare_deflate() {
+        // The deflate stream inside `FIXED`, between its header and trailer.
+        let fixed = hex(FIXED);
+        let mut decoder = Decoder::bare(&fixed[10..fixed.len() - 8]);
+        let mut output = Vec::new();
+        decoder.read_to_end(&mut output).unwrap();
+        assert_eq!(output, b"hello\r\nworld\n");
+        let error = Decoder::bare(&fixed[10..14])
+            .read_to_end(&mut output)
+            .unwrap_err();
+        assert_eq!(error.to_string(), "truncated deflate stream at byte 4");
+    }
+
+    #[test]
+    fn test_decompresses_members_one_after_another() {
+        let mut concatenated = hex(FIXED);
+        concatenated.extend(stored(b"!\n"));
+        concatenated.extend(stored(b""));
+        assert_eq!(inflate(&concatenated, 5).unwrap(), b"hello\r\nworld\n!\n");
+    }
+
+    #[test]
+    fn test_errors_name_the_byte() {
+        let error = |compressed: &[u8]| {
+            let error = inflate(compressed, 4096).unwrap_err();
+            (error.kind(), error.to_string())
+        };
+        let dynamic = hex(DYNAMIC);
+        assert_eq!(
+            error(&dynamic[..100]),
+            (
+                io::ErrorKind::UnexpectedEof,
+                "truncated gzip stream at byte 100".to_string()
+            )
+        );
+        assert_eq!(
+            error(b""),
+            (
+                io::ErrorKind::UnexpectedEof,
+                "truncated gzip stream at byte 0".to_string()
+            )
+        );
+        let mut crc = hex(FIXED);
+        let len = crc.len();
+        crc[len - 8] ^= 1;
+        assert_eq!(
+            error(&crc),
+            (
+                io::ErrorKind::InvalidData,
+                format!("corrupt gzip stream at byte {}: CRC mismatch", len - 8)
+            )
+        );
+        assert_eq!(
+            error(b"plain text, not gzip").1,
+            "corrupt gzip stream at byte 0: not a gzip stream"
+        );
+        let mut block = hex(FIXED)[..10].to_vec();
+        block.push(0x07);
+        assert_eq!(
+            error(&block).1,
+            "corrupt gzip stream at byte 11: invalid block type"
+        );
+        let mut trailing = hex(FIXED);
+        trailing.extend_from_slice(b"junk");
+        assert!(error(&trailing).1.ends_with(": not a gzip stream"));
+    }
+
+    #[test]
+    fn test_decompress_applies_to() {
+        assert!(Decompress::Auto.applies_to("app.log.gz"));
+        assert!(Decompress::Auto.applies_to("APP.GZ"));
+        assert!(!Decompress::Auto.applies_to("app.log"));
+        assert!(!Decompress::Auto.applies_to(".gz"));
+        assert!(Decompress::Gzip.applies_to("app.log"));
+        assert!(!Decompress::Never.applies_to("app.log.gz"));
+        assert_eq!(inner_name("app.log.gz"), "app.log");
+        assert_eq!(inner_name("app.log"), "app.log");
+        assert_eq!("none".parse(), Ok(Decompress::Never));
+        assert!("zstd".parse::<Decompress>().is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use std::io::{self, Write};
+use std::str::FromStr;
+
+/// Most bucket bounds `--histogram-buckets` accepts.
+pub const MAX_BOUNDS: usize = 16;
+
+/// Widest bar drawn, in `#` characters.
+const BAR_WIDTH: usize = 40;
+
+/// The inclusive upper bounds of the histogram buckets; lengths above the
+/// last bound go into one more, open-ended bucket. Stored inline so scan
+/// settings stay `Copy`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Buckets {
+    bounds: [usize; MAX_BOUNDS],
+    len: usize,
+}
+
+impl Default for Buckets {
+    /// 0, 1-20, 21-40, 41-80, 81-120, 121-200 and 201+.
+    fn default() -> Self {
+        let mut bounds = [0; MAX_BOUNDS];
+        bounds[..6].copy_from_slice(&[0, 20, 40, 80, 120, 200]);
+        Buckets { bounds, len: 6 }
+    }
+}
+
+impl FromStr for Buckets {
+    type Err = String;
+
+    /// Parses a comma-separated list of increasing bounds such as
+    /// `40,80,120`.
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            format!(
+                "--histogram-buckets expects up to {} increasing numbers like 40,80,120, got '{}'",
+                MAX_BOUNDS, value
+            )
+        };
+        let mut buckets = Buckets {
+            bounds: [0; MAX_BOUNDS],
+            len: 0,
+        };
+        for part in value.split(',') {
+            let bound: usize = part.trim().parse().map_err(|_| invalid())?;
+            if buckets.len == MAX_BOUNDS || buckets.bounds().last() >= Some(&bound) {
+                return Err(invalid());
+            }
+            buckets.bounds[buckets.len] = bound;
+            buckets.len += 1;
+        }
+        Ok(buckets)
+    }
+}
+
+impl Buckets {
+    pub fn bounds(&self) -> &[usize] {
+        &self.bounds[..self.len]
+    }
+
+    /// The bucket a line of `length` falls into.
+    fn index(&self, length: usize) -> usize {
+        self.bounds().partition_point(|&bound| bound < length)
+    }
+
+    /// Describes bucket `index`, e.g. "0", "21-40" or "201+".
+    fn label(&self, index: usize) -> String {
+        let lower = match index {
+            0 => 0,
+            _ => self.bounds[index - 1] + 1,
+        };
+        match self.bounds().get(index) {
+            None => format!("{}+", lower),
+            Some(&upper) if upper == lower => upper.to_string(),
+            Some(&upper) => format!("{}-{}", lower, upper),
+        }
+    }
+}
+
+/// Line counts per length bucket, built up one line at a time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Histogram {
+    buckets: Buckets,
+    counts: Vec<usize>,
+}
+
+impl Histogram {
+    pub fn new(buckets: Buckets) -> Self {
+        Histogram {
+            buckets,
+            counts: vec![0; buckets.len + 1],
+        }
+    }
+
+    /// Rebuilds a histogram from what [`Histogram::buckets`] lists, or
+    /// `None` unless the bounds increase and only the last one is open.
+    #[cfg(any(feature = "cli", feature = "serde"))]
+    pub fn from_buckets(rows: &[(Option<usize>, usize)]) -> Option<Self> {
+        let (last, bounded) = rows.split_last()?;
+        if last.0.is_some() || bounded.len() > MAX_BOUNDS {
+            return None;
+        }
+        let mut buckets = Buckets {
+            bounds: [0; MAX_BOUNDS],
+            len: 0,
+        };
+        for &(bound, _) in bounded {
+            let bound = bound?;
+            if buckets.bounds().last() >= Some(&bound) {
+                return None;
+            }
+            buckets.bounds[buckets.len] = bound;
+            buckets.len += 1;
+        }
+        Some(Histogram {
+            buckets,
+            counts: rows.iter().map(|&(_, count)| count).collect(),
+        })
+    }
+
+    pub fn add(&mut self, length: usize) {
+        self.counts[self.buckets.index(length)] += 1;
+    }
+
+    /// Adds the counts of `other`, which has the same buckets.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, other: &Histogram) {
+        for (count, more) in self.counts.iter_mut().zip(&other.counts) {
+            *count += more;
+        }
+    }
+
+    /// Each bucket's upper bound (`None` for the last, open-ended one) and
+    /// count.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<usize>, usize)> + '_ {
+        let bounds = self.buckets.bounds().iter().map(|&bound| Some(bound));
+        bounds.chain([None]).zip(self.counts.iter().copied())
+    }
+
+    /// Writes one row per bucket with a bar scaled to the fullest bucket.
+    /// Any non-empty bucket gets at least one `#`.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let most = self.counts.iter().copied().max().unwrap_or(0);
+        let labels: Vec<String> = (0..self.counts.len())
+            .map(|index| self.buckets.label(index))
+            .collect();
+        let label_width = labels.iter().map(String::len).max().unwrap_or(0);
+        let count_width = most.to_string().len();
+        for (label, &count) in labels.iter().zip(&self.counts) {
+            let bar = match most {
+                0 => 0,
+                _ => (count * BAR_WIDTH).div_ceil(most),
+            };
+            let row = format!(
+                "  {:>label_width$}  {:>count_width$} {}",
+                label,
+                count,
+                "#".repeat(bar)
+            );
+            writeln!(writer, "{}", row.trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(histogram: &Histogram) -> String {
+        let mut buffer = Vec::new();
+        histogram.write(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_default_buckets() {
+        let mut histogram = Histogram::new(Buckets::default());
+        for length in [0, 0, 1, 20, 21, 80, 81, 200, 201, 5000] {
+            histogram.add(length);
+        }
+        let buckets: Vec<_> = histogram.buckets().collect();
+        assert_eq!(
+            buckets,
+            [
+                (Some(0), 2),
+                (Some(20), 2),
+                (Some(40), 1),
+                (Some(80), 1),
+                (Some(120), 1),
+                (Some(200), 1),
+                (None, 2)
+            ]
+        );
+        assert_eq!(
+            render(&histogram),
+            "        0  2 ########################################\n\
+             \x20    1-20  2 ########################################\n\
+             \x20   21-40  1 ####################\n\
+             \x20   41-80  1 ####################\n\
+             \x20  81-120  1 ####################\n\
+             \x20 121-200  1 ####################\n\
+             \x20    201+  2 ########################################\n"
+        );
+    }
+
+    #[test]
+    fn test_empty_histogram_has_no_bars() {
+        let histogram = Histogram::new("40,80".parse().unwrap());
+        assert_eq!(render(&histogram), "   0-40  0\n  41-80  0\n    81+  0\n");
+    }
+
+    #[test]
+    fn test_parse_buckets() {
+        let buckets: Buckets = "40, 80,120".parse().unwrap();
+        assert_eq!(buckets.bounds(), [40, 80, 120]);
+        for bad in ["", "40,40", "80,40", "-1", "a,b"] {
+            assert!(bad.parse::<Buckets>().is_err(), "{}", bad);
+        }
+        let too_many: Vec<String> = (0..=MAX_BOUNDS).map(|n| n.to_string()).collect();
+        assert!(too_many.join(",").parse::<Buckets>().is_err());
+    }
+}
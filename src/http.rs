@@ -0,0 +1,256 @@
+use std::io::{self, Read};
+#[cfg(feature = "http")]
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+/// How many redirects are followed before giving up.
+#[cfg(feature = "http")]
+const MAX_REDIRECTS: u32 = 10;
+
+/// Whether `path` is an `http://` or `https://` URL rather than a path.
+pub fn is_url(path: &str) -> bool {
+    ["http://", "https://"].iter().any(|scheme| {
+        path.len() > scheme.len()
+            && path
+                .get(..scheme.len())
+                .is_some_and(|start| start.eq_ignore_ascii_case(scheme))
+    })
+}
+
+/// The last segment of the path of `url`, percent-decoded, which names
+/// the file it fetches: `run.sh` for
+/// `https://example.com/raw/main/run.sh?token=1`. A URL whose path ends in
+/// `/` names nothing, and gives the empty string.
+pub fn file_name(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+    let segment = match path.split_once('/') {
+        Some((_, path)) => path.rsplit('/').next().unwrap_or_default(),
+        None => "",
+    };
+    let bytes = segment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escape = bytes
+            .get(index + 1..index + 3)
+            .filter(|_| bytes[index] == b'%')
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escape {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The body of a response, failing with `FileTooLarge` once it runs past
+/// `--max-size`.
+#[cfg(feature = "http")]
+struct Capped<R> {
+    reader: R,
+    left: Option<u64>,
+}
+
+#[cfg(feature = "http")]
+impl<R: Read> Read for Capped<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buf)?;
+        if let Some(left) = &mut self.left {
+            *left = left.checked_sub(read as u64).ok_or_else(too_large)?;
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(feature = "http")]
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::FileTooLarge, "larger than --max-size")
+}
+
+/// What went wrong, from curl's exit status and what it printed.
+#[cfg(feature = "http")]
+fn curl_error(code: Option<i32>, stderr: &str, timeout: Option<Duration>) -> io::Error {
+    let detail = stderr
+        .lines()
+        .last()
+        .unwrap_or_default()
+        .trim()
+        .trim_start_matches("curl: ");
+    // curl prefixes its messages with `(code)`.
+    let detail = match detail.split_once(") ") {
+        Some((code, rest)) if code.starts_with('(') => rest,
+        _ => detail,
+    };
+    let (kind, message) = match code {
+        Some(28) => (
+            io::ErrorKind::TimedOut,
+            match timeout {
+                Some(timeout) => format!("timed out after {}s (--timeout)", timeout.as_secs_f64()),
+                None => format!("timed out: {}", detail),
+            },
+        ),
+        Some(47) => (
+            io::ErrorKind::Other,
+            format!("more than {} redirects", MAX_REDIRECTS),
+        ),
+        Some(63) => return too_large(),
+        Some(35 | 51 | 53 | 54 | 58 | 59 | 60 | 64 | 66 | 77 | 80 | 82 | 83 | 90 | 91) => {
+            (io::ErrorKind::Other, format!("TLS error: {}", detail))
+        }
+        _ if detail.is_empty() => (io::ErrorKind::Other, "curl failed".to_string()),
+        _ => (io::ErrorKind::Other, detail.to_string()),
+    };
+    io::Error::new(kind, message)
+}
+
+/// Streams the body of `url` into `consume`, following redirects and
+/// decoding a gzip `Content-Encoding`, through `curl`, as git objects are
+/// read through `git`. A response that is not a 2xx, a timeout, a TLS
+/// failure and a body of more than `max_size` bytes are errors of their
+/// own, the last of kind `FileTooLarge`.
+#[cfg(feature = "http")]
+pub fn read<T>(
+    url: &str,
+    timeout: Option<Duration>,
+    max_size: Option<u64>,
+    consume: impl FnOnce(&mut dyn Read) -> io::Result<T>,
+) -> io::Result<T> {
+    let mut command = Command::new("curl");
+    command.args([
+        "--silent",
+        "--show-error",
+        "--fail",
+        "--location",
+        "--compressed",
+        "--proto",
+        "=http,https",
+        "--proto-redir",
+        "=http,https",
+        "--max-redirs",
+        &MAX_REDIRECTS.to_string(),
+    ]);
+    if let Some(timeout) = timeout {
+        command.args(["--max-time", &timeout.as_secs_f64().to_string()]);
+    }
+    if let Some(limit) = max_size {
+        command.args(["--max-filesize", &limit.to_string()]);
+    }
+    let mut child = command
+        .args(["--url", url])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| io::Error::other(format!("cannot run curl: {}", e)))?;
+    let result = match child.stdout.take() {
+        Some(stdout) => consume(&mut Capped {
+            reader: stdout,
+            left: max_size,
+        }),
+        None => Err(io::Error::other("curl has no output")),
+    };
+    // A body cut short leaves curl failing to write the rest.
+    if let Err(e) = &result {
+        if e.kind() == io::ErrorKind::FileTooLarge {
+            let _ = child.kill();
+        }
+    }
+    let output = child.wait_with_output()?;
+    match result {
+        Err(e) if e.kind() == io::ErrorKind::FileTooLarge => Err(e),
+        _ if !output.status.success() => Err(curl_error(
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
+            timeout,
+        )),
+        result => result,
+    }
+}
+
+#[cfg(not(feature = "http"))]
+pub fn read<T>(
+    _url: &str,
+    _timeout: Option<Duration>,
+    _max_size: Option<u64>,
+    _consume: impl FnOnce(&mut dyn Read) -> io::Result<T>,
+) -> io::Result<T> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "reading URLs needs the http cargo feature",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_url() {
+        assert!(is_url("https://example.com/run.sh"));
+        assert!(is_url("HTTP://example.com"));
+        assert!(!is_url("http://"));
+        assert!(!is_url("ftp://example.com/run.sh"));
+        assert!(!is_url("src/http.rs"));
+    }
+
+    #[test]
+    fn test_file_name() {
+        assert_eq!(
+            file_name("https://raw.example.com/o/r/main/bin/run.sh?token=1#L2"),
+            "run.sh"
+        );
+        assert_eq!(
+            file_name("http://example.com/release%20notes.md"),
+            "release notes.md"
+        );
+        assert_eq!(file_name("http://example.com/a%2"), "a%2");
+        assert_eq!(file_name("https://example.com/dir/"), "");
+        assert_eq!(file_name("https://example.com"), "");
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_capped() {
+        let mut body = Vec::new();
+        let capped = Capped {
+            reader: &b"0123456789"[..],
+            left: Some(10),
+        };
+        capped.take(64).read_to_end(&mut body).unwrap();
+        assert_eq!(body.len(), 10);
+        let mut capped = Capped {
+            reader: &b"0123456789"[..],
+            left: Some(9),
+        };
+        let error = capped.read_to_end(&mut body).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::FileTooLarge);
+    }
+
+    #[cfg(feature = "http")]
+    #[test]
+    fn test_curl_error() {
+        let error = curl_error(
+            Some(22),
+            "curl: (22) The requested URL returned error: 404\n",
+            None,
+        );
+        assert_eq!(error.to_string(), "The requested URL returned error: 404");
+        let timeout = Some(Duration::from_secs(5));
+        let error = curl_error(Some(28), "curl: (28) Operation timed out", timeout);
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(error.to_string(), "timed out after 5s (--timeout)");
+        let error = curl_error(Some(60), "curl: (60) SSL certificate problem", None);
+        assert_eq!(error.to_string(), "TLS error: SSL certificate problem");
+        assert_eq!(
+            curl_error(Some(63), "", None).kind(),
+            io::ErrorKind::FileTooLarge
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::glob::Glob;
+use crate::osname;
+
+#[derive(Debug)]
+struct Rule {
+    glob: Glob,
+    negated: bool,
+    dir_only: bool,
+}
+
+/// The rules of one `.gitignore` (or `.git/info/exclude`) file.
+#[derive(Debug)]
+pub struct IgnoreFile {
+    /// Walk path that the rules' relative paths hang off.
+    base: PathBuf,
+    /// Path of `base` relative to the directory holding the file, for rules
+    /// loaded from an ancestor of the walk root.
+    prefix: PathBuf,
+    rules: Vec<Rule>,
+}
+
+impl IgnoreFile {
+    pub fn parse(base: PathBuf, prefix: PathBuf, contents: &str) -> IgnoreFile {
+        let rules = contents.lines().filter_map(parse_rule).collect();
+        IgnoreFile {
+            base,
+            prefix,
+            rules,
+        }
+    }
+
+    fn load(path: &Path, base: PathBuf, prefix: PathBuf) -> Option<IgnoreFile> {
+        let contents = fs::read_to_string(osname::long(path)).ok()?;
+        Some(IgnoreFile::parse(base, prefix, &contents))
+    }
+
+    /// `Some(true)` if the last matching rule ignores `path`, `Some(false)` if
+    /// it re-includes it with `!`, `None` if no rule matches.
+    fn verdict(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = self.prefix.join(path.strip_prefix(&self.base).ok()?);
+        let relative = relative.to_string_lossy();
+        self.rules
+            .iter()
+            .rev()
+            .find(|rule| (is_dir || !rule.dir_only) && rule.glob.matches(&relative))
+            .map(|rule| !rule.negated)
+    }
+}
+
+fn parse_rule(line: &str) -> Option<Rule> {
+    let line = line.trim_end_matches([' ', '\r']);
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (negated, line) = match line.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, line.strip_prefix('\\').unwrap_or(line)),
+    };
+    let (dir_only, line) = match line.strip_suffix('/') {
+        Some(rest) => (true, rest),
+        None => (false, line),
+    };
+    // A pattern containing a slash is anchored to the file's directory;
+    // otherwise it matches a name at any depth.
+    let pattern = if line.contains('/') {
+        line.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{}", line)
+    };
+    let glob = Glob::new(&pattern).ok()?.matching_dotfiles();
+    Some(Rule {
+        glob,
+        negated,
+        dir_only,
+    })
+}
+
+/// The ignore files in effect for the directory currently being walked,
+/// outermost first so deeper files take precedence.
+#[derive(Debug, Default)]
+pub struct IgnoreStack {
+    files: Vec<IgnoreFile>,
+}
+
+impl IgnoreStack {
+    /// Loads `.git/info/exclude` and every `.gitignore` between the
+    /// repository root and `root` (exclusive; the walk loads `root`'s own).
+    pub fn for_root(root: &Path) -> IgnoreStack {
+        let mut stack = IgnoreStack::default();
+        let Ok(absolute) = fs::canonicalize(root) else {
+            return stack;
+        };
+        let Some(repo) = absolute.ancestors().find(|dir| dir.join(".git").exists()) else {
+            return stack;
+        };
+
+        let prefix_for = |dir: &Path| {
+            absolute
+                .strip_prefix(dir)
+                .unwrap_or(Path::new(""))
+                .to_path_buf()
+        };
+        if let Some(file) = IgnoreFile::load(
+            &repo.join(".git").join("info").join("exclude"),
+            root.to_path_buf(),
+            prefix_for(repo),
+        ) {
+            stack.files.push(file);
+        }
+        let mut ancestors: Vec<&Path> = absolute
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(repo))
+            .collect();
+        ancestors.reverse();
+        for dir in ancestors {
+            if let Some(file) =
+                IgnoreFile::load(&dir.join(".gitignore"), root.to_path_buf(), prefix_for(dir))
+            {
+                stack.files.push(file);
+            }
+        }
+        stack
+    }
+
+    /// Pushes `dir/.gitignore` if there is one, returning whether anything
+    /// was pushed so the caller can pop it again.
+    pub fn enter(&mut self, dir: &Path) -> bool {
+        match IgnoreFile::load(&dir.join(".gitignore"), dir.to_path_buf(), PathBuf::new()) {
+            Some(file) => {
+                self.files.push(file);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn leave(&mut self) {
+        self.files.pop();
+    }
+
+    pub fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        self.files
+            .iter()
+            .rev()
+            .find_map(|file| file.verdict(path, is_dir))
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stack(contents: &str) -> IgnoreStack {
+        IgnoreStack {
+            files: vec![IgnoreFile::parse(
+                PathBuf::from("root"),
+                PathBuf::new(),
+                contents,
+            )],
+        }
+    }
+
+    fn ignored(stack: &IgnoreStack, path: &str, is_dir: bool) -> bool {
+        stack.is_ignored(&Path::new("root").join(path), is_dir)
+    }
+
+    #[test]
+    fn test_unanchored_patterns_match_at_any_depth() {
+        let stack = stack("*.log\n# comment\n\ntarget/\n");
+        assert!(ignored(&stack, "app.log", false));
+        assert!(ignored(&stack, "deep/nested/app.log", false));
+        assert!(ignored(&stack, ".hidden.log", false));
+        assert!(ignored(&stack, "target", true));
+        assert!(ignored(&stack, "sub/target", true));
+        assert!(!ignored(&stack, "target", false));
+        assert!(!ignored(&stack, "main.rs", false));
+    }
+
+    #[test]
+    fn test_anchored_patterns() {
+        let stack = stack("/build\ndocs/*.html\n");
+        assert!(ignored(&stack, "build", true));
+        assert!(!ignored(&stack, "sub/build", true));
+        assert!(ignored(&stack, "docs/index.html", false));
+        assert!(!ignored(&stack, "docs/api/index.html", false));
+    }
+
+    #[test]
+    fn test_negation_last_match_wins() {
+        let stack = stack("*.txt\n!keep.txt\n\\!bang\n");
+        assert!(ignored(&stack, "drop.txt", false));
+        assert!(!ignored(&stack, "keep.txt", false));
+        assert!(ignored(&stack, "!bang", false));
+    }
+
+    #[test]
+    fn test_nested_file_overrides_outer() {
+        let mut stack = stack("*.txt\n");
+        stack.files.push(IgnoreFile::parse(
+            PathBuf::from("root/sub"),
+            PathBuf::new(),
+            "!special.txt\n",
+        ));
+        assert!(ignored(&stack, "special.txt", false));
+        assert!(!ignored(&stack, "sub/special.txt", false));
+        assert!(ignored(&stack, "sub/other.txt", false));
+    }
+
+    #[test]
+    fn test_ancestor_prefix() {
+        let stack = IgnoreStack {
+            files: vec![IgnoreFile::parse(
+                PathBuf::from("src"),
+                PathBuf::from("crate/src"),
+                "/crate/src/generated.rs\n",
+            )],
+        };
+        assert!(stack.is_ignored(Path::new("src/generated.rs"), false));
+        assert!(!stack.is_ignored(Path::new("src/main.rs"), false));
+    }
+}
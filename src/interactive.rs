@@ -0,0 +1,133 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+
+/// What `--interactive` was told about a file it would rewrite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Yes,
+    No,
+    /// Yes, and to every file after it without asking.
+    All,
+    /// No, and stop asking, leaving the files after it alone.
+    Quit,
+}
+
+/// What came of a file `--interactive` had a change for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Applied,
+    Declined,
+    /// Not looked at, because an earlier answer was to quit.
+    Unvisited,
+}
+
+impl Decision {
+    /// The name JSON gives it.
+    pub fn name(self) -> &'static str {
+        match self {
+            Decision::Applied => "applied",
+            Decision::Declined => "declined",
+            Decision::Unvisited => "unvisited",
+        }
+    }
+}
+
+/// The terminal the run was started from, which `--interactive` asks on
+/// whatever stdin and stdout are redirected to.
+pub struct Terminal {
+    input: BufReader<File>,
+    output: File,
+}
+
+impl Terminal {
+    /// The controlling terminal, or an error when there is none, as under
+    /// cron or in CI.
+    pub fn open() -> io::Result<Self> {
+        #[cfg(windows)]
+        let (input, output) = ("CONIN$", "CONOUT$");
+        #[cfg(not(windows))]
+        let (input, output) = ("/dev/tty", "/dev/tty");
+        Ok(Terminal {
+            input: BufReader::new(File::open(input)?),
+            output: OpenOptions::new().write(true).open(output)?,
+        })
+    }
+
+    /// Shows `preview` and asks `question` until it gets an answer.
+    pub fn ask(&mut self, preview: &str, question: &str) -> io::Result<Answer> {
+        writeln!(self.output, "{}", preview)?;
+        ask(&mut self.input, &mut self.output, question)
+    }
+}
+
+/// Asks `question` on `output` until a line of `input` answers it; the end
+/// of the input quits.
+pub fn ask(
+    input: &mut impl BufRead,
+    output: &mut impl Write,
+    question: &str,
+) -> io::Result<Answer> {
+    loop {
+        write!(output, "{} [y]es / [n]o / [a]ll / [q]uit ", question)?;
+        output.flush()?;
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            writeln!(output)?;
+            return Ok(Answer::Quit);
+        }
+        match line.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => return Ok(Answer::Yes),
+            "n" | "no" => return Ok(Answer::No),
+            "a" | "all" => return Ok(Answer::All),
+            "q" | "quit" => return Ok(Answer::Quit),
+            _ => writeln!(output, "Please answer y, n, a or q.")?,
+        }
+    }
+}
+
+/// "2 applied, 1 declined, 3 not visited", of the files `--interactive`
+/// had a change for.
+pub fn summary(decisions: impl IntoIterator<Item = Decision>) -> String {
+    let mut counts = [0; 3];
+    for decision in decisions {
+        counts[decision as usize] += 1;
+    }
+    format!(
+        "{} applied, {} declined, {} not visited",
+        counts[0], counts[1], counts[2]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answer(typed: &str) -> (Answer, String) {
+        let mut output = Vec::new();
+        let answer = ask(&mut typed.as_bytes(), &mut output, "Rewrite a.txt?").unwrap();
+        (answer, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_ask() {
+        assert_eq!(answer("y\n").0, Answer::Yes);
+        assert_eq!(answer("No\n").0, Answer::No);
+        assert_eq!(answer(" a \r\n").0, Answer::All);
+        assert_eq!(answer("q\n").0, Answer::Quit);
+        let (answer_given, shown) = answer("maybe\n\nyes\n");
+        assert_eq!(answer_given, Answer::Yes);
+        assert_eq!(shown.matches("Please answer y, n, a or q.").count(), 2);
+        assert!(shown.starts_with("Rewrite a.txt? [y]es / [n]o / [a]ll / [q]uit "));
+        // Ctrl-D, or input that runs out, quits.
+        assert_eq!(answer("").0, Answer::Quit);
+    }
+
+    #[test]
+    fn test_summary() {
+        use Decision::*;
+        assert_eq!(
+            summary([Applied, Declined, Applied, Unvisited]),
+            "2 applied, 1 declined, 1 not visited"
+        );
+    }
+}
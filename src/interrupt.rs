@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by Ctrl-C once `catch` is in place.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Whether Ctrl-C was pressed since `catch`.
+pub fn requested() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Makes Ctrl-C set a flag instead of killing the process, for the modes
+/// that run until interrupted and then end with exit status 0.
+#[cfg(unix)]
+pub fn catch() {
+    const SIGINT: i32 = 2;
+    extern "C" fn handle(_signal: i32) {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+    }
+    extern "C" {
+        fn signal(signal: i32, handler: extern "C" fn(i32)) -> usize;
+    }
+    // SAFETY: the handler only stores to an atomic, which is safe to do in
+    // a signal handler.
+    unsafe {
+        signal(SIGINT, handle);
+    }
+}
+
+#[cfg(windows)]
+pub fn catch() {
+    extern "system" fn handle(_event: u32) -> i32 {
+        INTERRUPTED.store(true, Ordering::SeqCst);
+        1
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetConsoleCtrlHandler(handler: extern "system" fn(u32) -> i32, add: i32) -> i32;
+    }
+    // SAFETY: the handler only stores to an atomic and runs on a thread of
+    // its own.
+    unsafe {
+        SetConsoleCtrlHandler(handle, 1);
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub fn catch() {}
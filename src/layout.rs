@@ -0,0 +1,212 @@
+#[cfg(feature = "cli")]
+use std::str::FromStr;
+
+/// A part of the text report of a file: a line, or the few lines that go
+/// together, shown only when the file has what it is about. A part whose
+/// wording changes becomes a new part, which only the layouts after the
+/// change list, so that a pinned layout never changes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Binary,
+    Generated,
+    FileName,
+    Window,
+    Head,
+    Extension,
+    Bom,
+    DetectedEncoding,
+    Shebang,
+    DetectedType,
+    TotalLines,
+    EmptyLines,
+    EmptyLinesAt,
+    CommentLines,
+    Size,
+    ChangedDuringScan,
+    CompressedSize,
+    Sha256,
+    TrailingBlankLines,
+    BlankRuns,
+    LineLengths,
+    LongestLine,
+    TopLines,
+    Peek,
+    LongLines,
+    Patterns,
+    Indentation,
+    MixedIndent,
+    LineEnding,
+    EndingCounts,
+    EndingOffsets,
+    MinorityEndings,
+    Transitions,
+    FinalNewline,
+    UnicodeBreaks,
+    Pages,
+    Utf8,
+    NulBytes,
+    ControlChars,
+    EmbeddedCr,
+    PhantomBlankLines,
+    NonAscii,
+    TrailingWhitespace,
+    Warnings,
+    Violations,
+    Histogram,
+    Markdown,
+    Duplicates,
+    Metadata,
+}
+
+/// The text report as of version 1 of `--stable-output`, which scripts
+/// that read it can count on: these parts, in this order, worded so.
+const V1: &[Field] = &[
+    Field::Title,
+    Field::Binary,
+    Field::Generated,
+    Field::FileName,
+    Field::Window,
+    Field::Head,
+    Field::Extension,
+    Field::Bom,
+    Field::DetectedEncoding,
+    Field::Shebang,
+    Field::TotalLines,
+    Field::EmptyLines,
+    Field::EmptyLinesAt,
+    Field::CommentLines,
+    Field::Size,
+    Field::CompressedSize,
+    Field::Sha256,
+    Field::TrailingBlankLines,
+    Field::BlankRuns,
+    Field::LineLengths,
+    Field::LongestLine,
+    Field::TopLines,
+    Field::Peek,
+    Field::LongLines,
+    Field::Patterns,
+    Field::Indentation,
+    Field::MixedIndent,
+    Field::LineEnding,
+    Field::EndingCounts,
+    Field::EndingOffsets,
+    Field::MinorityEndings,
+    Field::Transitions,
+    Field::FinalNewline,
+    Field::UnicodeBreaks,
+    Field::Pages,
+    Field::Utf8,
+    Field::NulBytes,
+    Field::ControlChars,
+    Field::EmbeddedCr,
+    Field::PhantomBlankLines,
+    Field::NonAscii,
+    Field::TrailingWhitespace,
+    Field::Warnings,
+    Field::Violations,
+    Field::Histogram,
+    Field::Markdown,
+    Field::Duplicates,
+    Field::Metadata,
+];
+
+/// The text report without `--stable-output`, free to gain, move and
+/// reword parts from one release to the next.
+const CURRENT: &[Field] = &[
+    Field::Title,
+    Field::Binary,
+    Field::Generated,
+    Field::FileName,
+    Field::Window,
+    Field::Head,
+    Field::Extension,
+    Field::Bom,
+    Field::DetectedEncoding,
+    Field::Shebang,
+    Field::DetectedType,
+    Field::TotalLines,
+    Field::EmptyLines,
+    Field::EmptyLinesAt,
+    Field::CommentLines,
+    Field::Size,
+    Field::ChangedDuringScan,
+    Field::CompressedSize,
+    Field::Sha256,
+    Field::TrailingBlankLines,
+    Field::BlankRuns,
+    Field::LineLengths,
+    Field::LongestLine,
+    Field::TopLines,
+    Field::Peek,
+    Field::LongLines,
+    Field::Patterns,
+    Field::Indentation,
+    Field::MixedIndent,
+    Field::LineEnding,
+    Field::EndingCounts,
+    Field::EndingOffsets,
+    Field::MinorityEndings,
+    Field::Transitions,
+    Field::FinalNewline,
+    Field::UnicodeBreaks,
+    Field::Pages,
+    Field::Utf8,
+    Field::NulBytes,
+    Field::ControlChars,
+    Field::EmbeddedCr,
+    Field::PhantomBlankLines,
+    Field::NonAscii,
+    Field::TrailingWhitespace,
+    Field::Warnings,
+    Field::Violations,
+    Field::Histogram,
+    Field::Markdown,
+    Field::Duplicates,
+    Field::Metadata,
+];
+
+/// Which parts the text report of a file has, and in which order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Layout {
+    /// Whatever this release writes.
+    #[default]
+    Current,
+    /// `--stable-output v1`.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    V1,
+}
+
+/// The versions `--stable-output` takes, oldest first.
+#[cfg(feature = "cli")]
+const VERSIONS: &[(&str, Layout)] = &[("v1", Layout::V1)];
+
+impl Layout {
+    pub fn fields(self) -> &'static [Field] {
+        match self {
+            Layout::Current => CURRENT,
+            Layout::V1 => V1,
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        VERSIONS
+            .iter()
+            .find(|&&(name, _)| name == s)
+            .map(|&(_, layout)| layout)
+            .ok_or_else(|| {
+                let names: Vec<&str> = VERSIONS.iter().map(|&(name, _)| name).collect();
+                format!(
+                    "Unknown --stable-output version '{}'; supported versions are: {}",
+                    s,
+                    names.join(", ")
+                )
+            })
+    }
+}
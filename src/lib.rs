@@ -0,0 +1,7798 @@
+//! Line ending and whitespace analysis of text files, as the `mdlt`
+//! command reports it.
+//!
+//! [`analyze_file`] and [`analyze_reader`] scan a file, or any reader, into
+//! [`FileStats`], whose getters hold the counts and whose
+//! [`write_report`](FileStats::write_report) writes the text report `mdlt`
+//! prints. [`run`] is the whole command, from its arguments to its exit
+//! status.
+//!
+//! Without the default `cli` feature, only [`analyze_bytes`],
+//! [`analyze_reader`] and the types they return are built, with nothing
+//! that reads files by path, so that the crate builds for
+//! `wasm32-unknown-unknown`.
+#![warn(missing_docs)]
+
+use std::borrow::Cow;
+#[cfg(feature = "cli")]
+use std::collections::{BTreeSet, HashMap, HashSet};
+#[cfg(feature = "cli")]
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::fmt;
+#[cfg(feature = "cli")]
+use std::fs::{self, File};
+#[cfg(feature = "cli")]
+use std::io::IsTerminal;
+use std::io::{self, Read, Write};
+use std::path::Path;
+#[cfg(feature = "cli")]
+use std::path::PathBuf;
+#[cfg(feature = "cli")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "cli")]
+use std::time::Instant;
+
+#[cfg(feature = "cli")]
+mod aggregate;
+#[cfg(feature = "cli")]
+mod archive;
+#[cfg(feature = "cli")]
+mod atomic;
+#[cfg(feature = "cli")]
+mod baseline;
+mod binary;
+mod blank;
+mod bom;
+#[cfg(feature = "cli")]
+mod budget;
+#[cfg(feature = "cli")]
+mod cache;
+mod charset;
+mod checks;
+#[cfg(feature = "cli")]
+mod cli;
+mod color;
+mod comment;
+#[cfg(feature = "cli")]
+mod compare;
+#[cfg(feature = "cli")]
+mod completions;
+#[cfg(feature = "cli")]
+mod config;
+#[cfg(feature = "cli")]
+mod convert;
+#[cfg(feature = "cli")]
+mod diff;
+mod duplicates;
+#[cfg(feature = "cli")]
+mod editorconfig;
+mod encoding;
+#[cfg(feature = "cli")]
+mod external;
+#[cfg(feature = "ffi")]
+mod ffi;
+mod figures;
+mod filetype;
+#[cfg(feature = "cli")]
+mod filter;
+#[cfg(feature = "cli")]
+mod follow;
+mod format;
+mod generated;
+#[cfg(feature = "cli")]
+mod git;
+#[cfg(feature = "cli")]
+mod gitattributes;
+#[cfg(feature = "cli")]
+mod glob;
+#[cfg(feature = "cli")]
+mod gzip;
+mod histogram;
+#[cfg(feature = "cli")]
+mod http;
+#[cfg(feature = "cli")]
+mod ignore;
+#[cfg(feature = "cli")]
+mod interactive;
+#[cfg(feature = "cli")]
+mod interrupt;
+mod layout;
+#[cfg(feature = "cli")]
+mod lines;
+mod locate;
+mod markdown;
+mod metadata;
+#[cfg(feature = "cli")]
+mod mmap;
+mod osname;
+mod pages;
+#[cfg(feature = "cli")]
+mod parallel;
+mod pattern;
+mod peek;
+mod preview;
+#[cfg(feature = "cli")]
+mod progress;
+#[cfg(feature = "serde")]
+mod record;
+// Patterns are only compiled from the command line.
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+mod regex;
+#[cfg(feature = "cli")]
+mod relative;
+#[cfg(feature = "cli")]
+mod sample;
+mod scanner;
+#[cfg(feature = "cli")]
+mod serve;
+mod sha256;
+mod shebang;
+#[cfg(feature = "cli")]
+mod sink;
+#[cfg(feature = "cli")]
+mod split;
+mod suppress;
+mod timing;
+mod top;
+mod transitions;
+#[cfg(feature = "cli")]
+mod tree;
+mod utf8;
+#[cfg(feature = "cli")]
+mod walk;
+#[cfg(feature = "cli")]
+mod watch;
+mod wc;
+mod window;
+
+#[cfg(feature = "cli")]
+use aggregate::{AggregateStats, Groups};
+#[cfg(feature = "cli")]
+use atomic::{Backup, InPlace};
+#[cfg(feature = "cli")]
+use baseline::Baseline;
+use blank::BlankRuns;
+use bom::Bom;
+#[cfg(feature = "cli")]
+use cache::Cache;
+use charset::Guess;
+use checks::Violation;
+#[cfg(feature = "cli")]
+use cli::{Changed, Conversion, ExpectedEndings, Fix, ListPredicate, Sort, SortKey, TreatBinary};
+#[cfg(feature = "cli")]
+use config::Config;
+use duplicates::DuplicateStats;
+#[cfg(feature = "cli")]
+use editorconfig::{EditorConfig, IndentStyle, Properties};
+#[cfg(feature = "cli")]
+use encoding::Encoding;
+#[cfg(feature = "cli")]
+use format::OutputFormat;
+use format::ReportOptions;
+use generated::Generated;
+#[cfg(feature = "cli")]
+use gitattributes::{GitAttributes, Verdict};
+use histogram::Histogram;
+#[cfg(feature = "cli")]
+use interactive::{Answer, Decision, Terminal};
+use layout::Field;
+use locate::MinorityLines;
+use markdown::MarkdownStats;
+use metadata::FileMeta;
+#[cfg(feature = "cli")]
+use mmap::MmapMode;
+use pages::Pages;
+use pattern::PatternCount;
+use peek::Peek;
+#[cfg(feature = "cli")]
+use progress::Progress;
+#[cfg(feature = "cli")]
+use relative::ShownPaths;
+#[cfg(feature = "cli")]
+use sample::{Sampled, Sampler};
+use scanner::{ScanOptions, Scanner, Tolerance};
+use suppress::Suppression;
+#[cfg(feature = "cli")]
+use timing::Timing;
+use top::TopLine;
+use transitions::Segment;
+#[cfg(feature = "cli")]
+use tree::Tree;
+use utf8::Utf8Report;
+use window::{Head, Window};
+
+/// Counts of the breaks recognized by `--unicode-linebreaks`.
+#[derive(Debug, Default)]
+struct UnicodeBreaks {
+    /// Next line, U+0085.
+    nel: usize,
+    /// Line separator, U+2028.
+    ls: usize,
+    /// Paragraph separator, U+2029.
+    ps: usize,
+    /// Vertical tab, U+000B.
+    vt: usize,
+    /// Form feed, U+000C.
+    ff: usize,
+}
+
+impl UnicodeBreaks {
+    fn total(&self) -> usize {
+        self.nel + self.ls + self.ps + self.vt + self.ff
+    }
+}
+
+/// How the lines with text are indented.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct Indentation {
+    tabs: usize,
+    spaces: usize,
+    /// Lines indented with both tabs and spaces.
+    mixed: usize,
+    none: usize,
+    /// The most likely indent width of the space-indented lines.
+    width: Option<usize>,
+}
+
+impl Indentation {
+    /// Whether both styles occur, on separate lines or the same one.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    fn is_mixed(&self) -> bool {
+        self.mixed > 0 || (self.tabs > 0 && self.spaces > 0)
+    }
+
+    /// Describes the styles in use, most common first, e.g. "spaces (312
+    /// lines, width 4), tabs (2 lines)", the counts grouped when `group`.
+    fn summary(&self, group: bool) -> String {
+        let width = self.width.map(|width| format!(", width {}", width));
+        let mut styles = [
+            ("spaces", self.spaces, width.unwrap_or_default()),
+            ("tabs", self.tabs, String::new()),
+            ("mixed", self.mixed, String::new()),
+        ];
+        // A stable sort keeps spaces ahead of tabs on a tie.
+        styles.sort_by_key(|&(_, lines, _)| std::cmp::Reverse(lines));
+        let used: Vec<String> = styles
+            .iter()
+            .filter(|(_, lines, _)| *lines > 0)
+            .map(|(name, lines, extra)| {
+                format!(
+                    "{} ({}{})",
+                    name,
+                    figures::counted(*lines as u64, "line", group),
+                    extra
+                )
+            })
+            .collect();
+        if used.is_empty() {
+            return "none".to_string();
+        }
+        used.join(", ")
+    }
+}
+
+/// The lines reported by `--check mixed-indent`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct MixedIndentLines {
+    count: usize,
+    /// The first few of their line numbers.
+    first: Vec<usize>,
+}
+
+/// The lines longer than `--max-line-length`.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct LongLines {
+    limit: usize,
+    count: usize,
+    /// The first few of their line numbers.
+    first: Vec<usize>,
+}
+
+/// What was found in one file. The fields are the analysis's own; the
+/// getters are what is kept stable for other programs. With the `serde`
+/// feature it serializes as the file objects of `--format json`.
+#[derive(Debug)]
+pub struct FileStats {
+    total_lines: usize,
+    unix_endings: usize,
+    dos_endings: usize,
+    /// Lone `\r` terminators, as used by classic Mac OS.
+    mac_endings: usize,
+    /// Byte offsets of the first LF, CRLF and CR endings; for CRLF this is
+    /// the offset of the `\r`.
+    first_lf_offset: Option<u64>,
+    first_crlf_offset: Option<u64>,
+    first_cr_offset: Option<u64>,
+    /// How many endings other than the most common one make a file mixed.
+    tolerance: Tolerance,
+    /// Lines with no content at all.
+    empty_lines: usize,
+    /// The first of the empty lines, present only with `--show-empty`.
+    empty_lines_at: Option<Vec<usize>>,
+    /// Lines of only spaces and tabs, which `--blank-as-empty` counts as
+    /// empty instead.
+    blank_lines: usize,
+    /// Lines holding only comments, and lines with text outside comments;
+    /// `None` unless the extension has a known comment syntax.
+    comment_lines: Option<usize>,
+    code_lines: Option<usize>,
+    /// Present only when `--unicode-linebreaks` is in effect.
+    unicode_breaks: Option<UnicodeBreaks>,
+    /// Present only when there is a form feed.
+    pages: Option<Pages>,
+    bom: Option<Bom>,
+    /// What `--detect-encoding` made of a file without a byte-order mark.
+    detected_encoding: Option<Guess>,
+    /// What follows `#!` on the first line, and the language of the
+    /// interpreter it names when that is a known one.
+    shebang: Option<String>,
+    detected_language: Option<&'static str>,
+    /// What a file without an extension was taken for, by its name, its
+    /// shebang, or `text/unknown` for a text file neither tells.
+    detected_type: Option<String>,
+    /// Present only when `--check-encoding` is in effect and the file was
+    /// scanned as UTF-8.
+    utf8: Option<Utf8Report>,
+    /// Problems found while decoding, such as a truncated UTF-16 code unit.
+    warnings: Vec<String>,
+    /// What the checks turned on found, filled in once the run has all its
+    /// files.
+    violations: Vec<Violation>,
+    /// The `mdlt:ignore-line` and `mdlt:ignore-file` markers in the file.
+    suppressions: Vec<Suppression>,
+    /// The start of the file looks like binary data rather than text.
+    is_binary: bool,
+    /// Why the file looks generated or minified, if it does.
+    generated: Option<Generated>,
+    /// Size of the file, BOM included; for a file decompressed from gzip,
+    /// the size of what it decompresses to.
+    total_bytes: u64,
+    /// Size on disk of a file decompressed from gzip, present only then.
+    compressed_bytes: Option<u64>,
+    /// The part of the file counted, with `--lines` or `--bytes`; all
+    /// the counts, offsets and line numbers are of that part.
+    window: Option<Window>,
+    /// The start of the file counted, with `--head-lines` or
+    /// `--head-bytes`, and whether the file went on past it, in which case
+    /// the counts are of the head alone.
+    head: Option<Head>,
+    truncated: bool,
+    /// The file was larger once read than when it was opened, and only the
+    /// bytes it had then were counted when it was streamed.
+    grew_during_scan: bool,
+    /// The file ended before its size when opened, or was smaller once
+    /// read, and the counts are of what was read before it shrank.
+    truncated_during_scan: bool,
+    /// SHA-256 of the contents in lowercase hex, present only with `--hash`.
+    sha256: Option<String>,
+    /// Characters after the BOM, terminators included, as `wc -m` counts
+    /// them; each byte of invalid UTF-8 counts as one.
+    total_chars: u64,
+    /// Runs of non-whitespace, as `wc -w` counts them.
+    total_words: u64,
+    nul_bytes: usize,
+    first_nul_line: Option<usize>,
+    /// C0 control characters other than tab, LF and CR (and form feed with
+    /// `--allow-form-feed`).
+    control_chars: usize,
+    /// Characters above U+007F, or bytes of 0x80 and above when the file is
+    /// not valid UTF-8; the BOM is not counted. The column of the first one
+    /// is in characters or bytes accordingly.
+    non_ascii_chars: usize,
+    first_non_ascii_line: Option<usize>,
+    first_non_ascii_column: Option<usize>,
+    first_control_line: Option<usize>,
+    /// Lone CRs inside lines of text that ends in LF or CRLF, as in
+    /// `foo\rbar\n`, with the line and column of the first few. They count
+    /// as classic Mac endings as well, since a CR can only be told apart
+    /// from one once an LF has followed it.
+    embedded_crs: usize,
+    embedded_cr_at: Vec<(usize, usize)>,
+    /// Empty lines ending in CRLF right after a line ending in LF, the
+    /// `\n\r\n` of half-converted text, and the first few of their
+    /// numbers. They count as empty lines and CRLF endings as well.
+    phantom_blank_lines: usize,
+    phantom_blank_at: Vec<usize>,
+    /// Lines ending in spaces or tabs, and the first few of their numbers.
+    trailing_whitespace_lines: usize,
+    trailing_whitespace_at: Vec<usize>,
+    /// Line lengths in display columns with tabs expanded to
+    /// `--tab-width`, not counting terminators; all zero for a file with no
+    /// lines.
+    min_line_length: usize,
+    max_line_length: usize,
+    total_line_length: u64,
+    /// The longest line in bytes (UTF-16 code units for UTF-16 input).
+    max_line_bytes: usize,
+    /// The 1-based number of the first line of `max_line_length`, with an
+    /// escaped and truncated copy of its start.
+    longest_line: Option<usize>,
+    longest_line_preview: String,
+    /// The longest lines, longest first, present only with `--top-lines`.
+    top_lines: Option<Vec<TopLine>>,
+    /// Present only with `--peek`.
+    peek: Option<Peek>,
+    /// Present only with `--histogram`.
+    histogram: Option<Histogram>,
+    /// Present for Markdown files, or for any file with `--lang markdown`.
+    markdown: Option<MarkdownStats>,
+    /// Present only with `--duplicates`.
+    duplicates: Option<DuplicateStats>,
+    /// One for each `--count-pattern`, in the order given.
+    patterns: Vec<PatternCount>,
+    /// Runs of lines with the same ending, present only with `--transitions`.
+    transitions: Option<Vec<Segment>>,
+    /// Present only with `--metadata`.
+    metadata: Option<FileMeta>,
+    /// Lines not using the most common ending, present only with `--locate`
+    /// and empty unless the endings are mixed.
+    minority_endings: Option<Vec<MinorityLines>>,
+    /// Present only with `--max-line-length`.
+    long_lines: Option<LongLines>,
+    indentation: Indentation,
+    /// Present only with `--check mixed-indent`.
+    mixed_indent: Option<MixedIndentLines>,
+    /// Empty or whitespace-only lines after the last line with text.
+    trailing_blank_lines: usize,
+    /// Present only when there is an empty line.
+    blank_runs: Option<BlankRuns>,
+    /// Whether the last line is terminated; `None` for a file with no lines.
+    final_newline: Option<bool>,
+    file_extension: Option<String>,
+    file_name: OsString,
+}
+
+impl FileStats {
+    fn new(file_name: impl Into<OsString>) -> Self {
+        let file_name = file_name.into();
+        FileStats {
+            total_lines: 0,
+            unix_endings: 0,
+            dos_endings: 0,
+            mac_endings: 0,
+            first_lf_offset: None,
+            first_crlf_offset: None,
+            first_cr_offset: None,
+            tolerance: Tolerance::default(),
+            empty_lines: 0,
+            empty_lines_at: None,
+            blank_lines: 0,
+            comment_lines: None,
+            code_lines: None,
+            unicode_breaks: None,
+            pages: None,
+            bom: None,
+            detected_encoding: None,
+            shebang: None,
+            detected_language: None,
+            detected_type: None,
+            utf8: None,
+            warnings: Vec::new(),
+            violations: Vec::new(),
+            suppressions: Vec::new(),
+            is_binary: false,
+            generated: None,
+            total_bytes: 0,
+            compressed_bytes: None,
+            window: None,
+            head: None,
+            truncated: false,
+            grew_during_scan: false,
+            truncated_during_scan: false,
+            sha256: None,
+            total_chars: 0,
+            total_words: 0,
+            nul_bytes: 0,
+            first_nul_line: None,
+            control_chars: 0,
+            non_ascii_chars: 0,
+            first_non_ascii_line: None,
+            first_non_ascii_column: None,
+            first_control_line: None,
+            embedded_crs: 0,
+            embedded_cr_at: Vec::new(),
+            phantom_blank_lines: 0,
+            phantom_blank_at: Vec::new(),
+            trailing_whitespace_lines: 0,
+            trailing_whitespace_at: Vec::new(),
+            min_line_length: 0,
+            max_line_length: 0,
+            total_line_length: 0,
+            max_line_bytes: 0,
+            longest_line: None,
+            longest_line_preview: String::new(),
+            top_lines: None,
+            peek: None,
+            histogram: None,
+            markdown: None,
+            duplicates: None,
+            patterns: Vec::new(),
+            transitions: None,
+            minority_endings: None,
+            metadata: None,
+            long_lines: None,
+            indentation: Indentation::default(),
+            mixed_indent: None,
+            trailing_blank_lines: 0,
+            blank_runs: None,
+            final_newline: None,
+            file_extension: Path::new(&file_name)
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .map(String::from),
+            file_name,
+        }
+    }
+
+    /// The path or name the file was analyzed by, as the system gave it,
+    /// which need not be valid UTF-8.
+    pub fn file_name(&self) -> &OsStr {
+        &self.file_name
+    }
+
+    /// The path the file was analyzed by, byte for byte.
+    pub fn path(&self) -> &Path {
+        Path::new(&self.file_name)
+    }
+
+    /// The name to show people, with `�` for what is not UTF-8, as
+    /// `Path::display` shows it.
+    pub fn display_name(&self) -> Cow<'_, str> {
+        self.file_name.to_string_lossy()
+    }
+
+    /// The name as a run carries it, for the reports to escape and the
+    /// lookups by name.
+    fn name(&self) -> Cow<'_, str> {
+        osname::encode(&self.file_name)
+    }
+
+    /// Names the file by `name`, as a run carries it.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    fn set_name(&mut self, name: &str) {
+        self.file_name = osname::decode(name).into_owned();
+    }
+
+    /// Rewrites the name, as a run carries it, with `rename`.
+    #[cfg(feature = "cli")]
+    fn rename(&mut self, rename: impl FnOnce(&mut String)) {
+        let mut name = self.name().into_owned();
+        rename(&mut name);
+        self.set_name(&name);
+    }
+
+    /// The extension of the name, without the dot.
+    pub fn file_extension(&self) -> Option<&str> {
+        self.file_extension.as_deref()
+    }
+
+    /// What a file without an extension was taken for, such as `makefile`
+    /// or `python`.
+    pub fn detected_type(&self) -> Option<&str> {
+        self.detected_type.as_deref()
+    }
+
+    /// The extension, or the detected type of a file without one, which
+    /// the rules and groups by extension go by.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    fn extension_or_type(&self) -> Option<&str> {
+        self.file_extension().or(self.detected_type())
+    }
+
+    /// All lines, the last one counted whether it is terminated or not.
+    pub fn total_lines(&self) -> usize {
+        self.total_lines
+    }
+
+    /// Lines with no content at all.
+    pub fn empty_lines(&self) -> usize {
+        self.empty_lines
+    }
+
+    /// Lines of only spaces and tabs.
+    pub fn blank_lines(&self) -> usize {
+        self.blank_lines
+    }
+
+    /// Lines ending in CRLF.
+    pub fn dos_endings(&self) -> usize {
+        self.dos_endings
+    }
+
+    /// Lines ending in LF alone.
+    pub fn unix_endings(&self) -> usize {
+        self.unix_endings
+    }
+
+    /// Lines ending in CR alone.
+    pub fn mac_endings(&self) -> usize {
+        self.mac_endings
+    }
+
+    /// Size of the file in bytes, BOM included.
+    pub fn total_bytes(&self) -> u64 {
+        self.total_bytes
+    }
+
+    /// Size on disk of a file decompressed from gzip, whose other counts
+    /// are of what it decompresses to; `None` for other files.
+    pub fn compressed_bytes(&self) -> Option<u64> {
+        self.compressed_bytes
+    }
+
+    /// Characters after the BOM, as `wc -m` counts them.
+    pub fn total_chars(&self) -> u64 {
+        self.total_chars
+    }
+
+    /// Runs of non-whitespace, as `wc -w` counts them.
+    pub fn total_words(&self) -> u64 {
+        self.total_words
+    }
+
+    /// Lines ending in spaces or tabs.
+    pub fn trailing_whitespace_lines(&self) -> usize {
+        self.trailing_whitespace_lines
+    }
+
+    /// The longest line in display columns, tabs expanded to 8.
+    pub fn max_line_length(&self) -> usize {
+        self.max_line_length
+    }
+
+    /// Whether the last line is terminated; `None` for a file with no
+    /// lines, or one only the head of which was analyzed.
+    pub fn final_newline(&self) -> Option<bool> {
+        self.final_newline
+    }
+
+    /// Whether `--head-lines` or `--head-bytes` stopped before the end of
+    /// the file, so that the counts are of its start alone.
+    pub fn truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Whether the file grew while it was being read, as a log being
+    /// written to does.
+    pub fn grew_during_scan(&self) -> bool {
+        self.grew_during_scan
+    }
+
+    /// Whether the file shrank while it was being read, as a log being
+    /// rotated does, so that the counts are of part of it.
+    pub fn truncated_during_scan(&self) -> bool {
+        self.truncated_during_scan
+    }
+
+    /// Notes how a regular file that was `len` bytes when opened changed
+    /// by the time it was read, by its size `now` then; a size that cannot
+    /// be had tells nothing.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    fn note_size(&mut self, len: u64, now: io::Result<u64>) {
+        if let Ok(now) = now {
+            self.grew_during_scan |= now > len;
+            self.truncated_during_scan |= now < len;
+        }
+    }
+
+    /// Whether the start of the file looks like binary data rather than
+    /// text.
+    pub fn is_binary(&self) -> bool {
+        self.is_binary
+    }
+
+    /// Whether the file looks generated or minified, by a marker such as
+    /// `@generated` near its start or end or by the length of its lines.
+    pub fn is_generated(&self) -> bool {
+        self.generated.is_some()
+    }
+
+    /// The ending that occurs most often. A tie between the most common
+    /// endings is reported as mixed. Whether the file counts as mixed is
+    /// [`FileStats::is_mixed`], which the reports go by.
+    pub fn line_ending(&self) -> LineEndingKind {
+        let counts = [
+            (self.dos_endings, LineEndingKind::Crlf),
+            (self.unix_endings, LineEndingKind::Lf),
+            (self.mac_endings, LineEndingKind::Cr),
+        ];
+        let most = counts.iter().map(|&(count, _)| count).max().unwrap_or(0);
+        let mut leaders = counts.iter().filter(|&&(count, _)| count == most);
+        let unicode = self.unicode_breaks.as_ref().map_or(0, UnicodeBreaks::total);
+        match (most, leaders.next(), leaders.next()) {
+            (0, _, _) if unicode > 0 => LineEndingKind::UnicodeOnly,
+            (0, _, _) => LineEndingKind::None,
+            (_, Some(&(_, kind)), None) => kind,
+            _ => LineEndingKind::Mixed,
+        }
+    }
+
+    /// "first 1,000 lines (the whole file)", or what was examined of a
+    /// truncated file and which checks of its end were left out, its
+    /// counts grouped when `group`.
+    fn head_summary(&self, head: Head, group: bool) -> String {
+        let limit = match head {
+            Head::Lines(count) => format!("first {}", figures::counted(count, "line", group)),
+            Head::Bytes(count) => format!("first {}", figures::counted(count, "byte", group)),
+        };
+        match self.truncated {
+            true => format!(
+                "{} (truncated; {} and {} examined, final newline and trailing blank lines not checked)",
+                limit,
+                figures::counted(self.total_lines as u64, "line", group),
+                figures::counted(self.total_bytes, "byte", group)
+            ),
+            false => format!("{} (the whole file)", limit),
+        }
+    }
+
+    /// "10,432 bytes, 10,000 characters, 1,500 words", or "10432 bytes, ..."
+    /// when not to `group` the digits.
+    fn size_summary(&self, group: bool) -> String {
+        format!(
+            "{}, {}, {}",
+            figures::counted(self.total_bytes, "byte", group),
+            figures::counted(self.total_chars, "character", group),
+            figures::counted(self.total_words, "word", group)
+        )
+    }
+
+    /// The size of the file as stored: what `--metadata` found, or else
+    /// the bytes before decompressing them, or those read.
+    #[cfg(feature = "cli")]
+    fn size_bytes(&self) -> u64 {
+        let stored = self.metadata.as_ref().and_then(|metadata| metadata.size);
+        stored.or(self.compressed_bytes).unwrap_or(self.total_bytes)
+    }
+
+    fn mean_line_length(&self) -> f64 {
+        if self.total_lines == 0 {
+            return 0.0;
+        }
+        self.total_line_length as f64 / self.total_lines as f64
+    }
+
+    /// Whether more of the line endings differ from the most common one
+    /// than the tolerance allows; by default, whether more than one kind
+    /// occurs at all.
+    pub fn is_mixed(&self) -> bool {
+        let counts = self.ending_counts();
+        let total: usize = counts.iter().sum();
+        let most = counts.iter().copied().max().unwrap_or(0);
+        self.tolerance.exceeded(total - most, total)
+    }
+
+    /// The line ending type as every report names it: that of
+    /// [`FileStats::line_ending`], after "Mostly " for a file that is mixed
+    /// all the same, such as "Mostly Unix/Linux (LF)".
+    pub fn line_ending_type(&self) -> String {
+        let kind = self.line_ending();
+        match self.is_mixed() && kind != LineEndingKind::Mixed {
+            true => format!("Mostly {}", kind),
+            false => kind.to_string(),
+        }
+    }
+
+    /// The line ending type in one lowercase word, as JSON gives it: that
+    /// of [`FileStats::line_ending`], or `mixed` for a file that is mixed.
+    pub fn line_ending_name(&self) -> &'static str {
+        match self.is_mixed() {
+            true => LineEndingKind::Mixed.name(),
+            false => self.line_ending().name(),
+        }
+    }
+
+    /// CRLF, LF and CR counts, in that order.
+    fn ending_counts(&self) -> [usize; 3] {
+        [self.dos_endings, self.unix_endings, self.mac_endings]
+    }
+
+    /// The counts `--convert auto` goes by, with the CRLFs of phantom blank
+    /// lines taken for the LFs they were before their stray CR.
+    #[cfg(feature = "cli")]
+    fn normalized_counts(&self) -> [usize; 3] {
+        let phantom = self.phantom_blank_lines;
+        [
+            self.dos_endings - phantom,
+            self.unix_endings + phantom,
+            self.mac_endings,
+        ]
+    }
+
+    /// The line endings in a word: the one the file uses, `mixed`, `none`
+    /// or `binary`.
+    fn ending_word(&self) -> &'static str {
+        let counts = self.ending_counts();
+        let most = counts.iter().copied().max().unwrap_or(0);
+        if self.is_binary {
+            "binary"
+        } else if self.is_mixed() {
+            "mixed"
+        } else if most == 0 {
+            "none"
+        } else {
+            match counts.iter().position(|&count| count == most) {
+                Some(0) => "CRLF",
+                Some(1) => "LF",
+                _ => "CR",
+            }
+        }
+    }
+
+    /// The `--quiet` line for the file, after its name: `LF (1,204 lines)`,
+    /// `MIXED (3 CRLF / 998 LF)` or `BINARY (18 bytes)`.
+    pub fn verdict(&self) -> String {
+        self.verdict_with(true)
+    }
+
+    /// [`FileStats::verdict`], its counts grouped only when `group`.
+    fn verdict_with(&self, group: bool) -> String {
+        let grouped = |n: u64| figures::count(n, group);
+        let word = self.ending_word();
+        let detail = match word {
+            "binary" => figures::counted(self.total_bytes, "byte", group),
+            "mixed" => {
+                let counts: Vec<String> = self
+                    .ending_counts()
+                    .into_iter()
+                    .zip(["CRLF", "LF", "CR"])
+                    .filter(|&(count, _)| count > 0)
+                    .map(|(count, name)| format!("{} {}", grouped(count as u64), name))
+                    .collect();
+                counts.join(" / ")
+            }
+            _ => figures::counted(self.total_lines as u64, "line", group),
+        };
+        format!("{} ({})", word.to_uppercase(), detail)
+    }
+
+    /// The share of CRLF, LF and CR endings, all zero without endings.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    fn ending_ratios(&self) -> [f64; 3] {
+        let counts = self.ending_counts();
+        let total: usize = counts.iter().sum();
+        counts.map(|count| match total {
+            0 => 0.0,
+            _ => count as f64 / total as f64,
+        })
+    }
+
+    /// The line ending type followed by the share of each ending when more
+    /// than one occurs, e.g. "Mostly Unix/Linux (LF) - 98.7% LF, 1.3% CRLF",
+    /// and whether the others are few enough to tolerate.
+    pub fn line_ending_summary(&self) -> String {
+        let counts = self.ending_counts();
+        if counts.iter().filter(|&&count| count > 0).count() < 2 {
+            return self.line_ending().to_string();
+        }
+        let mut shares: Vec<(u64, &str)> = figures::tenths_of_percent(counts)
+            .into_iter()
+            .zip(["CRLF", "LF", "CR"])
+            .zip(counts)
+            .filter(|&(_, count)| count > 0)
+            .map(|(share, _)| share)
+            .collect();
+        shares.sort_by_key(|&(tenths, _)| std::cmp::Reverse(tenths));
+        let shares: Vec<String> = shares
+            .iter()
+            .map(|(tenths, name)| format!("{} {}", figures::percent(*tenths), name))
+            .collect();
+        format!(
+            "{} - {}{}",
+            self.line_ending_type(),
+            shares.join(", "),
+            if self.is_mixed() {
+                ""
+            } else {
+                " (within tolerance)"
+            }
+        )
+    }
+
+    /// Writes the text report `mdlt` prints for the file, uncolored.
+    pub fn write_report(&self, writer: impl Write) -> io::Result<()> {
+        self.display(ReportOptions::default(), false, writer)
+    }
+
+    /// Writes the text report, with the file name in bold among `several`
+    /// when colored.
+    fn display(
+        &self,
+        options: ReportOptions,
+        several: bool,
+        mut writer: impl Write,
+    ) -> io::Result<()> {
+        let paint = |text: &str, style| color::paint(text, style, options.color);
+        let group = !options.no_group_digits;
+        let count = |n: usize| figures::count(n as u64, group);
+        for &field in options.layout.fields() {
+            match field {
+                Field::Title => {
+                    writeln!(writer, "File Analysis Report")?;
+                    writeln!(writer, "====================")?;
+                }
+                Field::Binary => {
+                    if self.is_binary {
+                        writeln!(writer, "{}", BINARY_WARNING)?;
+                    }
+                }
+                Field::Generated => match &self.generated {
+                    Some(generated) if options.verbose => {
+                        writeln!(writer, "{}: {}", GENERATED_NOTE, generated)?
+                    }
+                    Some(_) => writeln!(writer, "{}", GENERATED_NOTE)?,
+                    None => {}
+                },
+                Field::FileName => {
+                    let name = format::escape_name(&self.name());
+                    match several {
+                        true => writeln!(writer, "File name: {}", paint(&name, color::BOLD))?,
+                        false => writeln!(writer, "File name: {}", name)?,
+                    }
+                }
+                Field::Window => {
+                    if let Some(window) = self.window {
+                        writeln!(writer, "Window: {}", window)?;
+                    }
+                }
+                Field::Head => {
+                    if let Some(head) = self.head {
+                        writeln!(writer, "Head: {}", self.head_summary(head, group))?;
+                    }
+                }
+                Field::Extension => {
+                    writeln!(
+                        writer,
+                        "File extension: {}",
+                        self.file_extension
+                            .as_ref()
+                            .map_or("none", |ext| ext.as_str())
+                    )?;
+                }
+                Field::Bom => {
+                    writeln!(
+                        writer,
+                        "Byte-order mark: {}",
+                        self.bom.map_or("none", Bom::name)
+                    )?;
+                }
+                Field::DetectedEncoding => {
+                    if let Some(guess) = self.detected_encoding {
+                        writeln!(writer, "Detected encoding: {}", guess)?;
+                    }
+                }
+                Field::Shebang => {
+                    if let Some(shebang) = &self.shebang {
+                        writeln!(writer, "Shebang: {}", shebang)?;
+                        if let Some(language) = self.detected_language {
+                            writeln!(writer, "Detected language: {}", language)?;
+                        }
+                    }
+                }
+                Field::DetectedType => {
+                    if let Some(kind) = &self.detected_type {
+                        writeln!(writer, "Detected type: {}", kind)?;
+                    }
+                }
+                Field::TotalLines => {
+                    writeln!(writer, "Total lines: {}", count(self.total_lines))?;
+                }
+                Field::EmptyLines => {
+                    writeln!(
+                        writer,
+                        "Empty lines: {}, whitespace-only lines: {}",
+                        count(self.empty_lines),
+                        count(self.blank_lines)
+                    )?;
+                }
+                Field::EmptyLinesAt => {
+                    if let Some(at) = self.empty_lines_at.as_ref().filter(|at| !at.is_empty()) {
+                        writeln!(
+                            writer,
+                            "Empty lines at: {}",
+                            empty_lines_summary(at, self.empty_lines, group)
+                        )?;
+                    }
+                }
+                Field::CommentLines => {
+                    if let (Some(comment), Some(code)) = (self.comment_lines, self.code_lines) {
+                        writeln!(
+                            writer,
+                            "Comment lines: {}, code lines: {}",
+                            count(comment),
+                            count(code)
+                        )?;
+                    }
+                }
+                Field::Size => {
+                    writeln!(writer, "Size: {}", self.size_summary(group))?;
+                }
+                Field::ChangedDuringScan => {
+                    if self.grew_during_scan {
+                        writeln!(
+                            writer,
+                            "Grew during scan: yes, counted as it was when opened"
+                        )?;
+                    }
+                    if self.truncated_during_scan {
+                        writeln!(
+                            writer,
+                            "Truncated during scan: yes, counted up to where it shrank"
+                        )?;
+                    }
+                }
+                Field::CompressedSize => {
+                    if let Some(compressed) = self.compressed_bytes {
+                        writeln!(
+                            writer,
+                            "Compressed size: {} bytes (gzip)",
+                            figures::count(compressed, group)
+                        )?;
+                    }
+                }
+                Field::Sha256 => {
+                    if let Some(sha256) = &self.sha256 {
+                        writeln!(writer, "SHA-256: {}", sha256)?;
+                    }
+                }
+                Field::TrailingBlankLines => {
+                    writeln!(
+                        writer,
+                        "Trailing blank lines: {}",
+                        count(self.trailing_blank_lines)
+                    )?;
+                }
+                Field::BlankRuns => {
+                    if let Some(runs) = &self.blank_runs {
+                        writeln!(writer, "Blank-line runs: {}", runs.summary(group))?;
+                    }
+                }
+                Field::LineLengths => {
+                    writeln!(
+                        writer,
+                        "Line lengths (min/avg/max): {}/{:.1}/{}",
+                        count(self.min_line_length),
+                        self.mean_line_length(),
+                        count(self.max_line_length)
+                    )?;
+                }
+                Field::LongestLine => {
+                    if let Some(line) = self.longest_line {
+                        writeln!(
+                            writer,
+                            "Longest line: {} on line {}",
+                            figures::counted(self.max_line_length as u64, "column", group),
+                            line
+                        )?;
+                        writeln!(
+                            writer,
+                            "Longest line preview: {}",
+                            self.longest_line_preview
+                        )?;
+                    }
+                }
+                Field::TopLines => {
+                    if let Some(lines) = self.top_lines.as_deref().filter(|lines| !lines.is_empty())
+                    {
+                        top::write(lines, &mut writer)?;
+                    }
+                }
+                Field::Peek => {
+                    if let Some(peek) = &self.peek {
+                        let shown = |line: &Option<String>| {
+                            line.clone().unwrap_or_else(|| "none".to_string())
+                        };
+                        writeln!(writer, "First line: {}", shown(&peek.first))?;
+                        writeln!(writer, "Last line: {}", shown(&peek.last))?;
+                    }
+                }
+                Field::LongLines => {
+                    if let Some(long) = &self.long_lines {
+                        writeln!(writer, "{}", long_lines_summary(long, group))?;
+                    }
+                }
+                Field::Patterns => {
+                    for count in &self.patterns {
+                        writeln!(
+                            writer,
+                            "Pattern '{}': {}",
+                            count.pattern,
+                            count.summary(group)
+                        )?;
+                    }
+                }
+                Field::Indentation => {
+                    writeln!(writer, "Indentation: {}", self.indentation.summary(group))?;
+                }
+                Field::MixedIndent => {
+                    if let Some(mixed) = &self.mixed_indent {
+                        writeln!(writer, "{}", mixed_indent_summary(mixed, group))?;
+                    }
+                }
+                Field::LineEnding => {
+                    let ending_style = match self.ending_word() {
+                        "mixed" => Some(color::RED),
+                        "none" => Some(color::YELLOW),
+                        "binary" => None,
+                        _ => Some(color::GREEN),
+                    };
+                    let summary = self.line_ending_summary();
+                    writeln!(
+                        writer,
+                        "Line ending type: {}",
+                        ending_style.map_or(summary.clone(), |style| paint(&summary, style))
+                    )?;
+                }
+                Field::EndingCounts => {
+                    writeln!(
+                        writer,
+                        "DOS line endings (CRLF): {}",
+                        count(self.dos_endings)
+                    )?;
+                    writeln!(
+                        writer,
+                        "Unix line endings (LF): {}",
+                        count(self.unix_endings)
+                    )?;
+                    writeln!(writer, "Mac line endings (CR): {}", count(self.mac_endings))?;
+                }
+                Field::EndingOffsets => {
+                    if options.verbose {
+                        let offsets = [
+                            ("LF", self.first_lf_offset),
+                            ("CRLF", self.first_crlf_offset),
+                            ("CR", self.first_cr_offset),
+                        ];
+                        for (ending, offset) in offsets {
+                            if let Some(offset) = offset {
+                                writeln!(writer, "First {} at byte offset: {}", ending, offset)?;
+                            }
+                        }
+                    }
+                }
+                Field::MinorityEndings => {
+                    for minority in self.minority_endings.iter().flatten() {
+                        writeln!(writer, "{}", minority.summary(group))?;
+                    }
+                }
+                Field::Transitions => {
+                    if let Some(segments) = &self.transitions {
+                        transitions::write(segments, group, &mut writer)?;
+                    }
+                }
+                Field::FinalNewline => {
+                    let final_newline = final_newline_summary(self.final_newline);
+                    writeln!(
+                        writer,
+                        "Ends with newline: {}",
+                        match self.final_newline {
+                            Some(false) => paint(final_newline, color::YELLOW),
+                            _ => final_newline.to_string(),
+                        }
+                    )?;
+                }
+                Field::UnicodeBreaks => {
+                    if let Some(breaks) = &self.unicode_breaks {
+                        writeln!(writer, "Next line (NEL, U+0085): {}", count(breaks.nel))?;
+                        writeln!(writer, "Line separators (LS, U+2028): {}", count(breaks.ls))?;
+                        writeln!(
+                            writer,
+                            "Paragraph separators (PS, U+2029): {}",
+                            count(breaks.ps)
+                        )?;
+                        writeln!(writer, "Vertical tabs (VT): {}", count(breaks.vt))?;
+                        writeln!(writer, "Form feeds (FF): {}", count(breaks.ff))?;
+                    }
+                }
+                Field::Pages => {
+                    if let Some(pages) = &self.pages {
+                        writeln!(writer, "Pages: {}", pages.summary(group))?;
+                        writeln!(
+                            writer,
+                            "Lines per page (min/avg/max): {}/{:.1}/{}",
+                            count(pages.min_lines),
+                            pages.mean_lines(),
+                            count(pages.max_lines)
+                        )?;
+                    }
+                }
+                Field::Utf8 => {
+                    if let Some(report) = &self.utf8 {
+                        writeln!(writer, "UTF-8: {}", utf8_summary(report, group))?;
+                    }
+                }
+                Field::NulBytes => {
+                    if let Some(line) = self.first_nul_line {
+                        writeln!(
+                            writer,
+                            "NUL bytes: {} (first on line {})",
+                            count(self.nul_bytes),
+                            line
+                        )?;
+                    }
+                }
+                Field::ControlChars => {
+                    if let Some(line) = self.first_control_line {
+                        writeln!(
+                            writer,
+                            "Control characters: {} (first on line {})",
+                            count(self.control_chars),
+                            line
+                        )?;
+                    }
+                }
+                Field::EmbeddedCr => {
+                    if let Some(summary) = embedded_cr_summary(self, group) {
+                        writeln!(writer, "Embedded carriage returns: {}", summary)?;
+                    }
+                }
+                Field::PhantomBlankLines => {
+                    if let Some(&line) = self.phantom_blank_at.first() {
+                        writeln!(
+                            writer,
+                            "Phantom blank lines (lone CR content): {}, first at line {}",
+                            count(self.phantom_blank_lines),
+                            line
+                        )?;
+                    }
+                }
+                Field::NonAscii => {
+                    if let Some(summary) = non_ascii_summary(self, group) {
+                        writeln!(writer, "Non-ASCII characters: {}", summary)?;
+                    }
+                }
+                Field::TrailingWhitespace => {
+                    if self.trailing_whitespace_lines > 0 {
+                        writeln!(
+                            writer,
+                            "Trailing whitespace: {}",
+                            paint(&trailing_whitespace_summary(self, group), color::YELLOW)
+                        )?;
+                    }
+                }
+                Field::Warnings => {
+                    for warning in &self.warnings {
+                        writeln!(writer, "Warning: {}", warning)?;
+                    }
+                }
+                Field::Violations => {
+                    let (suppressed, violations): (Vec<&Violation>, Vec<&Violation>) = self
+                        .violations
+                        .iter()
+                        .partition(|violation| violation.suppressed);
+                    let tagged = |violation: &Violation| {
+                        format!(
+                            "  {}: {}",
+                            paint(violation.severity.name(), violation.severity.style()),
+                            violation
+                        )
+                    };
+                    if !violations.is_empty() {
+                        writeln!(writer, "Violations:")?;
+                        for violation in violations {
+                            writeln!(writer, "{}", tagged(violation))?;
+                        }
+                    }
+                    match suppressed.len() {
+                        0 => {}
+                        count if options.show_suppressed => {
+                            writeln!(writer, "Suppressed violations: {}", count)?;
+                            for violation in suppressed {
+                                writeln!(writer, "{}", tagged(violation))?;
+                            }
+                        }
+                        count => writeln!(
+                            writer,
+                            "Suppressed violations: {} (--show-suppressed lists them)",
+                            count
+                        )?,
+                    }
+                }
+                Field::Histogram => {
+                    if let Some(histogram) = &self.histogram {
+                        writeln!(writer, "Line length histogram:")?;
+                        histogram.write(&mut writer)?;
+                    }
+                }
+                Field::Markdown => {
+                    if let Some(markdown) = &self.markdown {
+                        markdown.write(&mut writer)?;
+                    }
+                }
+                Field::Duplicates => {
+                    if let Some(duplicates) = &self.duplicates {
+                        duplicates.write(group, &mut writer)?;
+                    }
+                }
+                Field::Metadata => {
+                    if let Some(metadata) = &self.metadata {
+                        metadata.write(std::time::SystemTime::now(), &mut writer)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The line ending a file mostly uses, as [`FileStats::line_ending`] names
+/// it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum LineEndingKind {
+    /// CRLF, as on DOS and Windows.
+    Crlf,
+    /// LF alone, as on Unix and Linux.
+    Lf,
+    /// CR alone, as on classic Mac OS.
+    Cr,
+    /// No one ending is the most common.
+    Mixed,
+    /// Only the breaks of `--unicode-linebreaks`, such as U+2028.
+    #[cfg_attr(feature = "serde", serde(rename = "unicode"))]
+    UnicodeOnly,
+    /// No line breaks at all.
+    None,
+}
+
+impl LineEndingKind {
+    /// The lowercase word JSON reports use, e.g. "lf" or "mixed".
+    pub fn name(self) -> &'static str {
+        match self {
+            LineEndingKind::Crlf => "crlf",
+            LineEndingKind::Lf => "lf",
+            LineEndingKind::Cr => "cr",
+            LineEndingKind::Mixed => "mixed",
+            LineEndingKind::UnicodeOnly => "unicode",
+            LineEndingKind::None => "none",
+        }
+    }
+
+    /// How reports name it, e.g. "Unix/Linux (LF)".
+    pub fn description(self) -> &'static str {
+        match self {
+            LineEndingKind::Crlf => "DOS/Windows (CRLF)",
+            LineEndingKind::Lf => "Unix/Linux (LF)",
+            LineEndingKind::Cr => "Classic Mac (CR)",
+            LineEndingKind::Mixed => "Mixed line endings",
+            LineEndingKind::UnicodeOnly => "Unicode line breaks only",
+            LineEndingKind::None => "No line endings detected",
+        }
+    }
+}
+
+impl fmt::Display for LineEndingKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// "7 (first at line 12, column 34)", or `None` for pure ASCII.
+fn non_ascii_summary(stats: &FileStats, group: bool) -> Option<String> {
+    let line = stats.first_non_ascii_line?;
+    Some(format!(
+        "{} (first at line {}, column {})",
+        figures::count(stats.non_ascii_chars as u64, group),
+        line,
+        stats.first_non_ascii_column.unwrap_or(1)
+    ))
+}
+
+/// "4 (first at line 12, col 8)", or `None` when there are none.
+fn embedded_cr_summary(stats: &FileStats, group: bool) -> Option<String> {
+    let (line, column) = stats.embedded_cr_at.first()?;
+    Some(format!(
+        "{} (first at line {}, col {})",
+        figures::count(stats.embedded_crs as u64, group),
+        line,
+        column
+    ))
+}
+
+/// Describes the `--max-line-length` result, e.g. "3 lines exceed 120
+/// columns: 14, 87, 203".
+fn long_lines_summary(long: &LongLines, group: bool) -> String {
+    if long.count == 0 {
+        return format!("No lines exceed {} columns", long.limit);
+    }
+    let numbers: Vec<String> = long.first.iter().map(|line| line.to_string()).collect();
+    let more = long.count - long.first.len();
+    format!(
+        "{} exceed{} {} columns: {}{}",
+        figures::counted(long.count as u64, "line", group),
+        if long.count == 1 { "s" } else { "" },
+        long.limit,
+        numbers.join(", "),
+        if more > 0 {
+            format!(" and {} more", figures::count(more as u64, group))
+        } else {
+            String::new()
+        }
+    )
+}
+
+/// The `--show-empty` lines as ranges, e.g. "3, 17-19, 240 and 12 more".
+fn empty_lines_summary(at: &[usize], count: usize, group: bool) -> String {
+    let more = count - at.len();
+    match more {
+        0 => transitions::ranges(at),
+        _ => format!(
+            "{} and {} more",
+            transitions::ranges(at),
+            figures::count(more as u64, group)
+        ),
+    }
+}
+
+/// Describes the `--check mixed-indent` result, e.g. "Mixed indentation on
+/// 4 lines (first: 23)".
+fn mixed_indent_summary(mixed: &MixedIndentLines, group: bool) -> String {
+    match mixed.first.first() {
+        None => "Mixed indentation: none".to_string(),
+        Some(first) => format!(
+            "Mixed indentation on {} (first: {})",
+            figures::counted(mixed.count as u64, "line", group),
+            first
+        ),
+    }
+}
+
+fn final_newline_summary(final_newline: Option<bool>) -> &'static str {
+    match final_newline {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "not applicable",
+    }
+}
+
+/// Describes the lines with trailing whitespace, e.g. "2 lines (4, 9)" or
+/// "12 lines (1, 2, ..., 10 and 2 more)".
+fn trailing_whitespace_summary(stats: &FileStats, group: bool) -> String {
+    let count = stats.trailing_whitespace_lines;
+    let numbers: Vec<String> = stats
+        .trailing_whitespace_at
+        .iter()
+        .map(|line| line.to_string())
+        .collect();
+    let more = count - numbers.len();
+    format!(
+        "{} ({}{})",
+        figures::counted(count as u64, "line", group),
+        numbers.join(", "),
+        if more > 0 {
+            format!(" and {} more", figures::count(more as u64, group))
+        } else {
+            String::new()
+        }
+    )
+}
+
+/// Describes a `--check-encoding` result, e.g. "valid" or "3 invalid
+/// sequences (first at line 17, byte 432)".
+fn utf8_summary(report: &Utf8Report, group: bool) -> String {
+    match report.first.first() {
+        None => "valid".to_string(),
+        Some(first) => format!(
+            "{} (first at line {}, byte {})",
+            figures::counted(report.invalid as u64, "invalid sequence", group),
+            first.line,
+            first.offset
+        ),
+    }
+}
+
+/// Shown at the top of the report for a binary file that was analyzed.
+const BINARY_WARNING: &str =
+    "Warning: this looks like a binary file; line counts are probably meaningless";
+
+/// Shown at the top of the report for a file that looks generated.
+const GENERATED_NOTE: &str = "Likely generated/minified";
+
+/// Why a file was skipped for looking binary.
+#[cfg(feature = "cli")]
+const BINARY_SKIP: &str = "binary file";
+
+/// A file left out of the report instead of being analyzed.
+#[cfg(feature = "cli")]
+#[derive(Debug)]
+struct SkippedFile {
+    file_name: String,
+    /// Size in bytes, when known.
+    size: Option<u64>,
+    reason: String,
+    /// How many of the analyzed files were found before it, which the run
+    /// sets so that the reports list it where it was found.
+    after: usize,
+}
+
+#[cfg(feature = "cli")]
+impl SkippedFile {
+    /// Whether it was skipped for looking binary, which the summary counts
+    /// among the binary files.
+    fn is_binary(&self) -> bool {
+        self.reason == BINARY_SKIP
+    }
+
+    /// The file name followed by its size, e.g. `dump.sql (2048 bytes)`.
+    fn label(&self) -> String {
+        match self.size {
+            Some(size) => format!("{} ({} bytes)", format::escape_name(&self.file_name), size),
+            None => format::escape_name(&self.file_name),
+        }
+    }
+
+    fn display(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "Skipped {}: {}", self.label(), self.reason)
+    }
+}
+
+/// A file that could not be analyzed, as `--format json` lists it.
+#[cfg(feature = "cli")]
+#[derive(Debug)]
+struct FailedFile {
+    file_name: String,
+    /// What went wrong, in one word from `MdltError::kind`.
+    kind: &'static str,
+    error: String,
+}
+
+#[cfg(feature = "cli")]
+enum Outcome {
+    Analyzed(Box<FileStats>),
+    Skipped(SkippedFile),
+    /// What became of each member of an archive, by name.
+    Archive(Vec<(String, Result<Outcome, MdltError>)>),
+}
+
+/// Returns the skip record for `path` if it is a regular file larger than
+/// `limit` bytes. Anything whose size cannot be known up front, such as a
+/// pipe, is never skipped, and metadata errors are left for the analysis to
+/// report.
+#[cfg(feature = "cli")]
+fn check_size(path: &str, limit: u64) -> Option<SkippedFile> {
+    let metadata = fs::metadata(osname::path(path)).ok()?;
+    (metadata.is_file() && metadata.len() > limit).then(|| SkippedFile {
+        file_name: path.to_string(),
+        size: Some(metadata.len()),
+        reason: format!("larger than --max-size ({} bytes)", limit),
+        after: 0,
+    })
+}
+
+/// Analyzes `bytes` as `mdlt` does a file, with no IO at all, under `name`,
+/// whose extension decides comment syntax and the like, or `<bytes>` when
+/// it is `None`. [`analyze_reader`] and [`analyze_file`] feed the same
+/// scanner, a chunk at a time, so all three count alike.
+///
+/// ```
+/// let stats = mdlt::analyze_bytes(b"a\r\nb\n", "snippet");
+/// assert_eq!(stats.total_lines(), 2);
+/// assert_eq!((stats.dos_endings(), stats.unix_endings()), (1, 1));
+/// assert_eq!(stats.line_ending(), mdlt::LineEndingKind::Mixed);
+///
+/// let stats = mdlt::analyze_bytes(b"no newline", None);
+/// assert_eq!(stats.file_name(), "<bytes>");
+/// assert_eq!(stats.final_newline(), Some(false));
+/// ```
+pub fn analyze_bytes<'a>(bytes: &[u8], name: impl Into<Option<&'a str>>) -> FileStats {
+    let name = name.into().unwrap_or("<bytes>");
+    analyze_bytes_with(bytes, name.to_string(), ScanOptions::default())
+}
+
+/// Analyzes the file at `path` as `mdlt <path>` does, with no flags, by
+/// opening it and handing it to [`analyze_reader`], so that the two always
+/// agree. An error is a [`MdltError::File`] naming the path.
+#[cfg(feature = "cli")]
+pub fn analyze_file(path: impl AsRef<Path>) -> Result<FileStats, MdltError> {
+    let path = path.as_ref();
+    match open_file(path) {
+        Ok(file) => analyze_reader(file, path),
+        Err(source) => Err(MdltError::File {
+            path: path.display().to_string(),
+            source,
+        }),
+    }
+}
+
+/// Analyzes everything `reader` yields as `mdlt` does a file, under `name`,
+/// whose extension decides comment syntax and the like, without touching
+/// the file system. The bytes need not be valid UTF-8, and they are
+/// streamed, so memory use does not grow with their size. Reads may return
+/// any number of bytes, a line ending split across two of them counts
+/// once, and reads failing with `ErrorKind::Interrupted` are retried. An
+/// error of the reader is a [`MdltError::File`] naming `name`.
+pub fn analyze_reader<R: Read>(reader: R, name: impl AsRef<OsStr>) -> Result<FileStats, MdltError> {
+    let name = name.as_ref();
+    analyze_reader_with(
+        reader,
+        osname::encode(name).into_owned(),
+        ScanOptions::default(),
+    )
+    .map_err(|source| MdltError::File {
+        path: name.to_string_lossy().into_owned(),
+        source,
+    })
+}
+
+/// The scanner [`analyze_bytes`] and [`analyze_reader`] share, for input
+/// that arrives in pieces this crate does not read itself, such as the
+/// reads of an async runtime other than the tokio one the `async` feature
+/// reads through: [`feed`](Analyzer::feed) each chunk as it
+/// comes, of any size, and [`finish`](Analyzer::finish) at the end. The
+/// counts are those of the whole input at once. It does no IO and holds
+/// only its own state, so dropping it halfway, as when a future that owns
+/// it is cancelled, leaves nothing behind.
+///
+/// ```
+/// let mut analyzer = mdlt::Analyzer::new("upload.txt");
+/// for chunk in [&b"a\r"[..], b"\nb\n"] {
+///     analyzer.feed(chunk);
+/// }
+/// let stats = analyzer.finish();
+/// assert_eq!((stats.dos_endings(), stats.unix_endings()), (1, 1));
+/// ```
+#[derive(Debug)]
+pub struct Analyzer(Scanner);
+
+impl Analyzer {
+    /// Starts on input named `name`, whose extension decides comment
+    /// syntax and the like.
+    pub fn new(name: impl AsRef<OsStr>) -> Self {
+        let name = osname::encode(name.as_ref()).into_owned();
+        Analyzer(Scanner::new(name, ScanOptions::default()))
+    }
+
+    /// Counts `chunk`, which follows the chunks fed before it; a line
+    /// ending or character split across two chunks counts once.
+    pub fn feed(&mut self, chunk: &[u8]) {
+        self.0.feed(chunk);
+    }
+
+    /// The stats of everything fed, counting the last line whether it is
+    /// terminated or not.
+    pub fn finish(self) -> FileStats {
+        self.0.finish()
+    }
+}
+
+/// [`analyze_reader`] for tokio's `AsyncRead`: everything `reader` yields,
+/// fed to an [`Analyzer`] as each read completes, so that no executor
+/// thread blocks on the input. The counts are those `analyze_reader` gives
+/// the same bytes, reads failing with `ErrorKind::Interrupted` are retried,
+/// and dropping the future at any await leaves nothing behind. An error of
+/// the reader is a [`MdltError::File`] naming `name`.
+#[cfg(feature = "async")]
+pub async fn analyze_async_reader<R: tokio::io::AsyncRead + Unpin>(
+    mut reader: R,
+    name: impl AsRef<OsStr>,
+) -> Result<FileStats, MdltError> {
+    use tokio::io::AsyncReadExt;
+
+    let name = name.as_ref();
+    let mut analyzer = Analyzer::new(name);
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    while !analyzer.0.is_done() {
+        match reader.read(&mut buffer).await {
+            Ok(0) => break,
+            Ok(read) => analyzer.feed(&buffer[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(source) => {
+                return Err(MdltError::File {
+                    path: name.to_string_lossy().into_owned(),
+                    source,
+                })
+            }
+        }
+    }
+    Ok(analyzer.finish())
+}
+
+/// [`analyze_file`] for async code: opens the file at `path` with tokio
+/// and hands it to [`analyze_async_reader`], so that the counts are those
+/// `analyze_file` gives. An error is a [`MdltError::File`] naming the path.
+#[cfg(all(feature = "async", feature = "cli"))]
+pub async fn analyze_file_async(path: impl AsRef<Path>) -> Result<FileStats, MdltError> {
+    let path = path.as_ref();
+    match open_file_async(path).await {
+        Ok(file) => analyze_async_reader(file, path).await,
+        Err(source) => Err(MdltError::File {
+            path: path.display().to_string(),
+            source,
+        }),
+    }
+}
+
+/// Analyzes the file at `path`, memory-mapping it when `mmap` says so and
+/// streaming it otherwise (or when the map fails, e.g. for special files).
+/// A regular file is read from the start of a `--bytes` window on, and
+/// one large enough is analyzed in up to `jobs` parts at a time. A regular
+/// file is streamed no further than its size when opened, and its size
+/// once read tells whether it grew or shrank meanwhile.
+#[cfg(feature = "cli")]
+fn analyze_file_with(
+    path: &str,
+    mmap: MmapMode,
+    scan: ScanOptions,
+    jobs: usize,
+) -> io::Result<FileStats> {
+    let mut file = open_file(&osname::path(path))?;
+    let metadata = file.metadata()?;
+    let len = metadata.len();
+    let size = |file: &File| file.metadata().map(|metadata| metadata.len());
+    if metadata.is_file() && jobs > 1 {
+        if let Some(stats) = split::analyze(path, len, jobs, scan) {
+            return stats.map(|mut stats| {
+                stats.note_size(len, size(&file));
+                stats
+            });
+        }
+    }
+    if metadata.is_file() && mmap.should_map(len) {
+        if let Some(mut stats) = mmap::analyze(&file, path, scan) {
+            stats.note_size(len, size(&file));
+            return Ok(stats);
+        }
+    }
+    let mut scanner = Scanner::new(path.to_string(), scan);
+    if !metadata.is_file() {
+        return scan_reader(file, scanner);
+    }
+    let mut offset = 0;
+    if let Some(seek) = scan.window.and_then(Window::seek_to) {
+        use std::io::Seek;
+        offset = file.seek(io::SeekFrom::Start(seek))?;
+        scanner.skipped(offset);
+    }
+    scan_snapshot(&file, scanner, offset, len, || size(&file))
+}
+
+/// A reader that stops after the bytes a file had when opened, and notes
+/// whether the file ended before them.
+#[cfg(feature = "cli")]
+struct Snapshot<R> {
+    reader: R,
+    left: u64,
+    ended_early: bool,
+}
+
+#[cfg(feature = "cli")]
+impl<R: Read> Read for Snapshot<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.left == 0 {
+            return Ok(0);
+        }
+        let wanted = buf
+            .len()
+            .min(usize::try_from(self.left).unwrap_or(usize::MAX));
+        let read = self.reader.read(&mut buf[..wanted])?;
+        self.ended_early |= read == 0;
+        self.left -= read as u64;
+        Ok(read)
+    }
+}
+
+/// Streams a regular file that was `len` bytes when opened into
+/// `scanner`, from `offset`, where `reader` is, up to `len`, so that what
+/// is appended meanwhile waits for the next run. `size` gives its size
+/// once read: a larger one marks it as grown during the scan, and a
+/// smaller one, or an end before `len`, as truncated during it, with the
+/// counts of what was read before.
+#[cfg(feature = "cli")]
+fn scan_snapshot(
+    reader: impl Read,
+    scanner: Scanner,
+    offset: u64,
+    len: u64,
+    size: impl FnOnce() -> io::Result<u64>,
+) -> io::Result<FileStats> {
+    let mut snapshot = Snapshot {
+        reader,
+        left: len.saturating_sub(offset),
+        ended_early: false,
+    };
+    let mut stats = scan_reader(&mut snapshot, scanner)?;
+    stats.truncated_during_scan = snapshot.ended_early;
+    stats.note_size(len, size());
+    Ok(stats)
+}
+
+/// Opens the file at `path` for reading, with a directory failing the same
+/// way everywhere: Unix opens one and only fails to read it, and Windows
+/// denies access to it. The open handle says what was opened, and a path
+/// that cannot be opened is looked at only to tell a directory from a file
+/// that may not be read, keeping the error of the open should it be gone
+/// by then.
+#[cfg(feature = "cli")]
+fn open_file(path: &Path) -> io::Result<File> {
+    let is_a_directory = || io::Error::new(io::ErrorKind::IsADirectory, "is a directory");
+    match File::open(path) {
+        Ok(file) if file.metadata().is_ok_and(|metadata| metadata.is_dir()) => {
+            Err(is_a_directory())
+        }
+        Err(e)
+            if e.kind() == io::ErrorKind::PermissionDenied
+                && fs::metadata(path).is_ok_and(|metadata| metadata.is_dir()) =>
+        {
+            Err(is_a_directory())
+        }
+        opened => opened,
+    }
+}
+
+/// Analyzes the gzip stream `reader` yields as the file it decompresses
+/// to, under `name` but with the extension inside `name` deciding comment
+/// syntax and the like. Only the decoder's window is held in memory.
+/// [`open_file`] through tokio.
+#[cfg(all(feature = "async", feature = "cli"))]
+async fn open_file_async(path: &Path) -> io::Result<tokio::fs::File> {
+    let is_a_directory = || io::Error::new(io::ErrorKind::IsADirectory, "is a directory");
+    match tokio::fs::File::open(path).await {
+        Ok(file)
+            if file
+                .metadata()
+                .await
+                .is_ok_and(|metadata| metadata.is_dir()) =>
+        {
+            Err(is_a_directory())
+        }
+        Err(e)
+            if e.kind() == io::ErrorKind::PermissionDenied
+                && tokio::fs::metadata(path)
+                    .await
+                    .is_ok_and(|metadata| metadata.is_dir()) =>
+        {
+            Err(is_a_directory())
+        }
+        opened => opened,
+    }
+}
+
+#[cfg(feature = "cli")]
+fn analyze_gzip(reader: impl Read, name: &str, scan: ScanOptions) -> io::Result<FileStats> {
+    let mut decoder = gzip::Decoder::new(reader);
+    let mut stats = analyze_reader_with(&mut decoder, gzip::inner_name(name).to_string(), scan)?;
+    stats.set_name(name);
+    stats.compressed_bytes = Some(decoder.compressed_bytes());
+    Ok(stats)
+}
+
+/// Size of the buffer files are streamed through, so memory use does not
+/// depend on file size.
+const READ_BUFFER_SIZE: usize = 64 * 1024;
+
+/// Analyzes `bytes` in one go, labelling the result `name`; a memory map
+/// is analyzed this way too. Readers feed the same scanner chunk by chunk.
+fn analyze_bytes_with(bytes: &[u8], name: String, scan: ScanOptions) -> FileStats {
+    let mut scanner = Scanner::new(name, scan);
+    scanner.feed(bytes);
+    scanner.finish()
+}
+
+/// Analyzes everything `reader` yields, labelling the result `name`. The
+/// bytes are not required to be valid UTF-8.
+fn analyze_reader_with(
+    reader: impl Read,
+    name: String,
+    scan: ScanOptions,
+) -> io::Result<FileStats> {
+    scan_reader(reader, Scanner::new(name, scan))
+}
+
+/// Feeds `scanner` what `reader` yields, stopping early once past the
+/// window of `--lines` or `--bytes`.
+fn scan_reader(mut reader: impl Read, mut scanner: Scanner) -> io::Result<FileStats> {
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    while !scanner.is_done() {
+        match timing::reading(|| reader.read(&mut buffer)) {
+            Ok(0) => break,
+            Ok(read) => scanner.feed(&buffer[..read]),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(scanner.finish())
+}
+
+/// Splits a `--files-from` list into paths, skipping blank entries.
+#[cfg(feature = "cli")]
+fn parse_path_list(contents: &[u8], null_separated: bool) -> Vec<String> {
+    let separator = if null_separated { b'\0' } else { b'\n' };
+    contents
+        .split(|byte| *byte == separator)
+        .map(|entry| {
+            let entry = osname::from_bytes(entry);
+            if null_separated {
+                entry
+            } else {
+                entry.trim_end_matches('\r').to_string()
+            }
+        })
+        .filter(|entry| !entry.trim().is_empty())
+        .collect()
+}
+
+#[cfg(feature = "cli")]
+fn read_path_list(source: &str, null_separated: bool) -> Result<Vec<String>, MdltError> {
+    let mut contents = Vec::new();
+    let read = if source == "-" {
+        io::stdin().lock().read_to_end(&mut contents)
+    } else {
+        File::open(source).and_then(|mut file| file.read_to_end(&mut contents))
+    };
+    read.map_err(|e| MdltError::io(format!("reading file list {}", source), e))?;
+    Ok(parse_path_list(&contents, null_separated))
+}
+
+/// Expands the positional arguments and any `--files-from` list into the
+/// files to analyze, in order. Each path is analyzed once: later duplicates
+/// are dropped (and mentioned in verbose mode). With `--sample` only the
+/// files picked are kept, as they are found, with what they were picked
+/// from.
+#[cfg(feature = "cli")]
+fn collect_paths(options: &cli::Options) -> Result<(Vec<String>, Option<Sampled>), MdltError> {
+    let mut inputs = options.paths.clone();
+    if let Some(list) = &options.files_from {
+        if list == "-" && inputs.iter().any(|path| path == "-") {
+            return Err(MdltError::Usage(
+                "Cannot read both a file list and file contents from stdin".to_string(),
+            ));
+        }
+        inputs.extend(read_path_list(list, options.null_data)?);
+    }
+
+    let mut paths = Vec::new();
+    let mut filtered = 0;
+    let mut sampler = options
+        .sample
+        .map(|size| Sampler::new(size, options.seed, inputs.len() > 1));
+    let keep = |sampler: &mut Option<Sampler>, paths: &mut Vec<String>, path: String| match sampler
+    {
+        Some(sampler) => sampler.offer(path),
+        None => paths.push(path),
+    };
+    for path in &inputs {
+        if options.glob
+            && options.rev.is_none()
+            && !http::is_url(path)
+            && glob::has_meta(path)
+            && !osname::path(path).exists()
+        {
+            for matched in glob::expand(path).map_err(MdltError::Usage)? {
+                if options.walk.allows(&matched) {
+                    keep(&mut sampler, &mut paths, matched);
+                } else {
+                    filtered += 1;
+                }
+            }
+        } else if options.recursive && osname::path(path).is_dir() {
+            let mut discovery = walk::Discovery {
+                sample: sampler.take(),
+                ..walk::Discovery::default()
+            };
+            walk::discover(&osname::path(path), &options.walk, &mut discovery);
+            sampler = discovery.sample.take();
+            for warning in &discovery.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            if options.verbose > 0 {
+                for note in &discovery.skipped {
+                    eprintln!("{}", note);
+                }
+            }
+            filtered += discovery.filtered;
+            paths.extend(discovery.files);
+        } else if path == "-" || options.walk.allows(path) {
+            keep(&mut sampler, &mut paths, path.clone());
+        } else {
+            filtered += 1;
+        }
+    }
+    if options.verbose > 0 && !options.walk.filter.is_empty() {
+        eprintln!(
+            "Skipped {} entries matching --include/--exclude filters",
+            filtered
+        );
+    }
+    if let Some(sampler) = sampler {
+        let (paths, sampled) = sampler.finish();
+        return Ok((paths, Some(sampled)));
+    }
+
+    let mut seen = HashSet::new();
+    paths.retain(|path| {
+        let first = seen.insert(path.clone());
+        if !first && options.verbose > 0 {
+            eprintln!("Skipping duplicate path {}", format::escape_name(path));
+        }
+        first
+    });
+    Ok((paths, None))
+}
+
+/// The files git picks out for `changed` that `--include` and `--exclude`
+/// allow, as paths from the current directory, and what leads from there
+/// to the repository root that git names them relative to.
+#[cfg(feature = "cli")]
+fn changed_paths(
+    options: &cli::Options,
+    changed: &Changed,
+) -> Result<(Vec<String>, String), MdltError> {
+    let selection = git::select(changed).map_err(MdltError::Usage)?;
+    let paths = selection
+        .paths
+        .iter()
+        .filter(|path| options.walk.filter.allows(path))
+        .map(|path| format!("{}{}", selection.root, path))
+        .collect();
+    Ok((paths, selection.root))
+}
+
+/// The git object read for `path` instead of the file on disk, with
+/// `--rev` or `--staged`.
+#[cfg(feature = "cli")]
+fn git_object(options: &cli::Options, path: &str) -> Option<String> {
+    match (&options.rev, &options.changed) {
+        (Some(rev), _) => Some(git::at(rev, path)),
+        (None, Some(Changed::Staged)) => Some(git::staged(path)),
+        _ => None,
+    }
+}
+
+/// Prints the `-vv` row of every line in `options.lines` of each text file,
+/// under a `==> path <==` header, reading each file again as it goes so
+/// that rows appear as they are found.
+#[cfg(feature = "cli")]
+fn write_line_detail(
+    options: &cli::Options,
+    paths: &[&String],
+    shown: &ShownPaths,
+) -> io::Result<()> {
+    let window = options.scan.window;
+    let head = options.scan.head;
+    let mut stdout = io::stdout().lock();
+    let detail = |reader: &mut dyn Read, stdout: &mut io::StdoutLock| match (window, head) {
+        (Some(window), _) => lines::write_detail(
+            io::BufReader::new(window::Clipped::new(reader, window::Clip::new(window))),
+            window.first_line(),
+            stdout,
+        ),
+        (None, Some(head)) => lines::write_detail(
+            io::BufReader::new(window::Clipped::new(reader, window::Clip::head(head))),
+            1,
+            stdout,
+        ),
+        (None, None) => lines::write_detail(io::BufReader::new(reader), 1, stdout),
+    };
+    for path in paths {
+        writeln!(stdout, "==> {} <==", format::escape_name(&shown.show(path)))?;
+        match git_object(options, path) {
+            Some(object) => git::read(&object, |blob| detail(blob, &mut stdout))?,
+            None => detail(&mut open_file(&osname::path(path))?, &mut stdout)?,
+        }
+        writeln!(stdout)?;
+    }
+    stdout.flush()
+}
+
+/// `stats` as analyzed, or skipped with `--skip-generated` when the file
+/// looks generated or minified.
+#[cfg(feature = "cli")]
+fn analyzed(options: &cli::Options, stats: FileStats) -> Outcome {
+    let Some(generated) = stats.generated.as_ref().filter(|_| options.skip_generated) else {
+        return Outcome::Analyzed(Box::new(stats));
+    };
+    Outcome::Skipped(SkippedFile {
+        reason: format!("likely generated or minified ({})", generated),
+        size: Some(stats.total_bytes),
+        file_name: stats.name().into_owned(),
+        after: 0,
+    })
+}
+
+/// Analyzes the members of the archive at `path` for `--archive`, each
+/// under a name such as `release.zip!bin/run.sh`, with the filters and
+/// `--max-size` applied to them rather than to the archive. Binary members
+/// are skipped unless `--treat-binary` says otherwise, and archives inside
+/// it are skipped without looking inside. An error with the archive itself
+/// comes last, after the members that could be read before it.
+#[cfg(feature = "cli")]
+fn analyze_archive(
+    options: &cli::Options,
+    kind: archive::Kind,
+    path: &str,
+) -> Vec<(String, Result<Outcome, MdltError>)> {
+    let treat_binary = options.treat_binary.unwrap_or(TreatBinary::Skip);
+    let mut members = Vec::new();
+    let read = archive::members(path, kind, |member, size, contents| {
+        if !options.walk.filter.allows(member) {
+            return;
+        }
+        let name = archive::member_name(path, member);
+        let skip = |reason: String| {
+            Ok(Outcome::Skipped(SkippedFile {
+                file_name: name.clone(),
+                size: Some(size),
+                reason,
+                after: 0,
+            }))
+        };
+        let outcome = match options.max_size {
+            _ if archive::Kind::of(member).is_some() => {
+                skip("an archive in an archive, not looked inside".to_string())
+            }
+            Some(limit) if size > limit => {
+                skip(format!("larger than --max-size ({} bytes)", limit))
+            }
+            _ => contents
+                .and_then(|contents| match options.decompress.applies_to(member) {
+                    true => analyze_gzip(contents, &name, options.scan),
+                    false => analyze_reader_with(contents, name.clone(), options.scan),
+                })
+                .map_err(|source| MdltError::File {
+                    path: name.clone(),
+                    source,
+                })
+                .and_then(|stats| match (stats.is_binary, treat_binary) {
+                    (false, _) | (true, TreatBinary::Analyze) => Ok(analyzed(options, stats)),
+                    (true, TreatBinary::Skip) => skip(BINARY_SKIP.to_string()),
+                    (true, TreatBinary::Fail) => Err(MdltError::BinaryFile { path: name.clone() }),
+                }),
+        };
+        members.push((name, outcome));
+    });
+    if let Err(source) = read {
+        let failure = MdltError::File {
+            path: path.to_string(),
+            source,
+        };
+        members.push((path.to_string(), Err(failure)));
+    }
+    members
+}
+
+/// Analyzes one path and applies `treat_binary` to the result. Files on
+/// disk that have not changed since `cache` stored them are not read.
+#[cfg(feature = "cli")]
+fn analyze_path(
+    options: &cli::Options,
+    cache: Option<&Cache>,
+    treat_binary: TreatBinary,
+    path: &str,
+) -> Result<Outcome, MdltError> {
+    let failed = |source| MdltError::File {
+        path: path.to_string(),
+        source,
+    };
+    let archive = archive::Kind::of(path).filter(|_| options.walk.archives);
+    if let (Some(kind), None) = (archive, git_object(options, path)) {
+        return Ok(Outcome::Archive(analyze_archive(options, kind, path)));
+    }
+    let mut stats = if path == "-" {
+        let name = options.stdin_filename.as_deref().unwrap_or("<stdin>");
+        let stdin = io::stdin().lock();
+        match options.decompress.applies_to(name) {
+            true => analyze_gzip(stdin, name, options.scan),
+            false => analyze_reader_with(stdin, name.to_string(), options.scan),
+        }
+        .map_err(failed)?
+    } else if http::is_url(path) {
+        // Analyzed under the last segment of the URL, so that its extension
+        // decides comment syntax and the like.
+        let name = http::file_name(path);
+        let fetched = http::read(
+            path,
+            options.timeout,
+            options.max_size,
+            |body| match options.decompress.applies_to(&name) {
+                true => analyze_gzip(body, &name, options.scan),
+                false => analyze_reader_with(body, name.clone(), options.scan),
+            },
+        );
+        let mut stats = match (fetched, options.max_size) {
+            (Err(e), Some(limit)) if e.kind() == io::ErrorKind::FileTooLarge => {
+                return Ok(Outcome::Skipped(SkippedFile {
+                    file_name: path.to_string(),
+                    size: None,
+                    reason: format!("larger than --max-size ({} bytes)", limit),
+                    after: 0,
+                }))
+            }
+            (fetched, _) => fetched.map_err(failed)?,
+        };
+        stats.set_name(path);
+        stats
+    } else if let Some(object) = git_object(options, path) {
+        let mut name = path.to_string();
+        if let Some(rev) = &options.rev {
+            match git::object_type(&object).as_deref() {
+                Some("blob") => {}
+                Some("tree") => {
+                    return Err(failed(io::Error::other(format!(
+                        "a directory at {}, not a file",
+                        rev
+                    ))))
+                }
+                _ => {
+                    return Err(failed(io::Error::new(
+                        io::ErrorKind::NotFound,
+                        format!("no such file at {}", rev),
+                    )))
+                }
+            }
+            name = format!("{} @ {}", path, rev);
+        }
+        if let Some(limit) = options.max_size {
+            let size = git::size(&object).map_err(failed)?;
+            if size > limit {
+                return Ok(Outcome::Skipped(SkippedFile {
+                    file_name: name,
+                    size: Some(size),
+                    reason: format!("larger than --max-size ({} bytes)", limit),
+                    after: 0,
+                }));
+            }
+        }
+        // Analyzed under the path itself, so that its extension still
+        // decides comment syntax and the like.
+        let mut stats = git::read(&object, |blob| match options.decompress.applies_to(path) {
+            true => analyze_gzip(blob, path, options.scan),
+            false => analyze_reader_with(blob, path.to_string(), options.scan),
+        })
+        .map_err(failed)?;
+        stats.set_name(&name);
+        stats
+    } else {
+        if let Some(skipped) = options.max_size.and_then(|limit| check_size(path, limit)) {
+            return Ok(Outcome::Skipped(skipped));
+        }
+        // Keyed on the file as it is before it is read, so that a change
+        // made while reading it is noticed next time.
+        let key = cache.and_then(|cache| cache.key(path));
+        let cached = cache
+            .zip(key.as_ref())
+            .and_then(|(cache, key)| cache.get(key, path));
+        match cached {
+            Some(stats) => stats,
+            None => {
+                let stats = match options.decompress.applies_to(path) {
+                    true => open_file(&osname::path(path))
+                        .and_then(|file| analyze_gzip(file, path, options.scan)),
+                    false => {
+                        analyze_file_with(path, options.mmap, options.scan, options.intra_file_jobs)
+                    }
+                }
+                .map_err(failed)?;
+                if let (Some(cache), Some(key)) = (cache, key) {
+                    cache.put(key, &stats);
+                }
+                stats
+            }
+        }
+    };
+    if options.metadata {
+        stats.metadata = Some(match path {
+            _ if path == "-" || options.rev.is_some() || http::is_url(path) => FileMeta::default(),
+            _ => FileMeta::read(path),
+        });
+    }
+    if !stats.is_binary {
+        return Ok(analyzed(options, stats));
+    }
+    match treat_binary {
+        TreatBinary::Analyze => Ok(analyzed(options, stats)),
+        TreatBinary::Skip => Ok(Outcome::Skipped(SkippedFile {
+            size: fs::metadata(osname::path(path))
+                .ok()
+                .map(|metadata| metadata.len()),
+            file_name: stats.name().into_owned(),
+            reason: BINARY_SKIP.to_string(),
+            after: 0,
+        })),
+        TreatBinary::Fail => Err(MdltError::BinaryFile {
+            path: path.to_string(),
+        }),
+    }
+}
+
+/// Handles `--convert` and `--fix`: analyzes each file, then rewrites those
+/// that need it, printing one line per file or, with `--format json`, an
+/// array of what was done. Files left alone for looking binary are warned
+/// about on stderr whatever the format, and end the run with
+/// `Exit::SkippedBinary`.
+#[cfg(feature = "cli")]
+fn rewrite_paths(
+    options: &cli::Options,
+    paths: &[String],
+    shown: &ShownPaths,
+) -> Result<Exit, MdltError> {
+    if let Some(url) = paths.iter().find(|path| http::is_url(path)) {
+        return Err(MdltError::Usage(format!(
+            "--convert and --fix rewrite files on disk, not {}",
+            url
+        )));
+    }
+    if paths.iter().any(|path| path == "-") {
+        return match (paths.len(), options.convert) {
+            _ if options.dry_run => Err(MdltError::Usage(
+                "--dry-run cannot preview a conversion of stdin".to_string(),
+            )),
+            _ if options.interactive => Err(MdltError::Usage(
+                "--interactive cannot ask about stdin, which is converted as it is read"
+                    .to_string(),
+            )),
+            (1, Some(Conversion::Auto)) => Err(MdltError::Usage(
+                "--convert auto cannot read stdin, which is converted before it is analyzed"
+                    .to_string(),
+            )),
+            (1, conversion) => convert_stdin(
+                options,
+                convert::Edits {
+                    target: match conversion {
+                        Some(Conversion::To(target)) => Some(target),
+                        _ => None,
+                    },
+                    strip_trailing_whitespace: options.fixes.contains(&Fix::TrailingWhitespace),
+                    final_newline: options.fixes.contains(&Fix::FinalNewline),
+                    strip_bom: options.fixes.contains(&Fix::StripBom),
+                    retab: options.retab(),
+                    squeeze_blank: options.squeeze_blank(),
+                    blank_as_empty: options.scan.blank_as_empty,
+                },
+            )
+            .map(|()| Exit::Clean),
+            _ => Err(MdltError::Usage(
+                "--convert reads stdin only when it is the sole input".to_string(),
+            )),
+        };
+    }
+    let names: Vec<String> = paths.iter().map(|path| shown.show(path)).collect();
+    let results = match options.interactive {
+        true => {
+            let mut terminal = Terminal::open().map_err(|_| {
+                MdltError::Usage(
+                    "--interactive asks on the terminal, and there is none; use --dry-run to see what would change"
+                        .to_string(),
+                )
+            })?;
+            rewrite_interactively(options, paths, &names, |preview, question| {
+                terminal.ask(preview, question)
+            })
+        }
+        false => parallel::map_ordered(paths, options.jobs, |path| rewrite_path(options, path)),
+    };
+    let mut outcomes = Vec::new();
+    let mut failures = Vec::new();
+    for (name, result) in names.iter().zip(results) {
+        match result {
+            Ok(outcome) => outcomes.push((name.as_str(), outcome)),
+            Err(failure) => {
+                if paths.len() > 1 {
+                    eprintln!("{}", failure_line(name, &failure));
+                }
+                failures.push(failure.shown_as(name.clone()));
+            }
+        }
+    }
+    match options.format {
+        _ if options.quiet > 0 => {}
+        OutputFormat::Json => {
+            format::json::write_conversions(&outcomes, io::stdout()).map_err(writing_report)?
+        }
+        _ => {
+            for (path, outcome) in &outcomes {
+                let summary = outcome.summary();
+                if !summary.is_empty() && outcome.skipped.is_none() {
+                    println!("{}: {}", format::escape_name(path), summary);
+                }
+            }
+            let backups = outcomes
+                .iter()
+                .filter(|(_, outcome)| outcome.backup.is_some())
+                .count();
+            if let (Some(suffix), 1..) = (&options.backup, backups) {
+                println!(
+                    "Wrote {} backup{} next to the originals, named with {} on the end",
+                    backups,
+                    if backups == 1 { "" } else { "s" },
+                    suffix
+                );
+            }
+            if options.interactive {
+                let decisions = outcomes.iter().filter_map(|(_, outcome)| outcome.decision);
+                println!("Interactive: {}", interactive::summary(decisions));
+            }
+        }
+    }
+    let binaries = outcomes
+        .iter()
+        .filter(|(_, outcome)| outcome.skipped.is_some())
+        .count();
+    for (path, _) in outcomes
+        .iter()
+        .filter(|(_, outcome)| outcome.skipped.is_some())
+    {
+        eprintln!(
+            "Warning: {} looks binary, not rewritten (--force rewrites it anyway)",
+            path
+        );
+    }
+    if paths.len() == 1 && failures.len() == 1 {
+        return Err(failures.remove(0));
+    }
+    if !failures.is_empty() {
+        return Err(MdltError::Files {
+            doing: "rewritten",
+            total: paths.len(),
+            failures,
+        });
+    }
+    let pending = outcomes
+        .iter()
+        .filter(|(_, outcome)| outcome.would_change() && outcome.decision.is_none())
+        .count();
+    if pending > 0 {
+        let message = format!(
+            "{} would change (--dry-run)",
+            figures::of(pending as u64, paths.len() as u64, "file", true)
+        );
+        return Err(MdltError::check("dry-run", message));
+    }
+    Ok(if binaries > 0 {
+        Exit::SkippedBinary
+    } else {
+        Exit::Clean
+    })
+}
+
+/// `--interactive`: works out what to do with each file in turn and, for
+/// one with a change, shows it under the file's verdict and has `ask` put
+/// the question before doing it. Once the answer is to quit, the files
+/// left are not even analyzed.
+#[cfg(feature = "cli")]
+fn rewrite_interactively(
+    options: &cli::Options,
+    paths: &[String],
+    names: &[String],
+    mut ask: impl FnMut(&str, &str) -> io::Result<Answer>,
+) -> Vec<Result<convert::Outcome, MdltError>> {
+    let (mut all, mut quit) = (false, false);
+    let mut results = Vec::new();
+    for (path, name) in paths.iter().zip(names) {
+        if quit {
+            results.push(Ok(convert::Outcome {
+                decision: Some(Decision::Unvisited),
+                ..convert::Outcome::default()
+            }));
+            continue;
+        }
+        let plan = match plan_rewrite(options, path) {
+            Ok(plan) if plan.outcome.changes() => plan,
+            planned => {
+                results.push(planned.map(|plan| plan.outcome));
+                continue;
+            }
+        };
+        let answer = match all {
+            true => Answer::All,
+            false => {
+                let name = format::escape_name(name);
+                let preview = convert::Outcome {
+                    dry_run: true,
+                    ..plan.outcome.clone()
+                };
+                // A terminal that can no longer be read is taken as a quit.
+                ask(
+                    &format!("{}: {}\n  {}", name, plan.verdict, preview.summary()),
+                    &format!("Rewrite {}?", name),
+                )
+                .unwrap_or(Answer::Quit)
+            }
+        };
+        match answer {
+            Answer::Yes | Answer::All => {
+                all = answer == Answer::All;
+                results.push(
+                    apply_rewrite(options, path, plan).map(|outcome| convert::Outcome {
+                        decision: Some(Decision::Applied),
+                        ..outcome
+                    }),
+                );
+            }
+            Answer::No | Answer::Quit => {
+                quit = answer == Answer::Quit;
+                results.push(Ok(convert::Outcome {
+                    dry_run: true,
+                    decision: Some(Decision::Declined),
+                    ..plan.outcome
+                }));
+            }
+        }
+    }
+    results
+}
+
+/// Decides what to do with one file from its analysis and, unless that is
+/// nothing or this is a `--dry-run`, does it.
+#[cfg(feature = "cli")]
+fn rewrite_path(options: &cli::Options, path: &str) -> Result<convert::Outcome, MdltError> {
+    let plan = plan_rewrite(options, path)?;
+    if options.dry_run || !plan.outcome.changes() {
+        return Ok(plan.outcome);
+    }
+    apply_rewrite(options, path, plan)
+}
+
+/// What `--convert` and `--fix` would do to one file, and the verdict on
+/// its line endings that `--interactive` shows with it.
+#[cfg(feature = "cli")]
+struct Plan {
+    outcome: convert::Outcome,
+    edits: convert::Edits,
+    verdict: String,
+}
+
+/// Works out what to do with one file from its analysis. Binary files are
+/// skipped and UTF-16 files refused rather than risk rewriting bytes that
+/// are not line endings, and `--fix strip-bom` refuses any mark but a UTF-8
+/// one, which cannot be removed without re-encoding the file.
+#[cfg(feature = "cli")]
+fn plan_rewrite(options: &cli::Options, path: &str) -> Result<Plan, MdltError> {
+    let failed = |source| MdltError::File {
+        path: path.to_string(),
+        source,
+    };
+    let refused = |reason: String| MdltError::Encoding {
+        path: path.to_string(),
+        reason,
+    };
+    let stats = analyze_file_with(path, options.mmap, options.scan, options.intra_file_jobs)
+        .map_err(failed)?;
+    let strip_bom = options.fixes.contains(&Fix::StripBom);
+    if let Some(bom) = stats.bom.filter(|&bom| strip_bom && bom != Bom::Utf8) {
+        return Err(refused(format!(
+            "{} byte-order mark, not stripped: --fix strip-bom only removes UTF-8 ones",
+            bom.name()
+        )));
+    }
+    if is_utf16(stats.bom, options) {
+        return Err(refused("UTF-16 file, not converted".to_string()));
+    }
+    // The sniffed start of a file vouches for reports, but rewriting the
+    // CR and LF bytes of a binary corrupts it, so a NUL anywhere counts.
+    if (stats.is_binary || stats.nul_bytes > 0) && !options.force {
+        return Ok(Plan {
+            outcome: convert::Outcome {
+                skipped: Some("binary file".to_string()),
+                ..convert::Outcome::default()
+            },
+            edits: convert::Edits::default(),
+            verdict: stats.verdict(),
+        });
+    }
+    let mut outcome = convert::Outcome {
+        dry_run: options.dry_run,
+        ..convert::Outcome::default()
+    };
+    match options.convert {
+        Some(Conversion::To(target)) => outcome.target = Some(target),
+        Some(Conversion::Auto) => match convert::auto_target(stats.normalized_counts()) {
+            Ok(target) => outcome.target = Some(target),
+            Err(reason) => outcome.left_alone = Some(reason),
+        },
+        None => {}
+    }
+    if let Some(target) = outcome.target {
+        outcome.endings = convert::pending(stats.ending_counts(), target);
+    }
+    let strip_trailing_whitespace = options.fixes.contains(&Fix::TrailingWhitespace);
+    if strip_trailing_whitespace {
+        outcome.trailing_whitespace = Some(stats.trailing_whitespace_lines);
+    }
+    let final_newline = options.fixes.contains(&Fix::FinalNewline);
+    if final_newline && stats.final_newline == Some(false) {
+        outcome.final_newline = Some(convert::final_newline(
+            stats.ending_counts(),
+            outcome.target,
+        ));
+    }
+    outcome.bom_stripped = strip_bom && stats.bom == Some(Bom::Utf8);
+    outcome.retab = options.retab();
+    if options.squeeze_blank().is_some() {
+        outcome.blank_lines_removed = Some(0);
+    }
+    let edits = convert::Edits {
+        target: outcome.target,
+        strip_trailing_whitespace,
+        final_newline,
+        strip_bom,
+        retab: outcome.retab,
+        squeeze_blank: options.squeeze_blank(),
+        blank_as_empty: options.scan.blank_as_empty,
+    };
+    // The analysis neither measures indentation in the style asked for nor
+    // knows which blank lines would go, so a pass that writes nothing
+    // finds out what would change.
+    if edits.retab.is_some() || edits.squeeze_blank.is_some() {
+        let file = open_file(&osname::path(path)).map_err(failed)?;
+        outcome.record(convert::rewrite(file, &mut io::sink(), edits).map_err(failed)?);
+    }
+    Ok(Plan {
+        outcome,
+        edits,
+        verdict: stats.verdict(),
+    })
+}
+
+/// Rewrites one file as `plan` says.
+#[cfg(feature = "cli")]
+fn apply_rewrite(
+    options: &cli::Options,
+    path: &str,
+    plan: Plan,
+) -> Result<convert::Outcome, MdltError> {
+    let Plan {
+        mut outcome, edits, ..
+    } = plan;
+    let failed = |source| MdltError::File {
+        path: path.to_string(),
+        source,
+    };
+    let in_place = InPlace {
+        backup: options.backup.as_ref().map(|suffix| Backup {
+            suffix: suffix.clone(),
+            overwrite: options.backup_overwrite,
+        }),
+        preserve_mtime: options.preserve_mtime,
+    };
+    outcome.record(convert::convert_file(path, edits, &in_place).map_err(failed)?);
+    outcome.backup = in_place.backup.map(|backup| backup.path(path));
+    Ok(outcome)
+}
+
+/// Whether a file with `bom` is read as UTF-16, whose line endings are not
+/// the single bytes `--convert` rewrites.
+#[cfg(feature = "cli")]
+fn is_utf16(bom: Option<Bom>, options: &cli::Options) -> bool {
+    let encoding = bom.and_then(Encoding::from_bom).or(options.scan.encoding);
+    encoding.is_some_and(|encoding| encoding != Encoding::Utf8)
+}
+
+/// Passes reads through while feeding them to a scanner, so a stream can
+/// be analyzed and converted in the same pass.
+#[cfg(feature = "cli")]
+struct Scanned<'a, R> {
+    reader: R,
+    scanner: &'a mut Scanner,
+}
+
+#[cfg(feature = "cli")]
+impl<R: Read> Read for Scanned<'_, R> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let read = self.reader.read(buffer)?;
+        self.scanner.feed(&buffer[..read]);
+        Ok(read)
+    }
+}
+
+/// Opens the `--output` file, replacing what it held or, with `--append`,
+/// adding to its end.
+#[cfg(feature = "cli")]
+fn open_output(path: &str, append: bool) -> Result<File, MdltError> {
+    match append {
+        true => fs::OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)
+            .map_err(|e| MdltError::io(format!("opening {} to append to it", path), e)),
+        false => File::create(path).map_err(|e| MdltError::io(format!("creating {}", path), e)),
+    }
+}
+
+/// Whether `output` is one of `paths`, which a report written as the files
+/// are analyzed would overwrite before it is read.
+#[cfg(feature = "cli")]
+fn is_analyzed(output: &str, paths: &[String]) -> bool {
+    let Ok(output) = fs::canonicalize(output) else {
+        return false;
+    };
+    paths
+        .iter()
+        .any(|path| fs::canonicalize(osname::path(path)).is_ok_and(|path| path == output))
+}
+
+/// Converts stdin to stdout, or to `--output`, like `dos2unix`. The report
+/// on the input goes to stderr unless `--quiet`.
+#[cfg(feature = "cli")]
+fn convert_stdin(options: &cli::Options, edits: convert::Edits) -> Result<(), MdltError> {
+    let output: Box<dyn Write> = match &options.output {
+        Some(path) => Box::new(open_output(path, options.append)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let stats = convert_stream(options, edits, io::stdin().lock(), output)?;
+    match stats {
+        Some(stats) if options.quiet == 0 => format::write_report(
+            options.format,
+            options.report,
+            std::slice::from_ref(&stats),
+            &[],
+            &[],
+            Some(&aggregate(std::slice::from_ref(&stats), &[], 0)),
+            None,
+            &mut io::stderr(),
+        )
+        .map_err(|e| MdltError::io("writing report", e)),
+        _ => Ok(()),
+    }
+}
+
+/// Converts `input` to `output` in one buffered pass, analyzing it along
+/// the way. A reader that goes away early, such as `head`, ends the
+/// conversion quietly with no stats.
+#[cfg(feature = "cli")]
+fn convert_stream(
+    options: &cli::Options,
+    edits: convert::Edits,
+    input: impl Read,
+    output: impl Write,
+) -> Result<Option<FileStats>, MdltError> {
+    let name = options.stdin_filename.as_deref().unwrap_or("<stdin>");
+    let mut scanner = Scanner::new(name.to_string(), options.scan);
+    let mut reader = Scanned {
+        reader: input,
+        scanner: &mut scanner,
+    };
+    let failed = |source| MdltError::File {
+        path: name.to_string(),
+        source,
+    };
+    let mut head = Vec::with_capacity(binary::SNIFF_LEN);
+    (&mut reader)
+        .take(binary::SNIFF_LEN as u64)
+        .read_to_end(&mut head)
+        .map_err(failed)?;
+    let mut sniffer = binary::Sniffer::default();
+    sniffer.feed(&head);
+    if sniffer.is_binary() && !options.force {
+        return Err(MdltError::Encoding {
+            path: name.to_string(),
+            reason: "binary file, not converted (--force converts it anyway)".to_string(),
+        });
+    }
+    if is_utf16(Bom::detect(&head), options) {
+        return Err(MdltError::Encoding {
+            path: name.to_string(),
+            reason: "UTF-16 file, not converted".to_string(),
+        });
+    }
+
+    let mut writer = io::BufWriter::new(output);
+    let converted = convert::rewrite(head.as_slice().chain(reader), &mut writer, edits)
+        .and_then(|_| writer.flush());
+    match converted {
+        Ok(()) => Ok(Some(scanner.finish())),
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(None),
+        Err(e) => Err(failed(e)),
+    }
+}
+
+/// Whether `file` breaks any check or line ending check the options turn
+/// on, or, when none is on, mixes line endings: the files `--only-problems`
+/// shows, along with those that have phantom blank lines whatever is on.
+/// `misfit` is whether it failed `--check`, `--gitattributes` or
+/// `--editorconfig`.
+#[cfg(feature = "cli")]
+fn has_problem(options: &cli::Options, file: &FileStats, misfit: bool) -> bool {
+    if file.phantom_blank_lines > 0 {
+        return true;
+    }
+    if checks::active(options).is_empty()
+        && options.external_checks.is_empty()
+        && options.expected_endings.is_none()
+        && options.expect.is_empty()
+        && !options.gitattributes
+        && !options.editorconfig
+    {
+        return file.is_mixed();
+    }
+    file.violations.iter().any(|found| !found.suppressed) || misfit
+}
+
+/// The names of the checks whose violations fail the run, the external
+/// ones after those built in.
+#[cfg(feature = "cli")]
+fn failing_checks(options: &cli::Options) -> Vec<&str> {
+    let external = options
+        .external_checks
+        .iter()
+        .filter(|check| check.fails(options))
+        .map(|check| check.name.as_str());
+    checks::CHECKS
+        .iter()
+        .filter(|check| check.fails(options))
+        .map(|check| check.name)
+        .chain(external)
+        .collect()
+}
+
+/// The checks that `file` fails, as `--baseline` records them, by name.
+#[cfg(feature = "cli")]
+fn failed_checks(failing: &[&str], file: &FileStats) -> BTreeSet<String> {
+    file.violations
+        .iter()
+        .filter(|found| !found.suppressed && failing.contains(&found.check.as_str()))
+        .map(|found| found.check.clone())
+        .collect()
+}
+
+/// What fails the run in each file that something does, for the failure
+/// budgets: its violations of checks that fail it and the `misfits`, the
+/// reasons files fail `--check` and the like, in the order of `stats`.
+#[cfg(feature = "cli")]
+fn problems(
+    options: &cli::Options,
+    stats: &[FileStats],
+    misfits: &[(String, String)],
+) -> Vec<(String, Vec<String>)> {
+    let failing = failing_checks(options);
+    let mut reasons: HashMap<&str, Vec<&String>> = HashMap::new();
+    for (name, reason) in misfits {
+        reasons.entry(name).or_default().push(reason);
+    }
+    stats
+        .iter()
+        .filter_map(|file| {
+            let found: Vec<String> = file
+                .violations
+                .iter()
+                .filter(|found| !found.suppressed && failing.contains(&found.check.as_str()))
+                .map(Violation::to_string)
+                .chain(
+                    reasons
+                        .get(&*file.name())
+                        .into_iter()
+                        .flatten()
+                        .map(|reason| reason.to_string()),
+                )
+                .collect();
+            (!found.is_empty()).then(|| (format::escape_name(&file.name()), found))
+        })
+        .collect()
+}
+
+/// "3 of 9 files have trailing whitespace", or "1 of 1 file has ...":
+/// `part` of the `total` files, which `does`, a verb phrase for several,
+/// in the number they call for.
+#[cfg(feature = "cli")]
+fn files_that(part: usize, total: usize, does: &str) -> String {
+    let files = figures::of(part as u64, total as u64, "file", true);
+    if total != 1 {
+        return format!("{} {}", files, does);
+    }
+    let (verb, rest) = does.split_once(' ').unwrap_or((does, ""));
+    let verb = match verb {
+        "are" => "is".to_string(),
+        "have" => "has".to_string(),
+        "do" => "does".to_string(),
+        "can" | "could" | "would" | "changed" => verb.to_string(),
+        verb if verb.ends_with('x') || verb.ends_with('s') => format!("{}es", verb),
+        verb => format!("{}s", verb),
+    };
+    match rest {
+        "" => format!("{} {}", files, verb),
+        rest => format!("{} {} {}", files, verb, rest),
+    }
+}
+
+/// Sums up the violations that fail the run in the analyzed files, one
+/// message per check that some file fails.
+#[cfg(feature = "cli")]
+fn policy_violations(options: &cli::Options, stats: &[FileStats]) -> Vec<CheckFailure> {
+    let external = options
+        .external_checks
+        .iter()
+        .filter(|check| check.fails(options))
+        .filter_map(|check| {
+            let failing = stats
+                .iter()
+                .filter(|file| {
+                    file.violations
+                        .iter()
+                        .any(|found| found.check == check.name && !found.suppressed)
+                })
+                .count();
+            (failing > 0).then(|| {
+                let fail = format!("fail --external-check {}", check.name);
+                let message = files_that(failing, stats.len(), &fail);
+                CheckFailure::new(&check.name, message)
+            })
+        })
+        .collect::<Vec<CheckFailure>>();
+    checks::CHECKS
+        .iter()
+        .filter(|check| check.fails(options))
+        .filter_map(|check| {
+            let failing: Vec<&FileStats> = stats
+                .iter()
+                .filter(|file| {
+                    file.violations
+                        .iter()
+                        .any(|found| found.check == check.name && !found.suppressed)
+                })
+                .collect();
+            if failing.is_empty() {
+                return None;
+            }
+            let summary = files_that(failing.len(), stats.len(), &check.problem(options));
+            // The files that mix endings are named, with what they mix.
+            if check.name != "mixed-endings" {
+                return Some(CheckFailure::new(check.name, summary));
+            }
+            let mixed: Vec<String> = failing
+                .iter()
+                .map(|file| {
+                    format!(
+                        "  {}: {}",
+                        format::escape_name(&file.name()),
+                        ending_counts_summary(file)
+                    )
+                })
+                .collect();
+            let message = format!("{}:\n{}", summary, mixed.join("\n"));
+            Some(CheckFailure::new(check.name, message))
+        })
+        .chain(external)
+        .collect()
+}
+
+/// Each failure in what became of `path`, its own or those of the members
+/// of an archive, by name.
+#[cfg(feature = "cli")]
+fn failures_of<'a>(
+    path: &'a str,
+    result: &'a Result<Outcome, MdltError>,
+) -> Vec<(&'a str, &'a MdltError)> {
+    match result {
+        Err(failure) => vec![(path, failure)],
+        Ok(Outcome::Archive(members)) => members
+            .iter()
+            .flat_map(|(name, result)| failures_of(name, result))
+            .collect(),
+        Ok(_) => Vec::new(),
+    }
+}
+
+/// The line telling of the failure of the file shown as `shown`.
+#[cfg(feature = "cli")]
+fn failure_line(shown: &str, failure: &MdltError) -> String {
+    format!(
+        "Error analyzing {}: {}",
+        format::escape_name(shown),
+        failure.reason()
+    )
+}
+
+/// How `--relative-to` and `--slash` ask for paths to be shown.
+#[cfg(feature = "cli")]
+fn shown_paths(options: &cli::Options) -> Result<ShownPaths, MdltError> {
+    let cwd = match &options.relative_to {
+        Some(base) if !Path::new(base).is_dir() => {
+            return Err(MdltError::Usage(format!(
+                "--relative-to {}: not a directory",
+                base
+            )))
+        }
+        Some(_) => {
+            env::current_dir().map_err(|e| MdltError::io("reading the current directory", e))?
+        }
+        None => PathBuf::new(),
+    };
+    Ok(ShownPaths::new(
+        &cwd,
+        options.relative_to.as_deref(),
+        options.slash,
+    ))
+}
+
+/// What a file is sorted by for `--sort` other than by name.
+#[cfg(feature = "cli")]
+fn sort_count(file: &FileStats, key: SortKey) -> u64 {
+    let counts = file.ending_counts();
+    let most = counts.iter().copied().max().unwrap_or(0);
+    let count = match key {
+        SortKey::Lines => file.total_lines,
+        SortKey::Mixed => counts.iter().sum::<usize>() - most,
+        SortKey::Crlf => file.dos_endings,
+        SortKey::Lf => file.unix_endings,
+        SortKey::Empty => file.empty_lines,
+        SortKey::Size => return file.total_bytes,
+        SortKey::Name | SortKey::Files => 0,
+    };
+    count as u64
+}
+
+/// Orders the files for `--sort`, by name among equals so that the order
+/// does not depend on the arguments or the walk.
+#[cfg(feature = "cli")]
+fn sort_files(stats: &mut [FileStats], sort: Sort) {
+    stats.sort_by(|first, second| {
+        let order = match sort.key {
+            SortKey::Name => first.file_name.cmp(&second.file_name),
+            key => sort_count(first, key).cmp(&sort_count(second, key)),
+        };
+        match sort.descending {
+            true => order.reverse(),
+            false => order,
+        }
+        .then_with(|| first.file_name.cmp(&second.file_name))
+    });
+}
+
+/// The totals of a run's analyzed, skipped and failed files.
+#[cfg(feature = "cli")]
+fn aggregate(stats: &[FileStats], skipped: &[SkippedFile], failed: usize) -> AggregateStats {
+    let mut aggregate = AggregateStats::default();
+    stats.iter().for_each(|file| aggregate.add(file));
+    skipped.iter().for_each(|file| aggregate.add_skipped(file));
+    (0..failed).for_each(|_| aggregate.add_failed());
+    aggregate
+}
+
+/// How a run that did not fail ended.
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exit {
+    /// Everything was analyzed or rewritten and passed every check.
+    Clean,
+    /// `--convert` or `--fix` left files alone for looking binary, which
+    /// scripts can tell from both success and failure by the exit status.
+    SkippedBinary,
+}
+
+#[cfg(feature = "cli")]
+impl Exit {
+    /// The exit status the command ends with.
+    pub fn status(self) -> i32 {
+        match self {
+            Exit::Clean => 0,
+            Exit::SkippedBinary => 4,
+        }
+    }
+}
+
+/// Why an analysis or a run failed, which decides the exit status of the
+/// command; `cli::help` lists them. Its `Display` is what `mdlt` prints on
+/// stderr, which is empty when `-qq` leaves it to the exit status, and names
+/// the file for failures of one.
+#[derive(Debug)]
+pub enum MdltError {
+    /// Checks or policies found files that break them.
+    CheckFailed {
+        /// What failed the run, one check or policy each, in the order the
+        /// message lists them; empty when `-qq` leaves it to the exit status.
+        failures: Vec<CheckFailure>,
+    },
+    /// The command line asks for something that cannot be done.
+    Usage(String),
+    /// Something other than the files, such as the report, a file list or
+    /// a config, could not be read or written.
+    Io {
+        /// What was being done: "writing report", "reading config mdlt.toml".
+        doing: String,
+        /// The error that stopped it.
+        source: io::Error,
+    },
+    /// A file could not be read, analyzed or written.
+    File {
+        /// The path as given, or as shown with `--relative-to`.
+        path: String,
+        /// The error that stopped it.
+        source: io::Error,
+    },
+    /// `--treat-binary fail` found a file that looks binary.
+    BinaryFile {
+        /// The path as given, or as shown with `--relative-to`.
+        path: String,
+    },
+    /// A file is in an encoding `--convert` and `--fix` cannot rewrite,
+    /// such as UTF-16.
+    Encoding {
+        /// The path as given, or as shown with `--relative-to`.
+        path: String,
+        /// What is wrong with it, e.g. "UTF-16 file, not converted".
+        reason: String,
+    },
+    /// Some of several files could not be analyzed or rewritten, each for
+    /// one of the reasons above, while the others were.
+    Files {
+        /// What could not be done to them: "analyzed" or "rewritten".
+        doing: &'static str,
+        /// How many files there were in all.
+        total: usize,
+        /// The files that failed, in the order they were given.
+        failures: Vec<MdltError>,
+    },
+    /// Whoever read the report, such as `head`, stopped before its end.
+    /// [`run`] ends cleanly on it, as filters do, and never returns it.
+    Closed,
+}
+
+/// A check or policy that failed a run, and what it found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CheckFailure {
+    /// The check by the name `--enable` takes, such as
+    /// `trailing-whitespace` or that of an `--external-check`, or what else
+    /// failed the run: `endings` for `--check`, `--expect` and
+    /// `.gitattributes`, `editorconfig`, `budget`, `baseline`,
+    /// `uniform-tree`, `external-check` for one that could not run,
+    /// `fail-on-skip`, `dry-run`, `diff` or `compare`.
+    pub check: String,
+    /// What it found, as `mdlt` prints it: "1 of 2 files have trailing
+    /// whitespace".
+    pub message: String,
+}
+
+#[cfg(feature = "cli")]
+impl CheckFailure {
+    pub(crate) fn new(check: &str, message: String) -> Self {
+        CheckFailure {
+            check: check.to_string(),
+            message,
+        }
+    }
+}
+
+impl MdltError {
+    /// The exit status the command ends with.
+    pub fn status(&self) -> i32 {
+        match self {
+            MdltError::Closed => 0,
+            MdltError::CheckFailed { .. } => 1,
+            MdltError::Usage(_) => 2,
+            MdltError::Io { .. }
+            | MdltError::File { .. }
+            | MdltError::BinaryFile { .. }
+            | MdltError::Encoding { .. }
+            | MdltError::Files { .. } => 3,
+        }
+    }
+
+    /// The path of a failure of one file, by which [`MdltError::Files`]
+    /// lists it.
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            MdltError::File { path, .. }
+            | MdltError::BinaryFile { path }
+            | MdltError::Encoding { path, .. } => Some(path),
+            _ => None,
+        }
+    }
+
+    /// The checks and policies that failed the run, empty for the other
+    /// failures.
+    pub fn check_failures(&self) -> &[CheckFailure] {
+        match self {
+            MdltError::CheckFailed { failures } => failures,
+            _ => &[],
+        }
+    }
+
+    /// Why one file failed, without its path: "binary file". The errors
+    /// of the system that come up most, a file that is not there, one that
+    /// may not be read and a directory given as a file, are told apart in
+    /// words of their own.
+    fn reason(&self) -> String {
+        match self {
+            MdltError::File { source, .. } if source.kind() == io::ErrorKind::IsADirectory => {
+                "is a directory; pass --recursive (-r) to analyze the files in it".to_string()
+            }
+            MdltError::File { source, .. } if source.raw_os_error().is_some() => {
+                match source.kind() {
+                    io::ErrorKind::NotFound => "no such file".to_string(),
+                    io::ErrorKind::PermissionDenied => "permission denied".to_string(),
+                    _ => source.to_string(),
+                }
+            }
+            MdltError::File { source, .. } => source.to_string(),
+            MdltError::BinaryFile { .. } => "binary file".to_string(),
+            MdltError::Encoding { reason, .. } => reason.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl MdltError {
+    /// The failure of checks and policies that found `failures`.
+    fn checks(failures: Vec<CheckFailure>) -> Self {
+        MdltError::CheckFailed { failures }
+    }
+
+    /// The failure of one check or policy, named `check`, that found
+    /// `message`.
+    fn check(check: &str, message: String) -> Self {
+        MdltError::checks(vec![CheckFailure::new(check, message)])
+    }
+
+    /// The failure of `doing` something other than the files.
+    fn io(doing: impl Into<String>, source: io::Error) -> Self {
+        MdltError::Io {
+            doing: doing.into(),
+            source,
+        }
+    }
+
+    /// What went wrong with one file, in one word for the JSON report:
+    /// `not_found`, `permission_denied`, `is_a_directory`, `binary`,
+    /// `encoding`, or `io` for any other error.
+    fn kind(&self) -> &'static str {
+        match self {
+            MdltError::File { source, .. } => match source.kind() {
+                io::ErrorKind::NotFound => "not_found",
+                io::ErrorKind::PermissionDenied => "permission_denied",
+                io::ErrorKind::IsADirectory => "is_a_directory",
+                _ => "io",
+            },
+            MdltError::BinaryFile { .. } => "binary",
+            MdltError::Encoding { .. } => "encoding",
+            _ => "io",
+        }
+    }
+
+    /// The failure of one file with its path as the report shows it.
+    fn shown_as(mut self, shown: String) -> Self {
+        if let MdltError::File { path, .. }
+        | MdltError::BinaryFile { path }
+        | MdltError::Encoding { path, .. } = &mut self
+        {
+            *path = shown;
+        }
+        self
+    }
+}
+
+impl fmt::Display for MdltError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MdltError::CheckFailed { failures } => {
+                let messages: Vec<&str> = failures
+                    .iter()
+                    .map(|failure| failure.message.as_str())
+                    .collect();
+                f.write_str(&messages.join("\n"))
+            }
+            MdltError::Usage(message) => f.write_str(message),
+            MdltError::Io { doing, source } => write!(f, "Error {}: {}", doing, source),
+            MdltError::Closed => Ok(()),
+            MdltError::File { path, .. }
+            | MdltError::BinaryFile { path }
+            | MdltError::Encoding { path, .. } => write!(
+                f,
+                "Error analyzing {}: {}",
+                format::escape_name(path),
+                self.reason()
+            ),
+            // Each failure was reported as it happened.
+            MdltError::Files {
+                doing,
+                total,
+                failures,
+            } => write!(
+                f,
+                "{} could not be {}",
+                figures::of(failures.len() as u64, *total as u64, "file", true),
+                doing
+            ),
+        }
+    }
+}
+
+/// An `io::Error` cannot be compared, so the error of a [`MdltError::Io`]
+/// or [`MdltError::File`] is compared by its kind and message.
+impl PartialEq for MdltError {
+    fn eq(&self, other: &Self) -> bool {
+        let same =
+            |a: &io::Error, b: &io::Error| a.kind() == b.kind() && a.to_string() == b.to_string();
+        match (self, other) {
+            (
+                MdltError::CheckFailed { failures },
+                MdltError::CheckFailed {
+                    failures: other_failures,
+                },
+            ) => failures == other_failures,
+            (MdltError::Usage(a), MdltError::Usage(b)) => a == b,
+            (
+                MdltError::Io { doing, source },
+                MdltError::Io {
+                    doing: other_doing,
+                    source: other_source,
+                },
+            ) => doing == other_doing && same(source, other_source),
+            (
+                MdltError::File { path, source },
+                MdltError::File {
+                    path: other_path,
+                    source: other_source,
+                },
+            ) => path == other_path && same(source, other_source),
+            (MdltError::BinaryFile { path }, MdltError::BinaryFile { path: other_path }) => {
+                path == other_path
+            }
+            (
+                MdltError::Encoding { path, reason },
+                MdltError::Encoding {
+                    path: other_path,
+                    reason: other_reason,
+                },
+            ) => path == other_path && reason == other_reason,
+            (
+                MdltError::Files {
+                    doing,
+                    total,
+                    failures,
+                },
+                MdltError::Files {
+                    doing: other_doing,
+                    total: other_total,
+                    failures: other_failures,
+                },
+            ) => doing == other_doing && total == other_total && failures == other_failures,
+            (MdltError::Closed, MdltError::Closed) => true,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for MdltError {}
+
+impl std::error::Error for MdltError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            MdltError::Io { source, .. } | MdltError::File { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+/// `-qq` leaves it to the exit status to say that a check failed.
+#[cfg(feature = "cli")]
+fn quieted(options: &cli::Options, failure: MdltError) -> MdltError {
+    match failure {
+        MdltError::CheckFailed { .. } if options.quiet > 1 => MdltError::checks(Vec::new()),
+        other => other,
+    }
+}
+
+/// The line endings `file` has, most common first: "812 LF, 3 CRLF".
+#[cfg(feature = "cli")]
+fn ending_counts_summary(file: &FileStats) -> String {
+    let mut counts: Vec<(usize, &str)> = file
+        .ending_counts()
+        .into_iter()
+        .zip(["CRLF", "LF", "CR"])
+        .filter(|&(count, _)| count > 0)
+        .collect();
+    counts.sort_by_key(|&(count, _)| std::cmp::Reverse(count));
+    let counts: Vec<String> = counts
+        .iter()
+        .map(|(count, name)| format!("{} {}", count, name))
+        .collect();
+    counts.join(", ")
+}
+
+/// Why `file` fails `--check expected`, as in "expected LF, found 3 CRLF",
+/// or "expected LF (from .gitattributes line 3), found 3 CRLF" given where
+/// the expectation comes from; a file without line endings passes whatever
+/// is expected.
+#[cfg(feature = "cli")]
+fn ending_violation(
+    expected: ExpectedEndings,
+    source: Option<&str>,
+    file: &FileStats,
+) -> Option<String> {
+    let counts = file.ending_counts();
+    let allowed = match expected {
+        ExpectedEndings::Crlf => Some(0),
+        ExpectedEndings::Lf => Some(1),
+        ExpectedEndings::Cr => Some(2),
+        ExpectedEndings::Consistent => None,
+    };
+    let found: Vec<String> = ["CRLF", "LF", "CR"]
+        .iter()
+        .zip(counts)
+        .enumerate()
+        .filter(|&(style, (_, count))| count > 0 && Some(style) != allowed)
+        .map(|(_, (name, count))| format!("{} {}", count, name))
+        .collect();
+    match allowed {
+        Some(_) if !found.is_empty() => Some(format!(
+            "expected {}{}, found {}",
+            expected.name().to_uppercase(),
+            source
+                .map(|source| format!(" (from {})", source))
+                .unwrap_or_default(),
+            found.join(" and ")
+        )),
+        None if found.len() > 1 && file.is_mixed() => {
+            Some(format!("mixed line endings: {}", found.join(" and ")))
+        }
+        _ => None,
+    }
+}
+
+/// Why `file` breaks the `.editorconfig` properties other than
+/// `end_of_line`, as in "no final newline (insert_final_newline = true in
+/// .editorconfig)".
+#[cfg(feature = "cli")]
+fn editorconfig_violations(properties: &Properties, file: &FileStats) -> Vec<String> {
+    let lines = |count: usize| format!("{} line{}", count, if count == 1 { "" } else { "s" });
+    let mut violations = Vec::new();
+    if let Some(setting) = &properties.insert_final_newline {
+        let problem = match (setting.value, file.final_newline) {
+            (true, Some(false)) => Some("no final newline"),
+            (false, Some(true)) => Some("ends with a newline"),
+            _ => None,
+        };
+        violations.extend(problem.map(|problem| {
+            format!(
+                "{} (insert_final_newline = {} in {})",
+                problem, setting.value, setting.source
+            )
+        }));
+    }
+    if let Some(setting) = &properties.trim_trailing_whitespace {
+        if setting.value && file.trailing_whitespace_lines > 0 {
+            violations.push(format!(
+                "trailing whitespace on {} (trim_trailing_whitespace = true in {})",
+                lines(file.trailing_whitespace_lines),
+                setting.source
+            ));
+        }
+    }
+    if let Some(setting) = &properties.indent_style {
+        let indentation = &file.indentation;
+        let (wrong, other) = match setting.value {
+            IndentStyle::Tab => (indentation.spaces, "spaces"),
+            IndentStyle::Space => (indentation.tabs + indentation.mixed, "tabs"),
+        };
+        if wrong > 0 {
+            violations.push(format!(
+                "{} indented with {} (indent_style = {} in {})",
+                lines(wrong),
+                other,
+                setting.value.name(),
+                setting.source
+            ));
+        }
+    }
+    if let Some(setting) = &properties.max_line_length {
+        if file.max_line_length > setting.value {
+            violations.push(format!(
+                "line {} is {} columns (max_line_length = {} in {})",
+                file.longest_line.unwrap_or(0),
+                file.max_line_length,
+                setting.value,
+                setting.source
+            ));
+        }
+    }
+    violations
+}
+
+/// Whether `file` is one `--list predicate` prints.
+#[cfg(feature = "cli")]
+fn listed(predicate: ListPredicate, file: &FileStats) -> bool {
+    match predicate {
+        ListPredicate::Crlf => file.dos_endings > 0,
+        ListPredicate::Lf => file.unix_endings > 0,
+        ListPredicate::Cr => file.mac_endings > 0,
+        ListPredicate::Mixed => file.is_mixed(),
+        ListPredicate::NoEndings => file.ending_counts() == [0; 3],
+        ListPredicate::MissingFinalNewline => file.final_newline == Some(false),
+        ListPredicate::TrailingWhitespace => file.trailing_whitespace_lines > 0,
+    }
+}
+
+/// Runs the `mdlt` command on its arguments, `args[0]` being the program
+/// name, writing the report to stdout and what went wrong to stderr only
+/// as it goes; the message of a `MdltError` is left to the caller to print.
+#[cfg(feature = "cli")]
+pub fn run<I>(args: I) -> Result<Exit, MdltError>
+where
+    I: IntoIterator,
+    I::Item: Into<OsString>,
+{
+    let args = args
+        .into_iter()
+        .map(|arg| osname::encode(&arg.into()).into_owned())
+        .collect();
+    match run_command(args) {
+        Err(MdltError::Closed) => Ok(Exit::Clean),
+        result => result,
+    }
+}
+
+/// The failure of writing the report, [`MdltError::Closed`] when its reader
+/// went away.
+#[cfg(feature = "cli")]
+fn writing_report(e: io::Error) -> MdltError {
+    match e.kind() {
+        io::ErrorKind::BrokenPipe => MdltError::Closed,
+        _ => MdltError::io("writing report", e),
+    }
+}
+
+/// [`run`], but for ending cleanly on [`MdltError::Closed`].
+#[cfg(feature = "cli")]
+fn run_command(args: Vec<String>) -> Result<Exit, MdltError> {
+    // Files named like a subcommand can still be analyzed as ./diff.
+    match args.get(1).map(String::as_str) {
+        Some("diff") => return diff::run(&args),
+        Some("compare") => return compare::run(&args),
+        Some("completions") => return completions::run(&args),
+        _ => {}
+    }
+    let options = cli::parse_args(&args).map_err(MdltError::Usage)?;
+    if options.help {
+        print!("{}", cli::help(&args[0]));
+        return Ok(Exit::Clean);
+    }
+    if options.version {
+        println!("mdlt {}", env!("CARGO_PKG_VERSION"));
+        return Ok(Exit::Clean);
+    }
+    let config = match config::path(&options) {
+        Some(path) => {
+            let name = path.display().to_string();
+            let contents = fs::read_to_string(&path)
+                .map_err(|e| MdltError::io(format!("reading config {}", name), e))?;
+            let config = Config::parse(&name, &contents).map_err(MdltError::Usage)?;
+            for warning in &config.warnings {
+                eprintln!("Warning: {}", warning);
+            }
+            Some(config)
+        }
+        None => None,
+    };
+    let options = match &config {
+        Some(config) => {
+            cli::parse_args_with_config(&args, &config.flags()).map_err(MdltError::Usage)?
+        }
+        None => options,
+    };
+    if options.list_checks {
+        checks::list(&mut io::stdout()).map_err(writing_report)?;
+        return Ok(Exit::Clean);
+    }
+    if options.print_config {
+        print!(
+            "{}",
+            config::describe(config.as_ref(), &options).map_err(MdltError::Usage)?
+        );
+        return Ok(Exit::Clean);
+    }
+    if options.serve {
+        let flags = config.as_ref().map(Config::flags).unwrap_or_default();
+        return serve::run(&args, &flags);
+    }
+    if options.cache_clear {
+        let path = options
+            .cache
+            .clone()
+            .map(PathBuf::from)
+            .or_else(cache::default_path)
+            .ok_or_else(|| {
+                MdltError::Usage(
+                    "--cache-clear finds no cache directory; add --cache=PATH".to_string(),
+                )
+            })?;
+        cache::clear(&path)
+            .map_err(|e| MdltError::io(format!("clearing cache {}", path.display()), e))?;
+        if options.paths.is_empty() && options.files_from.is_none() && options.changed.is_none() {
+            return Ok(Exit::Clean);
+        }
+    }
+    if options.watch {
+        return watch::run(options);
+    }
+    if options.follow {
+        return follow::run(options);
+    }
+    run_options(options)
+}
+
+/// The line ending policies files are held to and what the files of a run
+/// break of them. What `.gitattributes` says about a file overrides its
+/// `.editorconfig`, which overrides `--check`, and a file the attributes mark
+/// as not text is not checked at all.
+#[cfg(feature = "cli")]
+struct Policies<'a> {
+    options: &'a cli::Options,
+    /// What the files git picked out are named relative to.
+    root: &'a str,
+    attributes: Option<GitAttributes>,
+    editorconfig: Option<EditorConfig>,
+    /// The checks whose violations fail the run.
+    failing: Vec<&'a str>,
+    /// The checks each file fails, for `--baseline`.
+    found: Option<baseline::Found>,
+    /// How many files break `--check` or their `.gitattributes`.
+    wrong_endings: usize,
+    /// How many files break their `.editorconfig`.
+    off_config: usize,
+}
+
+#[cfg(feature = "cli")]
+impl<'a> Policies<'a> {
+    fn new(options: &'a cli::Options, root: &'a str) -> Self {
+        Policies {
+            options,
+            root,
+            attributes: options.gitattributes.then(GitAttributes::default),
+            editorconfig: options.editorconfig.then(EditorConfig::default),
+            failing: failing_checks(options),
+            found: options.baseline.is_some().then(Default::default),
+            wrong_endings: 0,
+            off_config: 0,
+        }
+    }
+
+    /// The reasons `file` breaks the policies it is held to, counting it and
+    /// recording what it fails for `--baseline`.
+    fn misfits(&mut self, file: &FileStats) -> Vec<String> {
+        // What `--baseline` compares, by the path each file was opened by.
+        let key = baseline::key(&format!("{}{}", self.root, file.name()));
+        if let Some(found) = &mut self.found {
+            found.insert(key.clone(), failed_checks(&self.failing, file));
+        }
+        let path = Path::new(self.root).join(file.path());
+        let verdict = match &mut self.attributes {
+            Some(_) if file.is_binary => Verdict::NotText,
+            Some(attributes) => attributes.verdict(&path),
+            None => Verdict::Unspecified,
+        };
+        let properties = match &mut self.editorconfig {
+            Some(config) if !file.is_binary => config.properties(&path),
+            _ => Properties::default(),
+        };
+        let (ending, from_config) = match verdict {
+            Verdict::NotText => return Vec::new(),
+            Verdict::Expect { endings, source } => {
+                (ending_violation(endings, Some(&source), file), false)
+            }
+            Verdict::Unspecified => match &properties.end_of_line {
+                Some(setting) => {
+                    let source = format!("end_of_line in {}", setting.source);
+                    (ending_violation(setting.value, Some(&source), file), true)
+                }
+                None => (
+                    self.options
+                        .expected_endings_for(file.extension_or_type())
+                        .and_then(|expected| ending_violation(expected, None, file)),
+                    false,
+                ),
+            },
+        };
+        let mut reasons: Vec<String> = ending.into_iter().collect();
+        let broken = editorconfig_violations(&properties, file);
+        if let Some(found) = &mut self.found {
+            let checks = found.entry(key).or_default();
+            if !reasons.is_empty() {
+                checks.insert("endings".to_string());
+            }
+            if !broken.is_empty() {
+                checks.insert("editorconfig".to_string());
+            }
+        }
+        if !broken.is_empty() || (from_config && !reasons.is_empty()) {
+            self.off_config += 1;
+        } else if !reasons.is_empty() {
+            self.wrong_endings += 1;
+        }
+        reasons.extend(broken);
+        reasons
+    }
+}
+
+/// What fails the run once every file is analyzed, one message each, and
+/// what `--baseline` has to say of it when nothing does: the checks and
+/// policies the files break within what the budgets and the baseline let
+/// pass, `skipped` of `total` files counting for `--fail-on-skip`.
+#[cfg(feature = "cli")]
+#[allow(clippy::too_many_arguments)]
+fn run_violations(
+    options: &cli::Options,
+    policies: &Policies,
+    stats: &[FileStats],
+    misfits: &[(String, String)],
+    skipped: usize,
+    total: usize,
+    hooks: &external::Hooks,
+    summary: &mut AggregateStats,
+) -> Result<(Vec<CheckFailure>, Option<String>), MdltError> {
+    let mut violations = policy_violations(options, stats);
+    if policies.wrong_endings > 0 {
+        let check = options
+            .expected_endings
+            .map(|expected| format!("--check {}", expected.name()));
+        let by_extension = (!options.expect.is_empty()).then(|| "--expect".to_string());
+        let policy = options
+            .gitattributes
+            .then(|| "the .gitattributes eol policy".to_string());
+        let failed: Vec<String> = check
+            .into_iter()
+            .chain(by_extension)
+            .chain(policy)
+            .collect();
+        let fail = format!("fail {}", failed.join(" or "));
+        let message = files_that(policies.wrong_endings, stats.len(), &fail);
+        violations.push(CheckFailure::new("endings", message));
+    }
+    if policies.off_config > 0 {
+        let message = files_that(
+            policies.off_config,
+            stats.len(),
+            "break their .editorconfig",
+        );
+        violations.push(CheckFailure::new("editorconfig", message));
+    }
+    // Within its budgets, what the checks found passes the run.
+    let budgets = [
+        (budget::Counted::Files, options.max_problem_files),
+        (budget::Counted::Problems, options.max_problems),
+    ];
+    if budgets.iter().any(|(_, limit)| limit.is_some()) {
+        let problems = problems(options, stats, misfits);
+        violations.clear();
+        for (counted, limit) in budgets {
+            if let Some(limit) = limit {
+                let (verdict, over) = budget::check(counted, limit, &problems);
+                violations.extend(over.map(|over| CheckFailure::new("budget", over)));
+                summary.budgets.push(verdict);
+            }
+        }
+    }
+    // Against a baseline only new problems fail the run.
+    let mut baseline_note = None;
+    if let (Some(path), Some(found)) = (&options.baseline, &policies.found) {
+        let mut baseline = Baseline::read(path, options.update_baseline)?;
+        violations.clear();
+        if options.update_baseline {
+            baseline.update(found);
+            baseline
+                .save(path)
+                .map_err(|e| MdltError::io(format!("writing baseline {}", path), e))?;
+            baseline_note = Some(baseline.recorded(path));
+        } else {
+            let comparison = baseline.compare(found);
+            let failure = comparison.failure(path);
+            violations.extend(failure.map(|failure| CheckFailure::new("baseline", failure)));
+            if violations.is_empty() {
+                baseline_note = Some(comparison.summary(path));
+            }
+        }
+    }
+    if let Some(check) = options.uniform_tree {
+        let mixed = aggregate::uniform_tree(stats, check, options.verbose > 0);
+        violations.extend(mixed.map(|mixed| CheckFailure::new("uniform-tree", mixed)));
+    }
+    let broken = hooks.broken().into_iter();
+    violations.extend(broken.map(|broken| CheckFailure::new("external-check", broken)));
+    if options.fail_on_skip && skipped > 0 {
+        let message = format!(
+            "Skipped {} (--fail-on-skip)",
+            figures::of(skipped as u64, total as u64, "file", true)
+        );
+        violations.push(CheckFailure::new("fail-on-skip", message));
+    }
+    Ok((violations, baseline_note))
+}
+
+/// What is told after the report of a run: the tally of `--only-problems`,
+/// `left` of `scanned` files having problems, what is left of the budgets
+/// and what `--baseline` has to say.
+#[cfg(feature = "cli")]
+fn run_notes(
+    options: &cli::Options,
+    scanned: usize,
+    left: usize,
+    summary: &AggregateStats,
+    baseline_note: Option<String>,
+) -> Vec<String> {
+    let tally = (options.only_problems && options.quiet == 0).then(|| {
+        format!(
+            "{} scanned, {} with problems",
+            figures::counted(scanned as u64, "file", true),
+            figures::grouped(left as u64)
+        )
+    });
+    tally
+        .into_iter()
+        .chain(
+            summary
+                .budgets
+                .iter()
+                .filter(|verdict| verdict.within())
+                .map(|verdict| verdict.to_string()),
+        )
+        .chain(baseline_note)
+        .filter(|_| options.quiet == 0)
+        .collect()
+}
+
+/// The sink of the report `options` ask for on `paths`, `drawn` telling
+/// that the progress counter is drawn meanwhile.
+#[cfg(feature = "cli")]
+fn open_sink<'a>(
+    options: &'a cli::Options,
+    paths: &[String],
+    drawn: bool,
+) -> Box<dyn sink::Sink + 'a> {
+    // `--check` on its own is for CI logs, which want the culprits only.
+    let listing = (options.changed.is_some()
+        || options.expected_endings.is_some()
+        || !options.expect.is_empty()
+        || options.gitattributes
+        || options.editorconfig)
+        && !options.report.summary_only
+        && options.group_by.is_none()
+        && options.quiet == 0
+        && options.verbose == 0
+        && options.template.is_none()
+        && options.get.is_empty()
+        && options.format == OutputFormat::Text;
+    // A table on a terminal fits in it: `COLUMNS` wide when the shell
+    // exports it, else 80.
+    let mut report = options.report;
+    if options.format == OutputFormat::Table
+        && report.width.is_none()
+        && options.output.is_none()
+        && io::stdout().is_terminal()
+    {
+        let columns = env::var("COLUMNS")
+            .ok()
+            .and_then(|value| value.parse().ok());
+        report.width = Some(columns.filter(|&columns| columns > 0).unwrap_or(80));
+    }
+    report.color = options.format == OutputFormat::Text
+        && color::enabled(
+            options.color,
+            options.output.is_none() && io::stdout().is_terminal(),
+        );
+    // Each file is written as soon as it is analyzed unless the report
+    // needs them all, or `--output` is one of them and is yet to be read.
+    let entry = sink::Entry::of(options, listing);
+    let streams = sink::streams(options, entry, paths.len() > 1, drawn)
+        && !options
+            .output
+            .as_deref()
+            .is_some_and(|output| is_analyzed(output, paths));
+    sink::new(options, report, entry, streams)
+}
+
+/// Analyzes, checks or rewrites the files as `options` says, once the
+/// command line and `mdlt.toml` are parsed.
+#[cfg(feature = "cli")]
+fn run_options(options: cli::Options) -> Result<Exit, MdltError> {
+    if let Some(rev) = &options.rev {
+        git::verify_revision(rev).map_err(MdltError::Usage)?;
+    }
+    let shown = shown_paths(&options)?;
+    let ((paths, sampled), root) = match &options.changed {
+        Some(changed) => {
+            let (paths, root) = changed_paths(&options, changed)?;
+            ((paths, None), root)
+        }
+        None => (collect_paths(&options)?, String::new()),
+    };
+    if options.rewrites() {
+        return rewrite_paths(&options, &paths, &shown)
+            .map_err(|failure| quieted(&options, failure));
+    }
+
+    let started = Instant::now();
+    let drawn = options.progress && paths.len() > progress::MIN_FILES && io::stderr().is_terminal();
+    let progress = Progress::new(paths.len(), drawn);
+    // A lone file is still analyzed by default, with the binary warning at
+    // the top of its report, since that is clearly what was asked for.
+    let treat_binary = options.treat_binary.unwrap_or(if paths.len() == 1 {
+        TreatBinary::Analyze
+    } else {
+        TreatBinary::Skip
+    });
+    let cache = options.cache.as_ref().map(|path| {
+        let fingerprint = cache::fingerprint(&options.scan, options.decompress);
+        Cache::open(PathBuf::from(path), fingerprint)
+    });
+    // Among several files each failure is told as it happens, above the
+    // report, unless `--fail-fast` is to tell the first one only.
+    let several = paths.len() > 1;
+
+    let mut sink = open_sink(&options, &paths, drawn);
+
+    let mut policies = Policies::new(&options, &root);
+    // Files git picked out are named relative to the repository root, as
+    // git names them.
+    let from_root = |name: &mut String| {
+        if let Some(relative) = name.strip_prefix(&root) {
+            *name = relative.to_string();
+        }
+    };
+    // Names are rewritten for display only once the checks above have
+    // looked the files up by the paths they were opened as. Stdin has no
+    // path to rewrite.
+    let stdin = options.stdin_filename.as_deref().unwrap_or("<stdin>");
+    let display = |name: &mut String| {
+        if name != "-" && name != stdin {
+            *name = match options.relative_to {
+                Some(_) => shown.show(&format!("{}{}", root, name)),
+                None => shown.show(name),
+            };
+        }
+    };
+
+    let mut stats = Vec::new();
+    let mut skipped = Vec::new();
+    let mut failures = Vec::new();
+    let mut detailed = Vec::new();
+    let mut misfits: Vec<(String, String)> = Vec::new();
+    let mut summary = AggregateStats {
+        sample: sampled,
+        ..AggregateStats::default()
+    };
+    let mut archived = false;
+    let mut timings = Vec::new();
+    // Set to stop the run with, by `--fail-fast` or a report that cannot
+    // be written.
+    let mut halted = None;
+    let stop = AtomicBool::new(false);
+    let hooks = external::Hooks::new(&options);
+    parallel::for_each_ordered(
+        &paths,
+        options.jobs,
+        |path| {
+            if stop.load(Ordering::Relaxed) {
+                return (path, None);
+            }
+            let clock = options.timing.then(timing::Clock::start);
+            let result = analyze_path(&options, cache.as_ref(), treat_binary, path);
+            // The external checks are run on files as they are on disk, on
+            // the worker threads, so that `--jobs` bounds them too.
+            let found = match &result {
+                Ok(Outcome::Analyzed(file))
+                    if path != "-"
+                        && !http::is_url(path)
+                        && git_object(&options, path).is_none() =>
+                {
+                    hooks.run(&options, path, file)
+                }
+                _ => Vec::new(),
+            };
+            let timed = clock.map(timing::Clock::stop);
+            progress.file_done(path);
+            let failed = failures_of(path, &result);
+            if options.fail_fast && !failed.is_empty() {
+                stop.store(true, Ordering::Relaxed);
+            } else if several {
+                for (name, failure) in failed {
+                    progress.note(&failure_line(&shown.show(name), failure));
+                }
+            }
+            (path, Some((result, timed, found)))
+        },
+        |(path, result)| {
+            // Files left once the run stopped have no result.
+            let Some((result, timed, mut found)) = result.filter(|_| halted.is_none()) else {
+                return;
+            };
+            if let Some(timed) = timed {
+                timings.push((shown.show(path), timed));
+            }
+            let mut pending = vec![(path.clone(), result)];
+            while let Some((name, result)) = pending.pop() {
+                match result {
+                    Ok(Outcome::Analyzed(file_stats)) => {
+                        let mut file = *file_stats;
+                        if !file.is_binary && name == *path {
+                            detailed.push(path);
+                        }
+                        if !root.is_empty() {
+                            file.rename(from_root);
+                        }
+                        file.violations = checks::violations(&options, &file);
+                        if name == *path {
+                            file.violations.append(&mut found);
+                        }
+                        summary.add(&file);
+                        let mut reasons: Vec<(String, String)> = policies
+                            .misfits(&file)
+                            .into_iter()
+                            .map(|reason| (file.name().into_owned(), reason))
+                            .collect();
+                        file.rename(display);
+                        reasons.iter_mut().for_each(|(name, _)| display(name));
+                        let reported = !options.only_problems
+                            || has_problem(&options, &file, !reasons.is_empty());
+                        if reported {
+                            if let Err(failure) = sink.file(&file, &reasons) {
+                                halted = Some(failure);
+                                stop.store(true, Ordering::Relaxed);
+                            }
+                        }
+                        misfits.extend(reasons);
+                        stats.push(file)
+                    }
+                    Ok(Outcome::Skipped(mut file)) => {
+                        if !root.is_empty() {
+                            from_root(&mut file.file_name);
+                        }
+                        display(&mut file.file_name);
+                        file.after = stats.len();
+                        summary.add_skipped(&file);
+                        if !options.only_problems || options.fail_on_skip {
+                            if let Err(failure) = sink.skipped(&file) {
+                                halted = Some(failure);
+                                stop.store(true, Ordering::Relaxed);
+                            }
+                        }
+                        skipped.push(file)
+                    }
+                    Ok(Outcome::Archive(members)) => {
+                        archived = true;
+                        pending.extend(members.into_iter().rev());
+                    }
+                    Err(e) if options.fail_fast => {
+                        halted = Some(e.shown_as(shown.show(&name)));
+                        return;
+                    }
+                    Err(e) => failures.push((name, e)),
+                }
+            }
+        },
+    );
+    let wall = started.elapsed();
+    progress.finish();
+    if options.verbose > 0 {
+        let cached = match &cache {
+            Some(cache) => {
+                for path in paths.iter().filter(|path| cache.hit(path)) {
+                    eprintln!("{} (cached)", format::escape_name(&shown.show(path)));
+                }
+                format!(", {} from the cache", cache.hits())
+            }
+            None => String::new(),
+        };
+        eprintln!(
+            "Scanned {} in {:.2}s{}",
+            figures::counted(paths.len() as u64, "file", true),
+            started.elapsed().as_secs_f64(),
+            cached
+        );
+    }
+    if let Some(cache) = cache {
+        let path = options.cache.as_deref().unwrap_or_default();
+        if let Err(e) = cache.save() {
+            eprintln!("Warning: cannot write cache {}: {}", path, e);
+        }
+    }
+    if let Some(failure) = halted {
+        return Err(failure);
+    }
+    // Counted once archives are opened up, each of their members a file.
+    let total = stats.len() + skipped.len() + failures.len();
+    failures.iter().for_each(|_| summary.add_failed());
+
+    // Someone piping the detail into `head` has seen what they wanted.
+    if options.verbose > 1 {
+        match write_line_detail(&options, &detailed, &shown) {
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(Exit::Clean),
+            result => result.map_err(|e| MdltError::io("writing line detail", e))?,
+        }
+    }
+
+    if options.timing {
+        summary.timing = Some(Timing {
+            files: timings,
+            wall,
+            bytes: summary.total_bytes,
+            jobs: parallel::workers(options.jobs, paths.len()),
+        });
+    }
+    if total == 1 && failures.len() == 1 {
+        let (path, failure) = failures.remove(0);
+        return Err(failure.shown_as(shown.show(&path)));
+    }
+    // The members of a lone archive were not told of as they failed.
+    if !several {
+        for (name, failure) in &failures {
+            eprintln!("{}", failure_line(&shown.show(name), failure));
+        }
+    }
+    let failures: Vec<(String, MdltError)> = failures
+        .into_iter()
+        .map(|(path, failure)| (shown.show(&path), failure))
+        .collect();
+    let failed: Vec<FailedFile> = failures
+        .iter()
+        .map(|(name, failure)| FailedFile {
+            file_name: name.clone(),
+            kind: failure.kind(),
+            error: failure.reason(),
+        })
+        .collect();
+
+    // The files of archives are totalled per archive unless asked otherwise.
+    let groups = options
+        .group_by
+        .or(archived.then_some(cli::GroupBy::Archive))
+        .map(|by| Groups::new(by, options.sort, &stats, &skipped));
+    let (violations, baseline_note) = run_violations(
+        &options,
+        &policies,
+        &stats,
+        &misfits,
+        skipped.len(),
+        total,
+        &hooks,
+        &mut summary,
+    )?;
+    // The tree counts every file, whatever `--only-problems` leaves out.
+    let tree = options.tree.map(|settings| {
+        let wrong: HashSet<&str> = misfits.iter().map(|(path, _)| path.as_str()).collect();
+        let tree = Tree::new(&stats, &skipped, |file| {
+            has_problem(&options, file, wrong.contains(&*file.name()))
+        });
+        (settings, tree)
+    });
+    let scanned = stats.len();
+    if options.only_problems {
+        let wrong: HashSet<&str> = misfits.iter().map(|(path, _)| path.as_str()).collect();
+        let kept: Vec<bool> = stats
+            .iter()
+            .map(|file| has_problem(&options, file, wrong.contains(&*file.name())))
+            .collect();
+        // The skipped files keep their places among the files left.
+        for file in &mut skipped {
+            file.after = kept[..file.after].iter().filter(|&&kept| kept).count();
+        }
+        let mut kept = kept.into_iter();
+        stats.retain(|_| kept.next().unwrap_or(false));
+        if !options.fail_on_skip {
+            skipped.clear();
+        }
+    }
+    if let Some(sort) = options.sort {
+        sort_files(&mut stats, sort);
+        // A sorted report has no order of discovery to keep.
+        skipped.iter_mut().for_each(|file| file.after = stats.len());
+    }
+    // The tally and what is left of the budgets would break the other
+    // formats, so they leave them to stderr.
+    let notes = run_notes(&options, scanned, stats.len(), &summary, baseline_note);
+    let tally_in_report = options.format == OutputFormat::Text
+        && options.template.is_none()
+        && options.get.is_empty()
+        && options.list.is_empty()
+        && !options.print0;
+    sink.finish(&sink::Run {
+        stats: &stats,
+        skipped: &skipped,
+        failed: &failed,
+        summary: &summary,
+        groups: groups.as_ref(),
+        tree: tree.as_ref(),
+        misfits: &misfits,
+        notes: if tally_in_report { &notes } else { &[] },
+    })?;
+    if !tally_in_report {
+        notes.iter().for_each(|note| eprintln!("{}", note));
+    }
+    if let Some(timing) = summary.timing.as_ref().filter(|_| options.quiet < 2) {
+        timing
+            .write(&mut io::stderr())
+            .map_err(|e| MdltError::io("writing timing", e))?;
+    }
+
+    if !failures.is_empty() {
+        return Err(MdltError::Files {
+            doing: "analyzed",
+            total,
+            failures: failures
+                .into_iter()
+                .map(|(name, failure)| failure.shown_as(name))
+                .collect(),
+        });
+    }
+    if !violations.is_empty() {
+        return Err(quieted(&options, MdltError::checks(violations)));
+    }
+    Ok(Exit::Clean)
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+    use cli::ConvertTo;
+    use std::io::Write;
+    use std::time::{Duration, SystemTime};
+
+    fn create_temp_file(name: &str, content: &str) -> String {
+        let file_path = format!(".\\{}", name);
+        let mut file = File::create(&file_path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+        file_path
+    }
+
+    #[test]
+    fn test_line_ending_summary() {
+        let mut stats = FileStats::new("test_file.txt".to_string());
+        assert_eq!(stats.line_ending_summary(), "No line endings detected");
+        assert_eq!(stats.ending_ratios(), [0.0; 3]);
+        stats.unix_endings = 987;
+        assert_eq!(stats.line_ending_summary(), "Unix/Linux (LF)");
+        stats.dos_endings = 13;
+        assert_eq!(
+            stats.line_ending_summary(),
+            "Mostly Unix/Linux (LF) - 98.7% LF, 1.3% CRLF"
+        );
+        assert_eq!(stats.ending_ratios(), [0.013, 0.987, 0.0]);
+        stats.tolerance.percent = Some(1.3);
+        assert!(!stats.is_mixed());
+        assert_eq!(
+            stats.line_ending_summary(),
+            "Unix/Linux (LF) - 98.7% LF, 1.3% CRLF (within tolerance)"
+        );
+        stats.tolerance.percent = Some(1.2);
+        assert!(stats.is_mixed());
+        stats.unix_endings = 13;
+        assert_eq!(
+            stats.line_ending_summary(),
+            "Mixed line endings - 50.0% CRLF, 50.0% LF"
+        );
+    }
+
+    #[test]
+    fn test_verdict() {
+        let file = |[dos, unix, mac]: [usize; 3]| {
+            let mut stats = FileStats::new("a.txt".to_string());
+            stats.dos_endings = dos;
+            stats.unix_endings = unix;
+            stats.mac_endings = mac;
+            stats.total_lines = dos + unix + mac;
+            stats
+        };
+        assert_eq!(file([0, 1204, 0]).verdict(), "LF (1,204 lines)");
+        assert_eq!(file([2, 0, 0]).verdict(), "CRLF (2 lines)");
+        assert_eq!(file([0, 0, 1]).verdict(), "CR (1 line)");
+        assert_eq!(file([3, 998, 0]).verdict(), "MIXED (3 CRLF / 998 LF)");
+        assert_eq!(file([1, 0, 1500]).verdict(), "MIXED (1 CRLF / 1,500 CR)");
+        let mut tolerated = file([1, 999, 0]);
+        tolerated.tolerance.endings = Some(1);
+        assert_eq!(tolerated.verdict(), "LF (1,000 lines)");
+        let mut single = file([0, 0, 0]);
+        single.total_lines = 1;
+        assert_eq!(single.verdict(), "NONE (1 line)");
+        let mut binary = file([0, 2, 0]);
+        binary.is_binary = true;
+        binary.total_bytes = 2048;
+        assert_eq!(binary.verdict(), "BINARY (2,048 bytes)");
+    }
+
+    #[test]
+    fn test_analyze_reader_binary_content() {
+        let bytes: &[u8] = b"\x00\xff\r\n\xfe\n";
+        let stats =
+            analyze_reader_with(bytes, "foo.c".to_string(), ScanOptions::default()).unwrap();
+        assert_eq!(stats.file_name, "foo.c");
+        assert_eq!(stats.file_extension, Some("c".to_string()));
+        assert_eq!(stats.total_lines, 2);
+        assert_eq!(stats.dos_endings, 1);
+        assert_eq!(stats.unix_endings, 1);
+    }
+
+    /// Hands out one byte per `read` call and fails with `Interrupted` every
+    /// other call, to shake out buffer-boundary and retry bugs.
+    struct TrickleReader<'a> {
+        bytes: &'a [u8],
+        interrupt: bool,
+    }
+
+    impl Read for TrickleReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            match self.bytes.split_first() {
+                Some((first, rest)) if !buf.is_empty() => {
+                    buf[0] = *first;
+                    self.bytes = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_analyze_reader_one_byte_reads() {
+        let content = b"line1\n\r\nline3\r\n\nline5\r";
+        let whole =
+            analyze_reader_with(&content[..], "a.txt".to_string(), ScanOptions::default()).unwrap();
+        let trickle = TrickleReader {
+            bytes: content,
+            interrupt: false,
+        };
+        let stats =
+            analyze_reader_with(trickle, "a.txt".to_string(), ScanOptions::default()).unwrap();
+        assert_eq!(stats.total_lines, 5);
+        assert_eq!(stats.total_lines, whole.total_lines);
+        assert_eq!(stats.empty_lines, whole.empty_lines);
+        assert_eq!(stats.dos_endings, whole.dos_endings);
+        assert_eq!(stats.unix_endings, whole.unix_endings);
+    }
+
+    #[test]
+    fn test_analyze_reader_crlf_on_buffer_boundary() {
+        let mut content = vec![b'x'; READ_BUFFER_SIZE - 1];
+        content.extend_from_slice(b"\r\nnext\n");
+        let stats =
+            analyze_reader_with(&content[..], "big.txt".to_string(), ScanOptions::default())
+                .unwrap();
+        assert_eq!(stats.total_lines, 2);
+        assert_eq!(stats.dos_endings, 1);
+        assert_eq!(stats.unix_endings, 1);
+    }
+
+    #[test]
+    fn test_scan_snapshot_of_a_changing_file() {
+        // What the reader delivers, how large the file was when opened, and
+        // how large its metadata says it is once read.
+        let scan = |bytes: &'static [u8], len: u64, now: io::Result<u64>| {
+            let reader = TrickleReader {
+                bytes,
+                interrupt: false,
+            };
+            let scanner = Scanner::new("app.log".to_string(), ScanOptions::default());
+            scan_snapshot(reader, scanner, 0, len, || now).unwrap()
+        };
+        let stable = scan(b"one\ntwo\n", 8, Ok(8));
+        assert!(!stable.grew_during_scan() && !stable.truncated_during_scan());
+        assert_eq!(stable.total_lines, 2);
+        // Appended to meanwhile: only the lines it had when opened count.
+        let grew = scan(b"one\ntwo\nthree\n", 8, Ok(14));
+        assert!(grew.grew_during_scan() && !grew.truncated_during_scan());
+        assert_eq!((grew.total_lines, grew.total_bytes), (2, 8));
+        // Truncated halfway through: what was read counts.
+        let shrank = scan(b"one\ntw", 8, Ok(0));
+        assert!(!shrank.grew_during_scan() && shrank.truncated_during_scan());
+        assert_eq!((shrank.total_lines, shrank.total_bytes), (2, 6));
+        // Rotated and written again past its old size.
+        let rotated = scan(b"one\n", 8, Ok(20));
+        assert!(rotated.grew_during_scan() && rotated.truncated_during_scan());
+        // Metadata that cannot be had says nothing of growth.
+        let unknown = scan(b"one\ntwo\nthree\n", 8, Err(io::ErrorKind::Other.into()));
+        assert!(!unknown.grew_during_scan() && !unknown.truncated_during_scan());
+        assert_eq!(unknown.total_bytes, 8);
+
+        let options = cli::parse_args(&[
+            "mdlt".to_string(),
+            "--fail-on-unstable".to_string(),
+            "app.log".to_string(),
+        ])
+        .unwrap();
+        let check = checks::find("--enable", "unstable").unwrap();
+        assert!(check.active(&options));
+        let messages = |stats: &FileStats| -> Vec<String> {
+            check
+                .violations(&options, stats)
+                .into_iter()
+                .map(|violation| violation.to_string())
+                .collect()
+        };
+        assert!(messages(&stable).is_empty());
+        assert_eq!(
+            messages(&rotated),
+            [
+                "grew while it was read [unstable]",
+                "shrank while it was read [unstable]"
+            ]
+        );
+        let mut report = Vec::new();
+        grew.write_report(&mut report).unwrap();
+        let report = String::from_utf8(report).unwrap();
+        assert!(
+            report.contains("\nGrew during scan: yes, counted as it was when opened\n"),
+            "{}",
+            report
+        );
+        let mut json = Vec::new();
+        format::json::write_object(&shrank, ReportOptions::default(), &mut json).unwrap();
+        let json = String::from_utf8(json).unwrap();
+        assert!(
+            json.contains("\"grew_during_scan\": false, \"truncated_during_scan\": true"),
+            "{}",
+            json
+        );
+    }
+
+    #[test]
+    fn test_analyze_file_mmap_matches_streaming() {
+        let fixtures: &[&[u8]] = &[
+            b"",
+            b"line1\nline2\n",
+            b"line1\r\nline2\r\n",
+            b"line1\rline2\r",
+            b"line1\n\r\nline3\r\n\nline5",
+            b"\xff\xfe\r\n\0\n",
+        ];
+        for (i, content) in fixtures.iter().enumerate() {
+            let file_path = env::temp_dir()
+                .join(format!("mdlt_mmap_{}_{}.txt", std::process::id(), i))
+                .to_string_lossy()
+                .into_owned();
+            fs::write(&file_path, content).unwrap();
+            let mapped =
+                analyze_file_with(&file_path, MmapMode::Always, ScanOptions::default(), 1).unwrap();
+            let streamed =
+                analyze_file_with(&file_path, MmapMode::Never, ScanOptions::default(), 1).unwrap();
+            assert_eq!(mapped.total_lines, streamed.total_lines);
+            assert_eq!(mapped.empty_lines, streamed.empty_lines);
+            assert_eq!(mapped.dos_endings, streamed.dos_endings);
+            assert_eq!(mapped.unix_endings, streamed.unix_endings);
+            assert_eq!(mapped.file_name, streamed.file_name);
+            fs::remove_file(file_path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_analyze_gzip() {
+        // `gzip` of "one\r\ntwo\nthree\r\n".
+        let mut member = vec![
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x03, 0xcb, 0xcf, 0x4b, 0xe5,
+            0xe5, 0x2a, 0x29, 0xcf, 0xe7, 0x2a, 0xc9, 0x28, 0x4a, 0x05, 0x32, 0x01, 0x01, 0x2b,
+            0x41, 0x71, 0x10, 0x00, 0x00, 0x00,
+        ];
+        let stats = analyze_gzip(&member[..], "app.log.gz", ScanOptions::default()).unwrap();
+        assert_eq!(stats.file_name, "app.log.gz");
+        assert_eq!(stats.file_extension(), Some("log"));
+        assert_eq!(
+            (stats.total_lines, stats.dos_endings, stats.unix_endings),
+            (3, 2, 1)
+        );
+        assert_eq!(stats.total_bytes, 16);
+        assert_eq!(stats.compressed_bytes, Some(34));
+        let mut report = Vec::new();
+        stats.write_report(&mut report).unwrap();
+        assert!(String::from_utf8(report)
+            .unwrap()
+            .contains("characters, 3 words\nCompressed size: 34 bytes (gzip)\n"));
+
+        member[30] ^= 1;
+        let error = analyze_gzip(&member[..], "app.log.gz", ScanOptions::default()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error.to_string(),
+            "corrupt gzip stream at byte 30: length mismatch"
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        let stats = FileStats {
+            total_lines: 10,
+            unix_endings: 5,
+            dos_endings: 5,
+            mac_endings: 0,
+            first_lf_offset: None,
+            first_crlf_offset: None,
+            first_cr_offset: None,
+            tolerance: Tolerance::default(),
+            empty_lines: 2,
+            empty_lines_at: None,
+            blank_lines: 1,
+            comment_lines: Some(3),
+            code_lines: Some(4),
+            unicode_breaks: None,
+            pages: None,
+            bom: None,
+            detected_encoding: None,
+            shebang: Some("/usr/bin/env python3".to_string()),
+            detected_language: Some("Python"),
+            detected_type: None,
+            utf8: None,
+            warnings: Vec::new(),
+            violations: Vec::new(),
+            suppressions: Vec::new(),
+            is_binary: false,
+            generated: None,
+            total_bytes: 160,
+            compressed_bytes: None,
+            window: None,
+            head: None,
+            truncated: false,
+            grew_during_scan: false,
+            truncated_during_scan: false,
+            sha256: None,
+            total_chars: 158,
+            total_words: 21,
+            nul_bytes: 0,
+            first_nul_line: None,
+            control_chars: 0,
+            non_ascii_chars: 0,
+            first_non_ascii_line: None,
+            first_non_ascii_column: None,
+            first_control_line: None,
+            embedded_crs: 0,
+            embedded_cr_at: Vec::new(),
+            phantom_blank_lines: 0,
+            phantom_blank_at: Vec::new(),
+            trailing_whitespace_lines: 0,
+            trailing_whitespace_at: Vec::new(),
+            min_line_length: 0,
+            max_line_length: 12,
+            total_line_length: 75,
+            max_line_bytes: 11,
+            longest_line: Some(3),
+            longest_line_preview: "let x = 1;\\t".to_string(),
+            top_lines: None,
+            peek: None,
+            histogram: None,
+            markdown: None,
+            duplicates: None,
+            patterns: Vec::new(),
+            transitions: None,
+            minority_endings: None,
+            metadata: None,
+            long_lines: None,
+            indentation: Indentation {
+                spaces: 6,
+                width: Some(4),
+                ..Indentation::default()
+            },
+            mixed_indent: None,
+            trailing_blank_lines: 1,
+            blank_runs: None,
+            final_newline: Some(true),
+            file_extension: Some("txt".to_string()),
+            file_name: "test.txt".into(),
+        };
+        let mut buffer = Vec::new();
+        stats
+            .display(ReportOptions::default(), false, &mut buffer)
+            .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains("File Analysis Report"));
+        assert!(output.contains("File name: test.txt"));
+        assert!(output.contains("File extension: txt"));
+        assert!(output.contains("Byte-order mark: none"));
+        assert!(output.contains("Shebang: /usr/bin/env python3\nDetected language: Python\n"));
+        assert!(output.contains("Total lines: 10"));
+        assert!(output.contains("Empty lines: 2, whitespace-only lines: 1"));
+        assert!(output.contains("Comment lines: 3, code lines: 4"));
+        assert!(output.contains("Size: 160 bytes, 158 characters, 21 words"));
+        assert!(output.contains("Line ending type: Mixed line endings"));
+        assert!(output.contains("DOS line endings (CRLF): 5"));
+        assert!(output.contains("Ends with newline: yes"));
+        assert!(output.contains("Trailing blank lines: 1"));
+        assert!(output.contains("Line lengths (min/avg/max): 0/7.5/12"));
+        assert!(output.contains("Longest line: 12 columns on line 3"));
+        assert!(output.contains("Indentation: spaces (6 lines, width 4)\n"));
+        assert!(output.contains("Longest line preview: let x = 1;\\t\n"));
+        assert!(output.contains("Unix line endings (LF): 5"));
+        assert!(output.contains("Mac line endings (CR): 0"));
+        assert!(!output.contains("byte offset"));
+        let stats = FileStats {
+            first_lf_offset: Some(40),
+            first_crlf_offset: Some(3),
+            ..stats
+        };
+        let verbose = ReportOptions {
+            verbose: true,
+            ..ReportOptions::default()
+        };
+        let mut buffer = Vec::new();
+        stats.display(verbose, false, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains(
+            "Mac line endings (CR): 0\nFirst LF at byte offset: 40\nFirst CRLF at byte offset: 3\n"
+        ));
+
+        let colored = ReportOptions {
+            color: true,
+            ..ReportOptions::default()
+        };
+        let mut buffer = Vec::new();
+        stats.display(colored, true, &mut buffer).unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains("File name: \x1b[1mtest.txt\x1b[0m\n"));
+        assert!(output.contains("Line ending type: \x1b[31mMixed line endings"));
+        assert!(output.contains("Ends with newline: yes\n"));
+    }
+
+    #[test]
+    fn test_analyze_file_hash_is_the_same_either_way() {
+        let file_path = create_temp_file("hashed.txt", "abc");
+        let hash = ScanOptions {
+            hash: true,
+            ..ScanOptions::default()
+        };
+        for mmap in [MmapMode::Never, MmapMode::Always] {
+            let stats = analyze_file_with(&file_path, mmap, hash, 1).unwrap();
+            assert_eq!(
+                stats.sha256.as_deref(),
+                Some("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad")
+            );
+        }
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_analyze_file_window() {
+        let file_path = create_temp_file("window.txt", "one\r\ntwo\n\nthree\r\n");
+        for (window, lines, crlf, lf, empty) in [
+            ("lines 2..4", 2, 0, 2, 1),
+            ("lines 3..", 2, 1, 1, 1),
+            // A window parts no CRLF: one starting on its `\n` starts after
+            // it, and one ending on its `\r` ends before it.
+            ("bytes 4..9", 1, 0, 1, 0),
+            ("bytes 0..4", 1, 0, 0, 0),
+            ("bytes 9..16", 2, 0, 1, 1),
+            ("bytes 3..17", 4, 2, 2, 2),
+        ] {
+            let scan = ScanOptions {
+                window: Some(window.parse().unwrap()),
+                ..ScanOptions::default()
+            };
+            for mmap in [MmapMode::Never, MmapMode::Always] {
+                let stats = analyze_file_with(&file_path, mmap, scan, 1).unwrap();
+                assert_eq!(
+                    (stats.total_lines, stats.dos_endings, stats.unix_endings),
+                    (lines, crlf, lf),
+                    "{}",
+                    window
+                );
+                assert_eq!(stats.empty_lines, empty, "{}", window);
+                let mut buffer = Vec::new();
+                stats
+                    .display(ReportOptions::default(), false, &mut buffer)
+                    .unwrap();
+                let output = String::from_utf8(buffer).unwrap();
+                assert!(output.contains(&format!("\nWindow: {}\n", window)));
+            }
+        }
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_analyze_file_head() {
+        // Past the read buffer, so that streaming stops before the end.
+        let text = format!("one\r\ntwo\r\n{}", "x\n".repeat(READ_BUFFER_SIZE));
+        let file_path = create_temp_file("head.txt", &text);
+        for (head, lines, crlf, lf, bytes, truncated) in [
+            (Head::Lines(2), 2, 2, 0, 10, true),
+            // The limit falls between the `\r` and `\n` of a CRLF.
+            (Head::Bytes(4), 1, 1, 0, 5, true),
+            (
+                Head::Lines(1 << 20),
+                READ_BUFFER_SIZE + 2,
+                2,
+                READ_BUFFER_SIZE,
+                text.len(),
+                false,
+            ),
+        ] {
+            let scan = ScanOptions {
+                head: Some(head),
+                ..ScanOptions::default()
+            };
+            for mmap in [MmapMode::Never, MmapMode::Always] {
+                let stats = analyze_file_with(&file_path, mmap, scan, 1).unwrap();
+                assert_eq!(
+                    (stats.total_lines, stats.dos_endings, stats.unix_endings),
+                    (lines, crlf, lf),
+                    "{}",
+                    head
+                );
+                assert_eq!(stats.total_bytes, bytes as u64, "{}", head);
+                assert_eq!(stats.truncated(), truncated, "{}", head);
+                assert_eq!(stats.final_newline(), (!truncated).then_some(true));
+            }
+        }
+        let scan = ScanOptions {
+            head: Some(Head::Lines(2)),
+            ..ScanOptions::default()
+        };
+        let stats = analyze_file_with(&file_path, MmapMode::Never, scan, 1).unwrap();
+        let mut buffer = Vec::new();
+        stats
+            .display(ReportOptions::default(), false, &mut buffer)
+            .unwrap();
+        assert!(String::from_utf8(buffer).unwrap().contains(
+            "\nHead: first 2 lines (truncated; 2 lines and 10 bytes examined, \
+             final newline and trailing blank lines not checked)\n"
+        ));
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_head() {
+        let file = create_temp_file("head_run.txt", "a\nb\n\n\nc");
+        let report_path = "head_run_report.json".to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend(["--output".to_string(), report_path.clone(), file.clone()]);
+            run(args)
+        };
+        let checks = [
+            "--fail-on-missing-final-newline",
+            "--max-trailing-blank-lines=0",
+        ];
+        assert!(matches!(
+            run_with(&checks),
+            Err(MdltError::CheckFailed { .. })
+        ));
+        // The head ends on blank lines and mid-file, which says nothing of
+        // how the file ends.
+        let mut args = checks.to_vec();
+        args.extend(["--head-lines=4", "--format=json"]);
+        assert_eq!(run_with(&args), Ok(Exit::Clean));
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains(", \"head\": \"lines 4\", \"truncated\": true, "));
+        let (read, _) = format::json::read(&format::json::parse(&report).unwrap()).unwrap();
+        assert!(read[0].truncated());
+        assert_eq!(read[0].total_lines, 4);
+        assert_eq!(
+            run_with(&["--head-bytes=1K", "--format=json"]),
+            Ok(Exit::Clean)
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains(", \"head\": \"bytes 1024\", \"truncated\": false, "));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_count_patterns() {
+        let regexes = [
+            regex::Regex::new("(?i)todo", regex::Units::Chars).unwrap(),
+            regex::Regex::new("^$", regex::Units::Chars).unwrap(),
+            regex::Regex::new("\\xE9", regex::Units::Bytes).unwrap(),
+        ];
+        let scan = ScanOptions {
+            patterns: Some(pattern::Patterns {
+                regexes: Box::leak(Box::new(regexes)),
+                listed: 10,
+            }),
+            ..ScanOptions::default()
+        };
+        let stats = analyze_bytes_with(
+            b"TODO: a todo\r\n\ncaf\xe9 todo\n",
+            "notes.txt".to_string(),
+            scan,
+        );
+        let mut buffer = Vec::new();
+        stats
+            .display(ReportOptions::default(), false, &mut buffer)
+            .unwrap();
+        let output = String::from_utf8(buffer).unwrap();
+        assert!(output.contains("\nPattern '(?i)todo': 1 matching line (1), 2 matches\n"));
+        assert!(output.contains("\nPattern '^$': 1 matching line (2), 1 match\n"));
+        assert!(output.contains("\nPattern '\\xE9': 1 matching line (3), 1 match\n"));
+    }
+
+    #[test]
+    fn test_analyze_file_unicode_linebreaks() {
+        let file_path = create_temp_file("unicode_breaks.txt", "a\u{85}b\u{2028}c\n");
+        let plain =
+            analyze_file_with(&file_path, MmapMode::Never, ScanOptions::default(), 1).unwrap();
+        assert_eq!(plain.total_lines, 1);
+        let unicode = ScanOptions {
+            unicode_linebreaks: true,
+            ..ScanOptions::default()
+        };
+        for mmap in [MmapMode::Never, MmapMode::Always] {
+            let stats = analyze_file_with(&file_path, mmap, unicode, 1).unwrap();
+            assert_eq!(stats.total_lines, 3);
+            assert_eq!(stats.unix_endings, 1);
+            let mut buffer = Vec::new();
+            stats
+                .display(ReportOptions::default(), false, &mut buffer)
+                .unwrap();
+            let output = String::from_utf8(buffer).unwrap();
+            assert!(output.contains("Next line (NEL, U+0085): 1\n"));
+            assert!(output.contains("Line separators (LS, U+2028): 1\n"));
+            assert!(output.contains("Form feeds (FF): 0\n"));
+        }
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_line_ending_unicode_only() {
+        let mut stats = FileStats::new("test_file.txt".to_string());
+        stats.unicode_breaks = Some(UnicodeBreaks {
+            nel: 2,
+            ..UnicodeBreaks::default()
+        });
+        assert_eq!(stats.line_ending(), LineEndingKind::UnicodeOnly);
+    }
+
+    #[test]
+    fn test_analyze_file_utf16le_notepad() {
+        // "Hi\r\n\r\n" as Notepad writes it: BOM, then 16-bit code units.
+        let bytes = b"\xff\xfeH\x00i\x00\r\x00\n\x00\r\x00\n\x00";
+        let stats = analyze_reader_with(
+            &bytes[..],
+            "notepad.txt".to_string(),
+            ScanOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(stats.bom, Some(Bom::Utf16Le));
+        assert_eq!(stats.total_lines, 2);
+        assert_eq!(stats.empty_lines, 1);
+        assert_eq!(stats.dos_endings, 2);
+        assert_eq!(stats.line_ending(), LineEndingKind::Crlf);
+    }
+
+    #[test]
+    fn test_run_valid_args() {
+        let file_path = create_temp_file("valid.txt", "line1\nline2");
+        let args = vec!["mdlt".to_string(), file_path.clone()];
+        let result = run(args);
+        assert!(result.is_ok());
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_invalid_args() {
+        let args = vec!["mdlt".to_string()];
+        let result = run(args);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_run_error_handling() {
+        let args = vec!["mdlt".to_string(), "non_existent_file.txt".to_string()];
+        let failure = run(args).unwrap_err();
+        assert!(matches!(
+            &failure,
+            MdltError::File { path, source }
+                if path == "non_existent_file.txt" && source.kind() == io::ErrorKind::NotFound
+        ));
+        assert_eq!(failure.status(), 3);
+        assert!(failure
+            .to_string()
+            .starts_with("Error analyzing non_existent_file.txt: "));
+    }
+
+    #[test]
+    fn test_run_format_markdown() {
+        let file_path = create_temp_file("format_markdown.txt", "line1\nline2\n");
+        let args = vec![
+            "mdlt".to_string(),
+            "--format".to_string(),
+            "markdown".to_string(),
+            file_path.clone(),
+        ];
+        assert!(run(args).is_ok());
+        let args = vec![
+            "mdlt".to_string(),
+            "--format=md".to_string(),
+            file_path.clone(),
+        ];
+        assert!(run(args).is_ok());
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_invalid_format() {
+        let args = vec![
+            "mdlt".to_string(),
+            "--format".to_string(),
+            "yaml".to_string(),
+            "file.txt".to_string(),
+        ];
+        let result = run(args);
+        assert!(
+            matches!(result, Err(MdltError::Usage(message)) if message.contains("Unknown format 'yaml'"))
+        );
+
+        let args = vec!["mdlt".to_string(), "--format".to_string()];
+        assert_eq!(
+            run(args),
+            Err(MdltError::Usage("Missing value for --format".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_run_html_output_file() {
+        let file_path = create_temp_file("html_input.txt", "line1\r\nline2\r\n");
+        let report_path = "html_report.html".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--format=html".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            file_path.clone(),
+        ];
+        assert!(run(args).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("<td>DOS/Windows (CRLF)</td>"));
+        assert!(report.contains("<dt>CRLF only</dt><dd>1</dd>"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_template() {
+        let file_path = create_temp_file("template_input.txt", "a\nb\r\n");
+        let report_path = "template_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--template".to_string(),
+            "{ending_type} {lf}/{crlf}".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            file_path.clone(),
+        ];
+        assert!(run(args).is_ok());
+        assert_eq!(
+            fs::read_to_string(&report_path).unwrap(),
+            "Mixed line endings 1/1\n"
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file_path).unwrap();
+
+        let args = vec![
+            "mdlt".to_string(),
+            "--template={bogus}".to_string(),
+            "file.txt".to_string(),
+        ];
+        assert!(
+            matches!(run(args), Err(MdltError::Usage(message)) if message.contains("valid placeholders are"))
+        );
+    }
+
+    #[test]
+    fn test_run_output_append() {
+        let file_path = create_temp_file("append_input.txt", "a\nb\n");
+        let report_path = "append_report.json".to_string();
+        fs::write(&report_path, "stale\n").unwrap();
+        let args = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--format=json".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([
+                "--output".to_string(),
+                report_path.clone(),
+                file_path.clone(),
+            ]);
+            args
+        };
+        assert!(run(args(&[])).is_ok());
+        let once = fs::read_to_string(&report_path).unwrap();
+        assert!(once.starts_with("{\n"));
+        // A failed check still leaves the report behind.
+        let result = run(args(&["--append", "--check=crlf"]));
+        assert!(matches!(result, Err(MdltError::CheckFailed { .. })));
+        let twice = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(twice, format!("{}{}", once, once));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_options() {
+        let file_path = create_temp_file("options_input.txt", "a\r\nb\n");
+        let report_path = "options_report.txt".to_string();
+        let options = cli::Options {
+            fail_on_mixed: true,
+            output: Some(report_path.clone()),
+            paths: vec![file_path.clone()],
+            ..cli::Options::default()
+        };
+        assert!(matches!(
+            run_options(options),
+            Err(MdltError::CheckFailed { .. })
+        ));
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("Line ending type: Mixed line endings"));
+
+        // A file named like a flag is a path after `--`.
+        fs::write("--check", "a\n").unwrap();
+        let args = ["mdlt", "--output", &report_path, "--", "--check"];
+        assert!(run(args.iter().map(|arg| arg.to_string())).is_ok());
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("File name: --check\n"));
+        fs::remove_file("--check").unwrap();
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_get() {
+        let first = create_temp_file("get_a.txt", "a\nb\r\n");
+        let second = create_temp_file("get_b", "a\n");
+        let report_path = "get_report.txt".to_string();
+        let get = |paths: &[&String], fields: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--output".to_string()];
+            args.push(report_path.clone());
+            for field in fields {
+                args.push(format!("--get={}", field));
+            }
+            args.extend(paths.iter().map(|path| path.to_string()));
+            let result = run(args);
+            let output = fs::read_to_string(&report_path).unwrap();
+            (result, output)
+        };
+        let (result, output) = get(&[&first], &["total_lines"]);
+        assert!(result.is_ok());
+        assert_eq!(output, "2\n");
+        let (_, output) = get(&[&second], &["crlf", "extension", "bom"]);
+        assert_eq!(output, "0\t\t\n");
+        let (_, output) = get(&[&first, &second], &["ending_type"]);
+        assert_eq!(
+            output,
+            format!(
+                "{}\tMixed line endings\n{}\tUnix/Linux (LF)\n",
+                first, second
+            )
+        );
+
+        let mut args = vec!["mdlt", "--get", "crlf", "--check", "lf", "--output"];
+        args.extend([report_path.as_str(), first.as_str()]);
+        let result = run(args.into_iter().map(String::from));
+        assert!(matches!(result, Err(MdltError::CheckFailed { .. })));
+        assert_eq!(fs::read_to_string(&report_path).unwrap(), "1\n");
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn test_run_multiple_files() {
+        let first = create_temp_file("multi_a.txt", "a\nb\n");
+        let second = create_temp_file("multi_b.txt", "a\r\n");
+        let report_path = "multi_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            first.clone(),
+            second.clone(),
+        ];
+        assert!(run(args).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 2);
+        let first_pos = report.find("multi_a.txt").unwrap();
+        let second_pos = report.find("multi_b.txt").unwrap();
+        assert!(first_pos < second_pos);
+        assert!(report.contains("\n\nFile Analysis Report"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(first).unwrap();
+        fs::remove_file(second).unwrap();
+    }
+
+    #[test]
+    fn test_run_multiple_files_keeps_going_after_failure() {
+        let existing = create_temp_file("multi_partial.txt", "a\n");
+        let report_path = "multi_partial_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            "missing_one.txt".to_string(),
+            existing.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(err.status(), 3);
+        assert_eq!(err.to_string(), "1 of 2 files could not be analyzed");
+        let MdltError::Files { failures, .. } = &err else {
+            panic!("expected the failures of several files, got {:?}", err);
+        };
+        assert_eq!(failures[0].path(), Some("missing_one.txt"));
+        assert_eq!(failures[0].reason(), "no such file");
+        assert_eq!(failures[0].kind(), "not_found");
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 1);
+        assert!(report.contains("multi_partial.txt"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(existing).unwrap();
+    }
+
+    #[test]
+    fn test_run_directory_points_at_recursive() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_directory_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let dir_arg = dir.display().to_string();
+        let existing = create_temp_file("directory_partial.txt", "a\n");
+        let report_path = "directory_report.txt".to_string();
+        let run_on = |paths: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--output".to_string()];
+            args.push(report_path.clone());
+            args.extend(paths.iter().map(|path| path.to_string()));
+            run(args).unwrap_err()
+        };
+
+        let err = run_on(&[&dir_arg]);
+        assert_eq!(err.status(), 3);
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Error analyzing {}: is a directory; pass --recursive (-r) to analyze the files in it",
+                dir_arg
+            )
+        );
+        assert_eq!(err.kind(), "is_a_directory");
+
+        let err = run_on(&[&existing, &dir_arg]);
+        assert_eq!(err.status(), 3);
+        assert_eq!(err.to_string(), "1 of 2 files could not be analyzed");
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("directory_partial.txt"));
+        assert_eq!(
+            open_file(&dir).unwrap_err().kind(),
+            io::ErrorKind::IsADirectory
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(existing).unwrap();
+        fs::remove_dir(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_fail_fast_stops_without_a_report() {
+        let existing = create_temp_file("fail_fast.txt", "a\n");
+        let report_path = "fail_fast_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-fast".to_string(),
+            "--jobs=1".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            "missing_fast.txt".to_string(),
+            existing.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(err.status(), 3);
+        assert_eq!(
+            err.to_string(),
+            "Error analyzing missing_fast.txt: no such file"
+        );
+        assert!(!Path::new(&report_path).exists());
+        fs::remove_file(existing).unwrap();
+    }
+
+    #[test]
+    fn test_run_max_size_skips_large_files() {
+        let small = create_temp_file("max_size_small.txt", "a\n");
+        let large = create_temp_file("max_size_large.txt", &"x\n".repeat(1024));
+        let report_path = "max_size_report.txt".to_string();
+        let args = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--output".to_string()];
+            args.push(report_path.clone());
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.push(small.clone());
+            args.push(large.clone());
+            args
+        };
+
+        assert!(run(args(&["--max-size", "1K"])).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 1);
+        assert!(report.contains(&format!(
+            "Skipped {} (2048 bytes): larger than --max-size (1024 bytes)\n\nSummary\n",
+            large
+        )));
+        assert!(report.contains("\nFiles scanned: 2\n"));
+        assert!(report.contains("\nSkipped: 1\n"));
+
+        let err = run(args(&["--max-size", "1K", "--fail-on-skip"])).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check(
+                "fail-on-skip",
+                "Skipped 1 of 2 files (--fail-on-skip)".to_string()
+            )
+        );
+        assert!(run(args(&["--max-size", "2K", "--fail-on-skip"])).is_ok());
+
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(small).unwrap();
+        fs::remove_file(large).unwrap();
+    }
+
+    #[test]
+    fn test_run_lists_skipped_files_where_they_were_found() {
+        let large = create_temp_file("in_order_large.txt", &"x\n".repeat(1024));
+        let small = create_temp_file("in_order_small.txt", "a\n");
+        let report_path = "in_order_report.txt".to_string();
+        let report = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--max-size=1K".to_string()];
+            args.extend(["--output".to_string(), report_path.clone()]);
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([large.clone(), small.clone()]);
+            assert!(run(args).is_ok());
+            fs::read_to_string(&report_path).unwrap()
+        };
+
+        let verdicts = format!(
+            "{}: SKIPPED (larger than --max-size (1024 bytes))\n{}: LF (1 line)\n",
+            large, small
+        );
+        assert_eq!(report(&["-q"]), verdicts);
+        assert_eq!(report(&["-q", "--buffer-output"]), verdicts);
+        let csv = report(&[
+            "--format=csv",
+            "--columns=file,bytes,type,status,skip_reason",
+        ]);
+        assert_eq!(
+            csv,
+            format!(
+                "file,bytes,type,status,skip_reason\n\
+                 {},2048,,skipped,larger than --max-size (1024 bytes)\n\
+                 {},2,Unix/Linux (LF),analyzed,\n",
+                large, small
+            )
+        );
+        let json = report(&["--format=json"]);
+        let large_at = json.find("\"skipped\": true").unwrap();
+        assert!(large_at < json.find("in_order_small").unwrap(), "{}", json);
+
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(small).unwrap();
+        fs::remove_file(large).unwrap();
+    }
+
+    #[test]
+    fn test_run_skip_generated() {
+        let source = create_temp_file("generated_source.js", "let a = 1;\n");
+        let bundle = create_temp_file(
+            "generated_bundle.js",
+            &format!("{}\n//# sourceMappingURL=b.map\n", "a=1;".repeat(200)),
+        );
+        let report_path = "generated_report.txt".to_string();
+        let args = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--output".to_string()];
+            args.push(report_path.clone());
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.push(source.clone());
+            args.push(bundle.clone());
+            args
+        };
+
+        assert!(run(args(&["-v"])).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 2);
+        assert_eq!(
+            report
+                .matches("Likely generated/minified: '# sourceMappingURL=' on line 2\n")
+                .count(),
+            1
+        );
+
+        assert!(run(args(&["--skip-generated"])).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 1);
+        assert!(report.contains(&format!(
+            "Skipped {} (828 bytes): likely generated or minified ('# sourceMappingURL=' on line 2)\n",
+            bundle
+        )));
+        assert!(report.contains("\nSkipped: 1\n"));
+
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(source).unwrap();
+        fs::remove_file(bundle).unwrap();
+    }
+
+    #[test]
+    fn test_run_check_encoding() {
+        let valid = create_temp_file("encoding_valid.txt", "caf\u{e9}\n");
+        let invalid = env::temp_dir()
+            .join(format!("mdlt_encoding_invalid_{}.txt", std::process::id()))
+            .to_string_lossy()
+            .into_owned();
+        fs::write(&invalid, b"ok\n\xff\xfe\n").unwrap();
+        let report_path = "encoding_report.txt".to_string();
+        let args = |flag: &str| {
+            vec![
+                "mdlt".to_string(),
+                flag.to_string(),
+                "--output".to_string(),
+                report_path.clone(),
+                valid.clone(),
+                invalid.clone(),
+            ]
+        };
+
+        assert!(run(args("--check-encoding")).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("UTF-8: valid\n"));
+        assert!(report.contains("UTF-8: 2 invalid sequences (first at line 2, byte 3)\n"));
+
+        let err = run(args("--strict-encoding")).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check("encoding", "1 of 2 files are not valid UTF-8".to_string())
+        );
+
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(valid).unwrap();
+        fs::remove_file(invalid).unwrap();
+    }
+
+    #[test]
+    fn test_run_binary_files() {
+        let dir = env::temp_dir().join(format!("mdlt_binary_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let text = dir.join("notes.txt").to_string_lossy().into_owned();
+        let png = dir.join("logo.png").to_string_lossy().into_owned();
+        fs::write(&text, "a\nb\n").unwrap();
+        fs::write(&png, b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00").unwrap();
+        let report_path = dir.join("report.txt").to_string_lossy().into_owned();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--output".to_string()];
+            args.push(report_path.clone());
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            run(args)
+        };
+
+        assert!(run_with(&[&text, &png]).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 1);
+        assert!(report.contains(&format!(
+            "Skipped {} (18 bytes): binary file\n\nSummary\n",
+            png
+        )));
+
+        assert!(run_with(&["--treat-binary=analyze", &text, &png]).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("File Analysis Report").count(), 2);
+        assert_eq!(report.matches(BINARY_WARNING).count(), 1);
+
+        let err = run_with(&["--treat-binary=fail", &text, &png]).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::Files {
+                doing: "analyzed",
+                total: 2,
+                failures: vec![MdltError::BinaryFile { path: png.clone() }],
+            }
+        );
+        assert_eq!(err.to_string(), "1 of 2 files could not be analyzed");
+
+        // A lone binary file is analyzed, with the warning up front.
+        assert!(run_with(&[&png]).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.starts_with(&format!(
+            "File Analysis Report\n====================\n{}\n",
+            BINARY_WARNING
+        )));
+        assert!(run_with(&["--treat-binary", "skip", &png]).is_ok());
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .starts_with("Skipped "));
+        // The summary counts a binary file it skipped among the binary ones.
+        assert!(run_with(&["--treat-binary", "skip", "--summary-only", &png]).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("\nBinary: 1\nSkipped: 1\n"), "{}", report);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_trailing_whitespace_summary() {
+        let mut stats = FileStats::new("a.txt".to_string());
+        stats.trailing_whitespace_lines = 1;
+        stats.trailing_whitespace_at = vec![4];
+        assert_eq!(trailing_whitespace_summary(&stats, true), "1 line (4)");
+        stats.trailing_whitespace_lines = 12;
+        stats.trailing_whitespace_at = vec![1, 2, 3];
+        assert_eq!(
+            trailing_whitespace_summary(&stats, true),
+            "12 lines (1, 2, 3 and 9 more)"
+        );
+    }
+
+    #[test]
+    fn test_run_fail_on_trailing_whitespace() {
+        let clean = create_temp_file("trailing_clean.txt", "a\nb\n");
+        let dirty = create_temp_file("trailing_dirty.txt", "a \nb\n\t\n");
+        let report_path = "trailing_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-on-trailing-whitespace".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            clean.clone(),
+            dirty.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check(
+                "trailing-whitespace",
+                "1 of 2 files have trailing whitespace".to_string()
+            )
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(
+            report
+                .matches("Trailing whitespace: 2 lines (1, 3)\n")
+                .count(),
+            1
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(clean).unwrap();
+        fs::remove_file(dirty).unwrap();
+    }
+
+    #[test]
+    fn test_run_fail_on_missing_final_newline() {
+        let empty = create_temp_file("final_newline_empty.txt", "");
+        let newline = create_temp_file("final_newline_only.txt", "\n");
+        let missing = create_temp_file("final_newline_missing.txt", "a\nb");
+        let report_path = "final_newline_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-on-missing-final-newline".to_string(),
+            "--no-mmap".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            empty.clone(),
+            newline.clone(),
+            missing.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check(
+                "final-newline",
+                "1 of 3 files do not end with a newline".to_string()
+            )
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        let answers: Vec<&str> = report
+            .lines()
+            .filter_map(|line| line.strip_prefix("Ends with newline: "))
+            .collect();
+        assert_eq!(answers, ["not applicable", "yes", "no"]);
+        fs::remove_file(report_path).unwrap();
+        for path in [empty, newline, missing] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_max_trailing_blank_lines() {
+        let one = create_temp_file("blank_one.txt", "a\n\n");
+        let two = create_temp_file("blank_two.txt", "a\r\n\r\n  \r\n");
+        let report_path = "blank_report.txt".to_string();
+        let run_with = |limit: &str| {
+            run(vec![
+                "mdlt".to_string(),
+                "--max-trailing-blank-lines".to_string(),
+                limit.to_string(),
+                "--output".to_string(),
+                report_path.clone(),
+                one.clone(),
+                two.clone(),
+            ])
+        };
+        assert_eq!(
+            run_with("1"),
+            Err(MdltError::check(
+                "trailing-blank-lines",
+                "1 of 2 files end with more than 1 blank line".to_string()
+            ))
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("Trailing blank lines: 1\n"));
+        assert!(report.contains("Trailing blank lines: 2\n"));
+        assert_eq!(run_with("2"), Ok(Exit::Clean));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(one).unwrap();
+        fs::remove_file(two).unwrap();
+    }
+
+    #[test]
+    fn test_run_max_blank_run() {
+        let file = create_temp_file("blank_runs.txt", "a\n\n\nb\n\n\n\n\nc\n\nd\n");
+        let report_path = "blank_runs_report.txt".to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend(["--output".to_string(), report_path.clone(), file.clone()]);
+            run(args)
+        };
+        assert_eq!(run_with(&[]), Ok(Exit::Clean));
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("Blank-line runs: 3 (longest: 4 lines starting at line 5)\n"));
+        assert_eq!(
+            run_with(&["--max-blank-run", "1"]),
+            Err(MdltError::check(
+                "blank-runs",
+                "1 of 1 file has more than 1 empty line in a row".to_string()
+            ))
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("over --max-blank-run)\n"));
+        assert!(report.contains(
+            "line 5: the longest of 2 runs of more than 1 blank lines, 4 in a row [blank-runs]"
+        ));
+        assert_eq!(
+            run_with(&["--max-blank-run", "1", "--disable", "blank-runs"]),
+            Ok(Exit::Clean)
+        );
+        assert_eq!(run_with(&["--max-blank-run", "4"]), Ok(Exit::Clean));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_run_tsv() {
+        let file = create_temp_file("tsv\tname.txt", "a\r\nb\n");
+        let report_path = "tsv_report.tsv".to_string();
+        let result = run(vec![
+            "mdlt".to_string(),
+            "--format=tsv".to_string(),
+            "--columns=file,lines,crlf,lf".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            file.clone(),
+        ]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert_eq!(
+            fs::read_to_string(&report_path).unwrap(),
+            format!(
+                "file\tlines\tcrlf\tlf\n{}\t2\t1\t1\n",
+                file.replace('\t', "\\t")
+            )
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file).unwrap();
+    }
+
+    #[test]
+    fn test_indentation_summary() {
+        let mut indentation = Indentation::default();
+        assert_eq!(indentation.summary(true), "none");
+        assert!(!indentation.is_mixed());
+        indentation.spaces = 312;
+        indentation.width = Some(4);
+        indentation.tabs = 2;
+        assert_eq!(
+            indentation.summary(true),
+            "spaces (312 lines, width 4), tabs (2 lines)"
+        );
+        assert!(indentation.is_mixed());
+        indentation.spaces = 0;
+        indentation.width = None;
+        indentation.mixed = 3;
+        assert_eq!(indentation.summary(true), "mixed (3 lines), tabs (2 lines)");
+        indentation.tabs = 0;
+        assert!(indentation.is_mixed());
+    }
+
+    #[test]
+    fn test_run_fail_on_mixed_indentation() {
+        let spaces = create_temp_file("indent_spaces.txt", "a\n  b\n");
+        let both = create_temp_file("indent_both.txt", "a\n  b\n\tc\n");
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-on-mixed-indentation".to_string(),
+            "--format=json".to_string(),
+            "--output".to_string(),
+            "indent_report.json".to_string(),
+            spaces.clone(),
+            both.clone(),
+        ];
+        assert_eq!(
+            run(args),
+            Err(MdltError::check(
+                "mixed-indentation",
+                "1 of 2 files mix tab and space indentation".to_string()
+            ))
+        );
+        fs::remove_file("indent_report.json").unwrap();
+        fs::remove_file(spaces).unwrap();
+        fs::remove_file(both).unwrap();
+    }
+
+    #[test]
+    fn test_run_fail_on_mixed() {
+        let dir = "fail_on_mixed_tree";
+        fs::create_dir_all(format!("{}/sub", dir)).unwrap();
+        fs::write(format!("{}/dos.txt", dir), "a\r\nb\r\n").unwrap();
+        fs::write(format!("{}/sub/unix.txt", dir), "a\nb\n").unwrap();
+        let run_with = |format: &str| {
+            run(vec![
+                "mdlt".to_string(),
+                "--fail-on-mixed".to_string(),
+                "-r".to_string(),
+                format!("--format={}", format),
+                "--output=fail_on_mixed_report".to_string(),
+                dir.to_string(),
+            ])
+        };
+        for format in ["text", "json", "csv"] {
+            assert_eq!(run_with(format), Ok(Exit::Clean), "{}", format);
+        }
+        let mixed = format!("{}/sub/mixed.txt", dir);
+        fs::write(&mixed, "a\nb\nc\r\nd\n\re\n").unwrap();
+        for format in ["text", "json", "csv"] {
+            assert_eq!(
+                run_with(format),
+                Err(MdltError::check(
+                    "mixed-endings",
+                    format!(
+                        "1 of 3 files mix line endings:\n  {}: 4 LF, 1 CRLF, 1 CR",
+                        mixed
+                    )
+                )),
+                "{}",
+                format
+            );
+        }
+        let tolerant = |limits: &[&str]| {
+            let mut args = vec![
+                "mdlt".to_string(),
+                "--fail-on-mixed".to_string(),
+                "--output=fail_on_mixed_report".to_string(),
+            ];
+            args.extend(limits.iter().map(|limit| limit.to_string()));
+            args.push(mixed.clone());
+            run(args)
+        };
+        assert_eq!(tolerant(&["--max-minority-endings=2"]), Ok(Exit::Clean));
+        assert!(fs::read_to_string("fail_on_mixed_report")
+            .unwrap()
+            .contains("16.6% CR (within tolerance)\n"));
+        assert!(tolerant(&["--max-minority-endings=1"]).is_err());
+        assert!(tolerant(&["--max-minority-endings=2", "--max-minority-percent=30"]).is_err());
+        assert_eq!(
+            tolerant(&["--max-minority-percent=40", "--check=consistent"]),
+            Ok(Exit::Clean)
+        );
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file("fail_on_mixed_report").unwrap();
+    }
+
+    #[test]
+    fn test_run_list() {
+        let dos = create_temp_file("list_dos.txt", "a\r\nb\r\n");
+        let unix = create_temp_file("list_unix.txt", "a \nb");
+        let empty = create_temp_file("list_empty.txt", "");
+        let report_path = "list_report".to_string();
+        let list = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), format!("--output={}", report_path)];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([dos.clone(), unix.clone(), empty.clone()]);
+            assert_eq!(run(args), Ok(Exit::Clean));
+            fs::read_to_string(&report_path).unwrap()
+        };
+        assert_eq!(list(&["--list=crlf"]), format!("{}\n", dos));
+        assert_eq!(list(&["--list", "none"]), format!("{}\n", empty));
+        assert_eq!(
+            list(&["--list=trailing-whitespace", "--list=crlf", "--print0"]),
+            format!("{}\0{}\0", dos, unix)
+        );
+        assert_eq!(
+            list(&["--list=missing-final-newline,mixed", "--format=json"]),
+            format!("{}\n", unix)
+        );
+        for path in [dos, unix, empty, report_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_only_problems() {
+        let clean = create_temp_file("only_problems_clean.txt", "a\nb\n");
+        let mixed = create_temp_file("only_problems_mixed.txt", "a\r\nb\n");
+        let spaced = create_temp_file("only_problems_spaced.txt", "a \nb\n");
+        let report_path = "only_problems_report".to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), format!("--output={}", report_path)];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([clean.clone(), mixed.clone(), spaced.clone()]);
+            let result = run(args);
+            (result, fs::read_to_string(&report_path).unwrap())
+        };
+
+        let (result, report) = run_with(&["--only-problems"]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(report.contains(&format!("File name: {}\n", mixed)));
+        assert!(!report.contains(&clean) && !report.contains(&spaced));
+        // The summary covers every file scanned, not just those reported.
+        assert!(report.contains("Ends with newline: yes\n\nSummary\n=======\nFiles scanned: 3\n"));
+        assert!(report.ends_with(" CR\n\n3 files scanned, 1 with problems\n"));
+
+        let (result, report) = run_with(&[
+            "--only-problems",
+            "--fail-on-trailing-whitespace",
+            "--format=json",
+        ]);
+        assert_eq!(
+            result,
+            Err(MdltError::check(
+                "trailing-whitespace",
+                "1 of 3 files have trailing whitespace".to_string()
+            ))
+        );
+        // JSON escapes the backslash in the paths.
+        assert!(report.contains("only_problems_spaced.txt"));
+        assert!(!report.contains("only_problems_mixed.txt") && !report.contains("scanned"));
+
+        let (result, report) = run_with(&["--only-problems", "--check=consistent"]);
+        assert_eq!(
+            result,
+            Err(MdltError::check(
+                "endings",
+                "1 of 3 files fail --check consistent".to_string()
+            ))
+        );
+        assert_eq!(
+            report,
+            format!(
+                "{}: mixed line endings: 1 CRLF and 1 LF\n3 files scanned, 1 with problems\n",
+                mixed
+            )
+        );
+
+        let (result, report) = run_with(&["--summary-only"]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(report.starts_with("Summary\n=======\nFiles scanned: 3\nLF only: 2\n"));
+        assert!(report.contains("\nMixed: 1\n") && !report.contains("File name"));
+        let (_, report) = run_with(&["--summary-only", "--format=json"]);
+        assert!(report
+            .starts_with("{\n  \"version\": 2,\n  \"summary\": {\"files\": 3, \"lf_files\": 2, "));
+        assert!(!report.contains("\"files\": ["));
+        for path in [clean, mixed, spaced, report_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_quiet() {
+        let unix = create_temp_file("quiet_unix.txt", "a\nb\n");
+        let mixed = create_temp_file("quiet_mixed.txt", "a\r\nb\nc\n");
+        let report_path = "quiet_report".to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), format!("--output={}", report_path)];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([unix.clone(), mixed.clone()]);
+            let result = run(args);
+            (result, fs::read_to_string(&report_path).unwrap())
+        };
+
+        let (result, report) = run_with(&["-q"]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert_eq!(
+            report,
+            format!("{}: LF (2 lines)\n{}: MIXED (1 CRLF / 2 LF)\n", unix, mixed)
+        );
+        // The violations are still listed at -q, but left to the exit
+        // status at -qq.
+        let (result, report) = run_with(&["-q", "--fail-on-mixed"]);
+        assert!(result
+            .unwrap_err()
+            .to_string()
+            .starts_with("1 of 2 files mix"));
+        assert_eq!(report.lines().count(), 2);
+        let (result, report) = run_with(&["-qq", "--fail-on-mixed"]);
+        assert_eq!(result, Err(MdltError::checks(Vec::new())));
+        assert_eq!(report, "");
+        let (result, report) = run_with(&["-qq", "--check=consistent"]);
+        assert_eq!(result.map_err(|failure| failure.status()), Err(1));
+        assert_eq!(report, "");
+        for path in [unix, mixed, report_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_relative_to() {
+        let dir = "relative_to_tree";
+        fs::create_dir_all(format!("{}/sub", dir)).unwrap();
+        fs::write(format!("{}/top.txt", dir), "a\n").unwrap();
+        fs::write(format!("{}/sub/dos.txt", dir), "a\r\n").unwrap();
+        let report_path = "relative_to_report";
+        let run_with = |base: &str, extra: &str| {
+            let result = run(vec![
+                "mdlt".to_string(),
+                format!("--relative-to={}", base),
+                "--list=lf,crlf".to_string(),
+                format!("--output={}", report_path),
+                extra.to_string(),
+                format!("./{}/sub/../sub/dos.txt", dir),
+                format!("{}/top.txt", dir),
+            ]);
+            (result, fs::read_to_string(report_path).unwrap())
+        };
+
+        let (result, report) = run_with(dir, "--slash");
+        assert_eq!(result, Ok(Exit::Clean));
+        assert_eq!(report, "sub/dos.txt\ntop.txt\n");
+        // A file outside the base is shown by its absolute path.
+        let (_, report) = run_with(&format!("{}/sub", dir), "--slash");
+        let top = env::current_dir().unwrap().join(dir).join("top.txt");
+        assert_eq!(report, format!("dos.txt\n{}\n", top.display()));
+        // Only the names change, not what is checked.
+        let (result, _) = run_with(&format!("{}/sub", dir), "--check=lf");
+        assert_eq!(
+            result,
+            Err(MdltError::check(
+                "endings",
+                "1 of 2 files fail --check lf".to_string()
+            ))
+        );
+
+        assert_eq!(
+            run(vec![
+                "mdlt".to_string(),
+                "--relative-to=relative_to_missing".to_string(),
+                dir.to_string(),
+            ]),
+            Err(MdltError::Usage(
+                "--relative-to relative_to_missing: not a directory".to_string()
+            ))
+        );
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file(report_path).unwrap();
+    }
+
+    #[test]
+    fn test_sort_files() {
+        let file = |name: &str, [dos, unix, mac]: [usize; 3]| {
+            let mut stats = FileStats::new(name.to_string());
+            stats.dos_endings = dos;
+            stats.unix_endings = unix;
+            stats.mac_endings = mac;
+            stats.total_lines = dos + unix + mac;
+            stats.total_bytes = (2 * dos + unix + mac) as u64;
+            stats
+        };
+        let mut stats = vec![
+            file("d.txt", [2, 5, 0]),
+            file("b.txt", [0, 9, 0]),
+            file("c.txt", [1, 4, 2]),
+            file("a.txt", [2, 0, 0]),
+        ];
+        let mut sorted = |value: &str| {
+            sort_files(&mut stats, value.parse().unwrap());
+            stats
+                .iter()
+                .map(|file| file.display_name())
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(sorted("name"), "a.txt b.txt c.txt d.txt");
+        assert_eq!(sorted("name:desc"), "d.txt c.txt b.txt a.txt");
+        // Ties fall back to the name, ascending, in either direction.
+        assert_eq!(sorted("lines:desc"), "b.txt c.txt d.txt a.txt");
+        assert_eq!(sorted("crlf:desc"), "a.txt d.txt c.txt b.txt");
+        assert_eq!(sorted("crlf"), "b.txt c.txt a.txt d.txt");
+        assert_eq!(sorted("mixed:desc"), "c.txt d.txt a.txt b.txt");
+        assert_eq!(sorted("size:desc"), "b.txt d.txt c.txt a.txt");
+    }
+
+    #[test]
+    fn test_mixed_indent_summary() {
+        let mut mixed = MixedIndentLines::default();
+        assert_eq!(
+            mixed_indent_summary(&mixed, true),
+            "Mixed indentation: none"
+        );
+        mixed.count = 4;
+        mixed.first = vec![23, 40];
+        assert_eq!(
+            mixed_indent_summary(&mixed, true),
+            "Mixed indentation on 4 lines (first: 23)"
+        );
+    }
+
+    #[test]
+    fn test_run_check_mixed_indent() {
+        let path = create_temp_file("mixed_indent.txt", "a\n\t  b\n  \tc\n");
+        let report_path = "mixed_indent_report.txt".to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt", "--output", &report_path];
+            args.extend_from_slice(extra);
+            args.push(&path);
+            run(args.into_iter().map(String::from))
+        };
+        assert_eq!(run_with(&[]), Ok(Exit::Clean));
+        assert!(!fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("Mixed indentation"));
+        assert_eq!(
+            run_with(&["--check", "mixed-indent"]),
+            Err(MdltError::check(
+                "mixed-indent",
+                "1 of 1 file has lines with mixed indentation".to_string()
+            ))
+        );
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("Mixed indentation on 1 line (first: 3)\n"));
+        assert!(run_with(&["--strict-mixed-indent"]).is_err());
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("Mixed indentation on 2 lines (first: 2)\n"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_long_lines_summary() {
+        let mut long = LongLines {
+            limit: 120,
+            ..LongLines::default()
+        };
+        assert_eq!(
+            long_lines_summary(&long, true),
+            "No lines exceed 120 columns"
+        );
+        long.count = 1;
+        long.first = vec![7];
+        assert_eq!(
+            long_lines_summary(&long, true),
+            "1 line exceeds 120 columns: 7"
+        );
+        long.count = 5;
+        long.first = vec![14, 87, 203];
+        assert_eq!(
+            long_lines_summary(&long, true),
+            "5 lines exceed 120 columns: 14, 87, 203 and 2 more"
+        );
+    }
+
+    #[test]
+    fn test_run_max_line_length() {
+        let path = create_temp_file("long_lines.txt", "short\nthis line is long\nok\n");
+        let report_path = "long_lines_report.txt".to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args = vec!["mdlt", "--max-line-length", "10", "--output", &report_path];
+            args.extend_from_slice(extra);
+            args.push(&path);
+            run(args.into_iter().map(String::from))
+        };
+        assert_eq!(
+            run_with(&[]),
+            Err(MdltError::check(
+                "line-length",
+                "1 of 1 file has lines longer than --max-line-length".to_string()
+            ))
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("1 line exceeds 10 columns: 2\n"));
+        assert_eq!(run_with(&["--no-fail"]), Ok(Exit::Clean));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_run_fail_on_control_chars() {
+        let clean = create_temp_file("control_clean.txt", "a\tb\n");
+        let dirty = create_temp_file("control_dirty.txt", "a\n\x00b\x01\n");
+        let report_path = "control_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-on-control-chars".to_string(),
+            "--treat-binary=analyze".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            clean.clone(),
+            dirty.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check(
+                "control-chars",
+                "1 of 2 files contain NUL bytes or control characters".to_string()
+            )
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(
+            report.matches("NUL bytes: 1 (first on line 2)\n").count(),
+            1
+        );
+        assert_eq!(
+            report
+                .matches("Control characters: 1 (first on line 2)\n")
+                .count(),
+            1
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(clean).unwrap();
+        fs::remove_file(dirty).unwrap();
+    }
+
+    #[test]
+    fn test_run_checks() {
+        let clean = create_temp_file("checks_clean.txt", "a\n");
+        let spaced = create_temp_file("checks_spaced.txt", "a \nb");
+        let report_path = "checks_report".to_string();
+        let checked = |format: &str| {
+            let args = vec![
+                "mdlt".to_string(),
+                "--enable".to_string(),
+                "trailing-whitespace,final-newline".to_string(),
+                format!("--format={}", format),
+                "--output".to_string(),
+                report_path.clone(),
+                clean.clone(),
+                spaced.clone(),
+            ];
+            let err = run(args).unwrap_err();
+            assert_eq!(
+                err,
+                MdltError::checks(vec![
+                    CheckFailure::new(
+                        "trailing-whitespace",
+                        "1 of 2 files have trailing whitespace".to_string()
+                    ),
+                    CheckFailure::new(
+                        "final-newline",
+                        "1 of 2 files do not end with a newline".to_string()
+                    ),
+                ])
+            );
+            fs::read_to_string(&report_path).unwrap()
+        };
+        assert!(checked("text").contains(
+            "Violations:\n  error: line 1: trailing whitespace [trailing-whitespace]\n  \
+             error: line 2: no newline at the end of the file [final-newline]\n"
+        ));
+        let json = checked("json");
+        assert_eq!(json.matches("\"violations\"").count(), 1);
+        assert!(json.contains(
+            "\"violations\": [{\"check\": \"trailing-whitespace\", \"severity\": \"error\", \
+             \"line\": 1, \"message\": \"trailing whitespace\", \"suppressed\": false}, \
+             {\"check\": \"final-newline\", \
+             \"severity\": \"error\", \"line\": 2, \"message\": \"no newline at the end of the file\", \
+             \"suppressed\": false}]"
+        ));
+        assert!(checked("table").contains("trailing-whitespace, final-newline"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(clean).unwrap();
+        fs::remove_file(spaced).unwrap();
+    }
+
+    #[test]
+    fn test_run_severity() {
+        let clean = create_temp_file("severity_clean.txt", "a\n");
+        let spaced = create_temp_file("severity_spaced.txt", "a \nb");
+        let report_path = "severity_report.txt".to_string();
+        let checked = |extra: &[&str]| {
+            let mut args: Vec<String> = [
+                "mdlt",
+                "--enable=trailing-whitespace,final-newline",
+                "--output",
+                &report_path,
+            ]
+            .iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect();
+            args.extend([clean.clone(), spaced.clone()]);
+            run(args)
+        };
+        // Only errors fail the run.
+        assert_eq!(
+            checked(&["--severity=final-newline=warning"]),
+            Err(MdltError::check(
+                "trailing-whitespace",
+                "1 of 2 files have trailing whitespace".to_string()
+            ))
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains(
+            "Violations:\n  error: line 1: trailing whitespace [trailing-whitespace]\n  \
+             warning: line 2: no newline at the end of the file [final-newline]\n"
+        ));
+        assert!(report.contains("Violations: 1 error, 1 warning, 0 info, 0 suppressed\n"));
+        let lenient = ["--severity=trailing-whitespace=warning,final-newline=info"];
+        assert_eq!(checked(&lenient), Ok(Exit::Clean));
+        assert_eq!(
+            checked(&[lenient[0], "--warnings-as-errors"]),
+            Err(MdltError::check(
+                "trailing-whitespace",
+                "1 of 2 files have trailing whitespace".to_string()
+            ))
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(clean).unwrap();
+        fs::remove_file(spaced).unwrap();
+    }
+
+    #[test]
+    fn test_run_suppressions() {
+        let marked = create_temp_file(
+            "suppressed_marked.txt",
+            "# mdlt:ignore-file final-newline\n\
+             a \n\
+             b   # mdlt:ignore-line trailing-whitespace \n\
+             c  # mdlt:ignore-line line-length \n\
+             end",
+        );
+        let report_path = "suppressed_report".to_string();
+        let checked = |extra: &[&str]| {
+            let mut args: Vec<String> = [
+                "mdlt",
+                "--enable=trailing-whitespace,final-newline",
+                "--output",
+                &report_path,
+            ]
+            .iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect();
+            args.push(marked.clone());
+            let result = run(args);
+            (result, fs::read_to_string(&report_path).unwrap())
+        };
+        // Line 2 has no marker, and line 4's is for another check.
+        let (result, report) = checked(&[]);
+        assert_eq!(
+            result,
+            Err(MdltError::check(
+                "trailing-whitespace",
+                "1 of 1 file has trailing whitespace".to_string()
+            ))
+        );
+        assert!(report.contains(
+            "Violations:\n  error: line 2: trailing whitespace [trailing-whitespace]\n  \
+             error: line 4: trailing whitespace [trailing-whitespace]\n\
+             Suppressed violations: 2 (--show-suppressed lists them)\n"
+        ));
+        let (_, report) = checked(&["--show-suppressed"]);
+        assert!(report.contains(
+            "Suppressed violations: 2\n  \
+             error: line 3: trailing whitespace [trailing-whitespace]\n  \
+             error: line 5: no newline at the end of the file [final-newline]\n"
+        ));
+        let (_, json) = checked(&["--format=json"]);
+        assert_eq!(json.matches("\"suppressed\": true").count(), 2);
+        assert!(json.contains(
+            "\"suppressions\": [{\"scope\": \"file\", \"line\": 1, \
+             \"checks\": [\"final-newline\"]}, {\"scope\": \"line\", \"line\": 3, \
+             \"checks\": [\"trailing-whitespace\"]}, {\"scope\": \"line\", \"line\": 4, \
+             \"checks\": [\"line-length\"]}]"
+        ));
+        // Silencing every finding leaves nothing to fail on.
+        fs::write(&marked, "a \t# mdlt:ignore-line \n").unwrap();
+        assert_eq!(checked(&[]).0, Ok(Exit::Clean));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(marked).unwrap();
+    }
+
+    #[test]
+    fn test_run_top_lines() {
+        let a = create_temp_file("top_lines_a.txt", "short\nthe longest\n\tmid\nlonger\n");
+        let b = create_temp_file("top_lines_b.txt", "x\n");
+        let report_path = "top_lines_report".to_string();
+        let report = |extra: &[&str]| {
+            let mut args: Vec<String> = ["mdlt", "--top-lines=2", "--output", &report_path]
+                .iter()
+                .chain(extra)
+                .map(|arg| arg.to_string())
+                .collect();
+            args.extend([a.clone(), b.clone()]);
+            assert_eq!(run(args), Ok(Exit::Clean));
+            fs::read_to_string(&report_path).unwrap()
+        };
+        let text = report(&[]);
+        assert!(text.contains(
+            "Longest 2 lines:\n  1. line 2  11 bytes  the longest\n  2. line 4   6 bytes  longer\n"
+        ));
+        assert!(text.contains("Longest 1 line:\n  1. line 1  1 byte   x\n"));
+        let text = report(&["--tab-width=8", "--lines=3.."]);
+        assert!(text.contains(
+            "Longest 2 lines:\n  1. line 2  6 bytes   6 columns  longer\n  2. line 1  4 bytes  11 columns  \\tmid\n"
+        ));
+        let json = report(&["--format=json"]);
+        assert!(json.contains(
+            "\"top_lines\": [{\"line\": 2, \"length\": 11}, {\"line\": 4, \"length\": 6}]"
+        ));
+        let json = report(&["--format=json", "--preview"]);
+        assert!(json.contains("{\"line\": 2, \"length\": 11, \"preview\": \"the longest\"}"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+    }
+
+    #[test]
+    fn test_run_phantom_blank_lines() {
+        let phantom = create_temp_file("phantom_half.txt", "a\n\r\nb\n\r\nc\n");
+        let spaced = create_temp_file("phantom_spaced.txt", "a \nb\n");
+        let report_path = "phantom_report".to_string();
+        let report = |extra: &[&str]| {
+            let mut args: Vec<String> = ["mdlt", "--output", &report_path]
+                .iter()
+                .chain(extra)
+                .map(|arg| arg.to_string())
+                .collect();
+            args.extend([phantom.clone(), spaced.clone()]);
+            // `--fail-on-trailing-whitespace` fails the run, after the
+            // report.
+            let _ = run(args);
+            fs::read_to_string(&report_path).unwrap()
+        };
+        let text = report(&[]);
+        assert!(text.contains("Empty lines: 2, "));
+        assert!(text.contains("Phantom blank lines (lone CR content): 2, first at line 2\n"));
+        // Whatever the checks, the file is shown among the problems.
+        let text = report(&["--only-problems", "--fail-on-trailing-whitespace"]);
+        assert!(text.contains("phantom_half.txt") && text.contains("phantom_spaced.txt"));
+        let json = report(&["--format=json"]);
+        assert!(json.contains("\"phantom_blank_lines\": 2, \"phantom_blank_at\": [2, 4], "));
+        let (read, _) = format::json::read(&format::json::parse(&json).unwrap()).unwrap();
+        assert_eq!(read[0].phantom_blank_at, vec![2, 4]);
+        assert_eq!(
+            report(&["--get=phantom_blank_lines"]),
+            format!("{}\t2\n{}\t0\n", phantom, spaced)
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(phantom).unwrap();
+        fs::remove_file(spaced).unwrap();
+    }
+
+    #[test]
+    fn test_run_peek() {
+        let a = create_temp_file("peek_a.txt", "\n  \t\n#!/bin/sh\nmiddle\n\tlast line");
+        let b = create_temp_file("peek_b.txt", "  \n\t\n");
+        let report_path = "peek_report".to_string();
+        let report = |extra: &[&str]| {
+            let mut args: Vec<String> = ["mdlt", "--output", &report_path]
+                .iter()
+                .chain(extra)
+                .map(|arg| arg.to_string())
+                .collect();
+            args.extend([a.clone(), b.clone()]);
+            assert_eq!(run(args), Ok(Exit::Clean));
+            fs::read_to_string(&report_path).unwrap()
+        };
+        assert!(!report(&[]).contains("First line:"));
+        let text = report(&["--peek"]);
+        assert!(text.contains("First line: #!/bin/sh\nLast line: \\tlast line\n"));
+        assert!(text.contains("First line: none\nLast line: none\n"));
+        let text = report(&["--peek-width=4"]);
+        assert!(text.contains("First line: #!/b…\nLast line: \\tlas…\n"));
+        assert!(!report(&["--format=json"]).contains("first_line_preview"));
+        let json = report(&["--format=json", "--peek"]);
+        assert!(json.contains(
+            "\"first_line_preview\": \"#!/bin/sh\", \"last_line_preview\": \"\\\\tlast line\""
+        ));
+        assert!(json.contains("\"first_line_preview\": null, \"last_line_preview\": null"));
+        let (read, _) = format::json::read(&format::json::parse(&json).unwrap()).unwrap();
+        assert_eq!(
+            read[0].peek.as_ref().and_then(|peek| peek.last.as_deref()),
+            Some("\\tlast line")
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run_external_check() {
+        let script = create_temp_file(
+            "external_tabs.sh",
+            "if grep -q \"$(printf '\\t')\" \"$1\"; then printf 'tab in %s\\n' \"$1\"; exit 1; fi\n",
+        );
+        let a = create_temp_file("external_a.yml", "key:\tvalue\n");
+        let b = create_temp_file("external_b.yml", "key: value\n");
+        let c = create_temp_file("external_c.yml", "# mdlt:ignore-file tabs\nkey:\tvalue\n");
+        let report_path = "external_report".to_string();
+        let tabs = format!("--external-check=tabs:sh '{}'", script);
+        let report = |extra: &[&str]| {
+            let mut args: Vec<String> = ["mdlt", "--output", &report_path]
+                .iter()
+                .chain(extra)
+                .map(|arg| arg.to_string())
+                .collect();
+            args.extend([a.clone(), b.clone(), c.clone()]);
+            let result = run(args);
+            (result, fs::read_to_string(&report_path).unwrap())
+        };
+        let (result, text) = report(&[&tabs]);
+        match result {
+            Err(failure) => assert_eq!(
+                failure.check_failures(),
+                [CheckFailure::new(
+                    "tabs",
+                    "1 of 3 files fail --external-check tabs".to_string()
+                )]
+            ),
+            other => panic!("{:?}", other),
+        }
+        assert!(text.contains(&format!("tab in {} [tabs]", a)));
+        let (result, json) = report(&[&tabs, "--severity=tabs=warning", "--format=json"]);
+        assert!(matches!(result, Ok(Exit::Clean)));
+        for (path, suppressed) in [(&a, false), (&c, true)] {
+            assert!(json.contains(&format!(
+                "{{\"check\": \"tabs\", \"severity\": \"warning\", \"line\": null, \
+                 \"message\": {}, \"suppressed\": {}}}",
+                format::json::string(&format!("tab in {}", path)),
+                suppressed
+            )));
+        }
+        // The file comes on stdin with `{stdin}`, and a command that
+        // cannot run is told of once.
+        let (result, _) = report(&[
+            "--external-check=clean:! grep -q value {stdin}",
+            "--external-check=gone:mdlt-no-such-command",
+        ]);
+        match result {
+            Err(MdltError::CheckFailed { failures }) => {
+                assert_eq!(failures.len(), 2);
+                assert_eq!(failures[0].check, "clean");
+                assert_eq!(
+                    failures[0].message,
+                    "3 of 3 files fail --external-check clean"
+                );
+                assert_eq!(failures[1].check, "external-check");
+                assert!(failures[1]
+                    .message
+                    .starts_with("--external-check gone could not run on 3 of 3 files: "));
+            }
+            other => panic!("{:?}", other),
+        }
+        for path in [report_path, script, a, b, c] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_budgets() {
+        let a = create_temp_file("budget_a.txt", "one \ntwo \n");
+        let b = create_temp_file("budget_b.txt", "three\r\n");
+        let c = create_temp_file("budget_c.txt", "four\n");
+        let report_path = "budget_report".to_string();
+        let report = |extra: &[&str]| {
+            let mut args: Vec<String> = [
+                "mdlt",
+                "--enable=trailing-whitespace",
+                "--check=lf",
+                "--output",
+                &report_path,
+            ]
+            .iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect();
+            args.extend([a.clone(), b.clone(), c.clone()]);
+            (run(args), fs::read_to_string(&report_path).unwrap())
+        };
+        let (result, text) = report(&["--max-problem-files=2"]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(
+            text.ends_with("\n2 problem files, budget 2 — OK\n"),
+            "{}",
+            text
+        );
+        // Both budgets have to hold.
+        let (result, _) = report(&["--max-problem-files=5", "--max-problems=2"]);
+        let Err(failure @ MdltError::CheckFailed { .. }) = result else {
+            panic!("{:?}", result);
+        };
+        let message = failure.to_string();
+        assert!(message.starts_with("3 problems, budget 2 — over by 1:\n  "));
+        assert!(
+            message.ends_with("budget_b.txt: expected LF, found 1 CRLF"),
+            "{}",
+            message
+        );
+        let (result, json) = report(&[
+            "--format=json",
+            "--max-problems=3",
+            "--severity=trailing-whitespace=warning",
+        ]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(json.contains(
+            "\"budgets\": [{\"counts\": \"problems\", \"budget\": 3, \"observed\": 1, \"verdict\": \"ok\"}]"
+        ), "{}", json);
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+        fs::remove_file(c).unwrap();
+    }
+
+    #[test]
+    fn test_run_tree() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_tree_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "a\n").unwrap();
+        fs::write(dir.join("sub").join("b.txt"), "b\r\n").unwrap();
+        let report_path = dir.join("report.txt").to_str().unwrap().to_string();
+        let args: Vec<String> = [
+            "mdlt",
+            "--tree",
+            "--ascii",
+            "--only-problems",
+            "--check=lf",
+            "--relative-to",
+            dir.to_str().unwrap(),
+            "--output",
+            &report_path,
+            dir.join("a.txt").to_str().unwrap(),
+            dir.join("sub").join("b.txt").to_str().unwrap(),
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        assert!(matches!(run(args), Err(MdltError::CheckFailed { .. })));
+        // Every file counts, whatever `--only-problems` leaves out.
+        assert_eq!(
+            fs::read_to_string(&report_path).unwrap(),
+            concat!(
+                ".         2 files: 1 LF, 1 CRLF  [1 problem file]\n",
+                "`-- sub/  1 file: 1 CRLF  [1 problem file]\n",
+                "\n",
+                "2 files scanned, 1 with problems\n",
+            )
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_timing() {
+        use format::json::{parse, Value};
+        let a = create_temp_file("timing_a.txt", "one\ntwo\n");
+        let b = create_temp_file("timing_b.txt", "three\r\n");
+        let report_path = "timing_report.json".to_string();
+        let args: Vec<String> = [
+            "mdlt",
+            "--timing",
+            "--jobs=2",
+            "--format=json",
+            "--output",
+            &report_path,
+            &a,
+            &b,
+        ]
+        .iter()
+        .map(|arg| arg.to_string())
+        .collect();
+        assert_eq!(run(args), Ok(Exit::Clean));
+        let report = parse(&fs::read_to_string(&report_path).unwrap()).unwrap();
+        let timing = report.get("summary").unwrap().get("timing").unwrap();
+        // Durations vary from run to run, so only their presence and sign
+        // are checked.
+        let number = |value: &Value, key: &str| match value.get(key) {
+            Some(Value::Number(number)) => number.parse::<f64>().unwrap(),
+            other => panic!("expected a number under {}, got {:?}", key, other),
+        };
+        for key in [
+            "wall_seconds",
+            "mb_per_second",
+            "read_seconds",
+            "analyze_seconds",
+            "cpu_seconds",
+            "speedup",
+        ] {
+            assert!(number(timing, key) >= 0.0, "{}", key);
+        }
+        assert_eq!(number(timing, "bytes"), 15.0);
+        assert_eq!(number(timing, "jobs"), 2.0);
+        let Some(Value::Array(files)) = timing.get("files") else {
+            panic!("expected a files array in {:?}", timing);
+        };
+        assert_eq!(files.len(), 2);
+        assert_eq!(
+            files[1].get("file").and_then(Value::as_str),
+            Some(b.as_str())
+        );
+        for file in files {
+            assert!(number(file, "read_seconds") >= 0.0);
+            assert!(number(file, "analyze_seconds") >= 0.0);
+        }
+        // Without the flag the summary has no timing.
+        let args: Vec<String> = ["mdlt", "--format=json", "--output", &report_path, &a]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        assert_eq!(run(args), Ok(Exit::Clean));
+        let report = parse(&fs::read_to_string(&report_path).unwrap()).unwrap();
+        assert_eq!(report.get("summary").unwrap().get("timing"), None);
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(a).unwrap();
+        fs::remove_file(b).unwrap();
+    }
+
+    // Opening a FIFO waits for something to write to it, which makes the
+    // last file as slow to open as the test needs.
+    #[cfg(unix)]
+    #[test]
+    fn test_run_streams_each_file() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_streams_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let first = dir.join("first.txt");
+        fs::write(&first, "one\n").unwrap();
+        let slow = dir.join("slow.txt");
+        let made = std::process::Command::new("mkfifo").arg(&slow).status();
+        assert!(made.unwrap().success());
+        let report = dir.join("report.txt");
+        let start = |buffered: bool| {
+            let mut args: Vec<String> = ["mdlt", "--jobs=1", "--output"]
+                .iter()
+                .map(|arg| arg.to_string())
+                .collect();
+            args.push(report.display().to_string());
+            if buffered {
+                args.push("--buffer-output".to_string());
+            }
+            args.extend([&first, &slow].map(|path| path.display().to_string()));
+            std::thread::spawn(move || run(args))
+        };
+        let report_text = || fs::read_to_string(&report).unwrap_or_default();
+
+        let running = start(false);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !report_text().contains("first.txt") {
+            assert!(Instant::now() < deadline, "no report of first.txt yet");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(!report_text().contains("slow.txt"));
+        fs::write(&slow, "two\r\n").unwrap();
+        assert_eq!(running.join().unwrap(), Ok(Exit::Clean));
+        assert!(report_text().ends_with("Line endings: 1 CRLF, 1 LF, 0 CR\n"));
+
+        // Buffered, nothing is written until the last file is in.
+        fs::remove_file(&report).unwrap();
+        let running = start(true);
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!report.exists());
+        fs::write(&slow, "two\r\n").unwrap();
+        assert_eq!(running.join().unwrap(), Ok(Exit::Clean));
+        assert!(report_text().contains("first.txt"));
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_uniform_tree() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_uniform_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "a\n").unwrap();
+        fs::write(dir.join("b.txt"), "b\nb\n").unwrap();
+        fs::write(dir.join("c.txt"), "c\r\n").unwrap();
+        fs::write(dir.join("no_ending.txt"), "x").unwrap();
+        fs::write(dir.join("sub").join("d.txt"), "d\n").unwrap();
+        fs::write(dir.join("sub").join("e.txt"), "e\r\n").unwrap();
+        let report_path = std::env::temp_dir()
+            .join(format!(
+                "mdlt_run_uniform_report_{}.txt",
+                std::process::id()
+            ))
+            .to_str()
+            .unwrap()
+            .to_string();
+        let run_with = |extra: &[&str]| {
+            let mut args: Vec<String> = ["mdlt", "-r", "--relative-to", dir.to_str().unwrap()]
+                .iter()
+                .chain(extra)
+                .map(|arg| arg.to_string())
+                .collect();
+            args.extend(["--output".to_string(), report_path.clone()]);
+            args.push(dir.to_str().unwrap().to_string());
+            run(args)
+        };
+        assert_eq!(
+            run_with(&["--check=uniform-tree"]),
+            Err(MdltError::check(
+                "uniform-tree",
+                "Line endings differ across the tree: 3 files LF, 2 files CRLF; 2 not LF:\n  \
+                 c.txt: CRLF\n  sub/e.txt: CRLF"
+                    .to_string()
+            ))
+        );
+        assert_eq!(
+            run_with(&["--check=uniform-tree=crlf", "--max-listed-lines=1"]),
+            Err(MdltError::check(
+                "uniform-tree",
+                "Line endings differ across the tree: 3 files LF, 2 files CRLF; 3 not CRLF, \
+                 which --check uniform-tree=crlf asks for:\n  a.txt: LF\n  \
+                 and 2 more, which --verbose lists"
+                    .to_string()
+            ))
+        );
+        let verbose = run_with(&["--check=uniform-tree=crlf", "--max-listed-lines=1", "-v"]);
+        let Err(failure @ MdltError::CheckFailed { .. }) = verbose else {
+            panic!("expected a violation, got {:?}", verbose);
+        };
+        let message = failure.to_string();
+        assert!(message.ends_with("  a.txt: LF\n  b.txt: LF\n  sub/d.txt: LF"));
+        fs::write(dir.join("c.txt"), "c\n").unwrap();
+        fs::write(dir.join("sub").join("e.txt"), "e\n").unwrap();
+        assert_eq!(run_with(&["--check=uniform-tree"]), Ok(Exit::Clean));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_baseline() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_baseline_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+        let (a, b, baseline) = (path("a.txt"), path("b.txt"), path("base.json"));
+        fs::write(&a, "one \n").unwrap();
+        fs::write(&b, "two\n").unwrap();
+        let report_path = path("report.txt");
+        let run_with = |extra: &[&str], files: &[&String]| {
+            let mut args: Vec<String> = [
+                "mdlt",
+                "--enable=trailing-whitespace",
+                "--baseline",
+                &baseline,
+                "--output",
+                &report_path,
+            ]
+            .iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect();
+            args.extend(files.iter().map(|file| file.to_string()));
+            (
+                run(args),
+                fs::read_to_string(&report_path).unwrap_or_default(),
+            )
+        };
+        assert!(matches!(
+            run_with(&[], &[&a, &b]).0,
+            Err(MdltError::Io { .. })
+        ));
+        let (result, text) = run_with(&["--update-baseline"], &[&a, &b]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(text.ends_with(&format!("Recorded 1 problem in 1 file in {}\n", baseline)));
+        assert_eq!(
+            fs::read_to_string(&baseline).unwrap(),
+            format!(
+                "{{\n  \"version\": 1,\n  \"files\": {{\n    {}: [\"trailing-whitespace\"]\n  }}\n}}\n",
+                format::json::string(&a)
+            )
+        );
+        let (result, text) = run_with(&[], &[&a, &b]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(text.ends_with(&format!("1 known problem in {}, none new\n", baseline)));
+        // Only what is new fails, and the known problems are told apart.
+        fs::write(&b, "two \n").unwrap();
+        assert_eq!(
+            run_with(&[], &[&a, &b]).0,
+            Err(MdltError::check(
+                "baseline",
+                format!(
+                    "1 new problem not in the baseline {}:\n  {}: trailing-whitespace\n\
+                 1 known problem, in the baseline:\n  {}: trailing-whitespace",
+                    baseline, b, a
+                )
+            ))
+        );
+        // Updating drops the files that are gone.
+        fs::remove_file(&a).unwrap();
+        let (result, _) = run_with(&["--update-baseline"], &[&b]);
+        assert_eq!(result, Ok(Exit::Clean));
+        let recorded = fs::read_to_string(&baseline).unwrap();
+        assert!(recorded.contains("b.txt") && !recorded.contains("a.txt"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_fail_on_embedded_cr() {
+        let mac = create_temp_file("embedded_cr_mac.txt", "a\rb\r");
+        let glued = create_temp_file("embedded_cr_glued.txt", "one\ntwo\rthree\n");
+        let report_path = "embedded_cr_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-on-embedded-cr".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            mac.clone(),
+            glued.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check(
+                "embedded-cr",
+                "1 of 2 files have carriage returns inside lines".to_string()
+            )
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(
+            report
+                .matches("Embedded carriage returns: 1 (first at line 2, col 4)\n")
+                .count(),
+            1
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(mac).unwrap();
+        fs::remove_file(glued).unwrap();
+    }
+
+    #[test]
+    fn test_run_convert() {
+        let mixed = create_temp_file("convert_mixed.txt", "one\r\ntwo\nthree\r\nfour");
+        let unix = create_temp_file("convert_unix.txt", "one\ntwo\n");
+        let binary = create_temp_file("convert_binary.bin", "\0\0\r\n\0");
+        let convert = |paths: &[&String]| {
+            let mut args = vec![
+                "mdlt".to_string(),
+                "--convert".to_string(),
+                "lf".to_string(),
+            ];
+            args.extend(paths.iter().map(|path| path.to_string()));
+            run(args)
+        };
+        convert(&[&mixed, &unix]).unwrap();
+        assert_eq!(fs::read_to_string(&mixed).unwrap(), "one\ntwo\nthree\nfour");
+        assert_eq!(fs::read_to_string(&unix).unwrap(), "one\ntwo\n");
+        assert_eq!(convert(&[&binary]), Ok(Exit::SkippedBinary));
+        assert_eq!(fs::read(&binary).unwrap(), b"\0\0\r\n\0");
+        for path in [mixed, unix, binary] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_convert_leaves_binaries_identical() {
+        // The sniffed window would call this text; the NUL past it must not.
+        let mut late_nul = "text\r\n".repeat(2000).into_bytes();
+        late_nul.extend_from_slice(b"\0\r\n\r");
+        let png =
+            b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR\x00\x00\x00\x01\r\n\x1f\x15\xc4\x89".to_vec();
+        let fixtures = [
+            ("convert_identical.png", png),
+            ("convert_late_nul.txt", late_nul),
+        ];
+        for (name, bytes) in &fixtures {
+            fs::write(name, bytes).unwrap();
+        }
+        let convert = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--convert=lf".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend(fixtures.iter().map(|(name, _)| name.to_string()));
+            run(args)
+        };
+        assert_eq!(
+            convert(&["--fix=trailing-whitespace,final-newline"]),
+            Ok(Exit::SkippedBinary)
+        );
+        assert_eq!(Exit::SkippedBinary.status(), 4);
+        for (name, bytes) in &fixtures {
+            assert_eq!(&fs::read(name).unwrap(), bytes, "{}", name);
+        }
+        assert_eq!(convert(&["--force"]), Ok(Exit::Clean));
+        assert!(!fs::read(fixtures[1].0).unwrap().contains(&b'\r'));
+        for (name, _) in fixtures {
+            fs::remove_file(name).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_fix_trailing_whitespace() {
+        let ragged = create_temp_file("fix_ragged.txt", "one  \r\ntwo\r\n\t\r\nthree \t");
+        let clean = create_temp_file("fix_clean.txt", "one\ntwo \tx\n");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(&clean)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let fix = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--fix=trailing-whitespace".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.push(ragged.clone());
+            args.push(clean.clone());
+            run(args)
+        };
+        assert_eq!(
+            fix(&["--dry-run"]),
+            Err(MdltError::check(
+                "dry-run",
+                "1 of 2 files would change (--dry-run)".to_string()
+            ))
+        );
+        fix(&[]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&ragged).unwrap(),
+            "one\r\ntwo\r\n\r\nthree"
+        );
+        assert_eq!(fs::read_to_string(&clean).unwrap(), "one\ntwo \tx\n");
+        assert_eq!(fs::metadata(&clean).unwrap().modified().unwrap(), modified);
+
+        fs::write(&ragged, "a \r\nb\r\n").unwrap();
+        fix(&["--convert=lf"]).unwrap();
+        assert_eq!(fs::read_to_string(&ragged).unwrap(), "a\nb\n");
+        for path in [ragged, clean] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_fix_final_newline() {
+        let dos = create_temp_file("final_newline_dos.txt", "a\r\nb\r\nc");
+        let empty = create_temp_file("final_newline_fix_empty.txt", "");
+        let done = create_temp_file("final_newline_done.txt", "a\n");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(&done)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let fix = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--fix=final-newline".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([dos.clone(), empty.clone(), done.clone()]);
+            run(args)
+        };
+        assert_eq!(
+            fix(&["--dry-run"]),
+            Err(MdltError::check(
+                "dry-run",
+                "1 of 3 files would change (--dry-run)".to_string()
+            ))
+        );
+        assert_eq!(fs::read_to_string(&dos).unwrap(), "a\r\nb\r\nc");
+        fix(&[]).unwrap();
+        assert_eq!(fs::read_to_string(&dos).unwrap(), "a\r\nb\r\nc\r\n");
+        assert_eq!(fs::read_to_string(&empty).unwrap(), "");
+        assert_eq!(fs::metadata(&done).unwrap().modified().unwrap(), modified);
+
+        fs::write(&dos, "a \r\nb ").unwrap();
+        fix(&["--fix=trailing-whitespace", "--convert=lf"]).unwrap();
+        assert_eq!(fs::read_to_string(&dos).unwrap(), "a\nb\n");
+        for path in [dos, empty, done] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_fix_strip_bom() {
+        let only = create_temp_file("strip_bom_only.txt", "\u{feff}");
+        let marked = create_temp_file("strip_bom_marked.txt", "\u{feff}a \r\nb");
+        let plain = create_temp_file("strip_bom_plain.txt", "a\n");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(&plain)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let fix = |extra: &[&str], paths: &[&String]| {
+            let mut args = vec!["mdlt".to_string(), "--fix=strip-bom".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend(paths.iter().map(|path| path.to_string()));
+            run(args)
+        };
+        assert_eq!(
+            fix(&["--dry-run"], &[&only, &marked, &plain]),
+            Err(MdltError::check(
+                "dry-run",
+                "2 of 3 files would change (--dry-run)".to_string()
+            ))
+        );
+        fix(
+            &["--fix=trailing-whitespace,final-newline"],
+            &[&only, &marked, &plain],
+        )
+        .unwrap();
+        assert_eq!(fs::read(&only).unwrap(), b"");
+        assert_eq!(fs::read_to_string(&marked).unwrap(), "a\r\nb\r\n");
+        assert_eq!(fs::metadata(&plain).unwrap().modified().unwrap(), modified);
+
+        fs::write(&plain, b"\xff\xfea\x00\n\x00").unwrap();
+        let error = fix(&[], &[&plain]).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("UTF-16LE byte-order mark, not stripped"),
+            "{}",
+            error.to_string()
+        );
+        assert_eq!(fs::read(&plain).unwrap(), b"\xff\xfea\x00\n\x00");
+        for path in [only, marked, plain] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_fix_retab() {
+        let tabbed = create_temp_file("retab_tabbed.txt", "a\r\n\tb\t\"\\t\"\r\n  \tc\r\n");
+        let spaced = create_temp_file("retab_spaced.txt", "a\n    b\n");
+        let binary = create_temp_file("retab_binary.bin", "\0\tx");
+        let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000_000);
+        File::options()
+            .write(true)
+            .open(&spaced)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let fix = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--fix=retab=spaces:4".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([tabbed.clone(), spaced.clone(), binary.clone()]);
+            run(args)
+        };
+        assert_eq!(
+            fix(&["--dry-run"]),
+            Err(MdltError::check(
+                "dry-run",
+                "1 of 3 files would change (--dry-run)".to_string()
+            ))
+        );
+        fix(&["--backup"]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&tabbed).unwrap(),
+            "a\r\n    b\t\"\\t\"\r\n    c\r\n"
+        );
+        assert_eq!(
+            fs::read_to_string(format!("{}.bak", tabbed)).unwrap(),
+            "a\r\n\tb\t\"\\t\"\r\n  \tc\r\n"
+        );
+        assert_eq!(fs::metadata(&spaced).unwrap().modified().unwrap(), modified);
+        assert_eq!(fs::read(&binary).unwrap(), b"\0\tx");
+        for path in [format!("{}.bak", tabbed), tabbed, spaced, binary] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_fix_squeeze_blank() {
+        let gappy = create_temp_file(
+            "squeeze_gappy.txt",
+            "a\r\n\r\n\r\n\r\nb\r\n \r\n\r\n\r\n  \r\n",
+        );
+        let tight = create_temp_file("squeeze_tight.txt", "a\n\nb\n");
+        let fix = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), "--fix=squeeze-blank".to_string()];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([gappy.clone(), tight.clone()]);
+            run(args)
+        };
+        assert_eq!(
+            fix(&["--dry-run"]),
+            Err(MdltError::check(
+                "dry-run",
+                "1 of 2 files would change (--dry-run)".to_string()
+            ))
+        );
+        fix(&[]).unwrap();
+        assert_eq!(
+            fs::read_to_string(&gappy).unwrap(),
+            "a\r\n\r\nb\r\n \r\n\r\n  \r\n"
+        );
+        fix(&["--blank-as-empty"]).unwrap();
+        assert_eq!(fs::read_to_string(&gappy).unwrap(), "a\r\n\r\nb\r\n \r\n");
+        assert_eq!(fs::read_to_string(&tight).unwrap(), "a\n\nb\n");
+        let check = run(vec![
+            "mdlt".to_string(),
+            "--max-trailing-blank-lines=1".to_string(),
+            "--output=squeeze_report.txt".to_string(),
+            gappy.clone(),
+        ]);
+        assert_eq!(check, Ok(Exit::Clean));
+        for path in [gappy, tight, "squeeze_report.txt".to_string()] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_ending_violation() {
+        let file = |crlf, lf, cr| FileStats {
+            dos_endings: crlf,
+            unix_endings: lf,
+            mac_endings: cr,
+            ..FileStats::new("f".to_string())
+        };
+        let violation = |expected, stats: FileStats| ending_violation(expected, None, &stats);
+        assert_eq!(violation(ExpectedEndings::Lf, file(0, 9, 0)), None);
+        assert_eq!(
+            violation(ExpectedEndings::Lf, file(3, 9, 1)),
+            Some("expected LF, found 3 CRLF and 1 CR".to_string())
+        );
+        assert_eq!(
+            violation(ExpectedEndings::Cr, file(0, 2, 0)),
+            Some("expected CR, found 2 LF".to_string())
+        );
+        assert_eq!(
+            ending_violation(
+                ExpectedEndings::Crlf,
+                Some(".gitattributes line 12"),
+                &file(0, 4, 0)
+            ),
+            Some("expected CRLF (from .gitattributes line 12), found 4 LF".to_string())
+        );
+        assert_eq!(violation(ExpectedEndings::Consistent, file(4, 0, 0)), None);
+        assert_eq!(
+            violation(ExpectedEndings::Consistent, file(2, 10, 0)),
+            Some("mixed line endings: 2 CRLF and 10 LF".to_string())
+        );
+        for expected in [
+            ExpectedEndings::Lf,
+            ExpectedEndings::Crlf,
+            ExpectedEndings::Cr,
+            ExpectedEndings::Consistent,
+        ] {
+            assert_eq!(violation(expected, file(0, 0, 0)), None);
+        }
+    }
+
+    #[test]
+    fn test_run_gitattributes() {
+        let dir = "gitattributes_tree";
+        fs::create_dir_all(format!("{}/.git", dir)).unwrap();
+        fs::create_dir_all(format!("{}/win", dir)).unwrap();
+        fs::write(
+            format!("{}/.gitattributes", dir),
+            "*.sh text eol=lf\n*.bat text eol=crlf\n*.bin -text\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/win/.gitattributes", dir), "*.sh eol=crlf\n").unwrap();
+        fs::write(format!("{}/run.sh", dir), "a\nb\n").unwrap();
+        fs::write(format!("{}/build.bat", dir), "a\nb\r\n").unwrap();
+        fs::write(format!("{}/win/run.sh", dir), "a\nb\n").unwrap();
+        fs::write(format!("{}/data.bin", dir), "a\r\nb\n").unwrap();
+        fs::write(format!("{}/notes.txt", dir), "a\r\n").unwrap();
+        let report_path = "gitattributes_report".to_string();
+        let check = |extra: &[&str]| {
+            let mut args = vec![
+                "mdlt".to_string(),
+                "-r".to_string(),
+                "--gitattributes".to_string(),
+                format!("--output={}", report_path),
+            ];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.push(dir.to_string());
+            (run(args), fs::read_to_string(&report_path).unwrap())
+        };
+        assert_eq!(
+            check(&[]),
+            (
+                Err(MdltError::check(
+                    "endings",
+                    "2 of 5 files fail the .gitattributes eol policy".to_string()
+                )),
+                format!(
+                    "{dir}/build.bat: expected CRLF (from .gitattributes line 2), found 1 LF\n\
+                     {dir}/win/run.sh: expected CRLF (from win/.gitattributes line 1), found 2 LF\n"
+                )
+            )
+        );
+        let (result, report) = check(&["--check=lf"]);
+        assert_eq!(
+            result,
+            Err(MdltError::check(
+                "endings",
+                "3 of 5 files fail --check lf or the .gitattributes eol policy".to_string()
+            ))
+        );
+        assert!(report.contains(&format!("{}/notes.txt: expected LF, found 1 CRLF\n", dir)));
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file(report_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_editorconfig() {
+        let dir = "editorconfig_tree";
+        fs::create_dir_all(format!("{}/sub", dir)).unwrap();
+        fs::write(
+            format!("{}/.editorconfig", dir),
+            "root = true\n\n[*]\nend_of_line = lf\ninsert_final_newline = true\n\n\
+             [*.{md,txt}]\ntrim_trailing_whitespace = true\n",
+        )
+        .unwrap();
+        fs::write(
+            format!("{}/sub/.editorconfig", dir),
+            "[*.txt]\nend_of_line = crlf\n\n[*.py]\nindent_style = space\nmax_line_length = 10\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/a.txt", dir), "a\nb\n").unwrap();
+        fs::write(format!("{}/b.md", dir), "a \nb").unwrap();
+        fs::write(format!("{}/sub/c.txt", dir), "a\nb\n").unwrap();
+        fs::write(format!("{}/sub/d.py", dir), "\tx = 1\n").unwrap();
+        let report_path = "editorconfig_report".to_string();
+        let result = run(vec![
+            "mdlt".to_string(),
+            "-r".to_string(),
+            "--editorconfig".to_string(),
+            format!("--output={}", report_path),
+            dir.to_string(),
+        ]);
+        assert_eq!(
+            result,
+            Err(MdltError::check(
+                "editorconfig",
+                "3 of 4 files break their .editorconfig".to_string()
+            ))
+        );
+        assert_eq!(
+            fs::read_to_string(&report_path).unwrap(),
+            format!(
+                "{dir}/b.md: no final newline (insert_final_newline = true in {dir}/.editorconfig)\n\
+                 {dir}/b.md: trailing whitespace on 1 line (trim_trailing_whitespace = true in {dir}/.editorconfig)\n\
+                 {dir}/sub/c.txt: expected CRLF (from end_of_line in {dir}/sub/.editorconfig), found 2 LF\n\
+                 {dir}/sub/d.py: 1 line indented with tabs (indent_style = space in {dir}/sub/.editorconfig)\n\
+                 {dir}/sub/d.py: line 1 is 13 columns (max_line_length = 10 in {dir}/sub/.editorconfig)\n"
+            )
+        );
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file(report_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_config() {
+        let dir = "config_tree";
+        fs::create_dir_all(dir).unwrap();
+        let config = format!("{}/mdlt.toml", dir);
+        fs::write(
+            &config,
+            "check = \"lf\"\nexclude = [\"*.log\"]\n[endings]\nbat = \"crlf\"\n",
+        )
+        .unwrap();
+        fs::write(format!("{}/run.sh", dir), "a\nb\n").unwrap();
+        fs::write(format!("{}/build.bat", dir), "a\nb\n").unwrap();
+        fs::write(format!("{}/debug.log", dir), "a\r\n").unwrap();
+        let report_path = "config_report".to_string();
+        let check = |extra: &[&str]| {
+            let mut args = vec![
+                "mdlt".to_string(),
+                "-r".to_string(),
+                format!("--config={}", config),
+                format!("--output={}", report_path),
+            ];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.push(dir.to_string());
+            (run(args), fs::read_to_string(&report_path).unwrap())
+        };
+        assert_eq!(
+            check(&[]),
+            (
+                Err(MdltError::check(
+                    "endings",
+                    "1 of 3 files fail --check lf or --expect".to_string()
+                )),
+                format!("{}/build.bat: expected CRLF, found 2 LF\n", dir)
+            )
+        );
+        // The command line wins over the file.
+        let (result, report) = check(&["--expect=bat=lf", "--format=json"]);
+        assert_eq!(result, Ok(Exit::Clean));
+        assert!(report.contains("run.sh"));
+        assert!(!report.contains("debug.log"));
+        fs::write(&config, "format = \"yaml\"\n").unwrap();
+        assert_eq!(
+            check(&[]).0,
+            Err(MdltError::Usage(format!(
+                "{}:1: Unknown format 'yaml': expected text, markdown, html, json, csv, tsv, table, sarif, checkstyle or github",
+                config
+            )))
+        );
+        fs::remove_dir_all(dir).unwrap();
+        fs::remove_file(report_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_check_endings() {
+        let unix = create_temp_file("check_unix.txt", "a\nb\n");
+        let dos = create_temp_file("check_dos.txt", "a\r\nb\r\n");
+        let none = create_temp_file("check_none.txt", "a");
+        let report_path = "check_report.txt".to_string();
+        let check = |extra: &[&str]| {
+            let mut args = vec!["mdlt".to_string(), format!("--output={}", report_path)];
+            args.extend(extra.iter().map(|arg| arg.to_string()));
+            args.extend([unix.clone(), dos.clone(), none.clone()]);
+            run(args)
+        };
+        let wrong = |expected: &str| {
+            Err(MdltError::check(
+                "endings",
+                format!("1 of 3 files fail --check {}", expected),
+            ))
+        };
+        assert_eq!(check(&["--check=lf"]), wrong("lf"));
+        assert_eq!(
+            fs::read_to_string(&report_path).unwrap(),
+            format!("{}: expected LF, found 2 CRLF\n", dos)
+        );
+        assert_eq!(check(&["--check=consistent"]), Ok(Exit::Clean));
+        assert_eq!(fs::read_to_string(&report_path).unwrap(), "");
+        assert_eq!(check(&["--check=crlf", "-v"]), wrong("crlf"));
+        assert!(fs::read_to_string(&report_path)
+            .unwrap()
+            .contains("Line ending type:"));
+        assert_eq!(
+            check(&["--check=lf", "--fail-on-missing-final-newline"]),
+            Err(MdltError::checks(vec![
+                CheckFailure::new(
+                    "final-newline",
+                    "1 of 3 files do not end with a newline".to_string()
+                ),
+                CheckFailure::new("endings", "1 of 3 files fail --check lf".to_string()),
+            ]))
+        );
+        for path in [unix, dos, none, report_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_convert_backup() {
+        let original = "one\r\ntwo\r\n";
+        let file_path = create_temp_file("convert_with_backup.txt", original);
+        let backup_path = format!("{}.bak", file_path);
+        let convert = || {
+            run(vec![
+                "mdlt".to_string(),
+                "--convert=lf".to_string(),
+                "--backup".to_string(),
+                file_path.clone(),
+            ])
+        };
+        convert().unwrap();
+        assert_eq!(fs::read_to_string(&backup_path).unwrap(), original);
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "one\ntwo\n");
+        fs::write(&file_path, "three\r\n").unwrap();
+        let error = convert().unwrap_err();
+        assert!(
+            error.to_string().contains("already exists"),
+            "{}",
+            error.to_string()
+        );
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "three\r\n");
+        fs::remove_file(file_path).unwrap();
+        fs::remove_file(backup_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_convert_dry_run() {
+        let dos = create_temp_file("dry_run_dos.txt", "a\r\nb\r\n");
+        let unix = create_temp_file("dry_run_unix.txt", "a\nb\n");
+        let dry_run = |path: &String| {
+            run(vec![
+                "mdlt".to_string(),
+                "--convert".to_string(),
+                "lf".to_string(),
+                "--dry-run".to_string(),
+                "--backup".to_string(),
+                path.clone(),
+            ])
+        };
+        assert_eq!(
+            dry_run(&dos),
+            Err(MdltError::check(
+                "dry-run",
+                "1 of 1 file would change (--dry-run)".to_string()
+            ))
+        );
+        assert_eq!(fs::read_to_string(&dos).unwrap(), "a\r\nb\r\n");
+        assert!(!Path::new(&format!("{}.bak", dos)).exists());
+        assert_eq!(dry_run(&unix), Ok(Exit::Clean));
+        for path in [dos, unix] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rewrite_interactively() {
+        let paths: Vec<String> = ["yes", "lf", "no", "quit", "after"]
+            .iter()
+            .map(|name| match *name {
+                "lf" => create_temp_file("interactive_lf.txt", "a\n"),
+                name => create_temp_file(&format!("interactive_{}.txt", name), "a\r\nb \r\n"),
+            })
+            .collect();
+        let mut argv = vec![
+            "mdlt",
+            "--convert=lf",
+            "--fix=trailing-whitespace",
+            "--interactive",
+        ];
+        argv.extend(paths.iter().map(String::as_str));
+        let options =
+            cli::parse_args(&argv.iter().map(|arg| arg.to_string()).collect::<Vec<_>>()).unwrap();
+        let mut answers = vec![Answer::Quit, Answer::No, Answer::Yes];
+        let mut asked = Vec::new();
+        let outcomes: Vec<convert::Outcome> =
+            rewrite_interactively(&options, &paths, &paths, |preview, _| {
+                asked.push(preview.to_string());
+                Ok(answers.pop().unwrap())
+            })
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        // An LF file without trailing whitespace has nothing to ask about.
+        assert_eq!(asked.len(), 3);
+        assert_eq!(
+            asked[0],
+            format!(
+                "{}: CRLF (2 lines)\n  would convert 2 CRLF \u{2192} LF, would strip trailing whitespace on 1 line",
+                paths[0]
+            )
+        );
+        let decisions: Vec<_> = outcomes.iter().map(|outcome| outcome.decision).collect();
+        use Decision::*;
+        assert_eq!(
+            decisions,
+            [
+                Some(Applied),
+                None,
+                Some(Declined),
+                Some(Declined),
+                Some(Unvisited)
+            ]
+        );
+        assert_eq!(
+            outcomes[0].summary(),
+            "converted 2 line endings to LF, stripped trailing whitespace on 1 line"
+        );
+        assert!(outcomes[2]
+            .summary()
+            .starts_with("declined; would convert 2 CRLF"));
+        assert_eq!(outcomes[4].summary(), "not visited");
+        let contents: Vec<String> = paths
+            .iter()
+            .map(|path| fs::read_to_string(path).unwrap())
+            .collect();
+        assert_eq!(
+            contents,
+            ["a\nb\n", "a\n", "a\r\nb \r\n", "a\r\nb \r\n", "a\r\nb \r\n"]
+        );
+        for path in paths {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_rewrite_interactively_all() {
+        let paths: Vec<String> = (0..3)
+            .map(|index| create_temp_file(&format!("interactive_all_{}.txt", index), "a\r\n"))
+            .collect();
+        let mut argv = vec!["mdlt", "--convert=lf", "--interactive"];
+        argv.extend(paths.iter().map(String::as_str));
+        let options =
+            cli::parse_args(&argv.iter().map(|arg| arg.to_string()).collect::<Vec<_>>()).unwrap();
+        let mut asked = 0;
+        let outcomes = rewrite_interactively(&options, &paths, &paths, |_, _| {
+            asked += 1;
+            Ok(Answer::All)
+        });
+        assert_eq!(asked, 1);
+        for (path, outcome) in paths.iter().zip(outcomes) {
+            assert_eq!(outcome.unwrap().decision, Some(Decision::Applied));
+            assert_eq!(fs::read_to_string(path).unwrap(), "a\n");
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_convert_auto() {
+        let dos = create_temp_file("auto_dos.txt", "a\r\nb\r\nc\n");
+        let tie = create_temp_file("auto_tie.txt", "a\r\nb\n");
+        // Its phantom blank lines were LF lines before they got a CR.
+        let phantom = create_temp_file("auto_phantom.txt", "a\n\r\nb\n\r\nc\r\n");
+        let args = vec![
+            "mdlt".to_string(),
+            "--convert=auto".to_string(),
+            dos.clone(),
+            tie.clone(),
+            phantom.clone(),
+        ];
+        run(args).unwrap();
+        assert_eq!(fs::read_to_string(&dos).unwrap(), "a\r\nb\r\nc\r\n");
+        assert_eq!(fs::read_to_string(&tie).unwrap(), "a\r\nb\n");
+        assert_eq!(fs::read_to_string(&phantom).unwrap(), "a\n\nb\n\nc\n");
+        for path in [dos, tie, phantom] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_convert_stream() {
+        let lf = convert::Edits {
+            target: Some(ConvertTo::Lf),
+            ..convert::Edits::default()
+        };
+        let options = |extra: &[&str]| {
+            let mut args = vec!["mdlt", "--convert", "lf"];
+            args.extend(extra);
+            args.push("-");
+            cli::parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>()).unwrap()
+        };
+        let input = "line\r\n".repeat(3000) + "last";
+        let mut output = Vec::new();
+        let stats = convert_stream(&options(&[]), lf, input.as_bytes(), &mut output)
+            .unwrap()
+            .unwrap();
+        assert_eq!(output, ("line\n".repeat(3000) + "last").into_bytes());
+        assert_eq!(stats.dos_endings, 3000);
+        assert_eq!(stats.file_name, "<stdin>");
+
+        let binary = b"\0\r\nx".as_slice();
+        let error = convert_stream(&options(&[]), lf, binary, Vec::new()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Error analyzing <stdin>: binary file, not converted (--force converts it anyway)"
+        );
+        let mut output = Vec::new();
+        convert_stream(&options(&["--force"]), lf, binary, &mut output).unwrap();
+        assert_eq!(output, b"\0\nx");
+    }
+
+    #[test]
+    fn test_convert_stream_broken_pipe() {
+        struct Closed;
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let args: Vec<String> = ["mdlt", "--convert", "crlf", "-"]
+            .iter()
+            .map(|arg| arg.to_string())
+            .collect();
+        let options = cli::parse_args(&args).unwrap();
+        let input = "x\n".repeat(100_000);
+        let stats = convert_stream(
+            &options,
+            convert::Edits::default(),
+            input.as_bytes(),
+            Closed,
+        );
+        assert_eq!(stats.map(|stats| stats.is_none()), Ok(true));
+    }
+
+    #[test]
+    fn test_run_metadata() {
+        let file_path = create_temp_file("metadata.txt", "one\ntwo\n");
+        let report_path = "metadata_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--metadata".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            file_path.clone(),
+        ];
+        run(args).unwrap();
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("Metadata:\n  Modified: "));
+        assert!(report.contains(")\n  Size: 8 bytes\n  Permissions: "));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(file_path).unwrap();
+    }
+
+    #[test]
+    fn test_run_fail_on_non_ascii() {
+        let ascii = create_temp_file("ascii_clean.toml", "name = \"x\"\n");
+        let quoted = create_temp_file("ascii_quoted.toml", "a = 1\nname = \u{201c}x\u{201d}\n");
+        let report_path = "ascii_report.txt".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--fail-on-non-ascii".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            ascii.clone(),
+            quoted.clone(),
+        ];
+        let err = run(args).unwrap_err();
+        assert_eq!(
+            err,
+            MdltError::check(
+                "non-ascii",
+                "1 of 2 files contain non-ASCII characters".to_string()
+            )
+        );
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(
+            report
+                .matches("Non-ASCII characters: 2 (first at line 2, column 8)\n")
+                .count(),
+            1
+        );
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(ascii).unwrap();
+        fs::remove_file(quoted).unwrap();
+    }
+
+    #[test]
+    fn test_run_recursive_json() {
+        let root = std::env::temp_dir().join(format!("mdlt_run_recursive_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("sub")).unwrap();
+        fs::write(root.join("b.txt"), "x\r\n").unwrap();
+        fs::write(root.join("sub").join("a.txt"), "x\n").unwrap();
+        fs::write(root.join(".hidden"), "x\n").unwrap();
+        let report_path = root
+            .join("..")
+            .join(format!("mdlt_run_recursive_{}.json", std::process::id()));
+
+        let args = vec![
+            "mdlt".to_string(),
+            "-r".to_string(),
+            "--format".to_string(),
+            "json".to_string(),
+            "--output".to_string(),
+            report_path.to_str().unwrap().to_string(),
+            root.to_str().unwrap().to_string(),
+        ];
+        assert!(run(args).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.matches("\"file_name\"").count(), 2);
+        assert!(report.find("b.txt").unwrap() < report.find("a.txt").unwrap());
+        assert!(!report.contains(".hidden"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_run_glob_patterns() {
+        let root = std::env::temp_dir().join(format!("mdlt_run_glob_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("file2.txt"), "a\r\n").unwrap();
+        fs::write(root.join("file1.txt"), "a\n").unwrap();
+        let report_path = root.join("report.csv").to_str().unwrap().to_string();
+        let pattern = root.join("file?.txt").to_str().unwrap().to_string();
+
+        let args = vec![
+            "mdlt".to_string(),
+            "--format=csv".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            pattern.clone(),
+        ];
+        assert!(run(args).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert_eq!(report.lines().count(), 3);
+        assert!(report.find("file1.txt").unwrap() < report.find("file2.txt").unwrap());
+
+        let args = vec!["mdlt".to_string(), "--no-glob".to_string(), pattern];
+        assert!(run(args)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Error analyzing "));
+
+        let missing = root.join("*.md").to_str().unwrap().to_string();
+        let args = vec!["mdlt".to_string(), missing.clone()];
+        assert_eq!(
+            run(args).unwrap_err(),
+            MdltError::Usage(format!("No files match pattern '{}'", missing))
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_run_exclude_applies_to_direct_arguments() {
+        let kept = create_temp_file("exclude_kept.txt", "a\n");
+        let dropped = create_temp_file("exclude_dropped.min.js", "a\n");
+        let report_path = "exclude_report.csv".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--format=csv".to_string(),
+            "--exclude=*.min.js".to_string(),
+            "--output".to_string(),
+            report_path.clone(),
+            kept.clone(),
+            dropped.clone(),
+        ];
+        assert!(run(args).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        assert!(report.contains("exclude_kept.txt"));
+        assert!(!report.contains("exclude_dropped"));
+        fs::remove_file(report_path).unwrap();
+        fs::remove_file(kept).unwrap();
+        fs::remove_file(dropped).unwrap();
+    }
+
+    #[test]
+    fn test_parse_path_list() {
+        assert_eq!(
+            parse_path_list(b"a.txt\n\nb.txt\r\n  \nc d.txt", false),
+            vec!["a.txt", "b.txt", "c d.txt"]
+        );
+        assert_eq!(
+            parse_path_list(b"with\nnewline\0b.txt\0\0", true),
+            vec!["with\nnewline", "b.txt"]
+        );
+    }
+
+    #[test]
+    fn test_run_sample() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_sample_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for index in 0..20 {
+            let content = if index == 7 { "a\r\nb\n" } else { "a\n" };
+            fs::write(dir.join(format!("{:02}.txt", index)), content).unwrap();
+        }
+        let report_path = "sample_report.json".to_string();
+        let run_with = |seed: u64| {
+            let args: Vec<String> = [
+                "mdlt",
+                "-r",
+                "--format=json",
+                "--fail-on-mixed",
+                "--sample",
+                "5",
+                "--output",
+                &report_path,
+            ]
+            .iter()
+            .map(|arg| arg.to_string())
+            .chain([format!("--seed={}", seed), dir.display().to_string()])
+            .collect();
+            let result = run(args);
+            (result, fs::read_to_string(&report_path).unwrap())
+        };
+        let mut failed = 0;
+        for seed in 0..10 {
+            let (result, report) = run_with(seed);
+            assert_eq!(report.matches("\"file_name\"").count(), 5);
+            assert!(report.contains(&format!(
+                "\"sample\": {{\"size\": 5, \"population\": 20, \"seed\": {}}}",
+                seed
+            )));
+            // Only a sample with the mixed file in it fails.
+            assert_eq!(result.is_err(), report.contains("07.txt"), "{}", seed);
+            failed += usize::from(result.is_err());
+            assert_eq!(run_with(seed).1, report);
+        }
+        assert!((1..10).contains(&failed), "{}", failed);
+        fs::remove_file(report_path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_detected_type() {
+        let dir = std::env::temp_dir().join(format!("mdlt_run_type_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("deploy"),
+            "#!/usr/bin/env bash\n# ship it\necho hi\n",
+        )
+        .unwrap();
+        fs::write(dir.join("Dockerfile"), "# base\nFROM alpine\nRUN true\n").unwrap();
+        fs::write(dir.join("notes"), "plain\n").unwrap();
+        let report_path = "type_report.txt".to_string();
+        let run_with = |extra: &[&str]| {
+            let args: Vec<String> = ["mdlt", "-r", "--output", &report_path]
+                .iter()
+                .chain(extra)
+                .map(|arg| arg.to_string())
+                .chain([dir.display().to_string()])
+                .collect();
+            let result = run(args);
+            (result, fs::read_to_string(&report_path).unwrap())
+        };
+        let (result, report) = run_with(&["--group-by", "extension"]);
+        assert!(result.is_ok());
+        assert!(report.contains(
+            "Shebang: /usr/bin/env bash\nDetected language: Bash\nDetected type: bash\n"
+        ));
+        assert!(report.contains("Detected type: dockerfile\n"));
+        assert!(report.contains("Detected type: text/unknown\n"));
+        assert!(
+            report.contains("Comment lines: 2, code lines: 1"),
+            "{}",
+            report
+        );
+        assert!(
+            report.contains("Comment lines: 1, code lines: 2"),
+            "{}",
+            report
+        );
+        for row in ["bash", "dockerfile", "text/unknown"] {
+            assert!(report.contains(&format!("\n{} ", row)), "{}", report);
+        }
+        // `--expect` goes by the type of a file without an extension.
+        let (result, _) = run_with(&["--expect", "dockerfile=crlf"]);
+        assert!(result.is_err());
+        let (result, report) = run_with(&[
+            "--format=json",
+            "--type-name=notes=markdown",
+            "--type-interpreter=bash=sh",
+        ]);
+        assert!(result.is_ok());
+        assert!(
+            report.contains("\"detected_type\": \"markdown\""),
+            "{}",
+            report
+        );
+        assert!(report.contains("\"detected_type\": \"sh\""), "{}", report);
+        assert!(!report.contains("text/unknown"), "{}", report);
+        fs::remove_file(report_path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_files_from() {
+        let first = create_temp_file("files_from_a.txt", "a\n");
+        let second = create_temp_file("files_from_b.txt", "a\r\n");
+        let list = create_temp_file(
+            "files_from_list.txt",
+            &format!("{}\n\n{}\n{}\n", second, first, second),
+        );
+        let report_path = "files_from_report.csv".to_string();
+        let args = vec![
+            "mdlt".to_string(),
+            "--format=csv".to_string(),
+            "--files-from".to_string(),
+            list.clone(),
+            "--output".to_string(),
+            report_path.clone(),
+            first.clone(),
+        ];
+        assert!(run(args).is_ok());
+        let report = fs::read_to_string(&report_path).unwrap();
+        let rows: Vec<&str> = report.lines().skip(1).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].contains("files_from_a.txt"));
+        assert!(rows[1].contains("files_from_b.txt"));
+
+        let args = vec![
+            "mdlt".to_string(),
+            "--files-from=missing_list.txt".to_string(),
+        ];
+        assert!(run(args)
+            .unwrap_err()
+            .to_string()
+            .starts_with("Error reading file list missing_list.txt"));
+        for path in [report_path, list, first, second] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_run_output_identical_across_job_counts() {
+        let root = std::env::temp_dir().join(format!("mdlt_run_jobs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        for i in 0..40 {
+            let content = "line\r\n".repeat(i) + &"line\n".repeat(40 - i);
+            fs::write(root.join(format!("file{:02}.txt", i)), content).unwrap();
+        }
+        fs::write(root.join("list.txt"), "file_missing_a\nfile_missing_b\n").unwrap();
+
+        let render = |jobs: &str| {
+            let report_path = root.join(format!("report_{}.csv", jobs));
+            let args = vec![
+                "mdlt".to_string(),
+                "-r".to_string(),
+                "--format=csv".to_string(),
+                "--include=file*".to_string(),
+                format!("--jobs={}", jobs),
+                format!("--files-from={}", root.join("list.txt").display()),
+                "--output".to_string(),
+                report_path.to_str().unwrap().to_string(),
+                root.to_str().unwrap().to_string(),
+            ];
+            let err = run(args).unwrap_err();
+            (err, fs::read_to_string(report_path).unwrap())
+        };
+        let sequential = render("1");
+        assert_eq!(sequential.1.lines().count(), 41);
+        assert!(sequential
+            .0
+            .to_string()
+            .starts_with("2 of 42 files could not be analyzed"));
+        assert_eq!(render("4"), sequential);
+        assert_eq!(render("16"), sequential);
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::{self, BufRead, Write};
+
+const CR: u8 = b'\r';
+const LF: u8 = b'\n';
+
+/// What is known about the line being read.
+#[derive(Debug, Default)]
+struct Line {
+    /// Bytes before the terminator.
+    len: usize,
+    has_text: bool,
+    /// The last byte so far was a space or tab.
+    trailing_whitespace: bool,
+}
+
+/// Writes one row per line of `reader`: its number, counting from `first`,
+/// terminator, length in bytes and flags, as each line is read, so that
+/// memory stays fixed and the rows appear as the file is scanned.
+pub fn write_detail(
+    mut reader: impl BufRead,
+    first: u64,
+    writer: &mut impl Write,
+) -> io::Result<()> {
+    let mut number = first - 1;
+    let mut line = Line::default();
+    let mut pending_cr = false;
+    let mut end_line = |line: &mut Line, terminator: &str| -> io::Result<()> {
+        number += 1;
+        let flags = if line.len == 0 {
+            "empty"
+        } else if !line.has_text {
+            "blank"
+        } else if line.trailing_whitespace {
+            "trailing-ws"
+        } else {
+            ""
+        };
+        let row = format!(
+            "{:>8}  {:<4}  {:>8}  {}",
+            number, terminator, line.len, flags
+        );
+        writeln!(writer, "{}", row.trim_end())?;
+        *line = Line::default();
+        Ok(())
+    };
+    loop {
+        let chunk = match reader.fill_buf() {
+            Ok([]) => break,
+            Ok(chunk) => chunk,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        for &byte in chunk {
+            if pending_cr {
+                pending_cr = false;
+                if byte == LF {
+                    end_line(&mut line, "CRLF")?;
+                    continue;
+                }
+                end_line(&mut line, "CR")?;
+            }
+            match byte {
+                CR => pending_cr = true,
+                LF => end_line(&mut line, "LF")?,
+                b' ' | b'\t' => {
+                    line.len += 1;
+                    line.trailing_whitespace = true;
+                }
+                _ => {
+                    line.len += 1;
+                    line.has_text = true;
+                    line.trailing_whitespace = false;
+                }
+            }
+        }
+        let read = chunk.len();
+        reader.consume(read);
+    }
+    if pending_cr {
+        end_line(&mut line, "CR")?;
+    } else if line.len > 0 {
+        end_line(&mut line, "none")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detail(bytes: &[u8], first: u64) -> String {
+        let mut buffer = Vec::new();
+        write_detail(bytes, first, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_write_detail() {
+        assert_eq!(
+            detail(b"fn main() {\r\n\n  \rx \nlast", 1),
+            "       1  CRLF        11\n\
+             \x20      2  LF           0  empty\n\
+             \x20      3  CR           2  blank\n\
+             \x20      4  LF           2  trailing-ws\n\
+             \x20      5  none         4\n"
+        );
+        assert_eq!(
+            detail(b"a\rb\r", 1),
+            "       1  CR           1\n       2  CR           1\n"
+        );
+        assert_eq!(
+            detail(b"b\nc\n", 2),
+            "       2  LF           1\n       3  LF           1\n"
+        );
+    }
+}
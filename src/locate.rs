@@ -0,0 +1,152 @@
+use crate::figures;
+use crate::transitions::Ending;
+
+/// The styles `--locate` compares, in the order ties for the most common
+/// style are settled.
+const STYLES: [Ending; 3] = [Ending::Lf, Ending::Crlf, Ending::Cr];
+
+/// Lines ending in a style other than the file's most common one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MinorityLines {
+    pub ending: Ending,
+    pub count: usize,
+    /// The first few of their line numbers.
+    #[cfg_attr(feature = "serde", serde(rename = "lines"))]
+    pub first: Vec<usize>,
+}
+
+impl MinorityLines {
+    /// "CRLF on lines: 17, 204, 1033 and 2 more", the counts grouped when
+    /// `group`.
+    pub fn summary(&self, group: bool) -> String {
+        let numbers: Vec<String> = self.first.iter().map(|line| line.to_string()).collect();
+        let more = self.count - self.first.len();
+        match (numbers.is_empty(), more) {
+            (true, _) => format!(
+                "{} on {}",
+                self.ending.name(),
+                figures::counted(self.count as u64, "line", group)
+            ),
+            (false, 0) => format!("{} on lines: {}", self.ending.name(), numbers.join(", ")),
+            (false, _) => format!(
+                "{} on lines: {} and {} more",
+                self.ending.name(),
+                numbers.join(", "),
+                figures::count(more as u64, group)
+            ),
+        }
+    }
+}
+
+/// Keeps the first `listed` line numbers of every style. Which style is the
+/// minority is only known at the end, but the first lines of the minority
+/// are always among them, so memory stays fixed however long the file is.
+#[derive(Debug)]
+pub struct Tracker {
+    listed: usize,
+    counts: [usize; 3],
+    lines: [Vec<usize>; 3],
+}
+
+impl Tracker {
+    pub fn new(listed: usize) -> Self {
+        Tracker {
+            listed,
+            counts: [0; 3],
+            lines: Default::default(),
+        }
+    }
+
+    /// Records that line `number` ended with `ending`; Unicode breaks are
+    /// not a style of their own here.
+    pub fn end_line(&mut self, number: usize, ending: Ending) {
+        let Some(style) = STYLES.iter().position(|&style| style == ending) else {
+            return;
+        };
+        self.counts[style] += 1;
+        if self.lines[style].len() < self.listed {
+            self.lines[style].push(number);
+        }
+    }
+
+    /// Takes the tracker of the text right after this one's, which starts
+    /// on line `lines + 1`, as if this one had gone on through it.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker, lines: usize) {
+        for ((count, first), (more, later)) in self
+            .counts
+            .iter_mut()
+            .zip(&mut self.lines)
+            .zip(next.counts.into_iter().zip(next.lines))
+        {
+            *count += more;
+            let room = self.listed - first.len();
+            first.extend(later.into_iter().take(room).map(|line| line + lines));
+        }
+    }
+
+    /// The lines of every style but the most common one, which is empty
+    /// unless the endings are mixed.
+    pub fn finish(self) -> Vec<MinorityLines> {
+        let most = self.counts.iter().copied().max().unwrap_or(0);
+        let dominant = self.counts.iter().position(|&count| count == most);
+        STYLES
+            .iter()
+            .zip(self.counts)
+            .zip(self.lines)
+            .enumerate()
+            .filter(|&(style, ((_, count), _))| Some(style) != dominant && count > 0)
+            .map(|(_, ((&ending, count), first))| MinorityLines {
+                ending,
+                count,
+                first,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn locate(listed: usize, endings: &[Ending]) -> Vec<MinorityLines> {
+        let mut tracker = Tracker::new(listed);
+        for (index, &ending) in endings.iter().enumerate() {
+            tracker.end_line(index + 1, ending);
+        }
+        tracker.finish()
+    }
+
+    #[test]
+    fn test_minority_lines() {
+        let mut endings = vec![Ending::Lf; 40];
+        endings[16] = Ending::Crlf;
+        endings[20] = Ending::Cr;
+        endings[29] = Ending::Crlf;
+        endings[33] = Ending::Unicode;
+        let minority = locate(10, &endings);
+        let summaries: Vec<String> = minority.iter().map(|lines| lines.summary(true)).collect();
+        assert_eq!(summaries, ["CRLF on lines: 17, 30", "CR on lines: 21"]);
+        assert_eq!(locate(10, &[Ending::Crlf; 5]), []);
+        assert_eq!(locate(10, &[]), []);
+    }
+
+    #[test]
+    fn test_listing_is_capped() {
+        let mut endings = vec![Ending::Crlf; 20];
+        for line in [2, 5, 9, 14] {
+            endings[line - 1] = Ending::Lf;
+        }
+        let minority = locate(2, &endings);
+        assert_eq!(minority.len(), 1);
+        assert_eq!(minority[0].summary(true), "LF on lines: 2, 5 and 2 more");
+        assert_eq!(locate(0, &endings)[0].summary(true), "LF on 4 lines");
+    }
+
+    #[test]
+    fn test_ties_favour_lf() {
+        let minority = locate(10, &[Ending::Crlf, Ending::Lf]);
+        assert_eq!(minority[0].summary(true), "CRLF on lines: 1");
+    }
+}
@@ -0,0 +1,239 @@
+use std::io::{self, Write};
+
+/// Extensions that get Markdown statistics without `--lang markdown`.
+pub const EXTENSIONS: &[&str] = &["md", "markdown"];
+
+/// Shortest run of backticks or tildes that opens a code fence.
+const MIN_FENCE: usize = 3;
+
+/// Counts for a Markdown file.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MarkdownStats {
+    /// ATX headings (`#` to `######`) by level.
+    pub headings: [usize; 6],
+    /// Fenced code blocks, closed or not.
+    pub code_blocks: usize,
+    /// Lines between fences, not counting the fences themselves.
+    pub fenced_lines: usize,
+    /// The line of a fence that is still open at the end of the file.
+    #[cfg_attr(feature = "serde", serde(rename = "unclosed_fence_line"))]
+    pub unclosed_fence: Option<usize>,
+    /// Inline links `[text](url)` and images `![alt](url)`.
+    pub links: usize,
+    pub images: usize,
+}
+
+impl MarkdownStats {
+    /// "h1: 1, h2: 4", leaving out levels with no headings.
+    pub fn headings_summary(&self) -> String {
+        let levels: Vec<String> = (1..)
+            .zip(self.headings)
+            .filter(|&(_, count)| count > 0)
+            .map(|(level, count)| format!("h{}: {}", level, count))
+            .collect();
+        match levels.is_empty() {
+            true => "none".to_string(),
+            false => levels.join(", "),
+        }
+    }
+
+    /// Writes the Markdown section of the text report.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "Markdown:")?;
+        writeln!(writer, "  Headings: {}", self.headings_summary())?;
+        writeln!(
+            writer,
+            "  Fenced code blocks: {} ({} lines inside)",
+            self.code_blocks, self.fenced_lines
+        )?;
+        if let Some(line) = self.unclosed_fence {
+            writeln!(writer, "  Unclosed code fence opened on line {}", line)?;
+        }
+        writeln!(writer, "  Links: {}, images: {}", self.links, self.images)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Fence {
+    marker: u8,
+    len: usize,
+    line: usize,
+}
+
+/// Follows a Markdown file line by line. Characters are fed as they arrive
+/// to count links, and each line's start is checked for headings and fences
+/// once the line ends. Fences may be indented any amount so that fences in
+/// list items are found; nothing inside a fence counts as a heading or link.
+/// Links inside inline code spans and reference-style links are not told
+/// apart.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    stats: MarkdownStats,
+    fence: Option<Fence>,
+    /// The previous character on the current line.
+    previous: u32,
+    /// The last `[` followed a `!`.
+    image: bool,
+}
+
+impl Tracker {
+    /// Feeds the next character of the current line, a byte of UTF-8 or a
+    /// UTF-16 code unit.
+    pub fn push(&mut self, c: u32) {
+        if self.fence.is_some() {
+            return;
+        }
+        if c == u32::from(b'[') {
+            self.image = self.previous == u32::from(b'!');
+        } else if c == u32::from(b'(') && self.previous == u32::from(b']') {
+            match std::mem::take(&mut self.image) {
+                true => self.stats.images += 1,
+                false => self.stats.links += 1,
+            }
+        }
+        self.previous = c;
+    }
+
+    /// Finishes line `number`, which starts with `head`, and says whether
+    /// it was inside a code fence.
+    pub fn end_line(&mut self, head: &[u8], number: usize) -> bool {
+        self.previous = 0;
+        self.image = false;
+        let text = trim_start(head);
+        if let Some(fence) = self.fence {
+            if closes(fence, text) {
+                self.fence = None;
+                return false;
+            }
+            self.stats.fenced_lines += 1;
+            return true;
+        }
+        if let Some(fence) = opens(text, number) {
+            self.fence = Some(fence);
+            self.stats.code_blocks += 1;
+        } else if let Some(level) = heading(head) {
+            self.stats.headings[level - 1] += 1;
+        }
+        false
+    }
+
+    pub fn finish(mut self) -> MarkdownStats {
+        self.stats.unclosed_fence = self.fence.map(|fence| fence.line);
+        self.stats
+    }
+}
+
+fn trim_start(text: &[u8]) -> &[u8] {
+    let start = text
+        .iter()
+        .position(|&byte| byte != b' ' && byte != b'\t')
+        .unwrap_or(text.len());
+    &text[start..]
+}
+
+fn run_length(text: &[u8], byte: u8) -> usize {
+    text.iter().take_while(|&&b| b == byte).count()
+}
+
+/// An opening fence: three or more backticks or tildes, where a backtick
+/// fence's info string may not contain backticks.
+fn opens(text: &[u8], line: usize) -> Option<Fence> {
+    let marker = *text.first().filter(|&&byte| byte == b'`' || byte == b'~')?;
+    let len = run_length(text, marker);
+    let valid = len >= MIN_FENCE && (marker == b'~' || !text[len..].contains(&b'`'));
+    valid.then_some(Fence { marker, len, line })
+}
+
+/// A closing fence uses the opening marker at least as many times, with
+/// nothing but whitespace after it.
+fn closes(fence: Fence, text: &[u8]) -> bool {
+    let len = run_length(text, fence.marker);
+    len >= fence.len && trim_start(&text[len..]).is_empty()
+}
+
+/// The level of an ATX heading: up to three spaces, one to six `#` and then
+/// whitespace or the end of the line.
+fn heading(line: &[u8]) -> Option<usize> {
+    let indent = run_length(line, b' ');
+    if indent > 3 {
+        return None;
+    }
+    let text = &line[indent..];
+    let level = run_length(text, b'#');
+    let separated = matches!(text.get(level), None | Some(b' ' | b'\t'));
+    ((1..=6).contains(&level) && separated).then_some(level)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(text: &str) -> MarkdownStats {
+        let mut tracker = Tracker::default();
+        for (index, line) in text.lines().enumerate() {
+            for byte in line.bytes() {
+                tracker.push(u32::from(byte));
+            }
+            tracker.end_line(line.as_bytes(), index + 1);
+        }
+        tracker.finish()
+    }
+
+    #[test]
+    fn test_headings() {
+        let stats =
+            track("# One\n## Two\n###Not\n   ### Three\n    # code\n####### seven\n#\n\t# tab");
+        assert_eq!(stats.headings, [2, 1, 1, 0, 0, 0]);
+        assert_eq!(stats.headings_summary(), "h1: 2, h2: 1, h3: 1");
+        assert_eq!(track("text").headings_summary(), "none");
+    }
+
+    #[test]
+    fn test_fences() {
+        let text = "```rust\n# not a heading\n[not](a link)\n```\n~~~~\n```\n~~~\n~~~~~\n- item\n  ```\n  x\n  ```\n# After";
+        let stats = track(text);
+        assert_eq!(stats.code_blocks, 3);
+        assert_eq!(stats.fenced_lines, 5);
+        assert_eq!(stats.unclosed_fence, None);
+        assert_eq!(stats.headings, [1, 0, 0, 0, 0, 0]);
+        assert_eq!(stats.links, 0);
+    }
+
+    #[test]
+    fn test_unclosed_fence() {
+        let stats = track("# Title\n\n``` `x`\n~~~\n# Not\n```\n");
+        assert_eq!(stats.code_blocks, 1);
+        assert_eq!(stats.unclosed_fence, Some(4));
+        assert_eq!(stats.fenced_lines, 2);
+        let stats = track("````\n```\n");
+        assert_eq!(stats.unclosed_fence, Some(1));
+    }
+
+    #[test]
+    fn test_write_section() {
+        let stats = MarkdownStats {
+            headings: [1, 3, 0, 0, 0, 0],
+            code_blocks: 2,
+            fenced_lines: 9,
+            unclosed_fence: Some(40),
+            links: 5,
+            images: 1,
+        };
+        let mut buffer = Vec::new();
+        stats.write(&mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Markdown:\n  Headings: h1: 1, h2: 3\n  Fenced code blocks: 2 (9 lines inside)\n  \
+             Unclosed code fence opened on line 40\n  Links: 5, images: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_links_and_images() {
+        let stats = track(
+            "See [docs](https://x) and ![logo](logo.png).\n[![badge](b.svg)](ci) [x] (y) [a]\n(b)",
+        );
+        assert_eq!((stats.links, stats.images), (2, 2));
+    }
+}
@@ -0,0 +1,192 @@
+#[cfg(feature = "cli")]
+use std::fs;
+use std::io::{self, Write};
+use std::time::SystemTime;
+
+use crate::figures;
+
+/// Who may do what with a file, as far as the platform says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permissions {
+    /// Unix permission bits, including setuid, setgid and sticky.
+    #[cfg_attr(any(not(unix), not(feature = "cli")), allow(dead_code))]
+    Mode(u32),
+    /// The read-only attribute elsewhere.
+    #[cfg_attr(any(unix, not(feature = "cli")), allow(dead_code))]
+    ReadOnly(bool),
+}
+
+impl Permissions {
+    #[cfg(feature = "cli")]
+    fn from_fs(permissions: &fs::Permissions) -> Self {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            Permissions::Mode(permissions.mode() & 0o7777)
+        }
+        #[cfg(not(unix))]
+        {
+            Permissions::ReadOnly(permissions.readonly())
+        }
+    }
+
+    /// "rw-r--r-- (0644)", or "read-only" and "writable".
+    pub fn summary(self) -> String {
+        match self {
+            Permissions::Mode(mode) => {
+                let bits: String = (0..9)
+                    .map(|bit| match mode & (0o400 >> bit) {
+                        0 => '-',
+                        _ => ['r', 'w', 'x'][bit % 3],
+                    })
+                    .collect();
+                format!("{} ({:04o})", bits, mode)
+            }
+            Permissions::ReadOnly(true) => "read-only".to_string(),
+            Permissions::ReadOnly(false) => "writable".to_string(),
+        }
+    }
+}
+
+/// Filesystem metadata for `--metadata`. Every part is optional: stdin has
+/// none, and a platform or filesystem may not provide some of it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileMeta {
+    pub modified: Option<SystemTime>,
+    pub size: Option<u64>,
+    pub permissions: Option<Permissions>,
+}
+
+impl FileMeta {
+    /// Reads the metadata of `path`, leaving out what cannot be read.
+    #[cfg(feature = "cli")]
+    pub fn read(path: &str) -> Self {
+        let Ok(metadata) = fs::metadata(crate::osname::path(path)) else {
+            return FileMeta::default();
+        };
+        FileMeta {
+            modified: metadata.modified().ok(),
+            size: Some(metadata.len()),
+            permissions: Some(Permissions::from_fs(&metadata.permissions())),
+        }
+    }
+
+    /// Writes the metadata section of the text report, with how long ago
+    /// the file was modified as of `now`.
+    pub fn write(&self, now: SystemTime, writer: &mut impl Write) -> io::Result<()> {
+        const UNAVAILABLE: &str = "unavailable";
+        writeln!(writer, "Metadata:")?;
+        match self.modified {
+            Some(modified) => writeln!(
+                writer,
+                "  Modified: {} ({})",
+                figures::rfc3339(modified),
+                relative(modified, now)
+            )?,
+            None => writeln!(writer, "  Modified: {}", UNAVAILABLE)?,
+        }
+        match self.size {
+            Some(size) => writeln!(writer, "  Size: {} bytes", figures::grouped(size))?,
+            None => writeln!(writer, "  Size: {}", UNAVAILABLE)?,
+        }
+        let permissions = self.permissions.map(Permissions::summary);
+        writeln!(
+            writer,
+            "  Permissions: {}",
+            permissions.as_deref().unwrap_or(UNAVAILABLE)
+        )
+    }
+}
+
+/// How long before `now` `time` was, in its largest whole unit: "just now",
+/// "5 minutes ago", "3 days ago"; times after `now` are "in the future".
+pub fn relative(time: SystemTime, now: SystemTime) -> String {
+    let Ok(elapsed) = now.duration_since(time) else {
+        return "in the future".to_string();
+    };
+    let seconds = elapsed.as_secs();
+    let units = [
+        (365 * 86_400, "year"),
+        (30 * 86_400, "month"),
+        (86_400, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+        (1, "second"),
+    ];
+    match units.iter().find(|&&(length, _)| seconds >= length) {
+        Some(&(length, unit)) => {
+            let count = seconds / length;
+            format!(
+                "{} {}{} ago",
+                count,
+                unit,
+                if count == 1 { "" } else { "s" }
+            )
+        }
+        None => "just now".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_relative() {
+        let now = at(1_000_000_000);
+        assert_eq!(relative(now, now), "just now");
+        assert_eq!(relative(at(999_999_999), now), "1 second ago");
+        assert_eq!(relative(at(1_000_000_000 - 300), now), "5 minutes ago");
+        assert_eq!(
+            relative(at(1_000_000_000 - 3 * 86_400 - 7), now),
+            "3 days ago"
+        );
+        assert_eq!(
+            relative(at(1_000_000_000 - 400 * 86_400), now),
+            "1 year ago"
+        );
+        assert_eq!(relative(at(1_000_000_001), now), "in the future");
+    }
+
+    #[test]
+    fn test_permissions() {
+        assert_eq!(Permissions::Mode(0o644).summary(), "rw-r--r-- (0644)");
+        assert_eq!(Permissions::Mode(0o4755).summary(), "rwxr-xr-x (4755)");
+        assert_eq!(Permissions::ReadOnly(true).summary(), "read-only");
+    }
+
+    #[test]
+    fn test_write_section() {
+        let meta = FileMeta {
+            modified: Some(at(1_791_729_000)),
+            size: Some(10_432),
+            permissions: Some(Permissions::Mode(0o600)),
+        };
+        let mut buffer = Vec::new();
+        meta.write(at(1_791_729_000 + 2 * 3600), &mut buffer)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Metadata:\n  Modified: 2026-10-11T14:30:00Z (2 hours ago)\n  \
+             Size: 10,432 bytes\n  Permissions: rw------- (0600)\n"
+        );
+        let mut buffer = Vec::new();
+        FileMeta::default().write(at(0), &mut buffer).unwrap();
+        assert_eq!(
+            String::from_utf8(buffer).unwrap(),
+            "Metadata:\n  Modified: unavailable\n  Size: unavailable\n  Permissions: unavailable\n"
+        );
+    }
+
+    #[cfg(feature = "cli")]
+    #[test]
+    fn test_read_missing_file() {
+        assert_eq!(FileMeta::read("no/such/file"), FileMeta::default());
+    }
+}
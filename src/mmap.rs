@@ -0,0 +1,64 @@
+use std::fs::File;
+
+use crate::scanner::ScanOptions;
+use crate::FileStats;
+
+/// Files at least this large are memory-mapped when neither `--mmap` nor
+/// `--no-mmap` is given.
+pub const AUTO_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// When to memory-map a file instead of streaming it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MmapMode {
+    /// Map files of at least [`AUTO_THRESHOLD`] bytes.
+    Auto,
+    /// Map every non-empty file (`--mmap`).
+    Always,
+    /// Never map (`--no-mmap`).
+    Never,
+}
+
+impl MmapMode {
+    /// Whether a regular file of `len` bytes should be mapped. Empty files
+    /// are never mapped, since mapping zero bytes fails on most platforms.
+    pub fn should_map(self, len: u64) -> bool {
+        len > 0
+            && match self {
+                MmapMode::Auto => len >= AUTO_THRESHOLD,
+                MmapMode::Always => true,
+                MmapMode::Never => false,
+            }
+    }
+}
+
+/// Maps `file` and scans it in one pass, returning `None` if it cannot be
+/// mapped so the caller can fall back to streaming.
+#[cfg(feature = "mmap")]
+pub fn analyze(file: &File, name: &str, options: ScanOptions) -> Option<FileStats> {
+    // SAFETY: the map is read-only and dropped before returning. If another
+    // process truncates the file meanwhile, reads past the new end fault
+    // (SIGBUS) rather than returning stale data; this is the usual caveat
+    // of mapping files and is why mapping is only automatic for big files.
+    let map = unsafe { memmap2::Mmap::map(file) }.ok()?;
+    Some(crate::analyze_bytes_with(&map, name.to_string(), options))
+}
+
+#[cfg(not(feature = "mmap"))]
+pub fn analyze(_file: &File, _name: &str, _options: ScanOptions) -> Option<FileStats> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_should_map() {
+        assert!(!MmapMode::Always.should_map(0));
+        assert!(!MmapMode::Auto.should_map(0));
+        assert!(MmapMode::Always.should_map(1));
+        assert!(!MmapMode::Auto.should_map(AUTO_THRESHOLD - 1));
+        assert!(MmapMode::Auto.should_map(AUTO_THRESHOLD));
+        assert!(!MmapMode::Never.should_map(AUTO_THRESHOLD));
+    }
+}
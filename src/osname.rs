@@ -0,0 +1,313 @@
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+#[cfg(feature = "cli")]
+use std::path::Path;
+#[cfg(all(windows, feature = "cli"))]
+use std::path::PathBuf;
+
+/// The first of the 256 characters that stand for the bytes of a file name
+/// that are not UTF-8 while a run carries it in a `String`, from the end of
+/// the last private use plane. A name may have these characters too, so
+/// those it has are carried as the bytes of their UTF-8 as well, and every
+/// one of them is a byte.
+const RAW_BYTES: u32 = 0x10FF00;
+
+/// `name` as the `String` a run carries paths in on their way from the
+/// command line to the file system: the name itself when it is valid UTF-8
+/// without any of the [`RAW_BYTES`] characters, and otherwise with each
+/// byte of its invalid sequences and of those characters kept as a
+/// character of its own, so that [`decode`] gives back `name`. On Windows
+/// the bytes are those of the name's WTF-8, which keeps the unpaired
+/// surrogates a name may have.
+pub fn encode(name: &OsStr) -> Cow<'_, str> {
+    match name.to_str() {
+        Some(text) if !is_encoded(text) => Cow::Borrowed(text),
+        _ => Cow::Owned(carry(name.as_encoded_bytes())),
+    }
+}
+
+/// The name made of `bytes`, as from a `--files-from` list, the way
+/// [`encode`] carries it. Outside Unix names are not bytes, and invalid
+/// sequences become `�`.
+#[cfg(feature = "cli")]
+pub fn from_bytes(bytes: &[u8]) -> String {
+    #[cfg(unix)]
+    {
+        carry(bytes)
+    }
+    #[cfg(not(unix))]
+    {
+        carry(String::from_utf8_lossy(bytes).as_bytes())
+    }
+}
+
+/// `bytes` with each byte of their invalid sequences, and of the
+/// [`RAW_BYTES`] characters they have, as a character of its own.
+fn carry(bytes: &[u8]) -> String {
+    let raw = |byte: &u8| char::from_u32(RAW_BYTES + u32::from(*byte));
+    let mut encoded = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match raw_byte(c) {
+                Some(_) => {
+                    encoded.extend(c.encode_utf8(&mut [0; 4]).as_bytes().iter().filter_map(raw))
+                }
+                None => encoded.push(c),
+            }
+        }
+        encoded.extend(chunk.invalid().iter().filter_map(raw));
+    }
+    encoded
+}
+
+/// The byte a character of an [`encode`]d name stands for.
+fn raw_byte(c: char) -> Option<u8> {
+    (c as u32)
+        .checked_sub(RAW_BYTES)
+        .and_then(|byte| u8::try_from(byte).ok())
+}
+
+/// Whether `name` is carried as other than its own characters, which
+/// [`bytes`] turns back.
+fn is_encoded(name: &str) -> bool {
+    name.chars().any(|c| raw_byte(c).is_some())
+}
+
+/// Whether `name` holds bytes that are not UTF-8, which [`display`]
+/// replaces.
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+pub fn is_lossy(name: &str) -> bool {
+    decode(name).to_str().is_none()
+}
+
+/// The bytes of the file name `name` stands for, for `--print0`.
+#[cfg_attr(not(feature = "cli"), allow(dead_code))]
+pub fn bytes(name: &str) -> Cow<'_, [u8]> {
+    if !is_encoded(name) {
+        return Cow::Borrowed(name.as_bytes());
+    }
+    let mut bytes = Vec::with_capacity(name.len());
+    for c in name.chars() {
+        match raw_byte(c) {
+            Some(byte) => bytes.push(byte),
+            None => bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+        }
+    }
+    Cow::Owned(bytes)
+}
+
+/// The name [`encode`] was given for `name`.
+pub fn decode(name: &str) -> Cow<'_, OsStr> {
+    if !is_encoded(name) {
+        return Cow::Borrowed(OsStr::new(name));
+    }
+    let bytes = bytes(name).into_owned();
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Cow::Owned(OsString::from_vec(bytes))
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStringExt;
+        Cow::Owned(OsString::from_wide(&wide(&bytes)))
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        Cow::Owned(OsString::from(String::from_utf8_lossy(&bytes).into_owned()))
+    }
+}
+
+/// The UTF-16 of the WTF-8 `bytes`, unpaired surrogates and all, with a
+/// `�` for each byte that starts no sequence of it.
+#[cfg(any(windows, all(test, feature = "cli")))]
+fn wide(bytes: &[u8]) -> Vec<u16> {
+    const SMALLEST: [u32; 5] = [0, 0, 0x80, 0x800, 0x10000];
+    let mut wide = Vec::with_capacity(bytes.len());
+    let mut rest = bytes;
+    while let Some(&first) = rest.first() {
+        let len = match first {
+            0x00..=0x7F => 1,
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => 0,
+        };
+        let sequence = rest
+            .get(1..len)
+            .filter(|tail| tail.iter().all(|byte| byte & 0xC0 == 0x80));
+        let code = sequence.map(|tail| {
+            let lead = u32::from(first & [0, 0x7F, 0x1F, 0x0F, 0x07][len]);
+            tail.iter()
+                .fold(lead, |code, byte| code << 6 | u32::from(byte & 0x3F))
+        });
+        match code.filter(|&code| code >= SMALLEST[len] && code <= 0x10FFFF) {
+            Some(code) => {
+                match char::from_u32(code) {
+                    Some(c) => wide.extend_from_slice(c.encode_utf16(&mut [0; 2])),
+                    // A surrogate, which WTF-8 keeps when it is unpaired.
+                    None => wide.push(code as u16),
+                }
+                rest = &rest[len..];
+            }
+            None => {
+                wide.push(0xFFFD);
+                rest = &rest[1..];
+            }
+        }
+    }
+    wide
+}
+
+/// The path to open for `name`, as [`encode`] was given it, in the
+/// [`long`] form when it needs it.
+#[cfg(feature = "cli")]
+pub fn path(name: &str) -> Cow<'_, Path> {
+    match decode(name) {
+        Cow::Borrowed(name) => long(Path::new(name)),
+        Cow::Owned(name) => Cow::Owned(long(Path::new(&name)).into_owned()),
+    }
+}
+
+/// How many characters a Windows path may have, with the NUL that ends it,
+/// before only the extended-length form opens it.
+#[cfg(any(windows, all(test, feature = "cli")))]
+const MAX_PATH: usize = 260;
+
+/// `path` in the form that opens it: on Windows, once it is [`MAX_PATH`]
+/// characters long made absolute, then [`extended_length`]. Elsewhere, and
+/// for shorter paths, `path` itself, so that what is shown of a path is
+/// always the form it was given in.
+#[cfg(feature = "cli")]
+pub fn long(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    if let Ok(absolute) = std::path::absolute(path) {
+        if let Some(text) = absolute.to_str() {
+            if text.encode_utf16().count() >= MAX_PATH {
+                return Cow::Owned(PathBuf::from(extended_length(text).into_owned()));
+            }
+        }
+    }
+    Cow::Borrowed(path)
+}
+
+/// The extended-length form of the absolute Windows path `path`, which
+/// Windows opens whatever its length: `\\?\C:\dir\file` for
+/// `C:\dir\file`, and `\\?\UNC\server\share\file` for the UNC path
+/// `\\server\share\file`. The prefix turns off the parsing that would
+/// otherwise read `/` as `\`, so slashes become backslashes first. Paths
+/// with a `\\?\` or `\\.\` prefix already, and relative ones, which the
+/// prefix cannot take, are left alone.
+#[cfg(any(windows, all(test, feature = "cli")))]
+fn extended_length(path: &str) -> Cow<'_, str> {
+    let backslashed = path.replace('/', "\\");
+    if backslashed.starts_with(r"\\?\") || backslashed.starts_with(r"\\.\") {
+        return Cow::Borrowed(path);
+    }
+    if let Some(unc) = backslashed.strip_prefix(r"\\") {
+        return Cow::Owned(format!(r"\\?\UNC\{}", unc));
+    }
+    match backslashed.as_bytes() {
+        [drive, b':', b'\\', ..] if drive.is_ascii_alphabetic() => {
+            Cow::Owned(format!(r"\\?\{}", backslashed))
+        }
+        _ => Cow::Borrowed(path),
+    }
+}
+
+/// `name` for people to read, each invalid sequence a `�` as
+/// `Path::display` shows it.
+pub fn display(name: &str) -> Cow<'_, str> {
+    match decode(name) {
+        Cow::Borrowed(_) => Cow::Borrowed(name),
+        Cow::Owned(name) => Cow::Owned(name.to_string_lossy().into_owned()),
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utf8_names_are_themselves() {
+        assert_eq!(encode(OsStr::new("说明.md")), "说明.md");
+        assert!(!is_lossy("说明.md"));
+        assert_eq!(path("说明.md"), Path::new("说明.md"));
+        assert_eq!(display("a\u{10feff}"), "a\u{10feff}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_private_use_names_are_not_bytes() {
+        let name = encode(OsStr::new("pua\u{10FF41}.txt"));
+        assert_ne!(name, "pua\u{10FF41}.txt");
+        assert!(!is_lossy(&name));
+        assert_eq!(path(&name), Path::new("pua\u{10FF41}.txt"));
+        assert_eq!(display(&name), "pua\u{10FF41}.txt");
+        assert_eq!(&*bytes(&name), "pua\u{10FF41}.txt".as_bytes());
+        assert_eq!(path(&encode(OsStr::new("puaA.txt"))), Path::new("puaA.txt"));
+    }
+
+    #[test]
+    fn test_extended_length() {
+        assert_eq!(extended_length(r"C:\src\main.rs"), r"\\?\C:\src\main.rs");
+        assert_eq!(
+            extended_length("C:/node_modules/a/b.js"),
+            r"\\?\C:\node_modules\a\b.js"
+        );
+        assert_eq!(
+            extended_length(r"\\server\share\file.txt"),
+            r"\\?\UNC\server\share\file.txt"
+        );
+        assert_eq!(
+            extended_length("//server/share/file.txt"),
+            r"\\?\UNC\server\share\file.txt"
+        );
+        for unchanged in [
+            r"\\?\C:\a.txt",
+            r"\\?\UNC\server\share\a",
+            r"\\.\COM1",
+            r"src\a.txt",
+            "b.txt",
+        ] {
+            assert_eq!(extended_length(unchanged), unchanged);
+        }
+    }
+
+    #[test]
+    fn test_long_keeps_short_paths() {
+        let short = Path::new("src/main.rs");
+        assert!(matches!(long(short), Cow::Borrowed(path) if path == short));
+        let deep = "d".repeat(MAX_PATH);
+        #[cfg(not(windows))]
+        assert_eq!(long(Path::new(&deep)), Path::new(&deep));
+        #[cfg(windows)]
+        assert!(long(Path::new(&deep))
+            .to_str()
+            .unwrap()
+            .starts_with(r"\\?\"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_invalid_names_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+        let raw = OsStr::from_bytes(b"caf\xe9 \xff\xfe.txt");
+        let name = encode(raw);
+        assert!(is_lossy(&name));
+        assert_eq!(&*bytes(&name), raw.as_bytes());
+        assert_eq!(path(&name).as_os_str(), raw);
+        assert_eq!(display(&name), "caf\u{fffd} \u{fffd}\u{fffd}.txt");
+        assert_eq!(display(&name), raw.to_string_lossy());
+    }
+
+    #[test]
+    fn test_wide_keeps_unpaired_surrogates() {
+        let utf16: Vec<u16> = "a\u{e9}\u{20ac}\u{1f600}".encode_utf16().collect();
+        assert_eq!(wide("a\u{e9}\u{20ac}\u{1f600}".as_bytes()), utf16);
+        // WTF-8 of a lone U+D800, which Windows names may hold.
+        assert_eq!(wide(b"x\xed\xa0\x80y"), [0x78, 0xD800, 0x79]);
+        assert_eq!(wide(b"\xff\xc3"), [0xFFFD, 0xFFFD]);
+        assert_eq!(wide(b"\xc0\x80"), [0xFFFD, 0xFFFD]);
+    }
+}
@@ -0,0 +1,172 @@
+use crate::figures;
+
+/// The pages form feeds split a file into, as line printers did: one more
+/// than there are form feeds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Pages {
+    pub form_feeds: usize,
+    pub min_lines: usize,
+    pub max_lines: usize,
+    /// The lines of all the pages, from which their mean comes.
+    pub lines: usize,
+}
+
+impl Pages {
+    pub fn count(&self) -> usize {
+        self.form_feeds + 1
+    }
+
+    pub fn mean_lines(&self) -> f64 {
+        self.lines as f64 / self.count() as f64
+    }
+
+    /// "3 (2 form feeds)".
+    /// The counts are grouped when `group`.
+    pub fn summary(&self, group: bool) -> String {
+        format!(
+            "{} ({})",
+            figures::count(self.count() as u64, group),
+            figures::counted(self.form_feeds as u64, "form feed", group)
+        )
+    }
+
+    /// Adds a page of `lines` lines, the first one when there is none.
+    fn add(pages: &mut Option<Pages>, lines: usize) {
+        Pages::join(
+            pages,
+            Pages {
+                form_feeds: 0,
+                min_lines: lines,
+                max_lines: lines,
+                lines,
+            },
+        );
+    }
+
+    /// Adds the pages `other` counts.
+    fn join(pages: &mut Option<Pages>, other: Pages) {
+        match pages {
+            Some(pages) => {
+                pages.min_lines = pages.min_lines.min(other.min_lines);
+                pages.max_lines = pages.max_lines.max(other.max_lines);
+                pages.lines += other.lines;
+            }
+            None => *pages = Some(other),
+        }
+    }
+}
+
+/// Counts the lines of each page as the form feeds go by. A page holds the
+/// lines that start on it, so a line a form feed is in the middle of, as in
+/// `total\x0c`, belongs to the page the form feed ends.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    form_feeds: usize,
+    /// The lines of the first page, once a form feed has ended it, kept
+    /// apart from `pages` until `finish` so that `merge` can join it to the
+    /// page the text before ends on.
+    first: Option<usize>,
+    /// The pages after it that have ended.
+    pages: Option<Pages>,
+    /// How many lines came before the current page.
+    start: usize,
+}
+
+impl Tracker {
+    /// Ends the current page at a form feed, after `end` lines of the file.
+    pub fn form_feed(&mut self, end: usize) {
+        let lines = end - self.start;
+        match self.first {
+            None => self.first = Some(lines),
+            Some(_) => Pages::add(&mut self.pages, lines),
+        }
+        self.form_feeds += 1;
+        self.start = end;
+    }
+
+    /// Takes the tracker of the text right after this one's, which starts
+    /// after `lines` lines, as if this one had gone on through it. This
+    /// one's text must end with a line break.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker, lines: usize) {
+        // Its first page is the rest of the one this ends on.
+        let Some(first) = next.first else {
+            return;
+        };
+        self.form_feed(lines + first);
+        if let Some(pages) = next.pages {
+            Pages::join(&mut self.pages, pages);
+        }
+        self.form_feeds += next.form_feeds - 1;
+        self.start = next.start + lines;
+    }
+
+    /// The pages of a file of `total_lines` lines, or `None` when it has no
+    /// form feed to split it.
+    pub fn finish(self, total_lines: usize) -> Option<Pages> {
+        let mut pages = self.pages;
+        Pages::add(&mut pages, self.first?);
+        Pages::add(&mut pages, total_lines.saturating_sub(self.start));
+        pages.map(|pages| Pages {
+            form_feeds: self.form_feeds,
+            ..pages
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pages() {
+        assert_eq!(Tracker::default().finish(10), None);
+        let mut tracker = Tracker::default();
+        tracker.form_feed(4);
+        tracker.form_feed(5);
+        let pages = tracker.finish(7).unwrap();
+        assert_eq!(
+            pages,
+            Pages {
+                form_feeds: 2,
+                min_lines: 1,
+                max_lines: 4,
+                lines: 7
+            }
+        );
+        assert_eq!(pages.summary(true), "3 (2 form feeds)");
+        let many = Pages {
+            form_feeds: 1000,
+            ..pages
+        };
+        assert_eq!(many.summary(true), "1,001 (1,000 form feeds)");
+        assert_eq!(many.summary(false), "1001 (1000 form feeds)");
+        assert!((pages.mean_lines() - 7.0 / 3.0).abs() < 1e-9);
+        // A form feed at the very end leaves an empty last page.
+        let mut tracker = Tracker::default();
+        tracker.form_feed(2);
+        assert_eq!(tracker.finish(2).unwrap().min_lines, 0);
+    }
+
+    #[test]
+    fn test_merge() {
+        let ends = [3, 3, 7, 12];
+        let whole = {
+            let mut tracker = Tracker::default();
+            ends.iter().for_each(|&end| tracker.form_feed(end));
+            tracker.finish(15)
+        };
+        for lines in 0..=15 {
+            let mut tracker = Tracker::default();
+            let mut next = Tracker::default();
+            for &end in &ends {
+                match end <= lines {
+                    true => tracker.form_feed(end),
+                    false => next.form_feed(end - lines),
+                }
+            }
+            tracker.merge(next, lines);
+            assert_eq!(tracker.finish(15), whole, "{}", lines);
+        }
+    }
+}
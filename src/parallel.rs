@@ -0,0 +1,144 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// Number of workers to use when `--jobs` is not given.
+pub fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// The threads `map_ordered` shares `items` items between with `jobs`
+/// jobs.
+pub fn workers(jobs: usize, items: usize) -> usize {
+    jobs.clamp(1, items.max(1))
+}
+
+/// Applies `f` to every item on up to `jobs` threads and returns the results
+/// in the order of `items`, regardless of which finished first. With one job
+/// (or one item) everything runs on the calling thread.
+pub fn map_ordered<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let mut results = Vec::with_capacity(items.len());
+    for_each_ordered(items, jobs, f, |result| results.push(result));
+    results
+}
+
+/// Like `map_ordered`, but hands each result to `consume` on the calling
+/// thread as soon as those of the items before it are in, instead of once
+/// every item is done. With one job each item is consumed before the next
+/// one is started.
+pub fn for_each_ordered<'a, T, R, F>(items: &'a [T], jobs: usize, f: F, mut consume: impl FnMut(R))
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&'a T) -> R + Sync,
+{
+    let jobs = workers(jobs, items.len());
+    if jobs == 1 {
+        items.iter().map(f).for_each(consume);
+        return;
+    }
+
+    let next = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            let sender = sender.clone();
+            let (next, f) = (&next, &f);
+            scope.spawn(move || loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                if sender.send((index, f(item))).is_err() {
+                    break;
+                }
+            });
+        }
+        drop(sender);
+
+        // Results that came in ahead of an earlier one wait for it.
+        let mut slots: Vec<Option<R>> = items.iter().map(|_| None).collect();
+        let mut consumed = 0;
+        for (index, result) in receiver {
+            slots[index] = Some(result);
+            while let Some(result) = slots.get_mut(consumed).and_then(Option::take) {
+                consume(result);
+                consumed += 1;
+            }
+        }
+        debug_assert_eq!(
+            consumed,
+            items.len(),
+            "every item is processed exactly once"
+        );
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_map_ordered_preserves_order() {
+        let items: Vec<u64> = (0..50).collect();
+        let slow_first = |n: &u64| {
+            // Early items take longest so they finish last.
+            thread::sleep(Duration::from_micros(500 - n * 10));
+            n * 2
+        };
+        let expected: Vec<u64> = items.iter().map(|n| n * 2).collect();
+        assert_eq!(map_ordered(&items, 1, slow_first), expected);
+        assert_eq!(map_ordered(&items, 4, slow_first), expected);
+        assert_eq!(map_ordered(&items, 100, slow_first), expected);
+    }
+
+    #[test]
+    fn test_for_each_ordered_consumes_early() {
+        use std::sync::Mutex;
+
+        // The first item is consumed before the last one is started on one
+        // thread, and before the slow last one is done on several.
+        let items: Vec<u64> = (0..8).collect();
+        for jobs in [1, 2] {
+            let events = Mutex::new(Vec::new());
+            let started = |n: &u64| {
+                events.lock().unwrap().push(format!("start {}", n));
+                if *n == 7 {
+                    thread::sleep(Duration::from_millis(50));
+                    events.lock().unwrap().push("end 7".to_string());
+                }
+                *n
+            };
+            let mut consumed = Vec::new();
+            for_each_ordered(&items, jobs, started, |n| {
+                events.lock().unwrap().push(format!("consume {}", n));
+                consumed.push(n);
+            });
+            assert_eq!(consumed, items);
+            let events = events.into_inner().unwrap();
+            let position = |event: &str| events.iter().position(|e| e == event).unwrap();
+            if jobs == 1 {
+                assert!(position("consume 0") < position("start 1"));
+            }
+            assert!(position("consume 0") < position("end 7"));
+        }
+    }
+
+    #[test]
+    fn test_map_ordered_empty_and_zero_jobs() {
+        let empty: Vec<u8> = Vec::new();
+        assert!(map_ordered(&empty, 8, |n| *n).is_empty());
+        assert_eq!(map_ordered(&[1, 2, 3], 0, |n| n + 1), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn test_default_jobs_is_positive() {
+        assert!(default_jobs() >= 1);
+    }
+}
@@ -0,0 +1,176 @@
+use std::str;
+
+use crate::figures;
+use crate::regex::{Matcher, Regex, Units};
+
+/// The `--count-pattern` regexes, compiled once for every file, and how
+/// many line numbers each lists. The regexes are leaked when the flags are
+/// parsed so that scan settings stay `Copy`.
+#[derive(Debug, Clone, Copy)]
+pub struct Patterns {
+    pub regexes: &'static [Regex],
+    pub listed: usize,
+}
+
+/// What one `--count-pattern` found in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternCount {
+    /// The pattern as given, which labels the counts.
+    pub pattern: String,
+    /// Lines with at least one match.
+    pub lines: usize,
+    /// Matches in all, several to a line or not.
+    pub matches: usize,
+    /// The first few of the matching line numbers.
+    pub first: Vec<usize>,
+}
+
+impl PatternCount {
+    /// "3 matching lines (2, 7, 9), 4 matches", the counts grouped when
+    /// `group`.
+    pub fn summary(&self, group: bool) -> String {
+        if self.lines == 0 {
+            return "no matching lines".to_string();
+        }
+        let numbers: Vec<String> = self.first.iter().map(|line| line.to_string()).collect();
+        let more = self.lines - self.first.len();
+        format!(
+            "{} ({}{}), {} match{}",
+            figures::counted(self.lines as u64, "matching line", group),
+            numbers.join(", "),
+            if more > 0 {
+                format!(" and {} more", figures::count(more as u64, group))
+            } else {
+                String::new()
+            },
+            figures::count(self.matches as u64, group),
+            if self.matches == 1 { "" } else { "es" }
+        )
+    }
+}
+
+/// Matches each line against the patterns once it has ended. Lines come
+/// without their terminators, as UTF-8 for UTF-16 input; one that is not
+/// valid UTF-8 matches only patterns read with `--bytes-regex`.
+#[derive(Debug)]
+pub struct Tracker {
+    listed: usize,
+    matchers: Vec<Matcher<'static>>,
+    counts: Vec<PatternCount>,
+    /// The current line so far.
+    line: Vec<u8>,
+    /// The line as characters or bytes, whichever the patterns read.
+    units: Vec<u32>,
+}
+
+impl Tracker {
+    pub fn new(patterns: Patterns) -> Self {
+        Tracker {
+            listed: patterns.listed,
+            matchers: patterns.regexes.iter().map(Regex::matcher).collect(),
+            counts: patterns
+                .regexes
+                .iter()
+                .map(|regex| PatternCount {
+                    pattern: regex.source().to_string(),
+                    lines: 0,
+                    matches: 0,
+                    first: Vec::new(),
+                })
+                .collect(),
+            line: Vec::new(),
+            units: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.line.extend_from_slice(bytes);
+    }
+
+    /// Matches the line that just ended, which is numbered `line`.
+    pub fn end_line(&mut self, line: usize) {
+        let text = str::from_utf8(&self.line).ok();
+        let mut read_as = None;
+        for (matcher, count) in self.matchers.iter_mut().zip(&mut self.counts) {
+            let units = matcher.units();
+            if units == Units::Chars && text.is_none() {
+                continue;
+            }
+            if read_as != Some(units) {
+                self.units.clear();
+                match (units, text) {
+                    (Units::Chars, Some(text)) => self.units.extend(text.chars().map(u32::from)),
+                    _ => self
+                        .units
+                        .extend(self.line.iter().map(|&byte| u32::from(byte))),
+                }
+                read_as = Some(units);
+            }
+            let matches = matcher.count(&self.units);
+            if matches > 0 {
+                count.lines += 1;
+                count.matches += matches;
+                if count.first.len() < self.listed {
+                    count.first.push(line);
+                }
+            }
+        }
+        self.line.clear();
+    }
+
+    pub fn finish(self) -> Vec<PatternCount> {
+        self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counts(patterns: &[(&str, Units)], lines: &[&[u8]]) -> Vec<PatternCount> {
+        let regexes: Vec<Regex> = patterns
+            .iter()
+            .map(|&(pattern, units)| Regex::new(pattern, units).unwrap())
+            .collect();
+        let mut tracker = Tracker::new(Patterns {
+            regexes: Box::leak(regexes.into_boxed_slice()),
+            listed: 2,
+        });
+        for (index, line) in lines.iter().enumerate() {
+            tracker.push(line);
+            tracker.end_line(index + 1);
+        }
+        tracker.finish()
+    }
+
+    #[test]
+    fn test_counts() {
+        let found = counts(
+            &[("TODO|FIXME", Units::Chars), ("caf\\xE9", Units::Bytes)],
+            &[
+                b"// TODO: one",
+                b"fine",
+                b"TODO and FIXME",
+                b"caf\xe9 TODO",
+                b"FIXME",
+            ],
+        );
+        assert_eq!(
+            found[0],
+            PatternCount {
+                pattern: "TODO|FIXME".to_string(),
+                lines: 3,
+                matches: 4,
+                first: vec![1, 3],
+            }
+        );
+        assert_eq!(
+            found[0].summary(true),
+            "3 matching lines (1, 3 and 1 more), 4 matches"
+        );
+        assert_eq!((found[1].lines, found[1].first.clone()), (1, vec![4]));
+        assert_eq!(found[1].summary(true), "1 matching line (4), 1 match");
+        let none = counts(&[("x", Units::Chars)], &[b"a"]);
+        assert_eq!(none[0].summary(true), "no matching lines");
+    }
+}
@@ -0,0 +1,107 @@
+use crate::preview;
+
+/// The first and last lines with something other than spaces and tabs on
+/// them, as `--peek` shows them; `None` for a file without any.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Peek {
+    pub first: Option<String>,
+    pub last: Option<String>,
+}
+
+/// Keeps the start of the first line with text and of the latest one, so
+/// that the last is known at the end however long the file is.
+#[derive(Debug)]
+pub struct Tracker {
+    /// Characters shown of each line.
+    width: usize,
+    first: Option<(Vec<u8>, bool)>,
+    last: Option<(Vec<u8>, bool)>,
+}
+
+impl Tracker {
+    pub fn new(width: usize) -> Self {
+        Tracker {
+            width,
+            first: None,
+            last: None,
+        }
+    }
+
+    /// Takes a line with text, which starts with `head`, all of it when
+    /// `complete`.
+    pub fn end_line(&mut self, head: &[u8], complete: bool) {
+        if self.first.is_none() {
+            self.first = Some((head.to_vec(), complete));
+            return;
+        }
+        // The buffer of the line before is reused.
+        let (last, last_complete) = self.last.get_or_insert_with(Default::default);
+        last.clear();
+        last.extend_from_slice(head);
+        *last_complete = complete;
+    }
+
+    /// Takes the tracker of the text right after this one's, as if this
+    /// one had gone on through it.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker) {
+        match (&self.first, next.first) {
+            (None, first) => {
+                self.first = first;
+                self.last = next.last;
+            }
+            (Some(_), Some(first)) => self.last = next.last.or(Some(first)),
+            (Some(_), None) => {}
+        }
+    }
+
+    /// The previews, every byte that is not printable ASCII escaped as
+    /// `\xNN` in a `binary` file. A file with one line of text has it as
+    /// its first and last.
+    pub fn finish(self, binary: bool) -> Peek {
+        let render = |(head, complete): &(Vec<u8>, bool)| match binary {
+            true => preview::hex(head, *complete, self.width),
+            false => preview::render_width(head, *complete, self.width),
+        };
+        let first = self.first.as_ref().map(render);
+        let last = self.last.as_ref().map(render).or_else(|| first.clone());
+        Peek { first, last }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn peek(lines: &[(&[u8], bool)], width: usize, binary: bool) -> Peek {
+        let mut tracker = Tracker::new(width);
+        for (head, complete) in lines {
+            tracker.end_line(head, *complete);
+        }
+        tracker.finish(binary)
+    }
+
+    #[test]
+    fn test_first_and_last() {
+        assert_eq!(peek(&[], 8, false), Peek::default());
+        let shebang = Some("#!/bin/s…".to_string());
+        assert_eq!(
+            peek(&[(b"#!/bin/sh", true)], 8, false),
+            Peek {
+                first: shebang.clone(),
+                last: shebang.clone(),
+            }
+        );
+        // Only the start of the last line was kept.
+        let lines: [(&[u8], bool); 3] = [(b"#!/bin/sh", true), (b"echo", true), (b"exit 0", false)];
+        assert_eq!(
+            peek(&lines, 8, false),
+            Peek {
+                first: shebang,
+                last: Some("exit 0…".to_string()),
+            }
+        );
+        let binary = peek(&[(b"\x7fELF\x02\x01", true)], 4, true);
+        assert_eq!(binary.first.unwrap(), "\\x7fELF…");
+    }
+}
@@ -0,0 +1,85 @@
+/// Characters of a line shown in a preview.
+pub const MAX_CHARS: usize = 80;
+
+/// Bytes of a line kept for its preview: enough for `MAX_CHARS` characters
+/// of any width.
+pub const MAX_BYTES: usize = MAX_CHARS * 4;
+
+/// Renders the start of a line for display, cut to `MAX_CHARS` characters
+/// on a character boundary. Control characters are written as escapes so
+/// the preview can never mangle a terminal, and invalid UTF-8 becomes
+/// U+FFFD. An ellipsis marks a cut, including when `head` is only the start
+/// of the line (`complete` is false).
+pub fn render(head: &[u8], complete: bool) -> String {
+    render_width(head, complete, MAX_CHARS)
+}
+
+/// `render`, cut to `width` characters instead.
+pub fn render_width(head: &[u8], complete: bool, width: usize) -> String {
+    let text = String::from_utf8_lossy(head);
+    let mut chars = text.chars();
+    let mut preview = String::new();
+    for c in chars.by_ref().take(width) {
+        match c {
+            '\0' => preview.push_str("\\0"),
+            '\t' => preview.push_str("\\t"),
+            '\r' => preview.push_str("\\r"),
+            '\n' => preview.push_str("\\n"),
+            c if (c as u32) < 0x80 && c.is_control() => {
+                preview.push_str(&format!("\\x{:02x}", c as u32))
+            }
+            c if c.is_control() => preview.push_str(&format!("\\u{{{:x}}}", c as u32)),
+            c => preview.push(c),
+        }
+    }
+    if chars.next().is_some() || !complete {
+        preview.push('…');
+    }
+    preview
+}
+
+/// The first `width` bytes of a line of a binary file, each that is not
+/// printable ASCII written as `\xNN`, with an ellipsis for a cut as in
+/// `render`.
+pub fn hex(head: &[u8], complete: bool, width: usize) -> String {
+    let mut preview = String::new();
+    for &byte in head.iter().take(width) {
+        match byte {
+            b' '..=b'~' => preview.push(char::from(byte)),
+            byte => preview.push_str(&format!("\\x{:02x}", byte)),
+        }
+    }
+    if head.len() > width || !complete {
+        preview.push('…');
+    }
+    preview
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_short_line_is_unchanged() {
+        assert_eq!(render(b"fn main() {", true), "fn main() {");
+        assert_eq!(render(b"", true), "");
+    }
+
+    #[test]
+    fn test_long_line_is_cut_on_a_char_boundary() {
+        let line = "é".repeat(100);
+        let preview = render(line.as_bytes(), true);
+        assert_eq!(preview, format!("{}…", "é".repeat(MAX_CHARS)));
+        assert_eq!(render(b"abc", false), "abc…");
+    }
+
+    #[test]
+    fn test_control_characters_are_escaped() {
+        let preview = render(b"a\0b\tc\x1b[31m\x7f\xc2\x85", true);
+        assert_eq!(preview, "a\\0b\\tc\\x1b[31m\\x7f\\u{85}");
+        assert!(!render(b"\0\0\0", true).contains('\0'));
+        assert_eq!(render(b"bad\xff", true), "bad\u{fffd}");
+        assert_eq!(hex(b"\0\x01MZ\xff", true, 8), "\\x00\\x01MZ\\xff");
+        assert_eq!(render_width(b"abcdef", true, 3), "abc…");
+    }
+}
@@ -0,0 +1,116 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Progress is only shown for runs with more files than this.
+pub const MIN_FILES: usize = 5;
+
+/// Longest path shown on the progress line; longer ones keep their tail.
+const MAX_PATH_CHARS: usize = 60;
+
+/// Minimum time between redraws, so tiny files don't flood the terminal.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A `[done/total] path` counter redrawn in place on stderr. It can be
+/// updated from several worker threads at once; a disabled counter does
+/// nothing.
+pub struct Progress {
+    total: usize,
+    enabled: bool,
+    completed: AtomicUsize,
+    last_draw: Mutex<Option<Instant>>,
+}
+
+impl Progress {
+    pub fn new(total: usize, enabled: bool) -> Self {
+        Progress {
+            total,
+            enabled,
+            completed: AtomicUsize::new(0),
+            last_draw: Mutex::new(None),
+        }
+    }
+
+    /// Records that `path` is finished and redraws the line if enough time
+    /// has passed since the last redraw (or this was the last file).
+    pub fn file_done(&self, path: &str) {
+        if !self.enabled {
+            return;
+        }
+        let completed = self.completed.fetch_add(1, Ordering::Relaxed) + 1;
+        let mut last_draw = self.last_draw.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let due = last_draw.is_none_or(|last| now.duration_since(last) >= REDRAW_INTERVAL);
+        if due || completed == self.total {
+            *last_draw = Some(now);
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[K{}", line(completed, self.total, path));
+            let _ = stderr.flush();
+        }
+    }
+
+    /// Prints `message` on a line of its own above the progress line, which
+    /// the next file redraws.
+    pub fn note(&self, message: &str) {
+        let mut stderr = io::stderr().lock();
+        if self.enabled {
+            let _ = write!(stderr, "\r\x1b[K");
+        }
+        let _ = writeln!(stderr, "{}", message);
+    }
+
+    /// Erases the progress line so the report starts on a clean line.
+    pub fn finish(&self) {
+        if self.enabled {
+            let mut stderr = io::stderr().lock();
+            let _ = write!(stderr, "\r\x1b[K");
+            let _ = stderr.flush();
+        }
+    }
+}
+
+fn line(completed: usize, total: usize, path: &str) -> String {
+    format!("[{}/{}] {}", completed, total, shorten(path))
+}
+
+/// Keeps the last `MAX_PATH_CHARS` characters of `path`, since the file name
+/// is the most useful part.
+fn shorten(path: &str) -> String {
+    let count = path.chars().count();
+    if count <= MAX_PATH_CHARS {
+        return path.to_string();
+    }
+    let tail: String = path.chars().skip(count - (MAX_PATH_CHARS - 3)).collect();
+    format!("...{}", tail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_format() {
+        assert_eq!(line(12, 345, "src/main.rs"), "[12/345] src/main.rs");
+    }
+
+    #[test]
+    fn test_shorten_keeps_the_tail() {
+        assert_eq!(shorten("short.txt"), "short.txt");
+        let long = format!("{}/file.txt", "dir/".repeat(30));
+        let shortened = shorten(&long);
+        assert_eq!(shortened.chars().count(), MAX_PATH_CHARS);
+        assert!(shortened.starts_with("..."));
+        assert!(shortened.ends_with("/file.txt"));
+        let wide = "é".repeat(100);
+        assert_eq!(shorten(&wide).chars().count(), MAX_PATH_CHARS);
+    }
+
+    #[test]
+    fn test_disabled_progress_does_nothing() {
+        let progress = Progress::new(2, false);
+        progress.file_done("a");
+        progress.finish();
+        assert_eq!(progress.completed.load(Ordering::Relaxed), 0);
+    }
+}
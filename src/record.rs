@@ -0,0 +1,1116 @@
+use std::fmt;
+
+use serde::de::{Error as _, MapAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::blank::BlankRuns;
+use crate::bom::Bom;
+use crate::charset::Guess;
+use crate::checks::Violation;
+use crate::duplicates::DuplicateStats;
+use crate::figures;
+use crate::generated::Generated;
+use crate::histogram::Histogram;
+use crate::locate::MinorityLines;
+use crate::markdown::MarkdownStats;
+use crate::metadata::{FileMeta, Permissions};
+use crate::pages::Pages;
+use crate::pattern::PatternCount;
+use crate::peek::Peek;
+use crate::suppress::Suppression;
+use crate::top::TopLine;
+use crate::transitions::Segment;
+use crate::utf8::{InvalidSequence, Utf8Report};
+use crate::{shebang, FileStats, LineEndingKind, LongLines, MixedIndentLines, UnicodeBreaks};
+
+/// One bucket of `histogram`: its upper bound, `null` for the last, and
+/// how many lines fall into it.
+#[derive(Serialize, Deserialize)]
+struct Bucket {
+    upper_bound: Option<usize>,
+    count: usize,
+}
+
+/// `pages`, with the count of pages and the mean of their lines that the
+/// report adds.
+#[derive(Serialize, Deserialize)]
+struct PageCounts {
+    form_feeds: usize,
+    pages: usize,
+    min_lines: usize,
+    max_lines: usize,
+    avg_lines: f64,
+}
+
+/// `metadata`, with the time and permissions as the report writes them:
+/// "2024-03-09T14:05:00Z", and "0644", "read-only" or "writable".
+#[derive(Serialize, Deserialize)]
+struct Metadata {
+    modified: Option<String>,
+    size: Option<u64>,
+    permissions: Option<String>,
+}
+
+/// What one `--count-pattern` found, under the pattern in `patterns`.
+#[derive(Serialize, Deserialize)]
+struct Counts {
+    lines: usize,
+    matches: usize,
+    lines_at: Vec<usize>,
+}
+
+/// `patterns` as an object keyed on the patterns, in the order given.
+/// A key that is there as `Some`, even when it is `null`, for the keys a
+/// flag writes whether or not they have a value.
+fn present<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+fn serialize_patterns<S: Serializer>(
+    patterns: &[(String, Counts)],
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(patterns.iter().map(|(pattern, counts)| (pattern, counts)))
+}
+
+/// `patterns` read back, keeping the order of the object.
+fn deserialize_patterns<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<(String, Counts)>, D::Error> {
+    struct Ordered;
+
+    impl<'de> Visitor<'de> for Ordered {
+        type Value = Vec<(String, Counts)>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("an object of pattern counts")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let mut patterns = Vec::new();
+            while let Some(entry) = map.next_entry()? {
+                patterns.push(entry);
+            }
+            Ok(patterns)
+        }
+    }
+
+    deserializer.deserialize_map(Ordered)
+}
+
+/// A file as `--format json` writes it, key for key and in the same order,
+/// so that what serde writes reads back as a report and the other way
+/// round. The keys only some options write are left out the same way, and
+/// the ones worked out from others, such as the ratios, are not read back.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    file_name: String,
+    file_extension: Option<String>,
+    bom: Option<Bom>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    detected_encoding: Option<Guess>,
+    shebang: Option<String>,
+    #[serde(skip_deserializing)]
+    detected_language: Option<String>,
+    #[serde(default)]
+    detected_type: Option<String>,
+    is_binary: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    generated: Option<Generated>,
+    total_bytes: u64,
+    #[serde(skip_deserializing)]
+    size_bytes: u64,
+    #[serde(default)]
+    grew_during_scan: bool,
+    #[serde(default)]
+    truncated_during_scan: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    compressed_bytes: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    window: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    head: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    truncated: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sha256: Option<String>,
+    total_chars: u64,
+    total_words: u64,
+    total_lines: usize,
+    empty_lines: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    empty_lines_at: Option<Vec<usize>>,
+    blank_lines: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    comment_lines: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    code_lines: Option<usize>,
+    dos_endings: usize,
+    unix_endings: usize,
+    mac_endings: usize,
+    #[serde(skip_deserializing)]
+    dos_ratio: f64,
+    #[serde(skip_deserializing)]
+    unix_ratio: f64,
+    #[serde(skip_deserializing)]
+    mac_ratio: f64,
+    first_lf_offset: Option<u64>,
+    first_crlf_offset: Option<u64>,
+    first_cr_offset: Option<u64>,
+    nul_bytes: usize,
+    first_nul_line: Option<usize>,
+    control_chars: usize,
+    first_control_line: Option<usize>,
+    non_ascii_chars: usize,
+    first_non_ascii_line: Option<usize>,
+    first_non_ascii_column: Option<usize>,
+    #[serde(default)]
+    embedded_crs: usize,
+    #[serde(default)]
+    embedded_cr_at: Vec<(usize, usize)>,
+    #[serde(default)]
+    phantom_blank_lines: usize,
+    #[serde(default)]
+    phantom_blank_at: Vec<usize>,
+    trailing_whitespace_lines: usize,
+    trailing_whitespace_at: Vec<usize>,
+    min_line_length: usize,
+    /// Read back into the total line length, as `mdlt diff` does.
+    avg_line_length: f64,
+    max_line_length: usize,
+    max_line_bytes: usize,
+    longest_line_number: Option<usize>,
+    #[serde(skip_deserializing)]
+    longest_line_length: usize,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    longest_line_preview: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    top_lines: Option<Vec<TopLine>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    first_line_preview: Option<Option<String>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    last_line_preview: Option<Option<String>>,
+    indent_tab_lines: usize,
+    indent_space_lines: usize,
+    indent_mixed_lines: usize,
+    unindented_lines: usize,
+    indent_width: Option<usize>,
+    trailing_blank_lines: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blank_runs: Option<BlankRuns>,
+    final_newline: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    nel_endings: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ls_endings: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ps_endings: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    vt_endings: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    ff_endings: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pages: Option<PageCounts>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    utf8_valid: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    utf8_invalid_sequences: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    utf8_invalid_at: Option<Vec<InvalidSequence>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    histogram: Option<Vec<Bucket>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    markdown: Option<MarkdownStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    duplicates: Option<DuplicateStats>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    transitions: Option<Vec<Segment>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    minority_ending_lines: Option<Vec<MinorityLines>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    metadata: Option<Metadata>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mixed_indent_lines: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    mixed_indent_at: Option<Vec<usize>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    max_line_length_limit: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    long_lines: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    long_lines_at: Option<Vec<usize>>,
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        serialize_with = "serialize_patterns",
+        deserialize_with = "deserialize_patterns"
+    )]
+    patterns: Vec<(String, Counts)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    violations: Vec<Violation>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    suppressions: Vec<Suppression>,
+    #[serde(skip_deserializing)]
+    is_mixed: bool,
+    #[serde(skip_deserializing)]
+    line_ending_type: String,
+    #[serde(skip_deserializing)]
+    line_ending: Option<LineEndingKind>,
+}
+
+impl From<&FileStats> for Record {
+    fn from(file: &FileStats) -> Self {
+        let [dos_ratio, unix_ratio, mac_ratio] = file.ending_ratios();
+        let breaks = file.unicode_breaks.as_ref();
+        let utf8 = file.utf8.as_ref();
+        let histogram = file.histogram.as_ref().map(|histogram| {
+            histogram
+                .buckets()
+                .map(|(upper_bound, count)| Bucket { upper_bound, count })
+                .collect()
+        });
+        let metadata = file.metadata.as_ref().map(|meta| Metadata {
+            modified: meta.modified.map(figures::rfc3339),
+            size: meta.size,
+            permissions: meta.permissions.map(|permissions| match permissions {
+                Permissions::Mode(mode) => format!("{:04o}", mode),
+                Permissions::ReadOnly(true) => "read-only".to_string(),
+                Permissions::ReadOnly(false) => "writable".to_string(),
+            }),
+        });
+        // The report writes both line counts or neither.
+        let (comment_lines, code_lines) = match (file.comment_lines, file.code_lines) {
+            (Some(comment), Some(code)) => (Some(comment), Some(code)),
+            _ => (None, None),
+        };
+        Record {
+            file_name: file.display_name().into_owned(),
+            file_extension: file.file_extension.clone(),
+            bom: file.bom,
+            detected_encoding: file.detected_encoding,
+            shebang: file.shebang.clone(),
+            detected_language: file.detected_language.map(str::to_string),
+            detected_type: file.detected_type.clone(),
+            is_binary: file.is_binary,
+            generated: file.generated.clone(),
+            total_bytes: file.total_bytes,
+            size_bytes: file.total_bytes,
+            grew_during_scan: file.grew_during_scan,
+            truncated_during_scan: file.truncated_during_scan,
+            compressed_bytes: file.compressed_bytes,
+            window: file.window.map(|window| window.to_string()),
+            head: file.head.map(|head| head.to_string()),
+            truncated: file.head.map(|_| file.truncated),
+            sha256: file.sha256.clone(),
+            total_chars: file.total_chars,
+            total_words: file.total_words,
+            total_lines: file.total_lines,
+            empty_lines: file.empty_lines,
+            empty_lines_at: file.empty_lines_at.clone(),
+            blank_lines: file.blank_lines,
+            comment_lines,
+            code_lines,
+            dos_endings: file.dos_endings,
+            unix_endings: file.unix_endings,
+            mac_endings: file.mac_endings,
+            dos_ratio,
+            unix_ratio,
+            mac_ratio,
+            first_lf_offset: file.first_lf_offset,
+            first_crlf_offset: file.first_crlf_offset,
+            first_cr_offset: file.first_cr_offset,
+            nul_bytes: file.nul_bytes,
+            first_nul_line: file.first_nul_line,
+            control_chars: file.control_chars,
+            first_control_line: file.first_control_line,
+            non_ascii_chars: file.non_ascii_chars,
+            first_non_ascii_line: file.first_non_ascii_line,
+            first_non_ascii_column: file.first_non_ascii_column,
+            embedded_crs: file.embedded_crs,
+            embedded_cr_at: file.embedded_cr_at.clone(),
+            phantom_blank_lines: file.phantom_blank_lines,
+            phantom_blank_at: file.phantom_blank_at.clone(),
+            trailing_whitespace_lines: file.trailing_whitespace_lines,
+            trailing_whitespace_at: file.trailing_whitespace_at.clone(),
+            min_line_length: file.min_line_length,
+            avg_line_length: file.mean_line_length(),
+            max_line_length: file.max_line_length,
+            max_line_bytes: file.max_line_bytes,
+            longest_line_number: file.longest_line,
+            longest_line_length: file.max_line_length,
+            longest_line_preview: file.longest_line_preview.clone(),
+            top_lines: file.top_lines.clone(),
+            first_line_preview: file.peek.as_ref().map(|peek| peek.first.clone()),
+            last_line_preview: file.peek.as_ref().map(|peek| peek.last.clone()),
+            indent_tab_lines: file.indentation.tabs,
+            indent_space_lines: file.indentation.spaces,
+            indent_mixed_lines: file.indentation.mixed,
+            unindented_lines: file.indentation.none,
+            indent_width: file.indentation.width,
+            trailing_blank_lines: file.trailing_blank_lines,
+            blank_runs: file.blank_runs,
+            final_newline: file.final_newline,
+            nel_endings: breaks.map(|breaks| breaks.nel),
+            ls_endings: breaks.map(|breaks| breaks.ls),
+            ps_endings: breaks.map(|breaks| breaks.ps),
+            vt_endings: breaks.map(|breaks| breaks.vt),
+            ff_endings: breaks.map(|breaks| breaks.ff),
+            pages: file.pages.map(|pages| PageCounts {
+                form_feeds: pages.form_feeds,
+                pages: pages.count(),
+                min_lines: pages.min_lines,
+                max_lines: pages.max_lines,
+                avg_lines: pages.mean_lines(),
+            }),
+            utf8_valid: utf8.map(|report| report.invalid == 0),
+            utf8_invalid_sequences: utf8.map(|report| report.invalid),
+            utf8_invalid_at: utf8.map(|report| report.first.clone()),
+            histogram,
+            markdown: file.markdown.clone(),
+            duplicates: file.duplicates.clone(),
+            transitions: file.transitions.clone(),
+            minority_ending_lines: file.minority_endings.clone(),
+            metadata,
+            mixed_indent_lines: file.mixed_indent.as_ref().map(|mixed| mixed.count),
+            mixed_indent_at: file.mixed_indent.as_ref().map(|mixed| mixed.first.clone()),
+            max_line_length_limit: file.long_lines.as_ref().map(|long| long.limit),
+            long_lines: file.long_lines.as_ref().map(|long| long.count),
+            long_lines_at: file.long_lines.as_ref().map(|long| long.first.clone()),
+            patterns: file
+                .patterns
+                .iter()
+                .map(|count| {
+                    let counts = Counts {
+                        lines: count.lines,
+                        matches: count.matches,
+                        lines_at: count.first.clone(),
+                    };
+                    (count.pattern.clone(), counts)
+                })
+                .collect(),
+            warnings: file.warnings.clone(),
+            violations: file.violations.clone(),
+            suppressions: file.suppressions.clone(),
+            is_mixed: file.is_mixed(),
+            line_ending_type: file.line_ending_type(),
+            line_ending: Some(match file.is_mixed() {
+                true => LineEndingKind::Mixed,
+                false => file.line_ending(),
+            }),
+        }
+    }
+}
+
+impl TryFrom<Record> for FileStats {
+    type Error = String;
+
+    fn try_from(record: Record) -> Result<Self, Self::Error> {
+        let mut file = FileStats::new(record.file_name);
+        file.file_extension = record.file_extension;
+        file.bom = record.bom;
+        file.detected_encoding = record.detected_encoding;
+        file.detected_language = record.shebang.as_deref().and_then(shebang::language);
+        file.detected_type = record.detected_type;
+        file.shebang = record.shebang;
+        file.is_binary = record.is_binary;
+        file.generated = record.generated;
+        file.total_bytes = record.total_bytes;
+        file.grew_during_scan = record.grew_during_scan;
+        file.truncated_during_scan = record.truncated_during_scan;
+        file.compressed_bytes = record.compressed_bytes;
+        file.window = record.window.map(|window| window.parse()).transpose()?;
+        file.head = record.head.map(|head| head.parse()).transpose()?;
+        file.truncated = record.truncated.unwrap_or(false);
+        file.sha256 = record.sha256;
+        file.total_chars = record.total_chars;
+        file.total_words = record.total_words;
+        file.total_lines = record.total_lines;
+        file.empty_lines = record.empty_lines;
+        file.empty_lines_at = record.empty_lines_at;
+        file.blank_lines = record.blank_lines;
+        file.comment_lines = record.comment_lines;
+        file.code_lines = record.code_lines;
+        file.dos_endings = record.dos_endings;
+        file.unix_endings = record.unix_endings;
+        file.mac_endings = record.mac_endings;
+        file.first_lf_offset = record.first_lf_offset;
+        file.first_crlf_offset = record.first_crlf_offset;
+        file.first_cr_offset = record.first_cr_offset;
+        file.nul_bytes = record.nul_bytes;
+        file.first_nul_line = record.first_nul_line;
+        file.control_chars = record.control_chars;
+        file.first_control_line = record.first_control_line;
+        file.non_ascii_chars = record.non_ascii_chars;
+        file.first_non_ascii_line = record.first_non_ascii_line;
+        file.first_non_ascii_column = record.first_non_ascii_column;
+        file.embedded_crs = record.embedded_crs;
+        file.embedded_cr_at = record.embedded_cr_at;
+        file.phantom_blank_lines = record.phantom_blank_lines;
+        file.phantom_blank_at = record.phantom_blank_at;
+        file.trailing_whitespace_lines = record.trailing_whitespace_lines;
+        file.trailing_whitespace_at = record.trailing_whitespace_at;
+        file.min_line_length = record.min_line_length;
+        file.total_line_length = (record.avg_line_length * file.total_lines as f64).round() as u64;
+        file.max_line_length = record.max_line_length;
+        file.max_line_bytes = record.max_line_bytes;
+        file.longest_line = record.longest_line_number;
+        file.longest_line_preview = record.longest_line_preview;
+        file.top_lines = record.top_lines;
+        file.peek = match (record.first_line_preview, record.last_line_preview) {
+            (None, None) => None,
+            (first, last) => Some(Peek {
+                first: first.flatten(),
+                last: last.flatten(),
+            }),
+        };
+        file.indentation.tabs = record.indent_tab_lines;
+        file.indentation.spaces = record.indent_space_lines;
+        file.indentation.mixed = record.indent_mixed_lines;
+        file.indentation.none = record.unindented_lines;
+        file.indentation.width = record.indent_width;
+        file.trailing_blank_lines = record.trailing_blank_lines;
+        file.blank_runs = record.blank_runs;
+        file.final_newline = record.final_newline;
+        if let (Some(nel), Some(ls), Some(ps), Some(vt), Some(ff)) = (
+            record.nel_endings,
+            record.ls_endings,
+            record.ps_endings,
+            record.vt_endings,
+            record.ff_endings,
+        ) {
+            file.unicode_breaks = Some(UnicodeBreaks {
+                nel,
+                ls,
+                ps,
+                vt,
+                ff,
+            });
+        }
+        file.pages = record.pages.map(|pages| Pages {
+            form_feeds: pages.form_feeds,
+            min_lines: pages.min_lines,
+            max_lines: pages.max_lines,
+            lines: (pages.avg_lines * pages.pages as f64).round() as usize,
+        });
+        file.utf8 = record.utf8_invalid_sequences.map(|invalid| Utf8Report {
+            invalid,
+            first: record.utf8_invalid_at.unwrap_or_default(),
+        });
+        if let Some(buckets) = record.histogram {
+            let rows: Vec<(Option<usize>, usize)> = buckets
+                .iter()
+                .map(|bucket| (bucket.upper_bound, bucket.count))
+                .collect();
+            file.histogram = Some(
+                Histogram::from_buckets(&rows)
+                    .ok_or("\"histogram\" needs increasing upper bounds and a last one of null")?,
+            );
+        }
+        file.markdown = record.markdown;
+        file.duplicates = record.duplicates;
+        file.transitions = record.transitions;
+        file.minority_endings = record.minority_ending_lines;
+        if let Some(meta) = record.metadata {
+            let modified = match meta.modified {
+                Some(time) => Some(
+                    figures::parse_rfc3339(&time)
+                        .ok_or(format!("\"modified\" is not a time: {}", time))?,
+                ),
+                None => None,
+            };
+            let permissions = match meta.permissions.as_deref() {
+                None => None,
+                Some("read-only") => Some(Permissions::ReadOnly(true)),
+                Some("writable") => Some(Permissions::ReadOnly(false)),
+                Some(mode) => Some(Permissions::Mode(
+                    u32::from_str_radix(mode, 8)
+                        .map_err(|_| format!("\"permissions\" is not a mode: {}", mode))?,
+                )),
+            };
+            file.metadata = Some(FileMeta {
+                modified,
+                size: meta.size,
+                permissions,
+            });
+        }
+        file.mixed_indent = record.mixed_indent_lines.map(|count| MixedIndentLines {
+            count,
+            first: record.mixed_indent_at.unwrap_or_default(),
+        });
+        if let (Some(limit), Some(count)) = (record.max_line_length_limit, record.long_lines) {
+            file.long_lines = Some(LongLines {
+                limit,
+                count,
+                first: record.long_lines_at.unwrap_or_default(),
+            });
+        }
+        file.patterns = record
+            .patterns
+            .into_iter()
+            .map(|(pattern, counts)| PatternCount {
+                pattern,
+                lines: counts.lines,
+                matches: counts.matches,
+                first: counts.lines_at,
+            })
+            .collect();
+        file.warnings = record.warnings;
+        file.violations = record.violations;
+        file.suppressions = record.suppressions;
+        Ok(file)
+    }
+}
+
+impl Serialize for FileStats {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Record::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for FileStats {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let record = Record::deserialize(deserializer)?;
+        FileStats::try_from(record).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use std::fmt::Write as _;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde::de::value::{Error, MapDeserializer, SeqDeserializer, StringDeserializer};
+    use serde::de::{self, DeserializeOwned, IntoDeserializer, Visitor};
+    use serde::forward_to_deserialize_any;
+    use serde::ser::{self, Impossible};
+
+    use super::*;
+    use crate::charset::DetectEncoding;
+    use crate::checks::Severity;
+    use crate::format::json::{self, Value};
+    use crate::format::ReportOptions;
+    use crate::histogram::Buckets;
+    use crate::pattern::Patterns;
+    use crate::regex::{Regex, Units};
+    use crate::scanner::{LineLimit, MixedIndent, ScanOptions};
+    use crate::suppress::Scope;
+    use crate::top::TopLines;
+
+    /// Just enough of a JSON serializer for what a report holds, written
+    /// the way `--format json` writes it.
+    struct Json(String);
+
+    fn to_json(value: &impl Serialize) -> String {
+        let mut json = Json(String::new());
+        value.serialize(&mut json).unwrap();
+        json.0
+    }
+
+    /// The elements of an array or the fields of an object being written.
+    struct Compound<'a> {
+        json: &'a mut Json,
+        first: bool,
+        close: char,
+    }
+
+    impl Compound<'_> {
+        fn separate(&mut self) {
+            if !self.first {
+                self.json.0.push_str(", ");
+            }
+            self.first = false;
+        }
+    }
+
+    impl Json {
+        fn write(&mut self, value: impl std::fmt::Display) -> Result<(), Error> {
+            write!(self.0, "{}", value).map_err(ser::Error::custom)
+        }
+
+        fn open(&mut self, open: char, close: char) -> Compound<'_> {
+            self.0.push(open);
+            Compound {
+                json: self,
+                first: true,
+                close,
+            }
+        }
+    }
+
+    fn unsupported<T>() -> Result<T, Error> {
+        Err(ser::Error::custom("not used by reports"))
+    }
+
+    impl<'a> ser::Serializer for &'a mut Json {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Compound<'a>;
+        type SerializeTuple = Compound<'a>;
+        type SerializeTupleStruct = Impossible<(), Error>;
+        type SerializeTupleVariant = Impossible<(), Error>;
+        type SerializeMap = Compound<'a>;
+        type SerializeStruct = Compound<'a>;
+        type SerializeStructVariant = Impossible<(), Error>;
+
+        fn serialize_bool(self, v: bool) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_i8(self, v: i8) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_i16(self, v: i16) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_i32(self, v: i32) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_i64(self, v: i64) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_u8(self, v: u8) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_u16(self, v: u16) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_u32(self, v: u32) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_u64(self, v: u64) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_f32(self, v: f32) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_f64(self, v: f64) -> Result<(), Error> {
+            self.write(v)
+        }
+        fn serialize_char(self, v: char) -> Result<(), Error> {
+            self.serialize_str(&v.to_string())
+        }
+        fn serialize_str(self, v: &str) -> Result<(), Error> {
+            self.write(json::string(v))
+        }
+        fn serialize_bytes(self, _: &[u8]) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_none(self) -> Result<(), Error> {
+            self.write("null")
+        }
+        fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<(), Error> {
+            value.serialize(self)
+        }
+        fn serialize_unit(self) -> Result<(), Error> {
+            self.write("null")
+        }
+        fn serialize_unit_struct(self, _: &'static str) -> Result<(), Error> {
+            self.write("null")
+        }
+        fn serialize_unit_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            variant: &'static str,
+        ) -> Result<(), Error> {
+            self.serialize_str(variant)
+        }
+        fn serialize_newtype_struct<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            value: &T,
+        ) -> Result<(), Error> {
+            value.serialize(self)
+        }
+        fn serialize_newtype_variant<T: ?Sized + Serialize>(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: &T,
+        ) -> Result<(), Error> {
+            unsupported()
+        }
+        fn serialize_seq(self, _: Option<usize>) -> Result<Compound<'a>, Error> {
+            Ok(self.open('[', ']'))
+        }
+        fn serialize_tuple(self, _: usize) -> Result<Compound<'a>, Error> {
+            Ok(self.open('[', ']'))
+        }
+        fn serialize_tuple_struct(
+            self,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleStruct, Error> {
+            unsupported()
+        }
+        fn serialize_tuple_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeTupleVariant, Error> {
+            unsupported()
+        }
+        fn serialize_map(self, _: Option<usize>) -> Result<Compound<'a>, Error> {
+            Ok(self.open('{', '}'))
+        }
+        fn serialize_struct(self, _: &'static str, _: usize) -> Result<Compound<'a>, Error> {
+            Ok(self.open('{', '}'))
+        }
+        fn serialize_struct_variant(
+            self,
+            _: &'static str,
+            _: u32,
+            _: &'static str,
+            _: usize,
+        ) -> Result<Self::SerializeStructVariant, Error> {
+            unsupported()
+        }
+    }
+
+    impl ser::SerializeSeq for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            self.separate();
+            value.serialize(&mut *self.json)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            self.json.0.push(self.close);
+            Ok(())
+        }
+    }
+
+    impl ser::SerializeTuple for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            ser::SerializeSeq::serialize_element(self, value)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            ser::SerializeSeq::end(self)
+        }
+    }
+
+    impl ser::SerializeMap for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), Error> {
+            self.separate();
+            key.serialize(&mut *self.json)?;
+            self.json.0.push_str(": ");
+            Ok(())
+        }
+
+        fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), Error> {
+            value.serialize(&mut *self.json)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            ser::SerializeSeq::end(self)
+        }
+    }
+
+    impl ser::SerializeStruct for Compound<'_> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: ?Sized + Serialize>(
+            &mut self,
+            key: &'static str,
+            value: &T,
+        ) -> Result<(), Error> {
+            self.separate();
+            self.json.0.push_str(&json::string(key));
+            self.json.0.push_str(": ");
+            value.serialize(&mut *self.json)
+        }
+
+        fn end(self) -> Result<(), Error> {
+            ser::SerializeSeq::end(self)
+        }
+    }
+
+    /// Reads a value the report parser parsed back into any `Deserialize`
+    /// type.
+    struct Reader(Value);
+
+    fn from_json<T: DeserializeOwned>(text: &str) -> Result<T, Error> {
+        T::deserialize(Reader(json::parse(text).map_err(de::Error::custom)?))
+    }
+
+    impl IntoDeserializer<'_, Error> for Reader {
+        type Deserializer = Reader;
+
+        fn into_deserializer(self) -> Reader {
+            self
+        }
+    }
+
+    impl<'de> de::Deserializer<'de> for Reader {
+        type Error = Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0 {
+                Value::Null => visitor.visit_unit(),
+                Value::Bool(value) => visitor.visit_bool(value),
+                Value::Number(number) => match (number.parse::<u64>(), number.parse::<i64>()) {
+                    (Ok(number), _) => visitor.visit_u64(number),
+                    (_, Ok(number)) => visitor.visit_i64(number),
+                    _ => visitor.visit_f64(number.parse().map_err(de::Error::custom)?),
+                },
+                Value::String(text) => visitor.visit_string(text),
+                Value::Array(items) => {
+                    let mut items = SeqDeserializer::new(items.into_iter().map(Reader));
+                    let value = visitor.visit_seq(&mut items)?;
+                    items.end()?;
+                    Ok(value)
+                }
+                Value::Object(fields) => {
+                    let fields = fields.into_iter().map(|(key, value)| (key, Reader(value)));
+                    let mut fields = MapDeserializer::new(fields);
+                    let value = visitor.visit_map(&mut fields)?;
+                    fields.end()?;
+                    Ok(value)
+                }
+            }
+        }
+
+        fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+            match self.0 {
+                Value::Null => visitor.visit_none(),
+                _ => visitor.visit_some(self),
+            }
+        }
+
+        fn deserialize_enum<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            _: &'static [&'static str],
+            visitor: V,
+        ) -> Result<V::Value, Error> {
+            match self.0 {
+                Value::String(text) => visitor.visit_enum(StringDeserializer::new(text)),
+                _ => Err(de::Error::custom("expected a string")),
+            }
+        }
+
+        forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+            bytes byte_buf unit unit_struct newtype_struct seq tuple tuple_struct map
+            struct identifier ignored_any
+        }
+    }
+
+    /// A Markdown file with something in every optional part of a report.
+    fn sample() -> FileStats {
+        let scan = ScanOptions {
+            unicode_linebreaks: true,
+            check_encoding: true,
+            detect_encoding: Some(DetectEncoding::Report),
+            markdown: true,
+            hash: true,
+            duplicates: true,
+            transitions: true,
+            locate: Some(3),
+            show_empty: Some(5),
+            max_blank_run: Some(1),
+            histogram: Some(Buckets::default()),
+            line_limit: Some(LineLimit {
+                max_columns: 10,
+                listed: 3,
+            }),
+            top_lines: Some(TopLines {
+                count: 2,
+                columns: true,
+            }),
+            peek: Some(12),
+            mixed_indent: Some(MixedIndent::Any),
+            patterns: Some(Patterns {
+                regexes: Box::leak(Box::new([
+                    Regex::new("dup", Units::Chars).unwrap(),
+                    Regex::new("\\xFF", Units::Bytes).unwrap(),
+                ])),
+                listed: 1,
+            }),
+            ..ScanOptions::default()
+        };
+        let text = b"#!/usr/bin/env python3\r\n# Title \"quoted\"\n\t  mixed indent  \n\
+                     dup\ndup\r!\n[a](b) ![c](d)\n\n\n```\ncode \xff\x0c\n";
+        let mut stats = crate::analyze_bytes_with(text, "notes.md".to_string(), scan);
+        stats.violations = vec![Violation {
+            check: "trailing-whitespace".to_string(),
+            severity: Severity::Warning,
+            line: Some(2),
+            message: "trailing whitespace".to_string(),
+            suppressed: true,
+        }];
+        stats.suppressions = vec![Suppression {
+            scope: Scope::Line,
+            line: 2,
+            checks: vec!["trailing-whitespace".to_string()],
+        }];
+        stats.metadata = Some(FileMeta {
+            modified: Some(UNIX_EPOCH + Duration::from_secs(1_791_729_000)),
+            size: Some(stats.total_bytes),
+            permissions: Some(Permissions::Mode(0o644)),
+        });
+        stats
+    }
+
+    /// The `--format json` object of `file`, as with `--preview`.
+    fn report(file: &FileStats) -> String {
+        let options = ReportOptions {
+            preview: true,
+            ..ReportOptions::default()
+        };
+        let mut buffer = Vec::new();
+        json::write_object(file, options, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    /// Whether two values are the same, taking numbers to the precision
+    /// the report rounds ratios and averages to.
+    fn same(a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => {
+                let (a, b): (f64, f64) = (a.parse().unwrap(), b.parse().unwrap());
+                (a - b).abs() < 0.005
+            }
+            (Value::Array(a), Value::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same(a, b))
+            }
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|((a, x), (b, y))| a == b && same(x, y))
+            }
+            _ => a == b,
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let stats = sample();
+        let json = to_json(&stats);
+        assert!(json.contains(
+            ", \"bom\": null, \"detected_encoding\": {\"charset\": \"unknown-8bit\", \"confidence\": \"low\", \"high_bytes\": 1, \"decoded\": false}, \"shebang\": \"/usr/bin/env python3\""
+        ));
+        assert!(json.contains("{\"start_line\": 1, \"end_line\": 1, \"ending\": \"CRLF\"}"));
+        assert!(json.contains(
+            "\"metadata\": {\"modified\": \"2026-10-11T14:30:00Z\", \"size\": 98, \"permissions\": \"0644\"}"
+        ));
+        assert!(json.contains(
+            "\"blank_runs\": {\"runs\": 2, \"longest\": 2, \"longest_at\": 7, \"over_limit\": 1}"
+        ));
+        assert!(json.contains(
+            "\"patterns\": {\"dup\": {\"lines\": 2, \"matches\": 2, \"lines_at\": [4]}, \"\\\\xFF\": {\"lines\": 1, "
+        ));
+        assert!(json.ends_with(
+            ", \"is_mixed\": true, \"line_ending_type\": \"Mostly Unix/Linux (LF)\", \"line_ending\": \"mixed\"}"
+        ));
+
+        let back: FileStats = from_json(&json).unwrap();
+        assert_eq!(to_json(&back), json);
+        assert_eq!(back.detected_language, Some("Python"));
+        let mut before = Vec::new();
+        let mut after = Vec::new();
+        stats.write_report(&mut before).unwrap();
+        back.write_report(&mut after).unwrap();
+        assert_eq!(after, before);
+    }
+
+    #[test]
+    fn test_matches_cli_json() {
+        let stats = sample();
+        let cli = report(&stats);
+        let (Value::Object(expected), Value::Object(written)) = (
+            json::parse(&cli).unwrap(),
+            json::parse(&to_json(&stats)).unwrap(),
+        ) else {
+            panic!("not objects");
+        };
+        let keys = |fields: &[(String, Value)]| -> Vec<String> {
+            fields.iter().map(|(key, _)| key.clone()).collect()
+        };
+        assert_eq!(keys(&written), keys(&expected));
+        for ((key, written), (_, expected)) in written.iter().zip(&expected) {
+            assert!(same(written, expected), "{}: {:?}", key, written);
+        }
+
+        let read: FileStats = from_json(&cli).unwrap();
+        assert_eq!(report(&read), cli);
+    }
+
+    #[test]
+    fn test_reads_reports_from_before_keys_were_added() {
+        let Value::Object(mut fields) = json::parse(&report(&sample())).unwrap() else {
+            panic!("not an object");
+        };
+        let added = [
+            "grew_during_scan",
+            "truncated_during_scan",
+            "embedded_crs",
+            "embedded_cr_at",
+            "phantom_blank_lines",
+            "phantom_blank_at",
+        ];
+        fields.retain(|(key, _)| !added.contains(&key.as_str()));
+        let read = FileStats::deserialize(Reader(Value::Object(fields))).unwrap();
+        assert_eq!(read.file_name, sample().file_name);
+        assert!(!read.grew_during_scan && !read.truncated_during_scan);
+        assert_eq!(read.embedded_crs, 0);
+        assert!(read.embedded_cr_at.is_empty());
+        assert_eq!(read.phantom_blank_lines, 0);
+        assert!(read.phantom_blank_at.is_empty());
+    }
+
+    #[test]
+    fn test_enums_are_words() {
+        assert_eq!(to_json(&LineEndingKind::Crlf), "\"crlf\"");
+        assert_eq!(to_json(&LineEndingKind::UnicodeOnly), "\"unicode\"");
+        assert_eq!(
+            from_json::<LineEndingKind>("\"mixed\"").unwrap(),
+            LineEndingKind::Mixed
+        );
+        for kind in [
+            LineEndingKind::Crlf,
+            LineEndingKind::Lf,
+            LineEndingKind::Cr,
+            LineEndingKind::Mixed,
+            LineEndingKind::UnicodeOnly,
+            LineEndingKind::None,
+        ] {
+            assert_eq!(to_json(&kind), json::string(kind.name()));
+        }
+        assert_eq!(to_json(&Bom::Utf16Le), "\"UTF-16LE\"");
+    }
+
+    #[test]
+    fn test_deserialize_errors() {
+        let missing = from_json::<FileStats>("{\"file_name\": \"a.txt\"}").unwrap_err();
+        assert_eq!(missing.to_string(), "missing field `is_binary`");
+        let cli = report(&sample());
+        let bad = cli.replace("\"0644\"", "\"rw-r--r--\"");
+        assert_eq!(
+            from_json::<FileStats>(&bad).unwrap_err().to_string(),
+            "\"permissions\" is not a mode: rw-r--r--"
+        );
+    }
+}
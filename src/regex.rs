@@ -0,0 +1,966 @@
+use std::fmt;
+
+/// How many times a `{n,m}` repetition may repeat, and how many
+/// instructions a pattern may compile to once repetitions are spelled out,
+/// so that `(a{1000}){1000}` is an error rather than a gigabyte.
+const MAX_REPEAT: u32 = 1000;
+const MAX_PROGRAM: usize = 100_000;
+
+/// What a regex is matched against: the characters of a line, or with
+/// `--bytes-regex` its bytes, which need not be UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Units {
+    Chars,
+    Bytes,
+}
+
+/// `\d`, `\w` and `\s`: Unicode digits, word characters and whitespace,
+/// or only their ASCII ones when matching bytes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Perl {
+    Digit,
+    Word,
+    Space,
+}
+
+impl Perl {
+    fn contains(self, unit: u32, units: Units) -> bool {
+        if units == Units::Bytes || unit < 0x80 {
+            let byte = unit as u8;
+            return unit < 0x80
+                && match self {
+                    Perl::Digit => byte.is_ascii_digit(),
+                    Perl::Word => byte.is_ascii_alphanumeric() || byte == b'_',
+                    Perl::Space => byte.is_ascii_whitespace() || byte == 0x0b,
+                };
+        }
+        let Some(c) = char::from_u32(unit) else {
+            return false;
+        };
+        match self {
+            Perl::Digit => c.is_numeric(),
+            Perl::Word => c.is_alphanumeric(),
+            Perl::Space => c.is_whitespace(),
+        }
+    }
+}
+
+/// A `[...]` class, or the `\d`-style class it stands for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Class {
+    negated: bool,
+    ranges: Vec<(u32, u32)>,
+    perls: Vec<(Perl, bool)>,
+}
+
+impl Class {
+    fn perl(perl: Perl, negated: bool) -> Self {
+        Class {
+            negated: false,
+            ranges: Vec::new(),
+            perls: vec![(perl, negated)],
+        }
+    }
+
+    fn contains(&self, unit: u32, units: Units, fold: bool) -> bool {
+        let has = |unit: u32| {
+            self.ranges.iter().any(|&(lo, hi)| lo <= unit && unit <= hi)
+                || self
+                    .perls
+                    .iter()
+                    .any(|&(perl, negated)| perl.contains(unit, units) != negated)
+        };
+        let found = has(unit) || (fold && cases(unit, units).into_iter().flatten().any(has));
+        found != self.negated
+    }
+}
+
+/// The other cases of `unit`: its simple lowercase and uppercase forms,
+/// ASCII only when matching bytes.
+fn cases(unit: u32, units: Units) -> [Option<u32>; 2] {
+    if units == Units::Bytes || unit < 0x80 {
+        let byte = u8::try_from(unit).ok().filter(u8::is_ascii_alphabetic);
+        return [
+            byte.map(|byte| u32::from(byte.to_ascii_lowercase())),
+            byte.map(|byte| u32::from(byte.to_ascii_uppercase())),
+        ];
+    }
+    match char::from_u32(unit) {
+        Some(c) => [single(c.to_lowercase()), single(c.to_uppercase())],
+        None => [None, None],
+    }
+}
+
+/// The one character of a case mapping, when it maps to just one.
+fn single(mut forms: impl Iterator<Item = char>) -> Option<u32> {
+    let first = forms.next()?;
+    forms.next().is_none().then_some(u32::from(first))
+}
+
+/// Zero-width conditions: `^`, `$` and `\b`, `\B`. Lines are matched
+/// without their terminators, so `^` and `$` are the ends of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assertion {
+    Start,
+    End,
+    WordBoundary,
+    NotWordBoundary,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    Empty,
+    Unit {
+        unit: u32,
+        fold: bool,
+    },
+    Class {
+        class: Class,
+        fold: bool,
+    },
+    Any,
+    Assert(Assertion),
+    Concat(Vec<Node>),
+    Alternate(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+        greedy: bool,
+    },
+}
+
+/// Reads a pattern into `Node`s. Positions in errors count characters from
+/// 1.
+struct Parser {
+    chars: Vec<char>,
+    index: usize,
+    units: Units,
+    /// Inside `(?i)`.
+    fold: bool,
+}
+
+impl Parser {
+    fn error(&self, message: &str, at: usize) -> String {
+        format!("{} at character {}", message, at + 1)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.index).copied()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Node, String> {
+        let mut branches = vec![self.concat()?];
+        while self.eat('|') {
+            branches.push(self.concat()?);
+        }
+        Ok(match branches.len() {
+            1 => branches.pop().unwrap_or(Node::Empty),
+            _ => Node::Alternate(branches),
+        })
+    }
+
+    fn concat(&mut self) -> Result<Node, String> {
+        let mut nodes = Vec::new();
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let at = self.index;
+            if let Some(atom) = self.atom(c)? {
+                nodes.push(self.repeat(atom, at)?);
+            }
+        }
+        Ok(match nodes.len() {
+            0 => Node::Empty,
+            1 => nodes.pop().unwrap_or(Node::Empty),
+            _ => Node::Concat(nodes),
+        })
+    }
+
+    /// The quantifier after `atom`, if any.
+    fn repeat(&mut self, atom: Node, at: usize) -> Result<Node, String> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => self.counts()?,
+            _ => return Ok(atom),
+        };
+        self.index += 1;
+        if matches!(atom, Node::Empty | Node::Assert(_)) {
+            return Err(self.error("nothing to repeat", at));
+        }
+        let greedy = !self.eat('?');
+        if matches!(self.peek(), Some('*' | '+' | '?')) {
+            return Err(self.error("a quantifier cannot follow another", self.index));
+        }
+        Ok(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+            greedy,
+        })
+    }
+
+    /// `{n}`, `{n,}` or `{n,m}`, consumed up to but not including the
+    /// closing `}`, which [`repeat`](Self::repeat) steps over.
+    fn counts(&mut self) -> Result<(u32, Option<u32>), String> {
+        let open = self.index;
+        let close = self.chars[open..].iter().position(|&c| c == '}');
+        let Some(close) = close.map(|close| open + close) else {
+            return Err(self.error("unclosed repetition", open));
+        };
+        let inside: String = self.chars[open + 1..close].iter().collect();
+        let number = |text: &str| -> Result<u32, String> {
+            let count: u32 = text
+                .trim()
+                .parse()
+                .map_err(|_| self.error("invalid repetition", open))?;
+            match count > MAX_REPEAT {
+                true => Err(self.error(
+                    &format!("repetition of more than {} times", MAX_REPEAT),
+                    open,
+                )),
+                false => Ok(count),
+            }
+        };
+        let (min, max) = match inside.split_once(',') {
+            None => {
+                let count = number(&inside)?;
+                (count, Some(count))
+            }
+            Some((min, "")) => (number(min)?, None),
+            Some((min, max)) => (number(min)?, Some(number(max)?)),
+        };
+        if max.is_some_and(|max| max < min) {
+            return Err(self.error("repetition whose maximum is below its minimum", open));
+        }
+        self.index = close;
+        Ok((min, max))
+    }
+
+    /// The atom that starts with `c`, or `None` for a flag group such as
+    /// `(?i)` that only changes how the rest is read.
+    fn atom(&mut self, c: char) -> Result<Option<Node>, String> {
+        let at = self.index;
+        self.index += 1;
+        let node = match c {
+            '(' => return self.group(at),
+            '[' => {
+                let class = self.class(at)?;
+                Node::Class {
+                    class,
+                    fold: self.fold,
+                }
+            }
+            '.' => Node::Any,
+            '^' => Node::Assert(Assertion::Start),
+            '$' => Node::Assert(Assertion::End),
+            '\\' => self.escape(at)?,
+            '*' | '+' | '?' | '{' => return Err(self.error("nothing to repeat", at)),
+            c => self.literal(c),
+        };
+        Ok(Some(node))
+    }
+
+    fn group(&mut self, at: usize) -> Result<Option<Node>, String> {
+        let outer = self.fold;
+        if self.eat('?') {
+            let mut on = true;
+            loop {
+                match self.peek() {
+                    Some('i') => self.fold = on,
+                    Some('-') if on => on = false,
+                    Some(':') => {
+                        self.index += 1;
+                        break;
+                    }
+                    Some(')') => {
+                        // `(?i)` sets the flag for the rest of the group it
+                        // is in.
+                        self.index += 1;
+                        return Ok(None);
+                    }
+                    _ => return Err(self.error("unknown group flag", self.index)),
+                }
+                self.index += 1;
+            }
+        }
+        let inner = self.alternation()?;
+        self.fold = outer;
+        if !self.eat(')') {
+            return Err(self.error("unclosed group opened", at));
+        }
+        Ok(Some(inner))
+    }
+
+    /// A literal character: a single unit, or the UTF-8 bytes of a
+    /// character that is not ASCII when matching bytes.
+    fn literal(&self, c: char) -> Node {
+        match self.units {
+            Units::Bytes if !c.is_ascii() => Node::Concat(
+                c.encode_utf8(&mut [0; 4])
+                    .bytes()
+                    .map(|byte| Node::Unit {
+                        unit: u32::from(byte),
+                        fold: false,
+                    })
+                    .collect(),
+            ),
+            _ => Node::Unit {
+                unit: u32::from(c),
+                fold: self.fold,
+            },
+        }
+    }
+
+    fn escape(&mut self, at: usize) -> Result<Node, String> {
+        if let Some(class) = self.perl_class() {
+            return Ok(Node::Class { class, fold: false });
+        }
+        let Some(c) = self.peek() else {
+            return Err(self.error("trailing backslash", at));
+        };
+        self.index += 1;
+        let assertion = match c {
+            'b' => Some(Assertion::WordBoundary),
+            'B' => Some(Assertion::NotWordBoundary),
+            'A' => Some(Assertion::Start),
+            'z' => Some(Assertion::End),
+            _ => None,
+        };
+        if let Some(assertion) = assertion {
+            return Ok(Node::Assert(assertion));
+        }
+        self.index -= 1;
+        let unit = self.escaped_unit(at)?;
+        Ok(match char::from_u32(unit) {
+            Some(c) if self.units == Units::Chars || c.is_ascii() => Node::Unit {
+                unit,
+                fold: self.fold,
+            },
+            _ => Node::Unit { unit, fold: false },
+        })
+    }
+
+    /// `\d`, `\D`, `\w`, `\W`, `\s` or `\S` after a backslash.
+    fn perl_class(&mut self) -> Option<Class> {
+        let (perl, negated) = match self.peek()? {
+            'd' => (Perl::Digit, false),
+            'D' => (Perl::Digit, true),
+            'w' => (Perl::Word, false),
+            'W' => (Perl::Word, true),
+            's' => (Perl::Space, false),
+            'S' => (Perl::Space, true),
+            _ => return None,
+        };
+        self.index += 1;
+        Some(Class::perl(perl, negated))
+    }
+
+    /// The unit a backslash escape other than a class or assertion stands
+    /// for: `\n`, `\t`, `\xHH`, `\x{H...}` or punctuation taken literally.
+    fn escaped_unit(&mut self, at: usize) -> Result<u32, String> {
+        let Some(c) = self.peek() else {
+            return Err(self.error("trailing backslash", at));
+        };
+        self.index += 1;
+        let unit = match c {
+            'n' => 0x0a,
+            'r' => 0x0d,
+            't' => 0x09,
+            'f' => 0x0c,
+            'v' => 0x0b,
+            '0' => 0,
+            'x' => self.hex(at)?,
+            c if c.is_ascii_alphanumeric() => {
+                return Err(self.error(&format!("unknown escape \\{}", c), at));
+            }
+            c => match self.units {
+                Units::Bytes if !c.is_ascii() => {
+                    return Err(self.error("only ASCII characters can be escaped", at))
+                }
+                _ => u32::from(c),
+            },
+        };
+        Ok(unit)
+    }
+
+    fn hex(&mut self, at: usize) -> Result<u32, String> {
+        let digits: String = if self.eat('{') {
+            let close = self.chars[self.index..].iter().position(|&c| c == '}');
+            let Some(close) = close.map(|close| self.index + close) else {
+                return Err(self.error("unclosed \\x{...}", at));
+            };
+            let digits = self.chars[self.index..close].iter().collect();
+            self.index = close + 1;
+            digits
+        } else {
+            let end = (self.index + 2).min(self.chars.len());
+            let digits = self.chars[self.index..end].iter().collect();
+            self.index = end;
+            digits
+        };
+        let unit = u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| !digits.is_empty())
+            .ok_or_else(|| self.error("invalid hex escape", at))?;
+        match self.units {
+            Units::Bytes if unit > 0xff => Err(self.error("a byte escape above \\xFF", at)),
+            Units::Chars if char::from_u32(unit).is_none() => {
+                Err(self.error("an escape that is not a character", at))
+            }
+            _ => Ok(unit),
+        }
+    }
+
+    /// A `[...]` class, after its `[`.
+    fn class(&mut self, at: usize) -> Result<Class, String> {
+        let negated = self.eat('^');
+        let mut class = Class {
+            negated,
+            ranges: Vec::new(),
+            perls: Vec::new(),
+        };
+        let mut first = true;
+        loop {
+            let item = self.index;
+            match self.peek() {
+                None => return Err(self.error("unclosed class opened", at)),
+                Some(']') if !first => {
+                    self.index += 1;
+                    return Ok(class);
+                }
+                Some('\\') => {
+                    self.index += 1;
+                    if let Some(perl) = self.perl_class() {
+                        class.perls.extend(perl.perls);
+                        first = false;
+                        continue;
+                    }
+                    self.index -= 1;
+                }
+                Some(_) => {}
+            }
+            first = false;
+            let start = self.class_unit(at)?;
+            let ranged = self.peek() == Some('-')
+                && self
+                    .chars
+                    .get(self.index + 1)
+                    .is_some_and(|&next| next != ']');
+            let end = match ranged {
+                true => {
+                    self.index += 1;
+                    self.class_unit(at)?
+                }
+                false => start,
+            };
+            if end < start {
+                return Err(self.error("class range out of order", item));
+            }
+            class.ranges.push((start, end));
+        }
+    }
+
+    /// One end of a class range: a character or an escape. When matching
+    /// bytes only ASCII characters may be given as they are, since the
+    /// others are several bytes; an escape such as `\xE9` is one byte.
+    fn class_unit(&mut self, at: usize) -> Result<u32, String> {
+        let item = self.index;
+        match self.peek() {
+            None => Err(self.error("unclosed class opened", at)),
+            Some('\\') => {
+                self.index += 1;
+                self.escaped_unit(item)
+            }
+            Some(c) if self.units == Units::Bytes && !c.is_ascii() => Err(self.error(
+                "a class of non-ASCII characters cannot match bytes; use \\xHH",
+                item,
+            )),
+            Some(c) => {
+                self.index += 1;
+                Ok(u32::from(c))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inst {
+    Unit {
+        unit: u32,
+        fold: bool,
+    },
+    Class {
+        class: usize,
+        fold: bool,
+    },
+    Any,
+    Assert(Assertion),
+    /// Try the first, then the second.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+/// Turns `Node`s into the instructions of a Pike VM.
+struct Compiler {
+    program: Vec<Inst>,
+    classes: Vec<Class>,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> Result<usize, String> {
+        if self.program.len() >= MAX_PROGRAM {
+            return Err("pattern too large once its repetitions are spelled out".to_string());
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    fn node(&mut self, node: &Node) -> Result<(), String> {
+        match node {
+            Node::Empty => {}
+            Node::Unit { unit, fold } => {
+                self.push(Inst::Unit {
+                    unit: *unit,
+                    fold: *fold,
+                })?;
+            }
+            Node::Class { class, fold } => {
+                self.classes.push(class.clone());
+                self.push(Inst::Class {
+                    class: self.classes.len() - 1,
+                    fold: *fold,
+                })?;
+            }
+            Node::Any => {
+                self.push(Inst::Any)?;
+            }
+            Node::Assert(assertion) => {
+                self.push(Inst::Assert(*assertion))?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.node(node)?;
+                }
+            }
+            Node::Alternate(branches) => {
+                let mut jumps = Vec::new();
+                for (index, branch) in branches.iter().enumerate() {
+                    if index + 1 < branches.len() {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.node(branch)?;
+                        jumps.push(self.push(Inst::Jump(0))?);
+                        let next = self.program.len();
+                        self.program[split] = Inst::Split(split + 1, next);
+                    } else {
+                        self.node(branch)?;
+                    }
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat {
+                node,
+                min,
+                max,
+                greedy,
+            } => {
+                for _ in 0..*min {
+                    self.node(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.node(node)?;
+                        self.push(Inst::Jump(split))?;
+                        let end = self.program.len();
+                        self.program[split] = self.split(split + 1, end, *greedy);
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.node(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = self.split(split + 1, end, *greedy);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn split(&self, more: usize, done: usize, greedy: bool) -> Inst {
+        match greedy {
+            true => Inst::Split(more, done),
+            false => Inst::Split(done, more),
+        }
+    }
+}
+
+/// A set of instruction indexes that clears in constant time.
+#[derive(Debug, Clone)]
+struct SparseSet {
+    dense: Vec<usize>,
+    sparse: Vec<usize>,
+}
+
+impl SparseSet {
+    fn new(size: usize) -> Self {
+        SparseSet {
+            dense: Vec::with_capacity(size),
+            sparse: vec![0; size],
+        }
+    }
+
+    fn insert(&mut self, value: usize) -> bool {
+        let index = self.sparse[value];
+        if index < self.dense.len() && self.dense[index] == value {
+            return false;
+        }
+        self.sparse[value] = self.dense.len();
+        self.dense.push(value);
+        true
+    }
+
+    fn clear(&mut self) {
+        self.dense.clear();
+    }
+}
+
+/// The threads of one step of the VM, in priority order, each with where
+/// its match started.
+#[derive(Debug, Clone)]
+struct Threads {
+    set: SparseSet,
+    starts: Vec<usize>,
+}
+
+impl Threads {
+    fn new(size: usize) -> Self {
+        Threads {
+            set: SparseSet::new(size),
+            starts: vec![0; size],
+        }
+    }
+}
+
+/// A regular expression in the usual syntax: literals, `.`, `[...]`
+/// classes with ranges and `^` negation, `\d`, `\w`, `\s` and their
+/// negations, `^`, `$`, `\b`, `\B`, groups `(...)` and `(?:...)`, `|`, the
+/// quantifiers `*`, `+`, `?` and `{n,m}` with lazy `?` forms, and `(?i)`
+/// for case-insensitive matching. It runs as a Pike VM, so that matching
+/// takes time in proportion to the input whatever the pattern, and finds
+/// the leftmost match, preferring the earlier alternative as Perl does.
+#[derive(Clone)]
+pub struct Regex {
+    source: String,
+    units: Units,
+    program: Vec<Inst>,
+    classes: Vec<Class>,
+}
+
+/// Only the pattern, which is what tells two regexes apart.
+impl fmt::Debug for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Regex({:?}, {:?})", self.source, self.units)
+    }
+}
+
+impl Regex {
+    /// Compiles `source`, or says what is wrong with it and where.
+    pub fn new(source: &str, units: Units) -> Result<Regex, String> {
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            index: 0,
+            units,
+            fold: false,
+        };
+        let node = parser.alternation()?;
+        if parser.peek() == Some(')') {
+            return Err(parser.error("unopened group closed", parser.index));
+        }
+        let mut compiler = Compiler {
+            program: Vec::new(),
+            classes: Vec::new(),
+        };
+        compiler.node(&node)?;
+        compiler.push(Inst::Match)?;
+        Ok(Regex {
+            source: source.to_string(),
+            units,
+            program: compiler.program,
+            classes: compiler.classes,
+        })
+    }
+
+    /// The pattern as it was given.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// A matcher for this regex, holding the memory its matching takes so
+    /// that matching line after line allocates nothing.
+    pub fn matcher(&self) -> Matcher<'_> {
+        let size = self.program.len();
+        Matcher {
+            regex: self,
+            current: Threads::new(size),
+            next: Threads::new(size),
+            stack: Vec::new(),
+        }
+    }
+
+    fn is_word(&self, input: &[u32], index: usize) -> bool {
+        input
+            .get(index)
+            .is_some_and(|&unit| Perl::Word.contains(unit, self.units))
+    }
+
+    fn holds(&self, assertion: Assertion, input: &[u32], at: usize) -> bool {
+        let boundary = || (at > 0 && self.is_word(input, at - 1)) != self.is_word(input, at);
+        match assertion {
+            Assertion::Start => at == 0,
+            Assertion::End => at == input.len(),
+            Assertion::WordBoundary => boundary(),
+            Assertion::NotWordBoundary => !boundary(),
+        }
+    }
+
+    fn reads(&self, inst: &Inst, unit: u32) -> bool {
+        match *inst {
+            Inst::Unit {
+                unit: expected,
+                fold,
+            } => expected == unit || (fold && cases(unit, self.units).contains(&Some(expected))),
+            Inst::Class { class, fold } => self.classes[class].contains(unit, self.units, fold),
+            Inst::Any => true,
+            _ => false,
+        }
+    }
+}
+
+/// Matches a [`Regex`] against lines given as units, characters or bytes
+/// according to [`units`](Self::units).
+#[derive(Debug)]
+pub struct Matcher<'a> {
+    regex: &'a Regex,
+    current: Threads,
+    next: Threads,
+    stack: Vec<usize>,
+}
+
+impl Matcher<'_> {
+    /// What the input is read as.
+    pub fn units(&self) -> Units {
+        self.regex.units
+    }
+
+    /// Adds the thread at `pc` to `next` together with those it leads to
+    /// without reading, highest priority first.
+    fn add(
+        regex: &Regex,
+        threads: &mut Threads,
+        stack: &mut Vec<usize>,
+        pc: usize,
+        start: usize,
+        input: &[u32],
+        at: usize,
+    ) {
+        stack.push(pc);
+        while let Some(pc) = stack.pop() {
+            if !threads.set.insert(pc) {
+                continue;
+            }
+            threads.starts[pc] = start;
+            match regex.program[pc] {
+                Inst::Jump(to) => stack.push(to),
+                Inst::Split(first, second) => {
+                    stack.push(second);
+                    stack.push(first);
+                }
+                Inst::Assert(assertion) if regex.holds(assertion, input, at) => stack.push(pc + 1),
+                _ => {}
+            }
+        }
+    }
+
+    /// The start and end of the leftmost match in `input` that starts at
+    /// `from` or later.
+    pub fn find_at(&mut self, input: &[u32], from: usize) -> Option<(usize, usize)> {
+        let regex = self.regex;
+        let mut found = None;
+        self.current.set.clear();
+        for at in from..=input.len() {
+            if found.is_none() {
+                Self::add(regex, &mut self.current, &mut self.stack, 0, at, input, at);
+            }
+            if self.current.set.dense.is_empty() {
+                break;
+            }
+            self.next.set.clear();
+            for index in 0..self.current.set.dense.len() {
+                let pc = self.current.set.dense[index];
+                let start = self.current.starts[pc];
+                let inst = &regex.program[pc];
+                if *inst == Inst::Match {
+                    found = Some((start, at));
+                    // The threads after this one could only give matches
+                    // that rank lower.
+                    break;
+                }
+                if input.get(at).is_some_and(|&unit| regex.reads(inst, unit)) {
+                    Self::add(
+                        regex,
+                        &mut self.next,
+                        &mut self.stack,
+                        pc + 1,
+                        start,
+                        input,
+                        at + 1,
+                    );
+                }
+            }
+            std::mem::swap(&mut self.current, &mut self.next);
+        }
+        found
+    }
+
+    /// How many matches `input` holds, none overlapping another, with an
+    /// empty match right after another match not counted.
+    pub fn count(&mut self, input: &[u32]) -> usize {
+        let mut count = 0;
+        let mut from = 0;
+        let mut last_end = None;
+        while from <= input.len() {
+            let Some((start, end)) = self.find_at(input, from) else {
+                break;
+            };
+            if start == end && last_end == Some(end) {
+                from = end + 1;
+                continue;
+            }
+            count += 1;
+            last_end = Some(end);
+            from = if start == end { end + 1 } else { end };
+        }
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn units(text: &str) -> Vec<u32> {
+        text.chars().map(u32::from).collect()
+    }
+
+    fn find(pattern: &str, text: &str) -> Option<(usize, usize)> {
+        Regex::new(pattern, Units::Chars)
+            .unwrap()
+            .matcher()
+            .find_at(&units(text), 0)
+    }
+
+    fn count(pattern: &str, text: &str) -> usize {
+        Regex::new(pattern, Units::Chars)
+            .unwrap()
+            .matcher()
+            .count(&units(text))
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(find("TODO|FIXME", "x FIXME y"), Some((2, 7)));
+        assert_eq!(find("a+", "baaab"), Some((1, 4)));
+        assert_eq!(find("a+?", "baaab"), Some((1, 2)));
+        assert_eq!(find("a|ab", "ab"), Some((0, 1)));
+        assert_eq!(find("^a", "ba"), None);
+        assert_eq!(find("a$", "aba"), Some((2, 3)));
+        assert_eq!(find(r"\bis\b", "this is"), Some((5, 7)));
+        assert_eq!(find(r"\d{2,3}", "a 1234"), Some((2, 5)));
+        assert_eq!(find("x{2}", "xyxx"), Some((2, 4)));
+        assert_eq!(find("[^a-c]", "abcd"), Some((3, 4)));
+        assert_eq!(find(r"[\d.]+", "v1.25"), Some((1, 5)));
+        assert_eq!(find("(?i)todo", "a ToDo"), Some((2, 6)));
+        assert_eq!(find("(?i:É)t", "ét"), Some((0, 2)));
+        assert_eq!(find("(?i:a)b", "AB"), None);
+        assert_eq!(find(r"\w+", "  naïve!"), Some((2, 7)));
+        assert_eq!(find(r"a\.b", "axb a.b"), Some((4, 7)));
+        assert_eq!(find("(ab)*c", "ababc"), Some((0, 5)));
+        assert_eq!(find("(a*)*b", "aaab"), Some((0, 4)));
+        assert_eq!(find("", "abc"), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_count() {
+        assert_eq!(count("TODO", "TODO TODO, TODOs"), 3);
+        assert_eq!(count("aa", "aaaaa"), 2);
+        assert_eq!(count("a*", "aab"), 2);
+        assert_eq!(count("x", "abc"), 0);
+    }
+
+    #[test]
+    fn test_bytes() {
+        let regex = Regex::new(r"caf\xE9|é", Units::Bytes).unwrap();
+        let mut matcher = regex.matcher();
+        let input: Vec<u32> = b"un caf\xe9".iter().map(|&byte| u32::from(byte)).collect();
+        assert_eq!(matcher.find_at(&input, 0), Some((3, 7)));
+        let utf8: Vec<u32> = "é".bytes().map(u32::from).collect();
+        assert_eq!(matcher.find_at(&utf8, 0), Some((0, 2)));
+        assert!(Regex::new("[é]", Units::Bytes).is_err());
+        assert!(Regex::new(r"\x{100}", Units::Bytes).is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        for (pattern, error) in [
+            ("TODO(", "unclosed group opened at character 5"),
+            ("a)", "unopened group closed at character 2"),
+            ("*a", "nothing to repeat at character 1"),
+            ("[a-", "unclosed class opened at character 1"),
+            ("[z-a]", "class range out of order at character 2"),
+            (r"\q", "unknown escape \\q at character 1"),
+            (
+                "a{3,1}",
+                "repetition whose maximum is below its minimum at character 2",
+            ),
+            (
+                "a{2000}",
+                "repetition of more than 1000 times at character 2",
+            ),
+            ("a**", "a quantifier cannot follow another at character 3"),
+            ("a\\", "trailing backslash at character 2"),
+        ] {
+            assert_eq!(
+                Regex::new(pattern, Units::Chars).unwrap_err(),
+                error,
+                "{}",
+                pattern
+            );
+        }
+        assert_eq!(
+            Regex::new("(a{1000}){1000}", Units::Chars).unwrap_err(),
+            "pattern too large once its repetitions are spelled out"
+        );
+    }
+}
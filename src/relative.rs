@@ -0,0 +1,118 @@
+use std::path::{Component, Path, PathBuf, MAIN_SEPARATOR};
+
+use crate::osname;
+
+/// How `--relative-to` and `--slash` show the paths in a report. Files are
+/// still opened by the paths they were given as; only what is printed
+/// changes.
+#[derive(Debug, Clone, Default)]
+pub struct ShownPaths {
+    /// The directory that relative paths are resolved against.
+    cwd: PathBuf,
+    /// The `--relative-to` directory, absolute and without `.` or `..`.
+    base: Option<PathBuf>,
+    slash: bool,
+}
+
+/// Drops `.` and resolves `..` without looking at the file system, so that
+/// a path behind a symbolic link keeps the name it was given by.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normal.pop();
+            }
+            other => normal.push(other),
+        }
+    }
+    normal
+}
+
+/// Whether `path` starts with the `\\?\` of a verbatim Windows path or the
+/// `\\.\` of a device, where `/` is not a separator.
+fn is_verbatim(path: &str) -> bool {
+    path.starts_with(r"\\?\") || path.starts_with(r"\\.\")
+}
+
+impl ShownPaths {
+    pub fn new(cwd: &Path, base: Option<&str>, slash: bool) -> Self {
+        ShownPaths {
+            cwd: cwd.to_path_buf(),
+            base: base.map(|base| normalize(&cwd.join(base))),
+            slash,
+        }
+    }
+
+    /// `path` relative to the base, or absolute when it is not under the
+    /// base, with `--slash` separators.
+    pub fn show(&self, path: &str) -> String {
+        if crate::http::is_url(path) {
+            return path.to_string();
+        }
+        let mut shown = match &self.base {
+            Some(base) => {
+                let absolute = normalize(&self.cwd.join(osname::path(path)));
+                match absolute.strip_prefix(base) {
+                    Ok(relative) if relative.as_os_str().is_empty() => ".".to_string(),
+                    Ok(relative) => osname::encode(relative.as_os_str()).into_owned(),
+                    Err(_) => osname::encode(absolute.as_os_str()).into_owned(),
+                }
+            }
+            None => path.to_string(),
+        };
+        // Elsewhere a backslash is part of the name, not a separator, and
+        // in a `\\?\` or `\\.\` path it can only be a backslash.
+        if self.slash && MAIN_SEPARATOR != '/' && !is_verbatim(&shown) {
+            shown = shown.replace(MAIN_SEPARATOR, "/");
+        }
+        shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_show_relative_to() {
+        let cwd = Path::new("/home/runner/work/project");
+        let shown = ShownPaths::new(cwd, Some("."), false);
+        assert_eq!(shown.show("src/main.rs"), "src/main.rs");
+        assert_eq!(shown.show("./src/../README.md"), "README.md");
+        assert_eq!(
+            shown.show("/home/runner/work/project/src/lib.rs"),
+            "src/lib.rs"
+        );
+        assert_eq!(
+            shown.show("../other/a.txt"),
+            "/home/runner/work/other/a.txt"
+        );
+
+        let shown = ShownPaths::new(cwd, Some("src"), true);
+        assert_eq!(shown.show("src/format/json.rs"), "format/json.rs");
+        assert_eq!(shown.show("src"), ".");
+        assert_eq!(
+            shown.show("tests/git.rs"),
+            "/home/runner/work/project/tests/git.rs"
+        );
+    }
+
+    #[test]
+    fn test_is_verbatim() {
+        assert!(is_verbatim(r"\\?\C:\very\long\path.txt"));
+        assert!(is_verbatim(r"\\?\UNC\server\share\a.txt"));
+        assert!(is_verbatim(r"\\.\COM1"));
+        assert!(!is_verbatim(r"\\server\share\a.txt"));
+        assert!(!is_verbatim(r"C:\a.txt"));
+    }
+
+    #[test]
+    fn test_show_unchanged_without_a_base() {
+        let shown = ShownPaths::new(Path::new("/tmp"), None, false);
+        assert_eq!(shown.show("./a/../b.txt"), "./a/../b.txt");
+        let shown = ShownPaths::new(Path::new("/tmp"), None, true);
+        assert_eq!(shown.show("a/b.txt"), "a/b.txt");
+    }
+}
@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::figures;
+
+/// How much of the files found `--sample` and `--sample-percent` analyze.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Size {
+    /// `--sample N`: N of them picked at random, or all when there are no
+    /// more.
+    Count(usize),
+    /// `--sample-percent P`: each with a chance of P percent.
+    Percent(f64),
+}
+
+/// What the files of a sampled run were picked from, for its summary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sampled {
+    /// The files picked.
+    pub size: u64,
+    /// The files there were to pick from, once filtered.
+    pub population: u64,
+    /// The `--seed` that picks the same files again.
+    pub seed: u64,
+}
+
+impl Sampled {
+    /// "sampled 5,000 of 1,943,201 files with --seed 42", its counts
+    /// grouped when `group`.
+    pub fn summary(&self, group: bool) -> String {
+        format!(
+            "sampled {} with --seed {}",
+            figures::of(self.size, self.population, "file", group),
+            self.seed
+        )
+    }
+
+    /// The `sample` object of the JSON summary.
+    pub fn json(&self) -> String {
+        format!(
+            "{{\"size\": {}, \"population\": {}, \"seed\": {}}}",
+            self.size, self.population, self.seed
+        )
+    }
+}
+
+/// Picks the files of a sample from the candidates as discovery comes upon
+/// them, holding only those picked so far: for a count by reservoir
+/// sampling, for a percentage by a draw per file.
+#[derive(Debug)]
+pub struct Sampler {
+    size: Size,
+    seed: u64,
+    random: Random,
+    /// The candidates offered so far.
+    offered: u64,
+    /// The files picked so far, by the order they were offered in.
+    kept: Vec<(u64, String)>,
+    /// The candidates offered so far, when several inputs may name a file
+    /// twice; a single walk finds each file once.
+    seen: Option<HashSet<String>>,
+}
+
+impl Sampler {
+    /// A sampler picking `size` files with `seed`, or with one of its own
+    /// without. `distinct` offers each path once however often it comes.
+    pub fn new(size: Size, seed: Option<u64>, distinct: bool) -> Self {
+        let seed = seed.unwrap_or_else(fresh_seed);
+        Sampler {
+            size,
+            seed,
+            random: Random(seed),
+            offered: 0,
+            kept: Vec::new(),
+            seen: distinct.then(HashSet::new),
+        }
+    }
+
+    /// Takes one more candidate, which the sample may keep in place of one
+    /// taken before.
+    pub fn offer(&mut self, path: String) {
+        if let Some(seen) = &mut self.seen {
+            if !seen.insert(path.clone()) {
+                return;
+            }
+        }
+        let index = self.offered;
+        self.offered += 1;
+        match self.size {
+            Size::Count(count) if self.kept.len() < count => self.kept.push((index, path)),
+            Size::Count(count) => {
+                let slot = self.random.below(index + 1);
+                if slot < count as u64 {
+                    self.kept[slot as usize] = (index, path);
+                }
+            }
+            Size::Percent(percent) => {
+                if self.random.chance(percent) {
+                    self.kept.push((index, path));
+                }
+            }
+        }
+    }
+
+    /// The files picked, in the order they were found, and what they were
+    /// picked from.
+    pub fn finish(mut self) -> (Vec<String>, Sampled) {
+        self.kept.sort_unstable_by_key(|&(index, _)| index);
+        let sampled = Sampled {
+            size: self.kept.len() as u64,
+            population: self.offered,
+            seed: self.seed,
+        };
+        let paths = self.kept.into_iter().map(|(_, path)| path).collect();
+        (paths, sampled)
+    }
+}
+
+/// A seed for a run without `--seed`, from the clock and the process, which
+/// the summary reports so that the run can be repeated. It is kept below
+/// 2^63 for the JSON readers that take numbers for signed.
+fn fresh_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    Random(nanos ^ (u64::from(std::process::id()) << 32)).next() >> 1
+}
+
+/// SplitMix64, which any seed, zero too, starts well.
+#[derive(Debug)]
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number below `n`, each as likely as the others.
+    fn below(&mut self, n: u64) -> u64 {
+        ((u128::from(self.next()) * u128::from(n)) >> 64) as u64
+    }
+
+    /// Whether a draw with a chance of `percent` percent comes up.
+    fn chance(&mut self, percent: f64) -> bool {
+        let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        unit * 100.0 < percent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(size: Size, seed: u64, paths: &[&str], distinct: bool) -> (Vec<String>, Sampled) {
+        let mut sampler = Sampler::new(size, Some(seed), distinct);
+        for path in paths {
+            sampler.offer(path.to_string());
+        }
+        sampler.finish()
+    }
+
+    #[test]
+    fn test_count_keeps_found_order() {
+        let names: Vec<String> = (0..1000).map(|index| format!("{}.txt", index)).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let (paths, sampled) = sample(Size::Count(10), 42, &names, false);
+        assert_eq!(
+            sampled,
+            Sampled {
+                size: 10,
+                population: 1000,
+                seed: 42
+            }
+        );
+        let indices: Vec<usize> = paths
+            .iter()
+            .map(|path| path.trim_end_matches(".txt").parse().unwrap())
+            .collect();
+        assert!(indices.windows(2).all(|pair| pair[0] < pair[1]));
+        // The same seed picks the same files, and another seed others.
+        assert_eq!(sample(Size::Count(10), 42, &names, false).0, paths);
+        assert_ne!(sample(Size::Count(10), 43, &names, false).0, paths);
+        // No more files than asked for are all of them.
+        assert_eq!(sample(Size::Count(5), 1, &names[..3], false).0, names[..3]);
+    }
+
+    #[test]
+    fn test_count_is_uniform() {
+        // Each of 10 files should be among 3 of them 30% of the time.
+        let names: Vec<String> = (0..10).map(|index| index.to_string()).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let mut picked = [0; 10];
+        for seed in 0..3000 {
+            for path in sample(Size::Count(3), seed, &names, false).0 {
+                picked[path.parse::<usize>().unwrap()] += 1;
+            }
+        }
+        assert!(
+            picked.iter().all(|&count| (800..1000).contains(&count)),
+            "{:?}",
+            picked
+        );
+    }
+
+    #[test]
+    fn test_percent() {
+        let names: Vec<String> = (0..10_000).map(|index| index.to_string()).collect();
+        let names: Vec<&str> = names.iter().map(String::as_str).collect();
+        let (paths, sampled) = sample(Size::Percent(10.0), 7, &names, false);
+        assert!((900..1100).contains(&paths.len()), "{}", paths.len());
+        assert_eq!(sampled.size, paths.len() as u64);
+        assert_eq!(
+            sample(Size::Percent(100.0), 7, &names, false).0.len(),
+            10_000
+        );
+    }
+
+    #[test]
+    fn test_distinct_offers_each_path_once() {
+        let (paths, sampled) = sample(Size::Count(5), 3, &["a", "b", "a", "c"], true);
+        assert_eq!(paths, ["a", "b", "c"]);
+        assert_eq!(sampled.population, 3);
+    }
+
+    #[test]
+    fn test_summary() {
+        let sampled = Sampled {
+            size: 5000,
+            population: 1_943_201,
+            seed: 42,
+        };
+        assert_eq!(
+            sampled.summary(true),
+            "sampled 5,000 of 1,943,201 files with --seed 42"
+        );
+        assert_eq!(
+            sampled.json(),
+            "{\"size\": 5000, \"population\": 1943201, \"seed\": 42}"
+        );
+    }
+}
@@ -0,0 +1,2662 @@
+use crate::binary::{Sniffer, SNIFF_LEN};
+use crate::blank;
+use crate::bom::{self, Bom};
+use crate::charset::{self, Charset, DetectEncoding, Guess};
+use crate::comment::{Syntax, Tracker};
+use crate::duplicates;
+use crate::encoding::Encoding;
+use crate::filetype::{self, Types};
+use crate::generated::{self, Markers, Thresholds};
+use crate::histogram::{Buckets, Histogram};
+use crate::locate;
+use crate::markdown;
+use crate::osname;
+use crate::pages;
+use crate::pattern::{self, Patterns};
+use crate::peek;
+use crate::preview;
+use crate::sha256::Sha256;
+use crate::shebang;
+use crate::suppress;
+use crate::top::{self, TopLines};
+use crate::transitions::{self, Ending};
+use crate::utf8::Validator;
+use crate::wc::Counter;
+use crate::window::{Clip, Head, Window};
+use crate::{FileStats, LongLines, MixedIndentLines, UnicodeBreaks};
+
+const CR: u32 = 0x0d;
+const LF: u32 = 0x0a;
+
+/// Indent widths considered for space-indented files, widest first.
+const INDENT_WIDTHS: [usize; 3] = [8, 4, 2];
+
+/// How many lines with trailing whitespace or mixed indentation are listed
+/// by number.
+pub const MAX_LISTED_LINES: usize = 10;
+
+/// Columns between tab stops unless `--tab-width` says otherwise.
+pub const DEFAULT_TAB_WIDTH: usize = 8;
+
+/// How much input after a lone CR between line content is held back
+/// waiting for an LF, past which the CR is taken for a line ending.
+const MAX_HELD_BYTES: usize = 1 << 20;
+
+/// Settings that change what the scanner counts.
+#[derive(Debug, Clone, Copy)]
+pub struct ScanOptions {
+    /// Also treat NEL, LS, PS, VT and FF as line breaks.
+    pub unicode_linebreaks: bool,
+    /// Decode the input this way instead of going by its byte-order mark.
+    pub encoding: Option<Encoding>,
+    /// Guess the encoding of input without a byte-order mark, and with
+    /// `Apply` decode it as UTF-16 if that is the guess.
+    pub detect_encoding: Option<DetectEncoding>,
+    /// Validate UTF-8 input and record where it is invalid.
+    pub check_encoding: bool,
+    /// Do not count form feeds as unexpected control characters.
+    pub allow_form_feed: bool,
+    /// Count whitespace-only lines as empty rather than as blank.
+    pub blank_as_empty: bool,
+    /// Compute Markdown statistics whatever the file extension.
+    pub markdown: bool,
+    /// Leave lines inside Markdown code fences out of the long line count.
+    pub skip_fenced_code: bool,
+    /// Hash the contents with SHA-256.
+    pub hash: bool,
+    /// Count repeated lines, which takes memory for every distinct line.
+    pub duplicates: bool,
+    /// Record the runs of lines that end the same way.
+    pub transitions: bool,
+    /// List the lines that do not use the most common line ending, up to
+    /// this many per ending.
+    pub locate: Option<usize>,
+    /// List the empty lines, up to this many.
+    pub show_empty: Option<usize>,
+    /// Count the runs of empty lines longer than this, with `--max-blank-run`.
+    pub max_blank_run: Option<usize>,
+    /// Build a line length histogram with these buckets.
+    pub histogram: Option<Buckets>,
+    /// Count the lines longer than this many columns.
+    pub line_limit: Option<LineLimit>,
+    /// Keep the longest lines, with `--top-lines`.
+    pub top_lines: Option<TopLines>,
+    /// Keep the first and last lines with text, with `--peek`, shown this
+    /// many characters wide.
+    pub peek: Option<usize>,
+    /// Look for lines indented with both tabs and spaces.
+    pub mixed_indent: Option<MixedIndent>,
+    /// Columns between tab stops when measuring lines; never zero.
+    pub tab_width: usize,
+    /// How many endings may differ from the most common one before a file
+    /// counts as mixed.
+    pub tolerance: Tolerance,
+    /// Count only this part of the input, with `--lines` or `--bytes`.
+    pub window: Option<Window>,
+    /// Count only the start of the input, with `--head-lines` or
+    /// `--head-bytes`.
+    pub head: Option<Head>,
+    /// Count the lines matching these, with `--count-pattern`.
+    pub patterns: Option<Patterns>,
+    /// When a file looks minified.
+    pub generated: Thresholds,
+    /// What files without an extension are taken for, by name and shebang.
+    pub types: Types,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        ScanOptions {
+            unicode_linebreaks: false,
+            encoding: None,
+            detect_encoding: None,
+            check_encoding: false,
+            allow_form_feed: false,
+            blank_as_empty: false,
+            markdown: false,
+            skip_fenced_code: false,
+            hash: false,
+            duplicates: false,
+            transitions: false,
+            locate: None,
+            show_empty: None,
+            max_blank_run: None,
+            histogram: None,
+            line_limit: None,
+            top_lines: None,
+            peek: None,
+            mixed_indent: None,
+            tab_width: DEFAULT_TAB_WIDTH,
+            tolerance: Tolerance::default(),
+            window: None,
+            head: None,
+            patterns: None,
+            generated: Thresholds::default(),
+            types: Types::default(),
+        }
+    }
+}
+
+/// Which lines `--check mixed-indent` reports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixedIndent {
+    /// Lines with a space before a tab in their indentation. Tabs followed
+    /// by spaces is the "smart tabs" way of aligning and is allowed.
+    SpaceBeforeTab,
+    /// Any line indented with both tabs and spaces.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    Any,
+}
+
+/// The `--max-minority-endings` and `--max-minority-percent` limits on the
+/// endings that differ from a file's most common one. With neither, any
+/// such ending makes the file mixed; with both, exceeding either does.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Tolerance {
+    pub endings: Option<usize>,
+    pub percent: Option<f64>,
+}
+
+impl Tolerance {
+    /// Whether `minority` of `total` endings is more than this tolerates.
+    pub fn exceeded(self, minority: usize, total: usize) -> bool {
+        // Without a clear majority there is no minority to tolerate, which
+        // keeps a two-line file with one of each from passing either limit.
+        if minority == 0 || minority * 2 >= total {
+            return minority > 0;
+        }
+        match (self.endings, self.percent) {
+            (None, None) => true,
+            (endings, percent) => {
+                endings.is_some_and(|limit| minority > limit)
+                    || percent.is_some_and(|limit| minority as f64 * 100.0 > limit * total as f64)
+            }
+        }
+    }
+}
+
+/// The `--max-line-length` settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineLimit {
+    /// Longest allowed line, in columns.
+    pub max_columns: usize,
+    /// How many of the long lines are listed by number.
+    pub listed: usize,
+}
+
+/// The leading bytes of a UTF-8 encoded NEL (`C2 85`), LS (`E2 80 A8`) or
+/// PS (`E2 80 A9`) seen so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Partial {
+    None,
+    C2,
+    E2,
+    E280,
+}
+
+/// How a lone CR with line content on both sides, as in `foo\rbar`, is
+/// taken: the input after one is held back until an LF shows it sits inside
+/// a line, or the end of the input or `MAX_HELD_BYTES` that it ends one, and
+/// is then scanned again knowing which.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CrMode {
+    Held,
+    Embedded,
+    Ending,
+}
+
+impl Partial {
+    fn bytes(self) -> &'static [u8] {
+        match self {
+            Partial::None => &[],
+            Partial::C2 => &[0xc2],
+            Partial::E2 => &[0xe2],
+            Partial::E280 => &[0xe2, 0x80],
+        }
+    }
+}
+
+/// Incremental line-ending counter. Bytes can be fed in chunks of any size;
+/// a `\r` at the end of one chunk is held back until the next chunk shows
+/// whether it starts a CRLF pair or ends a line on its own, and likewise for
+/// the start of a multi-byte Unicode line break or half of a UTF-16 code
+/// unit. The first few bytes are buffered until a byte-order mark can be
+/// recognized, so the mark never counts as line content.
+#[derive(Debug)]
+pub struct Scanner {
+    stats: FileStats,
+    options: ScanOptions,
+    /// Start of the input, kept until it is long enough to check for a BOM,
+    /// or to guess the encoding from with `--detect-encoding`.
+    head: Vec<u8>,
+    bom_checked: bool,
+    /// Chosen once the BOM has been checked.
+    encoding: Encoding,
+    /// First byte of a UTF-16 code unit whose second byte has not arrived.
+    odd_byte: Option<u8>,
+    /// Bytes (or UTF-16 code units) seen on the current line so far.
+    line_len: usize,
+    /// The last content character on the current line was a space or tab.
+    trailing_whitespace: bool,
+    /// The current line has content other than spaces and tabs.
+    has_text: bool,
+    /// The current line as UTF-8, up to `line_head_limit` bytes, kept in
+    /// case it turns out to be the longest.
+    line_head: Vec<u8>,
+    /// `preview::MAX_BYTES`, or more for a `--peek` preview wider than
+    /// the others.
+    line_head_limit: usize,
+    /// Some of the current line did not fit in `line_head`.
+    line_head_full: bool,
+    /// Display columns taken by the current line so far: one per Unicode
+    /// scalar value, or per byte of an invalid UTF-8 sequence, with tabs
+    /// expanded to the next tab stop.
+    columns: usize,
+    /// UTF-8 continuation bytes still expected by the current sequence.
+    continuations: u8,
+    /// Bytes of 0x80 and above, and characters above U+007F; which of the
+    /// two is reported depends on whether the file is valid UTF-8.
+    non_ascii_bytes: usize,
+    non_ascii_chars: usize,
+    /// Line and column of the first of them. Everything before it on its
+    /// line is ASCII, so the column is the same in characters and bytes.
+    first_non_ascii: Option<(usize, usize)>,
+    /// Still in the leading whitespace of the current line.
+    in_indent: bool,
+    indent_spaces: usize,
+    indent_tabs: usize,
+    /// A tab followed a space in the current indentation.
+    space_before_tab: bool,
+    /// Space-indented lines whose indent is a multiple of each of
+    /// `INDENT_WIDTHS`.
+    indent_multiples: [usize; INDENT_WIDTHS.len()],
+    pending_cr: bool,
+    /// Byte offset of the pending CR.
+    cr_offset: u64,
+    /// Line and column of the pending CR, when line content comes before
+    /// it.
+    cr_position: Option<(usize, usize)>,
+    /// The input held back after a lone CR between line content; see
+    /// `CrMode`.
+    held: Option<Vec<u8>>,
+    cr_mode: CrMode,
+    /// Byte offset of the byte being scanned, counted from the start of
+    /// the file so that it carries over from one chunk to the next.
+    offset: u64,
+    partial: Partial,
+    validator: Option<Validator>,
+    sniffer: Sniffer,
+    /// Characters and words of everything after the BOM.
+    text: Counter,
+    /// Set when the file extension has a known comment syntax.
+    comments: Option<Tracker>,
+    /// Set for Markdown files.
+    markdown: Option<markdown::Tracker>,
+    /// Set with `--duplicates`.
+    duplicates: Option<duplicates::Tracker>,
+    /// Set with `--transitions`.
+    transitions: Option<transitions::Tracker>,
+    /// Set with `--hash`.
+    sha256: Option<Sha256>,
+    /// Set with `--locate`.
+    locate: Option<locate::Tracker>,
+    /// Set with `--top-lines`.
+    top_lines: Option<top::Tracker>,
+    /// Set with `--peek`.
+    peek: Option<peek::Tracker>,
+    /// How the last line ended so far.
+    last_ending: Option<Ending>,
+    /// Set with `--lines`, `--bytes` or a head; input outside it is not
+    /// scanned.
+    clip: Option<Clip>,
+    /// Set with `--count-pattern`.
+    patterns: Option<pattern::Tracker>,
+    /// Markers of generated files in the first and last lines.
+    markers: Markers,
+    /// The pages form feeds split the file into.
+    pages: pages::Tracker,
+    /// The runs of empty lines.
+    blank_runs: blank::Tracker,
+    /// `mdlt:ignore-line` and `mdlt:ignore-file` markers.
+    suppressions: suppress::Tracker,
+    /// Set for a part of a file after its first, with `--intra-file-jobs`:
+    /// its first bytes, which may still be in the window the part before
+    /// tells binary files by.
+    lead: Option<Vec<u8>>,
+}
+
+impl Scanner {
+    pub fn new(name: String, options: ScanOptions) -> Self {
+        let mut stats = FileStats::new(osname::decode(&name));
+        if options.unicode_linebreaks {
+            stats.unicode_breaks = Some(UnicodeBreaks::default());
+        }
+        if stats.file_extension.is_none() {
+            stats.detected_type = options.types.by_name(&stats.name());
+        }
+        let comments = match (&stats.file_extension, &stats.detected_type) {
+            (Some(extension), _) => Syntax::for_extension(extension),
+            (None, Some(kind)) => Syntax::for_type(kind),
+            (None, None) => None,
+        }
+        .map(Tracker::new);
+        let is_markdown = options.markdown
+            || stats.file_extension.as_deref().is_some_and(|extension| {
+                markdown::EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+            });
+        if comments.is_some() {
+            stats.comment_lines = Some(0);
+            stats.code_lines = Some(0);
+        }
+        stats.histogram = options.histogram.map(Histogram::new);
+        stats.tolerance = options.tolerance;
+        stats.window = options.window;
+        stats.head = options.head;
+        stats.empty_lines_at = options.show_empty.map(|_| Vec::new());
+        stats.mixed_indent = options.mixed_indent.map(|_| MixedIndentLines::default());
+        stats.long_lines = options.line_limit.map(|limit| LongLines {
+            limit: limit.max_columns,
+            ..LongLines::default()
+        });
+        let line_head_limit = options.peek.map_or(preview::MAX_BYTES, |width| {
+            preview::MAX_BYTES.max(width * 4)
+        });
+        Scanner {
+            stats,
+            options,
+            head: Vec::new(),
+            bom_checked: false,
+            encoding: Encoding::Utf8,
+            odd_byte: None,
+            line_len: 0,
+            trailing_whitespace: false,
+            has_text: false,
+            line_head: Vec::with_capacity(line_head_limit),
+            line_head_limit,
+            line_head_full: false,
+            columns: 0,
+            continuations: 0,
+            non_ascii_bytes: 0,
+            non_ascii_chars: 0,
+            first_non_ascii: None,
+            in_indent: true,
+            indent_spaces: 0,
+            indent_tabs: 0,
+            space_before_tab: false,
+            indent_multiples: [0; INDENT_WIDTHS.len()],
+            pending_cr: false,
+            cr_offset: 0,
+            cr_position: None,
+            held: None,
+            cr_mode: CrMode::Held,
+            offset: 0,
+            partial: Partial::None,
+            validator: None,
+            sniffer: Sniffer::default(),
+            text: Counter::default(),
+            comments,
+            markdown: is_markdown.then(markdown::Tracker::default),
+            duplicates: options.duplicates.then(duplicates::Tracker::default),
+            transitions: options.transitions.then(transitions::Tracker::default),
+            sha256: options.hash.then(Sha256::default),
+            locate: options.locate.map(locate::Tracker::new),
+            top_lines: options.top_lines.map(top::Tracker::new),
+            peek: options.peek.map(peek::Tracker::new),
+            last_ending: None,
+            clip: options
+                .window
+                .map(Clip::new)
+                .or(options.head.map(Clip::head)),
+            patterns: options.patterns.map(pattern::Tracker::new),
+            markers: Markers::default(),
+            pages: pages::Tracker::default(),
+            blank_runs: blank::Tracker::new(options.max_blank_run),
+            suppressions: suppress::Tracker::default(),
+            lead: None,
+        }
+    }
+
+    /// A scanner for the part of a file that starts `offset` bytes in,
+    /// right after a line that ended with `after`, to be merged into the
+    /// scanner of the part before it. The file must be one `splits` allows.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn part(name: String, options: ScanOptions, offset: u64, after: Ending) -> Self {
+        let mut scanner = Scanner::new(name, options);
+        scanner.bom_checked = true;
+        scanner.offset = offset;
+        scanner.last_ending = Some(after);
+        scanner.lead = Some(Vec::new());
+        scanner
+    }
+
+    /// Whether the file can be scanned in parts that start after an LF and
+    /// are merged, for `--intra-file-jobs`: it must be read as UTF-8 with
+    /// only CR and LF breaking lines, and nothing can follow it across
+    /// lines that `merge` does not stitch together: comments, or a shebang
+    /// that could start them in a file without an extension or type,
+    /// Markdown, `--lines` and heads, and the options that hash, match or
+    /// remember every line.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn splits(&self) -> bool {
+        let options = &self.options;
+        matches!(options.encoding, None | Some(Encoding::Utf8))
+            && options.detect_encoding.is_none()
+            && !options.check_encoding
+            && !options.unicode_linebreaks
+            && self.comments.is_none()
+            && (self.stats.file_extension.is_some() || self.stats.detected_type.is_some())
+            && self.markdown.is_none()
+            && self.duplicates.is_none()
+            && self.sha256.is_none()
+            && self.patterns.is_none()
+            && self.clip.is_none()
+    }
+
+    /// Takes the scanner of the part of the file right after this one's, as
+    /// if this one had gone on through it, so that `finish` gives the stats
+    /// of both. This one's part must end with an LF, so that nothing but
+    /// line numbers and what runs across lines carries over.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Scanner) {
+        debug_assert!(self.line_len == 0 && !self.pending_cr);
+        if !self.bom_checked {
+            self.check_bom(false);
+        }
+        let lines = self.stats.total_lines;
+        let ended = next.stats.total_lines > 0;
+        let at = |(line, column): (usize, usize)| (line + lines, column);
+        self.sniffer.feed(next.lead.as_deref().unwrap_or_default());
+        merge_stats(&mut self.stats, next.stats, lines, self.options);
+        self.line_len = next.line_len;
+        self.trailing_whitespace = next.trailing_whitespace;
+        self.has_text = next.has_text;
+        self.line_head = next.line_head;
+        self.line_head_full = next.line_head_full;
+        self.columns = next.columns;
+        self.continuations = next.continuations;
+        self.non_ascii_bytes += next.non_ascii_bytes;
+        self.non_ascii_chars += next.non_ascii_chars;
+        self.first_non_ascii = self.first_non_ascii.or(next.first_non_ascii.map(at));
+        self.in_indent = next.in_indent;
+        self.indent_spaces = next.indent_spaces;
+        self.indent_tabs = next.indent_tabs;
+        self.space_before_tab = next.space_before_tab;
+        for (multiples, more) in self.indent_multiples.iter_mut().zip(next.indent_multiples) {
+            *multiples += more;
+        }
+        self.pending_cr = next.pending_cr;
+        self.cr_offset = next.cr_offset;
+        self.cr_position = next.cr_position.map(at);
+        self.held = next.held;
+        self.offset = next.offset;
+        self.text.merge(next.text);
+        if let (Some(tracker), Some(next)) = (&mut self.transitions, next.transitions) {
+            tracker.merge(next, lines);
+        }
+        if let (Some(tracker), Some(next)) = (&mut self.locate, next.locate) {
+            tracker.merge(next, lines);
+        }
+        if let (Some(tracker), Some(next)) = (&mut self.top_lines, next.top_lines) {
+            tracker.merge(next, lines);
+        }
+        if let (Some(tracker), Some(next)) = (&mut self.peek, next.peek) {
+            tracker.merge(next);
+        }
+        self.last_ending = next.last_ending;
+        self.markers.merge(next.markers, lines);
+        self.pages.merge(next.pages, lines);
+        // A part without a line break leaves the run this one ends in
+        // going, where a part of lines with text would end it.
+        if ended {
+            self.blank_runs.merge(next.blank_runs, lines);
+        }
+        self.suppressions.merge(next.suppressions, lines);
+    }
+
+    /// The stats of what has been fed so far, for reporting on a file that
+    /// is still being written; the line in progress and a CR held back are
+    /// only counted by `finish`.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn stats(&self) -> &FileStats {
+        &self.stats
+    }
+
+    /// How the last line fed so far ended, or `None` when it has not ended
+    /// yet; a CR held back counts as a CR until an LF follows it, unless
+    /// line content came on both sides of it.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn last_terminator(&self) -> Option<Ending> {
+        match (self.pending_cr && self.held.is_none(), self.line_len) {
+            (true, _) => Some(Ending::Cr),
+            (false, 0) => self.last_ending,
+            (false, _) => None,
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.stats.total_lines == 0 && self.lead.is_none() {
+            self.check_shebang();
+        }
+        self.check_trailing_whitespace();
+        self.classify_indent();
+        if let Some(tracker) = self.peek.as_mut().filter(|_| self.has_text) {
+            tracker.end_line(&self.line_head, !self.line_head_full);
+        }
+        let empty = self.line_len == 0 || (!self.has_text && self.options.blank_as_empty);
+        if empty {
+            self.stats.empty_lines += 1;
+            self.list_empty_line();
+        } else if !self.has_text {
+            self.stats.blank_lines += 1;
+        }
+        self.blank_runs.end_line(self.stats.total_lines + 1, empty);
+        self.count_comment_line();
+        if let Some(tracker) = &mut self.duplicates {
+            let line = self.stats.total_lines + 1;
+            tracker.end_line(
+                line,
+                self.line_len > 0,
+                &self.line_head,
+                !self.line_head_full,
+            );
+        }
+        if let Some(tracker) = &mut self.patterns {
+            tracker.end_line(self.stats.total_lines + 1);
+        }
+        self.suppressions.end_line(self.stats.total_lines + 1);
+        let fenced = self.track_markdown();
+        self.count_blank_line();
+        self.record_length(fenced);
+        self.stats.total_lines += 1;
+        self.line_len = 0;
+        self.markers
+            .end_line(self.stats.total_lines, &mut self.line_head);
+        self.line_head.clear();
+        self.line_head_full = false;
+        self.columns = 0;
+        self.continuations = 0;
+        self.in_indent = true;
+        self.indent_spaces = 0;
+        self.indent_tabs = 0;
+        self.space_before_tab = false;
+    }
+
+    /// Records the current line with `--show-empty`, which ends up listing
+    /// just the lines `empty_lines` counts, a last line without a
+    /// terminator among them when `--blank-as-empty` finds it empty.
+    fn list_empty_line(&mut self) {
+        let (Some(listed), Some(at)) = (self.options.show_empty, &mut self.stats.empty_lines_at)
+        else {
+            return;
+        };
+        if at.len() < listed {
+            at.push(self.stats.total_lines + 1);
+        }
+    }
+
+    /// Counts the current line's indentation style. Lines with no text are
+    /// left out, since their whitespace indents nothing.
+    fn classify_indent(&mut self) {
+        if !self.has_text {
+            return;
+        }
+        let indentation = &mut self.stats.indentation;
+        match (self.indent_spaces, self.indent_tabs) {
+            (0, 0) => indentation.none += 1,
+            (spaces, 0) => {
+                indentation.spaces += 1;
+                for (multiples, width) in self.indent_multiples.iter_mut().zip(INDENT_WIDTHS) {
+                    if spaces % width == 0 {
+                        *multiples += 1;
+                    }
+                }
+            }
+            (0, _) => indentation.tabs += 1,
+            _ => {
+                indentation.mixed += 1;
+                self.check_mixed_indent();
+            }
+        }
+    }
+
+    /// Records a line indented with both tabs and spaces if the
+    /// `--check mixed-indent` rule counts it.
+    fn check_mixed_indent(&mut self) {
+        let Some(mixed) = &mut self.stats.mixed_indent else {
+            return;
+        };
+        if self.options.mixed_indent == Some(MixedIndent::SpaceBeforeTab) && !self.space_before_tab
+        {
+            return;
+        }
+        mixed.count += 1;
+        if mixed.first.len() < MAX_LISTED_LINES {
+            mixed.first.push(self.stats.total_lines + 1);
+        }
+    }
+
+    /// The widest of `INDENT_WIDTHS` that at least 80% of the space indents
+    /// are a multiple of, so a few alignment indents do not hide the
+    /// width.
+    fn indent_width(&self) -> Option<usize> {
+        let lines = self.stats.indentation.spaces;
+        INDENT_WIDTHS
+            .into_iter()
+            .zip(self.indent_multiples)
+            .find(|&(_, multiples)| lines > 0 && multiples * 5 >= lines * 4)
+            .map(|(width, _)| width)
+    }
+
+    /// Follows Markdown structure, returning whether the line was inside a
+    /// code fence.
+    fn track_markdown(&mut self) -> bool {
+        let line = self.stats.total_lines + 1;
+        self.markdown
+            .as_mut()
+            .is_some_and(|tracker| tracker.end_line(&self.line_head, line))
+    }
+
+    /// Updates the length statistics, which are all in columns apart from
+    /// `max_line_bytes`. The first of several equally long lines is the one
+    /// reported as longest. With `--skip-fenced-code`, a `fenced` line is
+    /// never a long line.
+    fn record_length(&mut self, fenced: bool) {
+        let stats = &mut self.stats;
+        let columns = self.columns;
+        if stats.longest_line.is_none() || columns > stats.max_line_length {
+            stats.longest_line = Some(stats.total_lines + 1);
+            stats.longest_line_preview = preview::render(&self.line_head, !self.line_head_full);
+        }
+        if stats.total_lines == 0 || columns < stats.min_line_length {
+            stats.min_line_length = columns;
+        }
+        stats.max_line_length = stats.max_line_length.max(columns);
+        stats.max_line_bytes = stats.max_line_bytes.max(self.line_len);
+        if let Some(tracker) = &mut self.top_lines {
+            tracker.end_line(
+                stats.total_lines + 1,
+                self.line_len,
+                columns,
+                &self.line_head,
+                !self.line_head_full,
+            );
+        }
+        stats.total_line_length += columns as u64;
+        if let Some(histogram) = &mut stats.histogram {
+            histogram.add(columns);
+        }
+        if let (Some(long), Some(limit)) = (&mut stats.long_lines, self.options.line_limit) {
+            if columns > long.limit && !(fenced && self.options.skip_fenced_code) {
+                long.count += 1;
+                if long.first.len() < limit.listed {
+                    long.first.push(stats.total_lines + 1);
+                }
+            }
+        }
+    }
+
+    /// Records the current line if it ends in whitespace. Terminators are
+    /// never content, so the CR of a CRLF is not mistaken for whitespace.
+    fn check_trailing_whitespace(&mut self) {
+        if !self.trailing_whitespace {
+            return;
+        }
+        self.trailing_whitespace = false;
+        self.stats.trailing_whitespace_lines += 1;
+        if self.stats.trailing_whitespace_at.len() < MAX_LISTED_LINES {
+            self.stats
+                .trailing_whitespace_at
+                .push(self.stats.total_lines + 1);
+        }
+    }
+
+    /// Looks for a shebang on the first line, which kernels only honor
+    /// when `#!` are the very first bytes of the file.
+    fn check_shebang(&mut self) {
+        let Some(shebang) = shebang::parse(&self.line_head) else {
+            return;
+        };
+        if let Some(bom) = self.stats.bom {
+            self.stats.warnings.push(format!(
+                "{} byte-order mark before the shebang; kernels will not run this script",
+                bom.name()
+            ));
+        }
+        self.stats.detected_language = shebang::language(&shebang);
+        if self.stats.file_extension.is_none() && self.stats.detected_type.is_none() {
+            self.stats.detected_type = self.options.types.by_shebang(&shebang);
+            if let Some(syntax) = self
+                .stats
+                .detected_type
+                .as_deref()
+                .and_then(Syntax::for_type)
+            {
+                self.start_comments(syntax);
+            }
+        }
+        self.stats.shebang = Some(shebang);
+    }
+
+    /// Starts counting comments by `syntax` at the end of the first line,
+    /// once its shebang has told the language, from that line on.
+    fn start_comments(&mut self, syntax: Syntax) {
+        let mut tracker = Tracker::new(syntax);
+        for &byte in &self.line_head {
+            tracker.push(u32::from(byte));
+        }
+        self.comments = Some(tracker);
+        self.stats.comment_lines = Some(0);
+        self.stats.code_lines = Some(0);
+    }
+
+    fn count_comment_line(&mut self) {
+        if let Some(tracker) = &mut self.comments {
+            let count = match tracker.end_line(self.has_text) {
+                true => &mut self.stats.comment_lines,
+                false if self.has_text => &mut self.stats.code_lines,
+                false => return,
+            };
+            *count = count.map(|count| count + 1);
+        }
+    }
+
+    /// Extends or resets the run of blank lines at the end of the input,
+    /// where a line holding only spaces and tabs counts as blank.
+    fn count_blank_line(&mut self) {
+        if self.has_text {
+            self.has_text = false;
+            self.stats.trailing_blank_lines = 0;
+        } else {
+            self.stats.trailing_blank_lines += 1;
+        }
+    }
+
+    /// Counts `c`, a byte or UTF-16 code unit, as ordinary line content.
+    fn content(&mut self, c: u32) {
+        if c == 0x0c {
+            self.form_feed();
+        }
+        self.line_len += 1;
+        if let Some(tracker) = &mut self.comments {
+            tracker.push(c);
+        }
+        if let Some(tracker) = &mut self.markdown {
+            tracker.push(c);
+        }
+        self.trailing_whitespace = c == u32::from(b' ') || c == u32::from(b'\t');
+        self.has_text |= !self.trailing_whitespace;
+        if self.in_indent {
+            match c {
+                0x20 => self.indent_spaces += 1,
+                0x09 => {
+                    self.indent_tabs += 1;
+                    self.space_before_tab |= self.indent_spaces > 0;
+                }
+                _ => self.in_indent = false,
+            }
+        }
+        if let Some(tracker) = &mut self.duplicates {
+            match self.encoding {
+                Encoding::Utf8 => tracker.push(&[c as u8]),
+                _ => tracker.push(&(c as u16).to_le_bytes()),
+            }
+        }
+        if self.encoding == Encoding::Utf8 {
+            self.column_byte(c as u8);
+            self.keep(&[c as u8]);
+        } else if c == 0x09 {
+            self.tab();
+            self.keep(b"\t");
+        } else {
+            if !(0xdc00..=0xdfff).contains(&c) {
+                // Low surrogates complete a character counted already.
+                self.columns += 1;
+                if c > 0x7f {
+                    self.non_ascii_chars += 1;
+                    self.note_non_ascii();
+                }
+            }
+            // Surrogate halves have no UTF-8 form of their own.
+            let c = char::from_u32(c).unwrap_or(char::REPLACEMENT_CHARACTER);
+            self.keep(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+    }
+
+    /// Counts the bytes of a sequence that turned out not to be a line
+    /// break; none of them are whitespace.
+    fn content_bytes(&mut self, bytes: &[u8]) {
+        if !bytes.is_empty() {
+            self.trailing_whitespace = false;
+            self.has_text = true;
+            self.in_indent = false;
+            for &byte in bytes {
+                self.line_len += 1;
+                self.column_byte(byte);
+                if let Some(tracker) = &mut self.comments {
+                    tracker.push(u32::from(byte));
+                }
+            }
+            if let Some(tracker) = &mut self.duplicates {
+                tracker.push(bytes);
+            }
+            self.keep(bytes);
+        }
+    }
+
+    /// Counts a column for each UTF-8 lead byte, and for each continuation
+    /// byte that no lead byte asked for. `line_len` must already include
+    /// `byte`.
+    fn column_byte(&mut self, byte: u8) {
+        let continuation = (0x80..0xc0).contains(&byte) && self.continuations > 0;
+        if byte >= 0x80 {
+            self.non_ascii_bytes += 1;
+            self.non_ascii_chars += usize::from(!continuation);
+            self.note_non_ascii();
+        }
+        if continuation {
+            self.continuations -= 1;
+            return;
+        }
+        if byte == b'\t' {
+            self.tab();
+        } else {
+            self.columns += 1;
+        }
+        self.continuations = match byte {
+            0xc2..=0xdf => 1,
+            0xe0..=0xef => 2,
+            0xf0..=0xf4 => 3,
+            _ => 0,
+        };
+    }
+
+    /// Remembers where the first non-ASCII character is, which the current
+    /// character ends at if it is the first.
+    fn note_non_ascii(&mut self) {
+        if self.first_non_ascii.is_none() {
+            self.first_non_ascii = Some((self.stats.total_lines + 1, self.line_len));
+        }
+    }
+
+    /// Moves to the next tab stop.
+    fn tab(&mut self) {
+        let width = self.options.tab_width;
+        self.columns = (self.columns / width + 1) * width;
+    }
+
+    /// Appends to `line_head` unless that would make it too long for a
+    /// preview, and to the whole line `--count-pattern` matches and
+    /// suppression markers are looked for in.
+    fn keep(&mut self, bytes: &[u8]) {
+        if let Some(tracker) = &mut self.patterns {
+            tracker.push(bytes);
+        }
+        self.suppressions.push(bytes);
+        if self.line_head.len() + bytes.len() <= self.line_head_limit {
+            self.line_head.extend_from_slice(bytes);
+        } else {
+            self.line_head_full = true;
+        }
+    }
+
+    /// Whether the `--lines` or `--bytes` window has been read, so that the
+    /// rest of the input can be left unread. A head is read until there is
+    /// input past it, which tells a truncated file from one that fits.
+    pub fn is_done(&self) -> bool {
+        self.clip.as_ref().is_some_and(Clip::is_done)
+            && (self.options.head.is_none() || self.stats.truncated)
+    }
+
+    /// Notes that the input starts `count` bytes in, where it was seeked
+    /// to for a `--bytes` window.
+    #[cfg(feature = "cli")]
+    pub fn skipped(&mut self, count: u64) {
+        if let Some(clip) = &mut self.clip {
+            clip.skipped(count);
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8]) {
+        let chunk = match &mut self.clip {
+            Some(clip) => {
+                let taken = clip.take(chunk);
+                if self.options.head.is_some() && clip.is_done() && taken.len() < chunk.len() {
+                    self.stats.truncated = true;
+                }
+                taken
+            }
+            None => chunk,
+        };
+        self.feed_window(chunk);
+    }
+
+    /// Counts `chunk`, which is in the `--lines` or `--bytes` window if
+    /// there is one.
+    fn feed_window(&mut self, mut chunk: &[u8]) {
+        self.stats.total_bytes += chunk.len() as u64;
+        if let Some(sha256) = &mut self.sha256 {
+            sha256.update(chunk);
+        }
+        if !self.bom_checked {
+            let wanted = match self.options.detect_encoding {
+                Some(_) if self.options.encoding.is_none() => charset::SAMPLE_BYTES,
+                _ => bom::MAX_LEN,
+            };
+            let take = chunk.len().min(wanted - self.head.len());
+            self.head.extend_from_slice(&chunk[..take]);
+            chunk = &chunk[take..];
+            if self.head.len() < wanted {
+                return;
+            }
+            self.check_bom(false);
+        }
+        self.scan(chunk);
+    }
+
+    /// Records the byte-order mark, if any, picks the encoding and scans the
+    /// buffered bytes that follow the mark. A forced encoding only
+    /// recognizes its own mark. Without either, `--detect-encoding` guesses
+    /// from the buffered bytes, which are all of the input when `complete`.
+    fn check_bom(&mut self, complete: bool) {
+        self.bom_checked = true;
+        let head = std::mem::take(&mut self.head);
+        let head = head.as_slice();
+        self.stats.bom = match self.options.encoding {
+            Some(forced) => head
+                .starts_with(forced.bom().bytes())
+                .then_some(forced.bom()),
+            None => Bom::detect(head),
+        };
+        self.encoding = self
+            .options
+            .encoding
+            .or_else(|| self.stats.bom.and_then(Encoding::from_bom))
+            .unwrap_or(Encoding::Utf8);
+        if let Some(detect) = self.options.detect_encoding {
+            if self.stats.bom.is_none() && self.options.encoding.is_none() {
+                let mut guess = Guess::new(head, complete);
+                if detect == DetectEncoding::Apply {
+                    self.encoding = guess.apply().unwrap_or(self.encoding);
+                }
+                self.stats.detected_encoding = Some(guess);
+            }
+        }
+        let skip = self.stats.bom.map_or(0, |bom| bom.bytes().len());
+        self.offset = skip as u64;
+        if self.options.check_encoding && self.encoding == Encoding::Utf8 {
+            self.validator = Some(Validator::new(skip as u64));
+        }
+        self.scan(&head[skip..]);
+    }
+
+    fn scan(&mut self, bytes: &[u8]) {
+        if self.held.is_some() {
+            self.hold(bytes);
+            return;
+        }
+        match self.encoding {
+            Encoding::Utf8 => {
+                let mut scanned = bytes.len();
+                for (i, &byte) in bytes.iter().enumerate() {
+                    if self.holds(u32::from(byte)) {
+                        scanned = i;
+                        break;
+                    }
+                    self.text.byte(byte);
+                    self.byte(byte);
+                    self.offset += 1;
+                }
+                let (bytes, held) = bytes.split_at(scanned);
+                // Decoded UTF-16 is full of NUL bytes, so only byte-oriented
+                // input is sniffed.
+                self.sniffer.feed(bytes);
+                if let Some(lead) = &mut self.lead {
+                    let take = bytes.len().min(SNIFF_LEN - lead.len());
+                    lead.extend_from_slice(&bytes[..take]);
+                }
+                if !held.is_empty() {
+                    self.hold(held);
+                }
+            }
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                for (i, &byte) in bytes.iter().enumerate() {
+                    let Some(first) = self.odd_byte.take() else {
+                        self.odd_byte = Some(byte);
+                        self.offset += 1;
+                        continue;
+                    };
+                    let unit = if self.encoding == Encoding::Utf16Le {
+                        u16::from_le_bytes([first, byte])
+                    } else {
+                        u16::from_be_bytes([first, byte])
+                    };
+                    if self.holds(u32::from(unit)) {
+                        self.offset -= 1;
+                        self.hold(&[first]);
+                        self.hold(&bytes[i..]);
+                        return;
+                    }
+                    // Offsets point at the unit's first byte.
+                    self.code_unit(unit);
+                    self.offset += 1;
+                }
+            }
+        }
+    }
+
+    /// Whether `c` is line content right after a lone CR that has line
+    /// content before it, so that whether the CR ends a line waits on the
+    /// input from `c` on.
+    fn holds(&self, c: u32) -> bool {
+        self.cr_mode == CrMode::Held
+            && self.pending_cr
+            && self.cr_position.is_some()
+            && c != CR
+            && c != LF
+    }
+
+    /// Holds back `bytes` after a lone CR between line content. Once an LF
+    /// comes, the CRs like it up to there are scanned as line content, and
+    /// past `MAX_HELD_BYTES` as line endings.
+    fn hold(&mut self, bytes: &[u8]) {
+        let lf: &[u8] = match self.encoding {
+            Encoding::Utf8 => b"\n",
+            Encoding::Utf16Le => &[0x0a, 0],
+            Encoding::Utf16Be => &[0, 0x0a],
+        };
+        let held = self.held.get_or_insert_with(Vec::new);
+        let searched = held.len() - held.len() % lf.len();
+        held.extend_from_slice(bytes);
+        let found = held[searched..held.len().min(MAX_HELD_BYTES)]
+            .chunks_exact(lf.len())
+            .position(|unit| unit == lf);
+        let (split, mode) = match found {
+            Some(unit) => (searched + (unit + 1) * lf.len(), CrMode::Embedded),
+            None if held.len() >= MAX_HELD_BYTES => (MAX_HELD_BYTES, CrMode::Ending),
+            None => return,
+        };
+        let mut held = self.held.take().unwrap_or_default();
+        let rest = held.split_off(split);
+        self.replay(&held, mode);
+        self.scan(&rest);
+    }
+
+    /// Scans the input held back as if nothing more came after it, taking
+    /// the CRs it was held for as line endings.
+    fn release(&mut self) {
+        if let Some(held) = self.held.take() {
+            self.replay(&held, CrMode::Ending);
+        }
+    }
+
+    fn replay(&mut self, held: &[u8], mode: CrMode) {
+        self.cr_mode = mode;
+        self.scan(held);
+        self.cr_mode = CrMode::Held;
+    }
+
+    /// How far the byte being scanned is from the start of its character
+    /// unit: a UTF-16 unit is complete at its second byte.
+    fn unit_start(&self) -> u64 {
+        match self.encoding {
+            Encoding::Utf8 => 0,
+            _ => 1,
+        }
+    }
+
+    /// The 1-based line the next character belongs to. A pending CR has
+    /// already ended its line unless that character is the LF completing
+    /// it, and callers only ask about characters that are not LF.
+    fn current_line(&self) -> usize {
+        self.stats.total_lines + 1 + usize::from(self.pending_cr)
+    }
+
+    fn byte(&mut self, byte: u8) {
+        let line = self.current_line();
+        if let Some(validator) = &mut self.validator {
+            validator.byte(byte, line);
+        }
+        self.control(u32::from(byte));
+        if self.partial != Partial::None && self.continue_partial(byte) {
+            return;
+        }
+        if !self.terminator(u32::from(byte)) && !self.unicode_byte(byte) {
+            self.content(u32::from(byte));
+        }
+    }
+
+    fn code_unit(&mut self, unit: u16) {
+        self.text.unit(unit);
+        self.control(u32::from(unit));
+        if !self.terminator(u32::from(unit)) && !self.unicode_unit(unit) {
+            self.content(u32::from(unit));
+        }
+    }
+
+    /// Counts NUL and the C0 control characters that do not belong in text.
+    fn control(&mut self, c: u32) {
+        let line = self.current_line();
+        let (count, first_line) = match c {
+            0x00 => (&mut self.stats.nul_bytes, &mut self.stats.first_nul_line),
+            0x09 | 0x0a | 0x0d => return,
+            0x0c if self.options.allow_form_feed => return,
+            0x01..=0x1f => (
+                &mut self.stats.control_chars,
+                &mut self.stats.first_control_line,
+            ),
+            _ => return,
+        };
+        *count += 1;
+        if first_line.is_none() {
+            *first_line = Some(line);
+        }
+    }
+
+    /// Ends the current line with `ending`.
+    fn line_break(&mut self, ending: Ending) {
+        let lf_offset = self.offset - self.unit_start();
+        let first = match ending {
+            Ending::Lf => Some((&mut self.stats.first_lf_offset, lf_offset)),
+            Ending::Crlf => Some((&mut self.stats.first_crlf_offset, self.cr_offset)),
+            Ending::Cr => Some((&mut self.stats.first_cr_offset, self.cr_offset)),
+            Ending::Unicode => None,
+        };
+        if let Some((first, offset)) = first {
+            first.get_or_insert(offset);
+        }
+        let number = self.stats.total_lines + 1;
+        if let Some(tracker) = &mut self.transitions {
+            tracker.end_line(number, ending);
+        }
+        if let Some(tracker) = &mut self.locate {
+            tracker.end_line(number, ending);
+        }
+        self.last_ending = Some(ending);
+        self.end_line();
+    }
+
+    /// Ends a page at a form feed. One that is line content, as it is
+    /// unless `--unicode-linebreaks` makes it a line break, leaves the line
+    /// it is in on the page it ends when text comes before it; one that
+    /// breaks a line has just ended it.
+    fn form_feed(&mut self) {
+        self.pages
+            .form_feed(self.stats.total_lines + usize::from(self.line_len > 0));
+    }
+
+    /// Handles CR and LF, returning `false` for anything else. A pending CR
+    /// is resolved first: it pairs with an LF, sits inside the line when
+    /// `embed_cr` says so, or ends a line on its own.
+    fn terminator(&mut self, c: u32) -> bool {
+        if self.pending_cr {
+            self.pending_cr = false;
+            if c == LF {
+                // CRLF (DOS) ending
+                self.stats.dos_endings += 1;
+                self.phantom_blank_line();
+                self.line_break(Ending::Crlf);
+                return true;
+            }
+            match self.cr_position.take() {
+                Some(position) if c != CR && self.cr_mode == CrMode::Embedded => {
+                    self.embed_cr(position);
+                }
+                _ => {
+                    // Lone CR (classic Mac) ending
+                    self.stats.mac_endings += 1;
+                    self.line_break(Ending::Cr);
+                }
+            }
+        }
+        match c {
+            CR => {
+                self.pending_cr = true;
+                self.cr_offset = self.offset - self.unit_start();
+                self.cr_position =
+                    (self.line_len > 0).then_some((self.stats.total_lines + 1, self.columns + 1));
+            }
+            LF => {
+                // LF (Unix) ending
+                self.stats.unix_endings += 1;
+                self.line_break(Ending::Lf);
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Counts a lone CR at `position` that has line content on both sides,
+    /// as in `foo\rbar`, and an LF after it, as line content: the line goes
+    /// on through it. Lone CRs that no LF comes after, as in a classic Mac
+    /// file, or at the end of one otherwise ending in LF, end lines.
+    fn embed_cr(&mut self, position: (usize, usize)) {
+        self.stats.embedded_crs += 1;
+        if self.stats.embedded_cr_at.len() < MAX_LISTED_LINES {
+            self.stats.embedded_cr_at.push(position);
+        }
+        self.content(CR);
+    }
+
+    /// Counts the empty line a CRLF ends right after a line ending in LF,
+    /// as `\n\r\n` leaves when CRLF text was only partly converted: blank
+    /// to an editor that reads CRLF, a line holding a stray CR to one that
+    /// reads LF. It stays an empty line and a CRLF ending as well.
+    fn phantom_blank_line(&mut self) {
+        if self.line_len > 0 || self.last_ending != Some(Ending::Lf) {
+            return;
+        }
+        self.stats.phantom_blank_lines += 1;
+        if self.stats.phantom_blank_at.len() < MAX_LISTED_LINES {
+            self.stats.phantom_blank_at.push(self.stats.total_lines + 1);
+        }
+    }
+
+    /// Counts a single-byte break or starts a multi-byte one, returning
+    /// `false` if `byte` is ordinary line content.
+    fn unicode_byte(&mut self, byte: u8) -> bool {
+        let Some(breaks) = self.stats.unicode_breaks.as_mut() else {
+            return false;
+        };
+        match byte {
+            0x0b => breaks.vt += 1,
+            0x0c => breaks.ff += 1,
+            0xc2 => {
+                self.partial = Partial::C2;
+                return true;
+            }
+            0xe2 => {
+                self.partial = Partial::E2;
+                return true;
+            }
+            _ => return false,
+        }
+        self.line_break(Ending::Unicode);
+        if byte == 0x0c {
+            self.form_feed();
+        }
+        true
+    }
+
+    /// The UTF-16 counterpart of `unicode_byte`, where every break is a
+    /// single code unit.
+    fn unicode_unit(&mut self, unit: u16) -> bool {
+        let Some(breaks) = self.stats.unicode_breaks.as_mut() else {
+            return false;
+        };
+        match unit {
+            0x000b => breaks.vt += 1,
+            0x000c => breaks.ff += 1,
+            0x0085 => breaks.nel += 1,
+            0x2028 => breaks.ls += 1,
+            0x2029 => breaks.ps += 1,
+            _ => return false,
+        }
+        self.line_break(Ending::Unicode);
+        if unit == 0x000c {
+            self.form_feed();
+        }
+        true
+    }
+
+    /// Extends or completes the pending multi-byte sequence with `byte`,
+    /// returning `true` if `byte` was consumed. When the sequence turns out
+    /// not to be a line break its bytes become line content and `byte` is
+    /// left for the caller to handle.
+    fn continue_partial(&mut self, byte: u8) -> bool {
+        let breaks = self
+            .stats
+            .unicode_breaks
+            .as_mut()
+            .expect("partial breaks are only tracked in Unicode mode");
+        match (self.partial, byte) {
+            (Partial::C2, 0x85) => breaks.nel += 1,
+            (Partial::E2, 0x80) => {
+                self.partial = Partial::E280;
+                return true;
+            }
+            (Partial::E280, 0xa8) => breaks.ls += 1,
+            (Partial::E280, 0xa9) => breaks.ps += 1,
+            (partial, _) => {
+                self.content_bytes(partial.bytes());
+                self.partial = Partial::None;
+                return false;
+            }
+        }
+        self.partial = Partial::None;
+        self.line_break(Ending::Unicode);
+        true
+    }
+
+    pub fn finish(mut self) -> FileStats {
+        if let Some(rest) = self.clip.as_mut().map(Clip::finish) {
+            self.feed_window(rest);
+        }
+        if !self.bom_checked {
+            self.check_bom(true);
+        }
+        self.release();
+        self.content_bytes(self.partial.bytes());
+        if let Some(byte) = self.odd_byte {
+            self.content_bytes(&[byte]);
+            self.text.truncated_unit();
+            self.stats.warnings.push(format!(
+                "{} input ends with an odd trailing byte",
+                self.encoding.name()
+            ));
+        }
+        if self.pending_cr {
+            self.stats.mac_endings += 1;
+            self.line_break(Ending::Cr);
+        }
+        // Handle last line if it doesn't end with a newline
+        let unterminated = self.line_len > 0;
+        if unterminated {
+            self.end_line();
+        }
+        // Where a head stops says nothing of how the file ends.
+        self.stats.final_newline =
+            (self.stats.total_lines > 0 && !self.stats.truncated).then_some(!unterminated);
+        self.stats.indentation.width = self.indent_width();
+        let text = self.text.finish();
+        // Characters only make sense when the file decodes, so invalid
+        // UTF-8 gets bytes counted instead.
+        self.stats.non_ascii_chars = match self.encoding != Encoding::Utf8 || text.invalid == 0 {
+            true => self.non_ascii_chars,
+            false => self.non_ascii_bytes,
+        };
+        if let Some(guess) = &mut self.stats.detected_encoding {
+            if guess.charset == Charset::EightBit {
+                guess.high_bytes = Some(self.non_ascii_bytes);
+            }
+        }
+        if let Some((line, column)) = self.first_non_ascii {
+            self.stats.first_non_ascii_line = Some(line);
+            self.stats.first_non_ascii_column = Some(column);
+        }
+        self.stats.total_chars = text.chars;
+        self.stats.total_words = text.words;
+        if text.invalid > 0 {
+            let unit = match self.encoding {
+                Encoding::Utf8 => "byte",
+                _ => "code unit",
+            };
+            self.stats.warnings.push(format!(
+                "Counted {} invalid {} {}{} as one character each",
+                text.invalid,
+                self.encoding.name(),
+                unit,
+                if text.invalid == 1 { "" } else { "s" }
+            ));
+        }
+        self.stats.utf8 = self.validator.map(Validator::finish);
+        self.stats.markdown = self.markdown.map(markdown::Tracker::finish);
+        self.stats.duplicates = self.duplicates.map(duplicates::Tracker::finish);
+        self.stats.patterns = self
+            .patterns
+            .map(pattern::Tracker::finish)
+            .unwrap_or_default();
+        self.stats.transitions = self.transitions.map(transitions::Tracker::finish);
+        self.stats.sha256 = self.sha256.map(Sha256::finish);
+        self.stats.minority_endings = self.locate.map(locate::Tracker::finish);
+        self.stats.top_lines = self.top_lines.map(top::Tracker::finish);
+        self.stats.suppressions = self.suppressions.finish();
+        self.stats.pages = self.pages.finish(self.stats.total_lines);
+        self.stats.blank_runs = self.blank_runs.finish();
+        self.stats.is_binary = self.sniffer.is_binary();
+        self.stats.peek = self
+            .peek
+            .map(|tracker| tracker.finish(self.stats.is_binary));
+        if !self.stats.is_binary {
+            if self.stats.file_extension.is_none() && self.stats.detected_type.is_none() {
+                self.stats.detected_type = Some(filetype::UNKNOWN.to_string());
+            }
+            self.stats.generated = generated::classify(
+                self.markers.finish(),
+                self.stats.total_bytes,
+                self.stats.max_line_bytes,
+                self.stats.total_line_length,
+                self.stats.total_lines,
+                self.options.generated,
+            );
+        }
+        self.stats
+    }
+}
+
+/// Adds the stats `next` has counted so far, of the part of the file after
+/// the `lines` lines `stats` has, to `stats`; its line numbers are counted
+/// from the start of its part. What `finish` fills in is left to it.
+fn merge_stats(stats: &mut FileStats, next: FileStats, lines: usize, options: ScanOptions) {
+    /// Adds `more` to the line numbers of `first`, up to `listed` of them.
+    fn list(first: &mut Vec<usize>, more: Vec<usize>, lines: usize, listed: usize) {
+        let room = listed.saturating_sub(first.len());
+        first.extend(more.into_iter().take(room).map(|line| line + lines));
+    }
+    let later = |line: Option<usize>| line.map(|line| line + lines);
+    stats.total_lines += next.total_lines;
+    stats.unix_endings += next.unix_endings;
+    stats.dos_endings += next.dos_endings;
+    stats.mac_endings += next.mac_endings;
+    stats.first_lf_offset = stats.first_lf_offset.or(next.first_lf_offset);
+    stats.first_crlf_offset = stats.first_crlf_offset.or(next.first_crlf_offset);
+    stats.first_cr_offset = stats.first_cr_offset.or(next.first_cr_offset);
+    stats.empty_lines += next.empty_lines;
+    if let (Some(first), Some(more), Some(listed)) = (
+        &mut stats.empty_lines_at,
+        next.empty_lines_at,
+        options.show_empty,
+    ) {
+        list(first, more, lines, listed);
+    }
+    stats.blank_lines += next.blank_lines;
+    stats.warnings.extend(next.warnings);
+    stats.total_bytes += next.total_bytes;
+    stats.nul_bytes += next.nul_bytes;
+    stats.first_nul_line = stats.first_nul_line.or(later(next.first_nul_line));
+    stats.control_chars += next.control_chars;
+    stats.first_control_line = stats.first_control_line.or(later(next.first_control_line));
+    stats.embedded_crs += next.embedded_crs;
+    let room = MAX_LISTED_LINES.saturating_sub(stats.embedded_cr_at.len());
+    stats.embedded_cr_at.extend(
+        next.embedded_cr_at
+            .into_iter()
+            .take(room)
+            .map(|(line, column)| (line + lines, column)),
+    );
+    stats.phantom_blank_lines += next.phantom_blank_lines;
+    list(
+        &mut stats.phantom_blank_at,
+        next.phantom_blank_at,
+        lines,
+        MAX_LISTED_LINES,
+    );
+    stats.trailing_whitespace_lines += next.trailing_whitespace_lines;
+    list(
+        &mut stats.trailing_whitespace_at,
+        next.trailing_whitespace_at,
+        lines,
+        MAX_LISTED_LINES,
+    );
+    if next.total_lines > 0 {
+        if lines == 0 || next.min_line_length < stats.min_line_length {
+            stats.min_line_length = next.min_line_length;
+        }
+        // The first of several equally long lines is the longest.
+        if stats.longest_line.is_none() || next.max_line_length > stats.max_line_length {
+            stats.longest_line = later(next.longest_line);
+            stats.longest_line_preview = next.longest_line_preview;
+        }
+    }
+    stats.max_line_length = stats.max_line_length.max(next.max_line_length);
+    stats.total_line_length += next.total_line_length;
+    stats.max_line_bytes = stats.max_line_bytes.max(next.max_line_bytes);
+    if let (Some(histogram), Some(more)) = (&mut stats.histogram, &next.histogram) {
+        histogram.merge(more);
+    }
+    if let (Some(long), Some(more), Some(limit)) =
+        (&mut stats.long_lines, next.long_lines, options.line_limit)
+    {
+        long.count += more.count;
+        list(&mut long.first, more.first, lines, limit.listed);
+    }
+    let indentation = &mut stats.indentation;
+    indentation.tabs += next.indentation.tabs;
+    indentation.spaces += next.indentation.spaces;
+    indentation.mixed += next.indentation.mixed;
+    indentation.none += next.indentation.none;
+    if let (Some(mixed), Some(more)) = (&mut stats.mixed_indent, next.mixed_indent) {
+        mixed.count += more.count;
+        list(&mut mixed.first, more.first, lines, MAX_LISTED_LINES);
+    }
+    // A part with only blank lines goes on with the run before it.
+    stats.trailing_blank_lines = match next.trailing_blank_lines == next.total_lines {
+        true => stats.trailing_blank_lines + next.trailing_blank_lines,
+        false => next.trailing_blank_lines,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generated::Generated;
+
+    #[test]
+    fn test_tolerance() {
+        let strict = Tolerance::default();
+        assert!(!strict.exceeded(0, 100));
+        assert!(strict.exceeded(1, 100));
+        let endings = Tolerance {
+            endings: Some(2),
+            percent: None,
+        };
+        assert!(!endings.exceeded(2, 1000));
+        assert!(endings.exceeded(3, 1000));
+        let percent = Tolerance {
+            endings: None,
+            percent: Some(10.0),
+        };
+        assert!(!percent.exceeded(10, 100));
+        assert!(percent.exceeded(11, 100));
+        let both = Tolerance {
+            endings: Some(2),
+            percent: Some(10.0),
+        };
+        assert!(!both.exceeded(2, 100));
+        assert!(both.exceeded(3, 100));
+        assert!(both.exceeded(2, 10));
+        // One of each in a two-line file is mixed whatever the limits.
+        let lenient = Tolerance {
+            endings: Some(5),
+            percent: Some(49.0),
+        };
+        assert!(lenient.exceeded(1, 2));
+        assert!(!lenient.exceeded(1, 3));
+    }
+
+    fn scan_in_chunks(bytes: &[u8], chunk_size: usize) -> FileStats {
+        let mut scanner = Scanner::new("test.txt".to_string(), ScanOptions::default());
+        for chunk in bytes.chunks(chunk_size) {
+            scanner.feed(chunk);
+        }
+        scanner.finish()
+    }
+
+    fn counts(stats: &FileStats) -> (usize, usize, usize, usize, usize) {
+        (
+            stats.total_lines,
+            stats.empty_lines,
+            stats.dos_endings,
+            stats.unix_endings,
+            stats.mac_endings,
+        )
+    }
+
+    #[test]
+    fn test_chunk_size_does_not_change_counts() {
+        let fixtures: &[&[u8]] = &[
+            b"",
+            b"line1\nline2\n",
+            b"line1\r\nline2\r\n",
+            b"line1\rline2\r",
+            b"line1\n\r\nline3\r\n\nline5",
+            b"\r\r\n\r",
+            b"\r",
+            b"\r\n",
+        ];
+        for fixture in fixtures {
+            let whole = counts(&scan_in_chunks(fixture, fixture.len().max(1)));
+            for chunk_size in 1..=4 {
+                assert_eq!(
+                    counts(&scan_in_chunks(fixture, chunk_size)),
+                    whole,
+                    "fixture {:?} with chunk size {}",
+                    fixture,
+                    chunk_size
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_crlf_split_across_chunks() {
+        let mut scanner = Scanner::new("split.txt".to_string(), ScanOptions::default());
+        scanner.feed(b"a\r");
+        scanner.feed(b"\nb\r");
+        scanner.feed(b"\n");
+        let stats = scanner.finish();
+        assert_eq!(counts(&stats), (2, 0, 2, 0, 0));
+    }
+
+    #[test]
+    fn test_very_long_lines() {
+        let long = vec![b'x'; 8 * 1024 * 1024];
+        let mut scanner = Scanner::new("long.min.js".to_string(), ScanOptions::default());
+        scanner.feed(&long);
+        scanner.feed(b"\r\n\n");
+        scanner.feed(&long);
+        let stats = scanner.finish();
+        // Two long lines, one empty line between them, no trailing newline.
+        assert_eq!(counts(&stats), (3, 1, 1, 1, 0));
+    }
+
+    #[test]
+    fn test_long_run_of_lone_crs() {
+        let crs = vec![b'\r'; 1024 * 1024];
+        let mut scanner = Scanner::new("cr.txt".to_string(), ScanOptions::default());
+        for chunk in crs.chunks(4096) {
+            scanner.feed(chunk);
+        }
+        scanner.feed(b"\n");
+        let stats = scanner.finish();
+        // The last `\r` pairs with the `\n`; every other one ends an empty
+        // line of its own.
+        let crs = crs.len();
+        assert_eq!(counts(&stats), (crs, crs, 1, 0, crs - 1));
+    }
+
+    #[test]
+    fn test_lone_cr_ends_a_line() {
+        assert_eq!(counts(&scan_in_chunks(b"\r", 1)), (1, 1, 0, 0, 1));
+        assert_eq!(counts(&scan_in_chunks(b"a\rb\r", 1)), (2, 0, 0, 0, 2));
+        assert_eq!(counts(&scan_in_chunks(b"a\r\rb", 1)), (3, 1, 0, 0, 2));
+        assert_eq!(counts(&scan_in_chunks(b"a\r\n\rb\n", 2)), (3, 1, 1, 1, 1));
+    }
+
+    fn scan_unicode(bytes: &[u8], chunk_size: usize) -> FileStats {
+        let options = ScanOptions {
+            unicode_linebreaks: true,
+            ..ScanOptions::default()
+        };
+        let mut scanner = Scanner::new("test.txt".to_string(), options);
+        for chunk in bytes.chunks(chunk_size) {
+            scanner.feed(chunk);
+        }
+        scanner.finish()
+    }
+
+    fn breaks(stats: &FileStats) -> (usize, usize, usize, usize, usize) {
+        let breaks = stats.unicode_breaks.as_ref().unwrap();
+        (breaks.nel, breaks.ls, breaks.ps, breaks.vt, breaks.ff)
+    }
+
+    #[test]
+    fn test_unicode_breaks_are_off_by_default() {
+        let text = "a\u{85}b\u{2028}c\u{2029}d\x0be\x0cf\n";
+        let stats = scan_in_chunks(text.as_bytes(), 64);
+        assert_eq!(counts(&stats), (1, 0, 0, 1, 0));
+        assert!(stats.unicode_breaks.is_none());
+    }
+
+    #[test]
+    fn test_unicode_breaks_counted() {
+        let text = "a\u{85}b\u{2028}c\u{2029}\x0b\x0cf\n";
+        for chunk_size in 1..=4 {
+            let stats = scan_unicode(text.as_bytes(), chunk_size);
+            assert_eq!(breaks(&stats), (1, 1, 1, 1, 1), "chunk size {}", chunk_size);
+            // Six lines, of which the ones ended by VT and FF are empty.
+            assert_eq!(counts(&stats), (6, 2, 0, 1, 0), "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_form_feed_pages() {
+        let pages = |stats: &FileStats| {
+            stats
+                .pages
+                .map(|pages| (pages.count(), pages.min_lines, pages.max_lines, pages.lines))
+        };
+        assert_eq!(pages(&scan_in_chunks(b"a\nb\n", 1)), None);
+        // A form feed at the start of a line starts the page with it, and
+        // one after text leaves that line on the page it ends.
+        let text = b"one\ntwo\n\x0cthree\nfour\x0c\nfive\n";
+        for chunk_size in 1..=3 {
+            let stats = scan_in_chunks(text, chunk_size);
+            assert_eq!(stats.total_lines, 5, "chunk size {}", chunk_size);
+            assert_eq!(
+                pages(&stats),
+                Some((3, 1, 2, 5)),
+                "chunk size {}",
+                chunk_size
+            );
+        }
+        // Made line breaks, form feeds end the line they are on as well.
+        let stats = scan_unicode(b"a\x0cb\x0c\x0c", 1);
+        assert_eq!(stats.total_lines, 3);
+        assert_eq!(pages(&stats), Some((4, 0, 1, 3)));
+        let stats = scan_in_chunks(&utf16("a\r\x0cb\r", true, true), 1);
+        assert_eq!(pages(&stats), Some((2, 1, 1, 2)));
+    }
+
+    #[test]
+    fn test_lookalike_sequences_are_line_content() {
+        // U+00A9, U+2020 and a truncated E2 80 are not line breaks.
+        for text in [&b"\xc2\xa9"[..], b"\xe2\x80\xa0", b"\xe2\x80", b"\xc2\r\n"] {
+            let stats = scan_unicode(text, 1);
+            assert_eq!(breaks(&stats), (0, 0, 0, 0, 0), "text {:?}", text);
+            assert_eq!(stats.total_lines, 1, "text {:?}", text);
+            assert_eq!(stats.empty_lines, 0, "text {:?}", text);
+        }
+        let stats = scan_unicode(b"\xe2\xe2\x80\xa8", 1);
+        assert_eq!(breaks(&stats), (0, 1, 0, 0, 0));
+        assert_eq!(counts(&stats), (1, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_bom_is_not_line_content() {
+        for chunk_size in 1..=5 {
+            let stats = scan_in_chunks(b"\xef\xbb\xbf\nx\n", chunk_size);
+            assert_eq!(stats.bom, Some(Bom::Utf8));
+            assert_eq!(counts(&stats), (2, 1, 0, 2, 0), "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_bom_only_file_has_no_lines() {
+        for mark in [&b"\xef\xbb\xbf"[..], b"\xff\xfe", b"\x00\x00\xfe\xff"] {
+            let stats = scan_in_chunks(mark, 1);
+            assert!(stats.bom.is_some(), "mark {:?}", mark);
+            assert_eq!(counts(&stats), (0, 0, 0, 0, 0), "mark {:?}", mark);
+        }
+        let stats = scan_in_chunks(b"", 1);
+        assert_eq!(stats.bom, None);
+        assert_eq!(counts(&stats), (0, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_short_input_without_bom() {
+        let stats = scan_in_chunks(b"\xef\n", 1);
+        assert_eq!(stats.bom, None);
+        assert_eq!(counts(&stats), (1, 0, 0, 1, 0));
+    }
+
+    fn utf16(text: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let units = bom.then_some(0xfeff).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            if little_endian {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn scan_with(bytes: &[u8], chunk_size: usize, options: ScanOptions) -> FileStats {
+        let mut scanner = Scanner::new("test.txt".to_string(), options);
+        for chunk in bytes.chunks(chunk_size) {
+            scanner.feed(chunk);
+        }
+        scanner.finish()
+    }
+
+    #[test]
+    fn test_utf16_with_bom_is_decoded() {
+        let text = "line1\r\n\r\nline3\nline4\r";
+        for little_endian in [true, false] {
+            for chunk_size in 1..=5 {
+                let bytes = utf16(text, little_endian, true);
+                let stats = scan_in_chunks(&bytes, chunk_size);
+                let expected = if little_endian {
+                    Bom::Utf16Le
+                } else {
+                    Bom::Utf16Be
+                };
+                assert_eq!(stats.bom, Some(expected));
+                // Same counts as the text itself scanned as UTF-8.
+                assert_eq!(
+                    counts(&stats),
+                    counts(&scan_in_chunks(text.as_bytes(), 64)),
+                    "little endian {} chunk size {}",
+                    little_endian,
+                    chunk_size
+                );
+                assert!(stats.warnings.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_utf16_zero_bytes_are_not_content_lines() {
+        // Every LF in UTF-16LE is `0A 00`; the `00` must not start a line.
+        let stats = scan_in_chunks(&utf16("a\n\n", true, true), 3);
+        assert_eq!(counts(&stats), (2, 1, 0, 2, 0));
+    }
+
+    #[test]
+    fn test_forced_utf16_without_bom() {
+        let options = ScanOptions {
+            encoding: Some(Encoding::Utf16Be),
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(&utf16("x\r\n\n", false, false), 2, options);
+        assert_eq!(stats.bom, None);
+        assert_eq!(counts(&stats), (2, 1, 1, 1, 0));
+
+        // A forced encoding ignores other marks, which become content.
+        let stats = scan_with(&utf16("x\n", true, true), 2, options);
+        assert_eq!(stats.bom, None);
+        assert_eq!(counts(&stats), (1, 0, 0, 0, 0));
+    }
+
+    #[test]
+    fn test_forced_utf8_ignores_utf16_bom() {
+        let options = ScanOptions {
+            encoding: Some(Encoding::Utf8),
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(&utf16("a\n", true, true), 1, options);
+        assert_eq!(stats.bom, None);
+        // `FF FE 61 00 0A` then a trailing `00` line.
+        assert_eq!(counts(&stats), (2, 0, 0, 1, 0));
+    }
+
+    #[test]
+    fn test_utf16_odd_trailing_byte_is_a_warning() {
+        let mut bytes = utf16("a\n", true, true);
+        bytes.push(b'b');
+        let stats = scan_in_chunks(&bytes, 1);
+        assert_eq!(counts(&stats), (2, 0, 0, 1, 0));
+        assert_eq!(
+            stats.warnings,
+            vec!["UTF-16LE input ends with an odd trailing byte".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_utf16_unicode_breaks() {
+        let options = ScanOptions {
+            unicode_linebreaks: true,
+            ..ScanOptions::default()
+        };
+        let bytes = utf16("a\u{85}b\u{2028}c\u{2029}\x0b\x0cf\n", false, true);
+        let stats = scan_with(&bytes, 3, options);
+        assert_eq!(breaks(&stats), (1, 1, 1, 1, 1));
+        assert_eq!(counts(&stats), (6, 2, 0, 1, 0));
+    }
+
+    #[test]
+    fn test_check_encoding() {
+        let options = ScanOptions {
+            check_encoding: true,
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"ok\r\n\xe9t\xe9\r\xff\n", 1, options);
+        let report = stats.utf8.unwrap();
+        assert_eq!(report.invalid, 3);
+        let lines: Vec<(usize, u64)> = report.first.iter().map(|i| (i.line, i.offset)).collect();
+        assert_eq!(lines, vec![(2, 4), (2, 6), (3, 8)]);
+
+        // Offsets count the BOM, which is not itself validated.
+        let stats = scan_with(b"\xef\xbb\xbf\xff", 2, options);
+        assert_eq!(stats.utf8.unwrap().first[0].offset, 3);
+
+        let stats = scan_with(&utf16("é", true, true), 1, options);
+        assert!(stats.utf8.is_none());
+        assert!(scan_in_chunks(b"\xff", 1).utf8.is_none());
+    }
+
+    #[test]
+    fn test_check_encoding_across_chunks() {
+        let options = ScanOptions {
+            check_encoding: true,
+            ..ScanOptions::default()
+        };
+        let text = "h\u{e9}llo \u{1f389}\n\u{65e5}\u{672c}\n";
+        for chunk_size in 1..=7 {
+            let stats = scan_with(text.as_bytes(), chunk_size, options);
+            assert_eq!(stats.utf8.unwrap().invalid, 0, "chunk size {}", chunk_size);
+        }
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        assert!(scan_in_chunks(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR", 3).is_binary);
+        assert!(!scan_in_chunks(b"plain\ntext\n", 3).is_binary);
+        // NUL bytes inside UTF-16 code units are expected.
+        assert!(!scan_in_chunks(&utf16("text\n", true, true), 3).is_binary);
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let stats = scan_in_chunks(b"ok\tfine\r\nnul\x00\x00\rbell\x07\x0c\n\x1b", 2);
+        assert_eq!(stats.nul_bytes, 2);
+        assert_eq!(stats.first_nul_line, Some(2));
+        assert_eq!(stats.control_chars, 3);
+        // The CR before `bell` is inside line 2.
+        assert_eq!(stats.first_control_line, Some(2));
+
+        let options = ScanOptions {
+            allow_form_feed: true,
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"page\x0cbreak\n", 1, options);
+        assert_eq!(stats.control_chars, 0);
+        assert_eq!(stats.first_control_line, None);
+
+        let stats = scan_in_chunks(&utf16("a\n\x01", true, true), 1);
+        assert_eq!(stats.nul_bytes, 0);
+        assert_eq!(stats.control_chars, 1);
+        assert_eq!(stats.first_control_line, Some(2));
+    }
+
+    #[test]
+    fn test_embedded_carriage_returns() {
+        let embedded = |bytes: &[u8]| {
+            let stats = scan_in_chunks(bytes, 1);
+            (stats.embedded_crs, stats.embedded_cr_at)
+        };
+        let stats = scan_in_chunks(b"foo\rbar\n", 2);
+        assert_eq!(
+            (stats.embedded_crs, stats.embedded_cr_at),
+            (1, vec![(1, 4)])
+        );
+        // The CR is line content, so the file is one LF line.
+        assert_eq!(
+            (stats.total_lines, stats.unix_endings, stats.mac_endings),
+            (1, 1, 0)
+        );
+        assert_eq!(stats.max_line_length, 7);
+        assert_eq!(
+            embedded(b"one\r\ntwo\rthree\r\n\tx\ry\n"),
+            (2, vec![(2, 4), (3, 10)])
+        );
+        // Progress output overwriting itself is all one line.
+        let stats = scan_in_chunks(b"10%\r50%\r100%\n", 3);
+        assert_eq!((stats.embedded_crs, stats.total_lines), (2, 1));
+        // A classic Mac file, with no LF to show its CRs are inside lines.
+        assert_eq!(embedded(b"a\rb\rc\r"), (0, vec![]));
+        assert_eq!(embedded(b"a\nb\rc"), (0, vec![]));
+        // A CR with no content before it, or another CR after it, ends a
+        // line.
+        assert_eq!(embedded(b"x\n\rprogress\n"), (0, vec![]));
+        assert_eq!(embedded(b"a\r\rb\n"), (0, vec![]));
+        assert_eq!(embedded(b"a\r\nb\r"), (0, vec![]));
+        assert_eq!(embedded(&utf16("a\rb\n", true, true)), (1, vec![(1, 2)]));
+        let (count, at) = embedded("a\rb\n".repeat(12).as_bytes());
+        assert_eq!((count, at.len()), (12, MAX_LISTED_LINES));
+        assert_eq!(at[9], (10, 2));
+        // Too far from an LF, a CR is taken for a line ending.
+        let mut text = b"a\r".to_vec();
+        text.resize(MAX_HELD_BYTES + 3, b'b');
+        text.push(b'\n');
+        let stats = scan_in_chunks(&text, 4096);
+        assert_eq!((stats.embedded_crs, stats.total_lines), (0, 2));
+        assert_eq!((stats.mac_endings, stats.unix_endings), (1, 1));
+    }
+
+    #[test]
+    fn test_phantom_blank_lines() {
+        let phantom = |bytes: &[u8]| {
+            let stats = scan_in_chunks(bytes, 1);
+            (stats.phantom_blank_lines, stats.phantom_blank_at)
+        };
+        let stats = scan_in_chunks(b"a\n\r\nb\n\r\n\r\n", 2);
+        // Each is an empty line and a CRLF, not a classic Mac CR as well.
+        assert_eq!((stats.total_lines, stats.empty_lines), (5, 3));
+        assert_eq!(stats.ending_counts(), [3, 2, 0]);
+        assert_eq!(stats.embedded_crs, 0);
+        // The last empty line follows a CRLF.
+        assert_eq!(
+            (stats.phantom_blank_lines, stats.phantom_blank_at),
+            (2, vec![2, 4])
+        );
+        // A CRLF file has empty lines after CRLF lines, and a lone CR
+        // ending an empty line is a classic Mac ending.
+        assert_eq!(phantom(b"a\r\n\r\nb\r\n"), (0, vec![]));
+        assert_eq!(phantom(b"a\n\rb\n"), (0, vec![]));
+        assert_eq!(phantom(b"a\n\r\r\n"), (0, vec![]));
+        assert_eq!(phantom(b"\r\na\n"), (0, vec![]));
+        assert_eq!(phantom(&utf16("a\n\r\n", true, true)), (1, vec![2]));
+        let (count, at) = phantom("a\n\r\n".repeat(12).as_bytes());
+        assert_eq!((count, at.len()), (12, MAX_LISTED_LINES));
+        assert_eq!(at[9], 20);
+    }
+
+    #[test]
+    fn test_trailing_whitespace() {
+        let stats = scan_in_chunks(b"clean\nspace \r\n\t\n  \t\r\n\nmid dle\ntab\t", 1);
+        assert_eq!(stats.trailing_whitespace_lines, 4);
+        assert_eq!(stats.trailing_whitespace_at, vec![2, 3, 4, 7]);
+
+        let stats = scan_in_chunks(b"a\rb \rc", 2);
+        assert_eq!(stats.trailing_whitespace_at, vec![2]);
+
+        let stats = scan_in_chunks(&utf16("x \ny\n", true, true), 3);
+        assert_eq!(stats.trailing_whitespace_at, vec![1]);
+    }
+
+    #[test]
+    fn test_trailing_whitespace_line_numbers_are_capped() {
+        let stats = scan_in_chunks(" \n".repeat(25).as_bytes(), 7);
+        assert_eq!(stats.trailing_whitespace_lines, 25);
+        assert_eq!(
+            stats.trailing_whitespace_at,
+            (1..=MAX_LISTED_LINES).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_final_newline() {
+        let final_newline = |bytes: &[u8]| {
+            let stats = scan_in_chunks(bytes, 1);
+            (stats.total_lines, stats.final_newline)
+        };
+        assert_eq!(final_newline(b""), (0, None));
+        assert_eq!(final_newline(b"\xef\xbb\xbf"), (0, None));
+        assert_eq!(final_newline(b"\n"), (1, Some(true)));
+        assert_eq!(final_newline(b"a\r\nb\r\n"), (2, Some(true)));
+        assert_eq!(final_newline(b"a\rb\r"), (2, Some(true)));
+        assert_eq!(final_newline(b"a\nb"), (2, Some(false)));
+        assert_eq!(final_newline(b"a\n "), (2, Some(false)));
+        let stats = scan_in_chunks(&utf16("a\nb", false, true), 1);
+        assert_eq!(stats.final_newline, Some(false));
+    }
+
+    #[test]
+    fn test_whitespace_only_lines() {
+        let text = b"a\n\n  \n\t\r\n\n\x0c\n \t";
+        let stats = scan_in_chunks(text, 1);
+        assert_eq!(stats.total_lines, 7);
+        assert_eq!((stats.empty_lines, stats.blank_lines), (2, 3));
+
+        let options = ScanOptions {
+            blank_as_empty: true,
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(text, 2, options);
+        assert_eq!((stats.empty_lines, stats.blank_lines), (5, 0));
+    }
+
+    #[test]
+    fn test_generated() {
+        let generated = |bytes: &[u8], options| scan_with(bytes, 7, options).generated;
+        let options = ScanOptions::default();
+        assert_eq!(generated(b"fn main() {}\n", options), None);
+        assert_eq!(
+            generated(b"#!/bin/sh\n# @generated by make\nexit\n", options),
+            Some(Generated::Marker {
+                marker: "@generated".to_string(),
+                line: 2
+            })
+        );
+        let minified = format!("{}\n", "x=1;".repeat(200));
+        assert_eq!(
+            generated(minified.as_bytes(), options),
+            Some(Generated::LineShare { percent: 99 })
+        );
+        let wide = ScanOptions {
+            generated: Thresholds {
+                line_length: 1000,
+                line_share: 90,
+            },
+            ..options
+        };
+        assert_eq!(generated(minified.as_bytes(), wide), None);
+        let long = format!("{}\n", "y".repeat(600)).repeat(3);
+        assert_eq!(
+            generated(long.as_bytes(), options),
+            Some(Generated::LineLength { average: 600 })
+        );
+        assert_eq!(generated(b"\x00\x01 @generated\n", options), None);
+    }
+
+    #[test]
+    fn test_show_empty() {
+        let options = ScanOptions {
+            show_empty: Some(3),
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"a\n\n\n  \n\r\n", 1, options);
+        assert_eq!(stats.empty_lines_at, Some(vec![2, 3, 5]));
+        let stats = scan_with(b"\n\n\n\n\n", 2, options);
+        assert_eq!(
+            (stats.empty_lines, stats.empty_lines_at),
+            (5, Some(vec![1, 2, 3]))
+        );
+        // A last line without a terminator is only empty when it is blank
+        // and blank lines count as empty.
+        let blank = ScanOptions {
+            blank_as_empty: true,
+            ..options
+        };
+        for (options, listed) in [(options, vec![2]), (blank, vec![2, 3])] {
+            let stats = scan_with(b"a\n\n \t", 1, options);
+            assert_eq!(stats.empty_lines_at, Some(listed));
+        }
+        assert_eq!(scan_in_chunks(b"\n", 1).empty_lines_at, None);
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        let report = ScanOptions {
+            detect_encoding: Some(DetectEncoding::Report),
+            ..ScanOptions::default()
+        };
+        let apply = ScanOptions {
+            detect_encoding: Some(DetectEncoding::Apply),
+            ..ScanOptions::default()
+        };
+        let text = "one\r\ntwo\r\n";
+        let bytes = utf16(text, true, false);
+        // Reporting the guess leaves the bytes counted as bytes.
+        let plain = scan_in_chunks(&bytes, 3);
+        let reported = scan_with(&bytes, 3, report);
+        assert_eq!(
+            (reported.dos_endings, reported.total_bytes),
+            (plain.dos_endings, plain.total_bytes)
+        );
+        let guess = reported.detected_encoding.unwrap();
+        assert_eq!((guess.charset, guess.decoded), (Charset::Utf16Le, false));
+        for chunk_size in [1, 5, 4096] {
+            let stats = scan_with(&utf16(text, false, false), chunk_size, apply);
+            let guess = stats.detected_encoding.unwrap();
+            assert_eq!((guess.charset, guess.decoded), (Charset::Utf16Be, true));
+            assert_eq!((stats.dos_endings, stats.total_chars), (2, 10));
+        }
+        let latin1 = scan_with(b"caf\xe9\r\nna\xefve\r\n", 4, apply);
+        let guess = latin1.detected_encoding.unwrap();
+        assert_eq!(
+            (guess.charset, guess.high_bytes, guess.decoded),
+            (Charset::EightBit, Some(2), false)
+        );
+        // A sample cut short inside a sequence is still UTF-8.
+        let long = format!("{}é\n", "a".repeat(charset::SAMPLE_BYTES - 1));
+        let guess = scan_with(long.as_bytes(), 1000, report).detected_encoding;
+        assert_eq!(guess.map(|guess| guess.charset), Some(Charset::Utf8));
+        // A byte-order mark or a forced encoding leaves nothing to guess.
+        assert_eq!(
+            scan_with(&utf16(text, true, true), 3, report).detected_encoding,
+            None
+        );
+        let forced = ScanOptions {
+            encoding: Some(Encoding::Utf16Le),
+            ..report
+        };
+        assert_eq!(scan_with(&bytes, 3, forced).detected_encoding, None);
+    }
+
+    #[test]
+    fn test_trailing_blank_lines() {
+        let blank = |bytes: &[u8]| scan_in_chunks(bytes, 1).trailing_blank_lines;
+        assert_eq!(blank(b""), 0);
+        assert_eq!(blank(b"a\n"), 0);
+        assert_eq!(blank(b"a\n\n\nb\n\n"), 1);
+        assert_eq!(blank(b"\n\n\n"), 3);
+        assert_eq!(blank(b"a\r\n\r\n\n\r"), 3);
+        assert_eq!(blank(b"a\n\n \t"), 2);
+        assert_eq!(blank(b"a\n  \nb"), 0);
+    }
+
+    #[test]
+    fn test_head() {
+        let head = |head| ScanOptions {
+            head: Some(head),
+            ..ScanOptions::default()
+        };
+        for chunk_size in [1, 2, 3, 64] {
+            let stats = scan_with(b"a\r\nb\r\nc\n\n", chunk_size, head(Head::Lines(2)));
+            assert_eq!(counts(&stats), (2, 0, 2, 0, 0), "{}", chunk_size);
+            assert!(stats.truncated);
+            assert_eq!(stats.final_newline, None);
+            // The CRLF the limit falls in is counted once, as a CRLF.
+            let stats = scan_with(b"a\r\nb", chunk_size, head(Head::Bytes(2)));
+            assert_eq!(counts(&stats), (1, 0, 1, 0, 0), "{}", chunk_size);
+            assert_eq!((stats.total_bytes, stats.truncated), (3, true));
+            let stats = scan_with(b"a\nb", chunk_size, head(Head::Lines(2)));
+            assert!(!stats.truncated);
+            assert_eq!(stats.final_newline, Some(false));
+            let stats = scan_with(b"a\nb\n", chunk_size, head(Head::Bytes(4)));
+            assert_eq!((stats.truncated, stats.final_newline), (false, Some(true)));
+        }
+        // Once the head is read, the scanner waits for a byte past it to
+        // tell whether the file was cut.
+        let mut scanner = Scanner::new("head.txt".to_string(), head(Head::Lines(1)));
+        scanner.feed(b"a\n");
+        assert!(!scanner.is_done());
+        scanner.feed(b"b");
+        assert!(scanner.is_done());
+    }
+
+    #[test]
+    fn test_blank_runs() {
+        let runs = |bytes: &[u8], blank_as_empty: bool| {
+            let options = ScanOptions {
+                blank_as_empty,
+                max_blank_run: Some(1),
+                ..ScanOptions::default()
+            };
+            scan_with(bytes, 1, options)
+                .blank_runs
+                .map(|runs| (runs.runs, runs.longest, runs.longest_at, runs.over_limit))
+        };
+        assert_eq!(runs(b"a\nb", false), None);
+        assert_eq!(
+            runs(b"a\n\nb\r\n\r\n\r\nc\n\n", false),
+            Some((3, 2, 4, Some(1)))
+        );
+        // Whitespace-only lines end a run unless they count as empty.
+        assert_eq!(runs(b"a\n\n \n\n", false), Some((2, 1, 2, Some(0))));
+        assert_eq!(runs(b"a\n\n \n\n", true), Some((1, 3, 2, Some(1))));
+        assert_eq!(
+            scan_in_chunks(b"\n", 1).blank_runs.unwrap().over_limit,
+            None
+        );
+    }
+
+    #[test]
+    fn test_line_lengths() {
+        let lengths = |bytes: &[u8]| {
+            let stats = scan_in_chunks(bytes, 3);
+            (
+                stats.min_line_length,
+                stats.mean_line_length(),
+                stats.max_line_length,
+            )
+        };
+        assert_eq!(lengths(b""), (0, 0.0, 0));
+        assert_eq!(lengths(b"\n"), (0, 0.0, 0));
+        let long = "x".repeat(100_000);
+        assert_eq!(lengths(long.as_bytes()), (100_000, 100_000.0, 100_000));
+        assert_eq!(lengths(b"abcd\r\n\r\nab\rabcdefghij"), (0, 4.0, 10));
+    }
+
+    #[test]
+    fn test_longest_line() {
+        let stats = scan_in_chunks(b"short\nlonger\r\n\nsame!!\nend", 2);
+        assert_eq!(stats.longest_line, Some(2));
+        assert_eq!(stats.longest_line_preview, "longer");
+
+        let stats = scan_in_chunks(b"\n\n", 1);
+        assert_eq!(stats.longest_line, Some(1));
+        assert_eq!(stats.longest_line_preview, "");
+        assert_eq!(scan_in_chunks(b"", 1).longest_line, None);
+
+        let long = format!("a\n{}\x00", "b".repeat(1000));
+        let stats = scan_in_chunks(long.as_bytes(), 64);
+        assert_eq!(stats.longest_line, Some(2));
+        assert_eq!(
+            stats.longest_line_preview,
+            format!("{}…", "b".repeat(preview::MAX_CHARS))
+        );
+
+        let options = ScanOptions {
+            unicode_linebreaks: true,
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"x\xc2\xc3\xa9\x00\n", 1, options);
+        assert_eq!(stats.longest_line_preview, "x\u{fffd}é\\0");
+
+        let stats = scan_in_chunks(&utf16("a\nnaïve\u{1f600}\n", true, true), 3);
+        assert_eq!(stats.longest_line, Some(2));
+        assert_eq!(stats.longest_line_preview, "naïve\u{fffd}\u{fffd}");
+    }
+
+    #[test]
+    fn test_histogram() {
+        assert_eq!(scan_in_chunks(b"abc\n", 1).histogram, None);
+        let options = ScanOptions {
+            histogram: Some("0,3".parse().unwrap()),
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"\nabc\r\nabcd\r\n\nab", 2, options);
+        let counts: Vec<usize> = stats
+            .histogram
+            .unwrap()
+            .buckets()
+            .map(|(_, count)| count)
+            .collect();
+        assert_eq!(counts, [2, 2, 1]);
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let options = ScanOptions {
+            line_limit: Some(LineLimit {
+                max_columns: 3,
+                listed: 2,
+            }),
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"abc\nabcd\r\n\nabcde\nx\nlong one", 2, options);
+        let long = stats.long_lines.unwrap();
+        assert_eq!((long.limit, long.count), (3, 3));
+        assert_eq!(long.first, [2, 4]);
+
+        // Multi-byte characters count once, stray bytes once each.
+        let stats = scan_with("é€😀\n\u{e9}\u{e9}\u{e9}\u{e9}\n".as_bytes(), 1, options);
+        assert_eq!(stats.long_lines.unwrap().first, [2]);
+        let stats = scan_with(b"\x80\x80\x80\x80\nab\xe2\x82\n", 3, options);
+        assert_eq!(stats.long_lines.unwrap().first, [1]);
+
+        let stats = scan_with(&utf16("😀😀😀\n😀😀😀😀", true, true), 1, options);
+        assert_eq!(stats.long_lines.unwrap().first, [2]);
+
+        assert_eq!(scan_in_chunks(b"abcdef\n", 1).long_lines, None);
+    }
+
+    #[test]
+    fn test_indentation() {
+        let text = "fn main() {\n    let x = 1;\n    if x {\n        go();\n    }\n\n  \n\tstray();\n \tboth();\n}";
+        let indentation = scan_in_chunks(text.as_bytes(), 3).indentation;
+        assert_eq!(
+            indentation,
+            crate::Indentation {
+                tabs: 1,
+                spaces: 4,
+                mixed: 1,
+                none: 2,
+                width: Some(4),
+            }
+        );
+
+        let width = |text: &str| scan_in_chunks(text.as_bytes(), 1).indentation.width;
+        assert_eq!(width("a\n  b\n    c\n  d\n"), Some(2));
+        assert_eq!(width("a\n        b\n"), Some(8));
+        assert_eq!(width("a\n   b\n      c\n"), None);
+        assert_eq!(width("a\n\tb\n"), None);
+        // One alignment indent among many 4-space ones.
+        let text = format!("{}       aligned\n", "    x\n".repeat(9));
+        assert_eq!(width(&text), Some(4));
+    }
+
+    #[test]
+    fn test_mixed_indent() {
+        let text = b"\tok\n\t  aligned\n  \tbad\n\t \tbad\n \t\n    ok\n";
+        assert_eq!(scan_in_chunks(text, 1).mixed_indent, None);
+        let mixed = |rule| {
+            let options = ScanOptions {
+                mixed_indent: Some(rule),
+                ..ScanOptions::default()
+            };
+            scan_with(text, 2, options).mixed_indent.unwrap()
+        };
+        assert_eq!(
+            mixed(MixedIndent::SpaceBeforeTab),
+            MixedIndentLines {
+                count: 2,
+                first: vec![3, 4]
+            }
+        );
+        assert_eq!(mixed(MixedIndent::Any).first, [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_tab_width() {
+        let measure = |bytes: &[u8], tab_width| {
+            let options = ScanOptions {
+                tab_width,
+                histogram: Some("4,8".parse().unwrap()),
+                line_limit: Some(LineLimit {
+                    max_columns: 8,
+                    listed: 10,
+                }),
+                ..ScanOptions::default()
+            };
+            scan_with(bytes, 1, options)
+        };
+        let text = b"\tab\nab\tc\nabcdefgh\t\n";
+        let stats = measure(text, 8);
+        assert_eq!((stats.min_line_length, stats.max_line_length), (9, 16));
+        assert_eq!(stats.max_line_bytes, 9);
+        assert_eq!(stats.longest_line, Some(3));
+        assert_eq!(stats.long_lines.unwrap().first, [1, 2, 3]);
+
+        let stats = measure(text, 4);
+        assert_eq!((stats.min_line_length, stats.max_line_length), (5, 12));
+        assert_eq!(stats.long_lines.unwrap().first, [3]);
+        let counts: Vec<usize> = stats
+            .histogram
+            .unwrap()
+            .buckets()
+            .map(|(_, count)| count)
+            .collect();
+        assert_eq!(counts, [0, 2, 1]);
+
+        // Tab stops count characters, not bytes.
+        let stats = measure("é\tx".as_bytes(), 4);
+        assert_eq!(stats.max_line_length, 5);
+        let stats = scan_with(&utf16("ab\tc", true, true), 1, ScanOptions::default());
+        assert_eq!((stats.max_line_length, stats.max_line_bytes), (9, 4));
+    }
+
+    #[test]
+    fn test_text_counts() {
+        let text = "\u{feff}one two\r\nthree  f\u{f6}ur\r\n\r\nfi";
+        for chunk_size in 1..=5 {
+            let stats = scan_in_chunks(text.as_bytes(), chunk_size);
+            assert_eq!(stats.total_bytes, text.len() as u64);
+            // The BOM is not a character; each CRLF is two.
+            assert_eq!(stats.total_chars, text.chars().count() as u64 - 1);
+            assert_eq!(stats.total_words, 5, "chunk size {}", chunk_size);
+            assert!(stats.warnings.is_empty());
+        }
+
+        let stats = scan_in_chunks(b"ab\xff cd\n", 1);
+        assert_eq!((stats.total_chars, stats.total_words), (7, 2));
+        assert_eq!(
+            stats.warnings,
+            ["Counted 1 invalid UTF-8 byte as one character each"]
+        );
+
+        let bytes = utf16("\u{1f389} x\r\n", true, true);
+        let stats = scan_in_chunks(&bytes, 3);
+        assert_eq!(stats.total_bytes, bytes.len() as u64);
+        assert_eq!((stats.total_chars, stats.total_words), (5, 2));
+    }
+
+    #[test]
+    fn test_comment_lines() {
+        let scan = |name: &str, bytes: &[u8]| {
+            let mut scanner = Scanner::new(name.to_string(), ScanOptions::default());
+            for chunk in bytes.chunks(2) {
+                scanner.feed(chunk);
+            }
+            let stats = scanner.finish();
+            (stats.comment_lines, stats.code_lines)
+        };
+        let text = b"/* a\r\n\r\n   b */\r\nfn main() {} // c\r\n  // d\r\n \t\r\n}";
+        assert_eq!(scan("main.rs", text), (Some(3), Some(2)));
+        assert_eq!(scan("notes.txt", text), (None, None));
+        let bytes = utf16("# a\nx = 1\n", true, true);
+        assert_eq!(scan("script.py", &bytes), (Some(1), Some(1)));
+    }
+
+    #[test]
+    fn test_shebang() {
+        let shebang = |bytes: &[u8]| {
+            let stats = scan_in_chunks(bytes, 3);
+            (stats.shebang, stats.detected_language, stats.warnings)
+        };
+        assert_eq!(
+            shebang(b"#!/usr/bin/env bash\r\necho hi\r\n"),
+            (Some("/usr/bin/env bash".to_string()), Some("Bash"), vec![])
+        );
+        assert_eq!(
+            shebang(b"#!/opt/tool"),
+            (Some("/opt/tool".to_string()), None, vec![])
+        );
+        assert_eq!(shebang(b"# !/bin/sh\n"), (None, None, vec![]));
+        assert_eq!(shebang(b"#comment\n#!/bin/sh\n"), (None, None, vec![]));
+        assert_eq!(
+            shebang(b"\xef\xbb\xbf#!/bin/sh\n"),
+            (
+                Some("/bin/sh".to_string()),
+                Some("Shell"),
+                vec![
+                    "UTF-8 byte-order mark before the shebang; kernels will not run this script"
+                        .to_string()
+                ]
+            )
+        );
+    }
+
+    #[test]
+    fn test_markdown() {
+        let text = b"# Title\r\n\r\n```\r\n# code\r\nxxxxxxxxxx\r\n```\r\nyyyyyyyyyy [a](b)\r\n";
+        let scan = |name: &str, options: ScanOptions| {
+            let mut scanner = Scanner::new(name.to_string(), options);
+            for chunk in text.chunks(3) {
+                scanner.feed(chunk);
+            }
+            scanner.finish()
+        };
+        let limit = Some(LineLimit {
+            max_columns: 5,
+            listed: 10,
+        });
+        let stats = scan(
+            "README.MD",
+            ScanOptions {
+                line_limit: limit,
+                ..ScanOptions::default()
+            },
+        );
+        let markdown = stats.markdown.unwrap();
+        assert_eq!(markdown.headings, [1, 0, 0, 0, 0, 0]);
+        assert_eq!((markdown.code_blocks, markdown.fenced_lines), (1, 2));
+        assert_eq!(markdown.links, 1);
+        assert_eq!(stats.long_lines.unwrap().first, [1, 4, 5, 7]);
+
+        let stats = scan(
+            "notes",
+            ScanOptions {
+                markdown: true,
+                skip_fenced_code: true,
+                line_limit: limit,
+                ..ScanOptions::default()
+            },
+        );
+        assert!(stats.markdown.is_some());
+        assert_eq!(stats.long_lines.unwrap().first, [1, 7]);
+        assert_eq!(scan("notes.txt", ScanOptions::default()).markdown, None);
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let options = ScanOptions {
+            duplicates: true,
+            ..ScanOptions::default()
+        };
+        let text = b"GET /\r\nGET /a\nGET /\n\n\nGET /\rGET /a";
+        let stats = scan_with(text, 2, options);
+        let duplicates = stats.duplicates.unwrap();
+        assert_eq!(duplicates.distinct_lines, 2);
+        assert_eq!(duplicates.duplicate_lines, 3);
+        let top: Vec<(usize, usize)> = duplicates
+            .most_repeated
+            .iter()
+            .map(|line| (line.count, line.first_line))
+            .collect();
+        assert_eq!(top, [(3, 1), (2, 2)]);
+        assert_eq!(duplicates.most_repeated[0].preview, "GET /");
+        assert_eq!(scan_in_chunks(text, 2).duplicates, None);
+    }
+
+    #[test]
+    fn test_non_ascii() {
+        let non_ascii = |bytes: &[u8]| {
+            let stats = scan_in_chunks(bytes, 1);
+            (
+                stats.non_ascii_chars,
+                stats.first_non_ascii_line,
+                stats.first_non_ascii_column,
+            )
+        };
+        assert_eq!(non_ascii(b"plain\tascii\n"), (0, None, None));
+        // The BOM is not counted.
+        assert_eq!(non_ascii(b"\xef\xbb\xbfplain\n"), (0, None, None));
+        let text = "key = 1\r\n\t\u{e9}t\u{e9} = \u{201c}x\u{201d}\n";
+        assert_eq!(non_ascii(text.as_bytes()), (4, Some(2), Some(2)));
+        // Invalid UTF-8 counts bytes instead of characters.
+        assert_eq!(
+            non_ascii(b"a\n\t\xc3\xa9\n\xc3\xa9\xff"),
+            (5, Some(2), Some(2))
+        );
+        assert_eq!(non_ascii(b"ab\xe2\x82\n"), (2, Some(1), Some(3)));
+        let stats = scan_in_chunks(&utf16("ab\n\u{e9}\u{1f389}", false, true), 1);
+        assert_eq!(stats.non_ascii_chars, 2);
+        assert_eq!(stats.first_non_ascii_line, Some(2));
+        assert_eq!(stats.first_non_ascii_column, Some(1));
+    }
+
+    #[test]
+    fn test_transitions() {
+        let options = ScanOptions {
+            transitions: true,
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"a\nb\nc\r\nd\ne\n\nf\rg", 3, options);
+        let segments: Vec<(usize, usize, &str)> = stats
+            .transitions
+            .unwrap()
+            .iter()
+            .map(|s| (s.start_line, s.end_line, s.ending.name()))
+            .collect();
+        assert_eq!(
+            segments,
+            [(1, 2, "LF"), (3, 3, "CRLF"), (4, 6, "LF"), (7, 7, "CR")]
+        );
+        assert_eq!(stats.unix_endings, 5);
+        let stats = scan_with(b"x\r", 1, options);
+        assert_eq!(stats.transitions.unwrap().len(), 1);
+        assert_eq!(scan_in_chunks(b"a\n", 1).transitions, None);
+    }
+
+    #[test]
+    fn test_locate() {
+        let options = ScanOptions {
+            locate: Some(1),
+            ..ScanOptions::default()
+        };
+        let stats = scan_with(b"a\nb\r\nc\nd\r\r\nf\ng", 2, options);
+        let summaries: Vec<String> = stats
+            .minority_endings
+            .unwrap()
+            .iter()
+            .map(|minority| minority.summary(true))
+            .collect();
+        assert_eq!(summaries, ["CRLF on lines: 2 and 1 more", "CR on lines: 4"]);
+        assert_eq!(
+            scan_with(b"a\nb\n", 1, options).minority_endings,
+            Some(vec![])
+        );
+        assert_eq!(scan_in_chunks(b"a\r\nb\n", 1).minority_endings, None);
+    }
+
+    #[test]
+    fn test_first_ending_offsets() {
+        let offsets = |stats: FileStats| {
+            (
+                stats.first_lf_offset,
+                stats.first_crlf_offset,
+                stats.first_cr_offset,
+            )
+        };
+        let text = b"ab\ncd\r\ne\r\r\n";
+        for chunk in 1..text.len() + 1 {
+            assert_eq!(
+                offsets(scan_in_chunks(text, chunk)),
+                (Some(2), Some(5), Some(8))
+            );
+        }
+        assert_eq!(offsets(scan_in_chunks(b"", 1)), (None, None, None));
+        assert_eq!(offsets(scan_in_chunks(b"a\r\nb", 1)), (None, Some(1), None));
+        assert_eq!(offsets(scan_in_chunks(b"a\r", 1)), (None, None, Some(1)));
+        // Offsets count the BOM and, in UTF-16, two bytes per unit.
+        let bom = b"\xef\xbb\xbfa\n";
+        assert_eq!(offsets(scan_in_chunks(bom, 2)).0, Some(4));
+        let stats = scan_in_chunks(&utf16("ab\r\nc\n", false, true), 3);
+        assert_eq!(offsets(stats), (Some(12), Some(6), None));
+    }
+
+    #[test]
+    fn test_stats_so_far() {
+        let mut scanner = Scanner::new("app.log".to_string(), ScanOptions::default());
+        assert_eq!(scanner.last_terminator(), None);
+        scanner.feed(b"a\r\nb\n");
+        assert_eq!(scanner.stats().total_lines, 2);
+        assert_eq!(scanner.last_terminator(), Some(Ending::Lf));
+        scanner.feed(b"c\r");
+        assert_eq!(scanner.last_terminator(), Some(Ending::Cr));
+        scanner.feed(b"\nd");
+        assert_eq!(scanner.last_terminator(), None);
+        assert_eq!(scanner.stats().dos_endings, 2);
+        assert_eq!(scanner.finish().total_lines, 4);
+    }
+
+    #[test]
+    fn test_sha256() {
+        let options = ScanOptions {
+            hash: true,
+            ..ScanOptions::default()
+        };
+        let text = b"\xef\xbb\xbfabc";
+        let expected =
+            Some("1c28dc3f1f804a1ad9c9b4b4cf5e2658d16ad4ed08e3020d04a8d2865018947c".to_string());
+        for chunk in [1, 2, text.len()] {
+            assert_eq!(scan_with(text, chunk, options).sha256, expected);
+        }
+        assert_eq!(scan_in_chunks(text, 2).sha256, None);
+    }
+}
@@ -0,0 +1,282 @@
+use std::io::{self, BufRead, Write};
+
+use crate::cli::{self, TreatBinary};
+use crate::format::json::{self, Value};
+use crate::{checks, CheckFailure, Exit, MdltError, Outcome};
+
+/// What one request asks for: a file on disk, or text given in the
+/// request under a name, with the flags to analyze it by.
+struct Request {
+    /// The id the response echoes, as the JSON it was written in.
+    id: String,
+    source: Source,
+    args: Vec<String>,
+}
+
+enum Source {
+    Path(String),
+    Content { name: String, text: String },
+}
+
+/// The id of `request`, as its response gives it, when it has one that is
+/// a number or a string.
+fn id_of(request: &Value) -> Option<String> {
+    match request.get("id")? {
+        Value::Number(text) => Some(text.clone()),
+        Value::String(text) => Some(json::string(text)),
+        _ => None,
+    }
+}
+
+/// Reads one line of the input as a request, or says what is wrong with
+/// it, with the id it was given when there is one.
+fn parse_request(line: &str) -> Result<Request, (Option<String>, String)> {
+    let value = json::parse(line).map_err(|e| (None, format!("not a JSON request: {}", e)))?;
+    let Value::Object(fields) = &value else {
+        return Err((None, "a request is a JSON object".to_string()));
+    };
+    let id = id_of(&value);
+    let invalid = |message: String| (id.clone(), message);
+    let id = id
+        .clone()
+        .ok_or_else(|| invalid("\"id\" must be a number or a string".to_string()))?;
+    let text = |key: &str| match value.get(key) {
+        None => Ok(None),
+        Some(Value::String(text)) => Ok(Some(text.clone())),
+        Some(_) => Err(invalid(format!("\"{}\" must be a string", key))),
+    };
+    if let Some((key, _)) = fields
+        .iter()
+        .find(|(key, _)| !["id", "path", "content", "name", "args"].contains(&key.as_str()))
+    {
+        return Err(invalid(format!("unknown key \"{}\"", key)));
+    }
+    let source = match (text("path")?, text("content")?, text("name")?) {
+        (Some(path), None, None) => Source::Path(path),
+        (None, Some(text), name) => Source::Content {
+            name: name.unwrap_or_else(|| "<stdin>".to_string()),
+            text,
+        },
+        (Some(_), Some(_), _) => {
+            return Err(invalid(
+                "a request has \"path\" or \"content\", not both".to_string(),
+            ))
+        }
+        (Some(_), None, Some(_)) => {
+            return Err(invalid(
+                "\"name\" names \"content\" and needs it".to_string(),
+            ))
+        }
+        (None, None, _) => {
+            return Err(invalid(
+                "a request needs \"path\" or \"content\"".to_string(),
+            ))
+        }
+    };
+    let args = match value.get("args") {
+        None => Vec::new(),
+        Some(Value::Array(items)) => items
+            .iter()
+            .map(|item| item.as_str().map(str::to_string))
+            .collect::<Option<_>>()
+            .ok_or_else(|| invalid("\"args\" must be an array of strings".to_string()))?,
+        Some(_) => return Err(invalid("\"args\" must be an array of strings".to_string())),
+    };
+    Ok(Request { id, source, args })
+}
+
+/// The response to `request`, analyzed with the flags `--serve` was started
+/// with, then those of the request: what became of the file and why it
+/// fails the checks, if it does.
+fn respond(request: &Request, args: &[String], config: &[String]) -> Result<String, String> {
+    let mut args = args.to_vec();
+    args.extend(request.args.iter().cloned());
+    // Without the usage a mistyped flag is followed by on the command line.
+    let options = cli::parse_args_with_config(&args, config)
+        .map_err(|e| e.lines().next().unwrap_or_default().to_string())?;
+    let outcome = match &request.source {
+        Source::Path(path) => crate::analyze_path(
+            &options,
+            None,
+            options.treat_binary.unwrap_or(TreatBinary::Analyze),
+            path,
+        )
+        .map_err(|failure| failure.to_string())?,
+        Source::Content { name, text } => crate::analyzed(
+            &options,
+            crate::analyze_bytes_with(text.as_bytes(), name.clone(), options.scan),
+        ),
+    };
+    let mut stats = match outcome {
+        Outcome::Analyzed(stats) => *stats,
+        Outcome::Skipped(file) => {
+            return Ok(format!(
+                "{{\"id\": {}, \"skipped\": {}}}",
+                request.id,
+                json::string(&file.reason)
+            ))
+        }
+        Outcome::Archive(_) => return Err("archives are not analyzed by --serve".to_string()),
+    };
+    stats.violations = checks::violations(&options, &stats);
+    let mut failures = crate::policy_violations(&options, std::slice::from_ref(&stats));
+    let ending = options
+        .expected_endings_for(stats.extension_or_type())
+        .and_then(|expected| crate::ending_violation(expected, None, &stats));
+    failures.extend(ending.map(|reason| CheckFailure::new("endings", reason)));
+    let mut object = Vec::new();
+    json::write_object(&stats, options.report, &mut object).map_err(|e| e.to_string())?;
+    let failures: Vec<String> = failures
+        .iter()
+        .map(|failure| json::string(&failure.message))
+        .collect();
+    Ok(format!(
+        "{{\"id\": {}, \"stats\": {}, \"failures\": [{}]}}",
+        request.id,
+        String::from_utf8_lossy(&object),
+        failures.join(", ")
+    ))
+}
+
+/// The response to one line of the input: an error response for a request
+/// that cannot be read or analyzed, so that the next one is still served.
+fn response(line: &[u8], args: &[String], config: &[String]) -> String {
+    let error = |id: Option<String>, message: &str| {
+        format!(
+            "{{\"id\": {}, \"error\": {}}}",
+            id.as_deref().unwrap_or("null"),
+            json::string(message)
+        )
+    };
+    let Ok(line) = std::str::from_utf8(line) else {
+        return error(None, "a request must be valid UTF-8");
+    };
+    match parse_request(line) {
+        Ok(request) => {
+            respond(&request, args, config).unwrap_or_else(|e| error(Some(request.id), &e))
+        }
+        Err((id, message)) => error(id, &message),
+    }
+}
+
+/// Answers each line of `input` with one line of `output`, in order, until
+/// the input ends; blank lines are passed over.
+pub fn serve(
+    mut input: impl BufRead,
+    mut output: impl Write,
+    args: &[String],
+    config: &[String],
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if input.read_until(b'\n', &mut line)? == 0 {
+            return Ok(());
+        }
+        if line.trim_ascii().is_empty() {
+            continue;
+        }
+        writeln!(output, "{}", response(line.trim_ascii(), args, config))?;
+        output.flush()?;
+    }
+}
+
+/// `--serve`: answers the requests on stdin on stdout until stdin ends, or
+/// whoever reads the answers stops reading.
+pub fn run(args: &[String], config: &[String]) -> Result<Exit, MdltError> {
+    match serve(io::stdin().lock(), io::stdout().lock(), args, config) {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+            Err(MdltError::io("serving requests", e))
+        }
+        _ => Ok(Exit::Clean),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn served(input: &str) -> Vec<String> {
+        let mut output = Vec::new();
+        let args = ["mdlt".to_string(), "--serve".to_string()];
+        serve(input.as_bytes(), &mut output, &args, &[]).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn test_serve_content() {
+        let responses = served(concat!(
+            "{\"id\": 1, \"name\": \"a.md\", \"content\": \"one\\r\\ntwo\\n\"}\n",
+            "\n",
+            "{\"id\": \"b\", \"content\": \"x \\n\", \"args\": [\"--check=lf\", \"--enable=trailing-whitespace\"]}\n",
+        ));
+        assert_eq!(responses.len(), 2);
+        let first = json::parse(&responses[0]).unwrap();
+        assert_eq!(first.get("id"), Some(&Value::Number("1".to_string())));
+        let stats = first.get("stats").unwrap();
+        assert_eq!(stats.get("file_name").and_then(Value::as_str), Some("a.md"));
+        assert_eq!(
+            stats.get("unix_endings"),
+            Some(&Value::Number("1".to_string()))
+        );
+        assert_eq!(first.get("failures"), Some(&Value::Array(Vec::new())));
+        let second = json::parse(&responses[1]).unwrap();
+        assert_eq!(second.get("id").and_then(Value::as_str), Some("b"));
+        let Some(Value::Array(failures)) = second.get("failures") else {
+            panic!("{}", responses[1]);
+        };
+        assert_eq!(failures.len(), 1, "{}", responses[1]);
+        assert!(failures[0]
+            .as_str()
+            .unwrap()
+            .contains("trailing whitespace"));
+    }
+
+    #[test]
+    fn test_serve_errors() {
+        let responses = served(concat!(
+            "not json\n",
+            "{\"id\": 2}\n",
+            "{\"id\": 3, \"content\": \"a\\n\", \"args\": [\"--no-such-flag\"]}\n",
+            "{\"id\": [4], \"content\": \"a\"}\n",
+            "{\"id\": 5, \"content\": \"a\", \"path\": \"a\"}\n",
+            "{\"id\": 6, \"content\": \"a\", \"colour\": \"red\"}\n",
+        ));
+        let errors: Vec<(Value, String)> = responses
+            .iter()
+            .map(|line| {
+                let value = json::parse(line).unwrap();
+                let error = value.get("error").and_then(Value::as_str).unwrap();
+                (value.get("id").unwrap().clone(), error.to_string())
+            })
+            .collect();
+        let number = |n: &str| Value::Number(n.to_string());
+        assert_eq!(errors[0].0, Value::Null);
+        assert!(errors[0].1.starts_with("not a JSON request: "));
+        assert_eq!(
+            errors[1],
+            (
+                number("2"),
+                "a request needs \"path\" or \"content\"".to_string()
+            )
+        );
+        assert_eq!(errors[2].0, number("3"));
+        assert!(errors[2].1.contains("--no-such-flag"), "{}", errors[2].1);
+        assert_eq!(
+            errors[3],
+            (
+                Value::Null,
+                "\"id\" must be a number or a string".to_string()
+            )
+        );
+        assert_eq!(
+            errors[4].1,
+            "a request has \"path\" or \"content\", not both"
+        );
+        assert_eq!(errors[5].1, "unknown key \"colour\"");
+    }
+}
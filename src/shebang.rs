@@ -0,0 +1,95 @@
+/// Languages by interpreter name, with any version suffix such as the `3`
+/// in `python3` removed; add a row to recognize another interpreter.
+const INTERPRETERS: &[(&[&str], &str)] = &[
+    (&["sh", "dash", "ash"], "Shell"),
+    (&["bash"], "Bash"),
+    (&["zsh"], "Zsh"),
+    (&["fish"], "Fish"),
+    (&["python", "pypy"], "Python"),
+    (&["ruby"], "Ruby"),
+    (&["perl"], "Perl"),
+    (&["node", "nodejs"], "JavaScript"),
+    (&["php"], "PHP"),
+    (&["lua", "luajit"], "Lua"),
+    (&["Rscript"], "R"),
+    (&["tclsh", "wish"], "Tcl"),
+    (&["awk", "gawk", "mawk"], "AWK"),
+    (&["pwsh"], "PowerShell"),
+];
+
+/// The interpreter line of a script: whatever follows `#!` at the very
+/// start of `first_line`, trimmed. `#![` starts a Rust inner attribute
+/// rather than a shebang, and `#!` with nothing after it names no
+/// interpreter.
+pub fn parse(first_line: &[u8]) -> Option<String> {
+    let rest = first_line.strip_prefix(b"#!")?;
+    if rest.starts_with(b"[") {
+        return None;
+    }
+    let shebang = String::from_utf8_lossy(rest).trim().to_string();
+    (!shebang.is_empty()).then_some(shebang)
+}
+
+/// The name of the interpreter a shebang runs, without a version suffix.
+/// For `/usr/bin/env` the interpreter is its first argument that is neither
+/// an option nor a variable assignment.
+pub fn interpreter(shebang: &str) -> Option<&str> {
+    let mut words = shebang.split_whitespace();
+    let mut program = file_name(words.next()?);
+    if program == "env" {
+        program = file_name(words.find(|word| !word.starts_with('-') && !word.contains('='))?);
+    }
+    Some(program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.'))
+}
+
+/// The language of the interpreter a shebang runs.
+pub fn language(shebang: &str) -> Option<&'static str> {
+    let name = interpreter(shebang)?;
+    INTERPRETERS
+        .iter()
+        .find(|(names, _)| names.contains(&name))
+        .map(|&(_, language)| language)
+}
+
+fn file_name(path: &str) -> &str {
+    path.rsplit('/').next().unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(b"#!/bin/sh"), Some("/bin/sh".to_string()));
+        assert_eq!(
+            parse(b"#! /usr/bin/env python3 \t"),
+            Some("/usr/bin/env python3".to_string())
+        );
+        assert_eq!(parse(b"# !/bin/sh"), None);
+        assert_eq!(parse(b"#!"), None);
+        assert_eq!(parse(b"#![allow(dead_code)]"), None);
+        assert_eq!(parse(b" #!/bin/sh"), None);
+    }
+
+    #[test]
+    fn test_interpreter() {
+        assert_eq!(interpreter("/usr/bin/python3.11 -u"), Some("python"));
+        assert_eq!(interpreter("/usr/bin/env -S deno run"), Some("deno"));
+        assert_eq!(interpreter("/usr/bin/env"), None);
+    }
+
+    #[test]
+    fn test_language() {
+        assert_eq!(language("/bin/bash -e"), Some("Bash"));
+        assert_eq!(language("/usr/bin/env python3"), Some("Python"));
+        assert_eq!(language("/usr/bin/python3.11 -u"), Some("Python"));
+        assert_eq!(
+            language("/usr/bin/env -S node --harmony"),
+            Some("JavaScript")
+        );
+        assert_eq!(language("/usr/bin/env LC_ALL=C perl -w"), Some("Perl"));
+        assert_eq!(language("/usr/bin/env"), None);
+        assert_eq!(language("/opt/custom/tool"), None);
+    }
+}
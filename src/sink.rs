@@ -0,0 +1,378 @@
+use std::io::{self, Write};
+
+use crate::aggregate::{AggregateStats, Groups};
+use crate::format::{self, OutputFormat, ReportOptions, Reported};
+use crate::tree::{self, Tree};
+use crate::{cli, listed, open_output, osname, FailedFile, FileStats, MdltError, SkippedFile};
+
+/// What each file adds to a report that is made of one part per file, in
+/// the modes that write one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Entry {
+    /// `-qq`: nothing at all.
+    Nothing,
+    /// `-q`: "name: OK".
+    Verdict,
+    /// `--list` and `--print0`: the name alone.
+    Name,
+    /// `--get`: the values asked for, after the name among several files.
+    Values,
+    /// `--check` and the like on their own: what the file breaks.
+    Misfits,
+    /// `--template`: the template filled in.
+    Template,
+    /// The text report.
+    Report,
+}
+
+impl Entry {
+    /// The entries of the report `options` ask for, `None` for a report of
+    /// the whole run at once: the other formats, `--summary-only` and
+    /// `--tree`. `listing` tells the misfits are listed.
+    pub fn of(options: &cli::Options, listing: bool) -> Option<Entry> {
+        match &options.template {
+            _ if options.quiet > 1 => Some(Entry::Nothing),
+            _ if options.quiet == 1 => Some(Entry::Verdict),
+            _ if !options.list.is_empty() || options.print0 => Some(Entry::Name),
+            _ if !options.get.is_empty() => Some(Entry::Values),
+            _ if options.tree.is_some() => None,
+            _ if listing => Some(Entry::Misfits),
+            Some(_) => Some(Entry::Template),
+            None if options.format == OutputFormat::Text && !options.report.summary_only => {
+                Some(Entry::Report)
+            }
+            None => None,
+        }
+    }
+}
+
+/// Whether the report of `entry`s can go out file by file as they are
+/// analyzed, which is when each part stands on its own, `--sort` is not
+/// to reorder them, nothing is written ahead of them and `--progress` is
+/// not drawing on the terminal meanwhile. `several` tells more than one
+/// path was given, so that a lone archive or file is reported as before.
+pub fn streams(
+    options: &cli::Options,
+    entry: Option<Entry>,
+    several: bool,
+    progress: bool,
+) -> bool {
+    entry.is_some()
+        && several
+        && !options.buffer_output
+        && options.sort.is_none()
+        && options.verbose < 2
+        && !progress
+}
+
+/// What the end of a report needs of a run that is done.
+pub struct Run<'a> {
+    /// The files the report shows, sorted and filtered.
+    pub stats: &'a [FileStats],
+    pub skipped: &'a [SkippedFile],
+    pub failed: &'a [FailedFile],
+    pub summary: &'a AggregateStats,
+    pub groups: Option<&'a Groups>,
+    pub tree: Option<&'a (tree::Settings, Tree)>,
+    pub misfits: &'a [(String, String)],
+    /// The notes the text report ends with, which the other reports leave
+    /// to stderr.
+    pub notes: &'a [String],
+}
+
+/// Where the report of a run goes: each file as it is analyzed or
+/// skipped, in the order of the run, and then what is left once the run is
+/// done.
+pub trait Sink {
+    /// Takes a file the report shows, with what it breaks of its
+    /// `.gitattributes` and `.editorconfig`.
+    fn file(&mut self, file: &FileStats, misfits: &[(String, String)]) -> Result<(), MdltError>;
+
+    /// Takes a file the report shows as skipped.
+    fn skipped(&mut self, file: &SkippedFile) -> Result<(), MdltError>;
+
+    fn finish(&mut self, run: &Run) -> Result<(), MdltError>;
+}
+
+/// The sink `options` call for.
+pub fn new<'a>(
+    options: &'a cli::Options,
+    report: ReportOptions,
+    entry: Option<Entry>,
+    streams: bool,
+) -> Box<dyn Sink + 'a> {
+    let output = Output {
+        path: options.output.as_deref(),
+        append: options.append,
+        writer: None,
+    };
+    let entries = entry.map(|entry| Entries {
+        entry,
+        options,
+        report,
+        written: 0,
+    });
+    match (entries, streams) {
+        (Some(entries), true) => Box::new(Streaming { entries, output }),
+        (entries, _) => Box::new(Collecting {
+            entries,
+            options,
+            report,
+            output,
+        }),
+    }
+}
+
+/// Writes each file the moment it is taken, flushed, and the skipped files
+/// and totals at the end.
+struct Streaming<'a> {
+    entries: Entries<'a>,
+    output: Output<'a>,
+}
+
+impl Sink for Streaming<'_> {
+    fn file(&mut self, file: &FileStats, misfits: &[(String, String)]) -> Result<(), MdltError> {
+        let writer = self.output.writer()?;
+        self.entries
+            .file(file, misfits, true, writer)
+            .and_then(|_| writer.flush())
+            .map_err(crate::writing_report)
+    }
+
+    fn skipped(&mut self, file: &SkippedFile) -> Result<(), MdltError> {
+        let writer = self.output.writer()?;
+        self.entries
+            .skipped(file, writer)
+            .and_then(|_| writer.flush())
+            .map_err(crate::writing_report)
+    }
+
+    fn finish(&mut self, run: &Run) -> Result<(), MdltError> {
+        let writer = self.output.writer()?;
+        self.entries
+            .end(run, writer)
+            .and_then(|_| notes(Some(self.entries.entry), run, writer))
+            .and_then(|_| writer.flush())
+            .map_err(crate::writing_report)
+    }
+}
+
+/// Writes nothing until the run is done, for the reports that need every
+/// file at once and for `--buffer-output`. The files themselves are kept
+/// by the run, which needs them for its totals and checks anyway.
+struct Collecting<'a> {
+    entries: Option<Entries<'a>>,
+    options: &'a cli::Options,
+    report: ReportOptions,
+    output: Output<'a>,
+}
+
+impl Sink for Collecting<'_> {
+    fn file(&mut self, _: &FileStats, _: &[(String, String)]) -> Result<(), MdltError> {
+        Ok(())
+    }
+
+    fn skipped(&mut self, _: &SkippedFile) -> Result<(), MdltError> {
+        Ok(())
+    }
+
+    fn finish(&mut self, run: &Run) -> Result<(), MdltError> {
+        let writer = self.output.writer()?;
+        let mut write = || {
+            match &mut self.entries {
+                Some(entries) if entries.entry == Entry::Misfits => {
+                    entries.misfits(run.misfits, writer)?
+                }
+                Some(entries) => {
+                    let several = match entries.entry {
+                        Entry::Values => run.stats.len() + run.skipped.len() > 1,
+                        _ => run.stats.len() > 1,
+                    };
+                    for file in format::in_order(run.stats, run.skipped) {
+                        match file {
+                            Reported::Analyzed(file) => entries.file(file, &[], several, writer)?,
+                            Reported::Skipped(file) => entries.skipped(file, writer)?,
+                        }
+                    }
+                    entries.end(run, writer)?;
+                }
+                None => match run.tree {
+                    Some((settings, tree)) => tree.write(*settings, &mut *writer)?,
+                    None => format::write_report(
+                        self.options.format,
+                        self.report,
+                        run.stats,
+                        run.skipped,
+                        run.failed,
+                        Some(run.summary),
+                        run.groups,
+                        &mut *writer,
+                    )?,
+                },
+            }
+            notes(
+                self.entries.as_ref().map(|entries| entries.entry),
+                run,
+                writer,
+            )?;
+            writer.flush()
+        };
+        write().map_err(crate::writing_report)
+    }
+}
+
+/// Ends the text report with the notes of the run, apart from the files
+/// before them but for a list of misfits.
+fn notes(entry: Option<Entry>, run: &Run, writer: &mut dyn Write) -> io::Result<()> {
+    if run.notes.is_empty() {
+        return Ok(());
+    }
+    if entry != Some(Entry::Misfits) && !run.stats.is_empty() {
+        writeln!(writer)?;
+    }
+    for note in run.notes {
+        writeln!(writer, "{}", note)?;
+    }
+    Ok(())
+}
+
+/// The report's writer, `--output` or stdout, opened at the first write so
+/// that a run that fails before it leaves the file alone.
+struct Output<'a> {
+    path: Option<&'a str>,
+    append: bool,
+    writer: Option<Box<dyn Write>>,
+}
+
+impl Output<'_> {
+    fn writer(&mut self) -> Result<&mut dyn Write, MdltError> {
+        if self.writer.is_none() {
+            self.writer = Some(match self.path {
+                Some(path) => {
+                    let file = open_output(path, self.append)?;
+                    Box::new(io::BufWriter::new(file))
+                }
+                None => Box::new(io::stdout()),
+            });
+        }
+        Ok(self.writer.as_deref_mut().expect("opened above"))
+    }
+}
+
+/// Writes the part of each file in a report of `entry`s.
+struct Entries<'a> {
+    entry: Entry,
+    options: &'a cli::Options,
+    report: ReportOptions,
+    /// The files written so far.
+    written: usize,
+}
+
+impl Entries<'_> {
+    /// Writes `file`, one of `several` files.
+    fn file(
+        &mut self,
+        file: &FileStats,
+        misfits: &[(String, String)],
+        several: bool,
+        writer: &mut dyn Write,
+    ) -> io::Result<()> {
+        let options = self.options;
+        match self.entry {
+            Entry::Nothing => {}
+            Entry::Verdict => {
+                let name = format::escape_name(&file.name());
+                writeln!(
+                    writer,
+                    "{}: {}",
+                    name,
+                    file.verdict_with(!self.report.no_group_digits)
+                )?;
+            }
+            // Only the paths go to stdout, for `xargs` and the like; with
+            // `--only-problems --print0` those of every file left.
+            Entry::Name => {
+                if !options.list.is_empty()
+                    && !options
+                        .list
+                        .iter()
+                        .any(|&predicate| listed(predicate, file))
+                {
+                    return Ok(());
+                }
+                // NUL-separated names are for programs, which get the
+                // bytes of the name even when it is not UTF-8.
+                if options.print0 {
+                    writer.write_all(&osname::bytes(&file.name()))?;
+                    writer.write_all(b"\0")?;
+                } else {
+                    writeln!(writer, "{}", file.display_name())?;
+                }
+            }
+            // A lone file gets its values alone, for `$(mdlt --get ...)`.
+            Entry::Values => {
+                let mut values: Vec<String> = options
+                    .get
+                    .iter()
+                    .map(|field| format::template::placeholder_value(file, field))
+                    .collect();
+                if several {
+                    values.insert(0, file.name().into_owned());
+                }
+                writeln!(writer, "{}", values.join("\t"))?;
+            }
+            Entry::Misfits => self.misfits(misfits, writer)?,
+            Entry::Template => {
+                if let Some(template) = &options.template {
+                    format::template::write(template, std::slice::from_ref(file), writer)?;
+                }
+            }
+            Entry::Report => {
+                if self.written > 0 {
+                    writeln!(writer)?;
+                }
+                file.display(self.report, several, writer)?;
+            }
+        }
+        self.written += 1;
+        Ok(())
+    }
+
+    fn misfits(&self, misfits: &[(String, String)], writer: &mut dyn Write) -> io::Result<()> {
+        for (path, reason) in misfits {
+            writeln!(writer, "{}: {}", format::escape_name(path), reason)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a skipped file where it was found, which only `-q` has room
+    /// for and the others but the text report send to stderr so that it is
+    /// not silently dropped. The text report lists them at its end.
+    fn skipped(&self, file: &SkippedFile, writer: &mut dyn Write) -> io::Result<()> {
+        match self.entry {
+            Entry::Nothing | Entry::Misfits | Entry::Report => {}
+            Entry::Verdict => {
+                let name = format::escape_name(&file.file_name);
+                writeln!(writer, "{}: SKIPPED ({})", name, file.reason)?;
+            }
+            Entry::Name | Entry::Values | Entry::Template => file.display(&mut io::stderr())?,
+        }
+        Ok(())
+    }
+
+    /// Writes what follows the files: the skipped files and the totals of
+    /// the text report.
+    fn end(&self, run: &Run, writer: &mut dyn Write) -> io::Result<()> {
+        match self.entry {
+            Entry::Report => format::write_text_end(
+                self.report,
+                self.written > 0,
+                run.skipped,
+                Some(run.summary),
+                run.groups,
+                writer,
+            ),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use std::io::{self, Read, Seek};
+
+use crate::bom::{self, Bom};
+use crate::encoding::Encoding;
+use crate::parallel;
+use crate::scanner::{ScanOptions, Scanner};
+use crate::transitions::Ending;
+use crate::{open_file, osname, FileStats, READ_BUFFER_SIZE};
+
+/// Files smaller than this are scanned whole whatever `--intra-file-jobs`
+/// says.
+pub const THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// The smallest part a file is split into, so that a part is worth a
+/// thread.
+const MIN_PART: u64 = 16 * 1024 * 1024;
+
+/// Scans the regular file at `path`, `len` bytes long, in up to `jobs`
+/// parts at once, or returns `None` for the caller to scan it whole: when
+/// it is smaller than [`THRESHOLD`], not UTF-8 by its byte-order mark, or
+/// read in a way [`Scanner::splits`] rules out.
+pub fn analyze(
+    path: &str,
+    len: u64,
+    jobs: usize,
+    scan: ScanOptions,
+) -> Option<io::Result<FileStats>> {
+    let parts = jobs.min(usize::try_from(len / MIN_PART).unwrap_or(usize::MAX));
+    if len < THRESHOLD || parts < 2 || !Scanner::new(path.to_string(), scan).splits() {
+        return None;
+    }
+    let open = |offset: u64| {
+        let mut file = open_file(&osname::path(path))?;
+        file.seek(io::SeekFrom::Start(offset))?;
+        Ok(file)
+    };
+    let mut head = Vec::with_capacity(bom::MAX_LEN);
+    if let Err(e) = open(0).and_then(|file| file.take(bom::MAX_LEN as u64).read_to_end(&mut head)) {
+        return Some(Err(e));
+    }
+    let utf8 = Bom::detect(&head).is_none_or(|bom| Encoding::from_bom(bom) == Some(Encoding::Utf8));
+    utf8.then(|| scan_parts(open, len, parts, path, scan))
+}
+
+/// Scans the `len` bytes `open` reads from any offset on in `parts` parts
+/// on as many threads, and merges what they count into the stats of the
+/// whole. Each part starts right after an LF, so that no line, CRLF or
+/// character is split between two of them.
+fn scan_parts<R: Read>(
+    open: impl Fn(u64) -> io::Result<R> + Sync,
+    len: u64,
+    parts: usize,
+    name: &str,
+    scan: ScanOptions,
+) -> io::Result<FileStats> {
+    let bounds: Vec<(Option<u64>, u64)> = (0..parts as u64)
+        .map(|part| {
+            let at = |part: u64| len * part / parts as u64;
+            ((part > 0).then(|| at(part)), at(part + 1))
+        })
+        .collect();
+    let scanned = parallel::map_ordered(&bounds, parts, |&(start, end)| {
+        scan_part(&open, start, (end < len).then_some(end), name, scan)
+    });
+    let mut scanned = scanned.into_iter();
+    let mut whole = scanned
+        .next()
+        .expect("at least one part")?
+        .expect("the first part is never empty");
+    for part in scanned {
+        if let Some(part) = part? {
+            whole.merge(part);
+        }
+    }
+    Ok(whole.finish())
+}
+
+/// Scans the part of the input from the first line that starts at or
+/// after `start` to the first that starts at or after `end`, or to the end
+/// of the input without one, so that the parts between increasing offsets
+/// cover it once. The first part, without a `start`, starts at the start
+/// of the input; a part that no line starts in is `None`.
+fn scan_part<R: Read>(
+    open: &impl Fn(u64) -> io::Result<R>,
+    start: Option<u64>,
+    end: Option<u64>,
+    name: &str,
+    scan: ScanOptions,
+) -> io::Result<Option<Scanner>> {
+    let mut buffer = vec![0; READ_BUFFER_SIZE];
+    let (mut reader, mut scanner, mut position) = match start {
+        None => (open(0)?, Scanner::new(name.to_string(), scan), 0),
+        Some(start) => {
+            // The two bytes before `start` may be the CRLF the part's first
+            // line starts after.
+            let from = start.saturating_sub(2);
+            let mut reader = open(from)?;
+            let mut position = from;
+            let mut previous = None;
+            let (first, after) = loop {
+                let read = reader.read(&mut buffer)?;
+                if read == 0 {
+                    return Ok(None);
+                }
+                let chunk = &buffer[..read];
+                let found = chunk.iter().enumerate().position(|(index, &byte)| {
+                    byte == b'\n' && position + index as u64 + 1 >= start
+                });
+                if let Some(index) = found {
+                    let before = index.checked_sub(1).map(|index| chunk[index]).or(previous);
+                    let after = match before {
+                        Some(b'\r') => Ending::Crlf,
+                        _ => Ending::Lf,
+                    };
+                    let first = position + index as u64 + 1;
+                    buffer.copy_within(index + 1..read, 0);
+                    buffer.truncate(read - index - 1);
+                    break (first, after);
+                }
+                previous = chunk.last().copied();
+                position += read as u64;
+            };
+            if end.is_some_and(|end| first >= end) {
+                return Ok(None);
+            }
+            let mut scanner = Scanner::part(name.to_string(), scan, first, after);
+            let position = first + buffer.len() as u64;
+            if feed(&mut scanner, &buffer, first, end) {
+                return Ok(Some(scanner));
+            }
+            buffer.resize(READ_BUFFER_SIZE, 0);
+            (reader, scanner, position)
+        }
+    };
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 || feed(&mut scanner, &buffer[..read], position, end) {
+            return Ok(Some(scanner));
+        }
+        position += read as u64;
+    }
+}
+
+/// Feeds `scanner` the `chunk` read at `position`, up to the first LF
+/// that ends a line at or after `end`, returning whether it got there.
+fn feed(scanner: &mut Scanner, chunk: &[u8], position: u64, end: Option<u64>) -> bool {
+    let found = end.and_then(|end| {
+        chunk
+            .iter()
+            .enumerate()
+            .position(|(index, &byte)| byte == b'\n' && position + index as u64 + 1 >= end)
+    });
+    match found {
+        Some(index) => {
+            scanner.feed(&chunk[..=index]);
+            true
+        }
+        None => {
+            scanner.feed(chunk);
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::histogram::Buckets;
+    use crate::scanner::{LineLimit, MixedIndent};
+    use crate::top::TopLines;
+
+    /// A xorshift generator, enough to pick fixtures from.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+    }
+
+    const PIECES: &[&[u8]] = &[
+        b"a",
+        b"word",
+        b" ",
+        b"\t",
+        b"    ",
+        b"\r",
+        b"\n",
+        b"\n",
+        b"\r\n",
+        b"\r\n",
+        b"\n\r\n",
+        b"\x0c",
+        b"\0",
+        b"\x01",
+        "\u{e9}".as_bytes(),
+        b"\xe2\x82",
+        b"\xff",
+        b"#!/bin/sh",
+        b"// @generated",
+        b"mdlt:ignore-line",
+        b"mdlt:ignore-file",
+        b"\xef\xbb\xbf",
+    ];
+
+    fn fixture(random: &mut Random) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for _ in 0..random.below(120) {
+            bytes.extend_from_slice(PIECES[random.below(PIECES.len())]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parts_match_one_scan() {
+        let options = [
+            ScanOptions::default(),
+            ScanOptions {
+                blank_as_empty: true,
+                allow_form_feed: true,
+                transitions: true,
+                locate: Some(2),
+                show_empty: Some(3),
+                max_blank_run: Some(1),
+                histogram: Some(Buckets::default()),
+                line_limit: Some(LineLimit {
+                    max_columns: 6,
+                    listed: 2,
+                }),
+                top_lines: Some(TopLines {
+                    count: 3,
+                    columns: true,
+                }),
+                peek: Some(4),
+                mixed_indent: Some(MixedIndent::Any),
+                tab_width: 4,
+                ..ScanOptions::default()
+            },
+            ScanOptions {
+                locate: Some(1),
+                show_empty: Some(1),
+                top_lines: Some(TopLines {
+                    count: 1,
+                    columns: false,
+                }),
+                peek: Some(1),
+                mixed_indent: Some(MixedIndent::SpaceBeforeTab),
+                tab_width: 8,
+                ..ScanOptions::default()
+            },
+        ];
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for round in 0..300 {
+            let bytes = fixture(&mut random);
+            let scan = options[round % options.len()];
+            let whole = {
+                let mut scanner = Scanner::new("part.txt".to_string(), scan);
+                scanner.feed(&bytes);
+                assert!(scanner.splits());
+                format!("{:?}", scanner.finish())
+            };
+            let parts = 1 + random.below(6);
+            let open = |offset: u64| Ok(&bytes[offset as usize..]);
+            let split = scan_parts(open, bytes.len() as u64, parts, "part.txt", scan).unwrap();
+            assert_eq!(
+                format!("{:?}", split),
+                whole,
+                "{:?} in {} parts",
+                String::from_utf8_lossy(&bytes),
+                parts
+            );
+        }
+    }
+
+    #[test]
+    fn test_parts_start_after_an_lf() {
+        let bytes = b"ab\r\ncd\nef\n\ng";
+        let open = |offset: u64| Ok(&bytes[offset as usize..]);
+        let part = |start, end| {
+            scan_part(&open, start, end, "part.txt", ScanOptions::default())
+                .unwrap()
+                .map(|scanner| scanner.finish().total_bytes)
+        };
+        assert_eq!(part(None, Some(4)), Some(4));
+        assert_eq!(part(Some(1), Some(7)), Some(3));
+        // Offset 3 is within the CRLF, and 8 within the line "ef".
+        assert_eq!(part(Some(3), Some(8)), Some(6));
+        assert_eq!(part(Some(4), Some(5)), Some(3));
+        // No line starts between 5 and 7.
+        assert_eq!(part(Some(5), Some(7)), None);
+        assert_eq!(part(Some(11), None), Some(1));
+        assert_eq!(part(Some(12), None), None);
+    }
+}
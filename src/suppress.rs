@@ -0,0 +1,265 @@
+/// What starts a suppression marker, found anywhere in a line whatever
+/// comment syntax it is in, or none: `mdlt:ignore-line` and
+/// `mdlt:ignore-file`.
+const MARKER: &str = "mdlt:ignore-";
+
+/// How many lines at the start of a file `mdlt:ignore-file` counts in.
+pub const FILE_LINES: usize = 5;
+
+/// How much of a line is kept from its first marker on, which is plenty
+/// for the check names after it.
+const MAX_KEPT: usize = 256;
+
+/// Which findings a marker silences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Scope {
+    /// Those on the line the marker is on.
+    Line,
+    /// All of them in the file.
+    File,
+}
+
+impl Scope {
+    /// The name JSON gives it.
+    #[cfg(feature = "cli")]
+    pub fn name(self) -> &'static str {
+        match self {
+            Scope::Line => "line",
+            Scope::File => "file",
+        }
+    }
+}
+
+/// A marker found on `line`, silencing the named `checks`, or every check
+/// when none are named.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Suppression {
+    pub scope: Scope,
+    pub line: usize,
+    pub checks: Vec<String>,
+}
+
+impl Suppression {
+    /// Whether it silences what `check` found on `line`, or in the whole
+    /// file when that is `None`; a line marker leaves file-wide findings
+    /// alone.
+    #[cfg(feature = "cli")]
+    pub fn covers(&self, check: &str, line: Option<usize>) -> bool {
+        let scoped = match self.scope {
+            Scope::File => true,
+            Scope::Line => line == Some(self.line),
+        };
+        scoped && (self.checks.is_empty() || self.checks.iter().any(|name| name == check))
+    }
+}
+
+/// Whether `c` can be part of a word, so that a marker right after or
+/// before it is not one.
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// The check names after a marker: a comma-separated list of words that
+/// start with a lowercase letter and go on in lowercase letters, digits and
+/// hyphens, after spaces or tabs. Whatever else follows, such as `-->` or
+/// `*/`, ends the list.
+fn names(text: &str) -> Vec<String> {
+    let mut rest = text.trim_start_matches([' ', '\t']);
+    let mut names = Vec::new();
+    while rest.starts_with(|c: char| c.is_ascii_lowercase()) {
+        let end = rest
+            .find(|c: char| !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-'))
+            .unwrap_or(rest.len());
+        names.push(rest[..end].to_string());
+        match rest[end..].strip_prefix(',') {
+            Some(more) => rest = more.trim_start_matches([' ', '\t']),
+            None => break,
+        }
+    }
+    names
+}
+
+/// The markers in `text`, which is line `line` from its first marker on.
+fn parse(text: &str, line: usize) -> Vec<Suppression> {
+    let mut found = Vec::new();
+    for (start, _) in text.match_indices(MARKER) {
+        if text[..start].ends_with(is_word) {
+            continue;
+        }
+        let after = &text[start + MARKER.len()..];
+        let (scope, rest) = if let Some(rest) = after.strip_prefix("line") {
+            (Scope::Line, rest)
+        } else if let Some(rest) = after.strip_prefix("file") {
+            (Scope::File, rest)
+        } else {
+            continue;
+        };
+        // `mdlt:ignore-lines` is some other word.
+        if rest.starts_with(|c: char| is_word(c) || c == '-') {
+            continue;
+        }
+        if scope == Scope::File && line > FILE_LINES {
+            continue;
+        }
+        found.push(Suppression {
+            scope,
+            line,
+            checks: names(rest),
+        });
+    }
+    found
+}
+
+/// Looks for markers as a line goes by, keeping nothing of it until one
+/// starts.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    /// How much of `MARKER` the line ends in so far.
+    matched: usize,
+    /// The last byte could be part of a word.
+    in_word: bool,
+    /// The line from its first marker on, once there is one.
+    kept: Option<Vec<u8>>,
+    found: Vec<Suppression>,
+}
+
+impl Tracker {
+    pub fn push(&mut self, bytes: &[u8]) {
+        let marker = MARKER.as_bytes();
+        for &byte in bytes {
+            if let Some(kept) = &mut self.kept {
+                if kept.len() < MAX_KEPT {
+                    kept.push(byte);
+                }
+                continue;
+            }
+            let boundary = !self.in_word;
+            self.in_word = is_word(char::from(byte));
+            if byte == marker[self.matched] && (self.matched > 0 || boundary) {
+                self.matched += 1;
+                if self.matched == marker.len() {
+                    self.kept = Some(marker.to_vec());
+                }
+            } else {
+                self.matched = usize::from(byte == marker[0] && boundary);
+            }
+        }
+    }
+
+    /// Takes the markers of the line that just ended, which is numbered
+    /// `line`.
+    pub fn end_line(&mut self, line: usize) {
+        if let Some(kept) = self.kept.take() {
+            self.found
+                .extend(parse(&String::from_utf8_lossy(&kept), line));
+        }
+        self.matched = 0;
+        self.in_word = false;
+    }
+
+    /// Takes the tracker of the text right after this one's, which starts
+    /// on line `lines + 1`, as if this one had gone on through it. This
+    /// one's text must end with a line break. Its `mdlt:ignore-file`
+    /// markers only count if they are near enough the start of the file.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker, lines: usize) {
+        let found = std::mem::take(&mut self.found);
+        *self = next;
+        let later = std::mem::replace(&mut self.found, found);
+        self.found.extend(
+            later
+                .into_iter()
+                .map(|suppression| Suppression {
+                    line: suppression.line + lines,
+                    ..suppression
+                })
+                .filter(|suppression| {
+                    suppression.scope == Scope::Line || suppression.line <= FILE_LINES
+                }),
+        );
+    }
+
+    pub fn finish(self) -> Vec<Suppression> {
+        self.found
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    fn markers(lines: &[&str]) -> Vec<Suppression> {
+        let mut tracker = Tracker::default();
+        for (index, line) in lines.iter().enumerate() {
+            // In two pieces, as a line may come in.
+            let (a, b) = line.split_at(line.len() / 2);
+            tracker.push(a.as_bytes());
+            tracker.push(b.as_bytes());
+            tracker.end_line(index + 1);
+        }
+        tracker.finish()
+    }
+
+    fn suppression(scope: Scope, line: usize, checks: &[&str]) -> Suppression {
+        Suppression {
+            scope,
+            line,
+            checks: checks.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_markers() {
+        assert_eq!(
+            markers(&[
+                "<!-- mdlt:ignore-file trailing-whitespace,final-newline -->",
+                "url = \"https://example.com/a/long/path\"  # mdlt:ignore-line line-length",
+                "/* mdlt:ignore-line */ a\r",
+                "-- mdlt:ignore-line\tbom, line-length",
+            ]),
+            [
+                suppression(Scope::File, 1, &["trailing-whitespace", "final-newline"]),
+                suppression(Scope::Line, 2, &["line-length"]),
+                suppression(Scope::Line, 3, &[]),
+                suppression(Scope::Line, 4, &["bom", "line-length"]),
+            ]
+        );
+        let mut lines = vec!["x"; FILE_LINES];
+        lines.push("# mdlt:ignore-file");
+        assert_eq!(markers(&lines), []);
+        // A marker right at the end of a long line still counts.
+        let long = format!("{} // mdlt:ignore-line", "a".repeat(2 * MAX_KEPT));
+        assert_eq!(markers(&[&long]), [suppression(Scope::Line, 1, &[])]);
+    }
+
+    #[test]
+    fn test_lookalikes() {
+        // A marker glued to a word, or a word that only starts like one,
+        // silences nothing.
+        assert_eq!(
+            markers(&[
+                "xmdlt:ignore-line",
+                "mdlt:ignore-lines",
+                "mdlt:ignore-line-length",
+                "mdlt:ignore-everything",
+                "mmdlt:ignore-file",
+                "mdlt:ignore",
+            ]),
+            []
+        );
+        let found = markers(&["mdlt:mdlt:ignore-line", "\"mdlt:ignore-line\""]);
+        assert_eq!(found.len(), 2);
+        let a = &found[0];
+        assert!(a.covers("bom", Some(1)) && !a.covers("bom", Some(2)));
+        assert!(!a.covers("bom", None));
+        let file = suppression(Scope::File, 1, &["bom"]);
+        assert!(file.covers("bom", None) && file.covers("bom", Some(9)));
+        assert!(!file.covers("line-length", Some(1)));
+    }
+}
@@ -0,0 +1,265 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "cli")]
+use std::io::{self, Write};
+
+#[cfg(feature = "cli")]
+use crate::figures::grouped;
+#[cfg(feature = "cli")]
+use crate::format::{escape_name, json};
+
+thread_local! {
+    /// The time the file this thread is analyzing has spent reading so
+    /// far, while a [`Clock`] times it, and `None` otherwise.
+    static READING: Cell<Option<Duration>> = const { Cell::new(None) };
+}
+
+/// Runs `read`, counting the time it takes toward the file being timed on
+/// this thread. Without `--timing` nothing is timed and it only calls
+/// `read`.
+pub fn reading<T>(read: impl FnOnce() -> T) -> T {
+    if READING.get().is_none() {
+        return read();
+    }
+    let started = Instant::now();
+    let result = read();
+    let took = started.elapsed();
+    READING.set(READING.get().map(|reading| reading + took));
+    result
+}
+
+/// Times one file on the thread that analyzes it, from `start` to `stop`.
+#[cfg(feature = "cli")]
+pub struct Clock {
+    started: Instant,
+}
+
+#[cfg(feature = "cli")]
+impl Clock {
+    pub fn start() -> Clock {
+        READING.set(Some(Duration::ZERO));
+        Clock {
+            started: Instant::now(),
+        }
+    }
+
+    pub fn stop(self) -> FileTiming {
+        let took = self.started.elapsed();
+        let read = READING.take().unwrap_or_default();
+        FileTiming {
+            read,
+            analyze: took.saturating_sub(read),
+        }
+    }
+}
+
+/// How long a file took: reading it, including decompressing and fetching
+/// it, and everything else, which is mostly analyzing it. A memory-mapped
+/// file is read as it is analyzed, so all of its time is analyzing.
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct FileTiming {
+    pub read: Duration,
+    pub analyze: Duration,
+}
+
+#[cfg(feature = "cli")]
+impl FileTiming {
+    fn total(&self) -> Duration {
+        self.read + self.analyze
+    }
+}
+
+/// How many of the slowest files the stderr block names.
+#[cfg(feature = "cli")]
+const SLOWEST: usize = 5;
+
+/// What `--timing` tells of a run: each file's timing, by the name it is
+/// reported under, and the figures of the whole run.
+#[cfg(feature = "cli")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Timing {
+    pub files: Vec<(String, FileTiming)>,
+    /// From the start of the analysis to the last file done.
+    pub wall: Duration,
+    pub bytes: u64,
+    /// The threads the files were shared between.
+    pub jobs: usize,
+}
+
+#[cfg(feature = "cli")]
+impl Timing {
+    /// The time of every file together, which is the time the threads were
+    /// busy.
+    fn cpu(&self) -> Duration {
+        self.files.iter().map(|(_, timing)| timing.total()).sum()
+    }
+
+    /// Megabytes (of a million bytes) a second, 0 for a run too quick to
+    /// time.
+    fn throughput(&self) -> f64 {
+        match self.wall.as_secs_f64() {
+            0.0 => 0.0,
+            wall => self.bytes as f64 / 1e6 / wall,
+        }
+    }
+
+    fn speedup(&self) -> f64 {
+        match self.wall.as_secs_f64() {
+            0.0 => 0.0,
+            wall => self.cpu().as_secs_f64() / wall,
+        }
+    }
+
+    fn reading(&self) -> Duration {
+        self.files.iter().map(|(_, timing)| timing.read).sum()
+    }
+
+    fn analyzing(&self) -> Duration {
+        self.files.iter().map(|(_, timing)| timing.analyze).sum()
+    }
+
+    /// The block printed on stderr once the run is done.
+    pub fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        writeln!(writer, "Timing\n======")?;
+        writeln!(writer, "Wall clock: {:.3}s", self.wall.as_secs_f64())?;
+        writeln!(
+            writer,
+            "Processed: {} bytes, {:.2} MB/s",
+            grouped(self.bytes),
+            self.throughput()
+        )?;
+        writeln!(
+            writer,
+            "Reading: {:.3}s, analyzing: {:.3}s",
+            self.reading().as_secs_f64(),
+            self.analyzing().as_secs_f64()
+        )?;
+        if self.jobs > 1 {
+            writeln!(
+                writer,
+                "Jobs: {}, CPU time {:.3}s, speedup {:.2}x",
+                self.jobs,
+                self.cpu().as_secs_f64(),
+                self.speedup()
+            )?;
+        }
+        let mut slowest: Vec<&(String, FileTiming)> = self.files.iter().collect();
+        slowest.sort_by_key(|(_, timing)| std::cmp::Reverse(timing.total()));
+        slowest.truncate(SLOWEST);
+        if !slowest.is_empty() {
+            writeln!(writer, "Slowest files:")?;
+        }
+        for (name, timing) in slowest {
+            writeln!(
+                writer,
+                "  {}: {:.3}s reading, {:.3}s analyzing",
+                escape_name(name),
+                timing.read.as_secs_f64(),
+                timing.analyze.as_secs_f64()
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The `timing` object of the JSON summary, with `cpu_seconds` and
+    /// `speedup` only for a run on several threads.
+    pub fn json(&self) -> String {
+        let seconds = |duration: Duration| format!("{:.6}", duration.as_secs_f64());
+        let mut fields = vec![
+            format!("\"wall_seconds\": {}", seconds(self.wall)),
+            format!("\"bytes\": {}", self.bytes),
+            format!("\"mb_per_second\": {:.3}", self.throughput()),
+            format!("\"read_seconds\": {}", seconds(self.reading())),
+            format!("\"analyze_seconds\": {}", seconds(self.analyzing())),
+            format!("\"jobs\": {}", self.jobs),
+        ];
+        if self.jobs > 1 {
+            fields.push(format!("\"cpu_seconds\": {}", seconds(self.cpu())));
+            fields.push(format!("\"speedup\": {:.3}", self.speedup()));
+        }
+        let files: Vec<String> = self
+            .files
+            .iter()
+            .map(|(name, timing)| {
+                format!(
+                    "{{\"file\": {}, \"read_seconds\": {}, \"analyze_seconds\": {}}}",
+                    json::string(name),
+                    seconds(timing.read),
+                    seconds(timing.analyze)
+                )
+            })
+            .collect();
+        fields.push(format!("\"files\": [{}]", files.join(", ")));
+        format!("{{{}}}", fields.join(", "))
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clock() {
+        assert_eq!(reading(|| 1), 1);
+        let clock = Clock::start();
+        reading(|| std::thread::sleep(Duration::from_millis(2)));
+        let timing = clock.stop();
+        assert!(timing.read >= Duration::from_millis(2));
+        // Once stopped, reading counts toward nothing.
+        reading(|| std::thread::sleep(Duration::from_millis(1)));
+        assert_eq!(READING.get(), None);
+    }
+
+    #[test]
+    fn test_write_and_json() {
+        let ms = Duration::from_millis;
+        let timing = Timing {
+            files: vec![
+                (
+                    "a.txt".to_string(),
+                    FileTiming {
+                        read: ms(100),
+                        analyze: ms(300),
+                    },
+                ),
+                (
+                    "b\tc.txt".to_string(),
+                    FileTiming {
+                        read: ms(200),
+                        analyze: ms(400),
+                    },
+                ),
+            ],
+            wall: ms(500),
+            bytes: 2_000_000,
+            jobs: 2,
+        };
+        let mut written = Vec::new();
+        timing.write(&mut written).unwrap();
+        assert_eq!(
+            String::from_utf8(written).unwrap(),
+            "Timing\n======\nWall clock: 0.500s\nProcessed: 2,000,000 bytes, 4.00 MB/s\n\
+             Reading: 0.300s, analyzing: 0.700s\nJobs: 2, CPU time 1.000s, speedup 2.00x\n\
+             Slowest files:\n  b\\tc.txt: 0.200s reading, 0.400s analyzing\n  \
+             a.txt: 0.100s reading, 0.300s analyzing\n"
+        );
+        assert!(timing.json().starts_with(
+            "{\"wall_seconds\": 0.500000, \"bytes\": 2000000, \"mb_per_second\": 4.000, \
+             \"read_seconds\": 0.300000, \"analyze_seconds\": 0.700000, \"jobs\": 2, \
+             \"cpu_seconds\": 1.000000, \"speedup\": 2.000, \"files\": [{\"file\": \"a.txt\", "
+        ));
+        let serial = Timing {
+            jobs: 1,
+            files: Vec::new(),
+            wall: Duration::ZERO,
+            ..timing
+        };
+        assert_eq!(
+            serial.json(),
+            "{\"wall_seconds\": 0.000000, \"bytes\": 2000000, \"mb_per_second\": 0.000, \
+             \"read_seconds\": 0.000000, \"analyze_seconds\": 0.000000, \"jobs\": 1, \"files\": []}"
+        );
+    }
+}
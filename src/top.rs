@@ -0,0 +1,217 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::io::{self, Write};
+
+use crate::preview;
+
+/// The `--top-lines` settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TopLines {
+    /// How many of the longest lines are kept; never zero.
+    pub count: usize,
+    /// Give their length in columns too, which `--tab-width` asks for.
+    pub columns: bool,
+}
+
+/// One of the longest lines.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TopLine {
+    pub line: usize,
+    /// In bytes, without the terminator.
+    pub length: usize,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub columns: Option<usize>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "String::is_empty")
+    )]
+    pub preview: String,
+}
+
+/// A line as the heap orders it: longer first, and of two equally long
+/// lines the earlier one.
+#[derive(Debug, PartialEq, Eq)]
+struct Ranked(TopLine);
+
+impl Ranked {
+    fn key(&self) -> (usize, Reverse<usize>) {
+        (self.0.length, Reverse(self.0.line))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Keeps the longest lines seen so far in a heap whose top is the one to
+/// go first, so that memory stays fixed at `count` lines however long the
+/// file is, and only a line that makes it in has its preview rendered.
+#[derive(Debug)]
+pub struct Tracker {
+    settings: TopLines,
+    heap: BinaryHeap<Reverse<Ranked>>,
+}
+
+impl Tracker {
+    pub fn new(settings: TopLines) -> Self {
+        Tracker {
+            settings,
+            heap: BinaryHeap::with_capacity(settings.count + 1),
+        }
+    }
+
+    /// Considers line `line`, `length` bytes and `columns` columns long,
+    /// which starts with `head`, all of it when `complete`.
+    pub fn end_line(
+        &mut self,
+        line: usize,
+        length: usize,
+        columns: usize,
+        head: &[u8],
+        complete: bool,
+    ) {
+        if self.heap.len() == self.settings.count {
+            // Lines come in order, so a tie with the shortest kept loses.
+            let Some(Reverse(shortest)) = self.heap.peek() else {
+                return;
+            };
+            if length <= shortest.0.length {
+                return;
+            }
+            self.heap.pop();
+        }
+        self.heap.push(Reverse(Ranked(TopLine {
+            line,
+            length,
+            columns: self.settings.columns.then_some(columns),
+            preview: preview::render(head, complete),
+        })));
+    }
+
+    /// Takes the tracker of the text right after this one's, which starts
+    /// on line `lines + 1`, as if this one had gone on through it. Each of
+    /// its lines is offered in order, as `end_line` would have been: a line
+    /// it did not keep is one of those that keep it from the top anyway.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker, lines: usize) {
+        let mut later = next.finish();
+        later.sort_by_key(|line| line.line);
+        for line in later {
+            if self.heap.len() == self.settings.count {
+                let Some(Reverse(shortest)) = self.heap.peek() else {
+                    return;
+                };
+                if line.length <= shortest.0.length {
+                    continue;
+                }
+                self.heap.pop();
+            }
+            self.heap.push(Reverse(Ranked(TopLine {
+                line: line.line + lines,
+                ..line
+            })));
+        }
+    }
+
+    /// The lines kept, longest first.
+    pub fn finish(self) -> Vec<TopLine> {
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(Ranked(line))| line)
+            .collect()
+    }
+}
+
+/// Writes the `--top-lines` table of the text report, a row such as
+/// "  1. line 17  312 bytes  abc…" for each line, with the columns after
+/// the bytes when they were asked for.
+pub fn write(lines: &[TopLine], writer: &mut impl Write) -> io::Result<()> {
+    writeln!(
+        writer,
+        "Longest {} line{}:",
+        lines.len(),
+        if lines.len() == 1 { "" } else { "s" }
+    )?;
+    let width = |numbers: &mut dyn Iterator<Item = usize>| {
+        numbers.map(|n| n.to_string().len()).max().unwrap_or(0)
+    };
+    let rank = lines.len().to_string().len();
+    let line = width(&mut lines.iter().map(|top| top.line));
+    let length = width(&mut lines.iter().map(|top| top.length));
+    let columns = width(&mut lines.iter().filter_map(|top| top.columns));
+    let plural = |count: usize| if count == 1 { " " } else { "s" };
+    for (index, top) in lines.iter().enumerate() {
+        write!(
+            writer,
+            "  {:>rank$}. line {:<line$}  {:>length$} byte{}",
+            index + 1,
+            top.line,
+            top.length,
+            plural(top.length)
+        )?;
+        if let Some(count) = top.columns {
+            write!(writer, "  {:>columns$} column{}", count, plural(count))?;
+        }
+        writeln!(writer, "  {}", top.preview)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn top(count: usize, lines: &[&str]) -> Vec<(usize, usize)> {
+        let mut tracker = Tracker::new(TopLines {
+            count,
+            columns: false,
+        });
+        for (index, line) in lines.iter().enumerate() {
+            tracker.end_line(index + 1, line.len(), line.len(), line.as_bytes(), true);
+        }
+        tracker
+            .finish()
+            .into_iter()
+            .map(|top| (top.line, top.length))
+            .collect()
+    }
+
+    #[test]
+    fn test_longest_lines() {
+        let lines = ["ab", "abcd", "a", "abcd", "abc", "", "abcde"];
+        assert_eq!(top(3, &lines), [(7, 5), (2, 4), (4, 4)]);
+        // Ties go to the earlier line.
+        assert_eq!(top(1, &["xx", "yy", "zz"]), [(1, 2)]);
+        assert_eq!(top(5, &["a", "bb"]), [(2, 2), (1, 1)]);
+        assert_eq!(top(2, &[]), []);
+    }
+
+    #[test]
+    fn test_write() {
+        let mut tracker = Tracker::new(TopLines {
+            count: 2,
+            columns: true,
+        });
+        tracker.end_line(3, 9, 16, b"\tx\ty", true);
+        tracker.end_line(12, 10, 10, b"0123456789", true);
+        let mut output = Vec::new();
+        write(&tracker.finish(), &mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "Longest 2 lines:\n  1. line 12  10 bytes  10 columns  0123456789\n  2. line 3    9 bytes  16 columns  \\tx\\ty\n"
+        );
+    }
+}
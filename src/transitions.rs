@@ -0,0 +1,237 @@
+use std::io::{self, Write};
+
+use crate::figures;
+
+/// How many segments the text report lists before summing up the rest.
+pub const MAX_LISTED: usize = 10;
+
+/// The terminator that ends a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "UPPERCASE")
+)]
+pub enum Ending {
+    Crlf,
+    Lf,
+    Cr,
+    /// Any of the `--unicode-linebreaks` breaks.
+    #[cfg_attr(feature = "serde", serde(rename = "Unicode"))]
+    Unicode,
+}
+
+impl Ending {
+    pub fn name(self) -> &'static str {
+        match self {
+            Ending::Crlf => "CRLF",
+            Ending::Lf => "LF",
+            Ending::Cr => "CR",
+            Ending::Unicode => "Unicode",
+        }
+    }
+}
+
+/// Consecutive lines that all end the same way.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Segment {
+    pub start_line: usize,
+    pub end_line: usize,
+    pub ending: Ending,
+}
+
+impl Segment {
+    fn range(&self) -> String {
+        range(self.start_line, self.end_line)
+    }
+}
+
+/// "41" or "1-40".
+fn range(start: usize, end: usize) -> String {
+    match start == end {
+        true => start.to_string(),
+        false => format!("{}-{}", start, end),
+    }
+}
+
+/// Line numbers in increasing order with runs of consecutive lines
+/// written as one range, e.g. "3, 17-19, 240".
+pub fn ranges(lines: &[usize]) -> String {
+    let mut runs: Vec<String> = Vec::new();
+    let mut index = 0;
+    while index < lines.len() {
+        let start = lines[index];
+        let mut end = start;
+        while lines.get(index + 1) == Some(&(end + 1)) {
+            end += 1;
+            index += 1;
+        }
+        runs.push(range(start, end));
+        index += 1;
+    }
+    runs.join(", ")
+}
+
+/// Writes the `--transitions` section of the text report: the first
+/// `MAX_LISTED` segments grouped by ending, in the order the endings first
+/// appear, e.g. "  LF: lines 1-40, 42-100" and "  CRLF: line 41".
+/// Its counts are grouped when `group`.
+pub fn write(segments: &[Segment], group: bool, writer: &mut impl Write) -> io::Result<()> {
+    let count = |n: usize| figures::count(n as u64, group);
+    let transitions = segments.len().saturating_sub(1);
+    writeln!(writer, "Line ending transitions: {}", count(transitions))?;
+    let listed = &segments[..segments.len().min(MAX_LISTED)];
+    let mut endings: Vec<Ending> = Vec::new();
+    for segment in listed {
+        if !endings.contains(&segment.ending) {
+            endings.push(segment.ending);
+        }
+    }
+    for ending in endings {
+        let ranges: Vec<&Segment> = listed.iter().filter(|s| s.ending == ending).collect();
+        let noun = match ranges.as_slice() {
+            [only] if only.start_line == only.end_line => "line",
+            _ => "lines",
+        };
+        let ranges: Vec<String> = ranges.iter().map(|segment| segment.range()).collect();
+        writeln!(
+            writer,
+            "  {}: {} {}",
+            ending.name(),
+            noun,
+            ranges.join(", ")
+        )?;
+    }
+    if segments.len() > MAX_LISTED {
+        writeln!(
+            writer,
+            "  and {} more transitions",
+            count(segments.len() - MAX_LISTED)
+        )?;
+    }
+    Ok(())
+}
+
+/// Builds the segments as lines end, one comparison per line. A last line
+/// without a terminator belongs to no segment.
+#[derive(Debug, Default)]
+pub struct Tracker {
+    segments: Vec<Segment>,
+}
+
+impl Tracker {
+    /// Records that line `number` ended with `ending`.
+    pub fn end_line(&mut self, number: usize, ending: Ending) {
+        match self.segments.last_mut() {
+            Some(last) if last.ending == ending => last.end_line = number,
+            _ => self.segments.push(Segment {
+                start_line: number,
+                end_line: number,
+                ending,
+            }),
+        }
+    }
+
+    /// Takes the tracker of the text right after this one's, which starts
+    /// on line `lines + 1`, as if this one had gone on through it.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Tracker, lines: usize) {
+        for segment in next.segments {
+            self.end_line(segment.start_line + lines, segment.ending);
+            self.end_line(segment.end_line + lines, segment.ending);
+        }
+    }
+
+    pub fn finish(self) -> Vec<Segment> {
+        self.segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(endings: &[Ending]) -> Vec<Segment> {
+        let mut tracker = Tracker::default();
+        for (index, &ending) in endings.iter().enumerate() {
+            tracker.end_line(index + 1, ending);
+        }
+        tracker.finish()
+    }
+
+    fn section(segments: &[Segment]) -> String {
+        let mut buffer = Vec::new();
+        write(segments, true, &mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_many_transitions_are_grouped() {
+        let endings: Vec<Ending> = (0..1001)
+            .map(|line| [Ending::Lf, Ending::Crlf][line % 2])
+            .collect();
+        let section = section(&track(&endings));
+        assert!(section.starts_with("Line ending transitions: 1,000\n"));
+        assert!(section.ends_with(&format!(
+            "  and {} more transitions\n",
+            figures::grouped(1001 - MAX_LISTED as u64)
+        )));
+    }
+
+    #[test]
+    fn test_segments() {
+        let mut endings = vec![Ending::Lf; 40];
+        endings.push(Ending::Crlf);
+        endings.extend([Ending::Lf; 59]);
+        let segments = track(&endings);
+        assert_eq!(
+            segments,
+            [
+                Segment {
+                    start_line: 1,
+                    end_line: 40,
+                    ending: Ending::Lf
+                },
+                Segment {
+                    start_line: 41,
+                    end_line: 41,
+                    ending: Ending::Crlf
+                },
+                Segment {
+                    start_line: 42,
+                    end_line: 100,
+                    ending: Ending::Lf
+                },
+            ]
+        );
+        assert_eq!(
+            section(&segments),
+            "Line ending transitions: 2\n  LF: lines 1-40, 42-100\n  CRLF: line 41\n"
+        );
+        assert_eq!(section(&[]), "Line ending transitions: 0\n");
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert_eq!(ranges(&[3, 17, 18, 19, 240]), "3, 17-19, 240");
+        assert_eq!(ranges(&[1, 2]), "1-2");
+        assert_eq!(ranges(&[5]), "5");
+        assert_eq!(ranges(&[]), "");
+    }
+
+    #[test]
+    fn test_listing_is_capped() {
+        let endings: Vec<Ending> = (0..14)
+            .map(|line| match line % 2 {
+                0 => Ending::Crlf,
+                _ => Ending::Cr,
+            })
+            .collect();
+        assert_eq!(
+            section(&track(&endings)),
+            "Line ending transitions: 13\n  CRLF: lines 1, 3, 5, 7, 9\n  \
+             CR: lines 2, 4, 6, 8, 10\n  and 4 more transitions\n"
+        );
+    }
+}
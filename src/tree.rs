@@ -0,0 +1,250 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::path::{Component, Path};
+
+use crate::aggregate::AggregateStats;
+use crate::format::table::width;
+use crate::{FileStats, SkippedFile};
+
+/// The `--tree` settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Settings {
+    /// `--tree-depth`: how many levels of directories are shown below the
+    /// top, the deeper ones counting toward the last one shown.
+    pub depth: Option<usize>,
+    /// `--ascii`: draw the branches with `|`, `` ` `` and `-`.
+    pub ascii: bool,
+    /// `--collapse-clean`: show a directory without problem files anywhere
+    /// in it as one line, without what is under it.
+    pub collapse_clean: bool,
+}
+
+/// A directory with the totals of everything under it.
+#[derive(Debug, Default)]
+struct Dir {
+    totals: AggregateStats,
+    problem_files: u64,
+    dirs: BTreeMap<String, Dir>,
+}
+
+/// The directories of a run, from `.` down, each with the totals of the
+/// files anywhere under it.
+#[derive(Debug, Default)]
+pub struct Tree {
+    root: Dir,
+}
+
+/// The directories `name` is in, outermost first.
+fn dirs(name: &str) -> Vec<String> {
+    let mut parts: Vec<String> = Path::new(name)
+        .components()
+        .filter_map(|component| match component {
+            Component::Normal(part) => Some(part.to_string_lossy().into_owned()),
+            Component::ParentDir => Some("..".to_string()),
+            Component::RootDir => Some(String::new()),
+            Component::Prefix(prefix) => Some(prefix.as_os_str().to_string_lossy().into_owned()),
+            Component::CurDir => None,
+        })
+        .collect();
+    parts.pop();
+    parts
+}
+
+impl Tree {
+    /// The tree of `stats` and `skipped`, where `problem` tells the files
+    /// with problems that mark their directories.
+    pub fn new(
+        stats: &[FileStats],
+        skipped: &[SkippedFile],
+        problem: impl Fn(&FileStats) -> bool,
+    ) -> Tree {
+        let mut tree = Tree::default();
+        for file in stats {
+            let problem = problem(file);
+            tree.each_dir(&file.name(), |dir| {
+                dir.totals.add(file);
+                dir.problem_files += u64::from(problem);
+            });
+        }
+        for file in skipped {
+            tree.each_dir(&file.file_name, |dir| dir.totals.add_skipped(file));
+        }
+        tree
+    }
+
+    /// Calls `f` on `.` and every directory down to the one `name` is in.
+    fn each_dir(&mut self, name: &str, mut f: impl FnMut(&mut Dir)) {
+        let mut dir = &mut self.root;
+        f(dir);
+        for part in dirs(name) {
+            dir = dir.dirs.entry(part).or_default();
+            f(dir);
+        }
+    }
+
+    /// Writes the tree, one line per directory such as
+    /// "├── src/   12 files: 11 LF, 1 CRLF  [1 problem file]", with the
+    /// counts lined up after the names.
+    pub fn write(&self, settings: Settings, mut writer: impl Write) -> io::Result<()> {
+        let mut rows = vec![(".".to_string(), &self.root)];
+        rows_under(&self.root, settings, 0, "", &mut rows);
+        let name_width = rows.iter().map(|(name, _)| width(name)).max().unwrap_or(0);
+        for (name, dir) in rows {
+            let padding = " ".repeat(name_width - width(&name));
+            write!(writer, "{}{}  {}", name, padding, counts(&dir.totals))?;
+            match dir.problem_files {
+                0 => writeln!(writer)?,
+                1 => writeln!(writer, "  [1 problem file]")?,
+                count => writeln!(writer, "  [{} problem files]", count)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Adds the rows of the directories in `dir`, which is `depth` levels
+/// down, each name after the branches drawn to it.
+fn rows_under<'a>(
+    dir: &'a Dir,
+    settings: Settings,
+    depth: usize,
+    indent: &str,
+    rows: &mut Vec<(String, &'a Dir)>,
+) {
+    if settings.depth.is_some_and(|limit| depth >= limit)
+        || (settings.collapse_clean && dir.problem_files == 0)
+    {
+        return;
+    }
+    let (branch, last_branch, trunk) = match settings.ascii {
+        true => ("|-- ", "`-- ", "|   "),
+        false => ("├── ", "└── ", "│   "),
+    };
+    for (index, (name, child)) in dir.dirs.iter().enumerate() {
+        let last = index + 1 == dir.dirs.len();
+        let name = if name.is_empty() { "/" } else { name.as_str() };
+        let slash = if name.ends_with('/') { "" } else { "/" };
+        rows.push((
+            format!(
+                "{}{}{}{}",
+                indent,
+                if last { last_branch } else { branch },
+                name,
+                slash
+            ),
+            child,
+        ));
+        let indent = format!("{}{}", indent, if last { "    " } else { trunk });
+        rows_under(child, settings, depth + 1, &indent, rows);
+    }
+}
+
+/// "12 files: 11 LF, 1 CRLF", leaving out the endings no file has.
+fn counts(totals: &AggregateStats) -> String {
+    let files = format!(
+        "{} file{}",
+        totals.files,
+        if totals.files == 1 { "" } else { "s" }
+    );
+    let endings: Vec<String> = [
+        (totals.lf_files, "LF"),
+        (totals.crlf_files, "CRLF"),
+        (totals.cr_files, "CR"),
+        (totals.mixed_files, "mixed"),
+    ]
+    .iter()
+    .filter(|&&(count, _)| count > 0)
+    .map(|(count, name)| format!("{} {}", count, name))
+    .collect();
+    match endings.is_empty() {
+        true => files,
+        false => format!("{}: {}", files, endings.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(name: &str, [dos, unix]: [usize; 2]) -> FileStats {
+        let mut stats = FileStats::new(name.to_string());
+        stats.dos_endings = dos;
+        stats.unix_endings = unix;
+        stats.total_lines = dos + unix;
+        stats
+    }
+
+    fn tree() -> Tree {
+        let files = [
+            file("README.md", [0, 3]),
+            file("src/main.rs", [0, 9]),
+            file("src/format/json.rs", [0, 4]),
+            file("src/format/csv.rs", [2, 0]),
+            file("docs/日本語/index.md", [1, 1]),
+            file("./tests/cli.rs", [0, 2]),
+        ];
+        Tree::new(&files, &[], |file| file.dos_endings > 0)
+    }
+
+    fn written(tree: &Tree, settings: Settings) -> String {
+        let mut output = Vec::new();
+        tree.write(settings, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_nesting() {
+        assert_eq!(
+            written(&tree(), Settings::default()),
+            concat!(
+                ".                6 files: 4 LF, 1 CRLF, 1 mixed  [2 problem files]\n",
+                "├── docs/        1 file: 1 mixed  [1 problem file]\n",
+                "│   └── 日本語/  1 file: 1 mixed  [1 problem file]\n",
+                "├── src/         3 files: 2 LF, 1 CRLF  [1 problem file]\n",
+                "│   └── format/  2 files: 1 LF, 1 CRLF  [1 problem file]\n",
+                "└── tests/       1 file: 1 LF\n",
+            )
+        );
+        let ascii = Settings {
+            ascii: true,
+            depth: Some(1),
+            ..Settings::default()
+        };
+        assert_eq!(
+            written(&tree(), ascii),
+            concat!(
+                ".           6 files: 4 LF, 1 CRLF, 1 mixed  [2 problem files]\n",
+                "|-- docs/   1 file: 1 mixed  [1 problem file]\n",
+                "|-- src/    3 files: 2 LF, 1 CRLF  [1 problem file]\n",
+                "`-- tests/  1 file: 1 LF\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_collapse_clean() {
+        let files = [
+            file("a/b/c/x.txt", [0, 1]),
+            file("a/b/y.txt", [0, 1]),
+            file("a/d/z.txt", [1, 0]),
+            file("e/f/w.txt", [0, 1]),
+        ];
+        let tree = Tree::new(&files, &[], |file| file.dos_endings > 0);
+        let settings = Settings {
+            collapse_clean: true,
+            ..Settings::default()
+        };
+        assert_eq!(
+            written(&tree, settings),
+            concat!(
+                ".           4 files: 3 LF, 1 CRLF  [1 problem file]\n",
+                "├── a/      3 files: 2 LF, 1 CRLF  [1 problem file]\n",
+                "│   ├── b/  2 files: 2 LF\n",
+                "│   └── d/  1 file: 1 CRLF  [1 problem file]\n",
+                "└── e/      1 file: 1 LF\n",
+            )
+        );
+        let clean = Tree::new(&files[..1], &[], |_| false);
+        assert_eq!(written(&clean, settings), ".  1 file: 1 LF\n");
+    }
+}
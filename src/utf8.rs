@@ -0,0 +1,184 @@
+/// How many invalid sequences have their position recorded.
+pub const MAX_RECORDED: usize = 10;
+
+/// Where an invalid UTF-8 sequence starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InvalidSequence {
+    /// 1-based line number.
+    pub line: usize,
+    /// Byte offset from the start of the file.
+    pub offset: u64,
+}
+
+/// Result of `--check-encoding`.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Utf8Report {
+    pub invalid: usize,
+    /// The first [`MAX_RECORDED`] invalid sequences.
+    pub first: Vec<InvalidSequence>,
+}
+
+/// Incremental UTF-8 validator. Invalid input is counted the way
+/// `String::from_utf8_lossy` would replace it: each maximal prefix of a
+/// valid sequence that is cut short, and each byte that cannot start a
+/// sequence, is one invalid sequence.
+#[derive(Debug)]
+pub struct Validator {
+    report: Utf8Report,
+    offset: u64,
+    /// Continuation bytes still expected for the current sequence.
+    needed: u8,
+    /// Allowed range for the next continuation byte, which is narrower than
+    /// `80..=BF` right after some lead bytes to reject overlong encodings,
+    /// surrogates and code points above U+10FFFF.
+    lower: u8,
+    upper: u8,
+    start: InvalidSequence,
+}
+
+impl Validator {
+    /// Starts validating at byte `offset`, e.g. just after a byte-order mark.
+    pub fn new(offset: u64) -> Self {
+        Validator {
+            report: Utf8Report::default(),
+            offset,
+            needed: 0,
+            lower: 0x80,
+            upper: 0xbf,
+            start: InvalidSequence { line: 1, offset },
+        }
+    }
+
+    /// Checks the next byte, which is on line `line`.
+    pub fn byte(&mut self, byte: u8, line: usize) {
+        if self.needed > 0 {
+            if (self.lower..=self.upper).contains(&byte) {
+                self.needed -= 1;
+                self.lower = 0x80;
+                self.upper = 0xbf;
+                self.offset += 1;
+                return;
+            }
+            self.record(self.start);
+            self.needed = 0;
+            self.lower = 0x80;
+            self.upper = 0xbf;
+        }
+
+        let here = InvalidSequence {
+            line,
+            offset: self.offset,
+        };
+        self.offset += 1;
+        let Some((needed, lower, upper)) = lead(byte) else {
+            self.record(here);
+            return;
+        };
+        if needed == 0 {
+            return;
+        }
+        self.needed = needed;
+        self.lower = lower;
+        self.upper = upper;
+        self.start = here;
+    }
+
+    fn record(&mut self, at: InvalidSequence) {
+        self.report.invalid += 1;
+        if self.report.first.len() < MAX_RECORDED {
+            self.report.first.push(at);
+        }
+    }
+
+    /// Ends the input; a sequence still in progress is truncated.
+    pub fn finish(mut self) -> Utf8Report {
+        if self.needed > 0 {
+            self.record(self.start);
+        }
+        self.report
+    }
+}
+
+/// How many continuation bytes `byte` starts a sequence of, and the allowed
+/// range for the first of them, or `None` if `byte` cannot start a sequence.
+pub fn lead(byte: u8) -> Option<(u8, u8, u8)> {
+    Some(match byte {
+        0x00..=0x7f => (0, 0x80, 0xbf),
+        0xc2..=0xdf => (1, 0x80, 0xbf),
+        0xe0 => (2, 0xa0, 0xbf),
+        0xe1..=0xec | 0xee..=0xef => (2, 0x80, 0xbf),
+        0xed => (2, 0x80, 0x9f),
+        0xf0 => (3, 0x90, 0xbf),
+        0xf1..=0xf3 => (3, 0x80, 0xbf),
+        0xf4 => (3, 0x80, 0x8f),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(bytes: &[u8]) -> Utf8Report {
+        let mut validator = Validator::new(0);
+        let mut line = 1;
+        for &byte in bytes {
+            validator.byte(byte, line);
+            if byte == b'\n' {
+                line += 1;
+            }
+        }
+        validator.finish()
+    }
+
+    #[test]
+    fn test_valid_input() {
+        let text = "plain ascii\né ü ß — 日本語 🎉\n";
+        assert_eq!(validate(text.as_bytes()), Utf8Report::default());
+        assert_eq!(validate(b""), Utf8Report::default());
+    }
+
+    #[test]
+    fn test_counts_match_from_utf8_lossy() {
+        let samples: &[&[u8]] = &[
+            b"\xff",
+            b"a\x80b",
+            b"\xc3",
+            b"\xe2\x82",
+            b"\xe2\x82a",
+            b"\xf0\x9f\x8e",
+            b"\xc0\xaf",
+            b"\xe0\x80\x80",
+            b"\xed\xa0\x80",
+            b"\xf4\x90\x80\x80",
+            b"\xf8\x88\x80\x80\x80",
+            b"\xc3\xa9\xff\xe2\x82\xac\xe2",
+        ];
+        for sample in samples {
+            let expected = String::from_utf8_lossy(sample).matches('\u{fffd}').count();
+            assert_eq!(validate(sample).invalid, expected, "sample {:?}", sample);
+        }
+    }
+
+    #[test]
+    fn test_positions() {
+        let report = validate(b"ok\nab\xffc\n\xe2\x82\n");
+        assert_eq!(report.invalid, 2);
+        assert_eq!(
+            report.first,
+            vec![
+                InvalidSequence { line: 2, offset: 5 },
+                InvalidSequence { line: 3, offset: 8 },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_first_few_positions_are_kept() {
+        let report = validate(&[0xff; 25]);
+        assert_eq!(report.invalid, 25);
+        assert_eq!(report.first.len(), MAX_RECORDED);
+        assert_eq!(report.first[9].offset, 9);
+    }
+}
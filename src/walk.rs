@@ -0,0 +1,532 @@
+use std::fs;
+use std::path::Path;
+
+use crate::archive;
+use crate::filter::PathFilter;
+use crate::ignore::IgnoreStack;
+use crate::osname;
+use crate::sample::Sampler;
+
+#[derive(Debug, Clone)]
+pub struct WalkOptions {
+    /// Include entries whose name starts with a dot.
+    pub hidden: bool,
+    /// Skip entries matched by `.gitignore` files and `.git/info/exclude`.
+    pub ignore: bool,
+    /// `--include`/`--exclude` patterns, relative to the walk root.
+    pub filter: PathFilter,
+    /// Only report files at least this many levels below the root; files
+    /// directly inside the root are at depth 1.
+    pub min_depth: usize,
+    /// Never descend below this depth; 0 means the root itself only.
+    pub max_depth: Option<usize>,
+    /// Skip the directories in [`DEFAULT_PRUNE`] without reading them.
+    pub default_prune: bool,
+    /// Descend into symlinked directories and analyze symlinked files.
+    pub follow_symlinks: bool,
+    /// Analyze the files inside archives rather than the archives (`--archive`).
+    pub archives: bool,
+}
+
+impl Default for WalkOptions {
+    fn default() -> Self {
+        WalkOptions {
+            hidden: false,
+            ignore: true,
+            filter: PathFilter::default(),
+            min_depth: 0,
+            max_depth: None,
+            default_prune: true,
+            follow_symlinks: false,
+            archives: false,
+        }
+    }
+}
+
+impl WalkOptions {
+    /// Whether the filter lets the file at `relative` through. With
+    /// `--archive`, an archive gets through unless it is excluded, so that
+    /// `--include` picks from its members instead.
+    pub fn allows(&self, relative: &str) -> bool {
+        match self.archives && archive::Kind::of(relative).is_some() {
+            true => !self.filter.excludes(relative),
+            false => self.filter.allows(relative),
+        }
+    }
+}
+
+/// Files found under a directory, plus anything that went wrong along the way
+/// without being fatal to the walk.
+#[derive(Debug, Default)]
+pub struct Discovery {
+    pub files: Vec<String>,
+    pub warnings: Vec<String>,
+    /// Files and pruned directories left out because of
+    /// `--include`/`--exclude`.
+    pub filtered: usize,
+    /// Entries passed over on purpose, such as symlinks that are not
+    /// followed, for verbose output.
+    pub skipped: Vec<String>,
+    /// Where `--sample` has the files go instead of into `files`.
+    pub sample: Option<Sampler>,
+}
+
+/// Identifies a directory independently of the path used to reach it, so a
+/// symlink pointing back at an ancestor is recognized as a loop.
+#[derive(Debug, PartialEq, Eq)]
+enum DirId {
+    #[cfg(unix)]
+    Inode(u64, u64),
+    #[cfg(not(unix))]
+    Canonical(std::path::PathBuf),
+}
+
+#[cfg(unix)]
+fn dir_id(path: &Path) -> Option<DirId> {
+    use std::os::unix::fs::MetadataExt;
+    let metadata = fs::metadata(path).ok()?;
+    Some(DirId::Inode(metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path) -> Option<DirId> {
+    fs::canonicalize(osname::long(path))
+        .ok()
+        .map(DirId::Canonical)
+}
+
+/// Directories skipped by default because they are large and never worth
+/// analyzing.
+pub const DEFAULT_PRUNE: &[&str] = &[".git", ".hg", ".svn", "node_modules", "target"];
+
+fn is_hidden(name: &str) -> bool {
+    name.starts_with('.')
+}
+
+/// Walks `root` depth-first, visiting directory entries in sorted order so
+/// the result is the same on every run and platform. The `.git` directory is
+/// never entered.
+pub fn discover(root: &Path, options: &WalkOptions, discovery: &mut Discovery) {
+    let ignores = if options.ignore {
+        IgnoreStack::for_root(root)
+    } else {
+        IgnoreStack::default()
+    };
+    let mut walker = Walker {
+        root,
+        options,
+        ignores,
+        discovery,
+        ancestors: Vec::new(),
+    };
+    walker.walk_dir(root, 0);
+}
+
+/// `path` relative to the walk root, with `/` separators, for filter
+/// matching.
+fn relative_to(root: &Path, path: &Path) -> String {
+    osname::encode(path.strip_prefix(root).unwrap_or(path).as_os_str()).replace('\\', "/")
+}
+
+struct Walker<'a> {
+    root: &'a Path,
+    options: &'a WalkOptions,
+    ignores: IgnoreStack,
+    discovery: &'a mut Discovery,
+    /// Identities of the directories currently being walked, only tracked
+    /// when following symlinks.
+    ancestors: Vec<DirId>,
+}
+
+impl Walker<'_> {
+    fn warn(&mut self, warning: String) {
+        self.discovery.warnings.push(warning);
+    }
+
+    fn is_pruned(&self, name: &str) -> bool {
+        name == ".git" || (self.options.default_prune && DEFAULT_PRUNE.contains(&name))
+    }
+
+    /// Visits the entries of `dir`, which sits `depth` levels below the root.
+    fn walk_dir(&mut self, dir: &Path, depth: usize) {
+        if self.options.follow_symlinks {
+            if let Some(id) = dir_id(dir) {
+                if self.ancestors.contains(&id) {
+                    self.warn(format!(
+                        "Symlink loop detected at {}; not descending again",
+                        dir.display()
+                    ));
+                    return;
+                }
+                self.ancestors.push(id);
+                self.walk_entries(dir, depth);
+                self.ancestors.pop();
+                return;
+            }
+        }
+        self.walk_entries(dir, depth);
+    }
+
+    fn walk_entries(&mut self, dir: &Path, depth: usize) {
+        let entries = match fs::read_dir(osname::long(dir)) {
+            Ok(entries) => entries,
+            Err(e) => {
+                self.warn(format!("Cannot read directory {}: {}", dir.display(), e));
+                return;
+            }
+        };
+
+        let mut sorted = Vec::new();
+        for entry in entries {
+            match entry {
+                Ok(entry) => sorted.push(entry),
+                Err(e) => self.warn(format!("Cannot read entry in {}: {}", dir.display(), e)),
+            }
+        }
+        sorted.sort_by_key(|entry| entry.file_name());
+
+        let pushed = self.options.ignore && self.ignores.enter(dir);
+        for entry in sorted {
+            self.visit(dir, entry, depth + 1);
+        }
+        if pushed {
+            self.ignores.leave();
+        }
+    }
+
+    fn visit(&mut self, dir: &Path, entry: fs::DirEntry, depth: usize) {
+        let options = self.options;
+        // Not `entry.path()`, which has the `\\?\` of a long directory.
+        let path = dir.join(entry.file_name());
+        let name = entry.file_name();
+        let name = osname::encode(&name);
+        let name = &*name;
+        if !options.hidden && is_hidden(name) {
+            return;
+        }
+
+        let mut file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(e) => {
+                self.warn(format!("Cannot stat {}: {}", path.display(), e));
+                return;
+            }
+        };
+        if file_type.is_symlink() {
+            if !options.follow_symlinks {
+                self.discovery
+                    .skipped
+                    .push(format!("Skipping symlink {}", path.display()));
+                return;
+            }
+            file_type = match fs::metadata(osname::long(&path)) {
+                Ok(metadata) => metadata.file_type(),
+                Err(e) => {
+                    self.warn(format!("Broken symlink {}: {}", path.display(), e));
+                    return;
+                }
+            };
+        }
+        if file_type.is_dir() && self.is_pruned(name) {
+            return;
+        }
+        if options.ignore && self.ignores.is_ignored(&path, file_type.is_dir()) {
+            return;
+        }
+
+        let relative = relative_to(self.root, &path);
+        if file_type.is_dir() {
+            if options.filter.excludes(&relative) {
+                self.discovery.filtered += 1;
+            } else if options.max_depth.is_none_or(|max| depth < max) {
+                self.walk_dir(&path, depth);
+            }
+        } else if file_type.is_file() {
+            if depth < options.min_depth || options.max_depth.is_some_and(|max| depth > max) {
+                return;
+            }
+            if !options.allows(&relative) {
+                self.discovery.filtered += 1;
+                return;
+            }
+            let file = osname::encode(path.as_os_str()).into_owned();
+            match &mut self.discovery.sample {
+                Some(sampler) => sampler.offer(file),
+                None => self.discovery.files.push(file),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn fixture(name: &str, files: &[&str]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("mdlt_walk_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        for file in files {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x\n").unwrap();
+        }
+        root
+    }
+
+    fn relative(root: &Path, discovery: &Discovery) -> Vec<String> {
+        discovery
+            .files
+            .iter()
+            .map(|file| {
+                Path::new(file)
+                    .strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_discover_sorted_depth_first() {
+        let root = fixture(
+            "sorted",
+            &["b.txt", "a/2.txt", "a/1.txt", "c/d/e.txt", "a.txt"],
+        );
+        let mut discovery = Discovery::default();
+        discover(&root, &WalkOptions::default(), &mut discovery);
+        assert_eq!(
+            relative(&root, &discovery),
+            vec!["a/1.txt", "a/2.txt", "a.txt", "b.txt", "c/d/e.txt"]
+        );
+        assert!(discovery.warnings.is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_hidden() {
+        let root = fixture("hidden", &["visible.txt", ".hidden.txt", ".dir/inner.txt"]);
+        let mut discovery = Discovery::default();
+        discover(&root, &WalkOptions::default(), &mut discovery);
+        assert_eq!(relative(&root, &discovery), vec!["visible.txt"]);
+
+        let mut discovery = Discovery::default();
+        let options = WalkOptions {
+            hidden: true,
+            ..WalkOptions::default()
+        };
+        discover(&root, &options, &mut discovery);
+        assert_eq!(
+            relative(&root, &discovery),
+            vec![".dir/inner.txt", ".hidden.txt", "visible.txt"]
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_respects_gitignore() {
+        let root = fixture(
+            "gitignore",
+            &[
+                "src/main.rs",
+                "src/gen/out.rs",
+                "target/debug/app",
+                "notes.log",
+                "keep.log",
+                ".git/info/exclude",
+                ".git/HEAD",
+                "local.tmp",
+            ],
+        );
+        fs::write(root.join(".gitignore"), "target/\n*.log\n!keep.log\n").unwrap();
+        fs::write(root.join("src").join(".gitignore"), "gen/\n").unwrap();
+        fs::write(root.join(".git").join("info").join("exclude"), "*.tmp\n").unwrap();
+
+        let mut discovery = Discovery::default();
+        discover(&root, &WalkOptions::default(), &mut discovery);
+        assert_eq!(relative(&root, &discovery), vec!["keep.log", "src/main.rs"]);
+
+        let mut discovery = Discovery::default();
+        discover(&root.join("src"), &WalkOptions::default(), &mut discovery);
+        assert_eq!(discovery.files.len(), 1);
+
+        let options = WalkOptions {
+            hidden: true,
+            ignore: false,
+            default_prune: false,
+            ..WalkOptions::default()
+        };
+        let mut discovery = Discovery::default();
+        discover(&root, &options, &mut discovery);
+        assert_eq!(
+            relative(&root, &discovery),
+            vec![
+                ".gitignore",
+                "keep.log",
+                "local.tmp",
+                "notes.log",
+                "src/.gitignore",
+                "src/gen/out.rs",
+                "src/main.rs",
+                "target/debug/app"
+            ]
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_applies_filters() {
+        let root = fixture(
+            "filters",
+            &[
+                "app.js",
+                "app.min.js",
+                "vendor/lib.js",
+                "src/x.rs",
+                "src/vendor/y.js",
+            ],
+        );
+        let mut options = WalkOptions::default();
+        options.filter.exclude("*.min.js").unwrap();
+        options.filter.exclude("vendor/**").unwrap();
+        let mut discovery = Discovery::default();
+        discover(&root, &options, &mut discovery);
+        assert_eq!(
+            relative(&root, &discovery),
+            vec!["app.js", "src/vendor/y.js", "src/x.rs"]
+        );
+        assert_eq!(discovery.filtered, 2);
+
+        options.filter.include("*.rs").unwrap();
+        let mut discovery = Discovery::default();
+        discover(&root, &options, &mut discovery);
+        assert_eq!(relative(&root, &discovery), vec!["src/x.rs"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_depth_limits() {
+        let root = fixture("depth", &["top.txt", "a/mid.txt", "a/b/deep.txt"]);
+        let walk = |min_depth, max_depth| {
+            let options = WalkOptions {
+                min_depth,
+                max_depth,
+                ..WalkOptions::default()
+            };
+            let mut discovery = Discovery::default();
+            discover(&root, &options, &mut discovery);
+            relative(&root, &discovery)
+        };
+        assert!(walk(0, Some(0)).is_empty());
+        assert_eq!(walk(0, Some(1)), vec!["top.txt"]);
+        assert_eq!(walk(0, Some(2)), vec!["a/mid.txt", "top.txt"]);
+        assert_eq!(walk(2, None), vec!["a/b/deep.txt", "a/mid.txt"]);
+        assert_eq!(walk(3, Some(3)), vec!["a/b/deep.txt"]);
+        assert!(walk(4, None).is_empty());
+        assert!(walk(2, Some(1)).is_empty());
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_depth_ignores_symlinked_directories() {
+        let root = fixture("depth_symlink", &["real/inner.txt"]);
+        std::os::unix::fs::symlink(root.join("real"), root.join("link")).unwrap();
+        let options = WalkOptions {
+            max_depth: Some(2),
+            ..WalkOptions::default()
+        };
+        let mut discovery = Discovery::default();
+        discover(&root, &options, &mut discovery);
+        assert_eq!(relative(&root, &discovery), vec!["real/inner.txt"]);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_discover_symlinks() {
+        use std::os::unix::fs::symlink;
+        let root = fixture("symlinks", &["real/inner.txt", "file.txt"]);
+        symlink(root.join("real"), root.join("dir_link")).unwrap();
+        symlink(root.join("file.txt"), root.join("file_link.txt")).unwrap();
+        symlink(root.join("missing.txt"), root.join("broken.txt")).unwrap();
+        symlink(&root, root.join("real").join("loop")).unwrap();
+
+        let mut discovery = Discovery::default();
+        discover(&root, &WalkOptions::default(), &mut discovery);
+        assert_eq!(
+            relative(&root, &discovery),
+            vec!["file.txt", "real/inner.txt"]
+        );
+        assert_eq!(discovery.skipped.len(), 4);
+        assert!(discovery.warnings.is_empty());
+
+        let options = WalkOptions {
+            follow_symlinks: true,
+            ..WalkOptions::default()
+        };
+        let mut discovery = Discovery::default();
+        discover(&root, &options, &mut discovery);
+        assert_eq!(
+            relative(&root, &discovery),
+            vec![
+                "dir_link/inner.txt",
+                "file.txt",
+                "file_link.txt",
+                "real/inner.txt"
+            ]
+        );
+        assert!(discovery
+            .warnings
+            .iter()
+            .any(|w| w.starts_with("Broken symlink")));
+        assert_eq!(
+            discovery
+                .warnings
+                .iter()
+                .filter(|w| w.starts_with("Symlink loop detected"))
+                .count(),
+            2
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_default_prune() {
+        let root = fixture(
+            "prune",
+            &[
+                "src/a.rs",
+                "target/debug/b",
+                "node_modules/x/c.js",
+                "web/node_modules/d.js",
+            ],
+        );
+        let mut discovery = Discovery::default();
+        discover(&root, &WalkOptions::default(), &mut discovery);
+        assert_eq!(relative(&root, &discovery), vec!["src/a.rs"]);
+
+        let options = WalkOptions {
+            default_prune: false,
+            ..WalkOptions::default()
+        };
+        let mut discovery = Discovery::default();
+        discover(&root, &options, &mut discovery);
+        assert_eq!(discovery.files.len(), 4);
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn test_discover_unreadable_root_is_a_warning() {
+        let mut discovery = Discovery::default();
+        discover(
+            Path::new("definitely_missing_dir"),
+            &WalkOptions::default(),
+            &mut discovery,
+        );
+        assert!(discovery.files.is_empty());
+        assert_eq!(discovery.warnings.len(), 1);
+        assert!(discovery.warnings[0].starts_with("Cannot read directory definitely_missing_dir"));
+    }
+}
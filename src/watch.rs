@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+use crate::cli::{self, TreatBinary};
+use crate::{figures, format, interrupt, osname, Exit, MdltError};
+
+/// How often the files are looked at for changes.
+const POLL: Duration = Duration::from_millis(500);
+
+/// How long the files must stay as they are before a burst of changes, such
+/// as an editor writing a file and then its backup, is reported at once.
+const SETTLE: Duration = Duration::from_millis(200);
+
+/// What a file looked like when last seen: its modification time and size,
+/// or `None` when it was not there.
+type Stamp = Option<(Option<SystemTime>, u64)>;
+
+/// The files `--watch` follows, in the order they were found, with how each
+/// looked. New files under the directories of `-r`, and new matches of a
+/// glob, are found again on each look.
+fn snapshot(options: &cli::Options) -> Vec<(String, Stamp)> {
+    let paths = crate::collect_paths(options)
+        .map(|(paths, _)| paths)
+        .unwrap_or_default();
+    paths
+        .into_iter()
+        .map(|path| {
+            let stamp = fs::metadata(osname::path(&path))
+                .ok()
+                .map(|meta| (meta.modified().ok(), meta.len()));
+            (path, stamp)
+        })
+        .collect()
+}
+
+/// The files that appeared or changed between `old` and `new`, and those
+/// that went away.
+fn changes(old: &[(String, Stamp)], new: &[(String, Stamp)]) -> (Vec<String>, Vec<String>) {
+    let before: HashMap<&str, &Stamp> = old
+        .iter()
+        .map(|(path, stamp)| (path.as_str(), stamp))
+        .collect();
+    let after: HashMap<&str, &Stamp> = new
+        .iter()
+        .map(|(path, stamp)| (path.as_str(), stamp))
+        .collect();
+    let changed = new
+        .iter()
+        .filter(|(path, stamp)| stamp.is_some() && before.get(path.as_str()) != Some(&stamp))
+        .map(|(path, _)| path.clone())
+        .collect();
+    let deleted = old
+        .iter()
+        .filter(|(path, stamp)| {
+            stamp.is_some() && after.get(path.as_str()).is_none_or(|stamp| stamp.is_none())
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+    (changed, deleted)
+}
+
+/// Whether anything the options ask for can fail, so that each report
+/// ends with whether it did.
+fn checks(options: &cli::Options) -> bool {
+    !crate::checks::active(options).is_empty()
+        || options.fail_on_skip
+        || options.expected_endings.is_some()
+        || !options.expect.is_empty()
+        || options.gitattributes
+        || options.editorconfig
+}
+
+/// Sleeps for `duration`, returning whether Ctrl-C was pressed meanwhile.
+fn wait(duration: Duration) -> bool {
+    thread::sleep(duration);
+    interrupt::requested()
+}
+
+/// Runs `--watch`: the report `options` asks for, then, each time files
+/// change, one line per changed or deleted file, as with `-q`, under the
+/// time it was seen, and `PASS` or `FAIL` when there are checks. Ctrl-C
+/// stops it with a clean exit.
+pub fn run(options: cli::Options) -> Result<Exit, MdltError> {
+    interrupt::catch();
+    let mut seen = snapshot(&options);
+    match crate::run_options(options.clone()) {
+        Err(MdltError::Usage(message)) => return Err(MdltError::Usage(message)),
+        Err(failure) if !failure.to_string().is_empty() => eprintln!("{}", failure),
+        _ => {}
+    }
+    let shown = crate::shown_paths(&options)?;
+    // A file that changes is reported on as it is treated in the whole run.
+    let treat_binary = options.treat_binary.unwrap_or(match seen.len() {
+        1 => TreatBinary::Analyze,
+        _ => TreatBinary::Skip,
+    });
+    let checking = checks(&options);
+    loop {
+        if wait(POLL) {
+            return Ok(Exit::Clean);
+        }
+        let mut now = snapshot(&options);
+        if now == seen {
+            continue;
+        }
+        loop {
+            if wait(SETTLE) {
+                return Ok(Exit::Clean);
+            }
+            let again = snapshot(&options);
+            if again == now {
+                break;
+            }
+            now = again;
+        }
+        let (changed, deleted) = changes(&seen, &now);
+        seen = now;
+        if changed.is_empty() && deleted.is_empty() {
+            continue;
+        }
+
+        let mut counts = Vec::new();
+        if !changed.is_empty() {
+            counts.push(format!("{} changed", changed.len()));
+        }
+        if !deleted.is_empty() {
+            counts.push(format!("{} deleted", deleted.len()));
+        }
+        let time = figures::rfc3339(SystemTime::now());
+        println!("[{}] {}", &time[11..], counts.join(", "));
+        for path in &deleted {
+            println!("{}: deleted", format::escape_name(&shown.show(path)));
+        }
+        if changed.is_empty() {
+            continue;
+        }
+        let batch = cli::Options {
+            paths: changed,
+            files_from: None,
+            recursive: false,
+            glob: false,
+            treat_binary: Some(treat_binary),
+            quiet: options.quiet.max(1),
+            ..options.clone()
+        };
+        match crate::run_options(batch) {
+            Ok(_) if checking => println!("PASS"),
+            Ok(_) => {}
+            Err(failure @ MdltError::CheckFailed { .. }) => {
+                let message = failure.to_string();
+                if !message.is_empty() {
+                    eprintln!("{}", message);
+                }
+                println!("FAIL");
+            }
+            Err(failure) => eprintln!("{}", failure),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stamp(seconds: u64, len: u64) -> Stamp {
+        Some((
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)),
+            len,
+        ))
+    }
+
+    #[test]
+    fn test_changes() {
+        let old = vec![
+            ("a.txt".to_string(), stamp(1, 10)),
+            ("b.txt".to_string(), stamp(1, 10)),
+            ("c.txt".to_string(), stamp(1, 10)),
+            ("gone.txt".to_string(), None),
+        ];
+        let new = vec![
+            ("a.txt".to_string(), stamp(1, 10)),
+            ("b.txt".to_string(), stamp(2, 12)),
+            ("gone.txt".to_string(), stamp(3, 1)),
+            ("new.txt".to_string(), stamp(3, 1)),
+        ];
+        assert_eq!(
+            changes(&old, &new),
+            (
+                vec![
+                    "b.txt".to_string(),
+                    "gone.txt".to_string(),
+                    "new.txt".to_string()
+                ],
+                vec!["c.txt".to_string()]
+            )
+        );
+        assert_eq!(changes(&new, &new), (vec![], vec![]));
+    }
+}
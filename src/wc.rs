@@ -0,0 +1,228 @@
+use crate::utf8;
+
+/// Character and word counts for one file.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextCounts {
+    pub chars: u64,
+    pub words: u64,
+    /// Code units that were not part of a valid character and were counted
+    /// as a character each.
+    pub invalid: u64,
+}
+
+/// Counts characters and words the way `wc -m` and `wc -w` do, from UTF-8
+/// bytes or UTF-16 code units fed one at a time, so chunk boundaries never
+/// split a word or a character. Line terminators are characters like any
+/// other and a CRLF is two of them. A word is a maximal run of anything but
+/// whitespace, where the no-break spaces do not count as whitespace.
+#[derive(Debug)]
+pub struct Counter {
+    counts: TextCounts,
+    in_word: bool,
+    /// Bytes of the UTF-8 sequence in progress.
+    pending: u8,
+    /// Continuation bytes the sequence still needs, and the allowed range
+    /// for the next one.
+    needed: u8,
+    lower: u8,
+    upper: u8,
+    code_point: u32,
+    /// The previous UTF-16 code unit was a high surrogate.
+    high_surrogate: bool,
+}
+
+impl Default for Counter {
+    fn default() -> Self {
+        Counter {
+            counts: TextCounts::default(),
+            in_word: false,
+            pending: 0,
+            needed: 0,
+            lower: 0x80,
+            upper: 0xbf,
+            code_point: 0,
+            high_surrogate: false,
+        }
+    }
+}
+
+impl Counter {
+    /// Counts the next byte of UTF-8 input. The bytes of a sequence that is
+    /// cut short or malformed count as a character each.
+    pub fn byte(&mut self, byte: u8) {
+        if self.needed > 0 {
+            if (self.lower..=self.upper).contains(&byte) {
+                self.pending += 1;
+                self.needed -= 1;
+                self.lower = 0x80;
+                self.upper = 0xbf;
+                self.code_point = self.code_point << 6 | u32::from(byte & 0x3f);
+                if self.needed == 0 {
+                    self.pending = 0;
+                    self.char(self.code_point);
+                }
+                return;
+            }
+            self.invalid(self.pending);
+            self.pending = 0;
+            self.needed = 0;
+            self.lower = 0x80;
+            self.upper = 0xbf;
+        }
+        match utf8::lead(byte) {
+            Some((0, ..)) => self.char(u32::from(byte)),
+            Some((needed, lower, upper)) => {
+                self.pending = 1;
+                self.needed = needed;
+                self.lower = lower;
+                self.upper = upper;
+                self.code_point = u32::from(byte & (0x7f >> (needed + 1)));
+            }
+            None => self.invalid(1),
+        }
+    }
+
+    /// Counts the next UTF-16 code unit. An unpaired surrogate counts as a
+    /// character of its own.
+    pub fn unit(&mut self, unit: u16) {
+        let after_high = std::mem::take(&mut self.high_surrogate);
+        match unit {
+            0xdc00..=0xdfff if after_high => self.char(0x10000),
+            0xdc00..=0xdfff => self.invalid(1),
+            _ => {
+                if after_high {
+                    self.invalid(1);
+                }
+                if (0xd800..=0xdbff).contains(&unit) {
+                    self.high_surrogate = true;
+                } else {
+                    self.char(u32::from(unit));
+                }
+            }
+        }
+    }
+
+    /// Counts a code unit the input ended in the middle of as a character,
+    /// but not as invalid: the caller warns about it separately.
+    pub fn truncated_unit(&mut self) {
+        self.counts.chars += 1;
+        self.word(true);
+    }
+
+    /// Ends the input; a sequence still in progress counts as invalid.
+    /// Takes the counter of the text right after this one's, as if this
+    /// one had gone on through it. This one's text must end between words
+    /// and characters, as it does after a line break.
+    #[cfg_attr(not(feature = "cli"), allow(dead_code))]
+    pub fn merge(&mut self, next: Counter) {
+        let counts = self.counts;
+        *self = next;
+        self.counts.chars += counts.chars;
+        self.counts.words += counts.words;
+        self.counts.invalid += counts.invalid;
+    }
+
+    pub fn finish(mut self) -> TextCounts {
+        self.invalid(self.pending);
+        if self.high_surrogate {
+            self.invalid(1);
+        }
+        self.counts
+    }
+
+    fn char(&mut self, c: u32) {
+        self.counts.chars += 1;
+        self.word(!is_space(c));
+    }
+
+    fn invalid(&mut self, units: u8) {
+        if units > 0 {
+            self.counts.chars += u64::from(units);
+            self.counts.invalid += u64::from(units);
+            self.word(true);
+        }
+    }
+
+    fn word(&mut self, in_word: bool) {
+        if in_word && !self.in_word {
+            self.counts.words += 1;
+        }
+        self.in_word = in_word;
+    }
+}
+
+/// Whitespace as `wc` sees it.
+fn is_space(c: u32) -> bool {
+    char::from_u32(c)
+        .is_some_and(|c| c.is_whitespace() && !matches!(c, '\u{a0}' | '\u{2007}' | '\u{202f}'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count(bytes: &[u8]) -> TextCounts {
+        let mut counter = Counter::default();
+        for &byte in bytes {
+            counter.byte(byte);
+        }
+        counter.finish()
+    }
+
+    fn count_utf16(units: &[u16]) -> TextCounts {
+        let mut counter = Counter::default();
+        for &unit in units {
+            counter.unit(unit);
+        }
+        counter.finish()
+    }
+
+    #[test]
+    fn test_words_and_chars() {
+        let text = "  hello  wörld\r\n\tfoo\u{a0}bar\u{3000}baz 🎉\n";
+        let counts = count(text.as_bytes());
+        assert_eq!(counts.chars, text.chars().count() as u64);
+        assert_eq!(counts.words, 5);
+        assert_eq!(counts.invalid, 0);
+        assert_eq!(count(b""), TextCounts::default());
+        assert_eq!(count(b" \r\n\t ").words, 0);
+    }
+
+    #[test]
+    fn test_invalid_bytes_count_one_each() {
+        let samples: &[(&[u8], u64, u64)] = &[
+            (b"\xff", 1, 1),
+            (b"a\x80b", 3, 1),
+            (b"\xe2\x82", 2, 2),
+            (b"\xe2\x82a", 3, 2),
+            (b"\xc0\xaf", 2, 2),
+            (b"\xed\xa0\x80", 3, 3),
+            (b"\xc3\xa9\xff\xe2\x82\xac\xe2", 4, 2),
+        ];
+        for &(sample, chars, invalid) in samples {
+            let counts = count(sample);
+            assert_eq!(
+                (counts.chars, counts.invalid),
+                (chars, invalid),
+                "{:?}",
+                sample
+            );
+            assert_eq!(counts.words, 1, "{:?}", sample);
+        }
+    }
+
+    #[test]
+    fn test_utf16_units() {
+        let units: Vec<u16> = "ab 🎉\u{2028}c".encode_utf16().collect();
+        assert_eq!(
+            count_utf16(&units),
+            TextCounts {
+                chars: 6,
+                words: 3,
+                invalid: 0
+            }
+        );
+        let counts = count_utf16(&[0xdc00, 0x61, 0xd800, 0x20, 0xd800]);
+        assert_eq!((counts.chars, counts.words, counts.invalid), (5, 2, 3));
+    }
+}
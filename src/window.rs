@@ -0,0 +1,529 @@
+use std::fmt;
+#[cfg(feature = "cli")]
+use std::io::{self, Read};
+use std::str::FromStr;
+
+const CR: u8 = b'\r';
+const LF: u8 = b'\n';
+
+/// `START..END` of `--lines` or `--bytes`: the start is counted in, the
+/// end is not, and either may be left open as in `5000..` or `..20`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Range {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+/// `--lines` or `--bytes`: the part of each file that is analyzed. Lines
+/// count from 1 and bytes from 0, and lines end in LF, CRLF or CR
+/// whatever `--encoding` says.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Window {
+    Lines(Range),
+    Bytes(Range),
+}
+
+impl Window {
+    /// The `--lines` window `value`.
+    pub fn lines(value: &str) -> Result<Self, String> {
+        parse("--lines", "lines", 1, value).map(Window::Lines)
+    }
+
+    /// The `--bytes` window `value`.
+    pub fn bytes(value: &str) -> Result<Self, String> {
+        parse("--bytes", "bytes", 0, value).map(Window::Bytes)
+    }
+
+    /// The offset a file can be read from instead of being read up to it:
+    /// the byte before the window, which tells whether it starts inside a
+    /// CRLF.
+    #[cfg(feature = "cli")]
+    pub fn seek_to(self) -> Option<u64> {
+        match self {
+            Window::Bytes(range) if range.start > 1 => Some(range.start - 1),
+            _ => None,
+        }
+    }
+
+    /// The number of the first line in the window, when that is known
+    /// before reading.
+    #[cfg(feature = "cli")]
+    pub fn first_line(self) -> u64 {
+        match self {
+            Window::Lines(range) => range.start,
+            Window::Bytes(_) => 1,
+        }
+    }
+}
+
+/// `--head-lines` or `--head-bytes`: the first lines or bytes of each
+/// file, the sample a quick look over many files goes by. Where a
+/// `--bytes` window leaves out the `\r` of a CRLF its end parts, a byte
+/// limit between the `\r` and `\n` takes the `\n` too.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Head {
+    Lines(u64),
+    Bytes(u64),
+}
+
+impl Head {
+    /// The window of the same lines or bytes.
+    fn window(self) -> Window {
+        match self {
+            Head::Lines(count) => Window::Lines(Range {
+                start: 1,
+                end: Some(count + 1),
+            }),
+            Head::Bytes(count) => Window::Bytes(Range {
+                start: 0,
+                end: Some(count),
+            }),
+        }
+    }
+}
+
+/// As JSON gives it: `lines 1000` or `bytes 65536`.
+impl fmt::Display for Head {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Head::Lines(count) => write!(f, "lines {}", count),
+            Head::Bytes(count) => write!(f, "bytes {}", count),
+        }
+    }
+}
+
+impl FromStr for Head {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("expected lines or bytes and a count, got '{}'", value);
+        let (unit, count) = value.split_once(' ').ok_or_else(invalid)?;
+        let count = count.parse().map_err(|_| invalid())?;
+        match unit {
+            "lines" => Ok(Head::Lines(count)),
+            "bytes" => Ok(Head::Bytes(count)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+fn parse(flag: &str, unit: &str, first: u64, value: &str) -> Result<Range, String> {
+    let invalid = || {
+        format!(
+            "{} expects a range of {} such as 10..20, 10.. or ..20, got '{}'",
+            flag, unit, value
+        )
+    };
+    let (start, end) = value.split_once("..").ok_or_else(invalid)?;
+    let start = match start {
+        "" => first,
+        start => start.parse().map_err(|_| invalid())?,
+    };
+    let end = match end {
+        "" => None,
+        end => Some(end.parse().map_err(|_| invalid())?),
+    };
+    if start < first || end.is_some_and(|end| end < start) {
+        return Err(invalid());
+    }
+    Ok(Range { start, end })
+}
+
+/// As the report gives it: `lines 5000..6000` or `bytes 0..`.
+impl fmt::Display for Window {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (unit, range) = match self {
+            Window::Lines(range) => ("lines", range),
+            Window::Bytes(range) => ("bytes", range),
+        };
+        write!(f, "{} {}..", unit, range.start)?;
+        match range.end {
+            Some(end) => write!(f, "{}", end),
+            None => Ok(()),
+        }
+    }
+}
+
+impl FromStr for Window {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once(' ') {
+            Some(("lines", range)) => Window::lines(range),
+            Some(("bytes", range)) => Window::bytes(range),
+            _ => Err(format!(
+                "expected lines or bytes and a range, got '{}'",
+                value
+            )),
+        }
+    }
+}
+
+/// Cuts the window out of input that arrives a chunk at a time, counting
+/// bytes or line endings up to its start without keeping any of it.
+#[derive(Debug, Clone)]
+pub struct Clip {
+    window: Window,
+    /// Bytes gone by, or line endings for a window of lines.
+    passed: u64,
+    /// A CR ended the last chunk, so the next byte decides whether it was
+    /// a CR or the first half of a CRLF.
+    pending_cr: bool,
+    /// The last byte before a `--bytes` window was a CR, so an LF starting
+    /// the window is the second half of a CRLF.
+    after_cr: bool,
+    /// A `--bytes` window ended on a CR at the end of the last chunk, held
+    /// back until the next byte shows it is not the first half of a CRLF.
+    held_cr: bool,
+    inside: bool,
+    done: bool,
+    /// Set for a head, whose byte limit does not part a CRLF.
+    whole_crlf: bool,
+}
+
+impl Clip {
+    pub fn new(window: Window) -> Self {
+        let inside = match window {
+            Window::Lines(range) => range.start == 1,
+            Window::Bytes(range) => range.start == 0,
+        };
+        let done = match window {
+            Window::Lines(range) | Window::Bytes(range) => range.end == Some(range.start),
+        };
+        Clip {
+            window,
+            passed: 0,
+            pending_cr: false,
+            after_cr: false,
+            held_cr: false,
+            inside: inside && !done,
+            done,
+            whole_crlf: false,
+        }
+    }
+
+    /// Takes the head of the input.
+    pub fn head(head: Head) -> Self {
+        Clip {
+            whole_crlf: true,
+            ..Clip::new(head.window())
+        }
+    }
+
+    /// Notes that the first `count` bytes were skipped by seeking past them.
+    #[cfg(feature = "cli")]
+    pub fn skipped(&mut self, count: u64) {
+        if let Window::Bytes(range) = self.window {
+            self.passed += count;
+            self.inside = self.passed >= range.start && !self.done;
+        }
+    }
+
+    /// Whether the window is behind, so that nothing more needs reading.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// The part of `chunk` in the window, `chunk` following what was given
+    /// before.
+    pub fn take<'a>(&mut self, chunk: &'a [u8]) -> &'a [u8] {
+        if self.held_cr {
+            self.held_cr = false;
+            self.done = true;
+            return match chunk.first() {
+                Some(&LF) => b"",
+                _ => b"\r",
+            };
+        }
+        let (from, to) = self.span(chunk);
+        &chunk[from..to]
+    }
+
+    /// What is left of the window once the input has ended: the CR held
+    /// back at its end, which no LF came after.
+    pub fn finish(&mut self) -> &'static [u8] {
+        match std::mem::take(&mut self.held_cr) {
+            true => b"\r",
+            false => b"",
+        }
+    }
+
+    /// Where in `chunk` the part that [`take`](Self::take) gives starts
+    /// and ends.
+    fn span(&mut self, chunk: &[u8]) -> (usize, usize) {
+        if self.done {
+            return (0, 0);
+        }
+        let (from, to) = match self.window {
+            Window::Bytes(range) => self.bytes(range, chunk),
+            Window::Lines(range) => self.lines(range, chunk),
+        };
+        (from.min(chunk.len()), to.min(chunk.len()))
+    }
+
+    fn bytes(&mut self, range: Range, chunk: &[u8]) -> (usize, usize) {
+        // A head that ended on a CR at the end of the last chunk takes the
+        // LF that makes it a CRLF, and nothing else.
+        if self.pending_cr {
+            self.pending_cr = false;
+            self.done = true;
+            return (0, usize::from(chunk.first() == Some(&LF)));
+        }
+        let len = chunk.len() as u64;
+        let start = self.passed;
+        self.passed += len;
+        let mut from = range.start.saturating_sub(start);
+        // A window that starts on the LF of a CRLF starts after it.
+        if (start..self.passed).contains(&range.start) {
+            let before = match from {
+                0 => self.after_cr,
+                from => chunk[from as usize - 1] == CR,
+            };
+            if before && chunk[from as usize] == LF {
+                from += 1;
+            }
+        }
+        self.after_cr = chunk.last() == Some(&CR);
+        let to = match range.end {
+            Some(end) if end <= self.passed => {
+                self.done = true;
+                end - start
+            }
+            _ => len,
+        };
+        let index = |offset: u64| usize::try_from(offset).unwrap_or(usize::MAX);
+        let mut to = index(to);
+        if self.done && self.whole_crlf && to > 0 && chunk[to - 1] == CR {
+            match chunk.get(to) {
+                Some(&LF) => to += 1,
+                Some(_) => {}
+                None => {
+                    self.done = false;
+                    self.pending_cr = true;
+                }
+            }
+        } else if self.done && to as u64 > from && chunk[to - 1] == CR {
+            // A window that ends between the CR and LF of a CRLF leaves the
+            // CR out.
+            match chunk.get(to) {
+                Some(&LF) => to -= 1,
+                Some(_) => {}
+                None => {
+                    to -= 1;
+                    self.done = false;
+                    self.held_cr = true;
+                }
+            }
+        }
+        self.inside = !self.done && self.passed >= range.start;
+        (index(from), to)
+    }
+
+    fn lines(&mut self, range: Range, chunk: &[u8]) -> (usize, usize) {
+        let mut from = if self.inside { Some(0) } else { None };
+        let mut index = 0;
+        while index < chunk.len() {
+            // Where the line that just ended stops: after an LF, or before
+            // the byte that followed a lone CR.
+            let boundary = if self.pending_cr {
+                self.pending_cr = false;
+                if chunk[index] == LF {
+                    index += 1;
+                }
+                index
+            } else {
+                match chunk[index..]
+                    .iter()
+                    .position(|&byte| byte == CR || byte == LF)
+                {
+                    Some(found) => {
+                        index += found + 1;
+                        if chunk[index - 1] == CR {
+                            self.pending_cr = true;
+                            continue;
+                        }
+                        index
+                    }
+                    None => break,
+                }
+            };
+            self.passed += 1;
+            let line = self.passed + 1;
+            if line == range.start {
+                from = Some(boundary);
+                self.inside = true;
+            }
+            if range.end == Some(line) {
+                self.done = true;
+                self.inside = false;
+                return (from.unwrap_or(boundary), boundary);
+            }
+        }
+        match from {
+            Some(from) => (from, chunk.len()),
+            None => (chunk.len(), chunk.len()),
+        }
+    }
+}
+
+/// `reader` with only the window left of what it yields; it stops reading
+/// once past the window.
+#[cfg(feature = "cli")]
+pub struct Clipped<R> {
+    reader: R,
+    clip: Clip,
+}
+
+#[cfg(feature = "cli")]
+impl<R: Read> Clipped<R> {
+    pub fn new(reader: R, clip: Clip) -> Self {
+        Clipped { reader, clip }
+    }
+}
+
+#[cfg(feature = "cli")]
+impl<R: Read> Read for Clipped<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if self.clip.is_done() {
+                return Ok(0);
+            }
+            let read = self.reader.read(buf)?;
+            if read == 0 {
+                let rest = self.clip.finish();
+                let len = rest.len().min(buf.len());
+                buf[..len].copy_from_slice(&rest[..len]);
+                return Ok(len);
+            }
+            let (from, to) = match self.clip.held_cr {
+                true => {
+                    let kept = !self.clip.take(&buf[..read]).is_empty();
+                    buf[0] = CR;
+                    (0, usize::from(kept))
+                }
+                false => self.clip.span(&buf[..read]),
+            };
+            if from < to {
+                buf.copy_within(from..to, 0);
+                return Ok(to - from);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, feature = "cli"))]
+mod tests {
+    use super::*;
+
+    /// The window of `input` fed in chunks of `size` bytes.
+    fn clip(window: &str, input: &[u8], size: usize) -> Vec<u8> {
+        let mut clip = Clip::new(window.parse().unwrap());
+        let mut taken = Vec::new();
+        for chunk in input.chunks(size) {
+            taken.extend_from_slice(clip.take(chunk));
+        }
+        taken.extend_from_slice(clip.finish());
+        taken
+    }
+
+    #[test]
+    fn test_parse() {
+        let range = |start, end| Range { start, end };
+        assert_eq!(
+            Window::lines("10..20"),
+            Ok(Window::Lines(range(10, Some(20))))
+        );
+        assert_eq!(
+            Window::lines("5000.."),
+            Ok(Window::Lines(range(5000, None)))
+        );
+        assert_eq!(Window::lines("..20"), Ok(Window::Lines(range(1, Some(20)))));
+        assert_eq!(Window::bytes("..20"), Ok(Window::Bytes(range(0, Some(20)))));
+        assert_eq!(Window::bytes("0..0"), Ok(Window::Bytes(range(0, Some(0)))));
+        for invalid in ["10", "0..5", "20..10", "a..b", "-1..2"] {
+            assert!(Window::lines(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(
+            Window::bytes("x").unwrap_err(),
+            "--bytes expects a range of bytes such as 10..20, 10.. or ..20, got 'x'"
+        );
+        for window in ["lines 5000..6000", "bytes 0.."] {
+            assert_eq!(window.parse::<Window>().unwrap().to_string(), window);
+        }
+    }
+
+    #[test]
+    fn test_clip_lines() {
+        let input = b"one\r\ntwo\nthree\rfour\r\nfive";
+        for size in [1, 2, 3, 64] {
+            assert_eq!(clip("lines 2..4", input, size), b"two\nthree\r", "{}", size);
+            assert_eq!(clip("lines 1..2", input, size), b"one\r\n", "{}", size);
+            assert_eq!(clip("lines 4..", input, size), b"four\r\nfive", "{}", size);
+            assert_eq!(clip("lines 5..", input, size), b"five", "{}", size);
+            assert_eq!(clip("lines 3..3", input, size), b"", "{}", size);
+            assert_eq!(clip("lines 9..", input, size), b"", "{}", size);
+        }
+        assert_eq!(clip("lines 2..", b"a\r\r\nb", 1), b"\r\nb");
+    }
+
+    #[test]
+    fn test_clip_bytes() {
+        let input = b"a\r\nb\r\n";
+        for size in [1, 2, 64] {
+            assert_eq!(clip("bytes 2..", input, size), b"b\r\n", "{}", size);
+            assert_eq!(clip("bytes 0..2", input, size), b"a", "{}", size);
+            assert_eq!(clip("bytes 2..5", input, size), b"b", "{}", size);
+            assert_eq!(clip("bytes 1..3", input, size), b"\r\n", "{}", size);
+            assert_eq!(clip("bytes 3..4", input, size), b"b", "{}", size);
+            assert_eq!(clip("bytes 4..", input, size), b"\r\n", "{}", size);
+            // A CR that no LF follows stays in.
+            assert_eq!(clip("bytes 0..2", b"a\rb", size), b"a\r", "{}", size);
+            assert_eq!(clip("bytes 0..2", b"a\r", size), b"a\r", "{}", size);
+            assert_eq!(clip("bytes 1..", b"\n\nb", size), b"\nb", "{}", size);
+        }
+        let mut clip = Clip::new("bytes 4..".parse().unwrap());
+        clip.skipped(4);
+        assert_eq!(clip.take(b"\r\n"), b"\r\n");
+        let mut clip = Clip::new("bytes 2..".parse().unwrap());
+        clip.skipped(1);
+        assert_eq!(clip.take(b"\r\nb"), b"b");
+    }
+
+    #[test]
+    fn test_clip_head() {
+        let head = |head: &str, input: &[u8], size: usize| {
+            let mut clip = Clip::head(head.parse().unwrap());
+            let mut taken = Vec::new();
+            for chunk in input.chunks(size) {
+                taken.extend_from_slice(clip.take(chunk));
+            }
+            (taken, clip.is_done())
+        };
+        for size in [1, 2, 3, 64] {
+            assert_eq!(head("bytes 2", b"a\r\nb", size), (b"a\r\n".to_vec(), true));
+            assert_eq!(head("bytes 2", b"a\rb", size), (b"a\r".to_vec(), true));
+            assert_eq!(head("bytes 3", b"a\r\nb", size), (b"a\r\n".to_vec(), true));
+            assert_eq!(
+                head("lines 2", b"a\r\nb\r\nc", size),
+                (b"a\r\nb\r\n".to_vec(), true)
+            );
+            assert_eq!(head("lines 5", b"a\nb", size), (b"a\nb".to_vec(), false));
+        }
+        // A CR that ends the input is left for the end of the file to
+        // settle.
+        assert_eq!(head("bytes 2", b"a\r", 64), (b"a\r".to_vec(), false));
+        assert_eq!("lines 1000".parse(), Ok(Head::Lines(1000)));
+        assert_eq!(Head::Bytes(4096).to_string(), "bytes 4096");
+        assert!("lines".parse::<Head>().is_err());
+    }
+
+    #[test]
+    fn test_clipped_stops_reading() {
+        let mut read = String::new();
+        let clip = Clip::new("lines 2..4".parse().unwrap());
+        Clipped::new(&b"1\n2\n3\n4\n"[..], clip)
+            .read_to_string(&mut read)
+            .unwrap();
+        assert_eq!(read, "2\n3\n");
+    }
+}
@@ -0,0 +1,13 @@
+//! Helpers the integration tests share.
+
+use std::fs;
+use std::path::PathBuf;
+
+/// A fresh, empty directory for the test `name` of the suite `suite`, in
+/// the temporary directory and apart from those of other runs.
+pub fn scratch(suite: &str, name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("mdlt_{}_{}_{}", suite, name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
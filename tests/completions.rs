@@ -0,0 +1,52 @@
+#![cfg(feature = "cli")]
+
+use std::process::Command;
+
+fn mdlt(args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_bash_completes_every_long_flag() {
+    let (_, help) = mdlt(&["--help"]);
+    let usage = help.lines().next().unwrap();
+    let (status, script) = mdlt(&["completions", "bash"]);
+    assert_eq!(status, Some(0));
+    let long_flags: Vec<&str> = usage
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-'))
+        .filter(|word| word.starts_with("--") && word.len() > 2)
+        .collect();
+    assert!(long_flags.len() > 80);
+    for flag in long_flags {
+        assert!(
+            script.contains(&format!(" {} ", flag)) || script.contains(&format!(" {}\"", flag)),
+            "{} is not completed",
+            flag
+        );
+    }
+}
+
+#[test]
+fn test_every_shell_has_a_script() {
+    for shell in ["bash", "zsh", "fish", "powershell"] {
+        let (status, script) = mdlt(&["completions", shell]);
+        assert_eq!(status, Some(0));
+        assert!(script.contains("format"), "{}", shell);
+    }
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["completions", "tcsh"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Unknown shell 'tcsh': expected bash, zsh, fish or powershell\n"
+    );
+}
@@ -0,0 +1,72 @@
+//! `--detect-encoding` on one file of each encoding it tells apart, none
+//! of them with a byte-order mark. The fixtures keep their CRLF endings
+//! whatever Git is told, through `.gitattributes`.
+#![cfg(feature = "cli")]
+
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/encodings")
+        .join(name)
+}
+
+/// The JSON report of `name` with `args`.
+fn json(name: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .args(["--format=json", "--no-config", "--no-cache"])
+        .arg(fixture(name))
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_guesses() {
+    let guesses = [
+        (
+            "utf-8.txt",
+            "\"charset\": \"utf-8\", \"confidence\": \"high\"",
+        ),
+        (
+            "ascii.txt",
+            "\"charset\": \"utf-8\", \"confidence\": \"medium\"",
+        ),
+        (
+            "utf-16le.txt",
+            "\"charset\": \"utf-16le\", \"confidence\": \"high\"",
+        ),
+        (
+            "utf-16be.txt",
+            "\"charset\": \"utf-16be\", \"confidence\": \"high\"",
+        ),
+        (
+            "windows-1252.txt",
+            "\"charset\": \"unknown-8bit\", \"confidence\": \"low\", \"high_bytes\": 5",
+        ),
+    ];
+    for (name, guess) in guesses {
+        let report = json(name, &["--detect-encoding"]);
+        let expected = format!("\"detected_encoding\": {{{}, \"decoded\": false}}", guess);
+        assert!(report.contains(&expected), "{}: {}", name, report);
+    }
+    assert!(!json("utf-8.txt", &[]).contains("detected_encoding"));
+}
+
+#[test]
+fn test_apply_decodes_utf16() {
+    for name in ["utf-16le.txt", "utf-16be.txt"] {
+        let reported = json(name, &["--detect-encoding"]);
+        let applied = json(name, &["--detect-encoding=apply"]);
+        assert!(applied.contains("\"decoded\": true"), "{}", applied);
+        // As bytes, every other one is a NUL.
+        assert!(reported.contains("\"is_binary\": true"), "{}", reported);
+        assert!(applied.contains("\"is_binary\": false"), "{}", applied);
+        assert!(applied.contains("\"total_chars\": 33"), "{}", applied);
+        assert!(applied.contains("\"dos_endings\": 3"), "{}", applied);
+    }
+    let applied = json("windows-1252.txt", &["--detect-encoding=apply"]);
+    assert!(applied.contains("\"decoded\": false"), "{}", applied);
+}
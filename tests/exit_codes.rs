@@ -0,0 +1,220 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+use common::scratch;
+
+/// Runs mdlt in `dir` and returns its exit status.
+fn status(dir: &PathBuf, args: &[&str]) -> i32 {
+    Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+        .status
+        .code()
+        .unwrap()
+}
+
+#[test]
+fn test_success_is_0() {
+    let dir = scratch("exit", "success");
+    fs::write(dir.join("unix.txt"), "a\nb\n").unwrap();
+    assert_eq!(status(&dir, &["unix.txt"]), 0);
+    assert_eq!(status(&dir, &["--check=lf", "unix.txt"]), 0);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_violations_are_1() {
+    let dir = scratch("exit", "violation");
+    fs::write(dir.join("unix.txt"), "a\nb\n").unwrap();
+    fs::write(dir.join("mixed.txt"), "a\r\nb\n").unwrap();
+    assert_eq!(status(&dir, &["--check=lf", "unix.txt", "mixed.txt"]), 1);
+    assert_eq!(status(&dir, &["--fail-on-mixed", "mixed.txt"]), 1);
+    assert_eq!(status(&dir, &["--convert=lf", "--dry-run", "mixed.txt"]), 1);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_usage_errors_are_2() {
+    let dir = scratch("exit", "usage");
+    fs::write(dir.join("unix.txt"), "a\n").unwrap();
+    assert_eq!(status(&dir, &[]), 2);
+    assert_eq!(status(&dir, &["--bogus", "unix.txt"]), 2);
+    assert_eq!(status(&dir, &["--format=yaml", "unix.txt"]), 2);
+    assert_eq!(status(&dir, &["*.md"]), 2);
+    // Without a terminal to ask on, --interactive refuses instead of
+    // waiting for an answer; run from one, it would ask.
+    if fs::File::open("/dev/tty").is_err() {
+        fs::write(dir.join("dos.txt"), "a\r\n").unwrap();
+        assert_eq!(
+            status(&dir, &["--convert=lf", "--interactive", "dos.txt"]),
+            2
+        );
+        assert_eq!(fs::read(dir.join("dos.txt")).unwrap(), b"a\r\n");
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_unreadable_files_are_3() {
+    let dir = scratch("exit", "io");
+    fs::write(dir.join("unix.txt"), "a\n").unwrap();
+    assert_eq!(status(&dir, &["missing.txt"]), 3);
+    assert_eq!(status(&dir, &["unix.txt", "missing.txt"]), 3);
+    fs::create_dir(dir.join("src")).unwrap();
+    assert_eq!(status(&dir, &["src"]), 3);
+    assert_eq!(status(&dir, &["src", "unix.txt"]), 3);
+    assert_eq!(status(&dir, &["-r", "src", "unix.txt"]), 0);
+    // Being unable to read a file outranks what the readable ones violate.
+    assert_eq!(
+        status(&dir, &["--check=crlf", "unix.txt", "missing.txt"]),
+        3
+    );
+    assert_eq!(
+        status(&dir, &["--output=no/such/dir/report.txt", "unix.txt"]),
+        3
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_skipped_binaries_are_4() {
+    let dir = scratch("exit", "binary");
+    fs::write(dir.join("image.png"), b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR").unwrap();
+    assert_eq!(status(&dir, &["--convert=lf", "image.png"]), 4);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_help_is_0() {
+    let dir = scratch("exit", "help");
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .arg("--help")
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let help = String::from_utf8(output.stdout).unwrap();
+    assert!(help.starts_with("Usage: "));
+    assert!(help.contains("Exit status:\n  0  "));
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .arg("--version")
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("mdlt {}\n", env!("CARGO_PKG_VERSION"))
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_diff_regressions_are_1() {
+    let dir = scratch("exit", "diff");
+    let report = |name: &str, contents: &str| {
+        fs::write(dir.join("a.txt"), contents).unwrap();
+        let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+            .args(["--format=json", "a.txt"])
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        fs::write(dir.join(name), output.stdout).unwrap();
+    };
+    report("lf.json", "a\nb\n");
+    report("mixed.json", "a\r\nb\n");
+    assert_eq!(status(&dir, &["diff", "lf.json", "lf.json"]), 0);
+    assert_eq!(status(&dir, &["diff", "lf.json", "mixed.json"]), 1);
+    assert_eq!(status(&dir, &["diff", "mixed.json", "lf.json"]), 0);
+    assert_eq!(
+        status(&dir, &["diff", "--fail-on=any", "mixed.json", "lf.json"]),
+        1
+    );
+    assert_eq!(
+        status(&dir, &["diff", "--fail-on=none", "lf.json", "mixed.json"]),
+        0
+    );
+    assert_eq!(status(&dir, &["diff", "lf.json", "a.txt"]), 2);
+    assert_eq!(status(&dir, &["diff", "lf.json", "missing.json"]), 3);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_compare_differences_are_1() {
+    let dir = scratch("exit", "compare");
+    fs::write(dir.join("unix.txt"), "a\nb\n").unwrap();
+    fs::write(dir.join("copy.txt"), "a\nb\n").unwrap();
+    fs::write(dir.join("dos.txt"), "a\r\nb\r\n").unwrap();
+    assert_eq!(status(&dir, &["compare", "unix.txt", "copy.txt"]), 0);
+    assert_eq!(status(&dir, &["compare", "unix.txt", "dos.txt"]), 1);
+    assert_eq!(
+        status(
+            &dir,
+            &[
+                "compare",
+                "--ignore=line-endings,size",
+                "unix.txt",
+                "dos.txt"
+            ]
+        ),
+        0
+    );
+    assert_eq!(status(&dir, &["compare", "unix.txt"]), 2);
+    assert_eq!(status(&dir, &["compare", "unix.txt", "missing.txt"]), 3);
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["compare", "--format=json", "unix.txt", "-"])
+        .current_dir(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(b"a\r\nb\n").unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stdout).unwrap().contains(
+        "\"differences\": [\"total_bytes\", \"total_chars\", \"line_ending_type\", \"dos_endings\", \"unix_endings\"]"
+    ));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_a_reader_that_stops_early_is_0() {
+    use std::io::{BufRead, BufReader, Read};
+
+    let dir = scratch("exit", "closed");
+    for i in 0..2000 {
+        fs::write(dir.join(format!("{}.txt", i)), "a\nb\n").unwrap();
+    }
+    for format in ["text", "csv", "json"] {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+            .args(["-r", "--format", format, "."])
+            .current_dir(&dir)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        // As `head -1` does: one line, and then the pipe is closed.
+        let mut first = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut first)
+            .unwrap();
+        let mut stderr = String::new();
+        child
+            .stderr
+            .take()
+            .unwrap()
+            .read_to_string(&mut stderr)
+            .unwrap();
+        assert_eq!(child.wait().unwrap().code(), Some(0), "{}", format);
+        assert_eq!(stderr, "", "{}", format);
+    }
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,58 @@
+//! The C interface of the `ffi` feature, driven by the C program in
+//! `tests/ffi/main.c` compiled against `include/mdlt.h` and the cdylib.
+#![cfg(feature = "ffi")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// The directory cargo built the cdylib into, next to the test binary.
+fn library_dir() -> PathBuf {
+    let exe = std::env::current_exe().unwrap();
+    let deps = exe.parent().unwrap().to_path_buf();
+    let name = format!(
+        "{}mdlt{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    );
+    let target = deps.parent().unwrap().to_path_buf();
+    [deps, target]
+        .into_iter()
+        .find(|dir| dir.join(&name).exists())
+        .unwrap_or_else(|| panic!("{} was not built", name))
+}
+
+#[test]
+fn test_c_program() {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = std::env::temp_dir().join(format!("mdlt_ffi_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let program = dir.join("main");
+    let library = library_dir();
+    let status = Command::new(std::env::var("CC").unwrap_or("cc".to_string()))
+        .arg(root.join("tests/ffi/main.c"))
+        .arg("-I")
+        .arg(root.join("include"))
+        .arg("-L")
+        .arg(&library)
+        .arg(format!("-Wl,-rpath,{}", library.display()))
+        .args(["-lmdlt", "-Wall", "-Werror", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap();
+    assert!(status.success(), "the C program did not compile");
+
+    let input = dir.join("input.txt");
+    fs::write(&input, "a\r\nb\r\nc\n").unwrap();
+    let output = Command::new(&program).arg(&input).output().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        format!("{}\n", env!("CARGO_PKG_VERSION"))
+    );
+}
@@ -0,0 +1,48 @@
+/* Drives the C interface through include/mdlt.h; run by tests/ffi.rs with
+ * the path of a file holding "a\r\nb\r\nc\n". */
+#include <stdio.h>
+#include <string.h>
+
+#include "mdlt.h"
+
+#define CHECK(condition)                                                 \
+    do {                                                                 \
+        if (!(condition)) {                                              \
+            fprintf(stderr, "%s:%d: failed: %s\n", __FILE__, __LINE__,   \
+                    #condition);                                         \
+            return 1;                                                    \
+        }                                                                \
+    } while (0)
+
+int main(int argc, char **argv) {
+    MdltStats stats;
+    CHECK(argc == 2);
+
+    CHECK(mdlt_analyze_path(argv[1], &stats) == 0);
+    CHECK(mdlt_last_error_message() == NULL);
+    CHECK(stats.total_lines == 3 && stats.crlf == 2 && stats.lf == 1);
+    CHECK(stats.total_bytes == 8);
+    CHECK(stats.line_ending == MDLT_ENDING_CRLF);
+    CHECK(stats.final_newline == 1 && !stats.is_binary);
+
+    const uint8_t bytes[] = "x\ny";
+    CHECK(mdlt_analyze_bytes(bytes, 3, &stats) == 0);
+    CHECK(stats.total_lines == 2 && stats.lf == 1);
+    CHECK(stats.line_ending == MDLT_ENDING_LF && stats.final_newline == 0);
+    CHECK(mdlt_analyze_bytes(NULL, 0, &stats) == 0);
+    CHECK(stats.line_ending == MDLT_ENDING_NONE && stats.final_newline == -1);
+
+    CHECK(mdlt_analyze_path(NULL, &stats) == -1);
+    CHECK(strcmp(mdlt_last_error_message(), "path is NULL") == 0);
+    CHECK(mdlt_analyze_path(argv[1], NULL) == -1);
+    CHECK(strcmp(mdlt_last_error_message(), "out is NULL") == 0);
+    CHECK(mdlt_analyze_bytes(NULL, 1, &stats) == -1);
+    CHECK(mdlt_analyze_path("bad\xff.txt", &stats) == -1);
+    CHECK(strcmp(mdlt_last_error_message(), "path is not valid UTF-8") == 0);
+    CHECK(mdlt_analyze_path("no_such_file.txt", &stats) == -1);
+    CHECK(strncmp(mdlt_last_error_message(), "Error analyzing no_such_file.txt: ",
+                  34) == 0);
+
+    printf("%s\n", mdlt_version());
+    return 0;
+}
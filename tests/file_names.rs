@@ -0,0 +1,127 @@
+//! File names that would break a line of output: a newline in a name must
+//! not split a report line, and `--print0` must pass it through untouched,
+//! as it must the bytes of a name that is not UTF-8. Windows does not allow
+//! such names, so these run on Unix only.
+#![cfg(all(unix, feature = "cli"))]
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+/// A fresh directory holding `two\nlines.txt`, with mixed endings.
+fn fixture(name: &str) -> PathBuf {
+    let dir = common::scratch("names", name);
+    fs::write(dir.join("two\nlines.txt"), "a\r\nb\n").unwrap();
+    fs::write(dir.join("plain.txt"), "a\n").unwrap();
+    dir
+}
+
+fn mdlt(dir: &PathBuf, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+fn stdout(dir: &PathBuf, args: &[&str]) -> String {
+    String::from_utf8(mdlt(dir, args).stdout).unwrap()
+}
+
+#[test]
+fn test_text_formats_escape_newlines() {
+    let dir = fixture("text");
+    let names = ["two\nlines.txt", "plain.txt"];
+    let text = stdout(&dir, &names);
+    assert!(text.contains("\nFile name: two\\nlines.txt\n"));
+    assert!(stdout(&dir, &["-q", names[0]]).starts_with("two\\nlines.txt: MIXED"));
+    let table = stdout(&dir, &["--format=table", names[0], names[1]]);
+    assert!(table.contains("\ntwo\\nlines.txt   mixed"));
+    let markdown = stdout(&dir, &["--format=markdown", names[0]]);
+    assert!(markdown.contains("two\\nlines.txt"));
+    let failed = mdlt(&dir, &["--check=lf", names[0], names[1]]);
+    assert_eq!(failed.status.code(), Some(1));
+    assert!(String::from_utf8(failed.stdout)
+        .unwrap()
+        .starts_with("two\\nlines.txt: "));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_json_and_csv_encode_newlines() {
+    let dir = fixture("data");
+    let json = stdout(&dir, &["--format=json", "two\nlines.txt", "plain.txt"]);
+    assert!(json.contains("{\"file_name\": \"two\\nlines.txt\", "));
+    let csv = stdout(&dir, &["--format=csv", "two\nlines.txt"]);
+    assert!(csv.contains("\n\"two\nlines.txt\","));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_print0_keeps_names_intact() {
+    let dir = fixture("print0");
+    let listed = stdout(&dir, &["--list=crlf", "--print0", "-r", "."]);
+    assert_eq!(listed, "./two\nlines.txt\0");
+    let problems = stdout(&dir, &["--only-problems", "--print0", "-r", "."]);
+    assert_eq!(problems, "./two\nlines.txt\0");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_names_that_are_not_utf8_survive() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = fixture("bytes");
+    let raw = OsStr::from_bytes(b"caf\xe9.txt");
+    fs::write(dir.join(raw), "a\r\nb\r\n").unwrap();
+    let listed = mdlt(&dir, &["--list=crlf", "--print0", "-r", "."]).stdout;
+    assert_eq!(listed, b"./caf\xe9.txt\0./two\nlines.txt\0");
+    let listed = stdout(&dir, &["--list=crlf", "-r", "."]);
+    assert!(listed.starts_with("./caf\u{fffd}.txt\n"));
+    let json = stdout(&dir, &["--format=json", "-r", "."]);
+    assert!(json.contains("{\"file_name\": \"./caf\u{fffd}.txt\", \"file_name_lossy\": true, "));
+    assert!(json.contains("{\"file_name\": \"./plain.txt\", \"file_extension\""));
+
+    // Given on the command line, the name still opens the file, and
+    // `--convert` rewrites that file and no other.
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args([OsStr::new("--convert=lf"), raw])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "caf\u{fffd}.txt: converted 2 line endings to LF\n"
+    );
+    assert_eq!(fs::read(dir.join(raw)).unwrap(), b"a\nb\n");
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_private_use_names_are_not_taken_for_bytes() {
+    let dir = fixture("private_use");
+    // U+10FF41 is among the characters that carry the bytes of names that
+    // are not UTF-8, where it would stand for `A`.
+    fs::write(dir.join("pua\u{10FF41}.txt"), "a\r\nb\r\n").unwrap();
+    fs::write(dir.join("puaA.txt"), "a\n").unwrap();
+    let quiet = stdout(&dir, &["-q", "pua\u{10FF41}.txt", "puaA.txt"]);
+    assert_eq!(
+        quiet,
+        "pua\u{10FF41}.txt: CRLF (2 lines)\npuaA.txt: LF (1 line)\n"
+    );
+    let listed = stdout(&dir, &["--list=crlf", "-r", "."]);
+    assert!(listed.contains("./pua\u{10FF41}.txt\n"));
+    assert!(!listed.contains("puaA.txt"));
+    let json = stdout(&dir, &["--format=json", "pua\u{10FF41}.txt"]);
+    assert!(json.contains("{\"file_name\": \"pua\u{10FF41}.txt\", \"file_extension\""));
+
+    let output = mdlt(&dir, &["--convert=lf", "pua\u{10FF41}.txt"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(fs::read(dir.join("pua\u{10FF41}.txt")).unwrap(), b"a\nb\n");
+    assert_eq!(fs::read(dir.join("puaA.txt")).unwrap(), b"a\n");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,14 @@
+use std::process;
+
+fn main() {
+    match mdlt::run(std::env::args_os()) {
+        Ok(exit) => process::exit(exit.status()),
+        Err(failure) => {
+            let message = failure.to_string();
+            if !message.is_empty() {
+                eprintln!("{}", message);
+            }
+            process::exit(failure.status());
+        }
+    }
+}
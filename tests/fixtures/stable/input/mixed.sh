@@ -0,0 +1,7 @@
+#!/bin/sh
+	echo "a"  
+    echo b
+
+
+
+exit 0done é
\ No newline at end of file
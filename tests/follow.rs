@@ -0,0 +1,87 @@
+//! `--follow` keeps reading a growing file and prints the full report on
+//! Ctrl-C, which these send as SIGINT, so they run on Unix only.
+#![cfg(all(unix, feature = "cli"))]
+
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+/// Long enough for mdlt to reach the end of the file and look again.
+const PAUSE: Duration = Duration::from_millis(800);
+
+#[test]
+fn test_follow_reads_what_is_appended() {
+    let dir = std::env::temp_dir().join(format!("mdlt_follow_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let log = dir.join("app.log");
+    fs::write(&log, "one\ntwo\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args([
+            "--follow",
+            "--follow-lines=2",
+            "--interval=0.3",
+            "--check=lf",
+            "app.log",
+        ])
+        .current_dir(&dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    thread::sleep(PAUSE);
+    let mut appending = OpenOptions::new().append(true).open(&log).unwrap();
+    appending.write_all(b"three\r\nfour\r\nfive").unwrap();
+    thread::sleep(PAUSE);
+    // Truncated, as `> app.log` does, then written again.
+    fs::write(&log, "new\n").unwrap();
+    thread::sleep(PAUSE);
+    fs::write(&log, "new\nlines\r\n").unwrap();
+    thread::sleep(PAUSE);
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    assert_eq!(child.wait().unwrap().code(), Some(1));
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    let mut stderr = String::new();
+    child
+        .stderr
+        .take()
+        .unwrap()
+        .read_to_string(&mut stderr)
+        .unwrap();
+
+    let summaries: Vec<&str> = stdout
+        .lines()
+        .filter_map(|line| line.split_once("] ").map(|(_, summary)| summary))
+        .collect();
+    assert_eq!(
+        summaries,
+        [
+            "app.log: 2 lines, 2 LF / 0 CRLF, last line LF",
+            "app.log: 4 lines, 2 LF / 2 CRLF, last line unterminated",
+            "app.log: 1 line, 1 LF / 0 CRLF, last line LF",
+            "app.log: 2 lines, 1 LF / 1 CRLF, last line CRLF",
+        ],
+        "{}",
+        stdout
+    );
+    assert!(stderr.contains("app.log: truncated or replaced, counting from the start\n"));
+    assert!(
+        stderr.contains("app.log: expected LF, found 1 CRLF"),
+        "{}",
+        stderr
+    );
+    assert!(stdout.contains("File name: app.log\n"), "{}", stdout);
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,218 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A fresh git repository with one commit of `committed.txt`.
+fn repo(name: &str) -> PathBuf {
+    let dir = common::scratch("git", name);
+    git(&dir, &["init", "-q"]);
+    git(&dir, &["config", "user.email", "mdlt@example.com"]);
+    git(&dir, &["config", "user.name", "mdlt"]);
+    git(&dir, &["config", "core.autocrlf", "false"]);
+    fs::write(dir.join("committed.txt"), "a\r\nb\r\n").unwrap();
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "initial"]);
+    dir
+}
+
+fn git(dir: &Path, args: &[&str]) {
+    let status = Command::new("git")
+        .args(args)
+        .current_dir(dir)
+        .status()
+        .unwrap();
+    assert!(status.success(), "git {:?} failed", args);
+}
+
+/// Runs mdlt in `dir` and returns its exit status and stdout.
+fn mdlt(dir: &Path, args: &[&str]) -> (i32, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    (
+        output.status.code().unwrap(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_checks_staged_contents_not_the_working_tree() {
+    let dir = repo("contents");
+    // Staged with CRLF, then fixed on disk but not staged again.
+    fs::write(dir.join("staged.txt"), "a\r\nb\r\n").unwrap();
+    git(&dir, &["add", "staged.txt"]);
+    fs::write(dir.join("staged.txt"), "a\nb\n").unwrap();
+    // The other way round, as after `git add -p` took only the good hunk.
+    fs::write(dir.join("partial.txt"), "a\nb\n").unwrap();
+    git(&dir, &["add", "partial.txt"]);
+    fs::write(dir.join("partial.txt"), "a\nb\r\nc\r\n").unwrap();
+    // Untracked and committed-but-untouched files are not checked.
+    fs::write(dir.join("untracked.txt"), "a\r\n").unwrap();
+    assert_eq!(
+        mdlt(&dir, &["--staged", "--check", "lf"]),
+        (1, "staged.txt: expected LF, found 2 CRLF\n".to_string())
+    );
+    git(&dir, &["add", "staged.txt"]);
+    assert_eq!(mdlt(&dir, &["--staged", "--check=lf"]), (0, String::new()));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_renames_and_deletions() {
+    let dir = repo("renames");
+    fs::write(dir.join("gone.txt"), "a\n").unwrap();
+    git(&dir, &["add", "gone.txt"]);
+    git(&dir, &["commit", "-q", "-m", "more"]);
+    git(&dir, &["mv", "committed.txt", "renamed.txt"]);
+    git(&dir, &["rm", "-q", "gone.txt"]);
+    assert_eq!(
+        mdlt(&dir, &["--staged", "--check=lf"]),
+        (1, "renamed.txt: expected LF, found 2 CRLF\n".to_string())
+    );
+    // Even from a subdirectory the paths are relative to the root, as git
+    // shows them.
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    assert_eq!(
+        mdlt(&dir.join("sub"), &["--staged", "--check=lf"]),
+        (1, "renamed.txt: expected LF, found 2 CRLF\n".to_string())
+    );
+    git(&dir, &["commit", "-q", "-m", "rename"]);
+    assert_eq!(mdlt(&dir, &["--staged", "--check=lf"]), (0, String::new()));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_git_modified() {
+    let dir = repo("modified");
+    fs::write(dir.join(".gitignore"), "*.log\n").unwrap();
+    fs::write(dir.join("clean.txt"), "a\r\n").unwrap();
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "more"]);
+    // Changed on disk, staged or not, and new files git would see.
+    fs::write(dir.join("committed.txt"), "a\r\nb\r\nc\r\n").unwrap();
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::write(dir.join("sub/new.txt"), "a\r\n").unwrap();
+    fs::write(dir.join("sub/keep.md"), "a\n").unwrap();
+    fs::write(dir.join("debug.log"), "a\r\n").unwrap();
+    assert_eq!(
+        mdlt(&dir, &["--git-modified", "--check=lf"]),
+        (
+            1,
+            "committed.txt: expected LF, found 3 CRLF\n\
+             sub/new.txt: expected LF, found 1 CRLF\n"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        mdlt(
+            &dir.join("sub"),
+            &["--git-modified", "--check=lf", "--exclude=*.txt"]
+        ),
+        (0, String::new())
+    );
+    // `--git-diff` goes back to where HEAD left the given branch.
+    git(&dir, &["branch", "-q", "base", "HEAD~1"]);
+    fs::write(dir.join("committed.txt"), "a\nb\n").unwrap();
+    fs::remove_dir_all(dir.join("sub")).unwrap();
+    assert_eq!(
+        mdlt(&dir, &["--git-diff", "base", "--check=lf"]),
+        (1, "clean.txt: expected LF, found 1 CRLF\n".to_string())
+    );
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["--git-diff=no-such-branch"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("--git-diff no-such-branch: no common ancestor with HEAD"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_outside_a_repository() {
+    let dir = std::env::temp_dir().join(format!("mdlt_git_none_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["--git-modified"])
+        .current_dir(&dir)
+        .env("GIT_CEILING_DIRECTORIES", std::env::temp_dir())
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8(output.stderr)
+        .unwrap()
+        .starts_with("--git-modified needs a git repository"));
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_rev_reads_the_committed_file() {
+    let dir = repo("rev");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/lib.rs"), "a\r\n// b\r\n").unwrap();
+    git(&dir, &["add", "."]);
+    git(&dir, &["commit", "-q", "-m", "lib"]);
+    fs::write(dir.join("src/lib.rs"), "a\n").unwrap();
+    git(&dir, &["commit", "-q", "-a", "-m", "lf"]);
+
+    let (status, report) = mdlt(&dir, &["--rev", "HEAD~1", "src/lib.rs"]);
+    assert_eq!(status, 0);
+    assert!(report.contains("File name: src/lib.rs @ HEAD~1\n"));
+    // The extension is still the path's, so comments are recognised.
+    assert!(report.contains("Comment lines: 1, code lines: 1\n"));
+    assert_eq!(
+        mdlt(
+            &dir,
+            &["--rev=HEAD~1", "--check=lf", "src/lib.rs", "committed.txt"]
+        ),
+        (
+            1,
+            "src/lib.rs @ HEAD~1: expected LF, found 2 CRLF\n\
+             committed.txt @ HEAD~1: expected LF, found 2 CRLF\n"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        mdlt(&dir.join("src"), &["--rev=HEAD", "--check=lf", "lib.rs"]),
+        (0, String::new())
+    );
+
+    let error = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+            .args(args)
+            .current_dir(&dir)
+            .output()
+            .unwrap();
+        (
+            output.status.code().unwrap(),
+            String::from_utf8(output.stderr).unwrap(),
+        )
+    };
+    assert_eq!(
+        error(&["--rev=HEAD~1", "src/missing.rs"]),
+        (
+            3,
+            "Error analyzing src/missing.rs: no such file at HEAD~1\n".to_string()
+        )
+    );
+    assert_eq!(
+        error(&["--rev=HEAD~1", "src"]),
+        (
+            3,
+            "Error analyzing src: a directory at HEAD~1, not a file\n".to_string()
+        )
+    );
+    assert_eq!(
+        error(&["--rev=HEAD~9", "src/lib.rs"]),
+        (2, "--rev HEAD~9: no such revision\n".to_string())
+    );
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,547 @@
+//! The library as another program uses it: analyzing files and readers and
+//! reading the results through the public getters.
+
+#[cfg(feature = "cli")]
+use std::error::Error;
+#[cfg(feature = "cli")]
+use std::fs;
+use std::io::{self, Read};
+#[cfg(feature = "cli")]
+use std::path::PathBuf;
+#[cfg(feature = "async")]
+use std::pin::Pin;
+#[cfg(feature = "async")]
+use std::task::{Context, Poll};
+
+#[cfg(feature = "async")]
+use mdlt::analyze_async_reader;
+#[cfg(all(feature = "async", feature = "cli"))]
+use mdlt::analyze_file_async;
+use mdlt::{analyze_bytes, analyze_reader, Analyzer, FileStats, LineEndingKind};
+#[cfg(feature = "cli")]
+use mdlt::{analyze_file, Exit};
+#[cfg(feature = "async")]
+use tokio::io::{AsyncRead, ReadBuf};
+
+#[cfg(feature = "cli")]
+/// Writes `content` to a fresh file named `name` in the temp directory.
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(format!("mdlt_library_{}_{}", std::process::id(), name));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[cfg(feature = "cli")]
+/// Analyzes `content` in a file named `name`, removing it again.
+fn analyze(name: &str, content: &str) -> FileStats {
+    let path = temp_file(name, content);
+    let stats = analyze_file(&path).unwrap();
+    fs::remove_file(path).unwrap();
+    stats
+}
+
+fn line_ending(bytes: &[u8]) -> LineEndingKind {
+    analyze_reader(bytes, "test_file.txt")
+        .unwrap()
+        .line_ending()
+}
+
+#[test]
+fn test_analyze_reader_names() {
+    let stats = analyze_reader(&b""[..], "test_file").unwrap();
+    assert_eq!(stats.file_name(), "test_file");
+    assert_eq!(stats.file_extension(), None);
+    assert_eq!(stats.total_lines(), 0);
+    let stats = analyze_reader(&b""[..], "test_file.txt").unwrap();
+    assert_eq!(stats.file_name(), "test_file.txt");
+    assert_eq!(stats.file_extension(), Some("txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_names_that_are_not_utf8_are_kept() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    let raw = OsStr::from_bytes(b"caf\xe9.txt");
+    let stats = analyze_reader(&b"a\n"[..], raw).unwrap();
+    assert_eq!(stats.file_name(), raw);
+    assert_eq!(stats.path(), Path::new(raw));
+    assert_eq!(stats.display_name(), "caf\u{fffd}.txt");
+    assert_eq!(stats.file_extension(), Some("txt"));
+    let mut analyzer = Analyzer::new(raw);
+    analyzer.feed(b"a\n");
+    assert_eq!(analyzer.finish().file_name(), raw);
+}
+
+#[test]
+fn test_line_ending_dos() {
+    assert_eq!(line_ending(&b"a\r\n".repeat(10)), LineEndingKind::Crlf);
+    let mostly = [b"a\r\n".repeat(10), b"b\n".repeat(5)].concat();
+    assert_eq!(line_ending(&mostly), LineEndingKind::Crlf);
+}
+
+#[test]
+fn test_line_ending_unix() {
+    let mostly = [b"a\r\n".repeat(5), b"b\n".repeat(10)].concat();
+    assert_eq!(line_ending(&mostly), LineEndingKind::Lf);
+}
+
+#[test]
+fn test_line_ending_mixed() {
+    let tie = [b"a\r\n".repeat(10), b"b\n".repeat(10)].concat();
+    assert_eq!(line_ending(&tie), LineEndingKind::Mixed);
+}
+
+#[test]
+fn test_line_ending_with_cr() {
+    let stats = analyze_reader(&b"a\r".repeat(3)[..], "test_file.txt").unwrap();
+    assert_eq!(stats.line_ending(), LineEndingKind::Cr);
+    assert!(!stats.is_mixed());
+    let tie = [b"b\n".repeat(3), b"a\r".repeat(3)].concat();
+    let stats = analyze_reader(&tie[..], "test_file.txt").unwrap();
+    assert_eq!(stats.line_ending(), LineEndingKind::Mixed);
+    assert!(stats.is_mixed());
+    let crlf = [tie, b"c\r\n".repeat(4)].concat();
+    assert_eq!(line_ending(&crlf), LineEndingKind::Crlf);
+    // A CR inside a line ends nothing.
+    let stats = analyze_reader(&b"foo\rbar\n"[..], "test_file.txt").unwrap();
+    assert_eq!((stats.total_lines(), stats.mac_endings()), (1, 0));
+    assert_eq!(stats.line_ending(), LineEndingKind::Lf);
+    assert!(!stats.is_mixed());
+}
+
+#[test]
+fn test_line_ending_none() {
+    assert_eq!(line_ending(b""), LineEndingKind::None);
+    assert_eq!(line_ending(b"no newline"), LineEndingKind::None);
+}
+
+#[test]
+fn test_line_ending_description() {
+    assert_eq!(LineEndingKind::Crlf.description(), "DOS/Windows (CRLF)");
+    assert_eq!(LineEndingKind::Lf.to_string(), "Unix/Linux (LF)");
+    assert_eq!(LineEndingKind::Cr.to_string(), "Classic Mac (CR)");
+    assert_eq!(LineEndingKind::Mixed.to_string(), "Mixed line endings");
+    assert_eq!(LineEndingKind::None.to_string(), "No line endings detected");
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_empty_file() {
+    let stats = analyze("empty.txt", "");
+    assert_eq!(stats.total_lines(), 0);
+    assert_eq!(stats.unix_endings(), 0);
+    assert_eq!(stats.dos_endings(), 0);
+    assert_eq!(stats.empty_lines(), 0);
+    assert_eq!(stats.final_newline(), None);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_unix_endings() {
+    let stats = analyze("unix.txt", "line1\nline2\n");
+    assert_eq!(stats.total_lines(), 2);
+    assert_eq!(stats.unix_endings(), 2);
+    assert_eq!(stats.dos_endings(), 0);
+    assert_eq!(stats.final_newline(), Some(true));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_dos_endings() {
+    let stats = analyze("dos.txt", "line1\r\nline2\r\n");
+    assert_eq!(stats.total_lines(), 2);
+    assert_eq!(stats.unix_endings(), 0);
+    assert_eq!(stats.dos_endings(), 2);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_mac_endings() {
+    let stats = analyze("mac.txt", "line1\rline2\r");
+    assert_eq!(stats.total_lines(), 2);
+    assert_eq!(stats.unix_endings(), 0);
+    assert_eq!(stats.dos_endings(), 0);
+    assert_eq!(stats.mac_endings(), 2);
+    assert_eq!(stats.line_ending(), LineEndingKind::Cr);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_empty_lines() {
+    let stats = analyze("empty_lines.txt", "line1\n\nline3");
+    assert_eq!(stats.total_lines(), 3);
+    assert_eq!(stats.unix_endings(), 2);
+    assert_eq!(stats.dos_endings(), 0);
+    assert_eq!(stats.empty_lines(), 1);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_empty_lines_dos() {
+    let stats = analyze("empty_lines_dos.txt", "line1\r\n\r\n  \r\nline3");
+    assert_eq!(stats.total_lines(), 4);
+    assert_eq!(stats.unix_endings(), 0);
+    assert_eq!(stats.dos_endings(), 3);
+    assert_eq!(stats.empty_lines(), 1);
+    assert_eq!(stats.blank_lines(), 1);
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_no_newline_at_end() {
+    let stats = analyze("no_newline.txt", "line1\nline2");
+    assert_eq!(stats.total_lines(), 2);
+    assert_eq!(stats.unix_endings(), 1);
+    assert_eq!(stats.dos_endings(), 0);
+    assert_eq!(stats.final_newline(), Some(false));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_complex_file() {
+    let stats = analyze("complex.txt", "line1\n\r\nline3 \r\n\nline5");
+    assert_eq!(stats.total_lines(), 5);
+    assert_eq!(stats.unix_endings(), 2);
+    assert_eq!(stats.dos_endings(), 2);
+    assert_eq!(stats.empty_lines(), 2);
+    assert_eq!(stats.trailing_whitespace_lines(), 1);
+    assert_eq!(stats.max_line_length(), 6);
+    assert_eq!(stats.total_bytes(), 22);
+    assert_eq!(stats.total_words(), 3);
+    assert!(stats.is_mixed());
+    assert!(!stats.is_binary());
+    assert_eq!(stats.verdict(), "MIXED (2 CRLF / 2 LF)");
+    assert_eq!(
+        stats.line_ending_summary(),
+        "Mixed line endings - 50.0% CRLF, 50.0% LF"
+    );
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_file_not_found() {
+    let failure = analyze_file("non_existent_file.txt").unwrap_err();
+    assert_eq!(failure.path(), Some("non_existent_file.txt"));
+    assert_eq!(failure.status(), 3);
+    let source = failure.source().and_then(|e| e.downcast_ref::<io::Error>());
+    assert_eq!(source.map(io::Error::kind), Some(io::ErrorKind::NotFound));
+    assert!(failure
+        .to_string()
+        .starts_with("Error analyzing non_existent_file.txt: "));
+}
+
+/// Hands out one byte per `read` call and fails with `Interrupted` every
+/// other call, as a slow decompressor or socket might.
+#[cfg(feature = "cli")]
+struct OneByteReader<'a> {
+    bytes: &'a [u8],
+    interrupt: bool,
+}
+
+#[cfg(feature = "cli")]
+impl Read for OneByteReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.interrupt = !self.interrupt;
+        if self.interrupt {
+            return Err(io::ErrorKind::Interrupted.into());
+        }
+        match self.bytes.split_first() {
+            Some((first, rest)) if !buf.is_empty() => {
+                buf[0] = *first;
+                self.bytes = rest;
+                Ok(1)
+            }
+            _ => Ok(0),
+        }
+    }
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_analyze_reader_one_byte_at_a_time() {
+    let fixture = "\u{feff}line1\r\n\r\nline 3 \rline4\n\r\r\n\u{e9}t\u{e9}\r";
+    let path = temp_file("one_byte.txt", fixture);
+    let whole = analyze_file(&path).unwrap();
+    let reader = OneByteReader {
+        bytes: fixture.as_bytes(),
+        interrupt: false,
+    };
+    let trickled = analyze_reader(reader, path.display().to_string()).unwrap();
+    let counts = |stats: &FileStats| {
+        (
+            stats.total_lines(),
+            [
+                stats.dos_endings(),
+                stats.unix_endings(),
+                stats.mac_endings(),
+            ],
+            stats.empty_lines(),
+            stats.trailing_whitespace_lines(),
+            stats.total_chars(),
+            stats.final_newline(),
+        )
+    };
+    // The CR in `line 3 \rline4` is inside the line.
+    assert_eq!(counts(&trickled), (6, [3, 1, 2], 3, 0, 30, Some(true)));
+    assert_eq!(counts(&trickled), counts(&whole));
+    assert_eq!(trickled.file_name(), whole.file_name());
+    fs::remove_file(path).unwrap();
+}
+
+/// A xorshift generator, so that the random inputs are the same from run
+/// to run.
+struct Random(u64);
+
+impl Random {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, limit: u64) -> usize {
+        (self.next() % limit) as usize
+    }
+}
+
+/// Hands out `bytes` in reads of the sizes `sizes` picks.
+struct ChunkedReader<'a> {
+    bytes: &'a [u8],
+    sizes: Random,
+}
+
+impl Read for ChunkedReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let size = (self.sizes.below(9) + 1)
+            .min(buf.len())
+            .min(self.bytes.len());
+        let (chunk, rest) = self.bytes.split_at(size);
+        buf[..size].copy_from_slice(chunk);
+        self.bytes = rest;
+        Ok(size)
+    }
+}
+
+#[test]
+fn test_analyze_bytes_matches_analyze_reader() {
+    // Mostly the bytes that end lines, indent them or start multi-byte
+    // characters, where splitting a read could go wrong.
+    const ALPHABET: &[u8] = b"\r\n\r\n \t\tab\xef\xbb\xbf\xc3\xa9\xff\xfe\x00#";
+    let mut random = Random(0x9e37_79b9_7f4a_7c15);
+    for round in 0..300 {
+        let len = random.below(120);
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| ALPHABET[random.below(ALPHABET.len() as u64)])
+            .collect();
+        let name = ["snippet.txt", "main.rs", "notes.md"][round % 3];
+        let whole = analyze_bytes(&bytes, name);
+        let reader = ChunkedReader {
+            bytes: &bytes,
+            sizes: Random(round as u64 + 1),
+        };
+        let chunked = analyze_reader(reader, name).unwrap();
+        let report = |stats: &FileStats| {
+            let mut buffer = Vec::new();
+            stats.write_report(&mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert_eq!(report(&whole), report(&chunked), "{:?}", bytes);
+        assert_eq!(whole.line_ending(), chunked.line_ending());
+        assert_eq!(whole.total_chars(), chunked.total_chars());
+
+        // Fed by hand in other pieces, as an async reader would.
+        let mut analyzer = Analyzer::new(name);
+        let mut sizes = Random(round as u64 + 7);
+        let mut rest = &bytes[..];
+        while !rest.is_empty() {
+            let (chunk, after) = rest.split_at((sizes.below(17) + 1).min(rest.len()));
+            analyzer.feed(chunk);
+            rest = after;
+        }
+        assert_eq!(report(&whole), report(&analyzer.finish()), "{:?}", bytes);
+    }
+}
+
+#[test]
+fn test_analyze_reader_passes_errors_on() {
+    struct Broken;
+    impl Read for Broken {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::other("connection reset"))
+        }
+    }
+    let error = analyze_reader(Broken, String::from("stream.log")).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Error analyzing stream.log: connection reset"
+    );
+    assert_eq!(error.path(), Some("stream.log"));
+    assert_eq!(error.status(), 3);
+    let source = std::error::Error::source(&error).unwrap();
+    let source = source.downcast_ref::<io::Error>().unwrap();
+    assert_eq!(source.kind(), io::ErrorKind::Other);
+}
+
+/// A `Read` of the tests above as tokio's `AsyncRead`, ready at every poll.
+#[cfg(feature = "async")]
+fn poll_read(reader: &mut impl Read, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+    let read = reader.read(buf.initialize_unfilled())?;
+    buf.advance(read);
+    Poll::Ready(Ok(()))
+}
+
+#[cfg(all(feature = "async", feature = "cli"))]
+impl AsyncRead for OneByteReader<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        poll_read(self.get_mut(), buf)
+    }
+}
+
+#[cfg(feature = "async")]
+impl AsyncRead for ChunkedReader<'_> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        poll_read(self.get_mut(), buf)
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_analyze_async_reader_matches_analyze_reader() {
+    const ALPHABET: &[u8] = b"\r\n\r\n \t\tab\xef\xbb\xbf\xc3\xa9\xff\xfe\x00#";
+    let mut random = Random(0x2545_f491_4f6c_dd1d);
+    for round in 0..100 {
+        let len = random.below(120);
+        let bytes: Vec<u8> = (0..len)
+            .map(|_| ALPHABET[random.below(ALPHABET.len() as u64)])
+            .collect();
+        let name = ["snippet.txt", "main.rs", "notes.md"][round % 3];
+        let whole = analyze_reader(&bytes[..], name).unwrap();
+        let reader = ChunkedReader {
+            bytes: &bytes,
+            sizes: Random(round as u64 + 1),
+        };
+        let chunked = analyze_async_reader(reader, name).await.unwrap();
+        let report = |stats: &FileStats| {
+            let mut buffer = Vec::new();
+            stats.write_report(&mut buffer).unwrap();
+            String::from_utf8(buffer).unwrap()
+        };
+        assert_eq!(report(&whole), report(&chunked), "{:?}", bytes);
+        assert_eq!(chunked.file_name(), name);
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_analyze_async_reader_passes_errors_on() {
+    struct Broken;
+    impl AsyncRead for Broken {
+        fn poll_read(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            _buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            Poll::Ready(Err(io::Error::other("connection reset")))
+        }
+    }
+    let error = analyze_async_reader(Broken, "stream.log")
+        .await
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Error analyzing stream.log: connection reset"
+    );
+    assert_eq!(error.path(), Some("stream.log"));
+    assert_eq!(error.status(), 3);
+}
+
+#[cfg(all(feature = "async", feature = "cli"))]
+#[tokio::test]
+async fn test_analyze_file_async_matches_analyze_file() {
+    let fixture = "\u{feff}line1\r\n\r\nline 3 \rline4\n\r\r\n\u{e9}t\u{e9}\r";
+    let path = temp_file("async.txt", fixture);
+    let whole = analyze_file(&path).unwrap();
+    let opened = analyze_file_async(&path).await.unwrap();
+    let reader = OneByteReader {
+        bytes: fixture.as_bytes(),
+        interrupt: false,
+    };
+    let trickled = analyze_async_reader(reader, &path).await.unwrap();
+    let report = |stats: &FileStats| {
+        let mut buffer = Vec::new();
+        stats.write_report(&mut buffer).unwrap();
+        String::from_utf8(buffer).unwrap()
+    };
+    assert_eq!(report(&opened), report(&whole));
+    assert_eq!(report(&trickled), report(&whole));
+    assert_eq!(opened.file_name(), whole.file_name());
+    fs::remove_file(&path).unwrap();
+
+    let failure = analyze_file_async(&path).await.unwrap_err();
+    assert_eq!(failure.path(), Some(&*path.display().to_string()));
+    let source = failure.source().and_then(|e| e.downcast_ref::<io::Error>());
+    assert_eq!(source.map(io::Error::kind), Some(io::ErrorKind::NotFound));
+    let failure = analyze_file_async(std::env::temp_dir()).await.unwrap_err();
+    let source = failure.source().and_then(|e| e.downcast_ref::<io::Error>());
+    assert_eq!(
+        source.map(io::Error::kind),
+        Some(io::ErrorKind::IsADirectory)
+    );
+}
+
+#[test]
+fn test_write_report() {
+    let stats = analyze_reader(&b"a\r\nb\r\n"[..], "notes.txt").unwrap();
+    let mut buffer = Vec::new();
+    stats.write_report(&mut buffer).unwrap();
+    let report = String::from_utf8(buffer).unwrap();
+    assert!(report.starts_with("File Analysis Report\n"));
+    assert!(report.contains("\nFile name: notes.txt\n"));
+    assert!(report.contains("\nLine ending type: DOS/Windows (CRLF)\n"));
+    assert!(!report.contains('\x1b'));
+}
+
+#[cfg(feature = "cli")]
+#[test]
+fn test_run() {
+    let path = temp_file("run.txt", "a\n");
+    let args = vec![
+        "mdlt".to_string(),
+        "-qq".to_string(),
+        path.display().to_string(),
+    ];
+    let exit = mdlt::run(args).unwrap();
+    assert_eq!(exit, Exit::Clean);
+    assert_eq!(exit.status(), 0);
+    let args = vec!["mdlt".to_string(), "--format".to_string()];
+    let failure = mdlt::run(args).unwrap_err();
+    assert_eq!(failure.status(), 2);
+    assert_eq!(failure.to_string(), "Missing value for --format");
+    assert!(failure.check_failures().is_empty());
+
+    let args = vec![
+        "mdlt".to_string(),
+        "-q".to_string(),
+        "--check=crlf".to_string(),
+        path.display().to_string(),
+    ];
+    let failure = mdlt::run(args).unwrap_err();
+    assert_eq!(failure.status(), 1);
+    let failures = failure.check_failures();
+    assert_eq!(failures.len(), 1);
+    assert_eq!(failures[0].check, "endings");
+    assert_eq!(failures[0].message, "1 of 1 file fails --check crlf");
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,152 @@
+//! The reports must not depend on the locale mdlt runs in: a German or
+//! French one writes "1.234,5" where mdlt writes "1,234.5", and the
+//! machine-readable formats must read the same to any program anywhere.
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+const LOCALES: &[&str] = &["C", "de_DE.UTF-8", "fr_FR.UTF-8", "ar_EG.UTF-8"];
+
+/// A fresh directory holding a file of 1,234 lines, most of them LF.
+fn fixture(name: &str) -> PathBuf {
+    let dir = common::scratch("locale", name);
+    let mut text = "line\n".repeat(1230);
+    text.push_str("line\r\n".repeat(4).as_str());
+    fs::write(dir.join("big.txt"), text).unwrap();
+    dir
+}
+
+fn stdout(dir: &PathBuf, locale: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .current_dir(dir)
+        .env("LC_ALL", locale)
+        .env("LANG", locale)
+        .env("LC_NUMERIC", locale)
+        .env("LC_TIME", locale)
+        .output()
+        .unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_machine_output_is_the_same_in_every_locale() {
+    let dir = fixture("machine");
+    for format in ["json", "csv", "tsv"] {
+        let args = ["--format", format, "--metadata", "--no-fail", "big.txt"];
+        let expected = stdout(&dir, "C", &args);
+        assert!(expected.contains("1234"), "{}", expected);
+        assert!(!expected.contains("1,234"), "{}", expected);
+        for locale in LOCALES {
+            assert_eq!(
+                stdout(&dir, locale, &args),
+                expected,
+                "{} in {}",
+                format,
+                locale
+            );
+        }
+    }
+    let json = stdout(
+        &dir,
+        "de_DE.UTF-8",
+        &["--format", "json", "--metadata", "big.txt"],
+    );
+    assert!(json.contains("\"dos_ratio\": 0.0032"), "{}", json);
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_text_report_groups_digits_with_commas() {
+    let dir = fixture("text");
+    let expected = stdout(&dir, "C", &["--no-fail", "big.txt"]);
+    assert!(expected.contains("Total lines: 1,234\n"), "{}", expected);
+    assert!(
+        expected.contains("Unix line endings (LF): 1,230\n"),
+        "{}",
+        expected
+    );
+    assert!(expected.contains(" - 99.7% LF, 0.3% CRLF"), "{}", expected);
+    for locale in LOCALES {
+        assert_eq!(stdout(&dir, locale, &["--no-fail", "big.txt"]), expected);
+    }
+    let plain = stdout(
+        &dir,
+        "de_DE.UTF-8",
+        &["--no-group-digits", "--no-fail", "big.txt"],
+    );
+    assert!(plain.contains("Total lines: 1234\n"), "{}", plain);
+    assert!(plain.contains("Size: 6174 bytes, "), "{}", plain);
+    let quiet = stdout(&dir, "de_DE.UTF-8", &["-q", "--no-group-digits", "big.txt"]);
+    assert_eq!(quiet, "big.txt: MIXED (4 CRLF / 1230 LF)\n");
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_every_count_is_grouped() {
+    let dir = common::scratch("locale", "every");
+    let mut text = "    a \n\nx\ry\n\x0cb\nc\n\r\n".repeat(1000);
+    text.push_str(&"z".repeat(1500));
+    text.push('\n');
+    fs::write(dir.join("big.txt"), text).unwrap();
+    let args = ["--no-fail", "--duplicates", "--transitions", "big.txt"];
+    let report = stdout(&dir, "C", &args);
+    for line in [
+        "Blank-line runs: 2,000 (longest: 1 line starting at line 2)\n",
+        "Line lengths (min/avg/max): 0/2.2/1,500\n",
+        "Longest line: 1,500 columns on line 6001\n",
+        "Indentation: spaces (1,000 lines, width 4)\n",
+        "  and 1,991 more transitions\n",
+        "Pages: 1,001 (1,000 form feeds)\n",
+        "Embedded carriage returns: 1,000 (first at line 3, col 2)\n",
+        "Phantom blank lines (lone CR content): 1,000, first at line 6\n",
+        "Trailing whitespace: 1,000 lines (1, 7, 13, 19, 25, 31, 37, 43, 49, 55 and 990 more)\n",
+        "Duplicate lines: 3,996 (5 distinct lines)\n",
+        "  1,000x, first on line 1:     a \n",
+    ] {
+        assert!(report.contains(line), "{:?} in {}", line, report);
+    }
+    assert!(
+        report.contains("Line ending transitions: 2,000\n"),
+        "{}",
+        report
+    );
+    let markdown = stdout(
+        &dir,
+        "C",
+        &[
+            "--format",
+            "markdown",
+            "--duplicates",
+            "--no-fail",
+            "big.txt",
+        ],
+    );
+    for line in [
+        "- **Line lengths (min/avg/max):** 0/2.2/1,500\n",
+        "- **Pages:** 1,001 (1,000 form feeds), 3/6.0/6 lines per page (min/avg/max)\n",
+        "- **Phantom blank lines (lone CR content):** 1,000, first at line 6\n",
+        "- **Duplicate lines:** 3,996 (5 distinct lines)\n",
+        "  - 1,000x, first on line 1:     a \n",
+    ] {
+        assert!(markdown.contains(line), "{:?} in {}", line, markdown);
+    }
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_verbose_run_agrees_with_its_file_count() {
+    let dir = fixture("verbose");
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["-v", "--no-fail", "big.txt"])
+        .current_dir(&dir)
+        .output()
+        .unwrap();
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.starts_with("Scanned 1 file in "), "{}", stderr);
+    let _ = fs::remove_dir_all(&dir);
+}
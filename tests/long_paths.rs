@@ -0,0 +1,54 @@
+//! Paths longer than the 260 characters Windows allows by default, such as
+//! those deep in `node_modules`. Windows needs them opened in their `\\?\`
+//! form; elsewhere they open as they are. Either way they are shown as
+//! they were given.
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// A directory nested deep enough under a fresh one that a file in it has
+/// a path of more than 260 characters, with that path relative to the
+/// fresh directory, or `None` when the file system will not make it.
+fn deep(name: &str) -> Option<(PathBuf, String)> {
+    let dir = common::scratch("long", name);
+    let components: Vec<String> = (0..6).map(|i| format!("{}{}", i, "n".repeat(49))).collect();
+    let relative = format!("{}/a.txt", components.join("/"));
+    let path = dir.join(&relative);
+    fs::create_dir_all(path.parent()?).ok()?;
+    fs::write(&path, "a\r\nb\r\n").ok()?;
+    Some((dir, relative))
+}
+
+fn mdlt(dir: &PathBuf, args: &[&str]) -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(args)
+        .current_dir(dir)
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8(output.stdout).unwrap(),
+    )
+}
+
+#[test]
+fn test_long_paths_open_and_keep_their_names() {
+    let Some((dir, relative)) = deep("open") else {
+        return;
+    };
+    assert!(dir.join(&relative).to_string_lossy().len() > 260);
+    let (status, listed) = mdlt(&dir, &["--list=crlf", &relative]);
+    assert_eq!(status, Some(0));
+    assert_eq!(listed, format!("{}\n", relative));
+    let (status, listed) = mdlt(&dir, &["-r", "--list=crlf", "--slash", "."]);
+    assert_eq!(status, Some(0));
+    assert_eq!(listed, format!("./{}\n", relative));
+    let (status, _) = mdlt(&dir, &["--convert=lf", &relative]);
+    assert_eq!(status, Some(0));
+    assert_eq!(fs::read(dir.join(&relative)).unwrap(), b"a\nb\n");
+    fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,58 @@
+//! `--serve` answers the JSON requests piped to it, one line each, and
+//! exits once its stdin ends.
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[test]
+fn test_serve_answers_each_request_in_order() {
+    let dir = std::env::temp_dir().join(format!("mdlt_serve_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("dos.txt"), "one\r\ntwo\r\n").unwrap();
+    let mut child = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["--serve", "--check=lf"])
+        .current_dir(&dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let requests = concat!(
+        "{\"id\": 1, \"path\": \"dos.txt\"}\n",
+        "{\"id\": \"two\", \"name\": \"a.md\", \"content\": \"# Title\\n\"}\n",
+        "{\"id\": 3, \"path\"\n",
+        "{\"id\": 4, \"path\": \"missing.txt\"}\n",
+        "{\"id\": 5, \"content\": \"x\", \"args\": [\"--checks=none\"]}\n",
+    );
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(requests.as_bytes())
+        .unwrap();
+    // Dropping stdin ends it, which ends the server.
+    let output = child.wait_with_output().unwrap();
+    assert_eq!(output.status.code(), Some(0));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let responses: Vec<&str> = stdout.lines().collect();
+    assert_eq!(responses.len(), 5, "{}", stdout);
+
+    assert!(responses[0].starts_with("{\"id\": 1, \"stats\": {\"file_name\": \"dos.txt\""));
+    assert!(responses[0].contains("\"dos_endings\": 2"));
+    assert!(responses[0].ends_with("\"failures\": [\"expected LF, found 2 CRLF\"]}"));
+    assert!(responses[1].starts_with("{\"id\": \"two\", \"stats\": {\"file_name\": \"a.md\""));
+    assert!(responses[1].ends_with("\"failures\": []}"));
+    assert!(responses[2].starts_with("{\"id\": null, \"error\": \"not a JSON request: "));
+    assert_eq!(
+        responses[3],
+        "{\"id\": 4, \"error\": \"Error analyzing missing.txt: no such file\"}"
+    );
+    // The flags of a request add to those of the command line.
+    assert!(responses[4].contains("\"final_newline\": false"));
+    assert!(responses[4].ends_with("\"failures\": []}"));
+    assert!(output.stderr.is_empty());
+    let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,84 @@
+//! The text report of each `--stable-output` version, byte for byte: the
+//! files under `tests/fixtures/stable/<version>` are what it wrote when the
+//! version was made, and must never change. A new part of the report, or a
+//! part worded anew, goes into a new version alone.
+#![cfg(feature = "cli")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::process::Command;
+
+fn fixtures() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/stable")
+}
+
+/// The report of `args` on the input fixtures, by name so that the file
+/// names do not depend on where the crate is.
+fn report(version: &str, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["--stable-output", version])
+        .args(["--no-config", "--no-cache", "--no-fail", "--color", "never"])
+        .args(args)
+        .current_dir(fixtures().join("input"))
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn golden(version: &str, name: &str) -> String {
+    fs::read_to_string(fixtures().join(version).join(name)).unwrap()
+}
+
+#[test]
+fn test_v1_matches_its_golden_files() {
+    let verbose = [
+        "--verbose",
+        "--hash",
+        "--peek",
+        "--top-lines",
+        "2",
+        "--locate",
+        "--show-empty",
+        "--transitions",
+        "--histogram",
+        "--duplicates",
+        "mixed.sh",
+    ];
+    let cases: [(&str, &[&str]); 4] = [
+        ("plain.txt", &["plain.txt"]),
+        ("mixed.txt", &["mixed.sh"]),
+        ("data.txt", &["data.bin"]),
+        ("mixed-verbose.txt", &verbose),
+    ];
+    for (name, args) in cases {
+        assert_eq!(report("v1", args), golden("v1", name), "{}", name);
+    }
+}
+
+#[test]
+fn test_readme_example_is_a_v1_report() {
+    let readme = fs::read_to_string(fixtures().join("../../../README.md")).unwrap();
+    let example = readme
+        .split_once("### Example Output\n")
+        .and_then(|(_, rest)| rest.split_once("```text\n"))
+        .and_then(|(_, rest)| rest.split_once("```\n"))
+        .map(|(example, _)| example)
+        .unwrap();
+    assert_eq!(example, report("v1", &["main.rs"]));
+}
+
+#[test]
+fn test_unknown_version_lists_the_supported_ones() {
+    let output = Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .args(["--stable-output", "v0", "a.txt"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(
+        stderr.contains("Unknown --stable-output version 'v0'; supported versions are: v1"),
+        "{}",
+        stderr
+    );
+}
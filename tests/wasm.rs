@@ -0,0 +1,49 @@
+//! The analysis without the `cli` feature builds for
+//! `wasm32-unknown-unknown`, where there is no file system to read. The
+//! check is skipped, saying so, when that target is not installed.
+
+use std::path::Path;
+use std::process::Command;
+
+const TARGET: &str = "wasm32-unknown-unknown";
+
+/// Whether the standard library for `TARGET` is installed.
+fn target_installed() -> bool {
+    Command::new("rustc")
+        .args(["--print", "target-libdir", "--target", TARGET])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .is_some_and(|output| Path::new(String::from_utf8_lossy(&output.stdout).trim()).is_dir())
+}
+
+#[test]
+fn test_core_builds_for_wasm() {
+    if !target_installed() {
+        eprintln!("skipped: {} is not installed", TARGET);
+        return;
+    }
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let cargo = std::env::var("CARGO").unwrap_or("cargo".to_string());
+    for features in ["", "serde"] {
+        let output = Command::new(&cargo)
+            .args([
+                "check",
+                "--lib",
+                "--no-default-features",
+                "--target",
+                TARGET,
+            ])
+            .args(["--features", features])
+            .env("CARGO_TARGET_DIR", root.join("target").join(TARGET))
+            .current_dir(root)
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "features {:?}:\n{}",
+            features,
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
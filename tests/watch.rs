@@ -0,0 +1,97 @@
+//! `--watch` reports again as files change and stops cleanly on Ctrl-C,
+//! which these send as SIGINT, so they run on Unix only.
+#![cfg(all(unix, feature = "cli"))]
+
+mod common;
+
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use common::scratch;
+
+/// Long enough for a poll and the settling that follows it.
+const PAUSE: Duration = Duration::from_millis(1500);
+
+fn watch(dir: &PathBuf, args: &[&str]) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_mdlt"))
+        .arg("--watch")
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+/// Sends Ctrl-C and returns the exit status and stdout.
+fn interrupt(mut child: Child) -> (Option<i32>, String) {
+    let status = Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    let code = child.wait().unwrap().code();
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+    (code, stdout)
+}
+
+#[test]
+fn test_watch_reports_changes_to_files() {
+    let dir = scratch("watch", "files");
+    fs::write(dir.join("a.txt"), "a\n").unwrap();
+    let child = watch(&dir, &["--check=lf", "a.txt"]);
+    thread::sleep(PAUSE);
+    fs::write(dir.join("a.txt"), "a\r\nb\r\n").unwrap();
+    thread::sleep(PAUSE);
+    fs::remove_file(dir.join("a.txt")).unwrap();
+    thread::sleep(PAUSE);
+    fs::write(dir.join("a.txt"), "back\n").unwrap();
+    thread::sleep(PAUSE);
+    let (code, stdout) = interrupt(child);
+    assert_eq!(code, Some(0));
+    let updates: Vec<&str> = stdout
+        .lines()
+        .filter(|line| !line.starts_with('['))
+        .collect();
+    assert_eq!(
+        updates,
+        [
+            "a.txt: CRLF (2 lines)",
+            "FAIL",
+            "a.txt: deleted",
+            "a.txt: LF (1 line)",
+            "PASS"
+        ]
+    );
+    assert_eq!(stdout.matches(" 1 changed\n").count(), 2);
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn test_watch_finds_new_files_under_directories() {
+    let dir = scratch("watch", "dirs");
+    fs::create_dir_all(dir.join("src")).unwrap();
+    fs::write(dir.join("src/a.txt"), "a\n").unwrap();
+    let child = watch(&dir, &["-r", "src"]);
+    thread::sleep(PAUSE);
+    fs::write(dir.join("src/new.txt"), "x\r\n").unwrap();
+    thread::sleep(PAUSE);
+    let (code, stdout) = interrupt(child);
+    assert_eq!(code, Some(0));
+    assert!(
+        stdout.contains("] 1 changed\nsrc/new.txt: CRLF (1 line)\n"),
+        "{}",
+        stdout
+    );
+    fs::remove_dir_all(dir).unwrap();
+}